@@ -0,0 +1,65 @@
+use blockchain_p2p::{Block, BlockHeader, Hash256, HashAlgorithm, MiningOptions, Target};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::sync::atomic::AtomicBool;
+
+// ========== HEADER HASHING ==============
+fn compute_hash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compute_hash");
+    for algorithm in HashAlgorithm::ALL {
+        let mut header = BlockHeader {
+            index: 1,
+            timestamp: 1_704_067_200,
+            prev_hash: Hash256::digest(b"prev"),
+            merkle_root: Hash256::digest(b"merkle"),
+            nonce: 0,
+            extra_nonce: 0,
+            bits: 0x2000_ffff,
+            hash_algorithm: algorithm,
+        };
+        group.bench_function(algorithm.to_string(), |b| {
+            b.iter(|| {
+                header.nonce = header.nonce.wrapping_add(1);
+                black_box(header).compute_hash()
+            })
+        });
+    }
+    group.finish();
+}
+
+// ========== MINING ==============
+/// Блок из одной пустой транзакции-заглушки при сложности 1, 16 и 256;
+/// каждый прогон начинается с другого `prev_hash`, чтобы число
+/// перебранных nonce усреднялось.
+fn mine_block(c: &mut Criterion) {
+    let cancel = AtomicBool::new(false);
+    let mut group = c.benchmark_group("mine_block");
+    group.sample_size(20);
+
+    for difficulty in [1u64, 16, 256] {
+        let bits = Target::MAX.scale(1, difficulty).to_compact();
+        for threads in [1, 4] {
+            let options = MiningOptions::with_threads(threads);
+            let id = BenchmarkId::new(format!("{} thread(s)", threads), difficulty);
+            group.bench_with_input(id, &bits, |b, &bits| {
+                let mut round = 0u64;
+                b.iter(|| {
+                    round += 1;
+                    let prev_hash = Hash256::digest(&round.to_le_bytes());
+                    Block::mine_with(
+                        &options,
+                        HashAlgorithm::Sha256,
+                        1,
+                        Vec::new(),
+                        prev_hash,
+                        bits,
+                        &cancel,
+                    )
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, compute_hash, mine_block);
+criterion_main!(benches);
@@ -0,0 +1,57 @@
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Blockchain, MemPool, Transaction, Wallet};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+const CHAIN_LENGTH: usize = 10_000;
+const MEMPOOL_BATCH: usize = 100;
+
+/// Цепь из `CHAIN_LENGTH` блоков с одной coinbase. Пересчёт сложности
+/// отключён, иначе быстро добытые блоки подняли бы её до неподъёмной.
+fn long_chain() -> Blockchain {
+    let miner = Wallet::new().get_address();
+    let mut bc = Blockchain::default();
+    bc.adjustment_interval = 0;
+    while bc.chain.len() < CHAIN_LENGTH {
+        bc.mine_block(&miner).unwrap();
+    }
+    bc
+}
+
+// ========== CHAIN VALIDATION ==============
+fn validate_chain(c: &mut Criterion) {
+    let bc = long_chain();
+    let mut group = c.benchmark_group("validate_chain");
+    group.sample_size(10);
+    group.bench_function("10k blocks", |b| {
+        b.iter(|| bc.validate_chain(&bc.chain).unwrap())
+    });
+    group.finish();
+}
+
+// ========== MEMPOOL ==============
+/// Вставка `MEMPOOL_BATCH` подписанных транзакций в пустой пул:
+/// проверка подписи плюс поиск дубликатов.
+fn mempool_insert(c: &mut Criterion) {
+    let sender = Wallet::new();
+    let receiver = Wallet::new().get_address();
+    let transactions: Vec<Transaction> = (0..MEMPOOL_BATCH as u64)
+        .map(|nonce| sender.create_transaction(&receiver, coins(1.0), coins(0.1), nonce))
+        .collect();
+
+    c.bench_function("mempool_insert/100 txs", |b| {
+        b.iter_batched(
+            || transactions.clone(),
+            |transactions| {
+                let mut mempool = MemPool::new();
+                for tx in transactions {
+                    mempool.add_transaction(tx).unwrap();
+                }
+                mempool
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, validate_chain, mempool_insert);
+criterion_main!(benches);
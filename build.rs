@@ -0,0 +1,10 @@
+fn main() {
+    // Код gRPC генерируется из proto/node.proto только с фичей `grpc`;
+    // protoc берётся из protoc-bin-vendored, ставить его не нужно
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc");
+        std::env::set_var("PROTOC", protoc);
+        tonic_build::compile_protos("proto/node.proto").expect("compile proto/node.proto");
+    }
+}
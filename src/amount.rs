@@ -0,0 +1,246 @@
+use crate::error::AmountError;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+// ========== AMOUNT ==============
+// Суммы монет в целых наименьших единицах, как сатоши в Bitcoin: в
+// монете `COIN` единиц. В отличие от f64 сумма единиц точна, так что
+// балансы, комиссии и выпуск сходятся до единицы при любом порядке
+// сложения, а равенство сумм надёжно.
+//
+// Консенсус и mempool считают через `checked_*`: переполнение там —
+// повод отвергнуть транзакцию или блок. Операторы `+` и `-` для
+// остального кода паникуют при переполнении в любой сборке.
+//
+// `Display` пишет монеты без лишних нулей (`12.5`, `0.001`, `50`) и
+// входит в подписываемый payload транзакции. В JSON и TOML сумма
+// пишется той же строкой (`"12.5"`) и читается через `FromStr`: f64
+// точен только до 2^53 единиц, а файл цепи, mempool, снимки и дампы
+// должны читаться обратно в те же txid. На входе по-прежнему
+// принимаются и числа монет — для конфигураций и запросов RPC. В
+// bincode сумма — u64 единиц.
+
+/// Единиц в одной монете.
+pub const COIN: u64 = 100_000_000;
+/// Знаков после запятой в записи суммы.
+pub const DECIMALS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+    pub const MAX: Amount = Amount(u64::MAX);
+
+    pub const fn from_units(units: u64) -> Amount {
+        Amount(units)
+    }
+
+    /// Целое число монет. Паникует, если сумма не помещается в u64.
+    pub const fn from_coins(coins: u64) -> Amount {
+        match coins.checked_mul(COIN) {
+            Some(units) => Amount(units),
+            None => panic!("amount overflow"),
+        }
+    }
+
+    /// Сумма из числа монет, округлённая до единицы; `None` для NaN,
+    /// отрицательных и слишком больших чисел. Для входа из f64 (RPC,
+    /// старые конфигурации) — внутри цепь f64 не использует.
+    pub fn from_f64(coins: f64) -> Option<Amount> {
+        let units = (coins * COIN as f64).round();
+        // u64::MAX как f64 округляется вверх до 2^64
+        (units >= 0.0 && units < u64::MAX as f64).then_some(Amount(units as u64))
+    }
+
+    pub const fn units(self) -> u64 {
+        self.0
+    }
+
+    /// Приближение в монетах — для метрик, долей и ответов в JSON.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / COIN as f64
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+
+    pub fn checked_mul(self, factor: u64) -> Option<Amount> {
+        self.0.checked_mul(factor).map(Amount)
+    }
+
+    pub fn checked_div(self, divisor: u64) -> Option<Amount> {
+        self.0.checked_div(divisor).map(Amount)
+    }
+
+    pub fn saturating_add(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_sub(other.0))
+    }
+
+    /// Сумма всех `amounts`; `None` при переполнении.
+    pub fn checked_sum<I: IntoIterator<Item = Amount>>(amounts: I) -> Option<Amount> {
+        amounts
+            .into_iter()
+            .try_fold(Amount::ZERO, |total, amount| total.checked_add(amount))
+    }
+}
+
+impl Add for Amount {
+    type Output = Amount;
+
+    fn add(self, other: Amount) -> Amount {
+        self.checked_add(other).expect("amount overflow")
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, other: Amount) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, other: Amount) -> Amount {
+        self.checked_sub(other).expect("amount underflow")
+    }
+}
+
+impl SubAssign for Amount {
+    fn sub_assign(&mut self, other: Amount) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        Amount::checked_sum(iter).expect("amount overflow")
+    }
+}
+
+impl<'a> Sum<&'a Amount> for Amount {
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Amount {
+        iter.copied().sum()
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let coins = self.0 / COIN;
+        let fraction = self.0 % COIN;
+        if fraction == 0 {
+            return write!(f, "{}", coins);
+        }
+        let digits = format!("{:0width$}", fraction, width = DECIMALS);
+        write!(f, "{}.{}", coins, digits.trim_end_matches('0'))
+    }
+}
+
+impl fmt::Debug for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Amount({})", self)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = AmountError;
+
+    /// Десятичная запись монет: `12`, `12.5`, `.5`, не больше `DECIMALS`
+    /// знаков после точки.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AmountError::Invalid(s.to_string());
+        let (coins, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (coins.is_empty() && fraction.is_empty()) || !is_digits(coins) || !is_digits(fraction) {
+            return Err(invalid());
+        }
+        if fraction.len() > DECIMALS {
+            return Err(AmountError::TooPrecise(s.to_string()));
+        }
+
+        let overflow = || AmountError::Overflow(s.to_string());
+        let coins: u64 = match coins {
+            "" => 0,
+            coins => coins.parse().map_err(|_| overflow())?,
+        };
+        let fraction: u64 = format!("{:0<width$}", fraction, width = DECIMALS)
+            .parse()
+            .map_err(|_| invalid())?;
+        coins
+            .checked_mul(COIN)
+            .and_then(|units| units.checked_add(fraction))
+            .map(Amount)
+            .ok_or_else(overflow)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(CoinsVisitor)
+        } else {
+            u64::deserialize(deserializer).map(Amount)
+        }
+    }
+}
+
+/// Число монет в JSON или TOML: строка, как её пишет `Display`, целое
+/// или дробное число.
+struct CoinsVisitor;
+
+impl Visitor<'_> for CoinsVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a non-negative number of coins")
+    }
+
+    fn visit_u64<E: de::Error>(self, coins: u64) -> Result<Amount, E> {
+        coins
+            .checked_mul(COIN)
+            .map(Amount)
+            .ok_or_else(|| E::custom(AmountError::Overflow(coins.to_string())))
+    }
+
+    fn visit_i64<E: de::Error>(self, coins: i64) -> Result<Amount, E> {
+        let coins =
+            u64::try_from(coins).map_err(|_| E::custom(AmountError::Invalid(coins.to_string())))?;
+        self.visit_u64(coins)
+    }
+
+    fn visit_f64<E: de::Error>(self, coins: f64) -> Result<Amount, E> {
+        Amount::from_f64(coins).ok_or_else(|| E::custom(AmountError::Invalid(coins.to_string())))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Amount, E> {
+        s.parse().map_err(E::custom)
+    }
+}
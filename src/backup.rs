@@ -0,0 +1,212 @@
+use crate::block::{Block, Blockchain, Transaction};
+use crate::snapshot::StateSnapshot;
+use crate::storage::invalid_data;
+use crate::types::Hash256;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+// ========== CHAIN ARCHIVE ==============
+// Вся цепь одним сжатым файлом — для резервных копий и быстрого старта
+// нового узла. После сигнатуры `ARCHIVE_MAGIC` и версии идёт поток zstd:
+// заголовок (сеть и число блоков), блоки в bincode по одному и в конце
+// SHA-256 от всех сериализованных блоков. При импорте у каждого блока
+// сверяются индекс, хеш и связь с родителем, затем контрольная сумма,
+// и только после этого цепь проверяется целиком, как `restore_from_disk`.
+// Обрезанные блоки выгружаются из архива обрезки целиком.
+
+/// Сигнатура файла архива цепи.
+pub const ARCHIVE_MAGIC: &[u8; 4] = b"MBCA";
+/// Версия формата архива цепи.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Уровень сжатия zstd: архив пишется редко, так что можно сжать сильнее.
+const COMPRESSION_LEVEL: i32 = 9;
+
+/// Версия формата JSON-дампа цепи.
+pub const DUMP_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveHeader {
+    chain_id: String,
+    blocks: u64,
+}
+
+impl Blockchain {
+    /// Выгружает всю цепь в сжатый архив `path` (через временный файл).
+    pub fn export_archive<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let chain = self.full_blocks(0..self.chain.len())?;
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        writer.write_all(ARCHIVE_MAGIC)?;
+        writer.write_all(&ARCHIVE_VERSION.to_le_bytes())?;
+
+        let mut encoder = zstd::Encoder::new(writer, COMPRESSION_LEVEL)?;
+        let header = ArchiveHeader {
+            chain_id: self.chain_id().to_string(),
+            blocks: chain.len() as u64,
+        };
+        bincode::serialize_into(&mut encoder, &header).map_err(|e| invalid_data(e.to_string()))?;
+        let mut checksum = Sha256::new();
+        for block in &chain {
+            let bytes = bincode::serialize(block).map_err(|e| invalid_data(e.to_string()))?;
+            checksum.update(&bytes);
+            encoder.write_all(&bytes)?;
+        }
+        let checksum = Hash256(checksum.finalize().into());
+        bincode::serialize_into(&mut encoder, &checksum)
+            .map_err(|e| invalid_data(e.to_string()))?;
+        encoder.finish()?.flush()?;
+        fs::rename(tmp, path)
+    }
+
+    /// Загружает цепь из архива `export_archive`, см. `restore_from_archive`.
+    pub fn import_archive<P: AsRef<Path>>(path: P) -> io::Result<Blockchain> {
+        Blockchain::default().restore_from_archive(path)
+    }
+
+    /// То же, что `import_archive`, но блоки из архива заменяют цепь
+    /// `self`, а её параметры (сложность, mempool, консенсус) остаются
+    /// и используются при проверке.
+    pub fn restore_from_archive<P: AsRef<Path>>(mut self, path: P) -> io::Result<Blockchain> {
+        self.chain = read_archive(path.as_ref())?;
+        self.reindex();
+        self.validate_chain(&self.chain)
+            .map_err(|e| invalid_data(format!("archived chain failed validation: {}", e)))?;
+        self.bits = self.next_bits(&self.chain);
+
+        Ok(self)
+    }
+}
+
+/// Читает блоки архива, проверяя их хеши, связность и контрольную сумму.
+fn read_archive(path: &Path) -> io::Result<Vec<Block>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; 4];
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    reader.read_exact(&mut version)?;
+    if &magic != ARCHIVE_MAGIC {
+        return Err(invalid_data("not a chain archive".to_string()));
+    }
+    let version = u32::from_le_bytes(version);
+    if version != ARCHIVE_VERSION {
+        return Err(invalid_data(format!(
+            "unsupported archive version {}",
+            version
+        )));
+    }
+
+    let mut decoder = zstd::Decoder::new(reader)?;
+    let header: ArchiveHeader =
+        bincode::deserialize_from(&mut decoder).map_err(|e| invalid_data(e.to_string()))?;
+    if header.blocks == 0 {
+        return Err(invalid_data("chain archive is empty".to_string()));
+    }
+
+    let mut chain: Vec<Block> = Vec::new();
+    let mut checksum = Sha256::new();
+    for height in 0..header.blocks {
+        let block: Block = bincode::deserialize_from(&mut decoder)
+            .map_err(|e| invalid_data(format!("block {}: {}", height, e)))?;
+        let linked = chain
+            .last()
+            .is_none_or(|prev| block.header.prev_hash == prev.hash);
+        if block.header.index as u64 != height || !linked || !block.has_valid_hash() {
+            return Err(invalid_data(format!(
+                "archived block {} is corrupted",
+                height
+            )));
+        }
+        checksum.update(bincode::serialize(&block).map_err(|e| invalid_data(e.to_string()))?);
+        chain.push(block);
+    }
+
+    let expected: Hash256 =
+        bincode::deserialize_from(&mut decoder).map_err(|e| invalid_data(e.to_string()))?;
+    if Hash256(checksum.finalize().into()) != expected {
+        return Err(invalid_data("chain archive checksum mismatch".to_string()));
+    }
+    if chain[0].genesis_chain_id() != Some(header.chain_id.as_str()) {
+        return Err(invalid_data(format!(
+            "archive header names chain {}",
+            header.chain_id
+        )));
+    }
+    Ok(chain)
+}
+
+// ========== JSON DUMP ==============
+// Та же цепь, но читаемым JSON — для отчётов об ошибках и тестовых
+// фикстур: блоки с транзакциями, балансы на вершине и mempool. Дамп можно
+// открыть и поправить руками, поэтому при импорте цепь проверяется
+// целиком, а записанные балансы должны совпасть с пересчитанными по
+// блокам. Транзакции mempool заново проходят `add_transaction`.
+
+/// Полный дамп цепи, см. `Blockchain::export_json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainDump {
+    pub version: u32,
+    pub chain_id: String,
+    pub blocks: Vec<Block>,
+    /// Балансы на вершине цепи.
+    pub state: StateSnapshot,
+    pub mempool: Vec<Transaction>,
+}
+
+impl Blockchain {
+    /// Пишет в `writer` дамп всей цепи в JSON с отступами.
+    pub fn export_json<W: Write>(&self, writer: W) -> io::Result<()> {
+        let dump = ChainDump {
+            version: DUMP_VERSION,
+            chain_id: self.chain_id().to_string(),
+            blocks: self.full_blocks(0..self.chain.len())?,
+            state: self.snapshot(),
+            mempool: self.mempool.transactions.clone(),
+        };
+        serde_json::to_writer_pretty(writer, &dump)?;
+        Ok(())
+    }
+
+    /// Загружает цепь из дампа `export_json`, см. `restore_from_json`.
+    pub fn import_json<R: Read>(reader: R) -> io::Result<Blockchain> {
+        Blockchain::default().restore_from_json(reader)
+    }
+
+    /// То же, что `import_json`, но блоки дампа заменяют цепь `self`, а
+    /// её параметры остаются и используются при проверке.
+    pub fn restore_from_json<R: Read>(mut self, reader: R) -> io::Result<Blockchain> {
+        let dump: ChainDump =
+            serde_json::from_reader(reader).map_err(|e| invalid_data(e.to_string()))?;
+        if dump.version != DUMP_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported dump version {}",
+                dump.version
+            )));
+        }
+        let genesis = dump
+            .blocks
+            .first()
+            .ok_or_else(|| invalid_data("chain dump is empty".to_string()))?;
+        if genesis.genesis_chain_id() != Some(dump.chain_id.as_str()) {
+            return Err(invalid_data(format!("dump names chain {}", dump.chain_id)));
+        }
+
+        self.chain = dump.blocks;
+        self.reindex();
+        self.validate_chain(&self.chain)
+            .map_err(|e| invalid_data(format!("dumped chain failed validation: {}", e)))?;
+        self.bits = self.next_bits(&self.chain);
+        if self.snapshot() != dump.state {
+            return Err(invalid_data(
+                "dumped state does not match the blocks".to_string(),
+            ));
+        }
+        self.mempool.clear();
+        self.restore_mempool(dump.mempool);
+        Ok(self)
+    }
+}
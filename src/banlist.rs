@@ -0,0 +1,130 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+// ========== PEER SCORING ==============
+// Каждое нарушение протокола стоит пиру штрафных очков. Набравший
+// `BAN_THRESHOLD` отключается, а его IP попадает в бан-лист на
+// `ban_duration` секунд: входящие соединения с него сразу закрываются,
+// исходящие к нему не открываются. Банится адрес, а не порт — иначе
+// нарушителю хватило бы переподключиться. По истечении бана счёт
+// начинается с нуля.
+
+/// Сколько очков нужно набрать для бана.
+pub const BAN_THRESHOLD: u32 = 100;
+
+/// Срок бана по умолчанию — сутки.
+pub const DEFAULT_BAN_DURATION: u64 = 24 * 60 * 60;
+
+/// Нарушение, за которое пир получает штраф.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// Блок, не прошедший проверку.
+    InvalidBlock,
+    /// Заголовки без связности или без работы.
+    InvalidHeaders,
+    /// Кадр, который не разбирается как сообщение.
+    MalformedMessage,
+    /// Транзакция с неверной подписью или полями.
+    InvalidTransaction,
+    /// Ответ, о котором мы не просили, или не тот ответ на запрос.
+    UnsolicitedMessage,
+    /// Сообщение сверх лимита частоты, см. `ratelimit`.
+    Flooding,
+}
+
+impl Misbehavior {
+    pub fn penalty(self) -> u32 {
+        match self {
+            Misbehavior::InvalidBlock | Misbehavior::InvalidHeaders => BAN_THRESHOLD,
+            Misbehavior::MalformedMessage => 20,
+            Misbehavior::InvalidTransaction
+            | Misbehavior::UnsolicitedMessage
+            | Misbehavior::Flooding => 10,
+        }
+    }
+}
+
+/// Забаненный адрес и момент (unix-время), когда бан истечёт.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BannedPeer {
+    pub ip: IpAddr,
+    pub banned_until: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BanList {
+    /// Срок бана в секундах.
+    pub ban_duration: u64,
+    scores: HashMap<IpAddr, u32>,
+    /// IP → конец бана.
+    banned: HashMap<IpAddr, u64>,
+}
+
+impl BanList {
+    pub fn new(ban_duration: u64) -> Self {
+        BanList {
+            ban_duration,
+            scores: HashMap::new(),
+            banned: HashMap::new(),
+        }
+    }
+
+    /// Начисляет штраф за нарушение. `true` — если пир этим забанен.
+    pub fn penalize(&mut self, ip: IpAddr, misbehavior: Misbehavior, now: u64) -> bool {
+        if self.is_banned(ip, now) {
+            return false;
+        }
+        let score = self.scores.entry(ip).or_insert(0);
+        *score = score.saturating_add(misbehavior.penalty());
+        if *score < BAN_THRESHOLD {
+            return false;
+        }
+        self.ban(ip, now);
+        true
+    }
+
+    /// Банит адрес на `ban_duration` от `now` независимо от счёта.
+    pub fn ban(&mut self, ip: IpAddr, now: u64) {
+        self.scores.remove(&ip);
+        self.banned
+            .insert(ip, now.saturating_add(self.ban_duration));
+    }
+
+    /// Снимает бан. `false` — если адрес не был забанен.
+    pub fn unban(&mut self, ip: IpAddr) -> bool {
+        self.banned.remove(&ip).is_some()
+    }
+
+    pub fn is_banned(&self, ip: IpAddr, now: u64) -> bool {
+        self.banned.get(&ip).is_some_and(|&until| until > now)
+    }
+
+    /// Штрафные очки, набранные адресом с последнего бана.
+    pub fn score(&self, ip: IpAddr) -> u32 {
+        self.scores.get(&ip).copied().unwrap_or(0)
+    }
+
+    /// Действующие баны, упорядоченные по адресу; истёкшие забываются.
+    pub fn banned(&mut self, now: u64) -> Vec<BannedPeer> {
+        self.banned.retain(|_, until| *until > now);
+        let mut peers: Vec<BannedPeer> = self
+            .banned
+            .iter()
+            .map(|(&ip, &banned_until)| BannedPeer { ip, banned_until })
+            .collect();
+        peers.sort_by_key(|peer| peer.ip);
+        peers
+    }
+}
+
+impl Default for BanList {
+    fn default() -> Self {
+        Self::new(DEFAULT_BAN_DURATION)
+    }
+}
+
+/// IP из адреса пира вида `ip:port`; `None` для имён хостов.
+pub fn peer_ip(addr: &str) -> Option<IpAddr> {
+    addr.parse::<SocketAddr>().ok().map(|addr| addr.ip())
+}
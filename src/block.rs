@@ -1,320 +1,2400 @@
 #![allow(dead_code)]
 
-use rand::Rng;
+use crate::amount::Amount;
+use crate::blockstore::{BlockStore, DEFAULT_HOT_BLOCKS};
+use crate::clock::{system_clock, Clock, SystemClock};
+use crate::consensus::{Consensus, ProofOfWork, SignedBlocks, STAKE_ADDRESS};
+use crate::error::{
+    BlockchainError, ChainError, ConsensusError, TxValidationError, ValidationError, WalletError,
+};
+use crate::genesis::{ChainMode, GenesisConfig, DEFAULT_CHAIN_ID};
+use crate::hashing::HashAlgorithm;
+use crate::merkle::{self, MerkleProof};
+use crate::mining::{self, MiningOptions, MiningStats};
+use crate::multisig::{Cosignature, MultisigPolicy};
+use crate::orphans::OrphanPool;
+use crate::pruning::BlockArchive;
+use crate::script::{self, Script, ScriptContext};
+use crate::selection::{FeePriority, TxSelection};
+use crate::signer::Signer;
+use crate::tokens::{TokenInfo, TokenLedger, TokenOp};
+use crate::types::{Address, Hash256, Signature, Target};
+use crate::utxo::{BlockUndo, UtxoSet, UtxoTransaction};
+use crate::validation::{self, ReplayedState, StateView, ValidationContext};
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 use secp256k1::{PublicKey, Secp256k1, SecretKey};
-use sha2::{Digest, Sha256};
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::{debug, info_span, warn};
+
+pub const COINBASE_ADDRESS: &str = "COINBASE";
+/// Начальная цель PoW в компактной форме: `Target::MAX`, сложность 1.0.
+pub const INITIAL_BITS: u32 = 0x2000_ffff;
+/// Желаемое время между блоками в секундах.
+pub const DEFAULT_TARGET_BLOCK_TIME: u64 = 10;
+/// Через сколько блоков пересчитывается сложность.
+pub const DEFAULT_ADJUSTMENT_INTERVAL: u32 = 10;
+/// Награда за блок до первого уполовинивания.
+pub const DEFAULT_BLOCK_REWARD: Amount = Amount::from_coins(50);
+/// Через сколько блоков награда уменьшается вдвое.
+pub const DEFAULT_HALVING_INTERVAL: u32 = 210_000;
+/// Сколько транзакций mempool держит по умолчанию.
+pub const DEFAULT_MEMPOOL_SIZE: usize = 5_000;
+/// Сколько секунд транзакция может ждать включения в блок.
+pub const DEFAULT_MEMPOOL_TTL: u64 = 3 * 60 * 60;
+/// Правило консенсуса: не больше транзакций в блоке, считая coinbase
+/// и UTXO-транзакции.
+pub const MAX_BLOCK_TRANSACTIONS: usize = 1_000;
+/// Правило консенсуса: предел размера блока в байтах, см. `Block::serialized_size`.
+pub const MAX_BLOCK_SIZE: usize = 1_000_000;
+/// Время блока должно быть больше медианы времён стольких последних блоков.
+pub const MEDIAN_TIME_SPAN: usize = 11;
+/// Насколько секунд время блока может опережать часы узла.
+pub const MAX_FUTURE_BLOCK_TIME: u64 = 2 * 60 * 60;
+/// Правило консенсуса: предел поля `data` транзакции в байтах.
+pub const MAX_TX_DATA_SIZE: usize = 512;
+/// Минимальная комиссия за каждый байт `data`.
+pub const DATA_FEE_PER_BYTE: Amount = Amount::from_units(100_000);
+/// Сколько байт `MAX_BLOCK_SIZE` оставляется при сборке блока под
+/// заголовок и подпись производителя.
+const BLOCK_HEADER_RESERVE: usize = 512;
+
+/// Текущее время системных часов в секундах Unix.
+pub(crate) fn unix_now() -> u64 {
+    SystemClock.now()
+}
+
+// ========== TRANSACTION ==============
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Transaction {
+    pub from: Address,
+    pub to: Address,
+    pub amount: Amount,
+    pub fee: Amount,
+    pub timestamp: u64,
+    /// Порядковый номер транзакции отправителя. Повторно использовать
+    /// номер нельзя, поэтому перехваченную транзакцию не воспроизвести.
+    pub nonce: u64,
+    pub signature: Signature,
+    pub public_key: String,
+    /// Сеть, для которой подписана транзакция. Входит в подпись, так что
+    /// транзакцию из одной сети нельзя воспроизвести в другой.
+    #[serde(default)]
+    pub chain_id: String,
+    /// Политика, если `from` — адрес M-из-N (см. `multisig`). Тогда
+    /// `signature` и `public_key` пусты, а подписи лежат в `cosignatures`.
+    #[serde(default)]
+    pub multisig: Option<MultisigPolicy>,
+    #[serde(default)]
+    pub cosignatures: Vec<Cosignature>,
+    /// Произвольные данные приложения (хеш документа, сообщение), не
+    /// больше `MAX_TX_DATA_SIZE` байт. Входят в подпись и txid, а значит
+    /// и в merkle-корень блока; в JSON — hex-строка.
+    #[serde(default, with = "hex_data")]
+    pub data: Vec<u8>,
+    /// Операция с токеном, см. `tokens`.
+    #[serde(default)]
+    pub token: Option<TokenOp>,
+    /// Раньше этой высоты или этого времени транзакцию нельзя включить
+    /// в блок; до тех пор она ждёт в mempool.
+    #[serde(default)]
+    pub lock_time: Option<LockTime>,
+    /// Программа, которой заперт `from` (см. `script`): тогда `from` —
+    /// её адрес (`Script::address`), `signature` и `public_key` пусты, а
+    /// отпирающие данные лежат в `script_sig`.
+    #[serde(default)]
+    pub lock_script: Option<Script>,
+    #[serde(default)]
+    pub script_sig: Script,
+}
+
+/// Момент, с которого транзакция может попасть в блок.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockTime {
+    /// Индекс блока, не меньше которого должен быть включающий блок.
+    Height(u64),
+    /// Unix-время, не раньше которого должен быть добыт включающий блок.
+    Timestamp(u64),
+}
+
+impl LockTime {
+    /// Созрела ли транзакция для блока с индексом `height` и временем `time`.
+    pub fn is_reached(&self, height: u64, time: u64) -> bool {
+        match *self {
+            LockTime::Height(lock) => height >= lock,
+            LockTime::Timestamp(lock) => time >= lock,
+        }
+    }
+}
+
+impl fmt::Display for LockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockTime::Height(height) => write!(f, "height:{}", height),
+            LockTime::Timestamp(time) => write!(f, "time:{}", time),
+        }
+    }
+}
+
+/// `data` в JSON как hex-строка, а не массив чисел.
+mod hex_data {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Transaction {
+    /// Создаёт неподписанную транзакцию; подпись ставит кошелёк.
+    pub fn new(
+        from: Address,
+        to: Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+        public_key: String,
+    ) -> Transaction {
+        Transaction {
+            from,
+            to,
+            amount,
+            fee,
+            timestamp: unix_now(),
+            nonce,
+            signature: Signature::EMPTY,
+            public_key,
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            multisig: None,
+            cosignatures: Vec::new(),
+            data: Vec::new(),
+            token: None,
+            lock_time: None,
+            lock_script: None,
+            script_sig: Script::default(),
+        }
+    }
+
+    /// Неподписанный перевод с адреса политики `policy`; подписи
+    /// собираются через `Wallet::co_sign`.
+    pub fn new_multisig(
+        policy: &MultisigPolicy,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        Transaction {
+            multisig: Some(policy.clone()),
+            ..Transaction::new(
+                policy.address(),
+                to.clone(),
+                amount,
+                fee,
+                nonce,
+                String::new(),
+            )
+        }
+    }
+
+    /// Неподписанный перевод с адреса скрипта `lock`; отпирающие данные
+    /// кладутся в `script_sig` (см. `script_signature`).
+    pub fn new_script(
+        lock: &Script,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        Transaction {
+            lock_script: Some(lock.clone()),
+            ..Transaction::new(
+                lock.address(),
+                to.clone(),
+                amount,
+                fee,
+                nonce,
+                String::new(),
+            )
+        }
+    }
+
+    /// Coinbase-транзакция: награда майнеру за блок плюс комиссии.
+    /// В качестве nonce используется высота блока, чтобы txid был уникален.
+    pub fn coinbase(to: Address, amount: Amount, height: u32) -> Transaction {
+        Transaction::new(
+            Address::from(COINBASE_ADDRESS),
+            to,
+            amount,
+            Amount::ZERO,
+            height as u64,
+            String::new(),
+        )
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        self.from.as_str() == COINBASE_ADDRESS
+    }
+
+    /// Идентификатор транзакции (txid): SHA-256 канонической записи всех
+    /// её полей вместе с подписью. По нему mempool и индекс цепи ищут
+    /// дубликаты, gossip помнит уже виденное, а дерево Меркла берёт листья.
+    /// Не хранится в структуре: поля публичны, и сохранённый txid мог бы
+    /// разойтись с содержимым.
+    pub fn id(&self) -> Hash256 {
+        let input = format!("{}|{}", self.signing_payload(), self.signature);
+        Hash256::digest(input.as_bytes())
+    }
+
+    /// Размер транзакции в bincode — её вклад в `Block::serialized_size`.
+    pub fn serialized_size(&self) -> usize {
+        bincode::serialized_size(self).expect("Transaction serialization failed") as usize
+    }
+
+    /// Каноническое представление всех полей, кроме подписей; именно его
+    /// подписывает кошелёк и проверяет `verify_signature`.
+    pub fn signing_payload(&self) -> String {
+        let payload = format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}",
+            self.chain_id,
+            self.from,
+            self.to,
+            self.amount,
+            self.fee,
+            self.timestamp,
+            self.nonce,
+            self.public_key
+        );
+        let payload = match &self.multisig {
+            Some(policy) => format!("{}|{}", payload, policy),
+            None => payload,
+        };
+        // Без данных и токенов payload прежний, и txid старых транзакций
+        // не меняются
+        let payload = if self.data.is_empty() {
+            payload
+        } else {
+            format!("{}|data:{}", payload, hex::encode(&self.data))
+        };
+        let payload = match &self.token {
+            Some(op) => format!("{}|token:{}", payload, op),
+            None => payload,
+        };
+        let payload = match &self.lock_time {
+            Some(lock_time) => format!("{}|lock:{}", payload, lock_time),
+            None => payload,
+        };
+        match &self.lock_script {
+            Some(lock) => format!("{}|script:{}", payload, lock),
+            None => payload,
+        }
+    }
+
+    /// Может ли транзакция войти в блок с индексом `height` и временем `time`.
+    pub fn is_final(&self, height: u64, time: u64) -> bool {
+        self.lock_time
+            .is_none_or(|lock_time| lock_time.is_reached(height, time))
+    }
+
+    /// Наименьшая допустимая комиссия: плата за байты `data`.
+    pub fn min_fee(&self) -> Amount {
+        DATA_FEE_PER_BYTE
+            .checked_mul(self.data.len() as u64)
+            .unwrap_or(Amount::MAX)
+    }
+
+    /// Проверяет, что открытый ключ принадлежит отправителю и подпись
+    /// покрывает канонический payload; для адреса M-из-N — что подписей
+    /// ключами его политики набралось не меньше порога; для адреса
+    /// скрипта — что скрипт принимает `script_sig`.
+    pub fn verify_signature(&self) -> Result<(), TxValidationError> {
+        if let Some(policy) = &self.multisig {
+            return policy.verify(&self.from, &self.signing_payload(), &self.cosignatures);
+        }
+        if let Some(lock) = &self.lock_script {
+            return self.verify_script(lock);
+        }
+        verify_ecdsa(
+            &self.public_key,
+            &self.from,
+            &self.signing_payload(),
+            &self.signature,
+        )
+    }
+
+    /// Исполняет `script_sig` и запирающий скрипт `lock`. Блок с
+    /// транзакцией не ниже её `lock_time` (см. `is_final`), так что
+    /// `OP_CHECKLOCKTIMEVERIFY` сверяется с этой высотой.
+    fn verify_script(&self, lock: &Script) -> Result<(), TxValidationError> {
+        if lock.address() != self.from {
+            return Err(TxValidationError::PublicKeyMismatch);
+        }
+        let height = match self.lock_time {
+            Some(LockTime::Height(height)) => u32::try_from(height).unwrap_or(u32::MAX),
+            _ => 0,
+        };
+        let context = ScriptContext {
+            payload: &self.signing_payload(),
+            height,
+        };
+        script::verify(&self.script_sig, lock, context)
+            .map(|_| ())
+            .map_err(TxValidationError::Script)
+    }
+
+    /// Подпись `signer` для `script_sig` — compact-байты, которые
+    /// проверяют `OP_CHECKSIG` и `OP_CHECKMULTISIG`.
+    pub fn script_signature(&self, signer: &dyn Signer) -> Vec<u8> {
+        signer.sign_payload(&self.signing_payload()).0.to_vec()
+    }
+
+    /// Подписывает перевод ключом `signer` и ставит его открытый ключ.
+    pub fn sign_with(&mut self, signer: &dyn Signer) {
+        self.public_key = signer.public_key();
+        self.signature = signer.sign_payload(&self.signing_payload());
+    }
+
+    /// Добавляет к переводу с адреса M-из-N подпись `signer` (или
+    /// заменяет прежнюю подпись тем же ключом).
+    pub fn co_sign_with(&mut self, signer: &dyn Signer) -> Result<(), WalletError> {
+        let public_key = signer.public_key();
+        match &self.multisig {
+            Some(policy) if policy.contains(&public_key) => {}
+            _ => return Err(WalletError::NotACosigner),
+        }
+        let signature = signer.sign_payload(&self.signing_payload());
+        self.cosignatures
+            .retain(|cosignature| cosignature.public_key != public_key);
+        self.cosignatures.push(Cosignature {
+            public_key,
+            signature,
+        });
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), TxValidationError> {
+        // Списание `amount + fee` должно помещаться в сумму
+        if self.amount.checked_add(self.fee).is_none() {
+            return Err(TxValidationError::AmountOverflow);
+        }
+        // Операция с токеном может не переводить монет цепи
+        let amount_ok = match &self.token {
+            Some(op) => {
+                op.validate()?;
+                true
+            }
+            None => !self.amount.is_zero(),
+        };
+        if !amount_ok {
+            return Err(TxValidationError::NonPositiveAmount);
+        }
+
+        if self.data.len() > MAX_TX_DATA_SIZE {
+            return Err(TxValidationError::DataTooLarge {
+                size: self.data.len(),
+                max: MAX_TX_DATA_SIZE,
+            });
+        }
+
+        if self.fee < self.min_fee() {
+            return Err(TxValidationError::FeeTooLow {
+                required: self.min_fee(),
+                provided: self.fee,
+            });
+        }
+
+        if self.from.is_empty() || self.to.is_empty() {
+            return Err(TxValidationError::MissingAddress);
+        }
+
+        self.from
+            .validate()
+            .map_err(TxValidationError::InvalidAddress)?;
+        // Получатель стейкинга — служебный адрес, не Base58Check
+        if self.to.as_str() != STAKE_ADDRESS {
+            self.to
+                .validate()
+                .map_err(TxValidationError::InvalidAddress)?;
+        }
+
+        // Эмитент может оставить выпуск себе
+        let issuing = matches!(
+            self.token,
+            Some(TokenOp::CreateToken { .. } | TokenOp::Mint { .. })
+        );
+        if self.from == self.to && !issuing {
+            return Err(TxValidationError::SelfTransfer);
+        }
+
+        let key_signed = !self.signature.is_empty() || !self.public_key.is_empty();
+        let unsigned = if self.lock_script.is_some() {
+            self.script_sig.0.is_empty()
+        } else if self.multisig.is_some() {
+            self.cosignatures.is_empty()
+        } else {
+            self.signature.is_empty() || self.public_key.is_empty()
+        };
+        if unsigned {
+            return Err(TxValidationError::MissingSignature);
+        }
+        // Лишние подписи не входят в txid, и их не должно быть вовсе
+        let stray = if self.lock_script.is_some() {
+            key_signed || self.multisig.is_some() || !self.cosignatures.is_empty()
+        } else if self.multisig.is_some() {
+            key_signed || !self.script_sig.0.is_empty()
+        } else {
+            !self.cosignatures.is_empty() || !self.script_sig.0.is_empty()
+        };
+        if stray {
+            return Err(TxValidationError::InvalidSignature);
+        }
+
+        self.verify_signature()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+}
+
+/// Кратко, с txid — так транзакции выглядят в логах узла.
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} → {}: {}, fee {})",
+            self.id(),
+            self.from,
+            self.to,
+            self.amount,
+            self.fee
+        )
+    }
+}
+
+impl fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("txid", &self.id())
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("amount", &self.amount)
+            .field("fee", &self.fee)
+            .field("timestamp", &self.timestamp)
+            .field("nonce", &self.nonce)
+            .field("signature", &self.signature)
+            .field("public_key", &self.public_key)
+            .field("chain_id", &self.chain_id)
+            .field("multisig", &self.multisig)
+            .field("cosignatures", &self.cosignatures)
+            .field("data", &hex::encode(&self.data))
+            .field("token", &self.token)
+            .field("lock_time", &self.lock_time)
+            .field("lock_script", &self.lock_script)
+            .field("script_sig", &self.script_sig)
+            .finish()
+    }
+}
+
+/// Проверяет, что `public_key_hex` соответствует адресу `owner` и что
+/// `signature` — подпись этим ключом SHA-256 от `payload`.
+pub(crate) fn verify_ecdsa(
+    public_key_hex: &str,
+    owner: &Address,
+    payload: &str,
+    signature: &Signature,
+) -> Result<(), TxValidationError> {
+    let key_bytes = hex::decode(public_key_hex).map_err(|_| TxValidationError::InvalidSignature)?;
+    let public_key =
+        PublicKey::from_slice(&key_bytes).map_err(|_| TxValidationError::InvalidSignature)?;
+
+    if &Address::from_public_key(&key_bytes) != owner {
+        return Err(TxValidationError::PublicKeyMismatch);
+    }
+
+    let secp = Secp256k1::verification_only();
+    let digest = Sha256::digest(payload.as_bytes());
+    let message = secp256k1::Message::from_digest_slice(&digest)
+        .map_err(|_| TxValidationError::InvalidSignature)?;
+    let signature = secp256k1::ecdsa::Signature::from_compact(&signature.0)
+        .map_err(|_| TxValidationError::InvalidSignature)?;
+
+    secp.verify_ecdsa(&message, &signature, &public_key)
+        .map_err(|_| TxValidationError::InvalidSignature)
+}
+
+/// Медиана времён (0 для пустого списка).
+pub(crate) fn median_time(mut times: Vec<u64>) -> u64 {
+    times.sort_unstable();
+    times.get(times.len() / 2).copied().unwrap_or(0)
+}
+
+// ========== BLOCK HEADER ==============
+/// Заголовок блока: всё, что входит в его хеш, без транзакций. Заголовки
+/// хешируются, передаются и хранятся отдельно от тела: по их цепочке
+/// можно проверить работу и связность, а по merkle-корню — включение
+/// транзакции (см. `LightClient`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockHeader {
+    pub index: u32,
+    pub timestamp: u64,
+    pub prev_hash: Hash256,
+    pub merkle_root: Hash256,
+    pub nonce: u64,
+    /// Второй счётчик перебора: когда все значения `nonce` перебраны,
+    /// майнер увеличивает его и начинает `nonce` заново, см. `Block::solve`.
+    #[serde(default)]
+    pub extra_nonce: u64,
+    /// Цель PoW в компактной форме nBits, см. `Target`.
+    pub bits: u32,
+    /// Хеш-функция заголовка; у всех блоков цепи та же, что у genesis.
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+}
+
+impl BlockHeader {
+    /// Хеш заголовка функцией `hash_algorithm`. Поля кодируются в
+    /// фиксированные 96 байт (числа — little-endian), чтобы перебор nonce
+    /// не форматировал строки.
+    pub fn compute_hash(&self) -> Hash256 {
+        let mut header = [0u8; 96];
+        header[0..4].copy_from_slice(&self.index.to_le_bytes());
+        header[4..12].copy_from_slice(&self.timestamp.to_le_bytes());
+        header[12..44].copy_from_slice(self.merkle_root.as_bytes());
+        header[44..76].copy_from_slice(self.prev_hash.as_bytes());
+        header[76..84].copy_from_slice(&self.nonce.to_le_bytes());
+        header[84..92].copy_from_slice(&self.extra_nonce.to_le_bytes());
+        header[92..96].copy_from_slice(&self.bits.to_le_bytes());
+
+        self.hash_algorithm.hash(&header)
+    }
+
+    /// Работа, доказанная заголовком: `Target::work` его цели (0 для
+    /// некорректной цели). Ветки сравниваются по сумме работы.
+    pub fn work(&self) -> u128 {
+        Target::from_compact(self.bits).map_or(0, |target| target.work())
+    }
+
+    /// Транзакция `txid` входит в блок с этим заголовком.
+    pub fn verify_merkle_proof(&self, txid: &Hash256, proof: &MerkleProof) -> bool {
+        proof.verify(txid, &self.merkle_root)
+    }
+}
+
+// ========== BLOCK ==============
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Block {
+    pub header: BlockHeader,
+    /// Хеш заголовка, вычисленный при добыче; `has_valid_hash` сверяет
+    /// его с `header.compute_hash()`.
+    pub hash: Hash256,
+    pub transactions: Vec<Transaction>,
+    /// Открытый ключ производителя блока и его подпись хеша блока.
+    /// Обязательны в PoS и в сетях с `SignedBlocks::permissioned`,
+    /// в остальных сетях необязательны, см. `Block::sign`.
+    #[serde(default)]
+    pub producer_key: String,
+    #[serde(default)]
+    pub producer_signature: Signature,
+    /// Транзакции модели UTXO, см. `utxo`. Идут в merkle-корень после
+    /// `transactions`.
+    #[serde(default)]
+    pub utxo_transactions: Vec<UtxoTransaction>,
+}
+
+impl Block {
+    /// Добывает блок с хешем SHA-256.
+    pub fn new(index: u32, transactions: Vec<Transaction>, prev_hash: Hash256, bits: u32) -> Block {
+        Self::mine(
+            HashAlgorithm::default(),
+            index,
+            transactions,
+            prev_hash,
+            bits,
+            &AtomicBool::new(false),
+        )
+        .expect("mining without cancellation always completes")
+    }
+
+    /// Перебирает nonce, пока хеш не опустится до цели `bits`. Возвращает
+    /// `None`, если во время перебора был поднят флаг `cancel`
+    /// (например, пир прислал блок на той же высоте).
+    pub fn mine(
+        algorithm: HashAlgorithm,
+        index: u32,
+        transactions: Vec<Transaction>,
+        prev_hash: Hash256,
+        bits: u32,
+        cancel: &AtomicBool,
+    ) -> Option<Block> {
+        Self::mine_with(
+            &MiningOptions::default(),
+            algorithm,
+            index,
+            transactions,
+            prev_hash,
+            bits,
+            cancel,
+        )
+        .0
+    }
+
+    /// То же, что `mine`, но перебор идёт в `options.threads` потоках;
+    /// вместе с блоком возвращается статистика перебора.
+    pub fn mine_with(
+        options: &MiningOptions,
+        algorithm: HashAlgorithm,
+        index: u32,
+        transactions: Vec<Transaction>,
+        prev_hash: Hash256,
+        bits: u32,
+        cancel: &AtomicBool,
+    ) -> (Option<Block>, MiningStats) {
+        Self::template(algorithm, index, unix_now(), transactions, prev_hash, bits)
+            .solve(options, cancel)
+    }
+
+    /// То же, что `mine`, но с заданным временем блока.
+    pub fn mine_at(
+        algorithm: HashAlgorithm,
+        index: u32,
+        timestamp: u64,
+        transactions: Vec<Transaction>,
+        prev_hash: Hash256,
+        bits: u32,
+        cancel: &AtomicBool,
+    ) -> Option<Block> {
+        Self::template(algorithm, index, timestamp, transactions, prev_hash, bits)
+            .solve(&MiningOptions::default(), cancel)
+            .0
+    }
+
+    /// Заготовка блока: всё, кроме nonce и хеша; их подбирает `solve`.
+    pub fn template(
+        algorithm: HashAlgorithm,
+        index: u32,
+        timestamp: u64,
+        transactions: Vec<Transaction>,
+        prev_hash: Hash256,
+        bits: u32,
+    ) -> Block {
+        Block {
+            header: BlockHeader {
+                index,
+                timestamp,
+                prev_hash,
+                merkle_root: Self::compute_merkle_root(&transactions),
+                nonce: 0,
+                extra_nonce: 0,
+                bits,
+                hash_algorithm: algorithm,
+            },
+            hash: Hash256::ZERO,
+            transactions,
+            producer_key: String::new(),
+            producer_signature: Signature::EMPTY,
+            utxo_transactions: Vec::new(),
+        }
+    }
+
+    /// Ставит в заготовку UTXO-транзакции и пересчитывает merkle-корень.
+    pub fn with_utxo_transactions(mut self, utxo_transactions: Vec<UtxoTransaction>) -> Block {
+        self.header.merkle_root = Self::body_merkle_root(&self.transactions, &utxo_transactions);
+        self.utxo_transactions = utxo_transactions;
+        self
+    }
+
+    /// Подбирает nonce заготовке, начиная с её `header.nonce`. Если все
+    /// nonce до `u64::MAX` перебраны, увеличивает `extra_nonce` и
+    /// перебирает `nonce` с нуля. `None`, если цель `bits` некорректна,
+    /// перебор отменён или исчерпан и `extra_nonce`.
+    pub fn solve(
+        mut self,
+        options: &MiningOptions,
+        cancel: &AtomicBool,
+    ) -> (Option<Block>, MiningStats) {
+        let Some(target) = Target::from_compact(self.header.bits) else {
+            return (None, MiningStats::default());
+        };
+        let mut total = MiningStats::default();
+        loop {
+            let template = self.header;
+            let header = |nonce| BlockHeader { nonce, ..template }.compute_hash();
+            let (solution, stats) =
+                mining::search_nonce(header, template.nonce, &target, options, cancel);
+            total.hashes += stats.hashes;
+            total.elapsed += stats.elapsed;
+
+            if let Some((nonce, hash)) = solution {
+                self.header.nonce = nonce;
+                self.hash = hash;
+                return (Some(self), total);
+            }
+            if cancel.load(Ordering::Relaxed) {
+                return (None, total);
+            }
+            let Some(extra_nonce) = template.extra_nonce.checked_add(1) else {
+                return (None, total);
+            };
+            self.header.extra_nonce = extra_nonce;
+            self.header.nonce = 0;
+        }
+    }
+
+    /// Genesis-блок сети по умолчанию, см. `GenesisConfig`.
+    pub fn genesis() -> Self {
+        GenesisConfig::default().block()
+    }
+
+    /// Merkle-корень блока без UTXO-транзакций.
+    pub fn compute_merkle_root(transactions: &[Transaction]) -> Hash256 {
+        Self::body_merkle_root(transactions, &[])
+    }
+
+    /// Merkle-корень счётных транзакций, за которыми идут UTXO-транзакции.
+    pub fn body_merkle_root(
+        transactions: &[Transaction],
+        utxo_transactions: &[UtxoTransaction],
+    ) -> Hash256 {
+        merkle::merkle_root(&Self::leaves(transactions, utxo_transactions))
+    }
+
+    fn leaves(transactions: &[Transaction], utxo_transactions: &[UtxoTransaction]) -> Vec<Hash256> {
+        transactions
+            .iter()
+            .map(|tx| tx.id())
+            .chain(utxo_transactions.iter().map(|tx| tx.id()))
+            .collect()
+    }
+
+    /// Merkle-корень, посчитанный по транзакциям блока.
+    pub fn merkle_root(&self) -> Hash256 {
+        Self::body_merkle_root(&self.transactions, &self.utxo_transactions)
+    }
+
+    /// Блок корректен как преемник последнего из `ancestors`, несёт
+    /// достаточную работу и его время проходит `is_valid_timestamp`
+    /// по часам узла.
+    pub fn validate(&self, ancestors: &[Block]) -> Result<(), ValidationError> {
+        self.validate_at(ancestors, unix_now())
+    }
+
+    /// То же, что `validate`, но время сверяется с `now`.
+    pub fn validate_at(&self, ancestors: &[Block], now: u64) -> Result<(), ValidationError> {
+        let prev = ancestors.last().ok_or(ValidationError::MissingParent)?;
+        validation::check_successor(self, prev)?;
+        if !Self::meets_target(&self.hash, self.header.bits) {
+            return Err(ValidationError::BadPoW);
+        }
+        validation::check_timestamp(self, ancestors, now)
+    }
+
+    pub fn is_valid(&self, ancestors: &[Block]) -> bool {
+        self.validate(ancestors).is_ok()
+    }
+
+    pub fn is_valid_at(&self, ancestors: &[Block], now: u64) -> bool {
+        self.validate_at(ancestors, now).is_ok()
+    }
+
+    /// Время блока больше медианы последних `MEDIAN_TIME_SPAN` блоков
+    /// из `ancestors` и опережает `now` не больше чем на `MAX_FUTURE_BLOCK_TIME`.
+    /// Медиана, в отличие от времени одного предыдущего блока, не даёт
+    /// отдельному майнеру сдвигать время цепи назад.
+    pub fn is_valid_timestamp(&self, ancestors: &[Block], now: u64) -> bool {
+        validation::check_timestamp(self, ancestors, now).is_ok()
+    }
+
+    /// Медиана времён последних `MEDIAN_TIME_SPAN` блоков (0 для пустого среза).
+    pub fn median_time_past(ancestors: &[Block]) -> u64 {
+        let start = ancestors.len().saturating_sub(MEDIAN_TIME_SPAN);
+        median_time(
+            ancestors[start..]
+                .iter()
+                .map(|b| b.header.timestamp)
+                .collect(),
+        )
+    }
+
+    /// Время для нового блока поверх `ancestors`: часы узла, но не раньше,
+    /// чем позволяет медиана, — иначе блоки, добытые в одну секунду,
+    /// были бы отвергнуты.
+    pub fn next_timestamp(ancestors: &[Block]) -> u64 {
+        Self::next_timestamp_at(ancestors, unix_now())
+    }
+
+    /// То же, что `next_timestamp`, но по часам, показывающим `now`.
+    pub fn next_timestamp_at(ancestors: &[Block], now: u64) -> u64 {
+        now.max(Self::median_time_past(ancestors) + 1)
+    }
+
+    /// Merkle-корень соответствует транзакциям, а хеш — заголовку.
+    pub fn has_valid_hash(&self) -> bool {
+        self.header.merkle_root == self.merkle_root() && self.hash == self.header.compute_hash()
+    }
+
+    /// Все проверки блока, кроме доказательства права на него: связность
+    /// с `prev`, транзакции, merkle-корень и хеш заголовка. Причину отказа
+    /// сообщает `validation::check_successor`.
+    pub fn is_valid_successor(&self, prev: &Block) -> bool {
+        validation::check_successor(self, prev).is_ok()
+    }
+
+    /// Подписывает хеш блока ключом производителя `signer`. Подпись не
+    /// входит в заголовок, поэтому ставится уже после майнинга.
+    pub fn sign(&mut self, signer: &dyn Signer) {
+        self.producer_key = signer.public_key();
+        self.producer_signature = signer.sign_payload(&self.hash.to_string());
+    }
+
+    /// Проверяет подпись производителя и возвращает его адрес.
+    pub fn verify_producer(&self) -> Result<Address, ConsensusError> {
+        if self.producer_key.is_empty() || self.producer_signature.is_empty() {
+            return Err(ConsensusError::MissingProducerSignature);
+        }
+
+        let key_bytes =
+            hex::decode(&self.producer_key).map_err(|_| ConsensusError::InvalidProducerKey)?;
+        let producer = Address::from_public_key(&key_bytes);
+        verify_ecdsa(
+            &self.producer_key,
+            &producer,
+            &self.hash.to_string(),
+            &self.producer_signature,
+        )
+        .map_err(ConsensusError::InvalidProducerSignature)?;
+        Ok(producer)
+    }
+
+    /// Хеш не превышает цель, закодированную в `bits`.
+    pub fn meets_target(hash: &Hash256, bits: u32) -> bool {
+        Target::from_compact(bits).is_some_and(|target| target.is_met_by(hash))
+    }
+
+    pub fn target(&self) -> Option<Target> {
+        Target::from_compact(self.header.bits)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Block serialization failed")
+    }
+
+    pub fn from_json(json: &str) -> Result<Block, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Блок укладывается в `MAX_BLOCK_TRANSACTIONS` и `MAX_BLOCK_SIZE`.
+    pub fn is_within_limits(&self) -> bool {
+        self.transactions.len() + self.utxo_transactions.len() <= MAX_BLOCK_TRANSACTIONS
+            && self.serialized_size() <= MAX_BLOCK_SIZE
+    }
+
+    /// Размер блока в байтах — длина `to_bytes`, но без её построения.
+    pub fn serialized_size(&self) -> usize {
+        bincode::serialized_size(self).expect("Block serialization failed") as usize
+    }
+
+    /// Компактное бинарное представление (bincode) для хранения на диске.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("Block serialization failed")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Block, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+
+    /// Доказательство включения транзакции с индексом `tx_index` в блок.
+    pub fn merkle_proof(&self, tx_index: usize) -> Option<MerkleProof> {
+        MerkleProof::build(
+            &Self::leaves(&self.transactions, &self.utxo_transactions),
+            tx_index,
+        )
+    }
+
+    pub fn verify_merkle_proof(&self, tx: &Transaction, proof: &MerkleProof) -> bool {
+        proof.verify(&tx.id(), &self.header.merkle_root)
+    }
+}
+
+// ========== MEMPOOL ==============
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MemPool {
+    pub transactions: Vec<Transaction>,
+    /// Предел числа транзакций. В полном пуле новая транзакция вытесняет
+    /// самую дешёвую, только если платит большую комиссию.
+    #[serde(default = "default_mempool_size")]
+    pub max_size: usize,
+    /// Время жизни транзакции в секундах от её `timestamp`.
+    #[serde(default = "default_mempool_ttl")]
+    pub ttl: u64,
+}
+
+fn default_mempool_size() -> usize {
+    DEFAULT_MEMPOOL_SIZE
+}
+
+fn default_mempool_ttl() -> u64 {
+    DEFAULT_MEMPOOL_TTL
+}
+
+impl MemPool {
+    pub fn new() -> MemPool {
+        Self::with_limits(DEFAULT_MEMPOOL_SIZE, DEFAULT_MEMPOOL_TTL)
+    }
+
+    pub fn with_limits(max_size: usize, ttl: u64) -> MemPool {
+        MemPool {
+            transactions: Vec::new(),
+            max_size,
+            ttl,
+        }
+    }
+
+    pub fn add_transaction(&mut self, tx: Transaction) -> Result<(), BlockchainError> {
+        tx.validate()?;
+
+        let txid = tx.id();
+        if self.contains(&txid) {
+            return Err(BlockchainError::DuplicateTransaction(txid));
+        }
+
+        if self.transactions.len() >= self.max_size {
+            match self.eviction_candidate() {
+                Some(position) if tx.fee > self.transactions[position].fee => {
+                    self.transactions.remove(position);
+                }
+                candidate => {
+                    return Err(BlockchainError::MempoolFull {
+                        min_fee: candidate.map_or(Amount::MAX, |i| self.transactions[i].fee),
+                    })
+                }
+            }
+        }
+
+        self.transactions.push(tx);
+        Ok(())
+    }
+
+    /// Первая на вытеснение: с наименьшей комиссией, а из равных — самая старая.
+    fn eviction_candidate(&self) -> Option<usize> {
+        self.transactions
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                a.fee
+                    .cmp(&b.fee)
+                    .then_with(|| a.timestamp.cmp(&b.timestamp))
+            })
+            .map(|(position, _)| position)
+    }
+
+    /// Удаляет транзакции, прождавшие дольше `ttl` к моменту `now`
+    /// (Unix-время в секундах), и возвращает их. Для транзакции,
+    /// запертой до времени, срок отсчитывается от этого времени.
+    pub fn purge_expired(&mut self, now: u64) -> Vec<Transaction> {
+        let ttl = self.ttl;
+        let (expired, alive) = self.transactions.drain(..).partition(|tx| {
+            let since = match tx.lock_time {
+                Some(LockTime::Timestamp(lock)) => tx.timestamp.max(lock),
+                _ => tx.timestamp,
+            };
+            now.saturating_sub(since) > ttl
+        });
+        self.transactions = alive;
+        expired
+    }
+
+    pub fn contains(&self, txid: &Hash256) -> bool {
+        self.transactions.iter().any(|tx| &tx.id() == txid)
+    }
+
+    pub fn has_nonce(&self, address: &Address, nonce: u64) -> bool {
+        self.get_by_nonce(address, nonce).is_some()
+    }
+
+    /// Ожидающая транзакция адреса с номером `nonce`.
+    pub fn get_by_nonce(&self, address: &Address, nonce: u64) -> Option<&Transaction> {
+        self.transactions
+            .iter()
+            .find(|tx| &tx.from == address && tx.nonce == nonce)
+    }
+
+    /// Убирает транзакцию из пула и возвращает её.
+    pub fn remove(&mut self, txid: &Hash256) -> Option<Transaction> {
+        let position = self.transactions.iter().position(|tx| &tx.id() == txid)?;
+        Some(self.transactions.remove(position))
+    }
+
+    /// Сколько адрес уже потратил в ожидающих транзакциях (сумма + комиссия).
+    pub fn pending_spend(&self, address: &Address) -> Amount {
+        self.transactions
+            .iter()
+            .filter(|tx| &tx.from == address)
+            .fold(Amount::ZERO, |spent, tx| {
+                spent.saturating_add(tx.amount.saturating_add(tx.fee))
+            })
+    }
+
+    /// Забирает до `count` транзакций с наибольшей комиссией.
+    /// При равной комиссии первыми идут более старые транзакции.
+    pub fn get_transactions(&mut self, count: usize) -> Vec<Transaction> {
+        self.sort_by_fee();
+        let count = count.min(self.transactions.len());
+        self.transactions.drain(..count).collect()
+    }
+
+    /// Забирает транзакции для блока с индексом `height` и временем `time`
+    /// в том же порядке, что и `get_transactions`: не больше `max_count`
+    /// штук общим размером не больше `max_bytes`. Не поместившиеся и ещё
+    /// запертые (см. `Transaction::lock_time`) транзакции остаются в пуле.
+    pub fn take_for_block(
+        &mut self,
+        max_count: usize,
+        max_bytes: usize,
+        height: u64,
+        time: u64,
+    ) -> Vec<Transaction> {
+        self.take_for_block_with(&FeePriority, max_count, max_bytes, height, time)
+    }
+
+    /// То же, что `take_for_block`, но транзакции и их порядок выбирает
+    /// `selection`. Выбор сверх пределов и повторные индексы отбрасываются.
+    pub fn take_for_block_with(
+        &mut self,
+        selection: &dyn TxSelection,
+        max_count: usize,
+        max_bytes: usize,
+        height: u64,
+        time: u64,
+    ) -> Vec<Transaction> {
+        let (ready, locked): (Vec<_>, Vec<_>) = self
+            .transactions
+            .drain(..)
+            .partition(|tx| tx.is_final(height, time));
+        self.transactions = locked;
+
+        let mut slots: Vec<_> = ready.into_iter().map(Some).collect();
+        let candidates: Vec<_> = slots.iter().flatten().cloned().collect();
+        let mut taken = Vec::new();
+        let mut bytes = 0;
+        for i in selection.select(&candidates, max_count, max_bytes) {
+            if taken.len() == max_count {
+                break;
+            }
+            let size = match slots.get(i) {
+                Some(Some(tx)) => tx.serialized_size(),
+                _ => continue,
+            };
+            if bytes + size <= max_bytes {
+                bytes += size;
+                taken.extend(slots[i].take());
+            }
+        }
+        self.transactions.extend(slots.into_iter().flatten());
+        taken
+    }
+
+    fn sort_by_fee(&mut self) {
+        self.transactions.sort_by(|a, b| {
+            b.fee
+                .cmp(&a.fee)
+                .then_with(|| a.timestamp.cmp(&b.timestamp))
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.transactions.clear();
+    }
+
+    pub fn size(&self) -> usize {
+        self.transactions.len()
+    }
+}
+
+impl Default for MemPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ========== BLOCKCHAIN ==============
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Blockchain {
+    pub chain: Vec<Block>,
+    /// Цель для следующего блока в компактной форме.
+    pub bits: u32,
+    pub mempool: MemPool,
+    pub target_block_time: u64,
+    pub adjustment_interval: u32,
+    /// Награда за блок до первого уполовинивания, см. `current_reward`.
+    pub mining_reward: Amount,
+    /// Через сколько блоков награда уменьшается вдвое; 0 — никогда.
+    #[serde(default = "default_halving_interval")]
+    pub halving_interval: u32,
+    /// Предел выпуска вместе с начальными распределениями, см. `supply`.
+    #[serde(default)]
+    pub max_supply: Option<Amount>,
+    /// Режим сети из genesis-конфигурации.
+    #[serde(default)]
+    mode: ChainMode,
+    /// Индексы блоков и транзакций. Не сериализуются; после прямой
+    /// правки `chain` их нужно перестроить через `reindex`.
+    #[serde(skip)]
+    block_index: HashMap<Hash256, usize>,
+    /// txid → (высота блока, позиция в блоке)
+    #[serde(skip)]
+    tx_index: HashMap<Hash256, (usize, usize)>,
+    /// адрес → позиции транзакций, где он отправитель или получатель
+    #[serde(skip)]
+    address_index: HashMap<Address, Vec<(usize, usize)>>,
+    /// Правило консенсуса; не сериализуется и после загрузки равно PoW.
+    #[serde(skip, default = "default_consensus")]
+    consensus: Arc<dyn Consensus>,
+    /// Блоки, чей родитель ещё не получен, см. `process_block`.
+    #[serde(skip)]
+    pub orphans: OrphanPool,
+    /// Балансы после первых `balances_height` блоков цепи. Ведутся
+    /// вместе с индексами; восстанавливаются из снимка состояния без
+    /// проигрывания всей цепи (см. `snapshot`).
+    #[serde(skip)]
+    pub(crate) balances: HashMap<Address, Amount>,
+    #[serde(skip)]
+    pub(crate) balances_height: usize,
+    /// Накопленная работа: `cumulative_work[h]` — сумма работы блоков
+    /// `0..=h`. Ведётся вместе с индексами; по ней выбирается лучшая цепь.
+    #[serde(skip)]
+    cumulative_work: Vec<u128>,
+    /// Токены после первых `tokens_height` блоков, см. `tokens`.
+    #[serde(skip)]
+    tokens: TokenLedger,
+    #[serde(skip)]
+    tokens_height: usize,
+    /// Непотраченные выходы после первых `utxos.height()` блоков и
+    /// журнал отката: `utxo_undo[h]` возвращает множество к виду до
+    /// блока `h`. При reorg отброшенные блоки откатываются по журналу.
+    #[serde(skip)]
+    utxos: UtxoSet,
+    #[serde(skip)]
+    pub(crate) utxo_undo: Vec<BlockUndo>,
+    /// Часы, по которым проверяется и добывается время блоков; не
+    /// сериализуются и после загрузки системные.
+    #[serde(skip, default = "system_clock")]
+    clock: Arc<dyn Clock>,
+    /// Как майнер выбирает транзакции из mempool; не сериализуется и
+    /// после загрузки равно `FeePriority`.
+    #[serde(skip, default = "default_tx_selection")]
+    tx_selection: Arc<dyn TxSelection>,
+    /// Блоки `1..pruned_height` обрезаны: от них остались заголовки
+    /// с пустым списком транзакций, тела лежат в `store` или `archive`, а их след
+    /// в счетах — в `pruned_state`. См. `pruning`.
+    #[serde(skip)]
+    pub(crate) pruned_height: usize,
+    #[serde(skip)]
+    pub(crate) pruned_state: ReplayedState,
+    #[serde(skip)]
+    pub(crate) archive: Option<Arc<BlockArchive>>,
+    /// Куда пишется каждый принятый блок, см. `blockstore`.
+    #[serde(skip)]
+    pub(crate) store: Option<Arc<dyn BlockStore>>,
+    /// Сколько последних блоков держится в памяти целиком, пока
+    /// подключено хранилище; см. `set_hot_blocks`.
+    #[serde(skip, default = "default_hot_blocks")]
+    pub(crate) hot_blocks: usize,
+}
+
+/// Чем закончилась обработка блока в `Blockchain::process_block`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockStatus {
+    /// Блок добавлен в цепь вместе со столькими ждавшими его сиротами.
+    Connected { orphans: usize },
+    /// Родитель неизвестен: блок ждёт в пуле сирот.
+    Orphan { missing_parent: Hash256 },
+}
+
+/// Судьба транзакции, см. `Blockchain::transaction_status`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TxStatus {
+    /// Ждёт в mempool.
+    Pending,
+    /// В блоке `block` на высоте `height`; `confirmations` — 1 для
+    /// блока на вершине и на единицу больше за каждый блок поверх.
+    Confirmed {
+        block: Hash256,
+        height: u32,
+        confirmations: u32,
+    },
+    /// Не найдена ни в цепи, ни в mempool: не дошла, вытеснена или
+    /// отброшена реорганизацией вместе с блоком.
+    Unknown,
+}
+
+/// Итог смены цепи в `Blockchain::replace_chain`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reorg {
+    /// Высота последнего блока, общего для старой и новой цепи.
+    pub fork_height: u32,
+    /// Отброшенные блоки старой цепи, от точки ветвления к старой вершине.
+    pub disconnected: Vec<Block>,
+    /// Сколько блоков новой цепи подключено выше точки ветвления.
+    pub connected: usize,
+    /// Транзакции отброшенных блоков, вернувшиеся в mempool.
+    pub reinjected: Vec<Hash256>,
+}
+
+/// Проводит переводы `block` по балансам так же, как
+/// `Blockchain::get_balance`. Coinbase ни с кого не списывает; в
+/// проверенной цепи списания не уходят ниже нуля, а для непроверенных
+/// блоков суммы насыщаются, а не переполняются.
+pub(crate) fn apply_transfers(balances: &mut HashMap<Address, Amount>, block: &Block) {
+    for tx in &block.transactions {
+        if !tx.is_coinbase() {
+            let from = balances.entry(tx.from.clone()).or_default();
+            *from = from.saturating_sub(tx.amount.saturating_add(tx.fee));
+        }
+        let to = balances.entry(tx.to.clone()).or_default();
+        *to = to.saturating_add(tx.amount);
+    }
+}
+
+/// Суммарная работа блоков, см. `BlockHeader::work`; насыщается на
+/// `u128::MAX`.
+pub fn chain_work(blocks: &[Block]) -> u128 {
+    blocks
+        .iter()
+        .fold(0, |total, block| total.saturating_add(block.header.work()))
+}
+
+fn default_consensus() -> Arc<dyn Consensus> {
+    Arc::new(ProofOfWork)
+}
+
+fn default_tx_selection() -> Arc<dyn TxSelection> {
+    Arc::new(FeePriority)
+}
+
+fn default_hot_blocks() -> usize {
+    DEFAULT_HOT_BLOCKS
+}
+
+fn default_halving_interval() -> u32 {
+    DEFAULT_HALVING_INTERVAL
+}
+
+impl Blockchain {
+    /// Цепь из одного genesis-блока, построенного по `genesis`. Узлы
+    /// с одинаковой конфигурацией начинают с одного и того же блока.
+    pub fn new(genesis: &GenesisConfig) -> Blockchain {
+        let mut blockchain = Blockchain {
+            chain: Vec::new(),
+            bits: genesis.bits,
+            mempool: MemPool::new(),
+            target_block_time: DEFAULT_TARGET_BLOCK_TIME,
+            adjustment_interval: DEFAULT_ADJUSTMENT_INTERVAL,
+            mining_reward: genesis.block_reward,
+            halving_interval: genesis.halving_interval,
+            max_supply: genesis.max_supply,
+            mode: genesis.mode,
+            block_index: HashMap::new(),
+            tx_index: HashMap::new(),
+            address_index: HashMap::new(),
+            consensus: default_consensus(),
+            orphans: OrphanPool::default(),
+            balances: HashMap::new(),
+            balances_height: 0,
+            cumulative_work: Vec::new(),
+            tokens: TokenLedger::default(),
+            tokens_height: 0,
+            utxos: UtxoSet::new(),
+            utxo_undo: Vec::new(),
+            clock: system_clock(),
+            tx_selection: default_tx_selection(),
+            pruned_height: 0,
+            pruned_state: ReplayedState::default(),
+            archive: None,
+            store: None,
+            hot_blocks: DEFAULT_HOT_BLOCKS,
+        };
+        if !genesis.producers.is_empty() {
+            blockchain.consensus = Arc::new(SignedBlocks::permissioned(
+                default_consensus(),
+                genesis.producers.iter().cloned(),
+            ));
+        }
+        blockchain.push_block(genesis.block());
+
+        blockchain
+    }
+
+    fn push_block(&mut self, block: Block) {
+        let balances_current = !self.balances_are_stale();
+        let utxos_current = !self.utxos_are_stale();
+        self.chain.push(block);
+        let height = self.chain.len() - 1;
+        self.index_block(height);
+        if balances_current {
+            self.apply_balances(height);
+        }
+        if utxos_current {
+            self.connect_utxos(height);
+        }
+    }
+
+    /// Проводит по балансам переводы блока на высоте `height`; перед этим
+    /// балансы должны соответствовать всем блокам ниже него.
+    pub(crate) fn apply_balances(&mut self, height: usize) {
+        apply_transfers(&mut self.balances, &self.chain[height]);
+        self.balances_height = height + 1;
+    }
+
+    fn index_block(&mut self, height: usize) {
+        let block = &self.chain[height];
+        self.block_index.insert(block.hash, height);
+        if self.cumulative_work.len() == height {
+            let below = self.cumulative_work.last().copied().unwrap_or(0);
+            self.cumulative_work
+                .push(below.saturating_add(block.header.work()));
+        }
+
+        for (position, tx) in block.transactions.iter().enumerate() {
+            self.tx_index.insert(tx.id(), (height, position));
+            self.address_index
+                .entry(tx.from.clone())
+                .or_default()
+                .push((height, position));
+            if tx.to != tx.from {
+                self.address_index
+                    .entry(tx.to.clone())
+                    .or_default()
+                    .push((height, position));
+            }
+        }
+    }
+
+    /// Перестраивает индексы, балансы, токены и множество выходов по
+    /// текущему содержимому `chain`.
+    pub fn reindex(&mut self) {
+        self.reindex_blocks();
+        self.reindex_accounts();
+        self.rebuild_utxos();
+    }
+
+    /// Перестраивает балансы и токены.
+    fn reindex_accounts(&mut self) {
+        self.balances = self.pruned_state.balances().clone();
+        self.balances_height = self.pruned_height;
+        for height in self.pruned_height..self.chain.len() {
+            self.apply_balances(height);
+        }
+        self.tokens = self.replay_tokens();
+        self.tokens_height = self.chain.len();
+    }
+
+    /// Блоки выше границы обрезки — те, что хранятся целиком.
+    fn unpruned(&self) -> &[Block] {
+        self.chain.get(self.pruned_height..).unwrap_or(&[])
+    }
+
+    /// Перестраивает только индексы блоков, транзакций и адресов.
+    pub(crate) fn reindex_blocks(&mut self) {
+        self.block_index.clear();
+        self.tx_index.clear();
+        self.address_index.clear();
+        self.cumulative_work.clear();
+        for height in 0..self.chain.len() {
+            self.index_block(height);
+        }
+    }
+
+    /// Перестраивает индексы блоков с высоты `height`, сохраняя записи
+    /// ниже неё — в том числе о транзакциях блоков, чьи тела уже
+    /// вытеснены в хранилище.
+    fn reindex_blocks_from(&mut self, height: usize) {
+        self.block_index.retain(|_, &mut indexed| indexed < height);
+        self.tx_index
+            .retain(|_, &mut (indexed, _)| indexed < height);
+        for entries in self.address_index.values_mut() {
+            entries.retain(|&(indexed, _)| indexed < height);
+        }
+        self.address_index.retain(|_, entries| !entries.is_empty());
+        self.cumulative_work.truncate(height);
+        for height in height..self.chain.len() {
+            self.index_block(height);
+        }
+    }
+
+    /// Индексы не соответствуют `chain` (например, после десериализации).
+    fn index_is_stale(&self) -> bool {
+        self.block_index.len() != self.chain.len()
+    }
+
+    /// Суммарная работа блоков `0..=height`.
+    pub fn work_at(&self, height: usize) -> u128 {
+        if self.cumulative_work.len() == self.chain.len() {
+            self.cumulative_work[height]
+        } else {
+            chain_work(&self.chain[..=height])
+        }
+    }
+
+    /// Суммарная работа всей цепи — мера, по которой выбирается лучшая
+    /// из веток: длина сама по себе ничего не доказывает, если сложность
+    /// блоков разная.
+    pub fn total_work(&self) -> u128 {
+        self.work_at(self.chain.len() - 1)
+    }
+
+    /// Токены на вершине цепи.
+    pub(crate) fn current_tokens(&self) -> TokenLedger {
+        if self.tokens_height == self.chain.len() {
+            self.tokens.clone()
+        } else {
+            self.replay_tokens()
+        }
+    }
+
+    /// Токены, проигранные от состояния обрезки до вершины.
+    fn replay_tokens(&self) -> TokenLedger {
+        let mut tokens = self.pruned_state.tokens();
+        for tx in self.unpruned().iter().flat_map(|block| &block.transactions) {
+            let _ = tokens.apply(tx);
+        }
+        tokens
+    }
+
+    /// Баланс адреса в токене `token` (id — txid его CREATE_TOKEN).
+    pub fn token_balance(&self, address: &Address, token: &Hash256) -> u64 {
+        if self.tokens_height == self.chain.len() {
+            self.tokens.balance(address, token)
+        } else {
+            self.current_tokens().balance(address, token)
+        }
+    }
+
+    pub fn token_info(&self, token: &Hash256) -> Option<TokenInfo> {
+        self.current_tokens().token(token).cloned()
+    }
+
+    /// Токены с учётом операций, уже ждущих в mempool, кроме `except`
+    /// (транзакции, которую заменяют).
+    fn pending_tokens(&self, except: Option<&Hash256>) -> TokenLedger {
+        let mut tokens = self.current_tokens();
+        for tx in self
+            .mempool
+            .transactions
+            .iter()
+            .filter(|tx| tx.token.is_some() && except.is_none_or(|txid| &tx.id() != txid))
+        {
+            let _ = tokens.apply(tx);
+        }
+        tokens
+    }
+
+    fn balances_are_stale(&self) -> bool {
+        self.balances_height != self.chain.len()
+    }
+
+    /// Балансы всех адресов на вершине цепи.
+    pub(crate) fn current_balances(&self) -> HashMap<Address, Amount> {
+        if !self.balances_are_stale() {
+            return self.balances.clone();
+        }
+        let mut balances = self.pruned_state.balances().clone();
+        for block in self.unpruned() {
+            apply_transfers(&mut balances, block);
+        }
+        balances
+    }
 
-// ========== TRANSACTION ==============
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct Transaction {
-    pub from: String,
-    pub to: String,
-    pub amount: f64,
-    pub timestamp: u64,
-    pub signature: String,
-    pub public_key: String,
-}
+    fn utxos_are_stale(&self) -> bool {
+        self.utxos.height() as usize != self.chain.len()
+    }
 
-impl Transaction {
-    pub fn new(
-        from: String,
-        to: String,
-        amount: f64,
-        signature: String,
-        public_key: String,
-    ) -> Transaction {
-        let now = SystemTime::now();
-        let since_epoch = now.duration_since(UNIX_EPOCH).expect("Time went backwards");
-        let timestamp = since_epoch.as_secs();
+    /// Подключает к множеству выходов блок на высоте `height`; перед этим
+    /// множество должно соответствовать всем блокам ниже него.
+    fn connect_utxos(&mut self, height: usize) {
+        let undo = self
+            .utxos
+            .connect_block(&self.chain[height].utxo_transactions);
+        self.utxo_undo.push(undo);
+    }
 
-        Transaction {
-            from,
-            to,
-            amount,
-            timestamp,
-            signature,
-            public_key,
+    /// Откатывает множество выходов по журналу к виду после первых
+    /// `height` блоков.
+    fn disconnect_utxos(&mut self, height: usize) {
+        while self.utxo_undo.len() > height {
+            let undo = self.utxo_undo.pop().expect("journal is longer than height");
+            let block = &self.chain[self.utxo_undo.len()];
+            self.utxos.undo_block(&block.utxo_transactions, undo);
         }
     }
 
-    pub fn is_valid(&self) -> bool {
-        if self.amount <= 0.0 {
-            return false;
+    /// Перестраивает множество выходов от состояния обрезки.
+    pub(crate) fn rebuild_utxos(&mut self) {
+        self.utxos = self.pruned_state.utxos().into_owned();
+        // Глубже границы обрезки reorg невозможен, откатывать там нечего
+        self.utxo_undo = vec![BlockUndo::default(); self.pruned_height];
+        for height in self.pruned_height..self.chain.len() {
+            self.connect_utxos(height);
         }
+    }
 
-        if self.from.is_empty() || self.to.is_empty() {
-            return false;
+    /// Непотраченные выходы на вершине цепи.
+    pub fn utxos(&self) -> Cow<'_, UtxoSet> {
+        if !self.utxos_are_stale() {
+            return Cow::Borrowed(&self.utxos);
         }
+        let mut utxos = self.pruned_state.utxos().into_owned();
+        for block in self.unpruned() {
+            utxos.connect_block(&block.utxo_transactions);
+        }
+        Cow::Owned(utxos)
+    }
 
-        if self.from == self.to {
-            return false;
+    // ========== LOOKUP ==============
+
+    /// Блок высоты `height` целиком. Тело блока ниже границы обрезки
+    /// читается из хранилища или архива (см. `full_block`); если это не
+    /// удалось, блока нет.
+    fn block_at(&self, height: usize) -> Option<Cow<'_, Block>> {
+        let block = self.chain.get(height)?;
+        if height == 0 || height >= self.pruned_height {
+            return Some(Cow::Borrowed(block));
+        }
+        match self.full_block(height) {
+            Ok(block) => block.map(Cow::Owned),
+            Err(e) => {
+                warn!(height, error = %e, "cannot read pruned block");
+                None
+            }
         }
+    }
 
-        if self.signature.is_empty() || self.public_key.is_empty() {
-            return false;
+    /// Высота блока с хешем `hash` в цепи; тело блока не читается.
+    pub fn block_height(&self, hash: &Hash256) -> Option<usize> {
+        match self.block_index.get(hash) {
+            Some(&height) => self
+                .chain
+                .get(height)
+                .filter(|block| block.hash == *hash)
+                .map(|_| height),
+            None if self.index_is_stale() => {
+                self.chain.iter().position(|block| block.hash == *hash)
+            }
+            None => None,
         }
+    }
 
-        true
+    pub fn get_block_by_index(&self, index: u32) -> Option<Cow<'_, Block>> {
+        self.block_at(index as usize)
     }
-}
 
-// ========== BLOCK ==============
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct Block {
-    pub index: u32,
-    pub timestamp: u64,
-    pub transactions: Vec<Transaction>,
-    pub prev_hash: String,
-    pub hash: String,
-    pub nonce: u32,
-}
+    pub fn get_block_by_hash(&self, hash: &Hash256) -> Option<Cow<'_, Block>> {
+        self.block_at(self.block_height(hash)?)
+    }
 
-impl Block {
-    pub fn new(index: u32, transactions: Vec<Transaction>, prev_hash: String) -> Block {
-        let now = SystemTime::now();
-        let since_epoch = now.duration_since(UNIX_EPOCH).expect("Time went backwards");
-        let timestamp = since_epoch.as_secs();
+    pub fn contains_block(&self, hash: &Hash256) -> bool {
+        self.block_height(hash).is_some()
+    }
+
+    /// Транзакция на позиции `position` блока высоты `height` и высота.
+    fn transaction_at(
+        &self,
+        height: usize,
+        position: usize,
+    ) -> Option<(Cow<'_, Transaction>, u32)> {
+        let tx = match self.block_at(height)? {
+            Cow::Borrowed(block) => Cow::Borrowed(block.transactions.get(position)?),
+            Cow::Owned(mut block) => {
+                if position >= block.transactions.len() {
+                    return None;
+                }
+                Cow::Owned(block.transactions.swap_remove(position))
+            }
+        };
+        Some((tx, height as u32))
+    }
 
-        let difficulty = 2;
-        let mut nonce = 0;
-        let mut hash = Self::compute_hash(index, timestamp, &transactions, &prev_hash, nonce);
+    pub fn latest_block(&self) -> &Block {
+        &self.chain[self.chain.len() - 1]
+    }
+
+    /// Идентификатор сети из genesis-блока.
+    pub fn chain_id(&self) -> &str {
+        self.chain
+            .first()
+            .and_then(Block::genesis_chain_id)
+            .unwrap_or_default()
+    }
+
+    pub fn height(&self) -> usize {
+        self.chain.len()
+    }
 
-        while !hash.starts_with(&"0".repeat(difficulty as usize)) {
-            nonce += 1;
-            hash = Self::compute_hash(index, timestamp, &transactions, &prev_hash, nonce);
+    /// Подтверждённая транзакция и высота блока, в который она вошла.
+    pub fn get_transaction(&self, txid: &Hash256) -> Option<(Cow<'_, Transaction>, u32)> {
+        if self.index_is_stale() {
+            return (0..self.chain.len()).find_map(|height| {
+                let position = self
+                    .block_at(height)?
+                    .transactions
+                    .iter()
+                    .position(|tx| &tx.id() == txid)?;
+                self.transaction_at(height, position)
+            });
         }
 
-        Block {
-            index,
-            timestamp,
-            transactions,
-            prev_hash,
-            hash,
-            nonce,
+        let &(height, position) = self.tx_index.get(txid)?;
+        self.transaction_at(height, position)
+    }
+
+    /// Подтверждена ли транзакция и сколько раз, ждёт ли она в mempool.
+    pub fn transaction_status(&self, txid: &Hash256) -> TxStatus {
+        if let Some((_, height)) = self.get_transaction(txid) {
+            return TxStatus::Confirmed {
+                block: self.chain[height as usize].hash,
+                height,
+                confirmations: self.latest_block().header.index - height + 1,
+            };
+        }
+        if self.mempool.contains(txid) {
+            TxStatus::Pending
+        } else {
+            TxStatus::Unknown
         }
     }
 
-    pub fn genesis() -> Self {
-        let genesis_tx = Transaction::new(
-            "GENESIS".to_string(),
-            "GENESIS".to_string(),
-            0.0,
-            "genesis_signature".to_string(),
-            "genesis_key".to_string(),
-        );
-        Self::new(0, vec![genesis_tx], "0".repeat(64))
+    /// Merkle-доказательство включения подтверждённой транзакции вместе
+    /// с высотой её блока — то, что лёгкий клиент просит у полного узла.
+    pub fn transaction_proof(&self, txid: &Hash256) -> Option<(u32, MerkleProof)> {
+        let (_, height) = self.get_transaction(txid)?;
+        let block = self.block_at(height as usize)?;
+        let position = block.transactions.iter().position(|tx| &tx.id() == txid)?;
+        Some((height, block.merkle_proof(position)?))
     }
 
-    pub fn compute_hash(
-        index: u32,
-        timestamp: u64,
-        transactions: &[Transaction],
-        prev_hash: &str,
-        nonce: u32,
-    ) -> String {
-        let tx_data = transactions
+    /// Вся подтверждённая история адреса (входящие и исходящие) в порядке
+    /// цепи, вместе с высотами блоков.
+    pub fn get_transactions_for_address(
+        &self,
+        address: &Address,
+    ) -> Vec<(Cow<'_, Transaction>, u32)> {
+        if self.index_is_stale() {
+            return (0..self.chain.len())
+                .flat_map(|height| {
+                    let count = self
+                        .block_at(height)
+                        .map_or(0, |block| block.transactions.len());
+                    (0..count).filter_map(move |position| self.transaction_at(height, position))
+                })
+                .filter(|(tx, _)| &tx.from == address || &tx.to == address)
+                .collect();
+        }
+
+        self.address_index
+            .get(address)
+            .into_iter()
+            .flatten()
+            .filter_map(|&(height, position)| self.transaction_at(height, position))
+            .collect()
+    }
+
+    /// Сколько подтверждённых транзакций у адреса.
+    pub fn address_transaction_count(&self, address: &Address) -> usize {
+        if self.index_is_stale() {
+            return self.get_transactions_for_address(address).len();
+        }
+        self.address_index.get(address).map_or(0, Vec::len)
+    }
+
+    /// `limit` транзакций из истории адреса, начиная с `offset`-й, в
+    /// порядке цепи. По индексу берётся только нужный отрезок истории.
+    pub fn address_transactions_page(
+        &self,
+        address: &Address,
+        offset: usize,
+        limit: usize,
+    ) -> Vec<(Cow<'_, Transaction>, u32)> {
+        if self.index_is_stale() {
+            return self
+                .get_transactions_for_address(address)
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .collect();
+        }
+
+        self.address_index
+            .get(address)
+            .map_or(&[][..], Vec::as_slice)
             .iter()
-            .map(|tx| format!("{}->{}:{}", tx.from, tx.to, tx.amount))
-            .collect::<Vec<String>>()
-            .join("|");
+            .skip(offset)
+            .take(limit)
+            .filter_map(|&(height, position)| self.transaction_at(height, position))
+            .collect()
+    }
 
-        let input = format!(
-            "{}|{}|{}|{}|{}",
-            index, timestamp, tx_data, prev_hash, nonce
-        );
+    /// Блоки от genesis к вершине; обрезанные читаются так же, как в
+    /// `get_block_by_index`.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Cow<'_, Block>> + '_ {
+        (0..self.chain.len()).filter_map(|height| self.block_at(height))
+    }
 
-        let mut hasher = Sha256::new();
-        hasher.update(input.as_bytes());
-        let bytes = hasher.finalize();
-        hex::encode(bytes)
+    /// Блоки от вершины к genesis.
+    pub fn iter_rev(&self) -> impl Iterator<Item = Cow<'_, Block>> + '_ {
+        self.iter().rev()
     }
 
-    pub fn is_valid(&self, prev: &Block) -> bool {
-        if self.index != prev.index + 1 {
-            return false;
+    /// Принимает транзакцию в mempool, если она ещё не подтверждена
+    /// и отправитель может её оплатить с учётом ожидающих трат.
+    ///
+    /// Транзакция с тем же nonce, что у ожидающей транзакции отправителя,
+    /// заменяет её (replace-by-fee), если платит большую комиссию: так
+    /// можно протолкнуть застрявший дешёвый перевод. Старая версия
+    /// вытесняется из mempool, а её средства не считаются потраченными.
+    pub fn add_transaction(&mut self, tx: Transaction) -> Result<(), BlockchainError> {
+        self.check_chain_id(&tx)?;
+        let txid = tx.id();
+        if self.contains_transaction(&txid) || self.mempool.contains(&txid) {
+            return Err(BlockchainError::DuplicateTransaction(txid));
         }
 
-        if self.prev_hash != prev.hash {
-            return false;
+        if self.nonce_used(&tx.from, tx.nonce) {
+            return Err(BlockchainError::NonceReused {
+                address: tx.from.clone(),
+                nonce: tx.nonce,
+            });
         }
 
-        for tx in &self.transactions {
-            if !tx.is_valid() {
-                return false;
+        let replaced = self.mempool.get_by_nonce(&tx.from, tx.nonce).cloned();
+        if let Some(old) = &replaced {
+            if tx.fee <= old.fee {
+                return Err(BlockchainError::ReplacementUnderpriced {
+                    replaced: old.id(),
+                    min_fee: old.fee,
+                });
             }
         }
+        let released = replaced
+            .as_ref()
+            .map_or(Amount::ZERO, |old| old.amount.saturating_add(old.fee));
 
-        let expected = Self::compute_hash(
-            self.index,
-            self.timestamp,
-            &self.transactions,
-            &self.prev_hash,
-            self.nonce,
-        );
-        if self.hash != expected {
-            return false;
+        let available = self
+            .get_balance(&tx.from)
+            .saturating_add(released)
+            .saturating_sub(self.mempool.pending_spend(&tx.from));
+        let required = tx.amount.saturating_add(tx.fee);
+        if available < required {
+            return Err(BlockchainError::InsufficientFunds {
+                available,
+                required,
+            });
         }
 
-        let difficulty = 2;
-        if !self.hash.starts_with(&"0".repeat(difficulty as usize)) {
-            return false;
+        if tx.token.is_some() {
+            let replaced_id = replaced.as_ref().map(Transaction::id);
+            self.pending_tokens(replaced_id.as_ref()).apply(&tx)?;
         }
 
-        true
+        let Some(old) = replaced else {
+            return self.mempool.add_transaction(tx);
+        };
+        let old = self
+            .mempool
+            .remove(&old.id())
+            .expect("replaced transaction is in the mempool");
+        match self.mempool.add_transaction(tx) {
+            Ok(()) => {
+                debug!(replaced = %old.id(), by = %txid, "replaced pending transaction");
+                Ok(())
+            }
+            Err(e) => {
+                self.mempool.transactions.push(old);
+                Err(e)
+            }
+        }
     }
-}
-
-// ========== MEMPOOL ==============
-#[derive(Clone, Debug)]
-pub struct MemPool {
-    pub transactions: Vec<Transaction>,
-}
 
-impl MemPool {
-    pub fn new() -> MemPool {
-        MemPool {
-            transactions: Vec::new(),
+    /// Транзакция подписана для этой сети.
+    fn check_chain_id(&self, tx: &Transaction) -> Result<(), BlockchainError> {
+        if tx.chain_id != self.chain_id() {
+            return Err(BlockchainError::WrongChain {
+                expected: self.chain_id().to_string(),
+                actual: tx.chain_id.clone(),
+            });
         }
+        Ok(())
     }
 
-    pub fn add_transaction(&mut self, tx: Transaction) -> bool {
-        if tx.is_valid() {
-            self.transactions.push(tx);
-            true
-        } else {
-            false
+    pub fn contains_transaction(&self, txid: &Hash256) -> bool {
+        if self.index_is_stale() {
+            return self.get_transaction(txid).is_some();
         }
+        self.tx_index.contains_key(txid)
     }
 
-    pub fn get_transactions(&mut self, count: usize) -> Vec<Transaction> {
-        let mut result = Vec::new();
-        for _ in 0..count {
-            if let Some(tx) = self.transactions.pop() {
-                result.push(tx);
-            }
+    /// Подтверждённые транзакции, отправленные адресом выше границы
+    /// обрезки. Берутся по индексу адресов, без прохода по всей цепи.
+    fn sent_transactions<'a>(
+        &'a self,
+        address: &'a Address,
+    ) -> Box<dyn Iterator<Item = &'a Transaction> + 'a> {
+        let sent = move |tx: &&Transaction| !tx.is_coinbase() && &tx.from == address;
+        if self.index_is_stale() {
+            return Box::new(
+                self.unpruned()
+                    .iter()
+                    .flat_map(|block| &block.transactions)
+                    .filter(sent),
+            );
         }
-        result
+
+        Box::new(
+            self.address_index
+                .get(address)
+                .into_iter()
+                .flatten()
+                .filter(|&&(height, _)| height >= self.pruned_height)
+                .filter_map(|&(height, position)| {
+                    self.chain.get(height)?.transactions.get(position)
+                })
+                .filter(sent),
+        )
     }
 
-    pub fn clear(&mut self) {
-        self.transactions.clear();
+    /// Использован ли `nonce` отправителем в подтверждённых транзакциях.
+    pub fn nonce_used(&self, address: &Address, nonce: u64) -> bool {
+        self.pruned_state.nonce_used(address, nonce)
+            || self.sent_transactions(address).any(|tx| tx.nonce == nonce)
     }
 
-    pub fn size(&self) -> usize {
-        self.transactions.len()
+    /// Следующий свободный номер транзакции для адреса с учётом mempool.
+    pub fn next_nonce(&self, address: &Address) -> u64 {
+        let pruned = self
+            .pruned_state
+            .last_nonce(address)
+            .map(|nonce| nonce.saturating_add(1));
+        self.sent_transactions(address)
+            .chain(
+                self.mempool
+                    .transactions
+                    .iter()
+                    .filter(|tx| !tx.is_coinbase() && &tx.from == address),
+            )
+            .map(|tx| tx.nonce.saturating_add(1))
+            .max()
+            .max(pruned)
+            .unwrap_or(0)
     }
-}
 
-// ========== BLOCKCHAIN ==============
-#[derive(Clone)]
-pub struct Blockchain {
-    pub chain: Vec<Block>,
-    pub difficulty: u32,
-    pub mempool: MemPool,
-    pub target_block_time: u64,
-    pub adjustment_interval: u32,
-}
+    /// Производит блок из транзакций mempool по правилу консенсуса и
+    /// возвращает его копию. `NotSelected` — если в PoS очередь не наша.
+    pub fn mine_block(&mut self, miner_address: &Address) -> Result<Block, BlockchainError> {
+        self.mine_block_with(miner_address, &MiningOptions::default())
+            .map(|(block, _)| block)
+    }
 
-impl Blockchain {
-    pub fn new() -> Blockchain {
-        let mut blockchain = Blockchain {
-            chain: Vec::new(),
-            difficulty: 2,
-            mempool: MemPool::new(),
-            target_block_time: 10,
-            adjustment_interval: 10,
+    /// То же, что `mine_block`, с параметрами перебора `options`;
+    /// возвращает и статистику перебора, например для расчёта хешрейта.
+    pub fn mine_block_with(
+        &mut self,
+        miner_address: &Address,
+        options: &MiningOptions,
+    ) -> Result<(Block, MiningStats), BlockchainError> {
+        let _span =
+            info_span!("mining", height = self.chain.len(), miner = %miner_address).entered();
+        let transactions = self.take_block_transactions(miner_address);
+        let produced = self.consensus.produce_block(
+            &self.chain,
+            transactions.clone(),
+            self.bits,
+            Block::next_timestamp_at(&self.chain, self.now()),
+            options,
+            &AtomicBool::new(false),
+        );
+
+        let Some((new_block, stats)) = produced else {
+            // Блок сейчас не наш: возвращаем транзакции в mempool
+            self.return_to_mempool(transactions);
+            return Err(BlockchainError::Consensus(ConsensusError::NotSelected));
         };
-        let genesis = Block::genesis();
-        blockchain.chain.push(genesis);
 
-        blockchain
+        if let Err(e) = self.add_block(new_block.clone()) {
+            // Например, блок не записался в хранилище
+            self.return_to_mempool(transactions);
+            return Err(e);
+        }
+        debug!(
+            index = new_block.header.index,
+            nonce = new_block.header.nonce,
+            hashes = stats.hashes,
+            "mined block"
+        );
+        Ok((new_block, stats))
+    }
+
+    /// Возвращает в mempool транзакции несостоявшегося блока.
+    fn return_to_mempool(&mut self, transactions: Vec<Transaction>) {
+        for tx in transactions.into_iter().filter(|tx| !tx.is_coinbase()) {
+            let _ = self.mempool.add_transaction(tx);
+        }
+    }
+
+    /// Забирает транзакции для нового блока из mempool по стратегии
+    /// `tx_selection` и ставит перед ними coinbase с наградой и
+    /// комиссиями. При пустом mempool блок
+    /// состоит из одной coinbase.
+    pub fn take_block_transactions(&mut self, miner_address: &Address) -> Vec<Transaction> {
+        let height = self.chain.len() as u32;
+        let mut coinbase = Transaction {
+            chain_id: self.chain_id().to_string(),
+            ..Transaction::coinbase(miner_address.clone(), self.current_reward(height), height)
+        };
+        // Размер coinbase не зависит от суммы, так что её можно учесть заранее
+        let budget =
+            MAX_BLOCK_SIZE.saturating_sub(BLOCK_HEADER_RESERVE + coinbase.serialized_size());
+        // Блок получит время не раньше этого, так что созревшая к нему
+        // транзакция созреет и к блоку
+        let time = Block::next_timestamp_at(&self.chain, self.now());
+        let taken = self.mempool.take_for_block_with(
+            self.tx_selection.as_ref(),
+            MAX_BLOCK_TRANSACTIONS - 1,
+            budget,
+            height as u64,
+            time,
+        );
+        // С невыполнимой операцией с токеном блок был бы отвергнут. Она
+        // ждёт в mempool: перевод мог опередить создание токена по комиссии.
+        // Так же ждёт транзакция, чья комиссия переполнила бы coinbase
+        let mut tokens = self.current_tokens();
+        let mut transactions = vec![];
+        for tx in taken {
+            let Some(amount) = coinbase.amount.checked_add(tx.fee) else {
+                debug!(txid = %tx.id(), "deferring transaction: coinbase amount overflow");
+                self.mempool.transactions.push(tx);
+                continue;
+            };
+            if tokens.apply(&tx).is_err() {
+                debug!(txid = %tx.id(), "deferring token transaction");
+                self.mempool.transactions.push(tx);
+                continue;
+            }
+            coinbase.amount = amount;
+            transactions.push(tx);
+        }
+
+        transactions.insert(0, coinbase);
+        transactions
     }
 
-    pub fn add_transaction(&mut self, tx: Transaction) -> bool {
-        self.mempool.add_transaction(tx)
+    /// Принимает блок из сети, где блоки могут приходить не по порядку.
+    /// Блок выше вершины с неизвестным родителем откладывается в пул
+    /// сирот; иначе он добавляется через `add_block`, после чего к нему
+    /// подключаются ожидавшие его сироты.
+    pub fn process_block(&mut self, block: Block) -> Result<BlockStatus, BlockchainError> {
+        let parent_known = self.contains_block(&block.header.prev_hash);
+        if !parent_known && block.header.index > self.latest_block().header.index {
+            // Без родителя проверить можно немногое, но пустышки без
+            // работы или сверх лимитов в пул не попадут
+            let index = block.header.index;
+            if !block.is_within_limits() {
+                return Err(validation::rejection(index, ValidationError::OverLimits));
+            }
+            if !Block::meets_target(&block.hash, block.header.bits) {
+                return Err(validation::rejection(index, ValidationError::BadPoW));
+            }
+            let missing_parent = block.header.prev_hash;
+            self.orphans.insert(block);
+            return Ok(BlockStatus::Orphan { missing_parent });
+        }
+
+        self.add_block(block)?;
+        Ok(BlockStatus::Connected {
+            orphans: self.connect_orphans(),
+        })
     }
 
-    pub fn mine_block(&mut self) -> bool {
-        let new_index = self.chain.len() as u32;
-        let prev_block = &self.chain[self.chain.len() - 1];
-        let prev_hash = prev_block.hash.clone();
+    /// Подключает сирот, ожидавших текущую вершину, пока это удаётся.
+    /// Возвращает число подключённых блоков; непригодные сироты отбрасываются.
+    pub fn connect_orphans(&mut self) -> usize {
+        let mut connected = 0;
+        loop {
+            let tip = self.latest_block().hash;
+            let children = self.orphans.take_children(&tip);
+            if children.is_empty() {
+                return connected;
+            }
+            let before = connected;
+            for child in children {
+                // Из нескольких детей одной вершины подключается первый годный
+                if self.add_block(child).is_ok() {
+                    connected += 1;
+                    break;
+                }
+            }
+            if connected == before {
+                return connected;
+            }
+        }
+    }
 
-        let transactions = self.mempool.get_transactions(10);
+    /// Добавляет блок на вершину цепи, если он проходит
+    /// `validation::validate_block` (в том числе принят правилом консенсуса
+    /// и добыт с ожидаемой сложностью), после чего пересчитывает сложность.
+    pub fn add_block(&mut self, block: Block) -> Result<(), BlockchainError> {
+        self.validate_block(&block)
+            .map_err(|reason| validation::rejection(block.header.index, reason))?;
 
-        if transactions.is_empty() {
-            println!("⚠️  No transactions to mine");
-            return false;
+        let mut tokens = self.current_tokens();
+        for tx in &block.transactions {
+            tokens.apply(tx)?;
         }
 
-        println!(
-            "⛏️  Mining block {} with {} transactions...",
-            new_index,
-            transactions.len()
-        );
-        let new_block = Block::new(new_index, transactions, prev_hash);
-        println!("✅ Block mined! Hash: {}, nonce = {}", &new_block.hash[0..16], new_block.nonce);
+        self.write_store(self.chain.len(), std::slice::from_ref(&block))
+            .map_err(|e| BlockchainError::Storage(e.into()))?;
+        self.push_block(block);
+        self.tokens = tokens;
+        self.tokens_height = self.chain.len();
+        self.adjust_difficulty();
+        self.evict_cold_blocks();
+        Ok(())
+    }
 
-        if new_block.is_valid(prev_block) {
-            self.chain.push(new_block);
-            self.adjust_difficulty();
-            true
-        } else {
-            println!("❌ Block validation failed!");
-            false
+    /// Проверяет `block` как следующий блок поверх вершины, не добавляя
+    /// его, и сообщает причину отказа.
+    pub fn validate_block(&self, block: &Block) -> Result<(), ValidationError> {
+        validation::validate_block(block, &self.validation_context())
+    }
+
+    /// Контекст для проверки блока поверх вершины цепи.
+    pub fn validation_context(&self) -> ValidationContext<'_> {
+        ValidationContext {
+            ancestors: &self.chain,
+            expected_bits: self.bits,
+            now: self.now(),
+            chain_id: self.chain_id(),
+            mode: self.mode,
+            subsidy: self.current_reward(self.chain.len() as u32),
+            consensus: self.consensus.as_ref(),
+            state: self,
         }
     }
 
+    /// Номер первого негодного блока и причину сообщает `verify`.
     pub fn is_chain_valid(&self) -> bool {
-        for i in 1..self.chain.len() {
-            let current_block = &self.chain[i];
-            let prev_block = &self.chain[i - 1];
+        self.verify().is_ok()
+    }
+
+    /// `validate_chain` для собственной цепи. Обрезанные блоки не
+    /// проверяются: вместо них учитывается состояние после них.
+    pub fn verify(&self) -> Result<(), ChainError> {
+        self.validate_chain_on(&self.chain, self.pruned_height, self.pruned_state.clone())
+    }
+
+    /// Проверяет связность, время (см. `Block::is_valid_timestamp`),
+    /// доказательство консенсуса, сложность каждого блока `chain`
+    /// относительно предыдущего (genesis не проверяется), сеть каждой
+    /// транзакции, то, что ни один nonce отправителя не использован дважды,
+    /// и то, что отправителям хватает средств.
+    pub fn validate_chain(&self, chain: &[Block]) -> Result<(), ChainError> {
+        self.validate_chain_from(chain, 1)
+    }
+
+    /// `validate_chain` для блоков `chain[start..]`; более ранние блоки
+    /// считаются проверенными и только учитываются как история.
+    pub(crate) fn validate_chain_from(
+        &self,
+        chain: &[Block],
+        start: usize,
+    ) -> Result<(), ChainError> {
+        let state = ReplayedState::replay(&chain[..start.min(chain.len())]);
+        self.validate_chain_on(chain, start, state)
+    }
+
+    /// `validate_chain_from`, где `state` — уже известное состояние после
+    /// блоков ниже `start`.
+    fn validate_chain_on(
+        &self,
+        chain: &[Block],
+        start: usize,
+        mut state: ReplayedState,
+    ) -> Result<(), ChainError> {
+        let now = self.now();
+        let chain_id = chain
+            .first()
+            .and_then(Block::genesis_chain_id)
+            .unwrap_or_default();
+        // Genesis не проверяется, но учитывается в состоянии
+        if let (0, Some(genesis)) = (start, chain.first()) {
+            state.apply_block(genesis);
+        }
+
+        for i in start.max(1)..chain.len() {
+            let ctx = ValidationContext {
+                ancestors: &chain[..i],
+                expected_bits: self.next_bits(&chain[..i]),
+                now,
+                chain_id,
+                mode: self.mode,
+                subsidy: self.current_reward(i as u32),
+                consensus: self.consensus.as_ref(),
+                state: &state,
+            };
+            validation::validate_block(&chain[i], &ctx).map_err(|reason| {
+                ChainError::InvalidBlock {
+                    index: chain[i].header.index,
+                    reason,
+                }
+            })?;
+            state.apply_block(&chain[i]);
+        }
+        Ok(())
+    }
+
+    /// Заменяет цепь на цепь пира, если в ней больше работы, чем в текущей
+    /// (см. `total_work`), она начинается с того же genesis-блока и
+    /// полностью валидна. Более короткая, но тяжёлая цепь тоже побеждает.
+    /// Транзакции блоков,
+    /// отброшенных выше точки ветвления, возвращаются в mempool (см.
+    /// `reinject`); балансы считаются по цепи и следуют за ней сами.
+    /// Обрезанная цепь принимает только ветки, отходящие не ниже границы
+    /// обрезки.
+    pub fn replace_chain(&mut self, mut candidate: Vec<Block>) -> Result<Reorg, ChainError> {
+        let genesis = candidate.first().ok_or(ChainError::EmptyChain)?;
+        if genesis != &self.chain[0] {
+            return Err(ChainError::GenesisMismatch);
+        }
+
+        // Genesis совпадает, поэтому общий префикс не пуст
+        let common = self
+            .chain
+            .iter()
+            .zip(&candidate)
+            .take_while(|(ours, theirs)| ours.hash == theirs.hash)
+            .count();
+        if common < self.pruned_height {
+            return Err(ChainError::BelowPruned {
+                fork_height: (common - 1) as u32,
+                pruned_height: self.pruned_height as u32,
+            });
+        }
+
+        let current = self.total_work();
+        let candidate_work = self
+            .work_at(common - 1)
+            .saturating_add(chain_work(&candidate[common..]));
+        if candidate_work <= current {
+            return Err(ChainError::NotHeavier {
+                current,
+                candidate: candidate_work,
+            });
+        }
+
+        let pruned = self.pruned_height;
+        self.validate_chain_on(&candidate, pruned, self.pruned_state.clone())?;
+        // Ниже границы ветки совпадают: остаются свои обрезанные блоки
+        candidate[..pruned].clone_from_slice(&self.chain[..pruned]);
+        self.write_store(common, &candidate[common..])
+            .map_err(|e| ChainError::Storage(e.into()))?;
 
-            if !current_block.is_valid(prev_block) {
-                return false;
+        self.bits = self.next_bits(&candidate);
+        // Выходы откатываются по журналу до точки ветвления, и заново
+        // подключаются только блоки новой ветки
+        let utxos_current = !self.utxos_are_stale();
+        if utxos_current {
+            self.disconnect_utxos(common);
+        }
+        let index_current = !self.index_is_stale();
+        let old_chain = std::mem::replace(&mut self.chain, candidate);
+        if index_current {
+            self.reindex_blocks_from(common);
+        } else {
+            self.reindex_blocks();
+        }
+        self.reindex_accounts();
+        if utxos_current {
+            for height in common..self.chain.len() {
+                self.connect_utxos(height);
             }
+        } else {
+            self.rebuild_utxos();
         }
-        true
+        self.evict_cold_blocks();
+
+        let disconnected = old_chain[common..].to_vec();
+        let reinjected = self.reinject(&disconnected);
+        Ok(Reorg {
+            fork_height: (common - 1) as u32,
+            connected: self.chain.len() - common,
+            disconnected,
+            reinjected,
+        })
     }
 
-    pub fn adjust_difficulty(&mut self) {
-        if self.chain.len() < self.adjustment_interval as usize {
-            return;
+    /// Возвращает в mempool транзакции отброшенных блоков и заново
+    /// проверяет ожидавшие. Всё проходит через `add_transaction`, так что
+    /// уже подтверждённые в новой цепи, конфликтующие по nonce и
+    /// непокрытые балансом отсеиваются. Отброшенные идут первыми: они
+    /// старше ожидавших и раньше тратили те же средства.
+    fn reinject(&mut self, disconnected: &[Block]) -> Vec<Hash256> {
+        let pending = std::mem::take(&mut self.mempool.transactions);
+
+        let mut reinjected = Vec::new();
+        for tx in disconnected
+            .iter()
+            .flat_map(|block| &block.transactions)
+            .filter(|tx| !tx.is_coinbase())
+        {
+            if self.add_transaction(tx.clone()).is_ok() {
+                reinjected.push(tx.id());
+            }
+        }
+
+        for tx in pending {
+            let _ = self.add_transaction(tx);
         }
+        reinjected
+    }
 
-        if self.chain.len() % self.adjustment_interval as usize != 0 {
-            return;
+    /// Цель (nBits), с которой должен быть добыт блок, следующий за `chain`.
+    ///
+    /// Каждые `adjustment_interval` блоков цель умножается на отношение
+    /// фактического времени окна к ожидаемому (`target_block_time` на
+    /// каждый промежуток). Отношение ограничено диапазоном [1/4, 4],
+    /// а цель не может стать легче `Target::MAX`. В regtest цель не
+    /// пересчитывается.
+    pub fn next_bits(&self, chain: &[Block]) -> u32 {
+        let last_block = &chain[chain.len() - 1];
+        let current = last_block.header.bits;
+        let interval = self.adjustment_interval as usize;
+
+        if self.mode == ChainMode::Regtest
+            || interval < 2
+            || chain.len() < interval
+            || !chain.len().is_multiple_of(interval)
+        {
+            return current;
         }
+        let Some(target) = Target::from_compact(current) else {
+            return current;
+        };
 
-        let start_idx = self.chain.len() - self.adjustment_interval as usize;
-        let end_idx = self.chain.len() - 1;
+        let first_block = &chain[chain.len() - interval];
+        let expected = (self.target_block_time * (interval as u64 - 1)).max(1);
+        let elapsed = last_block
+            .header
+            .timestamp
+            .saturating_sub(first_block.header.timestamp)
+            .clamp(expected.div_ceil(4), expected * 4);
 
-        let first_block = &self.chain[start_idx];
-        let last_block = &self.chain[end_idx];
+        target.scale(elapsed, expected).to_compact()
+    }
+
+    pub fn adjust_difficulty(&mut self) {
+        self.bits = self.next_bits(&self.chain);
+    }
 
-        let actual_time = last_block.timestamp - first_block.timestamp;
-        let target_time = self.target_block_time * self.adjustment_interval as u64;
+    /// Текущая цель в развёрнутом виде.
+    pub fn target(&self) -> Target {
+        Target::from_compact(self.bits).unwrap_or(Target::MAX)
+    }
+
+    /// Текущая сложность относительно `Target::MAX`.
+    pub fn difficulty(&self) -> f64 {
+        self.target().difficulty()
+    }
 
-        if actual_time < target_time && actual_time > 0 {
-            self.difficulty += 1;
-            println!("📈 Difficulty increased to: {}", self.difficulty);
-        } else if actual_time > target_time && self.difficulty > 1 {
-            self.difficulty -= 1;
-            println!("📉 Difficulty decreased to: {}", self.difficulty);
+    /// Задаёт цель для следующих блоков; хранится в компактной форме,
+    /// поэтому младшие биты цели округляются. Только в regtest: в других
+    /// сетях цель — правило консенсуса (`next_bits`), и пиры, как и
+    /// `verify`, отвергли бы блоки с заданной вручную целью.
+    pub fn set_target(&mut self, target: Target) -> Result<(), BlockchainError> {
+        if self.mode != ChainMode::Regtest {
+            return Err(BlockchainError::RegtestOnly);
         }
+        self.bits = target.min(Target::MAX).to_compact();
+        Ok(())
+    }
+
+    /// Задаёт сложность (1.0 — `Target::MAX`, 2.0 — вдвое меньшая цель);
+    /// как и `set_target`, только в regtest.
+    pub fn set_difficulty(&mut self, difficulty: f64) -> Result<(), BlockchainError> {
+        let scaled = (difficulty.max(1.0) * 1_000_000.0) as u64;
+        self.set_target(Target::MAX.scale(1_000_000, scaled))
+    }
+
+    /// Текущее время по часам цепи.
+    pub fn now(&self) -> u64 {
+        self.clock.now()
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Меняет часы цепи, например на `MockClock` в тестах.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn tx_selection(&self) -> &Arc<dyn TxSelection> {
+        &self.tx_selection
+    }
+
+    /// Меняет стратегию, по которой `take_block_transactions` наполняет
+    /// блок; на проверку чужих блоков она не влияет.
+    pub fn set_tx_selection(&mut self, selection: Arc<dyn TxSelection>) {
+        self.tx_selection = selection;
+    }
+
+    pub fn mode(&self) -> ChainMode {
+        self.mode
+    }
+
+    pub fn consensus(&self) -> &Arc<dyn Consensus> {
+        &self.consensus
+    }
+
+    /// Меняет правило консенсуса, например на `ProofOfStake`. Уже
+    /// принятые блоки не перепроверяются.
+    pub fn set_consensus(&mut self, consensus: Arc<dyn Consensus>) {
+        self.consensus = consensus;
+    }
+
+    /// Подписывать блоки, которые добывает эта цепь, ключом `signer`.
+    /// Правило консенсуса остаётся прежним; в закрытой сети ключ должен
+    /// быть в её списке производителей, иначе блоки будут отвергнуты.
+    pub fn sign_blocks_with<S: Signer + 'static>(&mut self, signer: S) {
+        let inner = Arc::clone(&self.consensus);
+        self.consensus = Arc::new(SignedBlocks::open(inner).with_signer(signer));
     }
 
-    pub fn get_balance(&self, address: &str) -> f64 {
-        let mut balance = 0.0;
+    pub fn get_balance(&self, address: &Address) -> Amount {
+        if !self.balances_are_stale() {
+            return self.balances.get(address).copied().unwrap_or_default();
+        }
+
+        let mut balance = self.pruned_state.balance(address);
 
-        for block in &self.chain {
+        for block in self.unpruned() {
             for tx in &block.transactions {
-                if tx.from == address {
-                    balance -= tx.amount;
+                if &tx.from == address && !tx.is_coinbase() {
+                    balance = balance.saturating_sub(tx.amount.saturating_add(tx.fee));
                 }
-                if tx.to == address {
-                    balance += tx.amount;
+                if &tx.to == address {
+                    balance = balance.saturating_add(tx.amount);
                 }
             }
         }
@@ -324,57 +2404,214 @@ impl Blockchain {
 
     pub fn chain_stats(&self) -> String {
         format!(
-            "Blocks: {} | Valid: {} | Difficulty: {} | Mempool: {}",
+            "Blocks: {} | Valid: {} | Difficulty: {:.2} | Mempool: {}",
             self.chain.len(),
             self.is_chain_valid(),
-            self.difficulty,
+            self.difficulty(),
             self.mempool.size()
         )
     }
 }
 
+impl Default for Blockchain {
+    fn default() -> Self {
+        Self::new(&GenesisConfig::default())
+    }
+}
+
 // ========== WALLET ==============
 #[derive(Clone)]
 pub struct Wallet {
     pub private_key: String,
     pub public_key: String,
+    /// Chain code BIP32; есть только у кошельков из сида (см. `hd`).
+    pub chain_code: Option<[u8; 32]>,
+    /// Часы, которыми помечаются создаваемые транзакции.
+    pub(crate) clock: Arc<dyn Clock>,
 }
 
 impl Wallet {
+    /// Новый кошелёк со случайным ключом из генератора ОС.
     pub fn new() -> Wallet {
-        let secp = Secp256k1::new();
-        let mut rng = rand::thread_rng();
-        let mut secret_key_bytes = [0u8; 32];
-        rng.fill(&mut secret_key_bytes);
+        Wallet::from_rng(&mut OsRng)
+    }
+
+    /// Кошелёк с ключом из генератора `rng`; с генератором из
+    /// фиксированного сида ключ всякий раз один и тот же.
+    /// `SecretKey::new` сам отбрасывает значения вне порядка кривой.
+    pub fn from_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Wallet {
+        Wallet::from_secret_key(&SecretKey::new(rng))
+    }
+
+    /// Помечать транзакции временем часов `clock`, а не системных.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Wallet {
+        self.clock = clock;
+        self
+    }
 
-        let secret_key = SecretKey::from_slice(&secret_key_bytes).expect("Invalid secret key");
-        let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+    /// Импорт существующего приватного ключа в hex.
+    pub fn from_secret_key_hex(private_key: &str) -> Result<Wallet, WalletError> {
+        let bytes = hex::decode(private_key.trim()).map_err(|_| WalletError::InvalidPrivateKey)?;
+        let secret_key =
+            SecretKey::from_slice(&bytes).map_err(|_| WalletError::InvalidPrivateKey)?;
+        Ok(Wallet::from_secret_key(&secret_key))
+    }
 
-        let private_key_hex = hex::encode(&secret_key_bytes);
-        let public_key_hex = hex::encode(public_key.serialize());
+    pub(crate) fn from_secret_key(secret_key: &SecretKey) -> Wallet {
+        let public_key = PublicKey::from_secret_key(&Secp256k1::signing_only(), secret_key);
 
         Wallet {
-            private_key: private_key_hex,
-            public_key: public_key_hex,
+            private_key: hex::encode(secret_key.secret_bytes()),
+            public_key: hex::encode(public_key.serialize()),
+            chain_code: None,
+            clock: system_clock(),
         }
     }
 
-    pub fn get_address(&self) -> String {
-        self.public_key[0..10].to_string()
+    pub fn get_address(&self) -> Address {
+        Address::from_public_key(&hex::decode(&self.public_key).expect("Invalid public key"))
+    }
+
+    /// Подпись SHA-256 от `tx_data`, см. `Signer::sign_payload`.
+    pub fn sign_transaction(&self, tx_data: &str) -> Signature {
+        self.sign_payload(tx_data)
     }
 
-    pub fn sign_transaction(&self, tx_data: &str) -> String {
-        let secp = Secp256k1::new();
+    /// Собирает и подписывает перевод на `to`: подписывается ровно тот
+    /// payload, который потом проверяется у сохранённой транзакции.
+    /// `nonce` — очередной номер отправителя, см. `Blockchain::next_nonce`.
+    /// Транзакция подписывается для сети по умолчанию.
+    pub fn create_transaction(
+        &self,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        self.create_transaction_for_chain(DEFAULT_CHAIN_ID, to, amount, fee, nonce)
+    }
 
-        let secret_key =
-            SecretKey::from_slice(&hex::decode(&self.private_key).expect("Invalid key"))
-                .expect("Invalid secret key");
+    /// То же, что `create_transaction`, но для сети `chain_id`.
+    pub fn create_transaction_for_chain(
+        &self,
+        chain_id: &str,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        self.create_transaction_with_data(chain_id, to, amount, fee, nonce, Vec::new())
+    }
+
+    /// Перевод с данными приложения в поле `data`. Комиссия должна
+    /// покрывать `Transaction::min_fee`.
+    pub fn create_transaction_with_data(
+        &self,
+        chain_id: &str,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+        data: Vec<u8>,
+    ) -> Transaction {
+        let mut tx = self.unsigned_transaction(to, amount, fee, nonce);
+        tx.chain_id = chain_id.to_string();
+        tx.data = data;
+        tx.sign_with(self);
+        tx
+    }
+
+    /// Подписанная операция с токеном `op` (см. `tokens`); `to` —
+    /// получатель выпуска или перевода. Транзакция подписывается для
+    /// сети по умолчанию.
+    pub fn create_token_transaction(
+        &self,
+        to: &Address,
+        op: TokenOp,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        self.create_token_transaction_for_chain(DEFAULT_CHAIN_ID, to, op, fee, nonce)
+    }
+
+    /// То же, что `create_token_transaction`, но для сети `chain_id`.
+    pub fn create_token_transaction_for_chain(
+        &self,
+        chain_id: &str,
+        to: &Address,
+        op: TokenOp,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        let mut tx = self.unsigned_transaction(to, Amount::ZERO, fee, nonce);
+        tx.chain_id = chain_id.to_string();
+        tx.token = Some(op);
+        tx.sign_with(self);
+        tx
+    }
+
+    /// Подписанный перевод, который можно включить в блок не раньше
+    /// `lock_time`. Транзакция подписывается для сети по умолчанию.
+    pub fn create_locked_transaction(
+        &self,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+        lock_time: LockTime,
+    ) -> Transaction {
+        self.create_locked_transaction_for_chain(
+            DEFAULT_CHAIN_ID,
+            to,
+            amount,
+            fee,
+            nonce,
+            lock_time,
+        )
+    }
+
+    /// То же, что `create_locked_transaction`, но для сети `chain_id`.
+    pub fn create_locked_transaction_for_chain(
+        &self,
+        chain_id: &str,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+        lock_time: LockTime,
+    ) -> Transaction {
+        let mut tx = self.unsigned_transaction(to, amount, fee, nonce);
+        tx.chain_id = chain_id.to_string();
+        tx.lock_time = Some(lock_time);
+        tx.sign_with(self);
+        tx
+    }
 
-        let message = secp256k1::Message::from_slice(&Sha256::digest(tx_data.as_bytes()))
-            .expect("Invalid message");
+    /// Перевод от кошелька со временем по его часам, ещё без подписи.
+    fn unsigned_transaction(
+        &self,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        Transaction {
+            timestamp: self.clock.now(),
+            ..Transaction::new(
+                self.get_address(),
+                to.clone(),
+                amount,
+                fee,
+                nonce,
+                self.public_key.clone(),
+            )
+        }
+    }
 
-        let signature = secp.sign_ecdsa(&message, &secret_key);
-        hex::encode(signature.serialize_compact())
+    /// Добавляет к транзакции M-из-N подпись ключом кошелька (или
+    /// заменяет прежнюю подпись тем же ключом).
+    pub fn co_sign(&self, tx: &mut Transaction) -> Result<(), WalletError> {
+        tx.co_sign_with(self)
     }
 
     pub fn export_private_key(&self) -> String {
@@ -385,3 +2622,9 @@ impl Wallet {
         self.public_key.clone()
     }
 }
+
+impl Default for Wallet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,386 @@
+use crate::block::{Block, Blockchain};
+use crate::storage::invalid_data;
+use crate::types::Hash256;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+// ========== BLOCK STORE ==============
+// Хранилище блоков по высоте и хешу. Цепь, к которой подключено
+// хранилище (`Blockchain::set_block_store`), пишет в него каждый новый
+// блок, а при reorg сначала отбрасывает блоки выше точки ветвления, так
+// что после перезапуска её можно поднять из хранилища
+// (`restore_from_store`) без JSONL-файла. `MemoryStore` держит блоки
+// в памяти, `SledStore` (фича `sled`) — во встроенной базе sled.
+//
+// С подключённым хранилищем цепь держит в памяти целиком только
+// последние `hot_blocks` блоков (`DEFAULT_HOT_BLOCKS`, см.
+// `set_hot_blocks`). Более старые вытесняются так же, как при обрезке:
+// от них остаются заголовки, их след в счетах уходит в состояние
+// обрезки, а `get_block_by_*`, `iter` и поиск транзакций читают тела из
+// хранилища. Записи индексов о вытесненных блоках сохраняются. Reorg
+// глубже окна узел не принимает. Консенсус, которому нужна вся история,
+// держит в памяти всю цепь.
+//
+// Запись идёт до того, как цепь примет блок или ветку: если хранилище
+// вернуло ошибку, `add_block` и `replace_chain` возвращают её
+// (`StoreError`) и цепь не меняется, так что память не уходит вперёд
+// диска молча.
+//
+// Хранилище работает через `&self`, чтобы одно и то же можно было
+// разделить между копиями цепи; синхронизацию берёт на себя реализация.
+
+pub trait BlockStore: Send + Sync {
+    /// Записывает блок на его высоту (`header.index`), заменяя прежний.
+    /// Высота не может быть больше следующей за вершиной.
+    fn put_block(&self, block: &Block) -> io::Result<()>;
+
+    fn get_block_by_height(&self, height: usize) -> io::Result<Option<Block>>;
+
+    fn get_block_by_hash(&self, hash: &Hash256) -> io::Result<Option<Block>>;
+
+    /// Высота и хеш последнего блока; `None`, если хранилище пусто.
+    fn tip(&self) -> io::Result<Option<(usize, Hash256)>>;
+
+    /// Удаляет блоки с высоты `height` и выше.
+    fn truncate(&self, height: usize) -> io::Result<()>;
+
+    /// Сбрасывает записанное на диск, если хранилище его буферизует.
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Блоки от genesis до вершины по порядку.
+    fn iter(&self) -> Box<dyn Iterator<Item = io::Result<Block>> + '_> {
+        let len = match self.tip() {
+            Ok(tip) => tip.map_or(0, |(height, _)| height + 1),
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        };
+        Box::new((0..len).map(move |height| {
+            self.get_block_by_height(height)?
+                .ok_or_else(|| invalid_data(format!("block {} is missing", height)))
+        }))
+    }
+}
+
+/// Сколько последних блоков цепь с хранилищем держит в памяти целиком.
+pub const DEFAULT_HOT_BLOCKS: usize = 1_000;
+
+/// Ошибка записи блока выше следующей за вершиной высоты.
+fn gap_error(height: usize, len: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("block {} leaves a gap after height {}", height, len),
+    )
+}
+
+// ========== MEMORY STORE ==============
+
+#[derive(Debug, Default)]
+struct MemoryBlocks {
+    blocks: Vec<Block>,
+    by_hash: HashMap<Hash256, usize>,
+}
+
+/// Хранилище в памяти: для тестов и узлов без диска.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    inner: RwLock<MemoryBlocks>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BlockStore for MemoryStore {
+    fn put_block(&self, block: &Block) -> io::Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        let height = block.header.index as usize;
+        let len = inner.blocks.len();
+        if height > len {
+            return Err(gap_error(height, len));
+        }
+        if height == len {
+            inner.blocks.push(block.clone());
+        } else {
+            let old = std::mem::replace(&mut inner.blocks[height], block.clone());
+            inner.by_hash.remove(&old.hash);
+        }
+        inner.by_hash.insert(block.hash, height);
+        Ok(())
+    }
+
+    fn get_block_by_height(&self, height: usize) -> io::Result<Option<Block>> {
+        Ok(self.inner.read().unwrap().blocks.get(height).cloned())
+    }
+
+    fn get_block_by_hash(&self, hash: &Hash256) -> io::Result<Option<Block>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .by_hash
+            .get(hash)
+            .map(|&height| inner.blocks[height].clone()))
+    }
+
+    fn tip(&self) -> io::Result<Option<(usize, Hash256)>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .blocks
+            .last()
+            .map(|block| (inner.blocks.len() - 1, block.hash)))
+    }
+
+    fn truncate(&self, height: usize) -> io::Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        if height < inner.blocks.len() {
+            let removed: Vec<Block> = inner.blocks.drain(height..).collect();
+            for block in removed {
+                inner.by_hash.remove(&block.hash);
+            }
+        }
+        Ok(())
+    }
+}
+
+// ========== SLED STORE ==============
+
+/// Хранилище во встроенной базе sled: дерево `blocks` (высота в big
+/// endian → блок в bincode) и дерево `hashes` (хеш → высота).
+#[cfg(feature = "sled")]
+pub struct SledStore {
+    db: sled::Db,
+    blocks: sled::Tree,
+    hashes: sled::Tree,
+}
+
+#[cfg(feature = "sled")]
+impl SledStore {
+    /// Открывает базу в каталоге `path`, создавая её при необходимости.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> io::Result<SledStore> {
+        let db = sled::open(path)?;
+        Ok(SledStore {
+            blocks: db.open_tree("blocks")?,
+            hashes: db.open_tree("hashes")?,
+            db,
+        })
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Block> {
+        bincode::deserialize(bytes).map_err(|e| invalid_data(e.to_string()))
+    }
+
+    fn height_key(height: usize) -> [u8; 8] {
+        (height as u64).to_be_bytes()
+    }
+}
+
+#[cfg(feature = "sled")]
+impl BlockStore for SledStore {
+    fn put_block(&self, block: &Block) -> io::Result<()> {
+        let height = block.header.index as usize;
+        let len = self.tip()?.map_or(0, |(tip, _)| tip + 1);
+        if height > len {
+            return Err(gap_error(height, len));
+        }
+        let bytes = bincode::serialize(block).map_err(|e| invalid_data(e.to_string()))?;
+        if let Some(old) = self.blocks.insert(Self::height_key(height), bytes)? {
+            self.hashes.remove(Self::decode(&old)?.hash.as_bytes())?;
+        }
+        self.hashes
+            .insert(block.hash.as_bytes(), &Self::height_key(height))?;
+        Ok(())
+    }
+
+    fn get_block_by_height(&self, height: usize) -> io::Result<Option<Block>> {
+        self.blocks
+            .get(Self::height_key(height))?
+            .map(|bytes| Self::decode(&bytes))
+            .transpose()
+    }
+
+    fn get_block_by_hash(&self, hash: &Hash256) -> io::Result<Option<Block>> {
+        match self.hashes.get(hash.as_bytes())? {
+            Some(height) => self
+                .blocks
+                .get(height)?
+                .map(|bytes| Self::decode(&bytes))
+                .transpose(),
+            None => Ok(None),
+        }
+    }
+
+    fn tip(&self) -> io::Result<Option<(usize, Hash256)>> {
+        match self.blocks.last()? {
+            Some((_, bytes)) => {
+                let block = Self::decode(&bytes)?;
+                Ok(Some((block.header.index as usize, block.hash)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn truncate(&self, height: usize) -> io::Result<()> {
+        for entry in self.blocks.range(Self::height_key(height)..) {
+            let (key, bytes) = entry?;
+            self.hashes.remove(Self::decode(&bytes)?.hash.as_bytes())?;
+            self.blocks.remove(key)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+/// Где узел хранит блоки: ключ `block_store` конфигурации.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockStoreKind {
+    /// Только JSONL-файл `chain_file`, переписываемый при остановке.
+    #[default]
+    File,
+    /// База sled в `data_dir/blocks.sled` (нужна фича `sled`).
+    Sled,
+}
+
+impl fmt::Display for BlockStoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BlockStoreKind::File => "file",
+            BlockStoreKind::Sled => "sled",
+        })
+    }
+}
+
+impl FromStr for BlockStoreKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(BlockStoreKind::File),
+            "sled" => Ok(BlockStoreKind::Sled),
+            _ => Err(format!("unknown block store: {}", s)),
+        }
+    }
+}
+
+impl Blockchain {
+    /// Подключает хранилище блоков и дописывает в него цепь, начиная
+    /// с первого блока, которого там нет или который там другой.
+    pub fn set_block_store(&mut self, store: Arc<dyn BlockStore>) -> io::Result<()> {
+        self.store = Some(store);
+        self.write_store(self.chain.len(), &[])?;
+        self.evict_cold_blocks();
+        Ok(())
+    }
+
+    /// Задаёт, сколько последних блоков держать в памяти целиком, пока
+    /// подключено хранилище; тела более старых сразу вытесняются.
+    /// Вершина остаётся в памяти всегда.
+    pub fn set_hot_blocks(&mut self, blocks: usize) {
+        self.hot_blocks = blocks.max(1);
+        self.evict_cold_blocks();
+    }
+
+    /// Вытесняет из памяти тела блоков ниже окна `hot_blocks`. Без
+    /// хранилища и при консенсусе, которому нужна вся история, ничего
+    /// не делает.
+    pub(crate) fn evict_cold_blocks(&mut self) {
+        if self.store.is_none() || self.consensus().needs_full_history() {
+            return;
+        }
+        self.evict_blocks(self.chain.len().saturating_sub(self.hot_blocks));
+    }
+
+    /// Переносит блоки ниже высоты `target` в состояние обрезки и
+    /// оставляет от них в цепи заголовки; их тела должны уже лежать в
+    /// хранилище. Возвращает, сколько тел вытеснено.
+    pub(crate) fn evict_blocks(&mut self, target: usize) -> usize {
+        if target <= self.pruned_height {
+            return 0;
+        }
+        for height in self.pruned_height..target {
+            self.pruned_state.apply_block(&self.chain[height]);
+            if height > 0 {
+                let block = &mut self.chain[height];
+                block.transactions = Vec::new();
+                block.utxo_transactions = Vec::new();
+            }
+            // Откатить блок ниже границы уже нельзя
+            if let Some(undo) = self.utxo_undo.get_mut(height) {
+                *undo = Default::default();
+            }
+        }
+        let evicted = target - self.pruned_height.max(1);
+        self.pruned_height = target;
+        evicted
+    }
+
+    pub fn block_store(&self) -> Option<&Arc<dyn BlockStore>> {
+        self.store.as_ref()
+    }
+
+    /// То же, что `restore_from_disk`, но блоки читаются из `store`,
+    /// которое затем остаётся подключённым к цепи.
+    pub fn restore_from_store(mut self, store: Arc<dyn BlockStore>) -> io::Result<Blockchain> {
+        self.chain = store.iter().collect::<io::Result<_>>()?;
+        if self.chain.is_empty() {
+            return Err(invalid_data("block store is empty".to_string()));
+        }
+        self.reindex();
+        self.validate_chain(&self.chain)
+            .map_err(|e| invalid_data(format!("stored chain failed validation: {}", e)))?;
+        self.bits = self.next_bits(&self.chain);
+        self.store = Some(store);
+        self.evict_cold_blocks();
+
+        Ok(self)
+    }
+
+    /// Записывает в хранилище `blocks` с высоты `from` (не выше вершины
+    /// цепи плюс один), отбросив всё, что там выше. Блоки цепи ниже
+    /// `from`, которых в хранилище нет или которые там другие, сначала
+    /// дописываются заново.
+    pub(crate) fn write_store(&self, from: usize, blocks: &[Block]) -> io::Result<()> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let start = self.stored_prefix(store.as_ref(), from)?;
+        store.truncate(start)?;
+        for block in self.full_blocks(start..from)? {
+            store.put_block(&block)?;
+        }
+        for block in blocks {
+            store.put_block(block)?;
+        }
+        store.flush()
+    }
+
+    /// Сколько первых блоков цепи, но не больше `from`, лежит в
+    /// хранилище. Обычно это `from`; меньше — если прошлая запись
+    /// прервалась и оставила пропуск или часть отвергнутой ветки.
+    fn stored_prefix(&self, store: &dyn BlockStore, from: usize) -> io::Result<usize> {
+        let tip = store.tip()?;
+        let mut height = from.min(tip.map_or(0, |(tip, _)| tip + 1));
+        while height > 0 {
+            let stored = match tip {
+                Some((tip, hash)) if tip == height - 1 => Some(hash),
+                _ => store
+                    .get_block_by_height(height - 1)?
+                    .map(|block| block.hash),
+            };
+            if stored == Some(self.chain[height - 1].hash) {
+                break;
+            }
+            height -= 1;
+        }
+        Ok(height)
+    }
+}
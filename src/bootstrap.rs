@@ -0,0 +1,71 @@
+use crate::config::DEFAULT_LISTEN_PORT;
+use crate::network::Node;
+use std::net::ToSocketAddrs;
+use tracing::{debug, info, warn};
+
+// ========== BOOTSTRAP ==============
+// Новому узлу не к кому подключиться, пока ему не назвали хотя бы один
+// адрес. Если после `peers` и `seeds` из конфигурации таблица пиров
+// пуста, узел берёт адреса из статического списка `bootstrap_nodes` и из
+// DNS-сидов `dns_seeds` — имён, чьи A/AAAA-записи указывают на живые
+// узлы сети. Найденные адреса обходятся как seed-узлы
+// (`discover_peers`): узел подключается к ним, узнаёт их пиров и
+// запоминает всех в менеджере соединений.
+
+/// Адреса узлов, на которые указывает DNS-сид. `seed` — имя хоста,
+/// возможно с портом; без порта берётся `DEFAULT_LISTEN_PORT`.
+pub fn resolve_dns_seed(seed: &str) -> Vec<String> {
+    // «хост:порт» и «[IPv6]:порт»; голый IPv6-адрес содержит несколько
+    // двоеточий и порта не имеет
+    let resolved = if seed.contains(']') || seed.matches(':').count() == 1 {
+        seed.to_socket_addrs()
+    } else {
+        (seed, DEFAULT_LISTEN_PORT).to_socket_addrs()
+    };
+    match resolved {
+        Ok(addrs) => {
+            let mut unique: Vec<String> = Vec::new();
+            for addr in addrs.map(|addr| addr.to_string()) {
+                if !unique.contains(&addr) {
+                    unique.push(addr);
+                }
+            }
+            unique
+        }
+        Err(e) => {
+            warn!(seed, error = %e, "cannot resolve DNS seed");
+            Vec::new()
+        }
+    }
+}
+
+impl Node {
+    /// Подключается к сети через `bootstrap_nodes` и DNS-сиды, если
+    /// таблица пиров пуста. Возвращает число пиров после обхода.
+    pub fn bootstrap(&self, dns_seeds: &[String], bootstrap_nodes: &[String]) -> usize {
+        let connected = self.peers.lock().unwrap().len();
+        if connected > 0 {
+            return connected;
+        }
+
+        let mut candidates: Vec<String> = bootstrap_nodes.to_vec();
+        for seed in dns_seeds {
+            let resolved = resolve_dns_seed(seed);
+            debug!(seed = %seed, count = resolved.len(), "DNS seed resolved");
+            candidates.extend(resolved);
+        }
+        let mut unique = Vec::new();
+        for candidate in candidates {
+            if !unique.contains(&candidate) {
+                unique.push(candidate);
+            }
+        }
+        if unique.is_empty() {
+            warn!(node = %self.id, "no peers and no bootstrap addresses");
+            return 0;
+        }
+
+        info!(node = %self.id, candidates = unique.len(), "bootstrapping");
+        self.discover_peers(&unique)
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// ========== CLOCK ==============
+// Источник времени для цепи и кошелька. Узел берёт время у системных
+// часов, а тесты подставляют `MockClock` и двигают время сами, так что
+// время блоков и транзакций, а с ним и их хеши, воспроизводимы.
+
+pub trait Clock: Send + Sync {
+    /// Текущее время в секундах Unix.
+    fn now(&self) -> u64;
+}
+
+/// Системные часы.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+    }
+}
+
+/// Часы, которые идут только по команде. Клоны показывают одно и то же
+/// время, поэтому одни часы можно раздать цепи и нескольким кошелькам.
+#[derive(Clone, Default)]
+pub struct MockClock {
+    now: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    pub fn new(now: u64) -> Self {
+        MockClock {
+            now: Arc::new(AtomicU64::new(now)),
+        }
+    }
+
+    pub fn set(&self, now: u64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    /// Переводит часы на `secs` секунд вперёд.
+    pub fn advance(&self, secs: u64) {
+        self.now.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+impl fmt::Debug for MockClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MockClock").field(&self.now()).finish()
+    }
+}
+
+pub(crate) fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
@@ -0,0 +1,238 @@
+use crate::block::{Block, BlockHeader, Transaction};
+use crate::network::{runtime, send_request, Node};
+use crate::protocol::Message;
+use crate::types::{Hash256, Signature};
+use crate::utxo::UtxoTransaction;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::thread;
+use tracing::{debug, warn};
+
+// ========== COMPACT BLOCKS ==============
+// Новый блок рассылается не целиком, а как COMPACT_BLOCK: заголовок и
+// 8-байтовые короткие id транзакций. Почти все транзакции блока пир уже
+// получил раньше через NEW_TX и держит в mempool, так что блок собирается
+// у него на месте. Недостающие транзакции пир запрашивает у отправителя
+// (GET_BLOCK_TXN по позициям в блоке), а если и это не удалось — весь
+// блок через GET_BLOCKS. Coinbase у получателя заведомо нет, она
+// передаётся целиком, как и UTXO-транзакции: в mempool их не бывает.
+//
+// Короткий id берётся из хеша блока и txid, поэтому подобрать
+// транзакцию-двойника заранее нельзя, а случайное совпадение двух
+// транзакций mempool лишь делает позицию недостающей. Собранный блок
+// сверяется с merkle-корнем заголовка.
+
+/// Короткий id транзакции внутри блока.
+pub type ShortId = u64;
+
+/// Первые 8 байт SHA-256 от хеша блока и txid.
+pub fn short_id(block_hash: &Hash256, txid: &Hash256) -> ShortId {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(block_hash.as_bytes());
+    data[32..].copy_from_slice(txid.as_bytes());
+    let digest = Hash256::digest(&data);
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest.as_bytes()[..8]);
+    u64::from_le_bytes(prefix)
+}
+
+/// Блок для рассылки: заголовок, короткие id всех транзакций по порядку
+/// и те транзакции, которых у получателя быть не может.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompactBlock {
+    pub header: BlockHeader,
+    pub hash: Hash256,
+    pub short_ids: Vec<ShortId>,
+    /// Транзакции, переданные целиком, с их позициями в блоке.
+    pub prefilled: Vec<(u32, Transaction)>,
+    #[serde(default)]
+    pub producer_key: String,
+    #[serde(default)]
+    pub producer_signature: Signature,
+    #[serde(default)]
+    pub utxo_transactions: Vec<UtxoTransaction>,
+}
+
+impl CompactBlock {
+    pub fn from_block(block: &Block) -> Self {
+        CompactBlock {
+            header: block.header,
+            hash: block.hash,
+            short_ids: block
+                .transactions
+                .iter()
+                .map(|tx| short_id(&block.hash, &tx.id()))
+                .collect(),
+            prefilled: block
+                .transactions
+                .iter()
+                .enumerate()
+                .filter(|(_, tx)| tx.is_coinbase())
+                .map(|(index, tx)| (index as u32, tx.clone()))
+                .collect(),
+            producer_key: block.producer_key.clone(),
+            producer_signature: block.producer_signature,
+            utxo_transactions: block.utxo_transactions.clone(),
+        }
+    }
+
+    /// Раскладывает по местам переданные целиком транзакции и найденные
+    /// в `pool` по коротким id.
+    pub fn fill<'a>(&self, pool: impl IntoIterator<Item = &'a Transaction>) -> PartialBlock {
+        // None — под одним коротким id несколько транзакций
+        let mut by_id: HashMap<ShortId, Option<&Transaction>> = HashMap::new();
+        for tx in pool {
+            by_id
+                .entry(short_id(&self.hash, &tx.id()))
+                .and_modify(|found| *found = None)
+                .or_insert(Some(tx));
+        }
+
+        let mut slots: Vec<Option<Transaction>> = self
+            .short_ids
+            .iter()
+            .map(|id| by_id.get(id).copied().flatten().cloned())
+            .collect();
+        for (index, tx) in &self.prefilled {
+            if let Some(slot) = slots.get_mut(*index as usize) {
+                *slot = Some(tx.clone());
+            }
+        }
+        PartialBlock {
+            compact: self.clone(),
+            slots,
+        }
+    }
+}
+
+/// Блок, собираемый из `CompactBlock`.
+#[derive(Debug, Clone)]
+pub struct PartialBlock {
+    compact: CompactBlock,
+    slots: Vec<Option<Transaction>>,
+}
+
+impl PartialBlock {
+    /// Позиции транзакций, которых не нашлось.
+    pub fn missing(&self) -> Vec<u32> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_none())
+            .map(|(index, _)| index as u32)
+            .collect()
+    }
+
+    /// Ставит на недостающие позиции транзакции из ответа BLOCK_TXN.
+    /// `false`, если их число или короткие id не совпали.
+    pub fn fill_missing(&mut self, transactions: Vec<Transaction>) -> bool {
+        let missing = self.missing();
+        if missing.len() != transactions.len() {
+            return false;
+        }
+        for (index, tx) in missing.into_iter().zip(transactions) {
+            let index = index as usize;
+            if short_id(&self.compact.hash, &tx.id()) != self.compact.short_ids[index] {
+                return false;
+            }
+            self.slots[index] = Some(tx);
+        }
+        true
+    }
+
+    /// Готовый блок, если все транзакции на месте и их merkle-корень
+    /// совпал с заголовком.
+    pub fn into_block(self) -> Option<Block> {
+        let transactions: Vec<Transaction> = self.slots.into_iter().collect::<Option<_>>()?;
+        let root = Block::body_merkle_root(&transactions, &self.compact.utxo_transactions);
+        if root != self.compact.header.merkle_root {
+            return None;
+        }
+        Some(Block {
+            header: self.compact.header,
+            hash: self.compact.hash,
+            transactions,
+            producer_key: self.compact.producer_key,
+            producer_signature: self.compact.producer_signature,
+            utxo_transactions: self.compact.utxo_transactions,
+        })
+    }
+}
+
+impl Node {
+    /// Собирает блок из COMPACT_BLOCK пира `peer` и подключает его.
+    /// Недостающие транзакции дозапрашиваются у `origin` в фоне.
+    pub(crate) async fn accept_compact_block(
+        &self,
+        compact: CompactBlock,
+        origin: String,
+        peer: IpAddr,
+    ) {
+        let partial = {
+            let bc = self.blockchain.read().await;
+            if bc.contains_block(&compact.hash) {
+                return;
+            }
+            compact.fill(&bc.mempool.transactions)
+        };
+        let missing = partial.missing();
+        if missing.is_empty() {
+            if let Some(block) = partial.clone().into_block() {
+                self.accept_block(block, peer).await;
+                return;
+            }
+        }
+
+        debug!(
+            index = compact.header.index,
+            missing = missing.len(),
+            "compact block incomplete, asking sender"
+        );
+        let node = self.clone_node();
+        let worker = node.worker();
+        thread::spawn(move || {
+            let _worker = worker;
+            match node.fetch_block_transactions(partial, missing, &origin) {
+                Some(block) => runtime().block_on(node.accept_block(block, peer)),
+                None => {
+                    warn!(peer = %origin, hash = %compact.hash, "cannot reconstruct compact block")
+                }
+            }
+        });
+    }
+
+    /// Дозапрашивает у `origin` транзакции с позиций `missing`, а если
+    /// блок так и не собрался — весь блок.
+    fn fetch_block_transactions(
+        &self,
+        mut partial: PartialBlock,
+        missing: Vec<u32>,
+        origin: &str,
+    ) -> Option<Block> {
+        let (hash, index) = (partial.compact.hash, partial.compact.header.index);
+        if !missing.is_empty() {
+            let request = Message::GetBlockTxn {
+                hash,
+                indexes: missing,
+            };
+            if let Some(Message::BlockTxn { transactions, .. }) =
+                send_request(origin, &request, &self.transport)
+            {
+                if partial.fill_missing(transactions) {
+                    if let Some(block) = partial.into_block() {
+                        return Some(block);
+                    }
+                }
+            }
+        }
+
+        debug!(index, "falling back to the full block");
+        let block = self
+            .request_blocks(origin, index, index + 1)
+            .ok()?
+            .into_iter()
+            .next()?;
+        (block.hash == hash).then_some(block)
+    }
+}
@@ -0,0 +1,536 @@
+use crate::banlist::DEFAULT_BAN_DURATION;
+use crate::block::{
+    Blockchain, MemPool, Wallet, DEFAULT_ADJUSTMENT_INTERVAL, DEFAULT_MEMPOOL_SIZE, DEFAULT_MEMPOOL_TTL,
+    DEFAULT_TARGET_BLOCK_TIME,
+};
+#[cfg(feature = "sled")]
+use crate::blockstore::SledStore;
+use crate::blockstore::{BlockStore, BlockStoreKind};
+use crate::connections::DEFAULT_TARGET_OUTBOUND;
+use crate::error::ConfigError;
+use crate::genesis::GenesisConfig;
+use crate::health::DEFAULT_PING_INTERVAL;
+use crate::network::{Node, DEFAULT_MAX_PEERS};
+use crate::pruning::{BlockArchive, ARCHIVE_DIR_NAME};
+use crate::resync::DEFAULT_RESYNC_INTERVAL;
+use crate::rpc::DEFAULT_RPC_PORT;
+use crate::selection::SelectionStrategy;
+use crate::snapshot::{StateSnapshot, DEFAULT_SNAPSHOT_INTERVAL, SNAPSHOT_FILE_NAME};
+use crate::transport::Transport;
+use crate::types::Address;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+// ========== NODE CONFIG ==============
+// Настройки узла. Источники по возрастанию приоритета: значения по
+// умолчанию, TOML-файл, переменные окружения `BLOCKCHAIN_*`, флаги CLI.
+//
+//   node_id = "alice"
+//   listen_port = 3000
+//   listen_addrs = ["0.0.0.0", "::"]
+//   rpc_port = 8545
+//   data_dir = "data/alice"
+//   seeds = ["127.0.0.1:3001"]
+//   dns_seeds = ["seed.example.org"]
+//   miner_address = "1A1zP1…"
+//   mempool_size = 5000
+//   log_level = "info"
+
+pub const DEFAULT_LISTEN_PORT: u16 = 3000;
+/// Имя файла цепи внутри `data_dir`.
+pub const CHAIN_FILE_NAME: &str = "node_chain.jsonl";
+/// Имя каталога базы блоков для `block_store = "sled"` внутри `data_dir`.
+pub const BLOCK_STORE_DIR_NAME: &str = "blocks.sled";
+/// Имя файла ожидающих транзакций внутри `data_dir`.
+pub const MEMPOOL_FILE_NAME: &str = "mempool.jsonl";
+/// Имя файла с ключом узла для шифрованного транспорта внутри `data_dir`.
+pub const NODE_KEY_FILE_NAME: &str = "node_key";
+/// Префикс переменных окружения: `BLOCKCHAIN_LISTEN_PORT` и т. д.
+pub const ENV_PREFIX: &str = "BLOCKCHAIN_";
+
+const LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub node_id: String,
+    pub listen_port: u16,
+    /// Адреса, на которых узел принимает пиров (порт — `listen_port`):
+    /// конкретные интерфейсы или `0.0.0.0` и `::` для всех IPv4 и IPv6.
+    pub listen_addrs: Vec<IpAddr>,
+    pub rpc_port: u16,
+    /// Каталог с файлом цепи; создаётся при запуске узла.
+    pub data_dir: PathBuf,
+    /// Конфигурация genesis; без неё — сеть по умолчанию.
+    pub genesis: Option<PathBuf>,
+    /// Без `genesis` — сеть regtest (`GenesisConfig::regtest`) вместо
+    /// сети по умолчанию.
+    pub regtest: bool,
+    /// Пиры, к которым узел подключается сразу.
+    pub peers: Vec<String>,
+    /// Seed-узлы для обнаружения пиров.
+    pub seeds: Vec<String>,
+    /// Имена хостов, A/AAAA-записи которых указывают на узлы сети; к
+    /// ним узел обращается, если таблица пиров при запуске пуста. См.
+    /// `bootstrap`.
+    pub dns_seeds: Vec<String>,
+    /// Адреса узлов для первого подключения, когда таблица пиров пуста.
+    pub bootstrap_nodes: Vec<String>,
+    /// Пробросить `listen_port` на роутере по UPnP и сообщать пирам
+    /// внешний адрес; нужна сборка с `--features upnp`. См. `upnp`.
+    pub upnp: bool,
+    pub max_peers: usize,
+    /// Сколько исходящих соединений поддерживать, переподключаясь к
+    /// пропавшим пирам, см. `connections`.
+    pub target_outbound: usize,
+    /// На сколько секунд банится пир, набравший штрафной порог.
+    pub ban_duration: u64,
+    /// Как часто сверять вершину цепи с пирами, в секундах; 0 — только
+    /// при подключении. См. `resync`.
+    pub resync_interval: u64,
+    /// Как часто опрашивать пиров PING, в секундах; 0 — не опрашивать.
+    /// См. `health`.
+    pub ping_interval: u64,
+    /// Шифровать соединения с пирами ключом из `node_key_file`; у всех
+    /// пиров настройка должна совпадать.
+    pub encrypted_transport: bool,
+    /// Если задан, узел майнит в фоне с наградой на этот адрес.
+    pub miner_address: Option<Address>,
+    pub mining_threads: usize,
+    /// Желаемое время между блоками в секундах.
+    pub target_block_time: u64,
+    /// Через сколько блоков пересчитывается сложность.
+    pub adjustment_interval: u32,
+    pub mempool_size: usize,
+    pub mempool_ttl: u64,
+    /// Как майнер выбирает транзакции для блока, см. `selection`.
+    pub tx_selection: SelectionStrategy,
+    /// Через сколько блоков сохранять снимок состояния; 0 — только при
+    /// остановке узла.
+    pub snapshot_interval: u32,
+    /// Сколько последних блоков держать целиком; более старые вместе со
+    /// снимком уходят в `archive_dir` (0 — не обрезать), см. `pruning`.
+    pub prune_blocks: usize,
+    /// Где хранятся блоки, см. `blockstore`.
+    pub block_store: BlockStoreKind,
+    pub log_level: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            node_id: "node".to_string(),
+            listen_port: DEFAULT_LISTEN_PORT,
+            listen_addrs: vec![IpAddr::V4(Ipv4Addr::LOCALHOST)],
+            rpc_port: DEFAULT_RPC_PORT,
+            data_dir: PathBuf::from("."),
+            genesis: None,
+            regtest: false,
+            peers: Vec::new(),
+            seeds: Vec::new(),
+            dns_seeds: Vec::new(),
+            bootstrap_nodes: Vec::new(),
+            upnp: false,
+            max_peers: DEFAULT_MAX_PEERS,
+            target_outbound: DEFAULT_TARGET_OUTBOUND,
+            ban_duration: DEFAULT_BAN_DURATION,
+            resync_interval: DEFAULT_RESYNC_INTERVAL,
+            ping_interval: DEFAULT_PING_INTERVAL,
+            encrypted_transport: false,
+            miner_address: None,
+            mining_threads: 1,
+            target_block_time: DEFAULT_TARGET_BLOCK_TIME,
+            adjustment_interval: DEFAULT_ADJUSTMENT_INTERVAL,
+            mempool_size: DEFAULT_MEMPOOL_SIZE,
+            mempool_ttl: DEFAULT_MEMPOOL_TTL,
+            tx_selection: SelectionStrategy::default(),
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            prune_blocks: 0,
+            block_store: BlockStoreKind::default(),
+            log_level: "info".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Читает TOML-файл; отсутствующие ключи берутся по умолчанию.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|e| ConfigError::Malformed(e.to_string()))
+    }
+
+    /// Применяет переопределения из переменных окружения процесса.
+    pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+        self.apply_overrides(|key| env::var(key).ok())
+    }
+
+    /// Применяет переопределения `BLOCKCHAIN_<КЛЮЧ>`, которые находит
+    /// `lookup`. Списки (`LISTEN_ADDRS`, `PEERS`, `SEEDS`,
+    /// `DNS_SEEDS`, `BOOTSTRAP_NODES`) задаются через запятую.
+    pub fn apply_overrides<F>(&mut self, lookup: F) -> Result<(), ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |name: &str| lookup(&format!("{}{}", ENV_PREFIX, name));
+
+        if let Some(value) = var("NODE_ID") {
+            self.node_id = value;
+        }
+        if let Some(value) = var("LISTEN_PORT") {
+            self.listen_port = parse("LISTEN_PORT", &value)?;
+        }
+        if let Some(value) = var("LISTEN_ADDRS") {
+            self.listen_addrs = split_list(&value)
+                .iter()
+                .map(|addr| parse("LISTEN_ADDRS", addr))
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(value) = var("RPC_PORT") {
+            self.rpc_port = parse("RPC_PORT", &value)?;
+        }
+        if let Some(value) = var("DATA_DIR") {
+            self.data_dir = PathBuf::from(value);
+        }
+        if let Some(value) = var("GENESIS") {
+            self.genesis = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("REGTEST") {
+            self.regtest = parse("REGTEST", &value)?;
+        }
+        if let Some(value) = var("PEERS") {
+            self.peers = split_list(&value);
+        }
+        if let Some(value) = var("SEEDS") {
+            self.seeds = split_list(&value);
+        }
+        if let Some(value) = var("DNS_SEEDS") {
+            self.dns_seeds = split_list(&value);
+        }
+        if let Some(value) = var("BOOTSTRAP_NODES") {
+            self.bootstrap_nodes = split_list(&value);
+        }
+        if let Some(value) = var("UPNP") {
+            self.upnp = parse("UPNP", &value)?;
+        }
+        if let Some(value) = var("MAX_PEERS") {
+            self.max_peers = parse("MAX_PEERS", &value)?;
+        }
+        if let Some(value) = var("TARGET_OUTBOUND") {
+            self.target_outbound = parse("TARGET_OUTBOUND", &value)?;
+        }
+        if let Some(value) = var("BAN_DURATION") {
+            self.ban_duration = parse("BAN_DURATION", &value)?;
+        }
+        if let Some(value) = var("RESYNC_INTERVAL") {
+            self.resync_interval = parse("RESYNC_INTERVAL", &value)?;
+        }
+        if let Some(value) = var("PING_INTERVAL") {
+            self.ping_interval = parse("PING_INTERVAL", &value)?;
+        }
+        if let Some(value) = var("ENCRYPTED_TRANSPORT") {
+            self.encrypted_transport = parse("ENCRYPTED_TRANSPORT", &value)?;
+        }
+        if let Some(value) = var("MINER_ADDRESS") {
+            self.miner_address = Some(Address::from(value.as_str()));
+        }
+        if let Some(value) = var("MINING_THREADS") {
+            self.mining_threads = parse("MINING_THREADS", &value)?;
+        }
+        if let Some(value) = var("TARGET_BLOCK_TIME") {
+            self.target_block_time = parse("TARGET_BLOCK_TIME", &value)?;
+        }
+        if let Some(value) = var("ADJUSTMENT_INTERVAL") {
+            self.adjustment_interval = parse("ADJUSTMENT_INTERVAL", &value)?;
+        }
+        if let Some(value) = var("MEMPOOL_SIZE") {
+            self.mempool_size = parse("MEMPOOL_SIZE", &value)?;
+        }
+        if let Some(value) = var("MEMPOOL_TTL") {
+            self.mempool_ttl = parse("MEMPOOL_TTL", &value)?;
+        }
+        if let Some(value) = var("TX_SELECTION") {
+            self.tx_selection = parse("TX_SELECTION", &value)?;
+        }
+        if let Some(value) = var("SNAPSHOT_INTERVAL") {
+            self.snapshot_interval = parse("SNAPSHOT_INTERVAL", &value)?;
+        }
+        if let Some(value) = var("PRUNE_BLOCKS") {
+            self.prune_blocks = parse("PRUNE_BLOCKS", &value)?;
+        }
+        if let Some(value) = var("BLOCK_STORE") {
+            self.block_store = parse("BLOCK_STORE", &value)?;
+        }
+        if let Some(value) = var("LOG_LEVEL") {
+            self.log_level = value;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |key: &str, value: String| {
+            Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                value,
+            })
+        };
+
+        if self.node_id.is_empty() {
+            return invalid("node_id", String::new());
+        }
+        // Режим сети задаёт genesis-конфигурация, если она есть
+        if self.regtest && self.genesis.is_some() {
+            return invalid("regtest", "true".to_string());
+        }
+        if let Some(address) = &self.miner_address {
+            if address.validate().is_err() {
+                return invalid("miner_address", address.to_string());
+            }
+        }
+        if self.target_block_time == 0 {
+            return invalid("target_block_time", "0".to_string());
+        }
+        if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            return invalid("log_level", self.log_level.clone());
+        }
+        if self.block_store == BlockStoreKind::Sled && !cfg!(feature = "sled") {
+            return invalid("block_store", self.block_store.to_string());
+        }
+        if self.upnp && !cfg!(feature = "upnp") {
+            return invalid("upnp", "true".to_string());
+        }
+        if self.listen_addrs.is_empty() {
+            return invalid("listen_addrs", String::new());
+        }
+        Ok(())
+    }
+
+    /// Адреса слушающих сокетов узла: `listen_addrs` с `listen_port`.
+    pub fn listen_sockets(&self) -> Vec<SocketAddr> {
+        self.listen_addrs
+            .iter()
+            .map(|ip| SocketAddr::new(*ip, self.listen_port))
+            .collect()
+    }
+
+    pub fn chain_file(&self) -> PathBuf {
+        self.data_dir.join(CHAIN_FILE_NAME)
+    }
+
+    pub fn snapshot_file(&self) -> PathBuf {
+        self.data_dir.join(SNAPSHOT_FILE_NAME)
+    }
+
+    pub fn block_store_dir(&self) -> PathBuf {
+        self.data_dir.join(BLOCK_STORE_DIR_NAME)
+    }
+
+    pub fn archive_dir(&self) -> PathBuf {
+        self.data_dir.join(ARCHIVE_DIR_NAME)
+    }
+
+    pub fn mempool_file(&self) -> PathBuf {
+        self.data_dir.join(MEMPOOL_FILE_NAME)
+    }
+
+    pub fn node_key_file(&self) -> PathBuf {
+        self.data_dir.join(NODE_KEY_FILE_NAME)
+    }
+
+    /// Ключ узла из `node_key_file` (hex закрытого ключа); при первом
+    /// запуске создаётся и сохраняется новый, так что узел сохраняет
+    /// одну и ту же идентичность между перезапусками.
+    fn node_key(&self) -> Result<Wallet, ConfigError> {
+        let path = self.node_key_file();
+        if !path.exists() {
+            let wallet = Wallet::new();
+            fs::write(&path, &wallet.private_key)?;
+            return Ok(wallet);
+        }
+        let hex = fs::read_to_string(&path)?;
+        Wallet::from_secret_key_hex(hex.trim()).map_err(|_| ConfigError::InvalidValue {
+            key: "node_key".to_string(),
+            value: path.display().to_string(),
+        })
+    }
+
+    /// Хранилище блоков из `block_store`; для `file` его нет — цепь
+    /// живёт в `chain_file`.
+    fn open_block_store(&self) -> Result<Option<Arc<dyn BlockStore>>, ConfigError> {
+        match self.block_store {
+            BlockStoreKind::File => Ok(None),
+            #[cfg(feature = "sled")]
+            BlockStoreKind::Sled => Ok(Some(Arc::new(SledStore::open(self.block_store_dir())?))),
+            #[cfg(not(feature = "sled"))]
+            BlockStoreKind::Sled => Err(ConfigError::InvalidValue {
+                key: "block_store".to_string(),
+                value: self.block_store.to_string(),
+            }),
+        }
+    }
+
+    /// Пустая цепь сети из `genesis` с параметрами сложности, mempool и
+    /// выбора транзакций из конфигурации.
+    fn empty_chain(&self) -> Result<Blockchain, ConfigError> {
+        let genesis = match &self.genesis {
+            Some(path) => GenesisConfig::load(path)?,
+            None if self.regtest => GenesisConfig::regtest(),
+            None => GenesisConfig::default(),
+        };
+
+        let mut chain = Blockchain::new(&genesis);
+        chain.target_block_time = self.target_block_time;
+        chain.adjustment_interval = self.adjustment_interval;
+        chain.mempool.max_size = self.mempool_size;
+        chain.mempool.ttl = self.mempool_ttl;
+        chain.set_tx_selection(self.tx_selection.selection());
+        Ok(chain)
+    }
+}
+
+impl Node {
+    /// Узел по конфигурации: проверяет её, создаёт `data_dir` и загружает
+    /// цепь из `chain_file` (или начинает новую с genesis); туда же её
+    /// сохраняет `Node::shutdown`. Если рядом лежит подходящий снимок
+    /// состояния, проигрываются только блоки после него; обрезанная цепь
+    /// восстанавливается из `archive_dir`, а с `block_store = "sled"` цепь
+    /// берётся из базы, если в ней уже есть блоки. Ожидающие
+    /// транзакции из `mempool_file` заново проверяются и возвращаются
+    /// в mempool. Серверы и
+    /// майнер запускает вызывающий код — порты и адрес майнера берутся
+    /// из той же конфигурации.
+    pub fn from_config(config: &Config) -> Result<Node, ConfigError> {
+        config.validate()?;
+        fs::create_dir_all(&config.data_dir)?;
+
+        let empty = config.empty_chain()?;
+        let chain_file = config.chain_file();
+        let archive = if config.prune_blocks > 0 || config.archive_dir().exists() {
+            Some(BlockArchive::open(config.archive_dir())?)
+        } else {
+            None
+        };
+        let store = config.open_block_store()?;
+        let stored = match &store {
+            Some(store) => store.tip()?.is_some(),
+            None => false,
+        };
+        let mut blockchain = if stored || chain_file.exists() {
+            let genesis = empty.chain[0].clone();
+            let (path, loaded) = match (&store, &archive) {
+                (Some(store), _) if stored => {
+                    let path = config.block_store_dir();
+                    let loaded = empty
+                        .restore_from_store(Arc::clone(store))
+                        .map_err(|e| ConfigError::ChainFile(path.clone(), e))?;
+                    (path, loaded)
+                }
+                (_, Some(archive)) if archive.has_state() => {
+                    let loaded = empty
+                        .restore_pruned(&chain_file, archive.clone())
+                        .map_err(|e| ConfigError::ChainFile(chain_file.clone(), e))?;
+                    (chain_file.clone(), loaded)
+                }
+                _ => {
+                    let loaded =
+                        match restore_with_snapshot(&empty, &chain_file, &config.snapshot_file()) {
+                            Some(loaded) => loaded,
+                            None => empty
+                                .restore_from_disk(&chain_file)
+                                .map_err(|e| ConfigError::ChainFile(chain_file.clone(), e))?,
+                        };
+                    (chain_file.clone(), loaded)
+                }
+            };
+            if loaded.chain[0] != genesis {
+                return Err(ConfigError::ForeignChain {
+                    path,
+                    chain_id: loaded.chain_id().to_string(),
+                });
+            }
+            loaded
+        } else {
+            empty
+        };
+        if let Some(archive) = archive {
+            blockchain.set_archive(archive);
+        }
+        if let Some(store) = store {
+            blockchain
+                .set_block_store(store)
+                .map_err(|e| ConfigError::ChainFile(config.block_store_dir(), e))?;
+        }
+
+        let mempool_file = config.mempool_file();
+        if mempool_file.exists() {
+            match MemPool::load_from_disk(&mempool_file) {
+                Ok(transactions) => {
+                    let stored = transactions.len();
+                    let restored = blockchain.restore_mempool(transactions);
+                    info!(stored, restored, "mempool restored");
+                }
+                Err(e) => {
+                    warn!(path = %mempool_file.display(), error = %e, "ignoring stored mempool")
+                }
+            }
+        }
+
+        let mut node = Node::new(config.node_id.clone());
+        node.max_peers = config.max_peers;
+        node.connections.lock().unwrap().target_outbound = config.target_outbound;
+        node.bans.lock().unwrap().ban_duration = config.ban_duration;
+        if config.encrypted_transport {
+            node.transport = Transport::Encrypted(Arc::new(config.node_key()?));
+        }
+        node.chain_file = Some(chain_file);
+        node.snapshot_file = Some(config.snapshot_file());
+        node.snapshot_interval = config.snapshot_interval;
+        node.prune_blocks = config.prune_blocks;
+        node.mempool_file = Some(mempool_file);
+        *node.blockchain.blocking_write() = blockchain;
+        Ok(node)
+    }
+}
+
+/// Цепь из `chain_file` со снимком состояния; `None`, если снимка нет
+/// или он не подходит к цепи, — тогда цепь проигрывается целиком.
+fn restore_with_snapshot(
+    empty: &Blockchain,
+    chain_file: &Path,
+    snapshot_file: &Path,
+) -> Option<Blockchain> {
+    if !snapshot_file.exists() {
+        return None;
+    }
+    let restored = StateSnapshot::load(snapshot_file)
+        .and_then(|snapshot| empty.clone().restore_from_snapshot(chain_file, &snapshot));
+    match restored {
+        Ok(loaded) => Some(loaded),
+        Err(e) => {
+            warn!(path = %snapshot_file.display(), error = %e, "ignoring state snapshot");
+            None
+        }
+    }
+}
+
+/// Разбирает значение переменной `BLOCKCHAIN_<name>`.
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, ConfigError> {
+    value.trim().parse().map_err(|_| ConfigError::InvalidValue {
+        key: format!("{}{}", ENV_PREFIX, name),
+        value: value.to_string(),
+    })
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
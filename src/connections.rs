@@ -0,0 +1,179 @@
+use crate::network::Node;
+use std::collections::HashMap;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+// ========== CONNECTION MANAGER ==============
+// `connect_to_peer` подключается один раз: если пир потом пропал (его
+// удалил опрос PING, он забанен или просто не ответил при старте), узел
+// к нему больше не возвращается. Менеджер соединений помнит все адреса,
+// к которым узел подключался или о которых узнал от seed-узлов, и раз в
+// `CONNECT_INTERVAL` секунд добирает исходящие соединения до
+// `target_outbound`. Адрес, к которому подключиться не удалось, ждёт
+// следующей попытки всё дольше: `base_backoff`, вдвое больше и так далее
+// до `max_backoff`. Из готовых к попытке адресов первыми идут те, что
+// реже отказывали и быстрее отвечали на PING (см. `health`).
+
+/// Сколько исходящих соединений узел поддерживает по умолчанию.
+pub const DEFAULT_TARGET_OUTBOUND: usize = 4;
+
+/// Как часто узел добирает исходящие соединения, в секундах.
+pub const CONNECT_INTERVAL: u64 = 10;
+
+pub const DEFAULT_BASE_BACKOFF: Duration = Duration::from_secs(1);
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+/// Что узел знает об адресе пира.
+#[derive(Debug, Clone, Default)]
+struct KnownAddr {
+    /// Неудачных попыток подряд.
+    failures: u32,
+    /// Раньше этого момента адрес не набирается.
+    retry_at: Option<Instant>,
+    /// Последнее соединение с адресом открыл узел.
+    outbound: bool,
+    /// Время ответа на PING, пока пир был подключён.
+    rtt_ms: Option<f64>,
+}
+
+/// Адресная книга пиров и цель по исходящим соединениям.
+#[derive(Debug, Clone)]
+pub struct ConnectionManager {
+    pub target_outbound: usize,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    addrs: HashMap<String, KnownAddr>,
+}
+
+impl Default for ConnectionManager {
+    fn default() -> Self {
+        ConnectionManager {
+            target_outbound: DEFAULT_TARGET_OUTBOUND,
+            base_backoff: DEFAULT_BASE_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            addrs: HashMap::new(),
+        }
+    }
+}
+
+impl ConnectionManager {
+    /// Запоминает адрес; `false`, если он уже известен.
+    pub fn add(&mut self, addr: &str) -> bool {
+        if self.addrs.contains_key(addr) {
+            return false;
+        }
+        self.addrs.insert(addr.to_string(), KnownAddr::default());
+        true
+    }
+
+    pub fn is_known(&self, addr: &str) -> bool {
+        self.addrs.contains_key(addr)
+    }
+
+    /// Неудачных попыток подключиться к `addr` подряд.
+    pub fn failures(&self, addr: &str) -> u32 {
+        self.addrs.get(addr).map_or(0, |known| known.failures)
+    }
+
+    /// Сколько ждать после `failures` неудач подряд.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        if failures == 0 {
+            return Duration::ZERO;
+        }
+        let factor = 1u32.checked_shl(failures - 1).unwrap_or(u32::MAX);
+        self.base_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    /// Итог попытки подключиться к `addr`.
+    pub(crate) fn record_attempt(&mut self, addr: &str, connected: bool, now: Instant) {
+        let backoff = self.backoff(self.failures(addr) + 1);
+        let known = self.addrs.entry(addr.to_string()).or_default();
+        if connected {
+            known.failures = 0;
+            known.retry_at = None;
+            known.outbound = true;
+        } else {
+            known.failures += 1;
+            known.retry_at = Some(now + backoff);
+            known.outbound = false;
+        }
+    }
+
+    /// Адреса, к которым можно подключиться в момент `now`, от лучших к
+    /// худшим: меньше неудач, затем меньше время ответа.
+    fn candidates(&self, connected: &[String], now: Instant) -> Vec<String> {
+        let mut ready: Vec<(&String, &KnownAddr)> = self
+            .addrs
+            .iter()
+            .filter(|(addr, known)| {
+                !connected.contains(addr) && known.retry_at.is_none_or(|at| at <= now)
+            })
+            .collect();
+        ready.sort_by(|(a_addr, a), (b_addr, b)| {
+            let a_rtt = a.rtt_ms.unwrap_or(f64::INFINITY);
+            let b_rtt = b.rtt_ms.unwrap_or(f64::INFINITY);
+            a.failures
+                .cmp(&b.failures)
+                .then(a_rtt.total_cmp(&b_rtt))
+                .then(a_addr.cmp(b_addr))
+        });
+        ready.into_iter().map(|(addr, _)| addr.clone()).collect()
+    }
+}
+
+impl Node {
+    /// Добирает исходящие соединения до `target_outbound` из адресной
+    /// книги, начиная с лучших адресов. Возвращает число новых соединений.
+    pub fn maintain_connections(&self) -> usize {
+        let connected = self.peer_addrs();
+        let (mut missing, candidates) = {
+            let mut connections = self.connections.lock().unwrap();
+            for stats in self.peer_stats() {
+                if let Some(known) = connections.addrs.get_mut(&stats.addr) {
+                    known.rtt_ms = stats.rtt_ms.or(known.rtt_ms);
+                }
+            }
+            let outbound = connections
+                .addrs
+                .iter()
+                .filter(|(addr, known)| known.outbound && connected.contains(addr))
+                .count();
+            let missing = connections.target_outbound.saturating_sub(outbound);
+            (missing, connections.candidates(&connected, Instant::now()))
+        };
+
+        let mut opened = 0;
+        for candidate in candidates {
+            if missing == 0 || self.peers.lock().unwrap().len() >= self.max_peers {
+                break;
+            }
+            if self.is_own_addr(&candidate) {
+                continue;
+            }
+            if self.connect_to_peer(&candidate) {
+                missing -= 1;
+                opened += 1;
+            } else {
+                debug!(
+                    peer = %candidate,
+                    failures = self.connections.lock().unwrap().failures(&candidate),
+                    "reconnect failed"
+                );
+            }
+        }
+        if opened > 0 {
+            info!(opened, "outbound connections restored");
+        }
+        opened
+    }
+
+    /// Запускает менеджер соединений раз в `interval` до `shutdown`.
+    pub fn start_connection_manager(&self, interval: Duration) -> JoinHandle<()> {
+        self.every("connections", interval, |node| {
+            node.maintain_connections();
+        })
+    }
+}
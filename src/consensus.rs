@@ -0,0 +1,330 @@
+use crate::amount::Amount;
+use crate::block::{Block, Transaction, Wallet};
+use crate::error::ConsensusError;
+use crate::genesis::DEFAULT_CHAIN_ID;
+use crate::mining::{MiningOptions, MiningStats};
+use crate::signer::Signer;
+use crate::types::{Address, Hash256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+// ========== CONSENSUS ==============
+// Правило, по которому узлы соглашаются, кто и как может добавить
+// следующий блок. `Blockchain` хранит реализацию за `Arc<dyn Consensus>`,
+// по умолчанию — Proof of Work.
+
+/// Адрес-получатель для транзакций стейкинга: монеты, отправленные на
+/// него, блокируются и дают отправителю вес валидатора в PoS.
+pub const STAKE_ADDRESS: &str = "STAKE";
+
+pub trait Consensus: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Проверяет доказательство права на блок `block`; `chain` — все
+    /// блоки до него. Связность, merkle-корень и хеш заголовка
+    /// проверяются отдельно, в `Block::is_valid_successor`.
+    fn validate_block(&self, block: &Block, chain: &[Block]) -> Result<(), ConsensusError>;
+
+    /// Собирает блок поверх `chain` со временем `timestamp` (его выбирает
+    /// цепь по своим часам, см. `Block::next_timestamp_at`). `None` —
+    /// если блок отменён через `cancel` или этот узел сейчас не вправе
+    /// его производить. Вместе
+    /// с блоком возвращается статистика перебора (пустая, если перебора нет).
+    /// Если правилу не нужна вся история (`needs_full_history`), в `chain`
+    /// может быть передана только вершина цепи.
+    fn produce_block(
+        &self,
+        chain: &[Block],
+        transactions: Vec<Transaction>,
+        bits: u32,
+        timestamp: u64,
+        options: &MiningOptions,
+        cancel: &AtomicBool,
+    ) -> Option<(Block, MiningStats)>;
+
+    /// Правилу нужны транзакции всех блоков цепи (например, ставки), так
+    /// что цепь с ним нельзя обрезать, см. `pruning`.
+    fn needs_full_history(&self) -> bool {
+        false
+    }
+}
+
+// ========== PROOF OF WORK ==============
+/// Перебор nonce до тех пор, пока хеш заголовка не опустится до цели.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProofOfWork;
+
+impl Consensus for ProofOfWork {
+    fn name(&self) -> &'static str {
+        "proof-of-work"
+    }
+
+    fn validate_block(&self, block: &Block, _chain: &[Block]) -> Result<(), ConsensusError> {
+        if Block::meets_target(&block.hash, block.header.bits) {
+            Ok(())
+        } else {
+            Err(ConsensusError::InsufficientWork)
+        }
+    }
+
+    fn produce_block(
+        &self,
+        chain: &[Block],
+        transactions: Vec<Transaction>,
+        bits: u32,
+        timestamp: u64,
+        options: &MiningOptions,
+        cancel: &AtomicBool,
+    ) -> Option<(Block, MiningStats)> {
+        let tip = chain.last()?;
+        let (block, stats) = Block::template(
+            tip.header.hash_algorithm,
+            tip.header.index + 1,
+            timestamp,
+            transactions,
+            tip.hash,
+            bits,
+        )
+        .solve(options, cancel);
+        Some((block?, stats))
+    }
+}
+
+// ========== PROOF OF STAKE ==============
+/// Производитель блока выбирается псевдослучайно с весом по стейку;
+/// вместо перебора nonce блок подписывается его ключом. Пока в цепи
+/// нет ни одного стейка, блок может подписать любой ключ — иначе
+/// монеты для первого стейка было бы негде взять.
+#[derive(Clone, Default)]
+pub struct ProofOfStake {
+    validator: Option<Arc<dyn Signer>>,
+}
+
+impl ProofOfStake {
+    /// Только проверка блоков, без права их производить.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Узел-валидатор: подписывает блоки ключом `signer` (обычно
+    /// `Wallet`), когда выбран.
+    pub fn with_validator<S: Signer + 'static>(signer: S) -> Self {
+        ProofOfStake {
+            validator: Some(Arc::new(signer)),
+        }
+    }
+
+    pub fn validator_address(&self) -> Option<Address> {
+        self.validator.as_ref().map(|signer| signer.address())
+    }
+}
+
+/// Суммарный стейк каждого адреса по подтверждённым транзакциям на
+/// `STAKE_ADDRESS`.
+pub fn stakes(chain: &[Block]) -> BTreeMap<Address, Amount> {
+    let mut stakes = BTreeMap::new();
+    for tx in chain.iter().flat_map(|block| &block.transactions) {
+        if tx.to.as_str() == STAKE_ADDRESS {
+            let stake: &mut Amount = stakes.entry(tx.from.clone()).or_default();
+            *stake = stake.saturating_add(tx.amount);
+        }
+    }
+    stakes
+}
+
+/// Выбирает валидатора с вероятностью, пропорциональной стейку. Случайность
+/// берётся из `seed` (хеша предыдущего блока), поэтому все узлы получают
+/// один и тот же результат. Считается в целых единицах без округлений.
+pub fn select_validator(stakes: &BTreeMap<Address, Amount>, seed: &Hash256) -> Option<Address> {
+    let total: u128 = stakes.values().map(|stake| stake.units() as u128).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&seed.as_bytes()[..8]);
+    // Точка в `0..total`, пропорциональная префиксу seed
+    let point = (u64::from_be_bytes(prefix) as u128 * total) >> 64;
+
+    let mut cumulative = 0u128;
+    let mut selected = None;
+    for (address, stake) in stakes.iter().filter(|(_, stake)| !stake.is_zero()) {
+        cumulative += stake.units() as u128;
+        selected = Some(address);
+        if point < cumulative {
+            break;
+        }
+    }
+    selected.cloned()
+}
+
+/// Валидатор для блока поверх `chain`; `None`, пока стейков нет.
+pub fn expected_validator(chain: &[Block]) -> Option<Address> {
+    let tip = chain.last()?;
+    select_validator(&stakes(chain), &tip.hash)
+}
+
+impl Consensus for ProofOfStake {
+    fn name(&self) -> &'static str {
+        "proof-of-stake"
+    }
+
+    fn validate_block(&self, block: &Block, chain: &[Block]) -> Result<(), ConsensusError> {
+        let producer = block.verify_producer()?;
+        match expected_validator(chain) {
+            Some(expected) if producer != expected => Err(ConsensusError::UnexpectedProducer {
+                expected,
+                actual: producer,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn produce_block(
+        &self,
+        chain: &[Block],
+        transactions: Vec<Transaction>,
+        bits: u32,
+        timestamp: u64,
+        _options: &MiningOptions,
+        _cancel: &AtomicBool,
+    ) -> Option<(Block, MiningStats)> {
+        let signer = self.validator.as_ref()?;
+        let tip = chain.last()?;
+        if let Some(expected) = expected_validator(chain) {
+            if expected != signer.address() {
+                return None;
+            }
+        }
+
+        let mut block = Block::template(
+            tip.header.hash_algorithm,
+            tip.header.index + 1,
+            timestamp,
+            transactions,
+            tip.hash,
+            bits,
+        );
+        block.hash = block.header.compute_hash();
+        block.sign(signer.as_ref());
+        Some((block, MiningStats::default()))
+    }
+
+    /// Ставки считаются по всем транзакциям на `STAKE_ADDRESS`.
+    fn needs_full_history(&self) -> bool {
+        true
+    }
+}
+
+// ========== SIGNED BLOCKS ==============
+/// Обёртка над другим правилом: подписывает произведённые блоки ключом
+/// узла (см. `Block::sign`), а в закрытой сети ещё и принимает только
+/// блоки, подписанные ключами из списка производителей.
+#[derive(Clone)]
+pub struct SignedBlocks {
+    inner: Arc<dyn Consensus>,
+    /// Открытые ключи (hex) допущенных производителей; `None` — сеть
+    /// открыта и подпись необязательна.
+    producers: Option<BTreeSet<String>>,
+    signer: Option<Arc<dyn Signer>>,
+}
+
+impl SignedBlocks {
+    /// Открытая сеть: проверки `inner` без дополнительных требований.
+    pub fn open(inner: Arc<dyn Consensus>) -> Self {
+        SignedBlocks {
+            inner,
+            producers: None,
+            signer: None,
+        }
+    }
+
+    /// Закрытая сеть: каждый блок должен быть подписан ключом из `producers`.
+    pub fn permissioned<I>(inner: Arc<dyn Consensus>, producers: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        SignedBlocks {
+            inner,
+            producers: Some(producers.into_iter().collect()),
+            signer: None,
+        }
+    }
+
+    /// Подписывать блоки, которые производит этот узел, ключом `signer`.
+    pub fn with_signer<S: Signer + 'static>(mut self, signer: S) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    pub fn is_permissioned(&self) -> bool {
+        self.producers.is_some()
+    }
+}
+
+impl Consensus for SignedBlocks {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn validate_block(&self, block: &Block, chain: &[Block]) -> Result<(), ConsensusError> {
+        self.inner.validate_block(block, chain)?;
+        if let Some(producers) = &self.producers {
+            block.verify_producer()?;
+            if !producers.contains(&block.producer_key) {
+                return Err(ConsensusError::UnauthorizedProducer(
+                    block.producer_key.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn produce_block(
+        &self,
+        chain: &[Block],
+        transactions: Vec<Transaction>,
+        bits: u32,
+        timestamp: u64,
+        options: &MiningOptions,
+        cancel: &AtomicBool,
+    ) -> Option<(Block, MiningStats)> {
+        let (mut block, stats) =
+            self.inner
+                .produce_block(chain, transactions, bits, timestamp, options, cancel)?;
+        if let Some(signer) = &self.signer {
+            block.sign(signer.as_ref());
+        }
+        Some((block, stats))
+    }
+
+    fn needs_full_history(&self) -> bool {
+        self.inner.needs_full_history()
+    }
+}
+
+impl Wallet {
+    /// Транзакция стейкинга: блокирует `amount` монет на `STAKE_ADDRESS`.
+    /// Транзакция подписывается для сети по умолчанию.
+    pub fn create_stake_transaction(&self, amount: Amount, fee: Amount, nonce: u64) -> Transaction {
+        self.create_stake_transaction_for_chain(DEFAULT_CHAIN_ID, amount, fee, nonce)
+    }
+
+    /// То же, что `create_stake_transaction`, но для сети `chain_id`.
+    pub fn create_stake_transaction_for_chain(
+        &self,
+        chain_id: &str,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        self.create_transaction_for_chain(
+            chain_id,
+            &Address::from(STAKE_ADDRESS),
+            amount,
+            fee,
+            nonce,
+        )
+    }
+}
@@ -0,0 +1,132 @@
+use crate::amount::Amount;
+use crate::block::{Transaction, Wallet};
+use crate::error::ContactsError;
+use crate::types::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// ========== CONTACTS ==============
+// Адресная книга: человекочитаемые имена для адресов. Хранится открыто
+// в JSON вида {"bob": "1BoB…"}; адреса проверяются и при добавлении,
+// и при загрузке, так что опечатка в файле не уведёт перевод.
+
+/// Имя файла адресной книги по умолчанию.
+pub const CONTACTS_FILE_NAME: &str = "contacts.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Contacts {
+    entries: BTreeMap<String, Address>,
+}
+
+impl Contacts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Читает адресную книгу и проверяет каждую запись.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Contacts, ContactsError> {
+        let json = fs::read_to_string(path)?;
+        let contacts: Contacts =
+            serde_json::from_str(&json).map_err(|e| ContactsError::Malformed(e.to_string()))?;
+        for (name, address) in &contacts.entries {
+            check_entry(name, address)?;
+        }
+        Ok(contacts)
+    }
+
+    /// Как `load`, но отсутствующий файл — пустая книга.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Contacts, ContactsError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Contacts::new());
+        }
+        Contacts::load(path)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ContactsError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| ContactsError::Malformed(e.to_string()))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Добавляет контакт. Занятое имя не перезаписывается.
+    pub fn add(&mut self, name: &str, address: Address) -> Result<(), ContactsError> {
+        check_entry(name, &address)?;
+        if self.entries.contains_key(name) {
+            return Err(ContactsError::Exists(name.to_string()));
+        }
+        self.entries.insert(name.to_string(), address);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Address> {
+        self.entries.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Address> {
+        self.entries.get(name)
+    }
+
+    /// Адрес контакта `name` или `ContactsError::Unknown`.
+    pub fn resolve(&self, name: &str) -> Result<&Address, ContactsError> {
+        self.get(name)
+            .ok_or_else(|| ContactsError::Unknown(name.to_string()))
+    }
+
+    /// Имя, под которым записан `address`, если он есть в книге.
+    pub fn name_of(&self, address: &Address) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(_, a)| *a == address)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Контакты по алфавиту.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Address)> {
+        self.entries
+            .iter()
+            .map(|(name, address)| (name.as_str(), address))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Имя не должно само быть адресом: иначе `tx send --to` не отличит
+/// одно от другого.
+fn check_entry(name: &str, address: &Address) -> Result<(), ContactsError> {
+    if name.is_empty() || name.trim() != name || Address::from(name).validate().is_ok() {
+        return Err(ContactsError::InvalidName(name.to_string()));
+    }
+    address
+        .validate()
+        .map_err(|reason| ContactsError::InvalidAddress {
+            address: address.clone(),
+            reason,
+        })
+}
+
+impl Wallet {
+    /// Перевод контакту `name` из адресной книги; `nonce` — очередной
+    /// номер отправителя, см. `Blockchain::next_nonce`.
+    pub fn send_to_contact(
+        &self,
+        contacts: &Contacts,
+        name: &str,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+    ) -> Result<Transaction, ContactsError> {
+        let to = contacts.resolve(name)?;
+        Ok(self.create_transaction(to, amount, fee, nonce))
+    }
+}
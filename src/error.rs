@@ -0,0 +1,1249 @@
+use crate::amount::{Amount, DECIMALS};
+use crate::types::{Address, Hash256, ParseError};
+use crate::utxo::OutPoint;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// ========== TRANSACTION ERRORS ==============
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxValidationError {
+    NonPositiveAmount,
+    /// Сумма вместе с комиссией не помещается в `Amount`.
+    AmountOverflow,
+    MissingAddress,
+    InvalidAddress(ParseError),
+    SelfTransfer,
+    MissingSignature,
+    InvalidSignature,
+    PublicKeyMismatch,
+    /// Порог вне 1..=N, повторы или негодные ключи в политике M-из-N.
+    InvalidMultisigPolicy {
+        threshold: usize,
+        keys: usize,
+    },
+    NotEnoughSignatures {
+        required: usize,
+        provided: usize,
+    },
+    /// Поле `data` длиннее `MAX_TX_DATA_SIZE`.
+    DataTooLarge {
+        size: usize,
+        max: usize,
+    },
+    /// Комиссия не покрывает плату за `data`.
+    FeeTooLow {
+        required: Amount,
+        provided: Amount,
+    },
+    InvalidTokenSymbol(String),
+    /// Скрипт адреса отправителя не принял `script_sig`.
+    Script(ScriptError),
+}
+
+impl fmt::Display for TxValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxValidationError::NonPositiveAmount => write!(f, "amount must be positive"),
+            TxValidationError::AmountOverflow => write!(f, "amount plus fee overflows"),
+            TxValidationError::MissingAddress => write!(f, "sender or recipient is empty"),
+            TxValidationError::InvalidAddress(e) => write!(f, "invalid address: {}", e),
+            TxValidationError::SelfTransfer => write!(f, "sender and recipient are the same"),
+            TxValidationError::MissingSignature => write!(f, "signature or public key is missing"),
+            TxValidationError::InvalidSignature => write!(f, "signature verification failed"),
+            TxValidationError::PublicKeyMismatch => {
+                write!(f, "public key does not belong to the sender")
+            }
+            TxValidationError::InvalidMultisigPolicy { threshold, keys } => {
+                write!(f, "invalid {}-of-{} multisig policy", threshold, keys)
+            }
+            TxValidationError::NotEnoughSignatures { required, provided } => write!(
+                f,
+                "{} valid signatures provided, {} required",
+                provided, required
+            ),
+            TxValidationError::DataTooLarge { size, max } => {
+                write!(f, "data is {} bytes, at most {} allowed", size, max)
+            }
+            TxValidationError::FeeTooLow { required, provided } => {
+                write!(f, "fee {} is below the required {}", provided, required)
+            }
+            TxValidationError::InvalidTokenSymbol(symbol) => {
+                write!(
+                    f,
+                    "token symbol {:?} is not 1-10 uppercase letters or digits",
+                    symbol
+                )
+            }
+            TxValidationError::Script(e) => write!(f, "script failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TxValidationError {}
+
+// ========== AMOUNT ERRORS ==============
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AmountError {
+    Invalid(String),
+    /// Больше знаков после точки, чем `amount::DECIMALS`.
+    TooPrecise(String),
+    Overflow(String),
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountError::Invalid(s) => write!(f, "invalid amount {:?}", s),
+            AmountError::TooPrecise(s) => {
+                write!(f, "amount {} has more than {} decimal places", s, DECIMALS)
+            }
+            AmountError::Overflow(s) => write!(f, "amount {} is too large", s),
+        }
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+// ========== TOKEN ERRORS ==============
+/// Операция с токеном невыполнима в текущем состоянии цепи.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    UnknownToken(Hash256),
+    NotIssuer {
+        token: Hash256,
+        address: Address,
+    },
+    InsufficientTokens {
+        token: Hash256,
+        available: u64,
+        required: u64,
+    },
+    SupplyOverflow(Hash256),
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::UnknownToken(token) => write!(f, "token {} does not exist", token),
+            TokenError::NotIssuer { token, address } => {
+                write!(f, "{} is not the issuer of token {}", address, token)
+            }
+            TokenError::InsufficientTokens {
+                token,
+                available,
+                required,
+            } => write!(
+                f,
+                "insufficient balance of token {}: available {}, required {}",
+                token, available, required
+            ),
+            TokenError::SupplyOverflow(token) => write!(f, "supply of token {} overflows", token),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+// ========== SCRIPT ERRORS ==============
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    UnknownOpcode(String),
+    /// P2PKH строится только для обычного адреса.
+    NotAKeyAddress,
+    InvalidPublicKey,
+    /// Отпирающий скрипт содержит что-то кроме данных.
+    NonPushUnlock,
+    ScriptTooLong,
+    PushTooLarge,
+    StackUnderflow,
+    StackOverflow,
+    OutOfGas,
+    InvalidNumber,
+    InvalidMultisigCount,
+    VerifyFailed,
+    OpReturn,
+    LockTimeNotReached {
+        lock: u64,
+        height: u64,
+    },
+    /// Скрипт завершился, но на вершине стека не истина.
+    FalseResult,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::UnknownOpcode(op) => write!(f, "unknown opcode {:?}", op),
+            ScriptError::NotAKeyAddress => write!(f, "address is not a public key hash"),
+            ScriptError::InvalidPublicKey => write!(f, "public key is not valid hex"),
+            ScriptError::NonPushUnlock => write!(f, "unlocking script must only push data"),
+            ScriptError::ScriptTooLong => write!(f, "script has too many operations"),
+            ScriptError::PushTooLarge => write!(f, "pushed data is too large"),
+            ScriptError::StackUnderflow => write!(f, "stack underflow"),
+            ScriptError::StackOverflow => write!(f, "stack is too deep"),
+            ScriptError::OutOfGas => write!(f, "script ran out of gas"),
+            ScriptError::InvalidNumber => write!(f, "number is longer than 8 bytes"),
+            ScriptError::InvalidMultisigCount => {
+                write!(f, "invalid multisig key or signature count")
+            }
+            ScriptError::VerifyFailed => write!(f, "verify failed"),
+            ScriptError::OpReturn => write!(f, "OP_RETURN output is unspendable"),
+            ScriptError::LockTimeNotReached { lock, height } => {
+                write!(f, "output is locked until height {}, now {}", lock, height)
+            }
+            ScriptError::FalseResult => write!(f, "script finished with false on the stack"),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+// ========== BLOCK STORE ERRORS ==============
+/// Ошибка записи в `BlockStore`, из-за которой цепь не приняла блок.
+/// `io::Error` не клонируется и не сравнивается, поэтому лежит в `Arc`,
+/// а ошибки равны, если совпадают вид и текст.
+#[derive(Debug, Clone)]
+pub struct StoreError(pub Arc<io::Error>);
+
+impl PartialEq for StoreError {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.kind() == other.0.kind() && self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block store write failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for StoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.0.as_ref())
+    }
+}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> Self {
+        StoreError(Arc::new(e))
+    }
+}
+
+// ========== CHAIN ERRORS ==============
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainError {
+    EmptyChain,
+    GenesisMismatch,
+    /// Суммарная работа цепи-кандидата не больше текущей.
+    NotHeavier {
+        current: u128,
+        candidate: u128,
+    },
+    /// Цепь-кандидат ответвляется ниже границы обрезки: тел блоков под
+    /// ней у узла нет, и проверить ветку он не может.
+    BelowPruned {
+        fork_height: u32,
+        pruned_height: u32,
+    },
+    /// Первый блок цепи, не прошедший проверку, и причина.
+    InvalidBlock {
+        index: u32,
+        reason: ValidationError,
+    },
+    /// Ветка прошла проверку, но не записалась в хранилище блоков;
+    /// цепь осталась прежней.
+    Storage(StoreError),
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::EmptyChain => write!(f, "candidate chain is empty"),
+            ChainError::GenesisMismatch => write!(f, "genesis block mismatch"),
+            ChainError::NotHeavier { current, candidate } => write!(
+                f,
+                "candidate chain work {} does not exceed current {}",
+                candidate, current
+            ),
+            ChainError::BelowPruned {
+                fork_height,
+                pruned_height,
+            } => write!(
+                f,
+                "candidate forks at height {} below pruned height {}",
+                fork_height, pruned_height
+            ),
+            ChainError::InvalidBlock { index, reason } => {
+                write!(f, "block {} is invalid: {}", index, reason)
+            }
+            ChainError::Storage(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ChainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChainError::InvalidBlock { reason, .. } => Some(reason),
+            ChainError::Storage(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// ========== BLOCKCHAIN ERRORS ==============
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockchainError {
+    InvalidTransaction(TxValidationError),
+    DuplicateTransaction(Hash256),
+    MempoolFull { min_fee: Amount },
+    NonceReused { address: Address, nonce: u64 },
+    /// Замена ожидающей транзакции должна платить большую комиссию.
+    ReplacementUnderpriced { replaced: Hash256, min_fee: Amount },
+    InsufficientFunds { available: Amount, required: Amount },
+    InvalidBlock { index: u32, reason: ValidationError },
+    InvalidTimestamp { index: u32, timestamp: u64 },
+    UnexpectedDifficulty { expected: u32, actual: u32 },
+    Consensus(ConsensusError),
+    WrongChain { expected: String, actual: String },
+    Token(TokenError),
+    /// Блок прошёл проверку, но не записался в хранилище блоков.
+    Storage(StoreError),
+    /// Цель задаётся вручную только в regtest.
+    RegtestOnly,
+}
+
+impl fmt::Display for BlockchainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockchainError::InvalidTransaction(e) => write!(f, "invalid transaction: {}", e),
+            BlockchainError::DuplicateTransaction(txid) => {
+                write!(f, "transaction {} is already known", txid)
+            }
+            BlockchainError::MempoolFull { min_fee } => {
+                write!(f, "mempool is full, fee must exceed {}", min_fee)
+            }
+            BlockchainError::NonceReused { address, nonce } => {
+                write!(f, "nonce {} of {} was already used", nonce, address)
+            }
+            BlockchainError::ReplacementUnderpriced { replaced, min_fee } => write!(
+                f,
+                "replacement for pending transaction {} must pay a fee above {}",
+                replaced, min_fee
+            ),
+            BlockchainError::InsufficientFunds {
+                available,
+                required,
+            } => write!(
+                f,
+                "insufficient funds: available {}, required {}",
+                available, required
+            ),
+            BlockchainError::InvalidBlock { index, reason } => {
+                write!(f, "block {} is invalid: {}", index, reason)
+            }
+            BlockchainError::InvalidTimestamp { index, timestamp } => write!(
+                f,
+                "block {} timestamp {} is before median time past or too far in the future",
+                index, timestamp
+            ),
+            BlockchainError::UnexpectedDifficulty { expected, actual } => write!(
+                f,
+                "block target bits {:#010x} do not match expected {:#010x}",
+                actual, expected
+            ),
+            BlockchainError::Consensus(e) => write!(f, "consensus rejected block: {}", e),
+            BlockchainError::WrongChain { expected, actual } => write!(
+                f,
+                "transaction is signed for chain {:?}, expected {:?}",
+                actual, expected
+            ),
+            BlockchainError::Token(e) => write!(f, "token operation rejected: {}", e),
+            BlockchainError::Storage(e) => write!(f, "{}", e),
+            BlockchainError::RegtestOnly => {
+                write!(f, "the target can only be set manually in regtest")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BlockchainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BlockchainError::InvalidTransaction(e) => Some(e),
+            BlockchainError::InvalidBlock { reason, .. } => Some(reason),
+            BlockchainError::Consensus(e) => Some(e),
+            BlockchainError::Token(e) => Some(e),
+            BlockchainError::Storage(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<TxValidationError> for BlockchainError {
+    fn from(e: TxValidationError) -> Self {
+        BlockchainError::InvalidTransaction(e)
+    }
+}
+
+impl From<TokenError> for BlockchainError {
+    fn from(e: TokenError) -> Self {
+        BlockchainError::Token(e)
+    }
+}
+
+// ========== VALIDATION ERRORS ==============
+/// Причина, по которой блок не может продолжить цепь.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// Не с чем сравнить: у блока нет предка.
+    MissingParent,
+    BadIndex {
+        expected: u32,
+        actual: u32,
+    },
+    BadPrevHash,
+    HashAlgorithmMismatch,
+    /// Больше `MAX_BLOCK_TRANSACTIONS` транзакций или `MAX_BLOCK_SIZE` байт.
+    OverLimits,
+    MisplacedCoinbase,
+    /// Coinbase платит больше награды за блок и комиссий.
+    ExcessiveCoinbase {
+        allowed: Amount,
+        actual: Amount,
+    },
+    InvalidTransaction {
+        txid: Hash256,
+        reason: TxValidationError,
+    },
+    /// Подпись транзакции отсутствует, не сходится с ключом или подписей
+    /// M-из-N меньше порога.
+    InvalidSignature {
+        txid: Hash256,
+    },
+    /// Блокировка транзакции по времени ещё не снята.
+    NonFinalTransaction {
+        txid: Hash256,
+    },
+    BadMerkleRoot,
+    BadHash,
+    /// Хеш блока не достигает его цели.
+    BadPoW,
+    BadProducerSignature(ConsensusError),
+    /// Время не больше медианы последних блоков.
+    TimestampTooOld {
+        timestamp: u64,
+        median: u64,
+    },
+    /// Время опережает часы узла больше чем на `MAX_FUTURE_BLOCK_TIME`.
+    FutureTimestamp {
+        timestamp: u64,
+        max: u64,
+    },
+    Consensus(ConsensusError),
+    UnexpectedDifficulty {
+        expected: u32,
+        actual: u32,
+    },
+    WrongChain {
+        txid: Hash256,
+        expected: String,
+        actual: String,
+    },
+    NonceReused {
+        address: Address,
+        nonce: u64,
+    },
+    InsufficientFunds {
+        address: Address,
+        available: Amount,
+        required: Amount,
+    },
+    /// Суммы блока не помещаются в `Amount`.
+    AmountOverflow,
+    Token(TokenError),
+    /// UTXO-транзакции блока не применимы к множеству выходов.
+    Utxo(UtxoError),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MissingParent => write!(f, "parent block is unknown"),
+            ValidationError::BadIndex { expected, actual } => {
+                write!(
+                    f,
+                    "block index {} does not follow parent, expected {}",
+                    actual, expected
+                )
+            }
+            ValidationError::BadPrevHash => write!(f, "previous hash does not match parent"),
+            ValidationError::HashAlgorithmMismatch => {
+                write!(f, "hash algorithm differs from parent")
+            }
+            ValidationError::OverLimits => write!(f, "block exceeds size limits"),
+            ValidationError::MisplacedCoinbase => {
+                write!(f, "coinbase is not the first transaction")
+            }
+            ValidationError::ExcessiveCoinbase { allowed, actual } => write!(
+                f,
+                "coinbase pays {} while at most {} is allowed",
+                actual, allowed
+            ),
+            ValidationError::InvalidTransaction { txid, reason } => {
+                write!(f, "transaction {} is invalid: {}", txid, reason)
+            }
+            ValidationError::InvalidSignature { txid } => {
+                write!(f, "transaction {} has an invalid signature", txid)
+            }
+            ValidationError::NonFinalTransaction { txid } => {
+                write!(f, "transaction {} is still time-locked", txid)
+            }
+            ValidationError::BadMerkleRoot => {
+                write!(f, "merkle root does not match transactions")
+            }
+            ValidationError::BadHash => write!(f, "block hash does not match header"),
+            ValidationError::BadPoW => write!(f, "block hash does not meet its target"),
+            ValidationError::BadProducerSignature(e) => {
+                write!(f, "producer signature is invalid: {}", e)
+            }
+            ValidationError::TimestampTooOld { timestamp, median } => write!(
+                f,
+                "timestamp {} is not after median time past {}",
+                timestamp, median
+            ),
+            ValidationError::FutureTimestamp { timestamp, max } => {
+                write!(f, "timestamp {} is later than allowed {}", timestamp, max)
+            }
+            ValidationError::Consensus(e) => write!(f, "consensus rejected block: {}", e),
+            ValidationError::UnexpectedDifficulty { expected, actual } => write!(
+                f,
+                "block target bits {:#010x} do not match expected {:#010x}",
+                actual, expected
+            ),
+            ValidationError::WrongChain {
+                txid,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "transaction {} is signed for chain {:?}, expected {:?}",
+                txid, actual, expected
+            ),
+            ValidationError::NonceReused { address, nonce } => {
+                write!(f, "nonce {} of {} was already used", nonce, address)
+            }
+            ValidationError::InsufficientFunds {
+                address,
+                available,
+                required,
+            } => write!(
+                f,
+                "{} spends {} with only {} available",
+                address, required, available
+            ),
+            ValidationError::AmountOverflow => write!(f, "block amounts overflow"),
+            ValidationError::Token(e) => write!(f, "token operation rejected: {}", e),
+            ValidationError::Utxo(e) => write!(f, "utxo transactions rejected: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ValidationError::InvalidTransaction { reason, .. } => Some(reason),
+            ValidationError::BadProducerSignature(e) | ValidationError::Consensus(e) => Some(e),
+            ValidationError::Token(e) => Some(e),
+            ValidationError::Utxo(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// ========== CONSENSUS ERRORS ==============
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsensusError {
+    InsufficientWork,
+    MissingProducerSignature,
+    InvalidProducerKey,
+    InvalidProducerSignature(TxValidationError),
+    UnexpectedProducer { expected: Address, actual: Address },
+    UnauthorizedProducer(String),
+    NotSelected,
+}
+
+impl fmt::Display for ConsensusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsensusError::InsufficientWork => write!(f, "block hash does not meet its target"),
+            ConsensusError::MissingProducerSignature => {
+                write!(f, "block is not signed by its producer")
+            }
+            ConsensusError::InvalidProducerKey => write!(f, "producer key is malformed"),
+            ConsensusError::InvalidProducerSignature(e) => {
+                write!(f, "producer signature is invalid: {}", e)
+            }
+            ConsensusError::UnexpectedProducer { expected, actual } => write!(
+                f,
+                "block produced by {}, but {} was selected",
+                actual, expected
+            ),
+            ConsensusError::UnauthorizedProducer(key) => {
+                write!(f, "producer key {} is not on the producer list", key)
+            }
+            ConsensusError::NotSelected => {
+                write!(f, "this node is not selected to produce the block")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConsensusError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConsensusError::InvalidProducerSignature(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// ========== KEYSTORE ERRORS ==============
+#[derive(Debug)]
+pub enum KeystoreError {
+    Io(std::io::Error),
+    Malformed(String),
+    UnsupportedVersion(u32),
+    WrongPassphrase,
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeystoreError::Io(e) => write!(f, "keystore I/O error: {}", e),
+            KeystoreError::Malformed(reason) => write!(f, "malformed keystore: {}", reason),
+            KeystoreError::UnsupportedVersion(version) => {
+                write!(f, "unsupported keystore version {}", version)
+            }
+            KeystoreError::WrongPassphrase => {
+                write!(f, "wrong passphrase or corrupted keystore")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KeystoreError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(e: std::io::Error) -> Self {
+        KeystoreError::Io(e)
+    }
+}
+
+// ========== GENESIS ERRORS ==============
+#[derive(Debug)]
+pub enum GenesisError {
+    Io(std::io::Error),
+    Malformed(String),
+    InvalidBits(u32),
+    InvalidAllocation(Address),
+    InvalidProducer(String),
+}
+
+impl fmt::Display for GenesisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenesisError::Io(e) => write!(f, "genesis config I/O error: {}", e),
+            GenesisError::Malformed(reason) => write!(f, "malformed genesis config: {}", reason),
+            GenesisError::InvalidBits(bits) => {
+                write!(f, "genesis bits {:#010x} are out of range", bits)
+            }
+            GenesisError::InvalidAllocation(address) => {
+                write!(f, "invalid genesis allocation for {}", address)
+            }
+            GenesisError::InvalidProducer(key) => {
+                write!(f, "invalid genesis producer key {}", key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenesisError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GenesisError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for GenesisError {
+    fn from(e: std::io::Error) -> Self {
+        GenesisError::Io(e)
+    }
+}
+
+// ========== CONFIG ERRORS ==============
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Malformed(String),
+    InvalidValue { key: String, value: String },
+    Genesis(GenesisError),
+    ChainFile(PathBuf, std::io::Error),
+    ForeignChain { path: PathBuf, chain_id: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "config I/O error: {}", e),
+            ConfigError::Malformed(reason) => write!(f, "malformed config: {}", reason),
+            ConfigError::InvalidValue { key, value } => {
+                write!(f, "invalid value {:?} for {}", value, key)
+            }
+            ConfigError::Genesis(e) => write!(f, "{}", e),
+            ConfigError::ChainFile(path, e) => {
+                write!(f, "failed to load {}: {}", path.display(), e)
+            }
+            ConfigError::ForeignChain { path, chain_id } => write!(
+                f,
+                "{} belongs to another network (chain id {})",
+                path.display(),
+                chain_id
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(e) | ConfigError::ChainFile(_, e) => Some(e),
+            ConfigError::Genesis(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<GenesisError> for ConfigError {
+    fn from(e: GenesisError) -> Self {
+        ConfigError::Genesis(e)
+    }
+}
+
+// ========== WALLET ERRORS ==============
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletError {
+    InvalidPrivateKey,
+    InvalidMnemonic(String),
+    InvalidSeed,
+    NotHierarchical,
+    InvalidChildKey(u32),
+    InvalidPath(String),
+    /// Ключа кошелька нет в политике транзакции (или она не M-из-N).
+    NotACosigner,
+    InvalidPublicKey,
+    InvalidAddress(ParseError),
+    /// Перевод не с адреса (или не с ключа) этого кошелька.
+    NotTheSender,
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletError::InvalidPrivateKey => write!(f, "private key is not a valid secp256k1 key"),
+            WalletError::InvalidMnemonic(reason) => write!(f, "invalid mnemonic: {}", reason),
+            WalletError::InvalidSeed => write!(f, "seed does not produce a valid master key"),
+            WalletError::NotHierarchical => write!(f, "wallet has no chain code"),
+            WalletError::InvalidChildKey(index) => {
+                write!(f, "child key {} is invalid, use the next index", index)
+            }
+            WalletError::InvalidPath(path) => write!(f, "invalid derivation path: {}", path),
+            WalletError::NotACosigner => {
+                write!(
+                    f,
+                    "wallet key is not part of the transaction's multisig policy"
+                )
+            }
+            WalletError::InvalidPublicKey => write!(f, "public key is not a valid secp256k1 key"),
+            WalletError::InvalidAddress(e) => write!(f, "invalid address: {}", e),
+            WalletError::NotTheSender => {
+                write!(f, "transaction is not sent from this wallet's address")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WalletError {}
+
+// ========== OFFLINE SIGNING ERRORS ==============
+#[derive(Debug, Clone, PartialEq)]
+pub enum PsbtError {
+    Malformed(String),
+    UnsupportedVersion(u32),
+    /// Подписей меньше, чем нужно, или какая-то из них неверна.
+    Incomplete {
+        signatures: usize,
+        required: usize,
+        reason: TxValidationError,
+    },
+}
+
+impl fmt::Display for PsbtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PsbtError::Malformed(reason) => {
+                write!(f, "malformed unsigned transaction: {}", reason)
+            }
+            PsbtError::UnsupportedVersion(version) => {
+                write!(f, "unsupported unsigned transaction version {}", version)
+            }
+            PsbtError::Incomplete {
+                signatures,
+                required,
+                reason,
+            } => write!(
+                f,
+                "transaction has {} of {} signatures: {}",
+                signatures, required, reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PsbtError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PsbtError::Incomplete { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+// ========== CONTACTS ERRORS ==============
+#[derive(Debug)]
+pub enum ContactsError {
+    Io(std::io::Error),
+    Malformed(String),
+    /// Имя пустое, с пробелами по краям или похоже на адрес.
+    InvalidName(String),
+    InvalidAddress { address: Address, reason: ParseError },
+    /// Контакт с таким именем уже есть; его нужно сначала удалить.
+    Exists(String),
+    Unknown(String),
+}
+
+impl fmt::Display for ContactsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContactsError::Io(e) => write!(f, "contacts I/O error: {}", e),
+            ContactsError::Malformed(reason) => write!(f, "malformed contacts file: {}", reason),
+            ContactsError::InvalidName(name) => write!(f, "invalid contact name {:?}", name),
+            ContactsError::InvalidAddress { address, reason } => {
+                write!(f, "invalid contact address {}: {}", address, reason)
+            }
+            ContactsError::Exists(name) => write!(f, "contact {} already exists", name),
+            ContactsError::Unknown(name) => write!(f, "unknown contact {}", name),
+        }
+    }
+}
+
+impl std::error::Error for ContactsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ContactsError::Io(e) => Some(e),
+            ContactsError::InvalidAddress { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ContactsError {
+    fn from(e: std::io::Error) -> Self {
+        ContactsError::Io(e)
+    }
+}
+
+// ========== SYNC ERRORS ==============
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncError {
+    PeerUnreachable,
+    /// Адрес пира в нашем бан-листе.
+    PeerBanned,
+    UnexpectedReply,
+    Block(BlockchainError),
+    Chain(ChainError),
+    ChainMismatch {
+        ours: String,
+        theirs: String,
+    },
+    GenesisMismatch,
+    VersionMismatch {
+        ours: u32,
+        theirs: u32,
+    },
+    /// Заголовок не продолжает цепь заголовков или не несёт нужной работы.
+    InvalidHeader {
+        index: u32,
+    },
+    TransactionNotFound(Hash256),
+    /// Доказательство включения не сходится с нашим заголовком.
+    InvalidProof(Hash256),
+    /// Тела блоков пачки, начинающейся с `index`, не совпали с заголовками.
+    BodyMismatch {
+        index: u32,
+    },
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::PeerUnreachable => write!(f, "peer did not respond"),
+            SyncError::PeerBanned => write!(f, "peer is banned"),
+            SyncError::UnexpectedReply => write!(f, "peer sent an unexpected reply"),
+            SyncError::Block(e) => write!(f, "downloaded block rejected: {}", e),
+            SyncError::Chain(e) => write!(f, "downloaded chain rejected: {}", e),
+            SyncError::ChainMismatch { ours, theirs } => {
+                write!(f, "peer is on chain {:?}, not {:?}", theirs, ours)
+            }
+            SyncError::GenesisMismatch => write!(f, "peer has a different genesis block"),
+            SyncError::VersionMismatch { ours, theirs } => write!(
+                f,
+                "peer speaks protocol version {}, we speak {}",
+                theirs, ours
+            ),
+            SyncError::InvalidHeader { index } => write!(f, "header {} is invalid", index),
+            SyncError::TransactionNotFound(txid) => {
+                write!(f, "peer does not know transaction {}", txid)
+            }
+            SyncError::InvalidProof(txid) => {
+                write!(f, "inclusion proof for {} does not match our headers", txid)
+            }
+            SyncError::BodyMismatch { index } => {
+                write!(f, "blocks from {} do not match their headers", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SyncError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SyncError::Block(e) => Some(e),
+            SyncError::Chain(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// ========== UTXO ERRORS ==============
+#[derive(Debug, Clone, PartialEq)]
+pub enum UtxoError {
+    MissingInput(OutPoint),
+    DoubleSpend(OutPoint),
+    InvalidInput {
+        index: usize,
+        reason: TxValidationError,
+    },
+    InsufficientInputs {
+        inputs: Amount,
+        outputs: Amount,
+    },
+    /// Сумма входов или выходов не помещается в `Amount`.
+    AmountOverflow,
+    NoOutputs,
+    NonPositiveOutput,
+    UnexpectedCoinbase,
+    /// Coinbase помечена не высотой своего блока.
+    CoinbaseHeight {
+        expected: u32,
+        actual: u32,
+    },
+    /// Coinbase платит больше остатка награды и комиссий блока.
+    ExcessiveCoinbase {
+        allowed: Amount,
+        actual: Amount,
+    },
+    /// Скрипт выхода не принял отпирающий скрипт входа.
+    ScriptFailed {
+        index: usize,
+        reason: ScriptError,
+    },
+}
+
+impl fmt::Display for UtxoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UtxoError::MissingInput(outpoint) => {
+                write!(f, "input {} is missing or already spent", outpoint)
+            }
+            UtxoError::DoubleSpend(outpoint) => write!(f, "input {} is spent twice", outpoint),
+            UtxoError::InvalidInput { index, reason } => {
+                write!(f, "input {} cannot be unlocked: {}", index, reason)
+            }
+            UtxoError::InsufficientInputs { inputs, outputs } => {
+                write!(f, "inputs ({}) do not cover outputs ({})", inputs, outputs)
+            }
+            UtxoError::AmountOverflow => write!(f, "input or output total overflows"),
+            UtxoError::NoOutputs => write!(f, "transaction has no outputs"),
+            UtxoError::NonPositiveOutput => write!(f, "output amount must be positive"),
+            UtxoError::UnexpectedCoinbase => {
+                write!(f, "coinbase is only allowed as the first transaction")
+            }
+            UtxoError::CoinbaseHeight { expected, actual } => write!(
+                f,
+                "coinbase is marked with height {}, expected {}",
+                actual, expected
+            ),
+            UtxoError::ExcessiveCoinbase { allowed, actual } => write!(
+                f,
+                "coinbase pays {} while at most {} is allowed",
+                actual, allowed
+            ),
+            UtxoError::ScriptFailed { index, reason } => {
+                write!(f, "input {} failed its script: {}", index, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UtxoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UtxoError::InvalidInput { reason, .. } => Some(reason),
+            UtxoError::ScriptFailed { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+// ========== FAUCET ERRORS ==============
+#[derive(Debug, Clone, PartialEq)]
+pub enum FaucetError {
+    InvalidAddress(ParseError),
+    /// Адрес уже получал монеты; следующая выдача через `retry_after` секунд.
+    TooSoon {
+        retry_after: u64,
+    },
+    /// Кран исчерпал общий лимит выдач на текущее окно.
+    LimitReached {
+        retry_after: u64,
+    },
+    /// Цепь не приняла перевод из крана.
+    Rejected(BlockchainError),
+}
+
+impl fmt::Display for FaucetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaucetError::InvalidAddress(e) => write!(f, "invalid address: {}", e),
+            FaucetError::TooSoon { retry_after } => {
+                write!(f, "address was funded recently, retry in {}s", retry_after)
+            }
+            FaucetError::LimitReached { retry_after } => {
+                write!(f, "faucet limit reached, retry in {}s", retry_after)
+            }
+            FaucetError::Rejected(e) => write!(f, "faucet transfer rejected: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FaucetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FaucetError::InvalidAddress(e) => Some(e),
+            FaucetError::Rejected(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// ========== SHELL ERRORS ==============
+#[derive(Debug)]
+pub enum ShellError {
+    UnknownCommand(String),
+    /// Неверные аргументы; внутри — синтаксис команды.
+    Usage(&'static str),
+    InvalidAddress(ParseError),
+    /// Узел отклонил перевод или не смог добыть блок.
+    Rejected(BlockchainError),
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShellError::UnknownCommand(command) => {
+                write!(f, "unknown command '{}', type 'help'", command)
+            }
+            ShellError::Usage(usage) => write!(f, "usage: {}", usage),
+            ShellError::InvalidAddress(e) => write!(f, "invalid address: {}", e),
+            ShellError::Rejected(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ShellError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShellError::InvalidAddress(e) => Some(e),
+            ShellError::Rejected(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<BlockchainError> for ShellError {
+    fn from(e: BlockchainError) -> Self {
+        ShellError::Rejected(e)
+    }
+}
+
+// ========== SUPPLY ERRORS ==============
+#[derive(Debug)]
+pub enum SupplyError {
+    /// Coinbase блока платит больше награды и комиссий.
+    Inflation {
+        height: u32,
+        minted: Amount,
+        allowed: Amount,
+    },
+    /// Выпуск к высоте `height` превысил `max_supply`.
+    CapExceeded {
+        height: u32,
+        issued: Amount,
+        cap: Amount,
+    },
+    /// Балансы адресов в сумме не равны выпуску: какой-то блок создал
+    /// или уничтожил монеты.
+    Unbalanced { issued: Amount, balances: Amount },
+    /// Обрезанные блоки не прочитались из архива.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for SupplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SupplyError::Inflation {
+                height,
+                minted,
+                allowed,
+            } => write!(
+                f,
+                "block {} mints {} while at most {} is allowed",
+                height, minted, allowed
+            ),
+            SupplyError::CapExceeded {
+                height,
+                issued,
+                cap,
+            } => write!(
+                f,
+                "{} coins issued by block {} exceed the cap of {}",
+                issued, height, cap
+            ),
+            SupplyError::Unbalanced { issued, balances } => write!(
+                f,
+                "balances sum to {} while {} coins were issued",
+                balances, issued
+            ),
+            SupplyError::Io(e) => write!(f, "failed to read blocks: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SupplyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SupplyError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SupplyError {
+    fn from(e: std::io::Error) -> Self {
+        SupplyError::Io(e)
+    }
+}
+
+// ========== UPNP ERRORS ==============
+#[derive(Debug)]
+pub enum UpnpError {
+    /// Роутер с UPnP в локальной сети не ответил.
+    NoGateway(String),
+    /// Не удалось узнать свой адрес в локальной сети.
+    LocalAddress(std::io::Error),
+    /// Роутер отказал в пробросе порта.
+    Mapping(String),
+    ExternalAddress(String),
+}
+
+impl fmt::Display for UpnpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpnpError::NoGateway(reason) => write!(f, "no UPnP gateway found: {}", reason),
+            UpnpError::LocalAddress(e) => write!(f, "cannot determine local address: {}", e),
+            UpnpError::Mapping(reason) => write!(f, "port mapping refused: {}", reason),
+            UpnpError::ExternalAddress(reason) => {
+                write!(f, "cannot get external address: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UpnpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UpnpError::LocalAddress(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// ========== PROTO ERRORS ==============
+/// Сообщение gRPC не переводится в типы цепи (см. `grpc`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtoError {
+    /// Хеш или подпись не той длины.
+    WrongLength {
+        field: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// Не заполнен обязательный oneof.
+    Missing(&'static str),
+    /// Текст скрипта не разбирается.
+    InvalidScript(ScriptError),
+}
+
+impl fmt::Display for ProtoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtoError::WrongLength {
+                field,
+                expected,
+                actual,
+            } => write!(f, "{} must be {} bytes, got {}", field, expected, actual),
+            ProtoError::Missing(field) => write!(f, "{} is required", field),
+            ProtoError::InvalidScript(e) => write!(f, "invalid script: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProtoError {}
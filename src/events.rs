@@ -0,0 +1,76 @@
+use crate::amount::Amount;
+use crate::block::{Block, Transaction};
+use crate::types::{Address, Hash256};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+// ========== NODE EVENTS ==============
+// Шина событий узла: всё, что происходит с цепью и пирами, узел
+// рассылает через широковещательный канал tokio. Подписаться может
+// любой код, встроивший библиотеку:
+//
+//   let mut events = node.subscribe();
+//   while let Ok(event) = events.blocking_recv() { ... }
+//
+// Каждый подписчик получает свою копию события. Отставший больше чем на
+// `EVENT_CHANNEL_CAPACITY` событий теряет самые старые (`Lagged`); узел
+// из-за медленного подписчика не ждёт. WebSocket и `GET /events` в RPC —
+// такие же подписчики.
+
+/// Сколько событий канал держит для отстающих подписчиков.
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Приёмник событий, см. `Node::subscribe`.
+pub type EventReceiver = broadcast::Receiver<NodeEvent>;
+
+/// Событие узла; в JSON тип задаётся полем `type`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NodeEvent {
+    /// Блок подключён к вершине цепи.
+    BlockAdded {
+        index: u32,
+        hash: Hash256,
+        timestamp: u64,
+        transactions: usize,
+    },
+    /// Транзакция принята в mempool.
+    TransactionAccepted {
+        txid: Hash256,
+        from: Address,
+        to: Address,
+        amount: Amount,
+        fee: Amount,
+    },
+    /// Цепь переключилась на ветку, ответвившуюся на `fork_height`.
+    ChainReorged {
+        fork_height: u32,
+        disconnected: Vec<Hash256>,
+        connected: usize,
+    },
+    /// Пир добавлен в таблицу.
+    PeerConnected { addr: String, node_id: String },
+    /// Пир удалён из таблицы (например, за бан).
+    PeerDisconnected { addr: String },
+}
+
+impl NodeEvent {
+    pub fn block_added(block: &Block) -> Self {
+        NodeEvent::BlockAdded {
+            index: block.header.index,
+            hash: block.hash,
+            timestamp: block.header.timestamp,
+            transactions: block.transactions.len(),
+        }
+    }
+
+    pub fn transaction_accepted(tx: &Transaction) -> Self {
+        NodeEvent::TransactionAccepted {
+            txid: tx.id(),
+            from: tx.from.clone(),
+            to: tx.to.clone(),
+            amount: tx.amount,
+            fee: tx.fee,
+        }
+    }
+}
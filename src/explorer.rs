@@ -0,0 +1,327 @@
+use crate::block::{Block, Transaction};
+use crate::network::{Node, ACCEPT_POLL_INTERVAL};
+use crate::types::{Address, Hash256};
+use std::fmt::Write;
+use std::io;
+use tiny_http::{Header, Response, Server};
+use tracing::{info, warn};
+
+// ========== BLOCK EXPLORER ==============
+// Встроенный обозреватель цепи: HTML-страницы без JavaScript.
+//
+//   GET /                   — высота, вершина и последние блоки
+//   GET /block/{index|hash} — блок и его транзакции
+//   GET /tx/{txid}          — подтверждённая транзакция
+//   GET /address/{address}  — баланс и история адреса
+//
+// Обработчик держит замок цепи только пока копирует нужные данные;
+// HTML строится уже без него, так что майнер не ждёт рендеринга.
+
+/// Сколько последних блоков показывает главная страница.
+pub const LATEST_BLOCKS: usize = 20;
+
+/// Готовая страница: HTTP-статус и HTML.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub status: u16,
+    pub html: String,
+}
+
+impl Page {
+    fn ok(title: &str, body: String) -> Self {
+        Page {
+            status: 200,
+            html: layout(title, &body),
+        }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Self {
+        let body = format!("<p class=\"error\">{}</p>", escape(&message.to_string()));
+        Page {
+            status,
+            html: layout("Error", &body),
+        }
+    }
+
+    fn not_found() -> Self {
+        Page::error(404, "not found")
+    }
+}
+
+/// Запускает обозреватель на 127.0.0.1:`port` и обслуживает запросы
+/// до `Node::shutdown`. Каждый запрос обрабатывается в своём потоке.
+pub fn start_explorer(node: &Node, port: u16) -> io::Result<()> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| io::Error::new(io::ErrorKind::AddrInUse, e.to_string()))?;
+    info!(node = %node.id, port, "explorer listening");
+
+    let _worker = node.worker();
+
+    while !node.is_shutting_down() {
+        let request = match server.recv_timeout(ACCEPT_POLL_INTERVAL)? {
+            Some(request) => request,
+            None => continue,
+        };
+        let node = node.clone_node();
+        let worker = node.worker();
+        std::thread::spawn(move || {
+            let _worker = worker;
+            let page = match request.method().as_str() {
+                "GET" => render(&node, request.url()),
+                _ => Page::error(405, "method not allowed"),
+            };
+
+            let content_type = Header::from_bytes("Content-Type", "text/html; charset=utf-8")
+                .expect("static header is valid");
+            let reply = Response::from_string(page.html)
+                .with_status_code(page.status)
+                .with_header(content_type);
+            if let Err(e) = request.respond(reply) {
+                warn!(error = %e, "explorer write failed");
+            }
+        });
+    }
+    info!(node = %node.id, port, "explorer stopped");
+    Ok(())
+}
+
+/// Строит страницу по пути запроса. Отделено от HTTP, чтобы страницы
+/// можно было проверять напрямую.
+pub fn render(node: &Node, url: &str) -> Page {
+    let path = url.split('?').next().unwrap_or("");
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match segments.as_slice() {
+        [] => overview_page(node),
+        ["block", id] => block_page(node, id),
+        ["tx", txid] => transaction_page(node, txid),
+        ["address", address] => address_page(node, address),
+        _ => Page::not_found(),
+    }
+}
+
+// ========== PAGES ==============
+fn overview_page(node: &Node) -> Page {
+    let (chain_id, height, difficulty, mempool, latest) = {
+        let bc = node.blockchain.blocking_read();
+        let latest: Vec<Block> = bc.chain.iter().rev().take(LATEST_BLOCKS).cloned().collect();
+        (
+            bc.chain_id().to_string(),
+            bc.chain.len() - 1,
+            bc.difficulty(),
+            bc.mempool.size(),
+            latest,
+        )
+    };
+
+    let mut body = String::new();
+    let _ = write!(
+        body,
+        "<h1>{}</h1>\n<dl>\n{}{}{}{}</dl>\n",
+        escape(&chain_id),
+        field("Height", &height.to_string()),
+        field("Tip", &block_link(&latest[0])),
+        field("Difficulty", &format!("{:.2}", difficulty)),
+        field("Mempool", &mempool.to_string()),
+    );
+
+    body.push_str("<h2>Latest blocks</h2>\n<table>\n");
+    body.push_str("<tr><th>Height</th><th>Hash</th><th>Transactions</th><th>Time</th></tr>\n");
+    for block in &latest {
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            block.header.index,
+            block_link(block),
+            block.transactions.len(),
+            block.header.timestamp,
+        );
+    }
+    body.push_str("</table>\n");
+
+    Page::ok("Overview", body)
+}
+
+fn block_page(node: &Node, id: &str) -> Page {
+    let block = {
+        let bc = node.blockchain.blocking_read();
+        let found = match id.parse::<u32>() {
+            Ok(index) => bc.get_block_by_index(index),
+            Err(_) => match id.parse::<Hash256>() {
+                Ok(hash) => bc.get_block_by_hash(&hash),
+                Err(e) => return Page::error(400, e),
+            },
+        };
+        match found {
+            Some(block) => block.into_owned(),
+            None => return Page::not_found(),
+        }
+    };
+
+    let previous = if block.header.index == 0 {
+        "—".to_string()
+    } else {
+        format!(
+            "<a href=\"/block/{}\">{}</a>",
+            block.header.prev_hash, block.header.prev_hash
+        )
+    };
+
+    let mut body = String::new();
+    let _ = write!(
+        body,
+        "<h1>Block #{}</h1>\n<dl>\n{}{}{}{}{}{}</dl>\n",
+        block.header.index,
+        field("Hash", &block.hash.to_string()),
+        field("Previous", &previous),
+        field("Merkle root", &block.header.merkle_root.to_string()),
+        field("Time", &block.header.timestamp.to_string()),
+        field("Bits", &format!("{:#010x}", block.header.bits)),
+        field("Nonce", &block.header.nonce.to_string()),
+    );
+    body.push_str("<h2>Transactions</h2>\n");
+    body.push_str(&transaction_table(&block.transactions));
+
+    Page::ok(&format!("Block #{}", block.header.index), body)
+}
+
+fn transaction_page(node: &Node, txid: &str) -> Page {
+    let txid: Hash256 = match txid.parse() {
+        Ok(txid) => txid,
+        Err(e) => return Page::error(400, e),
+    };
+    let (tx, height) = {
+        let bc = node.blockchain.blocking_read();
+        match bc.get_transaction(&txid) {
+            Some((tx, height)) => (tx.into_owned(), height),
+            None => return Page::not_found(),
+        }
+    };
+
+    let mut body = String::new();
+    let _ = write!(
+        body,
+        "<h1>Transaction</h1>\n<dl>\n{}{}{}{}{}{}{}</dl>\n",
+        field("Txid", &txid.to_string()),
+        field(
+            "Block",
+            &format!("<a href=\"/block/{}\">#{}</a>", height, height)
+        ),
+        field("From", &address_link(&tx.from)),
+        field("To", &address_link(&tx.to)),
+        field("Amount", &tx.amount.to_string()),
+        field("Fee", &tx.fee.to_string()),
+        field("Nonce", &tx.nonce.to_string()),
+    );
+
+    Page::ok("Transaction", body)
+}
+
+fn address_page(node: &Node, address: &str) -> Page {
+    let address = Address::from(address);
+    if let Err(e) = address.validate() {
+        return Page::error(400, e);
+    }
+    let (balance, history) = {
+        let bc = node.blockchain.blocking_read();
+        let history: Vec<(Transaction, u32)> = bc
+            .get_transactions_for_address(&address)
+            .into_iter()
+            .map(|(tx, height)| (tx.into_owned(), height))
+            .collect();
+        (bc.get_balance(&address), history)
+    };
+
+    let mut body = String::new();
+    let _ = write!(
+        body,
+        "<h1>Address</h1>\n<dl>\n{}{}{}</dl>\n",
+        field("Address", &escape(address.as_str())),
+        field("Balance", &balance.to_string()),
+        field("Transactions", &history.len().to_string()),
+    );
+    body.push_str("<h2>History</h2>\n<table>\n");
+    body.push_str("<tr><th>Block</th><th>Txid</th><th>Direction</th><th>Amount</th></tr>\n");
+    for (tx, height) in history.iter().rev() {
+        let direction = if tx.from == address { "out" } else { "in" };
+        let _ = writeln!(
+            body,
+            "<tr><td><a href=\"/block/{h}\">#{h}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            tx_link(tx),
+            direction,
+            tx.amount,
+            h = height,
+        );
+    }
+    body.push_str("</table>\n");
+
+    Page::ok("Address", body)
+}
+
+// ========== HTML ==============
+fn layout(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{} · mini blockchain</title>\n</head>\n<body>\n\
+         <nav><a href=\"/\">Overview</a></nav>\n{}</body>\n</html>\n",
+        escape(title),
+        body
+    )
+}
+
+/// Строка списка определений; `value` — уже готовый HTML.
+fn field(name: &str, value: &str) -> String {
+    format!("<dt>{}</dt><dd>{}</dd>\n", name, value)
+}
+
+fn transaction_table(transactions: &[Transaction]) -> String {
+    let mut table = String::from(
+        "<table>\n<tr><th>Txid</th><th>From</th><th>To</th><th>Amount</th><th>Fee</th></tr>\n",
+    );
+    for tx in transactions {
+        let _ = writeln!(
+            table,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            tx_link(tx),
+            address_link(&tx.from),
+            address_link(&tx.to),
+            tx.amount,
+            tx.fee,
+        );
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+fn block_link(block: &Block) -> String {
+    format!("<a href=\"/block/{h}\">{h}</a>", h = block.hash)
+}
+
+fn tx_link(tx: &Transaction) -> String {
+    format!("<a href=\"/tx/{id}\">{id}</a>", id = tx.id())
+}
+
+/// Служебные адреса (COINBASE, STAKE, …) не ссылаются никуда.
+fn address_link(address: &Address) -> String {
+    let text = escape(address.as_str());
+    if address.validate().is_ok() {
+        format!("<a href=\"/address/{t}\">{t}</a>", t = text)
+    } else {
+        text
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,107 @@
+use crate::amount::Amount;
+use crate::block::{Blockchain, Transaction, Wallet};
+use crate::error::FaucetError;
+use crate::network::Node;
+use crate::types::Address;
+use std::collections::{HashMap, VecDeque};
+
+// ========== FAUCET ==============
+// Кран для тестовых сетей: `POST /faucet` переводит на адрес немного
+// монет с кошелька крана, чтобы новичку в devnet не приходилось сначала
+// майнить. Один адрес получает монеты не чаще раза в `cooldown` секунд,
+// а все адреса вместе — не больше `max_drips` раз за `window` секунд,
+// так что кран нельзя опустошить перебором адресов. Время берётся из
+// часов цепи (`Blockchain::now`).
+
+/// Сколько монет кран выдаёт за раз.
+pub const DEFAULT_FAUCET_AMOUNT: Amount = Amount::from_coins(10);
+/// Комиссия перевода из крана.
+pub const DEFAULT_FAUCET_FEE: Amount = Amount::from_units(1_000_000);
+/// Сколько секунд адрес ждёт следующей выдачи.
+pub const DEFAULT_FAUCET_COOLDOWN: u64 = 24 * 60 * 60;
+/// Сколько выдач допускается на все адреса за `DEFAULT_FAUCET_WINDOW`.
+pub const DEFAULT_FAUCET_MAX_DRIPS: usize = 100;
+pub const DEFAULT_FAUCET_WINDOW: u64 = 60 * 60;
+
+pub struct Faucet {
+    wallet: Wallet,
+    pub amount: Amount,
+    pub fee: Amount,
+    pub cooldown: u64,
+    pub max_drips: usize,
+    pub window: u64,
+    /// Время последней выдачи по адресам, моложе `cooldown`.
+    last_drip: HashMap<Address, u64>,
+    /// Время выдач за последние `window` секунд, от старых к новым.
+    recent: VecDeque<u64>,
+}
+
+impl Faucet {
+    /// Кран с кошельком `wallet` и лимитами по умолчанию.
+    pub fn new(wallet: Wallet) -> Self {
+        Faucet {
+            wallet,
+            amount: DEFAULT_FAUCET_AMOUNT,
+            fee: DEFAULT_FAUCET_FEE,
+            cooldown: DEFAULT_FAUCET_COOLDOWN,
+            max_drips: DEFAULT_FAUCET_MAX_DRIPS,
+            window: DEFAULT_FAUCET_WINDOW,
+            last_drip: HashMap::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.get_address()
+    }
+
+    /// Переводит `amount` на `to`, если адрес и кран в целом не
+    /// исчерпали лимиты, и кладёт перевод в mempool `bc`. Отвергнутый
+    /// цепью перевод (например, кран опустел) в лимиты не засчитывается.
+    pub fn drip(&mut self, bc: &mut Blockchain, to: &Address) -> Result<Transaction, FaucetError> {
+        to.validate().map_err(FaucetError::InvalidAddress)?;
+        let now = bc.now();
+
+        self.last_drip
+            .retain(|_, last| now < last.saturating_add(self.cooldown));
+        if let Some(last) = self.last_drip.get(to) {
+            return Err(FaucetError::TooSoon {
+                retry_after: last + self.cooldown - now,
+            });
+        }
+        while let Some(&first) = self.recent.front() {
+            if now < first.saturating_add(self.window) {
+                break;
+            }
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= self.max_drips {
+            let first = self.recent.front().copied().unwrap_or(now);
+            return Err(FaucetError::LimitReached {
+                retry_after: (first + self.window).saturating_sub(now),
+            });
+        }
+
+        let nonce = bc.next_nonce(&self.address());
+        let tx = self.wallet.create_transaction_for_chain(
+            bc.chain_id(),
+            to,
+            self.amount,
+            self.fee,
+            nonce,
+        );
+        bc.add_transaction(tx.clone())
+            .map_err(FaucetError::Rejected)?;
+
+        self.last_drip.insert(to.clone(), now);
+        self.recent.push_back(now);
+        Ok(tx)
+    }
+}
+
+impl Node {
+    /// Включает `POST /faucet` с краном `faucet`.
+    pub fn enable_faucet(&self, faucet: Faucet) {
+        *self.faucet.lock().unwrap() = Some(faucet);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::amount::Amount;
+use crate::block::{Block, Transaction, Wallet, DEFAULT_TARGET_BLOCK_TIME, INITIAL_BITS};
+use crate::clock::MockClock;
+use crate::genesis::DEFAULT_GENESIS_TIMESTAMP;
+use crate::hashing::HashAlgorithm;
+use crate::testutil::test_wallet;
+use crate::types::{Address, Hash256};
+use std::sync::Arc;
+
+// ========== FIXTURES ==============
+// Блоки с заранее заданными полями для golden-тестов. Время, nonce и
+// транзакции фиксированы, а nonce не перебирается: хеш блока — просто
+// `compute_hash` его заголовка и цели может не достигать. Такие блоки
+// проходят `has_valid_hash`, но не проверку работы, поэтому годятся для
+// проверки хешей и сериализации, а не для цепи узла.
+//
+//   let alice = fixture_wallet(1);
+//   let block = BlockFixture::after(&genesis)
+//       .coinbase(&alice.get_address(), Amount::from_coins(50))
+//       .nonce(42)
+//       .build();
+//
+// tests/golden.rs сверяет хеши и сериализацию таких блоков с файлами в
+// tests/golden. Если они разошлись, изменение ломает консенсус; файлы
+// переписываются осознанно: `UPDATE_GOLDEN=1 cargo test --test golden`.
+
+/// Время первого блока фикстур и подписей кошельков `fixture_wallet`.
+pub const FIXTURE_TIMESTAMP: u64 = DEFAULT_GENESIS_TIMESTAMP;
+
+/// Кошелёк из сида `seed`, помечающий транзакции временем
+/// `FIXTURE_TIMESTAMP`: одни и те же аргументы дают ту же подпись.
+pub fn fixture_wallet(seed: u64) -> Wallet {
+    test_wallet(seed).with_clock(Arc::new(MockClock::new(FIXTURE_TIMESTAMP)))
+}
+
+/// Coinbase высоты `height` со временем `FIXTURE_TIMESTAMP`.
+pub fn fixture_coinbase(to: &Address, amount: Amount, height: u32) -> Transaction {
+    Transaction {
+        timestamp: FIXTURE_TIMESTAMP,
+        ..Transaction::coinbase(to.clone(), amount, height)
+    }
+}
+
+/// Блок, собранный из заданных полей без перебора nonce.
+#[derive(Debug, Clone)]
+pub struct BlockFixture {
+    algorithm: HashAlgorithm,
+    index: u32,
+    timestamp: u64,
+    prev_hash: Hash256,
+    nonce: u64,
+    extra_nonce: u64,
+    bits: u32,
+    transactions: Vec<Transaction>,
+}
+
+impl BlockFixture {
+    /// Блок высоты `index` без родителя: время — `FIXTURE_TIMESTAMP`
+    /// плюс `index` целевых интервалов, цель — `INITIAL_BITS`.
+    pub fn new(index: u32) -> Self {
+        BlockFixture {
+            algorithm: HashAlgorithm::default(),
+            index,
+            timestamp: FIXTURE_TIMESTAMP + index as u64 * DEFAULT_TARGET_BLOCK_TIME,
+            prev_hash: Hash256::ZERO,
+            nonce: 0,
+            extra_nonce: 0,
+            bits: INITIAL_BITS,
+            transactions: Vec::new(),
+        }
+    }
+
+    /// Блок после `parent`: высота, цель и хеш-функция берутся от него,
+    /// время — на целевой интервал позже.
+    pub fn after(parent: &Block) -> Self {
+        BlockFixture {
+            algorithm: parent.header.hash_algorithm,
+            index: parent.header.index + 1,
+            timestamp: parent.header.timestamp + DEFAULT_TARGET_BLOCK_TIME,
+            prev_hash: parent.hash,
+            nonce: 0,
+            extra_nonce: 0,
+            bits: parent.header.bits,
+            transactions: Vec::new(),
+        }
+    }
+
+    pub fn algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    pub fn extra_nonce(mut self, extra_nonce: u64) -> Self {
+        self.extra_nonce = extra_nonce;
+        self
+    }
+
+    pub fn bits(mut self, bits: u32) -> Self {
+        self.bits = bits;
+        self
+    }
+
+    pub fn transaction(mut self, tx: Transaction) -> Self {
+        self.transactions.push(tx);
+        self
+    }
+
+    /// Добавляет coinbase на `to` для высоты блока.
+    pub fn coinbase(self, to: &Address, amount: Amount) -> Self {
+        let tx = fixture_coinbase(to, amount, self.index);
+        self.transaction(tx)
+    }
+
+    pub fn build(self) -> Block {
+        let mut block = Block::template(
+            self.algorithm,
+            self.index,
+            self.timestamp,
+            self.transactions,
+            self.prev_hash,
+            self.bits,
+        );
+        block.header.nonce = self.nonce;
+        block.header.extra_nonce = self.extra_nonce;
+        block.hash = block.header.compute_hash();
+        block
+    }
+}
@@ -0,0 +1,210 @@
+use crate::amount::Amount;
+use crate::block::{
+    Block, Transaction, DEFAULT_BLOCK_REWARD, DEFAULT_HALVING_INTERVAL, INITIAL_BITS,
+};
+use crate::error::GenesisError;
+use crate::hashing::HashAlgorithm;
+use crate::types::{Address, Hash256, Target};
+use secp256k1::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+
+// ========== GENESIS ==============
+// Genesis-блок строится из конфигурации без обращения к часам, поэтому
+// узлы с одной и той же конфигурацией получают один и тот же блок.
+
+pub const DEFAULT_CHAIN_ID: &str = "mini-blockchain";
+/// 2024-01-01T00:00:00Z
+pub const DEFAULT_GENESIS_TIMESTAMP: u64 = 1_704_067_200;
+
+/// Имя сети `GenesisConfig::regtest`.
+pub const REGTEST_CHAIN_ID: &str = "regtest";
+/// Цель сети regtest: ей отвечает примерно каждый второй хеш.
+pub const REGTEST_BITS: u32 = 0x207f_ffff;
+/// Награда в сети regtest уменьшается вдвое каждые 150 блоков, как в
+/// regtest Bitcoin, чтобы уполовинивание можно было быстро проверить.
+pub const REGTEST_HALVING_INTERVAL: u32 = 150;
+
+const GENESIS_ADDRESS: &str = "GENESIS";
+
+/// Режим сети. В `Regtest`, как в regtest Bitcoin, цель может быть легче
+/// `Target::MAX` и не пересчитывается (но её можно задать вручную,
+/// `Blockchain::set_target`), а время блоков не проверяется:
+/// тесты и разработчики добывают сколько угодно блоков подряд
+/// (`POST /generate`) и быстро получают нужное состояние цепи.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChainMode {
+    #[default]
+    Normal,
+    Regtest,
+}
+
+/// Начальное распределение монет: зачисляется прямо в genesis-блоке.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Allocation {
+    pub address: Address,
+    pub amount: Amount,
+}
+
+/// Параметры сети, из которых детерминированно строится genesis.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenesisConfig {
+    /// Имя сети; у сетей с разными именами разные genesis-блоки.
+    pub chain_id: String,
+    pub timestamp: u64,
+    /// Начальная цель PoW в компактной форме.
+    pub bits: u32,
+    /// Хеш-функция заголовков блоков этой сети.
+    pub hash_algorithm: HashAlgorithm,
+    /// Награда за блок до первого уполовинивания.
+    pub block_reward: Amount,
+    /// Через сколько блоков награда уменьшается вдвое; 0 — никогда.
+    pub halving_interval: u32,
+    /// Сколько монет может быть выпущено всего, считая `allocations`;
+    /// без предела выпуск ограничен только расписанием наград.
+    pub max_supply: Option<Amount>,
+    pub allocations: Vec<Allocation>,
+    /// Открытые ключи (hex) производителей закрытой сети. Если список
+    /// не пуст, принимаются только блоки с подписью одного из них.
+    pub producers: Vec<String>,
+    pub mode: ChainMode,
+}
+
+impl Default for GenesisConfig {
+    fn default() -> Self {
+        GenesisConfig {
+            chain_id: DEFAULT_CHAIN_ID.to_string(),
+            timestamp: DEFAULT_GENESIS_TIMESTAMP,
+            bits: INITIAL_BITS,
+            hash_algorithm: HashAlgorithm::default(),
+            block_reward: DEFAULT_BLOCK_REWARD,
+            halving_interval: DEFAULT_HALVING_INTERVAL,
+            max_supply: None,
+            allocations: Vec::new(),
+            producers: Vec::new(),
+            mode: ChainMode::Normal,
+        }
+    }
+}
+
+impl GenesisConfig {
+    /// Сеть для разработки и тестов: `ChainMode::Regtest` с целью
+    /// `REGTEST_BITS` и уполовиниванием каждые `REGTEST_HALVING_INTERVAL` блоков.
+    pub fn regtest() -> Self {
+        GenesisConfig {
+            chain_id: REGTEST_CHAIN_ID.to_string(),
+            bits: REGTEST_BITS,
+            halving_interval: REGTEST_HALVING_INTERVAL,
+            mode: ChainMode::Regtest,
+            ..GenesisConfig::default()
+        }
+    }
+
+    /// Читает конфигурацию из TOML или JSON — по расширению файла
+    /// (`.json` — JSON, всё остальное — TOML) — и проверяет её.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<GenesisConfig, GenesisError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let config: GenesisConfig = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).map_err(|e| GenesisError::Malformed(e.to_string()))?
+        } else {
+            toml::from_str(&text).map_err(|e| GenesisError::Malformed(e.to_string()))?
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), GenesisError> {
+        if self.chain_id.is_empty() {
+            return Err(GenesisError::Malformed("chain_id is empty".into()));
+        }
+        // Нулевая цель недостижима, цель выше `Target::MAX` слишком лёгкая
+        // для всех сетей, кроме regtest
+        let in_range = Target::from_compact(self.bits).is_some_and(|target| {
+            target.0 != [0u8; 32] && (target <= Target::MAX || self.mode == ChainMode::Regtest)
+        });
+        if !in_range {
+            return Err(GenesisError::InvalidBits(self.bits));
+        }
+        for allocation in &self.allocations {
+            if allocation.address.validate().is_err() || allocation.amount.is_zero() {
+                return Err(GenesisError::InvalidAllocation(allocation.address.clone()));
+            }
+        }
+        let allocated = Amount::checked_sum(self.allocations.iter().map(|a| a.amount))
+            .ok_or_else(|| GenesisError::Malformed("allocations overflow".into()))?;
+        if let Some(cap) = self.max_supply {
+            if cap < allocated {
+                return Err(GenesisError::Malformed(format!(
+                    "max_supply {} does not cover the {} allocated in genesis",
+                    cap, allocated
+                )));
+            }
+        }
+        for key in &self.producers {
+            let parsed = hex::decode(key)
+                .ok()
+                .and_then(|bytes| PublicKey::from_slice(&bytes).ok());
+            if parsed.is_none() {
+                return Err(GenesisError::InvalidProducer(key.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Genesis-блок: служебная транзакция с `chain_id` и по одной
+    /// coinbase на каждое начальное распределение. Время всех транзакций
+    /// и блока — `timestamp`, так что результат зависит только от конфигурации.
+    /// Паникует, если `bits` не проходит `validate`.
+    pub fn block(&self) -> Block {
+        let marker = Transaction::new(
+            Address::from(GENESIS_ADDRESS),
+            Address::from(self.chain_id.as_str()),
+            Amount::ZERO,
+            Amount::ZERO,
+            0,
+            "genesis_key".to_string(),
+        );
+        let allocations = self
+            .allocations
+            .iter()
+            .enumerate()
+            .map(|(i, allocation)| Transaction {
+                nonce: i as u64,
+                ..Transaction::coinbase(allocation.address.clone(), allocation.amount, 0)
+            });
+
+        let transactions = std::iter::once(marker)
+            .chain(allocations)
+            .map(|tx| Transaction {
+                timestamp: self.timestamp,
+                chain_id: self.chain_id.clone(),
+                ..tx
+            })
+            .collect();
+
+        Block::mine_at(
+            self.hash_algorithm,
+            0,
+            self.timestamp,
+            transactions,
+            Hash256::ZERO,
+            self.bits,
+            &AtomicBool::new(false),
+        )
+        .expect("genesis bits are validated and mining is not cancelled")
+    }
+}
+
+impl Block {
+    /// Идентификатор сети, записанный в genesis-блок `GenesisConfig::block`.
+    pub fn genesis_chain_id(&self) -> Option<&str> {
+        let marker = self.transactions.first()?;
+        (self.header.index == 0 && marker.from.as_str() == GENESIS_ADDRESS)
+            .then(|| marker.to.as_str())
+    }
+}
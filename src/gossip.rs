@@ -0,0 +1,73 @@
+use crate::types::Hash256;
+use std::collections::{HashSet, VecDeque};
+
+// ========== GOSSIP ==============
+// Транзакции расходятся по сети эстафетой: узел, принявший новую
+// транзакцию, объявляет её своим пирам (INV, см. `inventory`). Кэш уже
+// виденных txid не даёт одной транзакции ходить по кругу.
+
+/// Сколько txid помнит узел по умолчанию.
+pub const DEFAULT_SEEN_CAPACITY: usize = 10_000;
+
+/// LRU-множество хешей ограниченного размера: при переполнении
+/// вытесняется тот, к которому дольше всего не обращались.
+#[derive(Debug, Clone)]
+pub struct SeenCache {
+    capacity: usize,
+    /// От давних обращений к свежим.
+    order: VecDeque<Hash256>,
+    entries: HashSet<Hash256>,
+}
+
+impl SeenCache {
+    pub fn new(capacity: usize) -> Self {
+        SeenCache {
+            capacity: capacity.max(1),
+            order: VecDeque::new(),
+            entries: HashSet::new(),
+        }
+    }
+
+    /// Запоминает хеш. `true` — если раньше его не было; иначе хеш
+    /// становится самым свежим и возвращается `false`.
+    pub fn insert(&mut self, hash: Hash256) -> bool {
+        if self.entries.contains(&hash) {
+            self.touch(&hash);
+            return false;
+        }
+
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.order.push_back(hash);
+        self.entries.insert(hash);
+        true
+    }
+
+    pub fn contains(&self, hash: &Hash256) -> bool {
+        self.entries.contains(hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    fn touch(&mut self, hash: &Hash256) {
+        if let Some(position) = self.order.iter().position(|h| h == hash) {
+            self.order.remove(position);
+            self.order.push_back(*hash);
+        }
+    }
+}
+
+impl Default for SeenCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEEN_CAPACITY)
+    }
+}
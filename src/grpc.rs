@@ -0,0 +1,388 @@
+use crate::amount::Amount;
+use crate::block::{Block, BlockHeader, LockTime, Transaction};
+use crate::error::ProtoError;
+use crate::events::NodeEvent;
+use crate::hashing::HashAlgorithm;
+use crate::multisig::{Cosignature, MultisigPolicy};
+use crate::network::{runtime, Node};
+use crate::tokens::TokenOp;
+use crate::types::{Address, Hash256, Signature};
+use proto::node_server::{Node as NodeApi, NodeServer};
+use std::io;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+use tracing::{debug, info};
+
+// ========== gRPC ==============
+// Те же операции, что у JSON RPC, но сервисом gRPC (proto/node.proto):
+// клиенты на других языках генерируются protoc из описания и получают
+// типизированные сообщения вместо разбора JSON.
+//
+//   SubmitTransaction — подписанная транзакция в mempool и пирам
+//   GetBlock          — блок по высоте или хешу
+//   GetBalance        — баланс и следующий nonce адреса
+//   SubscribeBlocks   — поток блоков, подключённых к вершине
+//
+// Сообщения proto отделены от типов цепи: ниже — преобразования в обе
+// стороны, так что формат цепи меняется, не ломая клиентов. Если
+// подписчик SubscribeBlocks отстал настолько, что события потеряны,
+// поток завершается статусом DATA_LOSS.
+
+/// Типы и заглушки, сгенерированные из proto/node.proto.
+pub mod proto {
+    tonic::include_proto!("blockchain.v1");
+}
+
+/// Сколько блоков копится для подписчика, пока он их не прочитал.
+const BLOCK_STREAM_BUFFER: usize = 16;
+
+/// Запускает gRPC-сервер на 127.0.0.1:`port` и обслуживает клиентов
+/// до `Node::shutdown`.
+pub fn start_grpc_server(node: &Node, port: u16) -> io::Result<()> {
+    runtime().block_on(serve(node, port))
+}
+
+async fn serve(node: &Node, port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    info!(node = %node.id, port, "gRPC listening");
+    let _worker = node.worker();
+
+    let service = NodeServer::new(GrpcService {
+        node: node.clone_node(),
+    });
+    Server::builder()
+        .add_service(service)
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), node.shutdown.cancelled())
+        .await
+        .map_err(io::Error::other)?;
+    info!(node = %node.id, port, "gRPC stopped");
+    Ok(())
+}
+
+/// Реализация сервиса `Node` из proto поверх узла.
+pub struct GrpcService {
+    node: Node,
+}
+
+#[tonic::async_trait]
+impl NodeApi for GrpcService {
+    async fn submit_transaction(
+        &self,
+        request: Request<proto::Transaction>,
+    ) -> Result<Response<proto::SubmitTransactionReply>, Status> {
+        if self.node.is_syncing() {
+            return Err(Status::unavailable("node is syncing"));
+        }
+        let tx = Transaction::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let txid = tx.id();
+
+        let added = self
+            .node
+            .blockchain
+            .write()
+            .await
+            .add_transaction(tx.clone());
+        added.map_err(|e| Status::failed_precondition(e.to_string()))?;
+        self.node.announce_transaction(&tx).await;
+
+        Ok(Response::new(proto::SubmitTransactionReply {
+            txid: txid.as_bytes().to_vec(),
+        }))
+    }
+
+    async fn get_block(
+        &self,
+        request: Request<proto::GetBlockRequest>,
+    ) -> Result<Response<proto::Block>, Status> {
+        let bc = self.node.blockchain.read().await;
+        let height = match request.into_inner().block {
+            Some(proto::get_block_request::Block::Height(height)) => height as usize,
+            Some(proto::get_block_request::Block::Hash(hash)) => {
+                let hash =
+                    fixed(&hash, "hash").map_err(|e| Status::invalid_argument(e.to_string()))?;
+                let hash = Hash256(hash);
+                bc.block_height(&hash)
+                    .ok_or_else(|| Status::not_found("block not found"))?
+            }
+            None => return Err(Status::invalid_argument("height or hash is required")),
+        };
+        match bc.full_block(height) {
+            Ok(Some(block)) => Ok(Response::new(proto::Block::from(&block))),
+            Ok(None) => Err(Status::not_found("block not found")),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn get_balance(
+        &self,
+        request: Request<proto::GetBalanceRequest>,
+    ) -> Result<Response<proto::Balance>, Status> {
+        let address = Address::from(request.into_inner().address.as_str());
+        address
+            .validate()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let bc = self.node.blockchain.read().await;
+        Ok(Response::new(proto::Balance {
+            balance: bc.get_balance(&address).units(),
+            next_nonce: bc.next_nonce(&address),
+            address: address.to_string(),
+        }))
+    }
+
+    type SubscribeBlocksStream = ReceiverStream<Result<proto::Block, Status>>;
+
+    async fn subscribe_blocks(
+        &self,
+        _request: Request<proto::SubscribeBlocksRequest>,
+    ) -> Result<Response<Self::SubscribeBlocksStream>, Status> {
+        let node = self.node.clone_node();
+        let mut events = node.subscribe();
+        let (sender, receiver) = mpsc::channel(BLOCK_STREAM_BUFFER);
+
+        tokio::spawn(async move {
+            let _worker = node.worker();
+            loop {
+                let (index, hash) = tokio::select! {
+                    _ = node.shutdown.cancelled() => return,
+                    _ = sender.closed() => return,
+                    event = events.recv() => match event {
+                        Ok(NodeEvent::BlockAdded { index, hash, .. }) => (index, hash),
+                        Ok(_) => continue,
+                        Err(RecvError::Lagged(missed)) => {
+                            let status = Status::data_loss(format!("missed {} events", missed));
+                            let _ = sender.send(Err(status)).await;
+                            return;
+                        }
+                        Err(RecvError::Closed) => return,
+                    },
+                };
+                let block = node.blockchain.read().await.full_block(index as usize);
+                let item = match block {
+                    Ok(Some(block)) if block.hash == hash => Ok(proto::Block::from(&block)),
+                    // Блок уже вытеснен reorg-ом, о новой ветке придут свои события
+                    Ok(_) => continue,
+                    Err(e) => Err(Status::internal(e.to_string())),
+                };
+                if sender.send(item).await.is_err() {
+                    debug!("block subscriber gone");
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+// ========== CONVERSIONS ==============
+/// Массив ровно из `N` байт поля `field`.
+fn fixed<const N: usize>(bytes: &[u8], field: &'static str) -> Result<[u8; N], ProtoError> {
+    bytes.try_into().map_err(|_| ProtoError::WrongLength {
+        field,
+        expected: N,
+        actual: bytes.len(),
+    })
+}
+
+/// Пустая подпись передаётся пустым полем.
+fn signature_bytes(signature: &Signature) -> Vec<u8> {
+    if signature.is_empty() {
+        Vec::new()
+    } else {
+        signature.0.to_vec()
+    }
+}
+
+fn parse_signature(bytes: &[u8], field: &'static str) -> Result<Signature, ProtoError> {
+    if bytes.is_empty() {
+        return Ok(Signature::EMPTY);
+    }
+    fixed(bytes, field).map(Signature)
+}
+
+impl From<HashAlgorithm> for proto::HashAlgorithm {
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => proto::HashAlgorithm::Sha256,
+            HashAlgorithm::DoubleSha256 => proto::HashAlgorithm::DoubleSha256,
+            HashAlgorithm::Blake3 => proto::HashAlgorithm::Blake3,
+        }
+    }
+}
+
+impl From<&BlockHeader> for proto::BlockHeader {
+    fn from(header: &BlockHeader) -> Self {
+        proto::BlockHeader {
+            index: header.index,
+            timestamp: header.timestamp,
+            prev_hash: header.prev_hash.as_bytes().to_vec(),
+            merkle_root: header.merkle_root.as_bytes().to_vec(),
+            nonce: header.nonce,
+            extra_nonce: header.extra_nonce,
+            bits: header.bits,
+            hash_algorithm: proto::HashAlgorithm::from(header.hash_algorithm).into(),
+        }
+    }
+}
+
+impl From<&Block> for proto::Block {
+    fn from(block: &Block) -> Self {
+        proto::Block {
+            header: Some(proto::BlockHeader::from(&block.header)),
+            hash: block.hash.as_bytes().to_vec(),
+            transactions: block.transactions.iter().map(Into::into).collect(),
+            producer_key: block.producer_key.clone(),
+            producer_signature: signature_bytes(&block.producer_signature),
+        }
+    }
+}
+
+impl From<&TokenOp> for proto::TokenOp {
+    fn from(op: &TokenOp) -> Self {
+        use proto::token_op::Op;
+        let op = match op {
+            TokenOp::CreateToken { symbol, supply } => Op::CreateToken(proto::CreateToken {
+                symbol: symbol.clone(),
+                supply: *supply,
+            }),
+            TokenOp::Mint { token, amount } => Op::Mint(proto::TokenAmount {
+                token: token.as_bytes().to_vec(),
+                amount: *amount,
+            }),
+            TokenOp::Transfer { token, amount } => Op::Transfer(proto::TokenAmount {
+                token: token.as_bytes().to_vec(),
+                amount: *amount,
+            }),
+        };
+        proto::TokenOp { op: Some(op) }
+    }
+}
+
+impl TryFrom<proto::TokenOp> for TokenOp {
+    type Error = ProtoError;
+
+    fn try_from(op: proto::TokenOp) -> Result<Self, ProtoError> {
+        use proto::token_op::Op;
+        match op.op {
+            Some(Op::CreateToken(create)) => Ok(TokenOp::CreateToken {
+                symbol: create.symbol,
+                supply: create.supply,
+            }),
+            Some(Op::Mint(mint)) => Ok(TokenOp::Mint {
+                token: Hash256(fixed(&mint.token, "token")?),
+                amount: mint.amount,
+            }),
+            Some(Op::Transfer(transfer)) => Ok(TokenOp::Transfer {
+                token: Hash256(fixed(&transfer.token, "token")?),
+                amount: transfer.amount,
+            }),
+            None => Err(ProtoError::Missing("token.op")),
+        }
+    }
+}
+
+impl From<LockTime> for proto::LockTime {
+    fn from(lock_time: LockTime) -> Self {
+        let lock = match lock_time {
+            LockTime::Height(height) => proto::lock_time::Lock::Height(height),
+            LockTime::Timestamp(timestamp) => proto::lock_time::Lock::Timestamp(timestamp),
+        };
+        proto::LockTime { lock: Some(lock) }
+    }
+}
+
+impl TryFrom<proto::LockTime> for LockTime {
+    type Error = ProtoError;
+
+    fn try_from(lock_time: proto::LockTime) -> Result<Self, ProtoError> {
+        match lock_time.lock {
+            Some(proto::lock_time::Lock::Height(height)) => Ok(LockTime::Height(height)),
+            Some(proto::lock_time::Lock::Timestamp(timestamp)) => {
+                Ok(LockTime::Timestamp(timestamp))
+            }
+            None => Err(ProtoError::Missing("lock_time.lock")),
+        }
+    }
+}
+
+impl From<&Transaction> for proto::Transaction {
+    fn from(tx: &Transaction) -> Self {
+        proto::Transaction {
+            from: tx.from.to_string(),
+            to: tx.to.to_string(),
+            amount: tx.amount.units(),
+            fee: tx.fee.units(),
+            timestamp: tx.timestamp,
+            nonce: tx.nonce,
+            signature: signature_bytes(&tx.signature),
+            public_key: tx.public_key.clone(),
+            chain_id: tx.chain_id.clone(),
+            multisig: tx.multisig.as_ref().map(|policy| proto::MultisigPolicy {
+                threshold: policy.threshold as u64,
+                public_keys: policy.public_keys.clone(),
+            }),
+            cosignatures: tx
+                .cosignatures
+                .iter()
+                .map(|cosignature| proto::Cosignature {
+                    public_key: cosignature.public_key.clone(),
+                    signature: signature_bytes(&cosignature.signature),
+                })
+                .collect(),
+            data: tx.data.clone(),
+            token: tx.token.as_ref().map(Into::into),
+            lock_time: tx.lock_time.map(Into::into),
+            lock_script: tx
+                .lock_script
+                .as_ref()
+                .map_or_else(String::new, ToString::to_string),
+            script_sig: tx.script_sig.to_string(),
+        }
+    }
+}
+
+impl TryFrom<proto::Transaction> for Transaction {
+    type Error = ProtoError;
+
+    fn try_from(tx: proto::Transaction) -> Result<Self, ProtoError> {
+        let cosignatures = tx
+            .cosignatures
+            .into_iter()
+            .map(|cosignature| {
+                Ok(Cosignature {
+                    signature: parse_signature(&cosignature.signature, "cosignature")?,
+                    public_key: cosignature.public_key,
+                })
+            })
+            .collect::<Result<_, ProtoError>>()?;
+        Ok(Transaction {
+            from: Address::from(tx.from.as_str()),
+            to: Address::from(tx.to.as_str()),
+            amount: Amount::from_units(tx.amount),
+            fee: Amount::from_units(tx.fee),
+            timestamp: tx.timestamp,
+            nonce: tx.nonce,
+            signature: parse_signature(&tx.signature, "signature")?,
+            public_key: tx.public_key,
+            chain_id: tx.chain_id,
+            multisig: tx.multisig.map(|policy| MultisigPolicy {
+                threshold: policy.threshold as usize,
+                public_keys: policy.public_keys,
+            }),
+            cosignatures,
+            data: tx.data,
+            token: tx.token.map(TryInto::try_into).transpose()?,
+            lock_time: tx.lock_time.map(TryInto::try_into).transpose()?,
+            lock_script: (!tx.lock_script.is_empty())
+                .then(|| tx.lock_script.parse())
+                .transpose()
+                .map_err(ProtoError::InvalidScript)?,
+            script_sig: tx.script_sig.parse().map_err(ProtoError::InvalidScript)?,
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use crate::types::Hash256;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+// ========== HASHING ==============
+// Хеш-функция заголовка блока. Выбирается один раз в `GenesisConfig`
+// и наследуется каждым блоком цепи. Txid и дерево Меркла всегда
+// считаются SHA-256 — от выбора зависит только доказательство работы.
+
+pub trait Hasher: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn hash(&self, data: &[u8]) -> Hash256;
+}
+
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+    fn name(&self) -> &'static str {
+        "sha256"
+    }
+
+    fn hash(&self, data: &[u8]) -> Hash256 {
+        Hash256::digest(data)
+    }
+}
+
+/// SHA-256 от SHA-256, как в заголовках блоков Bitcoin.
+pub struct DoubleSha256Hasher;
+
+impl Hasher for DoubleSha256Hasher {
+    fn name(&self) -> &'static str {
+        "double-sha256"
+    }
+
+    fn hash(&self, data: &[u8]) -> Hash256 {
+        Hash256(Sha256::digest(Sha256::digest(data)).into())
+    }
+}
+
+/// BLAKE3: заметно быстрее SHA-256 там, где нет аппаратного SHA.
+pub struct Blake3Hasher;
+
+impl Hasher for Blake3Hasher {
+    fn name(&self) -> &'static str {
+        "blake3"
+    }
+
+    fn hash(&self, data: &[u8]) -> Hash256 {
+        Hash256(*blake3::hash(data).as_bytes())
+    }
+}
+
+/// Имя алгоритма в конфигурации и в сериализованных блоках.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    DoubleSha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 3] = [
+        HashAlgorithm::Sha256,
+        HashAlgorithm::DoubleSha256,
+        HashAlgorithm::Blake3,
+    ];
+
+    pub fn hasher(self) -> &'static dyn Hasher {
+        match self {
+            HashAlgorithm::Sha256 => &Sha256Hasher,
+            HashAlgorithm::DoubleSha256 => &DoubleSha256Hasher,
+            HashAlgorithm::Blake3 => &Blake3Hasher,
+        }
+    }
+
+    pub fn hash(self, data: &[u8]) -> Hash256 {
+        self.hasher().hash(data)
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.hasher().name())
+    }
+}
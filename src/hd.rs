@@ -0,0 +1,108 @@
+use crate::block::Wallet;
+use crate::error::WalletError;
+use crate::mnemonic;
+use hmac::{Hmac, Mac};
+use secp256k1::{PublicKey, Scalar, Secp256k1, SecretKey};
+use sha2::Sha512;
+
+// ========== HD WALLET ==============
+// Иерархическая деривация ключей по BIP32: из одного сида получается
+// дерево ключей, каждый узел которого — обычный `Wallet`.
+
+/// Индексы от этого значения и выше дают hardened-потомков.
+pub const HARDENED: u32 = 0x8000_0000;
+
+type HmacSha512 = Hmac<Sha512>;
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn split(output: [u8; 64]) -> ([u8; 32], [u8; 32]) {
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    (left, right)
+}
+
+fn wallet_from_parts(secret_key: &SecretKey, chain_code: [u8; 32]) -> Wallet {
+    Wallet {
+        chain_code: Some(chain_code),
+        ..Wallet::from_secret_key(secret_key)
+    }
+}
+
+impl Wallet {
+    /// Мастер-ключ из BIP39-фразы (без дополнительного пароля).
+    pub fn from_mnemonic(phrase: &str) -> Result<Wallet, WalletError> {
+        Wallet::from_seed(&mnemonic::to_seed(phrase, "")?)
+    }
+
+    /// Мастер-ключ из произвольного сида: HMAC-SHA512("Bitcoin seed", seed).
+    pub fn from_seed(seed: &[u8]) -> Result<Wallet, WalletError> {
+        let (key, chain_code) = split(hmac_sha512(b"Bitcoin seed", seed));
+        let secret_key = SecretKey::from_slice(&key).map_err(|_| WalletError::InvalidSeed)?;
+        Ok(wallet_from_parts(&secret_key, chain_code))
+    }
+
+    pub fn is_hierarchical(&self) -> bool {
+        self.chain_code.is_some()
+    }
+
+    /// Дочерний ключ с номером `index`; для hardened-потомка передайте
+    /// `HARDENED + i`. Если BIP32 объявляет ключ недействительным,
+    /// возвращается `InvalidChildKey` и нужно взять следующий индекс.
+    pub fn derive_child(&self, index: u32) -> Result<Wallet, WalletError> {
+        let chain_code = self.chain_code.ok_or(WalletError::NotHierarchical)?;
+        let secret_key = SecretKey::from_slice(
+            &hex::decode(&self.private_key).map_err(|_| WalletError::InvalidChildKey(index))?,
+        )
+        .map_err(|_| WalletError::InvalidChildKey(index))?;
+
+        let mut data = Vec::with_capacity(37);
+        if index >= HARDENED {
+            data.push(0);
+            data.extend_from_slice(&secret_key.secret_bytes());
+        } else {
+            let public_key = PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret_key);
+            data.extend_from_slice(&public_key.serialize());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let (tweak, child_chain_code) = split(hmac_sha512(&chain_code, &data));
+        let tweak =
+            Scalar::from_be_bytes(tweak).map_err(|_| WalletError::InvalidChildKey(index))?;
+        let child_key = secret_key
+            .add_tweak(&tweak)
+            .map_err(|_| WalletError::InvalidChildKey(index))?;
+
+        Ok(wallet_from_parts(&child_key, child_chain_code))
+    }
+
+    /// Деривация по пути вида `m/44'/0'/0'/0/5` (апостроф или `h`
+    /// обозначают hardened-индекс).
+    pub fn derive_path(&self, path: &str) -> Result<Wallet, WalletError> {
+        let invalid = || WalletError::InvalidPath(path.to_string());
+        let mut parts = path.split('/');
+        if parts.next() != Some("m") {
+            return Err(invalid());
+        }
+
+        let mut wallet = self.clone();
+        for part in parts {
+            let (digits, hardened) = match part.strip_suffix(['\'', 'h']) {
+                Some(digits) => (digits, true),
+                None => (part, false),
+            };
+            let index: u32 = digits.parse().map_err(|_| invalid())?;
+            if index >= HARDENED {
+                return Err(invalid());
+            }
+            wallet = wallet.derive_child(if hardened { HARDENED + index } else { index })?;
+        }
+        Ok(wallet)
+    }
+}
@@ -0,0 +1,159 @@
+use crate::block::unix_now;
+use crate::events::NodeEvent;
+use crate::network::{request, runtime, Node};
+use crate::protocol::Message;
+use serde::Serialize;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+// ========== PEER HEALTH ==============
+// Раз в `ping_interval` секунд узел шлёт каждому пиру PING с отметкой
+// времени и ждёт PONG с той же отметкой. Время ответа (RTT) и момент
+// последнего ответа видны в `Node::peer_stats` и в GET /metrics. Пир,
+// пропустивший `MAX_MISSED_PINGS` опросов подряд, удаляется из таблицы:
+// в отличие от бана, к нему можно подключиться снова.
+
+/// Как часто узел опрашивает пиров, в секундах.
+pub const DEFAULT_PING_INTERVAL: u64 = 30;
+
+/// Сколько опросов подряд пир может пропустить, прежде чем его удалят.
+pub const MAX_MISSED_PINGS: u32 = 3;
+
+/// Результаты опросов пира.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PeerHealth {
+    rtt: Option<Duration>,
+    last_seen: Option<u64>,
+    missed_pings: u32,
+}
+
+/// Состояние пира из таблицы для RPC и метрик.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeerStats {
+    pub addr: String,
+    pub node_id: String,
+    /// Время ответа на последний PING в миллисекундах; `None`, пока пир
+    /// ни разу не ответил.
+    pub rtt_ms: Option<f64>,
+    /// Когда пир последний раз ответил (секунды Unix).
+    pub last_seen: Option<u64>,
+    /// Сколько опросов подряд остались без ответа.
+    pub missed_pings: u32,
+}
+
+/// Отметка времени PING: миллисекунды Unix.
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl Node {
+    /// Опрашивает всех пиров параллельно и обновляет их статистику; тех,
+    /// кто пропустил `MAX_MISSED_PINGS` опросов подряд, удаляет из
+    /// таблицы. Возвращает число удалённых.
+    pub fn ping_peers(&self) -> usize {
+        let peers = self.peer_addrs();
+        if peers.is_empty() {
+            return 0;
+        }
+
+        let replies = runtime().block_on(async {
+            let mut pings = JoinSet::new();
+            for peer_addr in peers {
+                let transport = self.transport.clone();
+                pings.spawn(async move {
+                    let timestamp = unix_millis();
+                    let sent = Instant::now();
+                    let reply = request(&peer_addr, Message::Ping { timestamp }, &transport).await;
+                    let rtt = match reply {
+                        Some(Message::Pong { timestamp: echoed }) if echoed == timestamp => {
+                            Some(sent.elapsed())
+                        }
+                        _ => None,
+                    };
+                    (peer_addr, rtt)
+                });
+            }
+            let mut replies = Vec::new();
+            while let Some(Ok(reply)) = pings.join_next().await {
+                replies.push(reply);
+            }
+            replies
+        });
+
+        let mut dead = Vec::new();
+        {
+            let mut health = self.health.lock().unwrap();
+            for (peer_addr, rtt) in replies {
+                let entry = health.entry(peer_addr.clone()).or_default();
+                match rtt {
+                    Some(rtt) => {
+                        entry.rtt = Some(rtt);
+                        entry.last_seen = Some(unix_now());
+                        entry.missed_pings = 0;
+                    }
+                    None => {
+                        entry.missed_pings += 1;
+                        debug!(peer = %peer_addr, missed = entry.missed_pings, "ping missed");
+                        if entry.missed_pings >= MAX_MISSED_PINGS {
+                            dead.push(peer_addr);
+                        }
+                    }
+                }
+            }
+            for peer_addr in &dead {
+                health.remove(peer_addr);
+            }
+        }
+
+        self.peers.lock().unwrap().retain(|peer| {
+            let keep = !dead.contains(&peer.addr.to_string());
+            if !keep {
+                warn!(peer = %peer.addr, "peer stopped answering pings, dropped");
+                self.emit(NodeEvent::PeerDisconnected {
+                    addr: peer.addr.to_string(),
+                });
+            }
+            keep
+        });
+        // Пиров, удалённых иначе (бан), больше не отслеживаем
+        let known = self.peer_addrs();
+        self.health
+            .lock()
+            .unwrap()
+            .retain(|addr, _| known.contains(addr));
+        dead.len()
+    }
+
+    /// Таблица пиров с результатами опросов.
+    pub fn peer_stats(&self) -> Vec<PeerStats> {
+        let health = self.health.lock().unwrap();
+        self.peers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|peer| {
+                let addr = peer.addr.to_string();
+                let entry = health.get(&addr).cloned().unwrap_or_default();
+                PeerStats {
+                    addr,
+                    node_id: peer.node_id.clone(),
+                    rtt_ms: entry.rtt.map(|rtt| rtt.as_secs_f64() * 1000.0),
+                    last_seen: entry.last_seen,
+                    missed_pings: entry.missed_pings,
+                }
+            })
+            .collect()
+    }
+
+    /// Запускает фоновый опрос пиров раз в `interval` до `shutdown`.
+    pub fn start_ping(&self, interval: Duration) -> JoinHandle<()> {
+        self.every("ping", interval, |node| {
+            node.ping_peers();
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use crate::block::Transaction;
+use crate::network::{request, runtime, Node};
+use crate::protocol::Message;
+use crate::types::Hash256;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use tracing::debug;
+
+// ========== INVENTORY ==============
+// Транзакции расходятся не целиком, а объявлениями: узел шлёт пирам INV
+// со списком txid, и только пир, которому они не знакомы, запрашивает
+// сами транзакции (GET_DATA) у объявившего узла. С ростом числа пиров
+// каждая транзакция приходит узлу целиком один раз, а не от каждого
+// соседа. Пока запрос к одному пиру не закончился, те же txid у других
+// не запрашиваются.
+//
+// Узел без своего адреса объявлять транзакции не может — у него нечего
+// запросить, — и рассылает их целиком (NEW_TX), как раньше.
+
+/// Сколько txid принимается в одном INV или GET_DATA.
+pub const MAX_INV_SIZE: usize = 1_000;
+
+impl Node {
+    /// Запрашивает у `origin` объявленные им транзакции, которых у нас
+    /// нет и которые ещё не запрошены у других пиров.
+    pub(crate) async fn handle_inventory(
+        &self,
+        mut txids: Vec<Hash256>,
+        origin: String,
+        peer: IpAddr,
+    ) {
+        txids.truncate(MAX_INV_SIZE);
+        let bc = self.blockchain.read().await;
+        let wanted: Vec<Hash256> = {
+            let seen = self.seen_txs.lock().unwrap();
+            let mut requested = self.requested_txs.lock().unwrap();
+            txids
+                .into_iter()
+                .filter(|txid| !seen.contains(txid) && !bc.mempool.contains(txid))
+                .filter(|txid| requested.insert(*txid))
+                .collect()
+        };
+        drop(bc);
+        if wanted.is_empty() {
+            return;
+        }
+
+        debug!(peer = %origin, count = wanted.len(), "requesting announced transactions");
+        let node = self.clone_node();
+        let worker = node.worker();
+        runtime().spawn(async move {
+            let _worker = worker;
+            let message = Message::GetData {
+                txids: wanted.clone(),
+            };
+            let transactions = match request(&origin, message, &node.transport).await {
+                Some(Message::Transactions { transactions }) => transactions,
+                _ => Vec::new(),
+            };
+            for tx in transactions {
+                if wanted.contains(&tx.id()) {
+                    node.accept_transaction(tx, Some(&origin), peer).await;
+                }
+            }
+            let mut requested = node.requested_txs.lock().unwrap();
+            for txid in &wanted {
+                requested.remove(txid);
+            }
+        });
+    }
+
+    /// Транзакции из mempool с txid из `txids` (ответ на GET_DATA).
+    pub(crate) async fn inventory_data(&self, txids: &[Hash256]) -> Vec<Transaction> {
+        let txids: HashSet<&Hash256> = txids.iter().take(MAX_INV_SIZE).collect();
+        let bc = self.blockchain.read().await;
+        bc.mempool
+            .transactions
+            .iter()
+            .filter(|tx| txids.contains(&tx.id()))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,148 @@
+use crate::block::Wallet;
+use crate::error::KeystoreError;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use secp256k1::SecretKey;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::Path;
+
+// ========== KEYSTORE ==============
+// Приватный ключ хранится зашифрованным AES-256-GCM; ключ шифрования
+// выводится из пароля через PBKDF2-HMAC-SHA256 со случайной солью.
+
+pub const KEYSTORE_VERSION: u32 = 1;
+pub const PBKDF2_ITERATIONS: u32 = 100_000;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    address: String,
+    public_key: String,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+fn cipher(key: &[u8; 32]) -> Aes256Gcm {
+    Aes256Gcm::new(&(*key).into())
+}
+
+fn decode_field(name: &str, value: &str) -> Result<Vec<u8>, KeystoreError> {
+    hex::decode(value).map_err(|_| KeystoreError::Malformed(format!("{} is not valid hex", name)))
+}
+
+impl Wallet {
+    /// Сохраняет кошелёк в файл, шифруя приватный ключ паролем.
+    /// Публичный ключ и адрес лежат открыто, чтобы файл можно было
+    /// опознать без пароля.
+    pub fn save_encrypted<P: AsRef<Path>>(
+        &self,
+        path: P,
+        passphrase: &str,
+    ) -> Result<(), KeystoreError> {
+        let mut rng = OsRng;
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut nonce);
+
+        let mut secret = hex::decode(&self.private_key)
+            .map_err(|_| KeystoreError::Malformed("wallet private key is not valid hex".into()))?;
+        // У HD-кошелька вместе с ключом шифруется и chain code
+        if let Some(chain_code) = &self.chain_code {
+            secret.extend_from_slice(chain_code);
+        }
+
+        let key = derive_key(passphrase, &salt, PBKDF2_ITERATIONS);
+        let cipher = cipher(&key);
+        let ciphertext = cipher
+            .encrypt(&Nonce::from(nonce), secret.as_slice())
+            .map_err(|_| KeystoreError::Malformed("encryption failed".into()))?;
+
+        let file = KeystoreFile {
+            version: KEYSTORE_VERSION,
+            address: self.get_address().to_string(),
+            public_key: self.public_key.clone(),
+            iterations: PBKDF2_ITERATIONS,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        };
+
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| KeystoreError::Malformed(e.to_string()))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Загружает кошелёк из зашифрованного файла. Неверный пароль и
+    /// повреждённый шифртекст неразличимы: оба дают `WrongPassphrase`.
+    pub fn load_encrypted<P: AsRef<Path>>(
+        path: P,
+        passphrase: &str,
+    ) -> Result<Wallet, KeystoreError> {
+        let json = fs::read_to_string(path)?;
+        let file: KeystoreFile =
+            serde_json::from_str(&json).map_err(|e| KeystoreError::Malformed(e.to_string()))?;
+
+        if file.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(file.version));
+        }
+
+        let salt = decode_field("salt", &file.salt)?;
+        let nonce = decode_field("nonce", &file.nonce)?;
+        let ciphertext = decode_field("ciphertext", &file.ciphertext)?;
+        let nonce: [u8; NONCE_LEN] = nonce
+            .try_into()
+            .map_err(|_| KeystoreError::Malformed("nonce has wrong length".into()))?;
+
+        let key = derive_key(passphrase, &salt, file.iterations);
+        let cipher = cipher(&key);
+        let secret = cipher
+            .decrypt(&Nonce::from(nonce), ciphertext.as_slice())
+            .map_err(|_| KeystoreError::WrongPassphrase)?;
+
+        let (secret, chain_code) = match secret.len() {
+            32 => (&secret[..], None),
+            64 => {
+                let mut chain_code = [0u8; 32];
+                chain_code.copy_from_slice(&secret[32..]);
+                (&secret[..32], Some(chain_code))
+            }
+            _ => {
+                return Err(KeystoreError::Malformed(
+                    "decrypted key has wrong length".into(),
+                ))
+            }
+        };
+
+        let secret_key = SecretKey::from_slice(secret)
+            .map_err(|_| KeystoreError::Malformed("decrypted key is invalid".into()))?;
+        let wallet = Wallet {
+            chain_code,
+            ..Wallet::from_secret_key(&secret_key)
+        };
+
+        if wallet.public_key != file.public_key {
+            return Err(KeystoreError::Malformed(
+                "public key does not match the private key".into(),
+            ));
+        }
+
+        Ok(wallet)
+    }
+}
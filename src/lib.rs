@@ -1,5 +1,103 @@
+pub mod amount;
+pub mod backup;
+pub mod banlist;
 pub mod block;
+pub mod blockstore;
+pub mod bootstrap;
+pub mod clock;
+pub mod compact;
+pub mod config;
+pub mod connections;
+pub mod consensus;
+pub mod contacts;
+pub mod error;
+pub mod events;
+#[cfg(feature = "explorer")]
+pub mod explorer;
+#[cfg(feature = "faucet")]
+pub mod faucet;
+#[cfg(any(test, feature = "testutil"))]
+pub mod fixtures;
+pub mod genesis;
+pub mod gossip;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hashing;
+pub mod hd;
+pub mod health;
+pub mod inventory;
+pub mod keystore;
+pub mod light;
+pub mod merkle;
+pub mod metrics;
+pub mod mining;
+pub mod mnemonic;
+pub mod multisig;
 pub mod network;
+pub mod offline;
+pub mod orphans;
+pub mod protocol;
+pub mod pruning;
+pub mod ratelimit;
+pub mod resync;
+pub mod rpc;
+pub mod script;
+pub mod selection;
+pub mod shell;
+pub mod signer;
+pub mod simulation;
+pub mod snapshot;
+pub mod storage;
+pub mod supply;
+#[cfg(any(test, feature = "testutil"))]
+pub mod testutil;
+pub mod tokens;
+pub mod transport;
+pub mod types;
+#[cfg(feature = "upnp")]
+pub mod upnp;
+pub mod utxo;
+pub mod validation;
+pub mod watch;
+pub mod websocket;
 
-pub use block::{Block, Blockchain, MemPool, Transaction, Wallet};
+pub use amount::Amount;
+pub use banlist::{BanList, BannedPeer, Misbehavior};
+pub use block::{
+    Block, BlockHeader, BlockStatus, Blockchain, LockTime, MemPool, Reorg, Transaction, TxStatus,
+    Wallet,
+};
+#[cfg(feature = "sled")]
+pub use blockstore::SledStore;
+pub use blockstore::{BlockStore, BlockStoreKind, MemoryStore};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use config::Config;
+pub use consensus::{Consensus, ProofOfStake, ProofOfWork, SignedBlocks};
+pub use contacts::Contacts;
+pub use error::{
+    AmountError, BlockchainError, ChainError, ConfigError, ConsensusError, ContactsError, FaucetError,
+    GenesisError, KeystoreError, ProtoError, PsbtError, ScriptError, ShellError, StoreError, SupplyError,
+    SyncError, TokenError, TxValidationError, UpnpError, UtxoError, ValidationError, WalletError,
+};
+pub use events::{EventReceiver, NodeEvent};
+pub use genesis::{ChainMode, GenesisConfig};
+pub use hashing::{HashAlgorithm, Hasher};
+pub use light::LightClient;
+pub use merkle::MerkleProof;
+pub use mining::{MiningOptions, MiningStats};
+pub use multisig::{Cosignature, MultisigPolicy};
 pub use network::Node;
+pub use offline::PartiallySignedTransaction;
+pub use protocol::Message;
+pub use pruning::BlockArchive;
+pub use ratelimit::RateLimiter;
+pub use script::Script;
+pub use selection::{SelectionStrategy, TxSelection};
+pub use signer::Signer;
+pub use snapshot::StateSnapshot;
+pub use supply::SupplyAudit;
+pub use tokens::{TokenInfo, TokenLedger, TokenOp};
+pub use transport::Transport;
+pub use types::{Address, Hash256, Signature, Target};
+pub use validation::{StateView, ValidationContext};
+pub use watch::WatchWallet;
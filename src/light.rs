@@ -0,0 +1,150 @@
+use crate::block::{
+    median_time, unix_now, Block, BlockHeader, MAX_FUTURE_BLOCK_TIME, MEDIAN_TIME_SPAN,
+};
+use crate::error::SyncError;
+use crate::genesis::GenesisConfig;
+use crate::merkle::MerkleProof;
+use crate::network::{send_request, HEADERS_BATCH_SIZE};
+use crate::protocol::Message;
+use crate::transport::Transport;
+use crate::types::Hash256;
+
+// ========== LIGHT CLIENT ==============
+// Лёгкий клиент (SPV) хранит только заголовки. Он проверяет их связность,
+// время и работу, а подтверждение транзакции получает от полного узла
+// в виде merkle-пути и сверяет с корнем своего заголовка. Подписи
+// производителей и сами транзакции не проверяются, поэтому клиент
+// годится только для PoW-сетей. Ветвления не обрабатываются: заголовок,
+// не продолжающий нашу вершину, отвергается.
+
+#[derive(Debug, Clone)]
+pub struct LightClient {
+    headers: Vec<BlockHeader>,
+    /// Хеш последнего заголовка, чтобы не пересчитывать его.
+    tip_hash: Hash256,
+    /// Транспорт к полным узлам; должен совпадать с их транспортом.
+    pub transport: Transport,
+}
+
+impl LightClient {
+    /// Клиент, знающий только заголовок genesis-блока.
+    pub fn new(genesis: BlockHeader) -> Self {
+        LightClient {
+            tip_hash: genesis.compute_hash(),
+            headers: vec![genesis],
+            transport: Transport::Plain,
+        }
+    }
+
+    /// Клиент, уже доверяющий `headers` (непустой цепочке от genesis) —
+    /// например, заголовкам проверенных блоков полного узла.
+    pub fn from_headers(headers: Vec<BlockHeader>) -> Self {
+        assert!(!headers.is_empty(), "header chain must start with genesis");
+        LightClient {
+            tip_hash: headers[headers.len() - 1].compute_hash(),
+            headers,
+            transport: Transport::Plain,
+        }
+    }
+
+    /// Клиент для сети из `genesis`: genesis-блок строится так же, как
+    /// у полных узлов.
+    pub fn for_network(genesis: &GenesisConfig) -> Self {
+        Self::new(genesis.block().header)
+    }
+
+    pub fn height(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn tip(&self) -> &BlockHeader {
+        &self.headers[self.headers.len() - 1]
+    }
+
+    pub fn tip_hash(&self) -> Hash256 {
+        self.tip_hash
+    }
+
+    pub fn header(&self, index: u32) -> Option<&BlockHeader> {
+        self.headers.get(index as usize)
+    }
+
+    /// Сколько блоков, включая сам блок `index`, лежит на нём.
+    pub fn confirmations(&self, index: u32) -> u32 {
+        (self.tip().index + 1).saturating_sub(index)
+    }
+
+    /// Добавляет заголовки по порядку и останавливается на первом
+    /// неверном; уже принятые остаются. Возвращает число добавленных.
+    pub fn add_headers(&mut self, headers: &[BlockHeader]) -> Result<usize, SyncError> {
+        for header in headers {
+            let hash = header.compute_hash();
+            if !self.extends_tip(header, &hash) {
+                return Err(SyncError::InvalidHeader {
+                    index: header.index,
+                });
+            }
+            self.headers.push(*header);
+            self.tip_hash = hash;
+        }
+        Ok(headers.len())
+    }
+
+    /// Заголовок продолжает вершину: ссылается на неё, несёт работу по
+    /// своей цели и проходит те же правила времени, что и полный блок.
+    fn extends_tip(&self, header: &BlockHeader, hash: &Hash256) -> bool {
+        let tip = self.tip();
+        let start = self.headers.len().saturating_sub(MEDIAN_TIME_SPAN);
+        let median = median_time(self.headers[start..].iter().map(|h| h.timestamp).collect());
+
+        header.index == tip.index + 1
+            && header.prev_hash == self.tip_hash
+            && header.hash_algorithm == tip.hash_algorithm
+            && Block::meets_target(hash, header.bits)
+            && header.timestamp > median
+            && header.timestamp <= unix_now().saturating_add(MAX_FUTURE_BLOCK_TIME)
+    }
+
+    /// Проверяет включение `txid` в блок `index` по нашему заголовку.
+    pub fn verify_inclusion(&self, txid: &Hash256, index: u32, proof: &MerkleProof) -> bool {
+        self.header(index)
+            .is_some_and(|header| header.verify_merkle_proof(txid, proof))
+    }
+
+    /// Догружает заголовки с пира, пока тот не вернёт пустую пачку.
+    /// Возвращает число добавленных.
+    pub fn sync(&mut self, peer_addr: &str) -> Result<usize, SyncError> {
+        let mut added = 0;
+        loop {
+            let start = self.height() as u32;
+            let request = Message::GetHeaders {
+                start,
+                end: start + HEADERS_BATCH_SIZE as u32,
+            };
+            let headers = match send_request(peer_addr, &request, &self.transport) {
+                Some(Message::Headers { headers }) => headers,
+                Some(_) => return Err(SyncError::UnexpectedReply),
+                None => return Err(SyncError::PeerUnreachable),
+            };
+            if headers.is_empty() {
+                return Ok(added);
+            }
+            added += self.add_headers(&headers)?;
+        }
+    }
+
+    /// Просит у пира доказательство включения `txid` и сверяет его со
+    /// своими заголовками. Возвращает высоту блока с транзакцией.
+    pub fn verify_transaction(&self, peer_addr: &str, txid: &Hash256) -> Result<u32, SyncError> {
+        let request = Message::GetMerkleProof { txid: *txid };
+        match send_request(peer_addr, &request, &self.transport) {
+            Some(Message::MerkleProof { txid: got, proof }) if got == *txid => match proof {
+                Some((index, proof)) if self.verify_inclusion(txid, index, &proof) => Ok(index),
+                Some(_) => Err(SyncError::InvalidProof(*txid)),
+                None => Err(SyncError::TransactionNotFound(*txid)),
+            },
+            Some(_) => Err(SyncError::UnexpectedReply),
+            None => Err(SyncError::PeerUnreachable),
+        }
+    }
+}
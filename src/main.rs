@@ -1,13 +1,1075 @@
-mod block;
-mod network;
-
-use block::{Transaction, Wallet};
-use network::Node;
-use std::io::{self, Write};
+use blockchain_p2p::block::MAX_BLOCK_TRANSACTIONS;
+use blockchain_p2p::connections::CONNECT_INTERVAL;
+use blockchain_p2p::contacts::CONTACTS_FILE_NAME;
+#[cfg(feature = "explorer")]
+use blockchain_p2p::explorer;
+#[cfg(feature = "faucet")]
+use blockchain_p2p::faucet::Faucet;
+#[cfg(feature = "grpc")]
+use blockchain_p2p::grpc::start_grpc_server;
+use blockchain_p2p::mining::MiningOptions;
+use blockchain_p2p::rpc::{start_rpc_server, DEFAULT_RPC_PORT};
+use blockchain_p2p::shell::{Shell, ShellCommand};
+use blockchain_p2p::websocket::start_ws_server;
+use blockchain_p2p::{
+    Address, Amount, Blockchain, Config, Contacts, GenesisConfig, LockTime, Node,
+    PartiallySignedTransaction, SelectionStrategy, Transaction, Wallet, WatchWallet,
+};
+use clap::{Args, Parser, Subcommand};
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{IpAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process;
 use std::thread;
 use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+
+// ========== CLI ==============
+#[derive(Parser)]
+#[command(
+    name = "blockchain",
+    version,
+    about = "Educational P2P blockchain node"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Уровень логов: error, warn, info, debug или trace. Переменная
+    /// RUST_LOG важнее флага, флаг важнее `log_level` из конфигурации узла.
+    #[arg(long, global = true)]
+    log_level: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Управление узлом
+    #[command(subcommand)]
+    Node(NodeCommand),
+    /// Кошельки и балансы
+    #[command(subcommand)]
+    Wallet(WalletCommand),
+    /// Транзакции
+    #[command(subcommand)]
+    Tx(TxCommand),
+    /// Адресная книга: имена вместо адресов в `tx send --to`
+    #[command(subcommand)]
+    Contacts(ContactsCommand),
+    /// Операции над файлом цепи
+    #[command(subcommand)]
+    Chain(ChainCommand),
+    /// Добыть блоки в файл цепи без запуска узла
+    Mine(MineArgs),
+    /// Запустить узел и управлять кошельком из командной строки
+    Shell(ShellArgs),
+    /// Демонстрационный сценарий из трёх узлов (alice, bob, charlie)
+    Demo {
+        #[arg(default_value = "alice")]
+        role: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum NodeCommand {
+    /// Запустить P2P-узел и RPC-сервер
+    Start(NodeStartArgs),
+}
+
+/// Флаги переопределяют файл `--config` и переменные `BLOCKCHAIN_*`.
+#[derive(Args)]
+struct NodeStartArgs {
+    /// Файл конфигурации узла (TOML)
+    #[arg(long)]
+    config: Option<PathBuf>,
+    #[arg(long)]
+    port: Option<u16>,
+    /// Принимать пиров на этом адресе, например 0.0.0.0 или :: (можно
+    /// повторять; по умолчанию 127.0.0.1)
+    #[arg(long = "listen", value_name = "IP")]
+    listen_addrs: Vec<IpAddr>,
+    #[arg(long)]
+    rpc_port: Option<u16>,
+    /// Запустить HTML-обозреватель цепи на этом порту
+    #[cfg(feature = "explorer")]
+    #[arg(long)]
+    explorer_port: Option<u16>,
+    /// Включить POST /faucet с кошельком из этого keystore
+    #[cfg(feature = "faucet")]
+    #[arg(long)]
+    faucet_key: Option<PathBuf>,
+    /// Сколько монет кран выдаёт за раз
+    #[cfg(feature = "faucet")]
+    #[arg(long, requires = "faucet_key")]
+    faucet_amount: Option<Amount>,
+    /// Адреса пиров вида 127.0.0.1:3001 (можно повторять)
+    #[arg(long = "peer")]
+    peers: Vec<String>,
+    /// Seed-узлы для обнаружения пиров (можно повторять)
+    #[arg(long = "seed")]
+    seeds: Vec<String>,
+    /// DNS-сиды: имена хостов с адресами узлов сети (можно повторять)
+    #[arg(long = "dns-seed")]
+    dns_seeds: Vec<String>,
+    /// Сверять вершину цепи с пирами каждые N секунд (0 — только при
+    /// подключении)
+    #[arg(long, value_name = "SECS")]
+    resync_interval: Option<u64>,
+    /// Опрашивать пиров PING каждые N секунд (0 — не опрашивать)
+    #[arg(long, value_name = "SECS")]
+    ping_interval: Option<u64>,
+    #[arg(long)]
+    id: Option<String>,
+    /// Каталог с файлом цепи
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+    /// Конфигурация genesis (TOML или JSON); без неё — сеть по умолчанию
+    #[arg(long)]
+    genesis: Option<PathBuf>,
+    /// Майнить в фоне с наградой на этот адрес
+    #[arg(long)]
+    mine_to: Option<String>,
+    /// Потоков перебора nonce для --mine-to
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Предел числа транзакций в mempool
+    #[arg(long)]
+    mempool_size: Option<usize>,
+    /// Сколько секунд транзакция может ждать включения в блок
+    #[arg(long)]
+    mempool_ttl: Option<u64>,
+    /// Выбор транзакций для блока: fee-priority, oldest-first,
+    /// sender-fairness или knapsack
+    #[arg(long)]
+    tx_selection: Option<SelectionStrategy>,
+    /// Держать целиком только последние N блоков, остальные — в архиве
+    #[arg(long, value_name = "N")]
+    prune: Option<usize>,
+    /// Keystore, ключом которого подписываются добытые блоки
+    #[arg(long)]
+    block_key: Option<PathBuf>,
+    /// Шифровать соединения с пирами (ключ узла — в data_dir/node_key)
+    #[arg(long)]
+    encrypt: bool,
+    /// Пробросить порт на роутере по UPnP и сообщать пирам внешний адрес
+    #[cfg(feature = "upnp")]
+    #[arg(long)]
+    upnp: bool,
+    /// Сеть regtest: почти нулевая сложность, блоки по POST /generate
+    #[arg(long, conflicts_with = "genesis")]
+    regtest: bool,
+    /// Транслировать события узла по WebSocket на этом порту
+    #[arg(long)]
+    ws_port: Option<u16>,
+    /// Запустить gRPC API узла на этом порту
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    grpc_port: Option<u16>,
+}
+
+/// Флаги `node start` и кошелёк, от имени которого работает оболочка.
+#[derive(Args)]
+struct ShellArgs {
+    /// Keystore кошелька для переводов и наград за блоки
+    #[arg(long)]
+    keyfile: PathBuf,
+    /// Пароль keystore (по умолчанию читается из stdin)
+    #[arg(long)]
+    passphrase: Option<String>,
+    #[command(flatten)]
+    node: NodeStartArgs,
+}
+
+impl NodeStartArgs {
+    /// Итоговая конфигурация: файл, затем окружение, затем флаги.
+    fn into_config(self, log_level: Option<String>) -> Result<Config, String> {
+        let mut config = match &self.config {
+            Some(path) => Config::load(path)
+                .map_err(|e| format!("failed to load {}: {}", path.display(), e))?,
+            None => Config::default(),
+        };
+        config.apply_env().map_err(|e| e.to_string())?;
+
+        if let Some(port) = self.port {
+            config.listen_port = port;
+        }
+        if !self.listen_addrs.is_empty() {
+            config.listen_addrs = self.listen_addrs;
+        }
+        if let Some(rpc_port) = self.rpc_port {
+            config.rpc_port = rpc_port;
+        }
+        config.peers.extend(self.peers);
+        config.seeds.extend(self.seeds);
+        config.dns_seeds.extend(self.dns_seeds);
+        if let Some(resync_interval) = self.resync_interval {
+            config.resync_interval = resync_interval;
+        }
+        if let Some(ping_interval) = self.ping_interval {
+            config.ping_interval = ping_interval;
+        }
+        if let Some(id) = self.id {
+            config.node_id = id;
+        }
+        if let Some(data_dir) = self.data_dir {
+            config.data_dir = data_dir;
+        }
+        if self.genesis.is_some() {
+            config.genesis = self.genesis;
+        }
+        if let Some(miner) = &self.mine_to {
+            config.miner_address = Some(parse_address(miner)?);
+        }
+        if let Some(threads) = self.threads {
+            config.mining_threads = threads;
+        }
+        if let Some(mempool_size) = self.mempool_size {
+            config.mempool_size = mempool_size;
+        }
+        if let Some(mempool_ttl) = self.mempool_ttl {
+            config.mempool_ttl = mempool_ttl;
+        }
+        if let Some(tx_selection) = self.tx_selection {
+            config.tx_selection = tx_selection;
+        }
+        if let Some(prune) = self.prune {
+            config.prune_blocks = prune;
+        }
+        if self.encrypt {
+            config.encrypted_transport = true;
+        }
+        #[cfg(feature = "upnp")]
+        if self.upnp {
+            config.upnp = true;
+        }
+        if self.regtest {
+            config.regtest = true;
+        }
+        if let Some(log_level) = log_level {
+            config.log_level = log_level;
+        }
+        config.validate().map_err(|e| e.to_string())?;
+        Ok(config)
+    }
+}
+
+#[derive(Subcommand)]
+enum WalletCommand {
+    /// Создать кошелёк; с --keyfile сохранить его зашифрованным
+    New {
+        #[arg(long)]
+        keyfile: Option<PathBuf>,
+        #[arg(long)]
+        passphrase: Option<String>,
+    },
+    /// Баланс адреса по файлу цепи
+    Balance {
+        address: String,
+        #[arg(long, default_value = "node_chain.jsonl")]
+        chain_file: PathBuf,
+    },
+    /// Баланс и история адреса или публичного ключа без закрытого ключа
+    Watch {
+        address_or_key: String,
+        #[arg(long, default_value = "node_chain.jsonl")]
+        chain_file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum TxCommand {
+    /// Подписать перевод и отправить его узлу через RPC
+    Send(SendArgs),
+    /// Собрать неподписанный перевод для подписи на другой машине
+    Create {
+        /// Адрес или публичный ключ отправителя
+        #[arg(long)]
+        from: String,
+        /// Адрес получателя или имя из адресной книги
+        #[arg(long)]
+        to: String,
+        #[arg(long, default_value = CONTACTS_FILE_NAME)]
+        contacts: PathBuf,
+        #[arg(long)]
+        amount: Amount,
+        #[arg(long, default_value = "0.1")]
+        fee: Amount,
+        /// Куда записать перевод (base64)
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long, default_value_t = format!("127.0.0.1:{}", DEFAULT_RPC_PORT))]
+        rpc: String,
+    },
+    /// Подписать перевод из `tx create` ключом keystore (сеть не нужна)
+    Sign {
+        #[arg(long)]
+        keyfile: PathBuf,
+        #[arg(long)]
+        passphrase: Option<String>,
+        /// Файл перевода; подпись дописывается в него же
+        file: PathBuf,
+    },
+    /// Проверить подписи и отправить перевод узлу
+    Broadcast {
+        file: PathBuf,
+        #[arg(long, default_value_t = format!("127.0.0.1:{}", DEFAULT_RPC_PORT))]
+        rpc: String,
+    },
+}
+
+#[derive(Args)]
+struct SendArgs {
+    #[arg(long)]
+    from_keyfile: PathBuf,
+    #[arg(long)]
+    passphrase: Option<String>,
+    /// Адрес получателя или имя из адресной книги
+    #[arg(long)]
+    to: String,
+    #[arg(long, default_value = CONTACTS_FILE_NAME)]
+    contacts: PathBuf,
+    #[arg(long)]
+    amount: Amount,
+    #[arg(long, default_value = "0.1")]
+    fee: Amount,
+    /// Номер транзакции (по умолчанию следующий свободный). Nonce
+    /// ожидающей транзакции с большей `--fee` заменяет её в mempool
+    #[arg(long)]
+    nonce: Option<u64>,
+    /// Данные в hex для записи в цепь (не больше 512 байт, комиссия
+    /// должна покрывать 0.001 за байт)
+    #[arg(long)]
+    data: Option<String>,
+    /// Не включать в блок с индексом ниже этого
+    #[arg(long, conflicts_with = "lock_time")]
+    lock_height: Option<u64>,
+    /// Не включать в блок, добытый раньше этого unix-времени
+    #[arg(long)]
+    lock_time: Option<u64>,
+    #[arg(long, default_value_t = format!("127.0.0.1:{}", DEFAULT_RPC_PORT))]
+    rpc: String,
+}
+
+#[derive(Subcommand)]
+enum ContactsCommand {
+    /// Записать адрес под именем
+    Add {
+        name: String,
+        address: String,
+        #[arg(long, default_value = CONTACTS_FILE_NAME)]
+        file: PathBuf,
+    },
+    /// Показать все контакты
+    List {
+        #[arg(long, default_value = CONTACTS_FILE_NAME)]
+        file: PathBuf,
+    },
+    /// Удалить контакт
+    Remove {
+        name: String,
+        #[arg(long, default_value = CONTACTS_FILE_NAME)]
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ChainCommand {
+    /// Загрузить файл цепи и проверить все блоки
+    Validate {
+        #[arg(long, default_value = "node_chain.jsonl")]
+        chain_file: PathBuf,
+    },
+    /// Выгрузить цепь в сжатый архив для резервной копии
+    Export {
+        /// Файл архива
+        out: PathBuf,
+        #[arg(long, default_value = "node_chain.jsonl")]
+        chain_file: PathBuf,
+        /// Читаемый JSON-дамп (блоки, балансы, mempool) вместо архива
+        #[arg(long)]
+        json: bool,
+    },
+    /// Проверить архив и записать из него файл цепи
+    Import {
+        /// Файл архива
+        archive: PathBuf,
+        #[arg(long, default_value = "node_chain.jsonl")]
+        chain_file: PathBuf,
+        /// Файл — JSON-дамп из `chain export --json`
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args)]
+struct MineArgs {
+    /// Адрес для награды
+    #[arg(long)]
+    to: String,
+    #[arg(long, default_value_t = 1)]
+    blocks: u32,
+    #[arg(long, default_value = "node_chain.jsonl")]
+    chain_file: PathBuf,
+    /// Конфигурация genesis (TOML или JSON)
+    #[arg(long)]
+    genesis: Option<PathBuf>,
+    /// Потоков перебора nonce
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+    /// Keystore, ключом которого подписываются добытые блоки
+    #[arg(long)]
+    block_key: Option<PathBuf>,
+}
 
 fn main() {
+    let cli = Cli::parse();
+
+    // Узел настраивает логи сам: уровень может прийти из его конфигурации
+    if !matches!(cli.command, Command::Node(_) | Command::Shell(_)) {
+        if let Err(e) = init_logging(cli.log_level.as_deref().unwrap_or("info")) {
+            eprintln!("❌ {}", e);
+            process::exit(1);
+        }
+    }
+
+    let result = match cli.command {
+        Command::Node(NodeCommand::Start(args)) => run_node(args, cli.log_level),
+        Command::Wallet(command) => run_wallet(command),
+        Command::Tx(command) => run_tx(command),
+        Command::Contacts(command) => run_contacts(command),
+        Command::Chain(ChainCommand::Validate { chain_file }) => validate_chain(&chain_file),
+        Command::Chain(ChainCommand::Export {
+            out,
+            chain_file,
+            json,
+        }) => export_chain(&chain_file, &out, json),
+        Command::Chain(ChainCommand::Import {
+            archive,
+            chain_file,
+            json,
+        }) => import_chain(&archive, &chain_file, json),
+        Command::Mine(args) => run_mine(args),
+        Command::Shell(args) => run_shell(args, cli.log_level),
+        Command::Demo { role } => {
+            run_demo(&role);
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("❌ {}", e);
+        process::exit(1);
+    }
+}
+
+// ========== HELPERS ==============
+/// Логи библиотеки пишутся в stderr, чтобы не смешиваться с выводом команд.
+fn init_logging(level: &str) -> Result<(), String> {
+    let filter = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => {
+            EnvFilter::try_new(level).map_err(|e| format!("invalid log level {}: {}", level, e))?
+        }
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .init();
+    Ok(())
+}
+
+fn parse_address(s: &str) -> Result<Address, String> {
+    let address = Address::from(s);
+    address
+        .validate()
+        .map_err(|e| format!("invalid address {}: {}", s, e))?;
+    Ok(address)
+}
+
+fn read_passphrase(passphrase: Option<String>) -> Result<String, String> {
+    if let Some(passphrase) = passphrase {
+        return Ok(passphrase);
+    }
+    print!("Passphrase: ");
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    let mut line = String::new();
+    io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Кошелёк из keystore; пароль спрашивается в терминале.
+fn load_keystore(keyfile: &Path) -> Result<Wallet, String> {
+    let passphrase = read_passphrase(None)?;
+    Wallet::load_encrypted(keyfile, &passphrase)
+        .map_err(|e| format!("failed to load {}: {}", keyfile.display(), e))
+}
+
+/// Загружает цепь из файла или создаёт новую из genesis-конфигурации,
+/// если файла ещё нет. Загруженная цепь должна начинаться с того же genesis.
+fn load_or_create(chain_file: &Path, genesis: Option<&Path>) -> Result<Blockchain, String> {
+    let config = match genesis {
+        Some(path) => GenesisConfig::load(path)
+            .map_err(|e| format!("failed to load {}: {}", path.display(), e))?,
+        None => GenesisConfig::default(),
+    };
+
+    let empty = Blockchain::new(&config);
+    if !chain_file.exists() {
+        return Ok(empty);
+    }
+    let genesis = empty.chain[0].clone();
+    let bc = empty
+        .restore_from_disk(chain_file)
+        .map_err(|e| format!("failed to load {}: {}", chain_file.display(), e))?;
+    if bc.chain[0] != genesis {
+        return Err(format!(
+            "{} belongs to another network (chain id {})",
+            chain_file.display(),
+            bc.chain_id()
+        ));
+    }
+    Ok(bc)
+}
+
+/// Минимальный HTTP-клиент для RPC узла.
+fn rpc_call(rpc: &str, method: &str, path: &str, body: &str) -> Result<Value, String> {
+    let mut stream =
+        TcpStream::connect(rpc).map_err(|e| format!("cannot reach RPC at {}: {}", rpc, e))?;
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        rpc,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .map_err(|e| e.to_string())?;
+    let (head, payload) = reply
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| "malformed RPC reply".to_string())?;
+    let value: Value = serde_json::from_str(payload).map_err(|e| e.to_string())?;
+
+    if !head.starts_with("HTTP/1.1 200") {
+        return Err(format!("RPC error: {}", value["error"]));
+    }
+    Ok(value)
+}
+
+// ========== COMMANDS ==============
+/// Запускает узел со всеми серверами и фоновыми задачами по флагам
+/// `node start`; ctrl-c сохраняет цепь и завершает процесс.
+fn launch_node(args: NodeStartArgs, log_level: Option<String>) -> Result<(Node, Config), String> {
+    #[cfg(feature = "explorer")]
+    let explorer_port = args.explorer_port;
+    #[cfg(feature = "faucet")]
+    let (faucet_key, faucet_amount) = (args.faucet_key.clone(), args.faucet_amount);
+    let block_key = args.block_key.clone();
+    let ws_port = args.ws_port;
+    #[cfg(feature = "grpc")]
+    let grpc_port = args.grpc_port;
+    let config = args.into_config(log_level)?;
+    init_logging(&config.log_level)?;
+    let node = Node::from_config(&config).map_err(|e| e.to_string())?;
+    if let Some(keyfile) = &block_key {
+        let wallet = load_keystore(keyfile)?;
+        node.blockchain.blocking_write().sign_blocks_with(wallet);
+    }
+    #[cfg(feature = "faucet")]
+    if let Some(keyfile) = &faucet_key {
+        let mut faucet = Faucet::new(load_keystore(keyfile)?);
+        if let Some(amount) = faucet_amount {
+            faucet.amount = amount;
+        }
+        println!("🚰 Faucet enabled: {}", faucet.address());
+        node.enable_faucet(faucet);
+    }
+
+    let server = node.clone_node();
+    let listen = config.listen_sockets();
+    thread::spawn(move || {
+        if let Err(e) = server.start_server_on(&listen) {
+            eprintln!("❌ Failed to start P2P server: {}", e);
+        }
+    });
+
+    let rpc_node = node.clone_node();
+    let rpc_port = config.rpc_port;
+    thread::spawn(move || {
+        if let Err(e) = start_rpc_server(&rpc_node, rpc_port) {
+            eprintln!("❌ Failed to start RPC server: {}", e);
+        }
+    });
+    #[cfg(feature = "explorer")]
+    if let Some(explorer_port) = explorer_port {
+        let explorer_node = node.clone_node();
+        thread::spawn(move || {
+            if let Err(e) = explorer::start_explorer(&explorer_node, explorer_port) {
+                eprintln!("❌ Failed to start explorer: {}", e);
+            }
+        });
+    }
+    if let Some(ws_port) = ws_port {
+        let ws_node = node.clone_node();
+        thread::spawn(move || {
+            if let Err(e) = start_ws_server(&ws_node, ws_port) {
+                eprintln!("❌ Failed to start WebSocket server: {}", e);
+            }
+        });
+    }
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_port) = grpc_port {
+        let grpc_node = node.clone_node();
+        thread::spawn(move || {
+            if let Err(e) = start_grpc_server(&grpc_node, grpc_port) {
+                eprintln!("❌ Failed to start gRPC server: {}", e);
+            }
+        });
+    }
+    thread::sleep(Duration::from_millis(300));
+
+    // Внешний адрес нужен до первых рукопожатий
+    #[cfg(feature = "upnp")]
+    if config.upnp {
+        node.start_port_mapping(config.listen_port);
+    }
+    for peer in &config.peers {
+        node.connect_to_peer(peer);
+    }
+    if !config.seeds.is_empty() {
+        node.discover_peers(&config.seeds);
+    }
+    node.bootstrap(&config.dns_seeds, &config.bootstrap_nodes);
+    node.start_connection_manager(Duration::from_secs(CONNECT_INTERVAL));
+    if config.resync_interval > 0 {
+        node.start_resync(Duration::from_secs(config.resync_interval));
+    }
+    if config.ping_interval > 0 {
+        node.start_ping(Duration::from_secs(config.ping_interval));
+    }
+
+    if let Some(miner) = &config.miner_address {
+        node.start_mining_with(
+            miner.clone(),
+            MiningOptions::with_threads(config.mining_threads),
+        );
+    }
+
+    let stopping = node.clone_node();
+    ctrlc::set_handler(move || match stopping.shutdown() {
+        Ok(()) => process::exit(0),
+        Err(e) => {
+            eprintln!("❌ Failed to save chain: {}", e);
+            process::exit(1);
+        }
+    })
+    .map_err(|e| format!("failed to install ctrl-c handler: {}", e))?;
+    Ok((node, config))
+}
+
+fn run_node(args: NodeStartArgs, log_level: Option<String>) -> Result<(), String> {
+    let (node, config) = launch_node(args, log_level)?;
+    let chain_file = config.chain_file();
+
+    println!("✅ Node [{}] is running. Type 'info' or 'quit'.", node.id);
+    let save = |node: &Node| {
+        let bc = node.blockchain.blocking_read();
+        match bc.save_to_disk(&chain_file) {
+            Ok(()) => println!("💾 Chain saved to {}", chain_file.display()),
+            Err(e) => eprintln!("❌ Failed to save chain: {}", e),
+        }
+    };
+
+    for line in io::stdin().lock().lines() {
+        match line.map_err(|e| e.to_string())?.trim() {
+            "info" => println!("{}", node.get_node_info()),
+            "save" => save(&node),
+            "quit" | "exit" => {
+                node.shutdown()
+                    .map_err(|e| format!("failed to save chain: {}", e))?;
+                println!("💾 Chain saved to {}", chain_file.display());
+                return Ok(());
+            }
+            "" => {}
+            other => println!("Unknown command '{}'. Use 'info', 'save' or 'quit'", other),
+        }
+    }
+
+    // stdin закрыт (узел запущен в фоне) — работаем до ctrl-c
+    loop {
+        thread::park();
+    }
+}
+
+fn run_shell(args: ShellArgs, log_level: Option<String>) -> Result<(), String> {
+    let passphrase = read_passphrase(args.passphrase)?;
+    let wallet = Wallet::load_encrypted(&args.keyfile, &passphrase)
+        .map_err(|e| format!("failed to load {}: {}", args.keyfile.display(), e))?;
+    // Без --log-level только предупреждения, чтобы логи не перебивали ввод
+    let log_level = log_level.or_else(|| Some("warn".to_string()));
+    let (node, config) = launch_node(args.node, log_level)?;
+
+    let shell = Shell::new(node.clone_node(), wallet);
+    shell.watch_blocks(|notice| println!("\n{}", notice));
+    println!(
+        "✅ Node [{}] is running with wallet {}. Type 'help' for commands.",
+        node.id,
+        shell.address()
+    );
+
+    let mut stdin = io::stdin().lock();
+    loop {
+        print!("> ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match line.parse::<ShellCommand>() {
+            Ok(ShellCommand::Quit) => break,
+            Ok(command) => shell.execute(&command),
+            Err(e) => Err(e),
+        };
+        match reply {
+            Ok(output) => println!("{}", output),
+            Err(e) => println!("❌ {}", e),
+        }
+    }
+
+    node.shutdown()
+        .map_err(|e| format!("failed to save chain: {}", e))?;
+    println!("💾 Chain saved to {}", config.chain_file().display());
+    Ok(())
+}
+
+fn run_wallet(command: WalletCommand) -> Result<(), String> {
+    match command {
+        WalletCommand::New {
+            keyfile,
+            passphrase,
+        } => {
+            let wallet = Wallet::new();
+            println!("Address:    {}", wallet.get_address());
+            println!("Public key: {}", wallet.public_key);
+
+            match keyfile {
+                Some(path) => {
+                    let passphrase = read_passphrase(passphrase)?;
+                    wallet
+                        .save_encrypted(&path, &passphrase)
+                        .map_err(|e| e.to_string())?;
+                    println!("🔐 Keystore written to {}", path.display());
+                }
+                None => println!("Private key: {}", wallet.private_key),
+            }
+            Ok(())
+        }
+        WalletCommand::Balance {
+            address,
+            chain_file,
+        } => {
+            let address = parse_address(&address)?;
+            let bc = Blockchain::load_from_disk(&chain_file)
+                .map_err(|e| format!("failed to load {}: {}", chain_file.display(), e))?;
+            println!("{} -> {}", address, bc.get_balance(&address));
+            Ok(())
+        }
+        WalletCommand::Watch {
+            address_or_key,
+            chain_file,
+        } => {
+            let watch = match parse_address(&address_or_key) {
+                Ok(address) => WatchWallet::from_address(address),
+                Err(_) => WatchWallet::from_public_key(&address_or_key),
+            }
+            .map_err(|e| e.to_string())?;
+            let bc = Blockchain::load_from_disk(&chain_file)
+                .map_err(|e| format!("failed to load {}: {}", chain_file.display(), e))?;
+            println!("{} -> {}", watch.address(), watch.balance(&bc));
+            for entry in watch.history(&bc) {
+                let net = entry.net_amount();
+                let change =
+                    Amount::from_units(u64::try_from(net.unsigned_abs()).unwrap_or(u64::MAX));
+                let sign = if net < 0 { '-' } else { '+' };
+                println!(
+                    "#{:<6} {:>14} {}",
+                    entry.height,
+                    format!("{}{}", sign, change),
+                    entry.tx.id()
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+fn run_tx(command: TxCommand) -> Result<(), String> {
+    match command {
+        TxCommand::Create {
+            from,
+            to,
+            contacts,
+            amount,
+            fee,
+            out,
+            rpc,
+        } => {
+            let watch = match parse_address(&from) {
+                Ok(address) => WatchWallet::from_address(address),
+                Err(_) => WatchWallet::from_public_key(&from),
+            }
+            .map_err(|e| e.to_string())?;
+            let to = resolve_recipient(&to, &contacts)?;
+            let (chain_id, nonce) = chain_id_and_nonce(&rpc, watch.address())?;
+            let tx = watch.unsigned_transaction(&chain_id, &to, amount, fee, nonce);
+            fs::write(&out, PartiallySignedTransaction::new(tx).to_base64())
+                .map_err(|e| format!("failed to write {}: {}", out.display(), e))?;
+            println!("📝 Unsigned transaction written to {}", out.display());
+            Ok(())
+        }
+        TxCommand::Sign {
+            keyfile,
+            passphrase,
+            file,
+        } => {
+            let mut psbt = read_psbt(&file)?;
+            let passphrase = read_passphrase(passphrase)?;
+            let wallet =
+                Wallet::load_encrypted(&keyfile, &passphrase).map_err(|e| e.to_string())?;
+            psbt.sign(&wallet).map_err(|e| e.to_string())?;
+            fs::write(&file, psbt.to_base64())
+                .map_err(|e| format!("failed to write {}: {}", file.display(), e))?;
+            let (signatures, required) = psbt.signatures();
+            println!("✍️  Signed: {} of {} signatures", signatures, required);
+            Ok(())
+        }
+        TxCommand::Broadcast { file, rpc } => {
+            let tx = read_psbt(&file)?.finalize().map_err(|e| e.to_string())?;
+            let body = serde_json::to_string(&tx).map_err(|e| e.to_string())?;
+            let reply = rpc_call(&rpc, "POST", "/transactions", &body)?;
+            println!("✅ Transaction accepted: {}", reply["txid"]);
+            Ok(())
+        }
+        TxCommand::Send(args) => send_transaction(args),
+    }
+}
+
+fn read_psbt(file: &Path) -> Result<PartiallySignedTransaction, String> {
+    let data = fs::read_to_string(file)
+        .map_err(|e| format!("failed to read {}: {}", file.display(), e))?;
+    PartiallySignedTransaction::import(&data).map_err(|e| e.to_string())
+}
+
+/// Адрес получателя: сам адрес или имя из адресной книги `contacts`.
+fn resolve_recipient(to: &str, contacts: &Path) -> Result<Address, String> {
+    match parse_address(to) {
+        Ok(address) => Ok(address),
+        Err(e) => Contacts::load_or_default(contacts)
+            .and_then(|book| book.resolve(to).cloned())
+            .map_err(|_| e),
+    }
+}
+
+/// Сеть узла и следующий свободный nonce адреса по его RPC.
+fn chain_id_and_nonce(rpc: &str, address: &Address) -> Result<(String, u64), String> {
+    let account = rpc_call(rpc, "GET", &format!("/balance/{}", address), "")?;
+    let nonce = account["next_nonce"]
+        .as_u64()
+        .ok_or_else(|| "RPC did not return a nonce".to_string())?;
+    let info = rpc_call(rpc, "GET", "/info", "")?;
+    let chain_id = info["chain_id"]
+        .as_str()
+        .ok_or_else(|| "RPC did not return a chain id".to_string())?;
+    Ok((chain_id.to_string(), nonce))
+}
+
+fn send_transaction(args: SendArgs) -> Result<(), String> {
+    let SendArgs {
+        from_keyfile,
+        passphrase,
+        to,
+        contacts,
+        amount,
+        fee,
+        nonce,
+        data,
+        lock_height,
+        lock_time,
+        rpc,
+    } = args;
+
+    let data = match data {
+        Some(data) => hex::decode(data.trim()).map_err(|e| format!("invalid --data: {}", e))?,
+        None => Vec::new(),
+    };
+    let passphrase = read_passphrase(passphrase)?;
+    let wallet = Wallet::load_encrypted(&from_keyfile, &passphrase).map_err(|e| e.to_string())?;
+    let to = resolve_recipient(&to, &contacts)?;
+
+    let (chain_id, next_nonce) = chain_id_and_nonce(&rpc, &wallet.get_address())?;
+    let nonce = nonce.unwrap_or(next_nonce);
+
+    let mut tx: Transaction =
+        wallet.create_transaction_with_data(&chain_id, &to, amount, fee, nonce, data);
+    let lock_time = lock_height
+        .map(LockTime::Height)
+        .or(lock_time.map(LockTime::Timestamp));
+    if lock_time.is_some() {
+        tx.lock_time = lock_time;
+        tx.signature = wallet.sign_transaction(&tx.signing_payload());
+    }
+    let body = serde_json::to_string(&tx).map_err(|e| e.to_string())?;
+    let reply = rpc_call(&rpc, "POST", "/transactions", &body)?;
+    println!("✅ Transaction accepted: {}", reply["txid"]);
+    Ok(())
+}
+
+fn run_contacts(command: ContactsCommand) -> Result<(), String> {
+    let load = |file: &Path| {
+        Contacts::load_or_default(file)
+            .map_err(|e| format!("failed to load {}: {}", file.display(), e))
+    };
+    match command {
+        ContactsCommand::Add {
+            name,
+            address,
+            file,
+        } => {
+            let mut contacts = load(&file)?;
+            contacts
+                .add(&name, Address::from(address.as_str()))
+                .map_err(|e| e.to_string())?;
+            contacts.save(&file).map_err(|e| e.to_string())?;
+            println!("✅ {} -> {}", name, address);
+        }
+        ContactsCommand::List { file } => {
+            for (name, address) in load(&file)?.iter() {
+                println!("{:<20} {}", name, address);
+            }
+        }
+        ContactsCommand::Remove { name, file } => {
+            let mut contacts = load(&file)?;
+            let address = contacts
+                .remove(&name)
+                .ok_or_else(|| format!("unknown contact {}", name))?;
+            contacts.save(&file).map_err(|e| e.to_string())?;
+            println!("🗑  {} ({})", name, address);
+        }
+    }
+    Ok(())
+}
+
+fn validate_chain(chain_file: &Path) -> Result<(), String> {
+    let bc = Blockchain::load_from_disk(chain_file)
+        .map_err(|e| format!("{} is invalid: {}", chain_file.display(), e))?;
+    let audit = bc
+        .audit_supply()
+        .map_err(|e| format!("{} fails the supply audit: {}", chain_file.display(), e))?;
+    println!("✅ {} is valid", chain_file.display());
+    println!("{}", bc.chain_stats());
+    println!(
+        "💰 Issued {} coins, {} paid in fees",
+        audit.issued, audit.fees
+    );
+    Ok(())
+}
+
+fn export_chain(chain_file: &Path, out: &Path, json: bool) -> Result<(), String> {
+    let bc = Blockchain::load_from_disk(chain_file)
+        .map_err(|e| format!("failed to load {}: {}", chain_file.display(), e))?;
+    let written = if json {
+        File::create(out).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            bc.export_json(&mut writer)?;
+            writer.flush()
+        })
+    } else {
+        bc.export_archive(out)
+    };
+    written.map_err(|e| format!("failed to write {}: {}", out.display(), e))?;
+    println!("📦 {} blocks exported to {}", bc.chain.len(), out.display());
+    Ok(())
+}
+
+fn import_chain(archive: &Path, chain_file: &Path, json: bool) -> Result<(), String> {
+    if chain_file.exists() {
+        return Err(format!("{} already exists", chain_file.display()));
+    }
+    let bc = if json {
+        File::open(archive).and_then(|file| Blockchain::import_json(BufReader::new(file)))
+    } else {
+        Blockchain::import_archive(archive)
+    }
+    .map_err(|e| format!("{} is invalid: {}", archive.display(), e))?;
+    bc.save_to_disk(chain_file)
+        .map_err(|e| format!("failed to save {}: {}", chain_file.display(), e))?;
+    println!(
+        "📥 {} blocks imported to {}",
+        bc.chain.len(),
+        chain_file.display()
+    );
+    Ok(())
+}
+
+fn run_mine(args: MineArgs) -> Result<(), String> {
+    let miner = parse_address(&args.to)?;
+    let mut bc = load_or_create(&args.chain_file, args.genesis.as_deref())?;
+    if let Some(keyfile) = &args.block_key {
+        bc.sign_blocks_with(load_keystore(keyfile)?);
+    }
+
+    let options = MiningOptions::with_threads(args.threads);
+    for _ in 0..args.blocks {
+        mine_and_report(&mut bc, &miner, &options);
+    }
+
+    bc.save_to_disk(&args.chain_file)
+        .map_err(|e| format!("failed to save {}: {}", args.chain_file.display(), e))?;
+    println!("💾 Chain saved to {}", args.chain_file.display());
+    Ok(())
+}
+
+// ========== DEMO ==============
+/// Майнит блок и печатает результат; библиотека сама ничего не выводит.
+fn mine_and_report(bc: &mut Blockchain, miner: &Address, options: &MiningOptions) {
+    let difficulty_before = bc.difficulty();
+    println!(
+        "⛏️  Mining block {} with {} transactions...",
+        bc.chain.len(),
+        bc.mempool.size().min(MAX_BLOCK_TRANSACTIONS - 1) + 1
+    );
+
+    match bc.mine_block_with(miner, options) {
+        Ok((block, stats)) => {
+            println!(
+                "✅ Block mined! Hash: {}, nonce = {}, {:.0} H/s",
+                &block.hash.to_string()[0..16],
+                block.header.nonce,
+                stats.hash_rate()
+            );
+            if bc.difficulty() > difficulty_before {
+                println!("📈 Difficulty increased to: {:.2}", bc.difficulty());
+            } else if bc.difficulty() < difficulty_before {
+                println!("📉 Difficulty decreased to: {:.2}", bc.difficulty());
+            }
+        }
+        Err(e) => println!("❌ Mining failed: {}", e),
+    }
+}
+
+fn run_demo(node_role: &str) {
     println!(
         "\n\
          ╔═════════════════════════════════════╗\n\
@@ -16,21 +1078,42 @@ fn main() {
          ╚═════════════════════════════════════╝\n"
     );
 
-    let node_role = std::env::args().nth(1).unwrap_or("alice".to_string());
-
-    let (node_id, my_port, peer_addr) = match node_role.as_str() {
+    let (node_id, my_port, peer_addr) = match node_role {
         "bob" => ("Bob", 3001u16, "127.0.0.1:3000"),
         "charlie" => ("Charlie", 3002u16, "127.0.0.1:3000"),
         _ => ("Alice", 3000u16, "127.0.0.1:3001"),
     };
+    // RPC-порты идут подряд от 8545: Alice — 8545, Bob — 8546, ...
+    let rpc_port = DEFAULT_RPC_PORT + (my_port - 3000);
 
     let node = Node::new(node_id.to_string());
     println!("✅ Created node: {}", node_id);
 
+    // Восстанавливаем цепь с диска, если узел уже запускался
+    let chain_file = format!("{}_chain.jsonl", node_role);
+    if std::path::Path::new(&chain_file).exists() {
+        match Blockchain::load_from_disk(&chain_file) {
+            Ok(bc) => {
+                println!("💾 Loaded {} blocks from {}", bc.chain.len(), chain_file);
+                *node.blockchain.blocking_write() = bc;
+            }
+            Err(e) => eprintln!("❌ Failed to load {}: {}", chain_file, e),
+        }
+    }
+
     // Запускаем сервер
     let node_for_server = node.clone_node();
     thread::spawn(move || {
-        node_for_server.start_server(my_port);
+        if let Err(e) = node_for_server.start_server(my_port) {
+            eprintln!("❌ Failed to start P2P server: {}", e);
+        }
+    });
+
+    let node_for_rpc = node.clone_node();
+    thread::spawn(move || {
+        if let Err(e) = start_rpc_server(&node_for_rpc, rpc_port) {
+            eprintln!("❌ Failed to start RPC server: {}", e);
+        }
     });
 
     thread::sleep(Duration::from_millis(1000));
@@ -42,27 +1125,35 @@ fn main() {
     println!("Wallet 1 address: {}", wallet1.get_address());
     println!("Wallet 2 address: {}", wallet2.get_address());
 
+    // Награда за блок — единственный источник монет, поэтому
+    // сначала добываем блок на адрес первого кошелька
+    println!("\n⛏️  Mining reward block for wallet 1...");
+    mine_and_report(
+        &mut node.blockchain.blocking_write(),
+        &wallet1.get_address(),
+        &MiningOptions::default(),
+    );
+
     // Создаём и подписываем транзакцию
     println!("\n💳 Creating transaction...");
-    let amount = 50.0;
-    let tx_data = format!("{}->{}:{}", wallet1.get_address(), wallet2.get_address(), amount);
-    let signature = wallet1.sign_transaction(&tx_data);
-
-    let tx = Transaction::new(
-        wallet1.get_address(),
-        wallet2.get_address(),
-        amount,
-        signature,
-        wallet1.public_key.clone(),
-    );
+    let amount = Amount::from_coins(25);
+    let fee = Amount::from_units(50_000_000);
+    let nonce = node
+        .blockchain
+        .blocking_read()
+        .next_nonce(&wallet1.get_address());
+    let tx = wallet1.create_transaction(&wallet2.get_address(), amount, fee, nonce);
 
     // Добавляем в блокчейн и майним
     {
-        let mut bc = node.blockchain.lock().unwrap();
-        bc.add_transaction(tx.clone());
+        let mut bc = node.blockchain.blocking_write();
+        match bc.add_transaction(tx.clone()) {
+            Ok(()) => println!("✅ Transaction {} added to mempool", tx),
+            Err(e) => println!("❌ Transaction rejected: {}", e),
+        }
 
         println!("\n⛏️  Mining block...");
-        bc.mine_block();
+        mine_and_report(&mut bc, &wallet1.get_address(), &MiningOptions::default());
 
         println!("Chain validation: {}", bc.is_chain_valid());
         println!("Total blocks: {}", bc.chain.len());
@@ -99,7 +1190,7 @@ fn main() {
     }
 
     // Broadcast блок и транзакцию
-    if let Some(last_block) = node.blockchain.lock().unwrap().chain.last().cloned() {
+    if let Some(last_block) = node.blockchain.blocking_read().chain.last().cloned() {
         node.broadcast_block(&last_block);
     }
     node.broadcast_transaction(&tx);
@@ -108,6 +1199,7 @@ fn main() {
     println!("═══════════════════════════════════════");
     println!("Commands:");
     println!("  Type 'mine' to mine a new block");
+    println!("  Type 'start' to mine in the background, 'stop' to pause");
     println!("  Type 'tx'   to create transaction");
     println!("  Type 'info' to show node info");
     println!("  Type 'quit' to exit");
@@ -124,54 +1216,60 @@ fn main() {
 
         match command {
             "mine" => {
-                let mut bc = node.blockchain.lock().unwrap();
-                if bc.mempool.size() > 0 {
-                    bc.mine_block();
+                let mut bc = node.blockchain.blocking_write();
+                mine_and_report(&mut bc, &wallet1.get_address(), &MiningOptions::default());
+            }
+
+            "start" => {
+                if node.is_mining() {
+                    println!("⚠️  Already mining");
                 } else {
-                    println!("⚠️  No transactions to mine");
+                    node.start_mining(wallet1.get_address());
                 }
             }
 
+            "stop" => {
+                node.stop_mining();
+            }
+
             "tx" => {
-                let wallet = Wallet::new();
                 let recipient = Wallet::new();
-                let amount = 10.0;
-
-                let tx_data = format!(
-                    "{}->{}:{}",
-                    wallet.get_address(),
-                    recipient.get_address(),
-                    amount
-                );
-                let sig = wallet.sign_transaction(&tx_data);
-                let tx = Transaction::new(
-                    wallet.get_address(),
-                    recipient.get_address(),
-                    amount,
-                    sig,
-                    wallet.public_key,
+                let mut bc = node.blockchain.blocking_write();
+                let nonce = bc.next_nonce(&wallet1.get_address());
+                let tx = wallet1.create_transaction(
+                    &recipient.get_address(),
+                    Amount::from_coins(10),
+                    Amount::from_units(10_000_000),
+                    nonce,
                 );
 
-                let mut bc = node.blockchain.lock().unwrap();
-                if bc.add_transaction(tx.clone()) {
-                    println!("✅ Transaction added to mempool");
-                    node.broadcast_transaction(&tx);
+                match bc.add_transaction(tx.clone()) {
+                    Ok(()) => {
+                        println!("✅ Transaction {} added to mempool", tx);
+                        node.broadcast_transaction(&tx);
+                    }
+                    Err(e) => println!("❌ Transaction rejected: {}", e),
                 }
             }
 
             "info" => {
                 println!("\n{}", node.get_node_info());
-                let bc = node.blockchain.lock().unwrap();
+                let bc = node.blockchain.blocking_read();
                 println!("Status: {}\n", bc.chain_stats());
             }
 
             "quit" | "exit" => {
+                let bc = node.blockchain.blocking_read();
+                match bc.save_to_disk(&chain_file) {
+                    Ok(()) => println!("💾 Chain saved to {}", chain_file),
+                    Err(e) => eprintln!("❌ Failed to save chain: {}", e),
+                }
                 println!("👋 Goodbye!");
                 break;
             }
 
             _ => {
-                println!("Unknown command. Type 'mine', 'tx', 'info', or 'quit'");
+                println!("Unknown command. Type 'mine', 'start', 'stop', 'tx', 'info', or 'quit'");
             }
         }
     }
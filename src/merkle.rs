@@ -0,0 +1,95 @@
+use crate::types::Hash256;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// ========== MERKLE TREE ==============
+
+pub fn hash_pair(left: &Hash256, right: &Hash256) -> Hash256 {
+    let mut hasher = Sha256::new();
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    Hash256(hasher.finalize().into())
+}
+
+/// Строит следующий уровень дерева. Нечётный последний узел
+/// хешируется сам с собой, как в Bitcoin.
+fn next_level(level: &[Hash256]) -> Vec<Hash256> {
+    level
+        .chunks(2)
+        .map(|pair| {
+            let right = pair.get(1).unwrap_or(&pair[0]);
+            hash_pair(&pair[0], right)
+        })
+        .collect()
+}
+
+/// Корень дерева; для пустого списка листьев — нулевой хеш.
+pub fn merkle_root(leaves: &[Hash256]) -> Hash256 {
+    if leaves.is_empty() {
+        return Hash256::ZERO;
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.remove(0)
+}
+
+// ========== MERKLE PROOF ==============
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProofStep {
+    pub hash: Hash256,
+    /// `true`, если соседний узел стоит слева от текущего.
+    pub is_left: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MerkleProof {
+    pub leaf_index: usize,
+    pub steps: Vec<ProofStep>,
+}
+
+impl MerkleProof {
+    pub fn build(leaves: &[Hash256], leaf_index: usize) -> Option<MerkleProof> {
+        if leaf_index >= leaves.len() {
+            return None;
+        }
+
+        let mut steps = Vec::new();
+        let mut level = leaves.to_vec();
+        let mut index = leaf_index;
+
+        while level.len() > 1 {
+            let is_left = index % 2 == 1;
+            let sibling = if is_left {
+                level[index - 1]
+            } else {
+                *level.get(index + 1).unwrap_or(&level[index])
+            };
+            steps.push(ProofStep {
+                hash: sibling,
+                is_left,
+            });
+
+            level = next_level(&level);
+            index /= 2;
+        }
+
+        Some(MerkleProof { leaf_index, steps })
+    }
+
+    pub fn compute_root(&self, leaf: &Hash256) -> Hash256 {
+        self.steps.iter().fold(*leaf, |acc, step| {
+            if step.is_left {
+                hash_pair(&step.hash, &acc)
+            } else {
+                hash_pair(&acc, &step.hash)
+            }
+        })
+    }
+
+    pub fn verify(&self, leaf: &Hash256, root: &Hash256) -> bool {
+        self.compute_root(leaf) == *root
+    }
+}
@@ -0,0 +1,172 @@
+use crate::network::Node;
+use crate::protocol::Message;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// ========== METRICS ==============
+// Счётчики узла в текстовом формате Prometheus; RPC-сервер отдаёт их
+// по GET /metrics. Показатели цепи (высота, mempool, пиры) не хранятся
+// отдельно, а читаются из узла в момент запроса; то же для времени ответа
+// пиров (`Node::peer_stats`).
+
+/// Content-Type текстового формата Prometheus.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Счётчики, которые узел накапливает за время работы.
+#[derive(Debug, Default)]
+pub struct NodeMetrics {
+    blocks_mined: AtomicU64,
+    orphaned_blocks: AtomicU64,
+    /// Последняя скорость перебора в H/s (биты `f64`).
+    hash_rate: AtomicU64,
+    messages: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl NodeMetrics {
+    pub fn record_mined_block(&self, hash_rate: f64) {
+        self.blocks_mined.fetch_add(1, Ordering::Relaxed);
+        self.hash_rate.store(hash_rate.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Добытый блок не попал в цепь: вершина сменилась раньше.
+    pub fn record_orphaned_block(&self) {
+        self.orphaned_blocks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_message(&self, message: &Message) {
+        *self
+            .messages
+            .lock()
+            .unwrap()
+            .entry(message.kind())
+            .or_insert(0) += 1;
+    }
+
+    pub fn blocks_mined(&self) -> u64 {
+        self.blocks_mined.load(Ordering::Relaxed)
+    }
+
+    pub fn orphaned_blocks(&self) -> u64 {
+        self.orphaned_blocks.load(Ordering::Relaxed)
+    }
+
+    pub fn hash_rate(&self) -> f64 {
+        f64::from_bits(self.hash_rate.load(Ordering::Relaxed))
+    }
+
+    /// Сколько сообщений каждого типа узел получил от пиров.
+    pub fn messages_received(&self) -> BTreeMap<&'static str, u64> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+/// Все метрики узла в формате Prometheus exposition.
+pub fn render(node: &Node) -> String {
+    let (height, mempool) = {
+        let bc = node.blockchain.blocking_read();
+        (bc.chain.len(), bc.mempool.size())
+    };
+    let peers = node.peers.lock().unwrap().len();
+    let metrics = &node.metrics;
+
+    let mut out = String::new();
+    gauge(
+        &mut out,
+        "chain_height",
+        "Blocks in the local chain.",
+        height,
+    );
+    gauge(
+        &mut out,
+        "mempool_size",
+        "Transactions waiting in the mempool.",
+        mempool,
+    );
+    gauge(
+        &mut out,
+        "peers_connected",
+        "Peers in the peer table.",
+        peers,
+    );
+    gauge(
+        &mut out,
+        "hash_rate",
+        "Hashes per second while mining the last block.",
+        metrics.hash_rate(),
+    );
+    counter(
+        &mut out,
+        "blocks_mined_total",
+        "Blocks mined by this node.",
+        metrics.blocks_mined(),
+    );
+    counter(
+        &mut out,
+        "orphaned_blocks_total",
+        "Mined blocks abandoned because the tip changed.",
+        metrics.orphaned_blocks(),
+    );
+
+    header(
+        &mut out,
+        "messages_received_total",
+        "Messages received from peers.",
+        "counter",
+    );
+    for (kind, count) in metrics.messages_received() {
+        let _ = writeln!(
+            out,
+            "blockchain_messages_received_total{{type=\"{}\"}} {}",
+            kind, count
+        );
+    }
+
+    let stats = node.peer_stats();
+    header(
+        &mut out,
+        "peer_rtt_seconds",
+        "Round-trip time of the last answered ping.",
+        "gauge",
+    );
+    for peer in &stats {
+        if let Some(rtt_ms) = peer.rtt_ms {
+            let _ = writeln!(
+                out,
+                "blockchain_peer_rtt_seconds{{peer=\"{}\"}} {}",
+                peer.addr,
+                rtt_ms / 1000.0
+            );
+        }
+    }
+    header(
+        &mut out,
+        "peer_missed_pings",
+        "Pings in a row the peer did not answer.",
+        "gauge",
+    );
+    for peer in &stats {
+        let _ = writeln!(
+            out,
+            "blockchain_peer_missed_pings{{peer=\"{}\"}} {}",
+            peer.addr, peer.missed_pings
+        );
+    }
+    out
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP blockchain_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE blockchain_{} {}", name, kind);
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    header(out, name, help, "gauge");
+    let _ = writeln!(out, "blockchain_{} {}", name, value);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    header(out, name, help, "counter");
+    let _ = writeln!(out, "blockchain_{} {}", name, value);
+}
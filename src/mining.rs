@@ -0,0 +1,120 @@
+use crate::types::{Hash256, Target};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ========== MINING ==============
+// Перебор nonce в нескольких потоках. Поток `i` из `n` проверяет nonce
+// `start + i, start + i + n, …`; первый нашедший подходящий хеш
+// останавливает остальных.
+//
+// Nonce — u64, и шаг потока проверяется на переполнение: дойдя до
+// `u64::MAX`, поток останавливается, а не начинает заново с тех же
+// хешей. Исчерпанный перебор возвращает `None`, и `Block::solve` меняет
+// `extra_nonce` заголовка — это новый набор хешей для того же блока.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MiningOptions {
+    /// Число потоков перебора; 0 считается за 1.
+    pub threads: usize,
+}
+
+impl MiningOptions {
+    pub fn with_threads(threads: usize) -> Self {
+        MiningOptions { threads }
+    }
+
+    /// По потоку на каждое доступное ядро.
+    pub fn all_cores() -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_threads(threads)
+    }
+}
+
+impl Default for MiningOptions {
+    fn default() -> Self {
+        Self::with_threads(1)
+    }
+}
+
+/// Сколько хешей посчитано и за какое время.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MiningStats {
+    pub hashes: u64,
+    pub elapsed: Duration,
+}
+
+impl MiningStats {
+    /// Хешей в секунду.
+    pub fn hash_rate(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.hashes as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Ищет nonce от `start` до `u64::MAX`, при котором `header(nonce)` не
+/// превышает `target`. `None` — если перебор отменён через `cancel` или
+/// пространство nonce исчерпано. Из нескольких одновременно найденных
+/// берётся наименьший nonce, так что в одном потоке результат совпадает
+/// с простым перебором.
+pub fn search_nonce<F>(
+    header: F,
+    start: u64,
+    target: &Target,
+    options: &MiningOptions,
+    cancel: &AtomicBool,
+) -> (Option<(u64, Hash256)>, MiningStats)
+where
+    F: Fn(u64) -> Hash256 + Sync,
+{
+    let threads = options.threads.max(1) as u64;
+    let started = Instant::now();
+    let found = AtomicBool::new(false);
+    let hashes = AtomicU64::new(0);
+
+    let worker = |offset: u64| {
+        let mut next = start.checked_add(offset);
+        let mut count = 0u64;
+        let outcome = loop {
+            let Some(nonce) = next else {
+                break None;
+            };
+            if found.load(Ordering::Relaxed) || cancel.load(Ordering::Relaxed) {
+                break None;
+            }
+            let hash = header(nonce);
+            count += 1;
+            if target.is_met_by(&hash) {
+                found.store(true, Ordering::Relaxed);
+                break Some((nonce, hash));
+            }
+            next = nonce.checked_add(threads);
+        };
+        hashes.fetch_add(count, Ordering::Relaxed);
+        outcome
+    };
+
+    let solution = if threads == 1 {
+        worker(0)
+    } else {
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|offset| scope.spawn(move || worker(offset)))
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|handle| handle.join().expect("mining thread panicked"))
+                .min_by_key(|&(nonce, _)| nonce)
+        })
+    };
+
+    let stats = MiningStats {
+        hashes: hashes.into_inner(),
+        elapsed: started.elapsed(),
+    };
+    (solution, stats)
+}
@@ -0,0 +1,31 @@
+use crate::error::WalletError;
+use bip39::Mnemonic;
+
+// ========== MNEMONIC ==============
+// Сид-фразы BIP39 (английский словарь): генерация, проверка
+// контрольной суммы и получение 64-байтового сида.
+
+/// Допустимые длины фразы по BIP39.
+pub const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// Генерирует новую фразу из `word_count` слов.
+pub fn generate(word_count: usize) -> Result<String, WalletError> {
+    Mnemonic::generate(word_count)
+        .map(|m| m.to_string())
+        .map_err(|e| WalletError::InvalidMnemonic(e.to_string()))
+}
+
+/// Проверяет слова и контрольную сумму фразы.
+pub fn validate(phrase: &str) -> Result<(), WalletError> {
+    parse(phrase).map(|_| ())
+}
+
+/// Превращает фразу в сид; `passphrase` — необязательное «25-е слово».
+pub fn to_seed(phrase: &str, passphrase: &str) -> Result<[u8; 64], WalletError> {
+    Ok(parse(phrase)?.to_seed(passphrase))
+}
+
+fn parse(phrase: &str) -> Result<Mnemonic, WalletError> {
+    Mnemonic::parse_in(bip39::Language::English, phrase)
+        .map_err(|e| WalletError::InvalidMnemonic(e.to_string()))
+}
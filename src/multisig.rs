@@ -0,0 +1,132 @@
+use crate::block::verify_ecdsa;
+use crate::error::TxValidationError;
+use crate::types::{Address, Signature};
+use secp256k1::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+// ========== MULTISIG ==============
+// Адрес M-из-N: средства с него тратит транзакция, подписанная любыми M
+// из N ключей. Адрес — хеш политики (порога и отсортированных ключей) с
+// версией `SCRIPT_ADDRESS_VERSION`, поэтому сама политика в цепи не
+// хранится, пока с адреса ничего не тратится. Тратящая транзакция несёт
+// политику в поле `multisig`, а подписи — в `cosignatures`:
+//
+//   let policy = MultisigPolicy::new(2, vec![a.public_key, b.public_key, c.public_key])?;
+//   let mut tx = Transaction::new_multisig(&policy, &to, 5.0, 0.1, nonce);
+//   a.co_sign(&mut tx)?;
+//   c.co_sign(&mut tx)?;
+//
+// Подписи не входят в txid, поэтому собирать их можно по одной, передавая
+// транзакцию между владельцами ключей, и идентификатор при этом не меняется.
+
+/// Больше ключей в политике не бывает.
+pub const MAX_MULTISIG_KEYS: usize = 15;
+
+/// Порог и открытые ключи (hex, сжатые) адреса M-из-N.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigPolicy {
+    pub threshold: usize,
+    /// Отсортированы, чтобы адрес не зависел от порядка ключей.
+    pub public_keys: Vec<String>,
+}
+
+/// Подпись одного из владельцев ключей политики.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cosignature {
+    pub public_key: String,
+    pub signature: Signature,
+}
+
+impl MultisigPolicy {
+    /// Политика `threshold`-из-`public_keys.len()`. Ключи должны быть
+    /// различными и корректными, порог — от 1 до числа ключей.
+    pub fn new(threshold: usize, mut public_keys: Vec<String>) -> Result<Self, TxValidationError> {
+        public_keys.sort();
+        let policy = MultisigPolicy {
+            threshold,
+            public_keys,
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn validate(&self) -> Result<(), TxValidationError> {
+        let invalid = || TxValidationError::InvalidMultisigPolicy {
+            threshold: self.threshold,
+            keys: self.public_keys.len(),
+        };
+        if self.threshold == 0
+            || self.threshold > self.public_keys.len()
+            || self.public_keys.len() > MAX_MULTISIG_KEYS
+        {
+            return Err(invalid());
+        }
+        let sorted = self.public_keys.windows(2).all(|pair| pair[0] < pair[1]);
+        let parsed = self
+            .public_keys
+            .iter()
+            .all(|key| hex::decode(key).is_ok_and(|bytes| PublicKey::from_slice(&bytes).is_ok()));
+        if !sorted || !parsed {
+            return Err(invalid());
+        }
+        Ok(())
+    }
+
+    /// Адрес, с которого тратит эта политика.
+    pub fn address(&self) -> Address {
+        Address::from_script(self.to_string().as_bytes())
+    }
+
+    pub fn contains(&self, public_key: &str) -> bool {
+        self.public_keys.iter().any(|key| key == public_key)
+    }
+
+    /// Проверяет, что политика соответствует адресу `owner` и что среди
+    /// `cosignatures` не меньше `threshold` подписей `payload` разными
+    /// ключами политики.
+    pub fn verify(
+        &self,
+        owner: &Address,
+        payload: &str,
+        cosignatures: &[Cosignature],
+    ) -> Result<(), TxValidationError> {
+        self.validate()?;
+        if &self.address() != owner {
+            return Err(TxValidationError::PublicKeyMismatch);
+        }
+
+        let mut signers = HashSet::new();
+        for cosignature in cosignatures {
+            if !self.contains(&cosignature.public_key) {
+                return Err(TxValidationError::PublicKeyMismatch);
+            }
+            let key_bytes = hex::decode(&cosignature.public_key)
+                .map_err(|_| TxValidationError::InvalidSignature)?;
+            verify_ecdsa(
+                &cosignature.public_key,
+                &Address::from_public_key(&key_bytes),
+                payload,
+                &cosignature.signature,
+            )?;
+            signers.insert(cosignature.public_key.as_str());
+        }
+
+        if signers.len() < self.threshold {
+            return Err(TxValidationError::NotEnoughSignatures {
+                required: self.threshold,
+                provided: signers.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Каноническая запись `M:key1,key2,…` — её хеш даёт адрес, а подписи
+/// её покрывают.
+impl fmt::Display for MultisigPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.threshold, self.public_keys.join(","))
+    }
+}
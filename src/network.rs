@@ -1,24 +1,238 @@
-use crate::block::{Block, Blockchain, Transaction};
-use serde_json::{json, Value};
-use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
+use crate::banlist::{peer_ip, BanList, BannedPeer, Misbehavior};
+use crate::block::{unix_now, Block, BlockHeader, BlockStatus, Blockchain, Transaction};
+use crate::compact::CompactBlock;
+use crate::config::DEFAULT_LISTEN_PORT;
+use crate::connections::ConnectionManager;
+use crate::error::{BlockchainError, ChainError, SyncError};
+use crate::events::{EventReceiver, NodeEvent, EVENT_CHANNEL_CAPACITY};
+#[cfg(feature = "faucet")]
+use crate::faucet::Faucet;
+use crate::gossip::SeenCache;
+use crate::health::PeerHealth;
+use crate::light::LightClient;
+use crate::metrics::NodeMetrics;
+use crate::mining::MiningOptions;
+use crate::protocol::{Hello, Message, MessageCodec, PROTOCOL_VERSION};
+use crate::ratelimit::RateLimiter;
+use crate::snapshot::DEFAULT_SNAPSHOT_INTERVAL;
+use crate::transport::{self, Transport};
+use crate::types::{Address, Hash256};
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+use tokio_util::codec::Framed;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+/// Сколько пиров узел держит в таблице по умолчанию.
+pub const DEFAULT_MAX_PEERS: usize = 8;
+
+/// Сколько блоков запрашивается одним GET_BLOCKS при начальной загрузке.
+pub const IBD_BATCH_SIZE: usize = 50;
+
+/// Сколько заголовков узел отдаёт на один GET_HEADERS.
+pub const HEADERS_BATCH_SIZE: usize = 2_000;
+
+/// Сколько ждать соединения и ответа пира.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Сколько входящее соединение может молчать, прежде чем узел его закроет.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Сколько `shutdown` ждёт завершения потоков и задач узла.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Как часто HTTP-серверы узла проверяют, не пора ли остановиться.
+pub(crate) const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// ========== RUNTIME ==============
+// Сетевой ввод-вывод идёт на общем многопоточном рантайме tokio: одна
+// задача на входящее соединение, исходящие запросы — тоже задачи.
+// Публичный API `Node` остаётся блокирующим и ждёт эти задачи через
+// `block_on`, поэтому его нельзя вызывать из задач самого рантайма.
+// Цепь под `tokio::sync::RwLock`: запросы берут её на чтение и не мешают
+// друг другу. Блокирующий API ждёт блокировку через `blocking_read` и
+// `blocking_write`, а обработчики сообщений пиров — через `.await`,
+// прямо в задачах рантайма.
+
+pub(crate) fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| Runtime::new().expect("failed to start tokio runtime"))
+}
+
+// ========== SHUTDOWN ==============
+// Каждый поток и задача узла (сервер, соединение с пиром, майнер, RPC)
+// держат `WorkerGuard`. `Node::shutdown` отменяет общий токен и ждёт,
+// пока все guard будут отпущены, — это работает и для потоков, чьи
+// `JoinHandle` остались у вызывающего кода.
+
+#[derive(Debug, Default)]
+struct Workers {
+    active: Mutex<usize>,
+    idle: Condvar,
+}
+
+impl Workers {
+    /// Ждёт, пока не останется активных работников; `false` — по таймауту.
+    fn wait_idle(&self, limit: Duration) -> bool {
+        let active = self.active.lock().unwrap();
+        let (_active, result) = self
+            .idle
+            .wait_timeout_while(active, limit, |active| *active > 0)
+            .unwrap();
+        !result.timed_out()
+    }
+}
+
+/// Отмечает поток или задачу узла как работающие, пока guard жив.
+#[derive(Debug)]
+pub struct WorkerGuard(Arc<Workers>);
+
+impl Drop for WorkerGuard {
+    fn drop(&mut self) {
+        let mut active = self.0.active.lock().unwrap();
+        *active -= 1;
+        if *active == 0 {
+            self.0.idle.notify_all();
+        }
+    }
+}
+
+/// Пир, прошедший рукопожатие.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeerInfo {
+    /// Адрес, на котором пир принимает соединения.
+    pub addr: SocketAddr,
+    pub node_id: String,
+    pub version: u32,
+    /// Высота цепи пира на момент рукопожатия.
+    pub best_height: usize,
+}
+
+impl PeerInfo {
+    fn from_hello(addr: SocketAddr, hello: Hello) -> Self {
+        PeerInfo {
+            addr,
+            node_id: hello.node_id,
+            version: hello.version,
+            best_height: hello.best_height,
+        }
+    }
+}
+
+/// Адрес пира из конфигурации или CLI: `ip:порт`, `[IPv6]:порт`, голый
+/// IPv4- или IPv6-адрес (порт — `DEFAULT_LISTEN_PORT`) или `хост:порт`.
+pub fn resolve_peer_addr(addr: &str) -> Option<SocketAddr> {
+    if let Ok(addr) = addr.parse::<SocketAddr>() {
+        return Some(addr);
+    }
+    let literal = addr.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = literal.parse::<IpAddr>() {
+        return Some(SocketAddr::new(ip, DEFAULT_LISTEN_PORT));
+    }
+    addr.to_socket_addrs().ok()?.next()
+}
 
 #[derive(Clone)]
 pub struct Node {
     pub id: String,
-    pub blockchain: Arc<Mutex<Blockchain>>,
-    pub peers: Arc<Mutex<Vec<String>>>,
+    pub blockchain: Arc<RwLock<Blockchain>>,
+    pub peers: Arc<Mutex<Vec<PeerInfo>>>,
+    pub mining: Arc<AtomicBool>,
+    /// Поднимается, когда вершина цепи сменилась извне, чтобы майнер
+    /// бросил устаревшую работу.
+    pub abort_mining: Arc<AtomicBool>,
+    /// Адрес, который узел слушает; известен после `start_server`.
+    pub listen_addr: Arc<Mutex<Option<String>>>,
+    /// Адрес, по которому узел доступен снаружи NAT; известен после
+    /// проброса порта (`map_port`, см. `upnp`).
+    pub external_addr: Arc<Mutex<Option<String>>>,
+    pub max_peers: usize,
+    /// Поднят, пока идёт начальная загрузка блоков.
+    pub syncing: Arc<AtomicBool>,
+    /// txid, которые узел уже принимал или рассылал.
+    pub seen_txs: Arc<Mutex<SeenCache>>,
+    /// txid из INV, запрошенные у пира и ещё не полученные.
+    pub(crate) requested_txs: Arc<Mutex<HashSet<Hash256>>>,
+    /// Штрафные очки пиров и забаненные адреса.
+    pub bans: Arc<Mutex<BanList>>,
+    /// Известные адреса пиров и цель по исходящим соединениям.
+    pub connections: Arc<Mutex<ConnectionManager>>,
+    /// Результаты опросов пиров по адресам, см. `health`.
+    pub(crate) health: Arc<Mutex<HashMap<String, PeerHealth>>>,
+    /// Лимит входящих сообщений на IP, см. `ratelimit`.
+    pub rate_limiter: Arc<Mutex<RateLimiter>>,
+    /// Сколько ждать следующего сообщения (или его конца) от пира.
+    pub idle_timeout: Duration,
+    /// Открытый или шифрованный транспорт; у всех пиров должен быть один.
+    pub transport: Transport,
+    /// Канал событий узла, см. `events`.
+    pub(crate) events: broadcast::Sender<NodeEvent>,
+    pub metrics: Arc<NodeMetrics>,
+    /// Отменяется в `shutdown`; серверы, пиры и майнер завершаются по нему.
+    pub shutdown: CancellationToken,
+    workers: Arc<Workers>,
+    /// Куда `shutdown` сохраняет цепь; задаётся `from_config`.
+    pub chain_file: Option<PathBuf>,
+    /// Куда сохраняется снимок состояния: каждые `snapshot_interval`
+    /// блоков (0 — только при `shutdown`).
+    pub snapshot_file: Option<PathBuf>,
+    pub snapshot_interval: u32,
+    /// Куда сохраняются ожидающие транзакции: вместе со снимком
+    /// и при `shutdown`.
+    pub mempool_file: Option<PathBuf>,
+    /// Сколько последних блоков держать целиком (0 — не обрезать);
+    /// остальные уходят в архив цепи вместе со снимком, см. `pruning`.
+    pub prune_blocks: usize,
+    /// Кран `POST /faucet`; пуст, пока не включён `enable_faucet`.
+    #[cfg(feature = "faucet")]
+    pub faucet: Arc<Mutex<Option<Faucet>>>,
 }
 
 impl Node {
     pub fn new(id: String) -> Self {
         Node {
             id,
-            blockchain: Arc::new(Mutex::new(Blockchain::new())),
+            blockchain: Arc::new(RwLock::new(Blockchain::default())),
             peers: Arc::new(Mutex::new(Vec::new())),
+            mining: Arc::new(AtomicBool::new(false)),
+            abort_mining: Arc::new(AtomicBool::new(false)),
+            listen_addr: Arc::new(Mutex::new(None)),
+            external_addr: Arc::new(Mutex::new(None)),
+            max_peers: DEFAULT_MAX_PEERS,
+            syncing: Arc::new(AtomicBool::new(false)),
+            seen_txs: Arc::new(Mutex::new(SeenCache::default())),
+            requested_txs: Arc::new(Mutex::new(HashSet::new())),
+            bans: Arc::new(Mutex::new(BanList::default())),
+            connections: Arc::new(Mutex::new(ConnectionManager::default())),
+            health: Arc::new(Mutex::new(HashMap::new())),
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::default())),
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            transport: Transport::Plain,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            metrics: Arc::new(NodeMetrics::default()),
+            shutdown: CancellationToken::new(),
+            workers: Arc::new(Workers::default()),
+            chain_file: None,
+            snapshot_file: None,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            mempool_file: None,
+            prune_blocks: 0,
+            #[cfg(feature = "faucet")]
+            faucet: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -27,152 +241,816 @@ impl Node {
             id: self.id.clone(),
             blockchain: Arc::clone(&self.blockchain),
             peers: Arc::clone(&self.peers),
+            mining: Arc::clone(&self.mining),
+            abort_mining: Arc::clone(&self.abort_mining),
+            listen_addr: Arc::clone(&self.listen_addr),
+            external_addr: Arc::clone(&self.external_addr),
+            max_peers: self.max_peers,
+            syncing: Arc::clone(&self.syncing),
+            seen_txs: Arc::clone(&self.seen_txs),
+            requested_txs: Arc::clone(&self.requested_txs),
+            bans: Arc::clone(&self.bans),
+            connections: Arc::clone(&self.connections),
+            health: Arc::clone(&self.health),
+            rate_limiter: Arc::clone(&self.rate_limiter),
+            idle_timeout: self.idle_timeout,
+            transport: self.transport.clone(),
+            events: self.events.clone(),
+            metrics: Arc::clone(&self.metrics),
+            shutdown: self.shutdown.clone(),
+            workers: Arc::clone(&self.workers),
+            chain_file: self.chain_file.clone(),
+            snapshot_file: self.snapshot_file.clone(),
+            snapshot_interval: self.snapshot_interval,
+            mempool_file: self.mempool_file.clone(),
+            prune_blocks: self.prune_blocks,
+            #[cfg(feature = "faucet")]
+            faucet: Arc::clone(&self.faucet),
         }
     }
 
-    pub fn start_server(&self, port: u16) {
-        let listener = TcpListener::bind(format!("127.0.0.1:{}", port))
-            .expect("Failed to bind to port");
-        println!("🟢 Node [{}] listening on 127.0.0.1:{}", self.id, port);
+    /// Регистрирует поток или задачу узла до конца жизни guard.
+    pub fn worker(&self) -> WorkerGuard {
+        *self.workers.active.lock().unwrap() += 1;
+        WorkerGuard(Arc::clone(&self.workers))
+    }
 
-        let blockchain_clone = Arc::clone(&self.blockchain);
-        let id_clone = self.id.clone();
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
 
-        for stream in listener.incoming() {
-            match stream {
-                Ok(stream) => {
-                    let blockchain = Arc::clone(&blockchain_clone);
-                    let node_id = id_clone.clone();
-                    println!("📥 Incoming connection to Node [{}]", node_id);
+    /// Останавливает узел: закрывает порт, обрывает соединения с пирами,
+    /// останавливает майнер, ждёт завершения их потоков (не дольше
+    /// `SHUTDOWN_TIMEOUT`) и сохраняет цепь в `chain_file`, снимок
+    /// состояния в `snapshot_file` и mempool в `mempool_file`, если они
+    /// заданы. Повторный вызов только сохраняет их ещё раз.
+    pub fn shutdown(&self) -> io::Result<()> {
+        info!(node = %self.id, "shutting down");
+        self.shutdown.cancel();
+        self.stop_mining();
+        if !self.workers.wait_idle(SHUTDOWN_TIMEOUT) {
+            warn!(node = %self.id, "some workers did not stop in time");
+        }
 
-                    thread::spawn(move || {
-                        handle_client(stream, &blockchain, &node_id);
-                    });
+        let bc = self.blockchain.blocking_read();
+        if let Some(path) = &self.chain_file {
+            bc.save_to_disk(path)?;
+            info!(node = %self.id, path = %path.display(), "chain saved");
+        }
+        if let Some(path) = &self.snapshot_file {
+            bc.snapshot().save(path)?;
+        }
+        if let Some(path) = &self.mempool_file {
+            bc.mempool.save_to_disk(path)?;
+            info!(node = %self.id, transactions = bc.mempool.size(), "mempool saved");
+        }
+        Ok(())
+    }
+
+    /// Подписывает на события узла, см. `events`. Приёмник видит только
+    /// события, случившиеся после подписки.
+    pub fn subscribe(&self) -> EventReceiver {
+        self.events.subscribe()
+    }
+
+    /// Рассылает событие подписчикам; если их нет, оно просто теряется.
+    pub(crate) fn emit(&self, event: NodeEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Событие `BlockAdded` для каждого блока цепи начиная с высоты `from`.
+    fn emit_blocks_added(&self, bc: &Blockchain, from: usize) {
+        for block in bc.chain.iter().skip(from) {
+            self.emit(NodeEvent::block_added(block));
+        }
+    }
+
+    /// Сохраняет снимок состояния и mempool, если индекс вершины кратен
+    /// `snapshot_interval`; в режиме обрезки перед этим переносит старые
+    /// блоки в архив и переписывает `chain_file`. Ошибка записи не мешает
+    /// работе узла.
+    fn save_state_if_due(&self, bc: &mut Blockchain) {
+        let index = bc.latest_block().header.index;
+        if self.snapshot_interval == 0 || !index.is_multiple_of(self.snapshot_interval) {
+            return;
+        }
+        if self.prune_blocks > 0 {
+            match bc.prune(self.prune_blocks) {
+                Ok(0) => {}
+                Ok(pruned) => {
+                    info!(pruned, height = bc.pruned_height(), "archived old blocks");
+                    if let Some(path) = &self.chain_file {
+                        if let Err(e) = bc.save_to_disk(path) {
+                            warn!(index, error = %e, "failed to save pruned chain");
+                        }
+                    }
                 }
+                Err(e) => warn!(index, error = %e, "failed to prune chain"),
+            }
+        }
+        if let Some(path) = &self.snapshot_file {
+            match bc.snapshot().save(path) {
+                Ok(()) => debug!(index, "state snapshot saved"),
+                Err(e) => warn!(index, error = %e, "failed to save state snapshot"),
+            }
+        }
+        if let Some(path) = &self.mempool_file {
+            match bc.mempool.save_to_disk(path) {
+                Ok(()) => debug!(index, transactions = bc.mempool.size(), "mempool saved"),
+                Err(e) => warn!(index, error = %e, "failed to save mempool"),
+            }
+        }
+    }
+
+    /// Принимает соединения на 127.0.0.1:`port` до `shutdown`.
+    /// Каждый пир обслуживается отдельной задачей tokio. Ошибка — если
+    /// порт занять не удалось.
+    pub fn start_server(&self, port: u16) -> io::Result<()> {
+        self.start_server_on(&[SocketAddr::from((Ipv4Addr::LOCALHOST, port))])
+    }
+
+    /// Принимает соединения на всех `addrs` до `shutdown`: IPv4 и IPv6,
+    /// конкретные интерфейсы или все сразу (`0.0.0.0`, `::`). Пирам
+    /// сообщается первый адрес, который удалось занять; вместо `0.0.0.0`
+    /// и `::` — петлевой той же версии, пока внешний адрес не известен
+    /// (см. `upnp`). Адрес, который занять не удалось (порт занят, IPv6
+    /// недоступен), пропускается с предупреждением; ошибка — только
+    /// если не удалось ни один.
+    pub fn start_server_on(&self, addrs: &[SocketAddr]) -> io::Result<()> {
+        runtime().block_on(self.serve(addrs))
+    }
+
+    async fn serve(&self, addrs: &[SocketAddr]) -> io::Result<()> {
+        let mut listeners = Vec::new();
+        let mut failure = None;
+        for &addr in addrs {
+            match bind_listener(addr) {
+                Ok(listener) => listeners.push((addr, listener)),
                 Err(e) => {
-                    eprintln!("❌ Connection error: {}", e);
+                    warn!(node = %self.id, %addr, error = %e, "failed to bind");
+                    failure = Some(e);
                 }
             }
         }
+        let Some(&(first, _)) = listeners.first() else {
+            return Err(failure.unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "no listen addresses")
+            }));
+        };
+        *self.listen_addr.lock().unwrap() = Some(advertisable(first).to_string());
+        let _worker = self.worker();
+
+        let mut accepting = JoinSet::new();
+        for (_, listener) in listeners {
+            let node = self.clone_node();
+            accepting.spawn(async move { node.accept(listener).await });
+        }
+        while accepting.join_next().await.is_some() {}
+        Ok(())
     }
 
+    /// Принимает соединения одного слушающего сокета до `shutdown`.
+    async fn accept(&self, listener: TcpListener) {
+        let addr = listener.local_addr().ok();
+        info!(node = %self.id, addr = ?addr, "listening");
+        loop {
+            let accepted = tokio::select! {
+                _ = self.shutdown.cancelled() => break,
+                accepted = listener.accept() => accepted,
+            };
+            match accepted {
+                Ok((_, peer)) if self.is_banned(peer.ip()) => {
+                    debug!(node = %self.id, %peer, "refused banned peer");
+                }
+                Ok((stream, peer)) => {
+                    self.rate_limiter.lock().unwrap().prune(Instant::now());
+                    let node = self.clone_node();
+                    let span = info_span!("peer", node = %node.id, %peer);
+                    tokio::spawn(handle_client(stream, peer, node).instrument(span));
+                }
+                Err(e) => error!(node = %self.id, error = %e, "accept failed"),
+            }
+        }
+        info!(node = %self.id, addr = ?addr, "listener closed");
+    }
+
+    /// Подключается к пиру: проводит рукопожатие, заносит пира в таблицу
+    /// и догоняет его цепь. Адрес запоминает менеджер соединений, чтобы
+    /// вернуться к пиру, если тот пропадёт (см. `connections`). Адрес
+    /// разбирается `resolve_peer_addr`. `false` — пир не ответил или
+    /// несовместим (другая версия протокола, сеть или genesis).
     pub fn connect_to_peer(&self, peer_addr: &str) -> bool {
-        match TcpStream::connect(peer_addr) {
-            Ok(mut stream) => {
-                println!("🟢 Node [{}] connected to peer: {}", self.id, peer_addr);
+        // Один пир — один адрес в таблице и книге, как бы его ни записали
+        let peer_addr = &resolve_peer_addr(peer_addr)
+            .map_or_else(|| peer_addr.to_string(), |addr| addr.to_string());
+        let _span = info_span!("peer", node = %self.id, peer = %peer_addr).entered();
+        let handshake = self.handshake(peer_addr);
+        self.connections.lock().unwrap().record_attempt(
+            peer_addr,
+            handshake.is_ok(),
+            Instant::now(),
+        );
+        let info = match handshake {
+            Ok(info) => info,
+            Err(SyncError::PeerUnreachable) => {
+                warn!("peer unreachable");
+                return false;
+            }
+            Err(e) => {
+                warn!(error = %e, "peer refused");
+                return false;
+            }
+        };
 
-                self.peers.lock().unwrap().push(peer_addr.to_string());
+        info!(peer_id = %info.node_id, "connected");
+        self.add_peer(info);
+        match self.catch_up(peer_addr) {
+            Ok(0) => {}
+            Ok(added) => info!(
+                added,
+                height = self.blockchain.blocking_read().chain.len(),
+                "synced"
+            ),
+            Err(e) => warn!(error = %e, "sync failed"),
+        }
+        true
+    }
 
-                let sync_request = json!({
-                    "type": "SYNC_REQUEST",
-                    "from": self.id,
-                });
+    /// Начальная загрузка блоков (IBD) в два прохода, см. раздел
+    /// HEADERS-FIRST SYNC. Пока идёт загрузка, узел не майнит и не
+    /// принимает транзакции. Возвращает, на сколько выросла цепь.
+    pub fn sync_with_peer(&self, peer_addr: &str) -> Result<usize, SyncError> {
+        self.handshake(peer_addr)?;
+        self.catch_up(peer_addr)
+    }
 
-                if let Err(e) = stream.write_all(sync_request.to_string().as_bytes()) {
-                    eprintln!("❌ Failed to send sync request: {}", e);
-                    return false;
-                }
+    pub(crate) fn catch_up(&self, peer_addr: &str) -> Result<usize, SyncError> {
+        let _span = info_span!("sync", node = %self.id, peer = peer_addr).entered();
+        self.syncing.store(true, Ordering::SeqCst);
+        let result = self.sync_headers_first(peer_addr);
+        self.syncing.store(false, Ordering::SeqCst);
 
-                println!("📤 Sent SYNC_REQUEST to {}", peer_addr);
-
-                let mut buffer = [0; 131072]; // 128KB буфер
-                match stream.set_read_timeout(Some(Duration::from_secs(5))) {
-                    Ok(_) => {
-                        match stream.read(&mut buffer) {
-                            Ok(n) if n > 0 => {
-                                let response = String::from_utf8_lossy(&buffer[..n]);
-                                if let Ok(data) = serde_json::from_str::<Value>(&response) {
-                                    self.handle_sync_response(&data);
-                                    return true;
-                                }
-                            }
-                            _ => {
-                                println!("⏱️  Timeout waiting for sync response");
-                            }
-                        }
-                    }
-                    Err(e) => eprintln!("❌ Timeout setup error: {}", e),
-                }
+        if let (Err(e), Some(ip)) = (&result, peer_ip(peer_addr)) {
+            if let Some(misbehavior) = sync_misbehavior(e) {
+                self.misbehaving(ip, misbehavior);
+            }
+        }
 
-                false
+        if matches!(result, Ok(added) if added > 0) {
+            self.abort_current_block();
+        }
+        result
+    }
+
+    /// Подключает блок, пришедший от пира `peer`; за заведомо негодный
+    /// блок пир получает штраф, сирота запускает догрузку родителей.
+    pub(crate) async fn accept_block(&self, block: Block, peer: IpAddr) {
+        let mut bc = self.blockchain.write().await;
+        let index = block.header.index;
+        let height = bc.chain.len();
+        // Блок не выше нашей вершины мог честно проиграть гонку
+        let tip = bc.latest_block();
+        let stale = block.header.prev_hash != tip.hash && index <= tip.header.index;
+        match bc.process_block(block) {
+            Ok(BlockStatus::Connected { orphans }) => {
+                self.abort_current_block();
+                self.emit_blocks_added(&bc, height);
+                self.save_state_if_due(&mut bc);
+                info!(index, orphans, "accepted block");
+            }
+            Ok(BlockStatus::Orphan { missing_parent }) => {
+                debug!(index, parent = %missing_parent, "orphan block, requesting parents");
+                drop(bc);
+                self.request_missing_blocks();
             }
             Err(e) => {
-                println!(
-                    "❌ Node [{}] failed to connect to {}: {}",
-                    self.id, peer_addr, e
-                );
-                false
+                warn!(index, error = %e, "rejected block");
+                drop(bc);
+                // Своё хранилище, не записавшее блок, — не вина пира
+                let honest = stale
+                    || matches!(
+                        e,
+                        BlockchainError::InvalidTimestamp { .. } | BlockchainError::Storage(_)
+                    );
+                if !honest {
+                    self.misbehaving(peer, Misbehavior::InvalidBlock);
+                }
             }
         }
     }
 
-    fn handle_sync_response(&self, data: &Value) {
-        if let Some(chain_data) = data.get("chain").and_then(|v| v.as_array()) {
-            let mut bc = self.blockchain.lock().unwrap();
+    /// Догоняет пиров в фоне, когда пришёл блок-сирота: недостающие
+    /// блоки скачиваются через `catch_up`, после чего сироты подключаются.
+    /// Пока идёт загрузка, новые сироты её не перезапускают.
+    fn request_missing_blocks(&self) {
+        if self.is_syncing() {
+            return;
+        }
+        let node = self.clone_node();
+        let worker = node.worker();
+        thread::spawn(move || {
+            let _worker = worker;
+            for peer_addr in node.peer_addrs() {
+                match node.catch_up(&peer_addr) {
+                    Ok(0) => {}
+                    Ok(_) => return,
+                    Err(e) => debug!(peer = %peer_addr, error = %e, "cannot fetch missing blocks"),
+                }
+            }
+        });
+    }
 
-            let blocks_before = bc.chain.len();
+    pub fn is_syncing(&self) -> bool {
+        self.syncing.load(Ordering::SeqCst)
+    }
 
-            for (idx, block_value) in chain_data.iter().enumerate() {
-                if let Ok(block) = serde_json::from_value::<Block>(block_value.clone()) {
-                    if idx == 0 {
-                        // Проверяем, совпадает ли genesis блок
-                        if bc.chain[0].hash != block.hash {
-                            println!("⚠️  Genesis block mismatch - cannot sync");
-                            return;
-                        }
-                    } else {
-                        let prev_block = &bc.chain[bc.chain.len() - 1];
-                        if block.is_valid(prev_block) {
-                            bc.chain.push(block);
-                        }
-                    }
+    /// Запрашивает блоки с высотами из [start, end) (GET_BLOCKS).
+    pub fn request_blocks(
+        &self,
+        peer_addr: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<Block>, SyncError> {
+        match send_request(
+            peer_addr,
+            &Message::GetBlocks { start, end },
+            &self.transport,
+        ) {
+            Some(Message::Blocks { blocks }) => Ok(blocks),
+            Some(_) => Err(SyncError::UnexpectedReply),
+            None => Err(SyncError::PeerUnreachable),
+        }
+    }
+
+    /// Обменивается с пиром сообщениями HELLO и проверяет, что он
+    /// совместим с нами (см. `check_hello`). Наш HELLO несёт адрес
+    /// сервера, так что пир тоже может занести нас в свою таблицу.
+    pub fn handshake(&self, peer_addr: &str) -> Result<PeerInfo, SyncError> {
+        let addr = resolve_peer_addr(peer_addr).ok_or(SyncError::PeerUnreachable)?;
+        if self.is_banned(addr.ip()) {
+            return Err(SyncError::PeerBanned);
+        }
+        let hello = Message::Hello(self.hello());
+        match send_request(&addr.to_string(), &hello, &self.transport) {
+            Some(Message::Hello(hello)) => {
+                self.check_hello(&hello)?;
+                Ok(PeerInfo::from_hello(addr, hello))
+            }
+            Some(_) => Err(SyncError::UnexpectedReply),
+            None => Err(SyncError::PeerUnreachable),
+        }
+    }
+
+    /// Наше рукопожатие: версия протокола, сеть и вершина цепи.
+    pub fn hello(&self) -> Hello {
+        self.hello_for(&self.blockchain.blocking_read())
+    }
+
+    /// То же, что `hello`, для задач внутри рантайма.
+    async fn local_hello(&self) -> Hello {
+        self.hello_for(&*self.blockchain.read().await)
+    }
+
+    fn hello_for(&self, bc: &Blockchain) -> Hello {
+        Hello {
+            node_id: self.id.clone(),
+            version: PROTOCOL_VERSION,
+            chain_id: bc.chain_id().to_string(),
+            genesis_hash: bc.chain[0].hash,
+            best_height: bc.chain.len(),
+            listen_addr: self.listen_addr.lock().unwrap().clone(),
+            external_addr: self.external_addr.lock().unwrap().clone(),
+        }
+    }
+
+    /// Адрес, который узел сообщает пирам: внешний, если он известен.
+    pub fn advertised_addr(&self) -> Option<String> {
+        let external = self.external_addr.lock().unwrap().clone();
+        external.or_else(|| self.listen_addr.lock().unwrap().clone())
+    }
+
+    /// `addr` — один из адресов самого узла.
+    pub(crate) fn is_own_addr(&self, addr: &str) -> bool {
+        self.listen_addr.lock().unwrap().as_deref() == Some(addr)
+            || self.external_addr.lock().unwrap().as_deref() == Some(addr)
+    }
+
+    /// Пир совместим, если говорит на той же версии протокола и его цепь
+    /// начинается с того же genesis в той же сети.
+    pub fn check_hello(&self, hello: &Hello) -> Result<(), SyncError> {
+        check_compatible(&self.hello(), hello)
+    }
+
+    pub fn chain_id(&self) -> String {
+        self.blockchain.blocking_read().chain_id().to_string()
+    }
+
+    /// Запрашивает у пира длину его цепи (сообщение CHAIN_SIZE).
+    pub fn request_chain_size(&self, peer_addr: &str) -> Option<usize> {
+        let request = Message::GetChainSize {
+            from: self.id.clone(),
+        };
+
+        match send_request(peer_addr, &request, &self.transport)? {
+            Message::ChainSize { size } => Some(size),
+            _ => None,
+        }
+    }
+
+    /// Добавляет пира в таблицу без дубликатов и без самого себя.
+    /// Возвращает `false`, если адрес уже известен или таблица заполнена.
+    pub fn add_peer(&self, peer: PeerInfo) -> bool {
+        if self.is_own_addr(&peer.addr.to_string()) {
+            return false;
+        }
+
+        let mut peers = self.peers.lock().unwrap();
+        if peers.len() >= self.max_peers || peers.iter().any(|p| p.addr == peer.addr) {
+            return false;
+        }
+        self.emit(NodeEvent::PeerConnected {
+            addr: peer.addr.to_string(),
+            node_id: peer.node_id.clone(),
+        });
+        peers.push(peer);
+        true
+    }
+
+    /// Начисляет пиру штраф за нарушение. Если он этим забанен, пир
+    /// удаляется из таблицы, а его соединения закрываются.
+    pub fn misbehaving(&self, ip: IpAddr, misbehavior: Misbehavior) {
+        let banned = self
+            .bans
+            .lock()
+            .unwrap()
+            .penalize(ip, misbehavior, unix_now());
+        if banned {
+            warn!(%ip, ?misbehavior, "peer banned");
+            self.peers.lock().unwrap().retain(|peer| {
+                let keep = peer.addr.ip() != ip;
+                if !keep {
+                    self.emit(NodeEvent::PeerDisconnected {
+                        addr: peer.addr.to_string(),
+                    });
                 }
+                keep
+            });
+        } else {
+            debug!(%ip, ?misbehavior, "peer misbehaved");
+        }
+    }
+
+    pub fn is_banned(&self, ip: IpAddr) -> bool {
+        self.bans.lock().unwrap().is_banned(ip, unix_now())
+    }
+
+    /// Действующие баны.
+    pub fn banned_peers(&self) -> Vec<BannedPeer> {
+        self.bans.lock().unwrap().banned(unix_now())
+    }
+
+    /// Адреса пиров из таблицы.
+    pub fn peer_addrs(&self) -> Vec<String> {
+        self.peers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|peer| peer.addr.to_string())
+            .collect()
+    }
+
+    /// Запрашивает у пира список известных ему узлов (GET_PEERS).
+    pub fn request_peers(&self, peer_addr: &str) -> Option<Vec<String>> {
+        let request = Message::GetPeers {
+            from: self.id.clone(),
+        };
+
+        match send_request(peer_addr, &request, &self.transport)? {
+            Message::Peers { peers } => Some(peers),
+            _ => None,
+        }
+    }
+
+    /// Обходит seed-узлы: собирает их списки пиров и подключается к новым
+    /// адресам, пока таблица не заполнится. Рукопожатие при подключении
+    /// сообщает seed-узлу наш адрес. Все найденные адреса запоминает
+    /// менеджер соединений. Возвращает число пиров после обхода.
+    pub fn discover_peers(&self, seeds: &[String]) -> usize {
+        let mut candidates: Vec<String> = seeds.to_vec();
+
+        for seed in seeds {
+            if let Some(peers) = self.request_peers(seed) {
+                candidates.extend(peers);
             }
+        }
 
-            let blocks_after = bc.chain.len();
-            if blocks_after > blocks_before {
-                println!(
-                    "✅ Synced! Received {} new blocks. Total: {}",
-                    blocks_after - blocks_before,
-                    blocks_after
-                );
+        for candidate in &candidates {
+            if !self.is_own_addr(candidate) {
+                self.connections.lock().unwrap().add(candidate);
+            }
+        }
+        for candidate in candidates {
+            if self.peers.lock().unwrap().len() >= self.max_peers {
+                break;
+            }
+            let known = self.peer_addrs().contains(&candidate);
+            if !known && !self.is_own_addr(&candidate) {
+                self.connect_to_peer(&candidate);
             }
         }
+
+        let count = self.peers.lock().unwrap().len();
+        info!(node = %self.id, peers = count, "peer discovery finished");
+        count
     }
 
+    /// Рассылает блок пирам в сжатом виде (COMPACT_BLOCK). Узел без
+    /// своего адреса шлёт блок целиком: недостающие транзакции пирам
+    /// было бы не у кого дозапросить.
     pub fn broadcast_block(&self, block: &Block) {
-        let msg = json!({
-            "type": "NEW_BLOCK",
-            "block": block
-        });
-
-        self.broadcast_to_peers(&msg.to_string());
+        let message = match self.advertised_addr() {
+            Some(origin) => Message::CompactBlock {
+                block: CompactBlock::from_block(block),
+                origin,
+            },
+            None => Message::NewBlock {
+                block: block.clone(),
+            },
+        };
+        self.broadcast_to_peers(&message);
     }
 
+    /// Объявляет свою транзакцию пирам (INV, см. `inventory`); они
+    /// запросят её и передадут дальше.
     pub fn broadcast_transaction(&self, tx: &Transaction) {
-        let msg = json!({
-            "type": "NEW_TRANSACTION",
-            "transaction": tx
-        });
+        runtime().block_on(self.announce_transaction(tx));
+    }
 
-        self.broadcast_to_peers(&msg.to_string());
+    /// То же, что `broadcast_transaction`, для задач внутри рантайма.
+    pub(crate) async fn announce_transaction(&self, tx: &Transaction) {
+        self.seen_txs.lock().unwrap().insert(tx.id());
+        self.emit(NodeEvent::transaction_accepted(tx));
+        self.send_to_peers(&self.announcement(tx.clone())).await;
     }
 
-    fn broadcast_to_peers(&self, message: &str) {
-        let peers = self.peers.lock().unwrap().clone();
-        if peers.is_empty() {
+    /// INV с txid транзакции или, если у узла нет своего адреса, сама
+    /// транзакция (NEW_TX).
+    fn announcement(&self, tx: Transaction) -> Message {
+        match self.advertised_addr() {
+            Some(origin) => Message::Inv {
+                txids: vec![tx.id()],
+                origin,
+            },
+            None => Message::NewTx {
+                transaction: tx,
+                origin: None,
+            },
+        }
+    }
+
+    /// Принимает транзакцию от пира `peer` в mempool и передаёт её
+    /// дальше всем, кроме `origin`; за невалидную пир получает штраф.
+    pub(crate) async fn accept_transaction(
+        &self,
+        transaction: Transaction,
+        origin: Option<&str>,
+        peer: IpAddr,
+    ) {
+        if !self.seen_txs.lock().unwrap().insert(transaction.id()) {
             return;
         }
 
-        for peer_addr in peers {
-            if let Ok(mut stream) = TcpStream::connect(&peer_addr) {
-                let _ = stream.write_all(message.as_bytes());
+        let added = self
+            .blockchain
+            .write()
+            .await
+            .add_transaction(transaction.clone());
+        match added {
+            Ok(()) => {
+                info!(txid = %transaction.id(), "accepted transaction");
+                self.emit(NodeEvent::transaction_accepted(&transaction));
+                self.relay_transaction(transaction, origin);
+            }
+            Err(e) => {
+                warn!(txid = %transaction.id(), error = %e, "rejected transaction");
+                if matches!(e, BlockchainError::InvalidTransaction(_)) {
+                    self.misbehaving(peer, Misbehavior::InvalidTransaction);
+                }
+            }
+        }
+    }
+
+    /// Объявляет принятую транзакцию всем пирам, кроме `origin`. Не ждёт
+    /// отправки, поэтому годится и для обработчиков внутри рантайма.
+    fn relay_transaction(&self, tx: Transaction, origin: Option<&str>) {
+        let message = self.announcement(tx);
+        for peer_addr in self.peer_addrs() {
+            if Some(peer_addr.as_str()) != origin {
+                runtime().spawn(send_message(
+                    peer_addr,
+                    message.clone(),
+                    self.transport.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Рассылает сообщение всем пирам параллельно и ждёт, пока
+    /// все отправки завершатся или истечёт таймаут.
+    fn broadcast_to_peers(&self, message: &Message) {
+        runtime().block_on(self.send_to_peers(message));
+    }
+
+    async fn send_to_peers(&self, message: &Message) {
+        let mut sends = JoinSet::new();
+        for peer_addr in self.peer_addrs() {
+            sends.spawn(send_message(
+                peer_addr,
+                message.clone(),
+                self.transport.clone(),
+            ));
+        }
+        while sends.join_next().await.is_some() {}
+    }
+
+    /// Запускает фоновый майнер: пока флаг `mining` поднят, поток
+    /// забирает транзакции из mempool, добывает блок и рассылает его пирам.
+    pub fn start_mining(&self, miner_address: Address) -> JoinHandle<()> {
+        self.start_mining_with(miner_address, MiningOptions::default())
+    }
+
+    /// То же, что `start_mining`, но nonce перебирается в `options.threads` потоках.
+    pub fn start_mining_with(
+        &self,
+        miner_address: Address,
+        options: MiningOptions,
+    ) -> JoinHandle<()> {
+        self.mining.store(true, Ordering::SeqCst);
+        let node = self.clone_node();
+
+        let worker = node.worker();
+
+        thread::spawn(move || {
+            let _worker = worker;
+            let _span = info_span!("mining", node = %node.id, miner = %miner_address).entered();
+            info!(threads = options.threads.max(1), "mining started");
+            while node.mining.load(Ordering::SeqCst) && !node.is_shutting_down() {
+                node.purge_expired_transactions();
+                match node.mine_next_block(&miner_address, &options) {
+                    Some(block) => node.broadcast_block(&block),
+                    None => thread::sleep(Duration::from_millis(500)),
+                }
+            }
+            info!("mining stopped");
+        })
+    }
+
+    /// Запускает в фоновом потоке `task` раз в `interval`, пока узел не
+    /// остановлен; так работают сверка с пирами и их опрос.
+    pub(crate) fn every<F>(&self, name: &'static str, interval: Duration, task: F) -> JoinHandle<()>
+    where
+        F: Fn(&Node) + Send + 'static,
+    {
+        let node = self.clone_node();
+        let worker = node.worker();
+
+        thread::spawn(move || {
+            let _worker = worker;
+            let _span = info_span!("periodic", node = %node.id, task = name).entered();
+            loop {
+                let stopped = runtime().block_on(async {
+                    tokio::select! {
+                        _ = node.shutdown.cancelled() => true,
+                        _ = tokio::time::sleep(interval) => false,
+                    }
+                });
+                if stopped {
+                    return;
+                }
+                task(&node);
+            }
+        })
+    }
+
+    /// Сразу добывает `count` блоков (пустых, если mempool пуст) с
+    /// наградой на `miner` и рассылает их пирам. Для сетей regtest, где
+    /// блок находится почти мгновенно; см. `POST /generate`.
+    pub fn generate(&self, count: usize, miner: &Address) -> Result<Vec<Block>, BlockchainError> {
+        let mut blocks = Vec::with_capacity(count);
+        for _ in 0..count {
+            let block = {
+                let mut bc = self.blockchain.blocking_write();
+                let block = bc.mine_block(miner)?;
+                self.emit(NodeEvent::block_added(&block));
+                self.save_state_if_due(&mut bc);
+                block
+            };
+            self.abort_current_block();
+            self.broadcast_block(&block);
+            blocks.push(block);
+        }
+        Ok(blocks)
+    }
+
+    pub fn stop_mining(&self) {
+        self.mining.store(false, Ordering::SeqCst);
+        self.abort_current_block();
+    }
+
+    /// Прерывает перебор nonce для текущего блока; майнер начнёт
+    /// заново от новой вершины.
+    pub fn abort_current_block(&self) {
+        self.abort_mining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_mining(&self) -> bool {
+        self.mining.load(Ordering::SeqCst)
+    }
+
+    /// Выбрасывает из mempool транзакции, которые так и не попали в блок
+    /// за отведённое время.
+    fn purge_expired_transactions(&self) {
+        let mut bc = self.blockchain.blocking_write();
+        let now = bc.now();
+        let expired = bc.mempool.purge_expired(now);
+        if !expired.is_empty() {
+            info!(count = expired.len(), "dropped expired transactions");
+        }
+    }
+
+    /// Добывает один блок, не удерживая блокировку цепи во время
+    /// перебора nonce. Если за это время вершина сменилась, транзакции
+    /// возвращаются в mempool.
+    fn mine_next_block(&self, miner_address: &Address, options: &MiningOptions) -> Option<Block> {
+        // Снимок цепи, чтобы не держать блокировку на время майнинга.
+        // Копируется только нужное правилу: вершина или, если правилу
+        // нужна вся история, вся цепь.
+        let (consensus, chain, transactions, bits, timestamp) = {
+            let mut bc = self.blockchain.blocking_write();
+            if bc.mempool.size() == 0 || self.is_syncing() {
+                return None;
+            }
+            self.abort_mining.store(false, Ordering::SeqCst);
+            let transactions = bc.take_block_transactions(miner_address);
+            let consensus = Arc::clone(bc.consensus());
+            let from = if consensus.needs_full_history() {
+                0
+            } else {
+                bc.chain.len().saturating_sub(1)
+            };
+            (
+                consensus,
+                bc.chain[from..].to_vec(),
+                transactions,
+                bc.bits,
+                Block::next_timestamp_at(&bc.chain, bc.now()),
+            )
+        };
+        let index = chain.last().map_or(0, |tip| tip.header.index + 1);
+
+        let mined = consensus.produce_block(
+            &chain,
+            transactions.clone(),
+            bits,
+            timestamp,
+            options,
+            &self.abort_mining,
+        );
+
+        let mut bc = self.blockchain.blocking_write();
+        if let Some((block, stats)) = mined {
+            match bc.add_block(block.clone()) {
+                Ok(()) => {
+                    self.metrics.record_mined_block(stats.hash_rate());
+                    self.emit(NodeEvent::block_added(&block));
+                    self.save_state_if_due(&mut bc);
+                    info!(
+                        index = block.header.index,
+                        nonce = block.header.nonce,
+                        hash = %block.hash,
+                        hash_rate = stats.hash_rate().round(),
+                        "mined block"
+                    );
+                    return Some(block);
+                }
+                Err(e) => {
+                    self.metrics.record_orphaned_block();
+                    warn!(index = block.header.index, error = %e, "discarded mined block");
+                }
             }
+        } else {
+            self.metrics.record_orphaned_block();
+            debug!(index, "abandoned stale block");
+        }
+
+        for tx in transactions.into_iter().filter(|tx| !tx.is_coinbase()) {
+            let _ = bc.add_transaction(tx);
         }
+        None
     }
 
     pub fn get_node_info(&self) -> String {
-        let bc = self.blockchain.lock().unwrap();
+        let bc = self.blockchain.blocking_read();
         let peers = self.peers.lock().unwrap();
 
         format!(
@@ -181,93 +1059,604 @@ impl Node {
              ║ Blocks: {:<20} ║\n\
              ║ Valid: {:<21} ║\n\
              ║ Peers: {:<21} ║\n\
-             ║ Difficulty: {:<15} ║\n\
+             ║ Difficulty: {:<15.2} ║\n\
              ║ Mempool: {:<19} ║\n\
              ╚══════════════════════════════╝",
             self.id,
             bc.chain.len(),
             bc.is_chain_valid(),
             peers.len(),
-            bc.difficulty,
+            bc.difficulty(),
             bc.mempool.size()
         )
     }
 }
 
-fn handle_client(
-    mut stream: TcpStream,
-    blockchain: &Arc<Mutex<Blockchain>>,
-    node_id: &str,
-) {
-    let mut buffer = [0; 131072];
-
-    match stream.read(&mut buffer) {
-        Ok(n) if n > 0 => {
-            let message = String::from_utf8_lossy(&buffer[..n]);
-
-            if let Ok(data) = serde_json::from_str::<Value>(&message) {
-                let msg_type = data.get("type").and_then(|v| v.as_str());
-
-                match msg_type {
-                    Some("SYNC_REQUEST") => {
-                        let bc = blockchain.lock().unwrap();
-                        let response = json!({
-                            "type": "SYNC_RESPONSE",
-                            "chain": bc.chain
-                        });
-
-                        println!(
-                            "📤 Node [{}] sending chain with {} blocks",
-                            node_id,
-                            bc.chain.len()
-                        );
-                        let _ = stream.write_all(response.to_string().as_bytes());
-                    }
+// ========== HEADERS-FIRST SYNC ==============
+// Загрузка идёт в два прохода. Сначала у пира скачиваются только
+// заголовки: они малы, а их связность, время и работу можно проверить
+// без транзакций (тем же `LightClient`). Ветка пира принимается, только
+// если после точки ветвления несёт больше работы, чем наша. Затем тела
+// блоков запрашиваются пачками одновременно у всех известных пиров;
+// каждое сверяется с уже проверенным заголовком, а пачки, которые пир
+// не отдал или подменил, перезапрашиваются у пира с этой веткой.
 
-                    Some("NEW_BLOCK") => {
-                        if let Some(block_data) = data.get("block") {
-                            if let Ok(block) = serde_json::from_value::<Block>(block_data.clone())
-                            {
-                                let mut bc = blockchain.lock().unwrap();
-                                if bc.chain.len() > 0 {
-                                    let prev = &bc.chain[bc.chain.len() - 1];
-                                    if block.is_valid(prev) {
-                                        bc.chain.push(block);
-                                        println!("✅ Node [{}] added new block", node_id);
-                                    }
-                                }
-                            }
-                        }
-                    }
+/// Проверенная ветка пира поверх нашей цепи.
+struct HeaderBranch {
+    /// Высота последнего общего блока.
+    fork: usize,
+    headers: Vec<BlockHeader>,
+}
 
-                    Some("NEW_TRANSACTION") => {
-                        if let Some(tx_data) = data.get("transaction") {
-                            if let Ok(tx) =
-                                serde_json::from_value::<Transaction>(tx_data.clone())
-                            {
-                                let mut bc = blockchain.lock().unwrap();
-                                if bc.add_transaction(tx) {
-                                    println!(
-                                        "✅ Node [{}] added new transaction to mempool",
-                                        node_id
-                                    );
-                                }
-                            }
-                        }
-                    }
+impl Node {
+    fn sync_headers_first(&self, peer_addr: &str) -> Result<usize, SyncError> {
+        let Some(branch) = self.download_headers(peer_addr)? else {
+            return Ok(0);
+        };
+        debug!(
+            fork = branch.fork,
+            headers = branch.headers.len(),
+            "headers verified, downloading bodies"
+        );
+        let bodies = self.download_bodies(peer_addr, &branch)?;
+        self.apply_bodies(bodies)
+    }
+
+    /// Все заголовки пира начиная с высоты `start` (GET_HEADERS пачками).
+    pub fn request_headers(
+        &self,
+        peer_addr: &str,
+        start: u32,
+    ) -> Result<Vec<BlockHeader>, SyncError> {
+        let mut headers = Vec::new();
+        loop {
+            let from = start + headers.len() as u32;
+            let request = Message::GetHeaders {
+                start: from,
+                end: from + HEADERS_BATCH_SIZE as u32,
+            };
+            let batch = match send_request(peer_addr, &request, &self.transport) {
+                Some(Message::Headers { headers }) => headers,
+                Some(_) => return Err(SyncError::UnexpectedReply),
+                None => return Err(SyncError::PeerUnreachable),
+            };
+            let last = batch.len() < HEADERS_BATCH_SIZE;
+            headers.extend(batch);
+            if last {
+                return Ok(headers);
+            }
+        }
+    }
+
+    /// Скачивает и проверяет заголовки пира выше точки ветвления.
+    /// `None`, если его ветка не тяжелее нашей.
+    fn download_headers(&self, peer_addr: &str) -> Result<Option<HeaderBranch>, SyncError> {
+        let (ours, work): (Vec<(BlockHeader, Hash256)>, Vec<u128>) = {
+            let bc = self.blockchain.blocking_read();
+            let ours = bc.iter().map(|block| (block.header, block.hash)).collect();
+            let work = (0..bc.chain.len())
+                .map(|height| bc.work_at(height))
+                .collect();
+            (ours, work)
+        };
+        let tip_hash = ours[ours.len() - 1].1;
+
+        let mut headers = self.request_headers(peer_addr, ours.len() as u32)?;
+        let fork = if headers.first().is_some_and(|h| h.prev_hash == tip_hash) {
+            ours.len() - 1
+        } else {
+            // Пир не продолжает нашу вершину: ищем точку ветвления с начала
+            headers = self.request_headers(peer_addr, 1)?;
+            let common = headers
+                .iter()
+                .zip(&ours[1..])
+                .take_while(|(theirs, (_, our_hash))| theirs.compute_hash() == *our_hash)
+                .count();
+            headers.drain(..common);
+            common
+        };
+        if headers.is_empty() {
+            return Ok(None);
+        }
+
+        let mut client = LightClient::from_headers(ours[..=fork].iter().map(|(h, _)| *h).collect());
+        client.add_headers(&headers)?;
+
+        let their_work = headers.iter().fold(work[fork], |total, header| {
+            total.saturating_add(header.work())
+        });
+        let our_work = work[work.len() - 1];
+        if their_work <= our_work {
+            debug!(fork, their_work, our_work, "peer branch is not heavier");
+            return Ok(None);
+        }
+        Ok(Some(HeaderBranch { fork, headers }))
+    }
+
+    /// Тела блоков ветки: пачки по `IBD_BATCH_SIZE` запрашиваются
+    /// одновременно у `peer_addr` и остальных пиров по кругу.
+    fn download_bodies(
+        &self,
+        peer_addr: &str,
+        branch: &HeaderBranch,
+    ) -> Result<Vec<Block>, SyncError> {
+        let mut sources = vec![peer_addr.to_string()];
+        sources.extend(
+            self.peer_addrs()
+                .into_iter()
+                .filter(|addr| addr != peer_addr),
+        );
 
-                    _ => {
-                        println!("⚠️  Node [{}] received unknown message type", node_id);
+        let batches: Vec<&[BlockHeader]> = branch.headers.chunks(IBD_BATCH_SIZE).collect();
+        let mut replies = vec![None; batches.len()];
+        runtime().block_on(async {
+            let mut requests = JoinSet::new();
+            for (i, batch) in batches.iter().enumerate() {
+                let source = sources[i % sources.len()].clone();
+                let message = Message::GetBlocks {
+                    start: batch[0].index,
+                    end: batch[0].index + batch.len() as u32,
+                };
+                let transport = self.transport.clone();
+                requests.spawn(async move { (i, request(&source, message, &transport).await) });
+            }
+            while let Some(Ok((i, reply))) = requests.join_next().await {
+                replies[i] = reply;
+            }
+        });
+
+        let mut bodies = Vec::with_capacity(branch.headers.len());
+        for (batch, reply) in batches.into_iter().zip(replies) {
+            let blocks = match reply {
+                Some(Message::Blocks { blocks }) if bodies_match(batch, &blocks) => blocks,
+                _ => {
+                    let start = batch[0].index;
+                    debug!(start, "retrying batch from the syncing peer");
+                    let blocks =
+                        self.request_blocks(peer_addr, start, start + batch.len() as u32)?;
+                    if !bodies_match(batch, &blocks) {
+                        return Err(SyncError::BodyMismatch { index: start });
                     }
+                    blocks
+                }
+            };
+            bodies.extend(blocks);
+        }
+        Ok(bodies)
+    }
+
+    /// Подключает скачанные блоки: дописывает их к вершине или, если они
+    /// ответвились раньше, заменяет цепь (с возвратом транзакций
+    /// отброшенных блоков в mempool). Каждый блок проверяется полностью.
+    fn apply_bodies(&self, bodies: Vec<Block>) -> Result<usize, SyncError> {
+        let mut bc = self.blockchain.blocking_write();
+        let start_height = bc.chain.len();
+
+        // Часть блоков могла уже прийти через NEW_BLOCK
+        let bodies: Vec<Block> = bodies
+            .into_iter()
+            .filter(|block| !bc.contains_block(&block.hash))
+            .collect();
+        let Some(first) = bodies.first() else {
+            return Ok(0);
+        };
+
+        let mut new_from = start_height;
+        if first.header.prev_hash == bc.latest_block().hash {
+            for block in bodies {
+                if let Err(e) = bc.add_block(block) {
+                    self.emit_blocks_added(&bc, new_from);
+                    return Err(SyncError::Block(e));
+                }
+            }
+        } else {
+            let parent = first.header.index as usize - 1;
+            let mut candidate = bc.chain[..=parent].to_vec();
+            candidate.extend(bodies);
+            let reorg = bc.replace_chain(candidate).map_err(SyncError::Chain)?;
+            info!(
+                fork_height = reorg.fork_height,
+                disconnected = reorg.disconnected.len(),
+                connected = reorg.connected,
+                reinjected = reorg.reinjected.len(),
+                "chain reorganized"
+            );
+            self.emit(NodeEvent::ChainReorged {
+                fork_height: reorg.fork_height,
+                disconnected: reorg.disconnected.iter().map(|block| block.hash).collect(),
+                connected: reorg.connected,
+            });
+            new_from = reorg.fork_height as usize + 1;
+        }
+        bc.connect_orphans();
+        self.emit_blocks_added(&bc, new_from);
+        self.save_state_if_due(&mut bc);
+        Ok(bc.chain.len().saturating_sub(start_height))
+    }
+}
+
+/// Блоки пачки в точности соответствуют проверенным заголовкам.
+fn bodies_match(headers: &[BlockHeader], blocks: &[Block]) -> bool {
+    blocks.len() == headers.len()
+        && blocks
+            .iter()
+            .zip(headers)
+            .all(|(block, header)| block.header == *header && block.has_valid_hash())
+}
+
+// ========== TRANSPORT ==============
+async fn connect(
+    peer_addr: &str,
+    transport: &Transport,
+) -> Option<Framed<TcpStream, MessageCodec>> {
+    let mut stream = timeout(REQUEST_TIMEOUT, TcpStream::connect(peer_addr))
+        .await
+        .ok()?
+        .ok()?;
+    let codec = match transport {
+        Transport::Plain => MessageCodec::new(),
+        Transport::Encrypted(wallet) => {
+            match timeout(REQUEST_TIMEOUT, transport::initiate(&mut stream, wallet)).await {
+                Ok(Ok(channel)) => MessageCodec::encrypted(channel),
+                Ok(Err(e)) => {
+                    debug!(peer = peer_addr, error = %e, "encrypted handshake failed");
+                    return None;
+                }
+                Err(_) => {
+                    debug!(peer = peer_addr, "encrypted handshake timed out");
+                    return None;
+                }
+            }
+        }
+        Transport::Simulated(_) => return None,
+    };
+    Some(Framed::new(stream, codec))
+}
+
+/// Отправляет сообщение без ожидания ответа.
+async fn send_message(peer_addr: String, message: Message, transport: Transport) {
+    if let Transport::Simulated(link) = &transport {
+        link.send(peer_addr, message);
+        return;
+    }
+    if let Some(mut framed) = connect(&peer_addr, &transport).await {
+        let _ = timeout(REQUEST_TIMEOUT, framed.send(message)).await;
+    }
+}
+
+pub(crate) async fn request(
+    peer_addr: &str,
+    message: Message,
+    transport: &Transport,
+) -> Option<Message> {
+    if let Transport::Simulated(link) = transport {
+        return link.request(peer_addr, message).await;
+    }
+    let mut framed = connect(peer_addr, transport).await?;
+    timeout(REQUEST_TIMEOUT, framed.send(message))
+        .await
+        .ok()?
+        .ok()?;
+    timeout(REQUEST_TIMEOUT, framed.next()).await.ok()??.ok()
+}
+
+pub(crate) fn send_request(
+    peer_addr: &str,
+    message: &Message,
+    transport: &Transport,
+) -> Option<Message> {
+    runtime().block_on(request(peer_addr, message.clone(), transport))
+}
+
+/// Совместим ли пир, приславший `theirs`, с нашим рукопожатием `ours`,
+/// см. `Node::check_hello`.
+fn check_compatible(ours: &Hello, theirs: &Hello) -> Result<(), SyncError> {
+    if theirs.version != ours.version {
+        return Err(SyncError::VersionMismatch {
+            ours: ours.version,
+            theirs: theirs.version,
+        });
+    }
+    if theirs.chain_id != ours.chain_id {
+        return Err(SyncError::ChainMismatch {
+            ours: ours.chain_id.clone(),
+            theirs: theirs.chain_id.clone(),
+        });
+    }
+    if theirs.genesis_hash != ours.genesis_hash {
+        return Err(SyncError::GenesisMismatch);
+    }
+    Ok(())
+}
+
+/// Штраф, которого заслуживает пир, чья синхронизация закончилась `error`.
+/// Недоступность и несовместимость пира нарушениями не считаются, как и
+/// гонки с другими источниками блоков.
+fn sync_misbehavior(error: &SyncError) -> Option<Misbehavior> {
+    match error {
+        SyncError::InvalidHeader { .. } => Some(Misbehavior::InvalidHeaders),
+        SyncError::BodyMismatch { .. }
+        | SyncError::Chain(ChainError::InvalidBlock { .. })
+        | SyncError::Block(
+            BlockchainError::InvalidBlock { .. }
+            | BlockchainError::Consensus(_)
+            | BlockchainError::UnexpectedDifficulty { .. }
+            | BlockchainError::NonceReused { .. }
+            | BlockchainError::WrongChain { .. },
+        ) => Some(Misbehavior::InvalidBlock),
+        SyncError::UnexpectedReply => Some(Misbehavior::UnsolicitedMessage),
+        _ => None,
+    }
+}
+
+/// Слушающий сокет на `addr`; IPv6-сокет не принимает IPv4.
+fn bind_listener(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    socket.set_nonblocking(true)?;
+    TcpListener::from_std(socket.into())
+}
+
+/// Адрес, по которому к слушающему `addr` можно подключиться.
+fn advertisable(addr: SocketAddr) -> SocketAddr {
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    SocketAddr::new(ip, addr.port())
+}
+
+/// Обрабатывает сообщения пира, пока тот не закроет соединение, не
+/// замолчит дольше `idle_timeout` или не будет забанен. Кадры длиннее
+/// `MAX_MESSAGE_SIZE` отвергает кодек, сообщения сверх лимита частоты
+/// отбрасываются. Сами сообщения применяются к цепи прямо в задаче
+/// соединения: блокировка цепи ждётся через `.await` и не занимает
+/// поток рантайма.
+async fn handle_client(mut stream: TcpStream, peer: SocketAddr, node: Node) {
+    let _worker = node.worker();
+    let codec = match &node.transport {
+        Transport::Plain => MessageCodec::new(),
+        Transport::Encrypted(wallet) => {
+            match timeout(REQUEST_TIMEOUT, transport::respond(&mut stream, wallet)).await {
+                Ok(Ok(channel)) => {
+                    debug!(remote_key = %channel.remote_key(), "encrypted channel established");
+                    MessageCodec::encrypted(channel)
+                }
+                Ok(Err(e)) => {
+                    debug!(error = %e, "encrypted handshake failed");
+                    return;
+                }
+                Err(_) => {
+                    debug!("encrypted handshake timed out");
+                    return;
                 }
             }
         }
-        Ok(_) => {
-            println!("👋 Peer disconnected from Node [{}]", node_id);
+        // Узлы симуляции не слушают TCP
+        Transport::Simulated(_) => return,
+    };
+    let mut framed = Framed::new(stream, codec);
+    loop {
+        let next = tokio::select! {
+            _ = node.shutdown.cancelled() => return,
+            next = timeout(node.idle_timeout, framed.next()) => next,
+        };
+        let message = match next {
+            Err(_) => {
+                debug!("peer idle, closing connection");
+                return;
+            }
+            Ok(Some(Ok(message))) => message,
+            Ok(None) => {
+                debug!("peer disconnected");
+                return;
+            }
+            Ok(Some(Err(e))) => {
+                warn!(error = %e, "read failed");
+                if e.kind() == io::ErrorKind::InvalidData {
+                    node.misbehaving(peer.ip(), Misbehavior::MalformedMessage);
+                }
+                return;
+            }
+        };
+
+        if !node
+            .rate_limiter
+            .lock()
+            .unwrap()
+            .allow(peer.ip(), Instant::now())
+        {
+            debug!(
+                kind = message.kind(),
+                "rate limit exceeded, dropping message"
+            );
+            node.misbehaving(peer.ip(), Misbehavior::Flooding);
+            if node.is_banned(peer.ip()) {
+                return;
+            }
+            continue;
+        }
+
+        // Несовместимому пиру отвечаем своим HELLO и закрываем соединение
+        let refused = match &message {
+            Message::Hello(hello) => check_compatible(&node.local_hello().await, hello).is_err(),
+            _ => false,
+        };
+        let response = handle_message(message, peer.ip(), &node).await;
+        if let Some(response) = response {
+            match timeout(REQUEST_TIMEOUT, framed.send(response)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    warn!(error = %e, "write failed");
+                    return;
+                }
+                Err(_) => {
+                    warn!("write timed out");
+                    return;
+                }
+            }
         }
-        Err(e) => {
-            eprintln!("❌ Node [{}] read error: {}", node_id, e);
+        if refused || node.is_banned(peer.ip()) {
+            return;
         }
     }
 }
 
+/// Применяет сообщение пира `peer` к общей цепи и возвращает ответ,
+/// если он нужен.
+pub(crate) async fn handle_message(message: Message, peer: IpAddr, node: &Node) -> Option<Message> {
+    let blockchain = &node.blockchain;
+    node.metrics.record_message(&message);
+
+    match message {
+        Message::GetChain { .. } => {
+            let bc = blockchain.read().await;
+            debug!(blocks = bc.chain.len(), "sending chain");
+            match bc.full_blocks(0..bc.chain.len()) {
+                Ok(chain) => Some(Message::ChainResponse { chain }),
+                Err(e) => {
+                    warn!(error = %e, "failed to read archived blocks");
+                    None
+                }
+            }
+        }
+
+        Message::GetChainSize { .. } => {
+            let size = blockchain.read().await.chain.len();
+            Some(Message::ChainSize { size })
+        }
+
+        Message::Ping { timestamp } => Some(Message::Pong { timestamp }),
+
+        Message::GetTip => {
+            let bc = blockchain.read().await;
+            Some(Message::Tip {
+                best_height: bc.chain.len(),
+                hash: bc.latest_block().hash,
+                work: bc.total_work(),
+            })
+        }
+
+        Message::NewBlock { block } => {
+            node.accept_block(block, peer).await;
+            None
+        }
+
+        Message::CompactBlock { block, origin } => {
+            node.accept_compact_block(block, origin, peer).await;
+            None
+        }
+
+        Message::GetBlockTxn { hash, indexes } => {
+            let bc = blockchain.read().await;
+            let block = bc.get_block_by_hash(&hash)?;
+            let transactions = indexes
+                .iter()
+                .map(|&index| block.transactions.get(index as usize).cloned())
+                .collect::<Option<Vec<_>>>()?;
+            Some(Message::BlockTxn { hash, transactions })
+        }
+
+        Message::NewTx { .. } if node.is_syncing() => {
+            debug!("syncing, ignoring transaction");
+            None
+        }
+
+        Message::NewTx {
+            transaction,
+            origin,
+        } => {
+            node.accept_transaction(transaction, origin.as_deref(), peer)
+                .await;
+            None
+        }
+
+        Message::Inv { .. } if node.is_syncing() => None,
+
+        Message::Inv { txids, origin } => {
+            node.handle_inventory(txids, origin, peer).await;
+            None
+        }
+
+        Message::GetData { txids } => Some(Message::Transactions {
+            transactions: node.inventory_data(&txids).await,
+        }),
+
+        Message::Hello(hello) => {
+            let ours = node.local_hello().await;
+            match check_compatible(&ours, &hello) {
+                Err(e) => warn!(peer_id = %hello.node_id, error = %e, "refused peer"),
+                Ok(()) => {
+                    let addr = hello.external_addr.as_ref().or(hello.listen_addr.as_ref());
+                    if let Some(addr) = addr.and_then(|addr| addr.parse::<SocketAddr>().ok()) {
+                        let peer = PeerInfo::from_hello(addr, hello);
+                        if node.add_peer(peer) {
+                            info!(peer = %addr, "learned about peer");
+                        }
+                    }
+                }
+            }
+            Some(Message::Hello(ours))
+        }
+
+        Message::GetPeers { .. } => {
+            let mut peers = node.peer_addrs();
+            if let Some(addr) = node.advertised_addr() {
+                peers.push(addr);
+            }
+            Some(Message::Peers { peers })
+        }
+
+        Message::GetBlocks { start, end } => {
+            let bc = blockchain.read().await;
+            let end = (end as usize)
+                .min(bc.chain.len())
+                .min(start as usize + IBD_BATCH_SIZE);
+            // Обрезанные блоки отдаются из архива
+            let blocks = bc.full_blocks(start as usize..end).unwrap_or_else(|e| {
+                warn!(error = %e, "failed to read archived blocks");
+                Vec::new()
+            });
+            Some(Message::Blocks { blocks })
+        }
+
+        Message::GetHeaders { start, end } => {
+            let bc = blockchain.read().await;
+            let end = (end as usize)
+                .min(bc.chain.len())
+                .min(start as usize + HEADERS_BATCH_SIZE);
+            let headers = bc
+                .chain
+                .get(start as usize..end)
+                .map(|blocks| blocks.iter().map(|block| block.header).collect())
+                .unwrap_or_default();
+            Some(Message::Headers { headers })
+        }
+
+        Message::GetMerkleProof { txid } => {
+            let proof = blockchain.read().await.transaction_proof(&txid);
+            Some(Message::MerkleProof { txid, proof })
+        }
+
+        Message::ChainResponse { .. }
+        | Message::ChainSize { .. }
+        | Message::Pong { .. }
+        | Message::BlockTxn { .. }
+        | Message::Transactions { .. }
+        | Message::Tip { .. }
+        | Message::Headers { .. }
+        | Message::MerkleProof { .. }
+        | Message::Peers { .. }
+        | Message::Blocks { .. } => {
+            warn!("unsolicited response");
+            node.misbehaving(peer, Misbehavior::UnsolicitedMessage);
+            None
+        }
+    }
+}
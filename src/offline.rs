@@ -0,0 +1,147 @@
+use crate::block::{verify_ecdsa, Transaction, Wallet};
+use crate::error::{PsbtError, WalletError};
+use crate::types::Address;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+// ========== OFFLINE SIGNING ==============
+// Подпись на машине без сети в духе PSBT: онлайн-машина собирает
+// неподписанный перевод (например, через `WatchWallet`) и выгружает его
+// в JSON или base64; офлайн-машина с ключом подписывает его
+// `Wallet::sign_unsigned` и возвращает обратно; онлайн-машина проверяет
+// подписи и отправляет транзакцию в сеть:
+//
+//   let psbt = PartiallySignedTransaction::new(watch.transaction_for(&chain, &to, 5.0, 0.1));
+//   let exported = psbt.to_base64();                      // → офлайн
+//   let mut psbt = PartiallySignedTransaction::import(&exported)?;
+//   psbt.sign(&wallet)?;                                  // ← обратно
+//   let tx = psbt.finalize()?;
+//
+// Перевод с адреса M-из-N подписывают по очереди: каждый владелец
+// добавляет свою подпись, пока их не наберётся порог.
+
+/// Версия формата выгрузки.
+pub const PSBT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartiallySignedTransaction {
+    pub version: u32,
+    pub transaction: Transaction,
+}
+
+impl PartiallySignedTransaction {
+    pub fn new(transaction: Transaction) -> Self {
+        PartiallySignedTransaction {
+            version: PSBT_VERSION,
+            transaction,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("transaction serializes to JSON")
+    }
+
+    /// Тот же JSON в base64 — одной строкой, которую удобно переносить.
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(serde_json::to_vec(self).expect("transaction serializes to JSON"))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PsbtError> {
+        let psbt: PartiallySignedTransaction =
+            serde_json::from_str(json).map_err(|e| PsbtError::Malformed(e.to_string()))?;
+        if psbt.version != PSBT_VERSION {
+            return Err(PsbtError::UnsupportedVersion(psbt.version));
+        }
+        Ok(psbt)
+    }
+
+    pub fn from_base64(encoded: &str) -> Result<Self, PsbtError> {
+        let json = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| PsbtError::Malformed(e.to_string()))?;
+        let json = String::from_utf8(json).map_err(|e| PsbtError::Malformed(e.to_string()))?;
+        Self::from_json(&json)
+    }
+
+    /// Читает выгрузку в любом из двух форматов.
+    pub fn import(data: &str) -> Result<Self, PsbtError> {
+        if data.trim_start().starts_with('{') {
+            Self::from_json(data)
+        } else {
+            Self::from_base64(data)
+        }
+    }
+
+    /// Добавляет подпись ключом `wallet`, см. `Wallet::sign_unsigned`.
+    pub fn sign(&mut self, wallet: &Wallet) -> Result<(), WalletError> {
+        wallet.sign_unsigned(&mut self.transaction)
+    }
+
+    /// Сколько верных подписей уже есть и сколько нужно.
+    pub fn signatures(&self) -> (usize, usize) {
+        let tx = &self.transaction;
+        let payload = tx.signing_payload();
+        match &tx.multisig {
+            Some(policy) => {
+                let valid = policy
+                    .public_keys
+                    .iter()
+                    .filter(|key| {
+                        tx.cosignatures.iter().any(|cosignature| {
+                            &cosignature.public_key == *key
+                                && hex::decode(key).is_ok_and(|bytes| {
+                                    verify_ecdsa(
+                                        key,
+                                        &Address::from_public_key(&bytes),
+                                        &payload,
+                                        &cosignature.signature,
+                                    )
+                                    .is_ok()
+                                })
+                        })
+                    })
+                    .count();
+                (valid, policy.threshold)
+            }
+            None => (tx.verify_signature().is_ok() as usize, 1),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.transaction.verify_signature().is_ok()
+    }
+
+    /// Транзакция, готовая к отправке, — если подписей достаточно.
+    pub fn finalize(self) -> Result<Transaction, PsbtError> {
+        if let Err(e) = self.transaction.verify_signature() {
+            let (signatures, required) = self.signatures();
+            return Err(PsbtError::Incomplete {
+                signatures,
+                required,
+                reason: e,
+            });
+        }
+        Ok(self.transaction)
+    }
+}
+
+impl Wallet {
+    /// Подписывает перевод, собранный без ключа. Обычный перевод должен
+    /// быть с адреса кошелька; пустой публичный ключ (перевод собран по
+    /// одному адресу) заполняется ключом кошелька. К переводу с адреса
+    /// M-из-N добавляется подпись владельца, как в `co_sign`.
+    pub fn sign_unsigned(&self, tx: &mut Transaction) -> Result<(), WalletError> {
+        if tx.multisig.is_some() {
+            return self.co_sign(tx);
+        }
+        if tx.from != self.get_address() {
+            return Err(WalletError::NotTheSender);
+        }
+        if !tx.public_key.is_empty() && tx.public_key != self.public_key {
+            return Err(WalletError::NotTheSender);
+        }
+        tx.sign_with(self);
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use crate::block::Block;
+use crate::types::Hash256;
+use std::collections::{HashMap, VecDeque};
+
+// ========== ORPHAN BLOCKS ==============
+// Блок, родителя которого узел ещё не видел, не отбрасывается, а ждёт
+// в пуле сирот. Когда родитель появляется в цепи, `Blockchain`
+// подключает ожидавших его потомков.
+
+/// Сколько сирот узел держит по умолчанию.
+pub const DEFAULT_ORPHAN_CAPACITY: usize = 100;
+
+/// Блоки без родителя, сгруппированные по хешу недостающего родителя.
+/// При переполнении вытесняется самый давний.
+#[derive(Debug, Clone)]
+pub struct OrphanPool {
+    capacity: usize,
+    by_parent: HashMap<Hash256, Vec<Block>>,
+    /// Хеши сирот в порядке поступления.
+    order: VecDeque<Hash256>,
+}
+
+impl OrphanPool {
+    pub fn new(capacity: usize) -> Self {
+        OrphanPool {
+            capacity: capacity.max(1),
+            by_parent: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Кладёт блок в пул. `false` — если он уже там.
+    pub fn insert(&mut self, block: Block) -> bool {
+        if self.contains(&block.hash) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.remove(&oldest);
+            }
+        }
+        self.order.push_back(block.hash);
+        self.by_parent
+            .entry(block.header.prev_hash)
+            .or_default()
+            .push(block);
+        true
+    }
+
+    pub fn contains(&self, hash: &Hash256) -> bool {
+        self.order.contains(hash)
+    }
+
+    /// Забирает всех сирот, ожидавших родителя `parent`.
+    pub fn take_children(&mut self, parent: &Hash256) -> Vec<Block> {
+        let children = self.by_parent.remove(parent).unwrap_or_default();
+        self.order
+            .retain(|hash| !children.iter().any(|child| &child.hash == hash));
+        children
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    fn remove(&mut self, hash: &Hash256) {
+        self.by_parent.retain(|_, blocks| {
+            blocks.retain(|block| &block.hash != hash);
+            !blocks.is_empty()
+        });
+    }
+}
+
+impl Default for OrphanPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_ORPHAN_CAPACITY)
+    }
+}
@@ -0,0 +1,308 @@
+use crate::block::{Block, BlockHeader, Transaction};
+use crate::compact::CompactBlock;
+use crate::merkle::MerkleProof;
+use crate::transport::SecureChannel;
+use crate::types::Hash256;
+use bytes::{Bytes, BytesMut};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use tokio_util::codec::{Decoder, Encoder, LengthDelimitedCodec};
+
+/// Верхняя граница размера одного сообщения, чтобы пир не мог
+/// заставить узел выделить произвольно большой буфер.
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Версия протокола; узлы с разными версиями не соединяются.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Версия кодировки сообщений в заголовке кадра, см. FRAMING.
+pub const WIRE_VERSION: u8 = 1;
+
+/// Байты заголовка кадра после длины: версия и контрольная сумма.
+const HEADER_SIZE: usize = 1 + 4;
+
+/// Содержимое рукопожатия: кто узел, на каком протоколе и в какой сети.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Hello {
+    pub node_id: String,
+    pub version: u32,
+    pub chain_id: String,
+    pub genesis_hash: Hash256,
+    pub best_height: usize,
+    /// Адрес, на котором узел принимает соединения, если он их принимает.
+    pub listen_addr: Option<String>,
+    /// Адрес, по которому узел доступен из интернета (проброшенный на
+    /// роутере порт, см. `upnp`). Пиры запоминают и передают дальше его,
+    /// а не `listen_addr`.
+    #[serde(default)]
+    pub external_addr: Option<String>,
+}
+
+// ========== MESSAGES ==============
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Message {
+    /// Рукопожатие при подключении; пир отвечает своим HELLO.
+    Hello(Hello),
+    NewBlock {
+        block: Block,
+    },
+    /// Новый блок без транзакций, которые у пира уже есть, см. `compact`.
+    /// Недостающие получатель дозапрашивает у `origin`.
+    CompactBlock {
+        block: CompactBlock,
+        origin: String,
+    },
+    /// Транзакции блока `hash` с позициями `indexes`.
+    GetBlockTxn {
+        hash: Hash256,
+        indexes: Vec<u32>,
+    },
+    BlockTxn {
+        hash: Hash256,
+        transactions: Vec<Transaction>,
+    },
+    NewTx {
+        transaction: Transaction,
+        /// Адрес узла, переславшего транзакцию; ему её не возвращают.
+        #[serde(default)]
+        origin: Option<String>,
+    },
+    /// Объявление транзакций: получатель запрашивает незнакомые из
+    /// них у `origin` через GET_DATA, см. `inventory`.
+    Inv {
+        txids: Vec<Hash256>,
+        origin: String,
+    },
+    GetData {
+        txids: Vec<Hash256>,
+    },
+    /// Найденные в mempool транзакции из GET_DATA.
+    Transactions {
+        transactions: Vec<Transaction>,
+    },
+    GetChain {
+        from: String,
+    },
+    ChainResponse {
+        chain: Vec<Block>,
+    },
+    GetChainSize {
+        from: String,
+    },
+    ChainSize {
+        size: usize,
+    },
+    /// Проверка, что пир жив; он отвечает PONG с той же отметкой
+    /// времени (миллисекунды Unix), см. `health`.
+    Ping {
+        timestamp: u64,
+    },
+    Pong {
+        timestamp: u64,
+    },
+    /// Вершина цепи пира для периодической сверки, см. `resync`.
+    GetTip,
+    Tip {
+        best_height: usize,
+        hash: Hash256,
+        /// Суммарная работа цепи пира.
+        work: u128,
+    },
+    GetPeers {
+        from: String,
+    },
+    Peers {
+        peers: Vec<String>,
+    },
+    /// Блоки с высотами из полуинтервала [start, end).
+    GetBlocks {
+        start: u32,
+        end: u32,
+    },
+    Blocks {
+        blocks: Vec<Block>,
+    },
+    /// Заголовки с высотами из полуинтервала [start, end).
+    GetHeaders {
+        start: u32,
+        end: u32,
+    },
+    Headers {
+        headers: Vec<BlockHeader>,
+    },
+    /// Доказательство включения подтверждённой транзакции.
+    GetMerkleProof {
+        txid: Hash256,
+    },
+    /// Высота блока с транзакцией и merkle-путь до его корня;
+    /// `None`, если транзакция узлу не известна.
+    MerkleProof {
+        txid: Hash256,
+        proof: Option<(u32, MerkleProof)>,
+    },
+}
+
+impl Message {
+    /// Тип сообщения для логов и метрик: `new_block`, `get_peers` и т. д.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Message::Hello(_) => "hello",
+            Message::NewBlock { .. } => "new_block",
+            Message::CompactBlock { .. } => "compact_block",
+            Message::GetBlockTxn { .. } => "get_block_txn",
+            Message::BlockTxn { .. } => "block_txn",
+            Message::NewTx { .. } => "new_tx",
+            Message::Inv { .. } => "inv",
+            Message::GetData { .. } => "get_data",
+            Message::Transactions { .. } => "transactions",
+            Message::GetChain { .. } => "get_chain",
+            Message::ChainResponse { .. } => "chain_response",
+            Message::GetChainSize { .. } => "get_chain_size",
+            Message::ChainSize { .. } => "chain_size",
+            Message::Ping { .. } => "ping",
+            Message::Pong { .. } => "pong",
+            Message::GetTip => "get_tip",
+            Message::Tip { .. } => "tip",
+            Message::GetPeers { .. } => "get_peers",
+            Message::Peers { .. } => "peers",
+            Message::GetBlocks { .. } => "get_blocks",
+            Message::Blocks { .. } => "blocks",
+            Message::GetHeaders { .. } => "get_headers",
+            Message::Headers { .. } => "headers",
+            Message::GetMerkleProof { .. } => "get_merkle_proof",
+            Message::MerkleProof { .. } => "merkle_proof",
+        }
+    }
+}
+
+// ========== FRAMING ==============
+// Каждое сообщение передаётся как 4-байтовая длина (big-endian) и
+// следующее за ней тело: байт `WIRE_VERSION`, первые 4 байта SHA-256 от
+// полезной нагрузки и сам `Message` в bincode. Кадр другой версии или с
+// неверной суммой отвергается как InvalidData, а пир получает штраф,
+// как за любое нечитаемое сообщение.
+
+fn wire_error(reason: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.into())
+}
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let mut sum = [0u8; 4];
+    sum.copy_from_slice(&Hash256::digest(payload).as_bytes()[..4]);
+    sum
+}
+
+/// Тело кадра для `message`: версия, контрольная сумма и bincode.
+pub fn encode_message(message: &Message) -> io::Result<Vec<u8>> {
+    let payload = bincode::serialize(message).map_err(|e| wire_error(e.to_string()))?;
+    let mut body = Vec::with_capacity(HEADER_SIZE + payload.len());
+    body.push(WIRE_VERSION);
+    body.extend_from_slice(&checksum(&payload));
+    body.extend_from_slice(&payload);
+    Ok(body)
+}
+
+/// Разбирает тело кадра, проверив версию и контрольную сумму.
+pub fn decode_message(body: &[u8]) -> io::Result<Message> {
+    if body.len() < HEADER_SIZE {
+        return Err(wire_error("frame too short"));
+    }
+    let (header, payload) = body.split_at(HEADER_SIZE);
+    if header[0] != WIRE_VERSION {
+        return Err(wire_error(format!(
+            "unsupported wire version {}",
+            header[0]
+        )));
+    }
+    if header[1..] != checksum(payload) {
+        return Err(wire_error("checksum mismatch"));
+    }
+    bincode::deserialize(payload).map_err(|e| wire_error(e.to_string()))
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Message) -> io::Result<()> {
+    let payload = encode_message(message)?;
+    writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+    writer.write_all(&payload)?;
+    writer.flush()
+}
+
+pub fn read_message<R: Read>(reader: &mut R) -> io::Result<Message> {
+    let mut len_bytes = [0u8; 4];
+    reader.read_exact(&mut len_bytes)?;
+    let len = u32::from_be_bytes(len_bytes) as usize;
+
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes exceeds limit", len),
+        ));
+    }
+
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+    decode_message(&payload)
+}
+
+/// Та же рамка (длина + тело) для асинхронных соединений:
+/// `tokio_util::codec::Framed<TcpStream, MessageCodec>`. С каналом из
+/// рукопожатия (см. `transport`) тело кадра шифруется.
+#[derive(Debug)]
+pub struct MessageCodec {
+    frames: LengthDelimitedCodec,
+    channel: Option<SecureChannel>,
+}
+
+impl MessageCodec {
+    pub fn new() -> Self {
+        MessageCodec {
+            frames: LengthDelimitedCodec::builder()
+                .length_field_length(4)
+                .max_frame_length(MAX_MESSAGE_SIZE)
+                .new_codec(),
+            channel: None,
+        }
+    }
+
+    pub fn encrypted(channel: SecureChannel) -> Self {
+        MessageCodec {
+            channel: Some(channel),
+            ..Self::new()
+        }
+    }
+}
+
+impl Default for MessageCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for MessageCodec {
+    type Item = Message;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Message>> {
+        let Some(frame) = self.frames.decode(src)? else {
+            return Ok(None);
+        };
+        let payload = match &mut self.channel {
+            Some(channel) => channel.open(&frame)?,
+            None => frame.to_vec(),
+        };
+        decode_message(&payload).map(Some)
+    }
+}
+
+impl Encoder<Message> for MessageCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, message: Message, dst: &mut BytesMut) -> io::Result<()> {
+        let mut payload = encode_message(&message)?;
+        if let Some(channel) = &mut self.channel {
+            payload = channel.seal(&payload);
+        }
+        self.frames.encode(Bytes::from(payload), dst)
+    }
+}
@@ -0,0 +1,308 @@
+use crate::block::{Block, Blockchain};
+use crate::storage::{invalid_data, read_chain};
+use crate::types::Hash256;
+use crate::validation::ReplayedState;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// ========== PRUNING ==============
+// Обрезанный узел держит целиком только последние блоки. Более старые
+// уходят в архив — сжатые zstd файлы по `ARCHIVE_SEGMENT_SIZE` блоков, —
+// а в цепи от них остаются заголовки без транзакций: их хватает для
+// связности, медианы времени, сложности и суммарной работы. Балансы,
+// nonce, токены и непотраченные выходы после архивных блоков хранятся
+// в `PrunedState`.
+//
+// Genesis не обрезается. Архивные блоки по-прежнему отдаются пирам
+// (`Blockchain::full_blocks`), но reorg глубже границы обрезки узел
+// принять уже не может. Цепь с хранилищем блоков (см. `blockstore`)
+// архив не ведёт: её тела и так лежат в хранилище, и обрезка лишь
+// вытесняет их из памяти. Консенсус, которому нужна вся история
+// (`Consensus::needs_full_history`), обрезку не допускает.
+
+/// Сколько блоков лежит в одном файле архива; граница обрезки кратна ему.
+pub const ARCHIVE_SEGMENT_SIZE: usize = 100;
+/// Имя каталога архива внутри `data_dir`.
+pub const ARCHIVE_DIR_NAME: &str = "archive";
+/// Имя файла состояния обрезки внутри каталога архива.
+pub const PRUNED_STATE_FILE_NAME: &str = "pruned_state.bin.zst";
+
+/// Уровень сжатия zstd для файлов архива.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Состояние после обрезанных блоков `0..height`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrunedState {
+    /// Сколько блоков от genesis учтено; блоки `1..height` обрезаны.
+    pub height: u32,
+    /// Хеш блока `height - 1`.
+    pub tip_hash: Hash256,
+    pub state: ReplayedState,
+}
+
+/// Каталог с архивными блоками и состоянием обрезки.
+#[derive(Debug, Clone)]
+pub struct BlockArchive {
+    dir: PathBuf,
+}
+
+impl BlockArchive {
+    /// Открывает архив в `dir`, создавая каталог при необходимости.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<BlockArchive> {
+        fs::create_dir_all(&dir)?;
+        Ok(BlockArchive {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn segment_path(&self, segment: usize) -> PathBuf {
+        self.dir.join(format!("blocks_{:06}.bin.zst", segment))
+    }
+
+    pub fn state_path(&self) -> PathBuf {
+        self.dir.join(PRUNED_STATE_FILE_NAME)
+    }
+
+    pub fn has_state(&self) -> bool {
+        self.state_path().exists()
+    }
+
+    /// Записывает блоки сегмента `segment` (высоты с `segment *
+    /// ARCHIVE_SEGMENT_SIZE`).
+    pub fn write_segment(&self, segment: usize, blocks: &[Block]) -> io::Result<()> {
+        write_compressed(&self.segment_path(segment), &blocks)
+    }
+
+    /// Читает сегмент и проверяет, что в нём ровно его блоки с верными
+    /// хешами и merkle-корнями.
+    pub fn read_segment(&self, segment: usize) -> io::Result<Vec<Block>> {
+        let blocks: Vec<Block> = read_compressed(&self.segment_path(segment))?;
+        let first = segment * ARCHIVE_SEGMENT_SIZE;
+        if blocks.len() != ARCHIVE_SEGMENT_SIZE {
+            return Err(invalid_data(format!(
+                "archive segment {} has {} blocks",
+                segment,
+                blocks.len()
+            )));
+        }
+        for (height, block) in (first..).zip(&blocks) {
+            if block.header.index as usize != height || !block.has_valid_hash() {
+                return Err(invalid_data(format!(
+                    "archived block {} is corrupted",
+                    height
+                )));
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Архивные блоки высот `range`.
+    pub fn read_blocks(&self, range: Range<usize>) -> io::Result<Vec<Block>> {
+        let mut blocks = Vec::with_capacity(range.len());
+        if range.is_empty() {
+            return Ok(blocks);
+        }
+        let segments = range.start / ARCHIVE_SEGMENT_SIZE..=(range.end - 1) / ARCHIVE_SEGMENT_SIZE;
+        for segment in segments {
+            let first = segment * ARCHIVE_SEGMENT_SIZE;
+            let segment = self.read_segment(segment)?;
+            blocks.extend(
+                (first..)
+                    .zip(segment)
+                    .filter(|(height, _)| range.contains(height))
+                    .map(|(_, block)| block),
+            );
+        }
+        Ok(blocks)
+    }
+
+    pub fn save_state(&self, state: &PrunedState) -> io::Result<()> {
+        write_compressed(&self.state_path(), state)
+    }
+
+    pub fn load_state(&self) -> io::Result<PrunedState> {
+        read_compressed(&self.state_path())
+    }
+}
+
+/// Пишет `value` в bincode со сжатием через временный файл, чтобы
+/// оборванная запись не испортила предыдущий.
+fn write_compressed<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let writer = BufWriter::new(File::create(&tmp)?);
+    let mut encoder = zstd::Encoder::new(writer, COMPRESSION_LEVEL)?;
+    bincode::serialize_into(&mut encoder, value).map_err(|e| invalid_data(e.to_string()))?;
+    encoder.finish()?.flush()?;
+    fs::rename(tmp, path)
+}
+
+fn read_compressed<T: for<'de> Deserialize<'de>>(path: &Path) -> io::Result<T> {
+    let decoder = zstd::Decoder::new(File::open(path)?)?;
+    bincode::deserialize_from(decoder)
+        .map_err(|e| invalid_data(format!("{}: {}", path.display(), e)))
+}
+
+impl Blockchain {
+    /// Подключает архив, куда `prune` складывает старые блоки.
+    pub fn set_archive(&mut self, archive: BlockArchive) {
+        self.archive = Some(Arc::new(archive));
+    }
+
+    /// Сколько блоков от genesis учтено в состоянии обрезки; блоки
+    /// `1..pruned_height` хранятся только в архиве.
+    pub fn pruned_height(&self) -> usize {
+        self.pruned_height
+    }
+
+    /// Переносит в архив все блоки, кроме последних `keep` (граница
+    /// округляется вниз до `ARCHIVE_SEGMENT_SIZE`), сохраняет состояние
+    /// после них и оставляет в цепи только их заголовки. С подключённым
+    /// хранилищем блоков архив не нужен: тела просто вытесняются из
+    /// памяти, без округления границы. Возвращает, сколько блоков обрезано.
+    pub fn prune(&mut self, keep: usize) -> io::Result<usize> {
+        if self.consensus().needs_full_history() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "consensus needs the full chain history",
+            ));
+        }
+        if self.store.is_some() {
+            return Ok(self.evict_blocks(self.chain.len().saturating_sub(keep)));
+        }
+        let archive = self.archive.clone().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no block archive configured")
+        })?;
+
+        let target =
+            self.chain.len().saturating_sub(keep) / ARCHIVE_SEGMENT_SIZE * ARCHIVE_SEGMENT_SIZE;
+        if target <= self.pruned_height {
+            return Ok(0);
+        }
+
+        let mut state = self.pruned_state.clone();
+        for first in (self.pruned_height..target).step_by(ARCHIVE_SEGMENT_SIZE) {
+            let blocks = &self.chain[first..first + ARCHIVE_SEGMENT_SIZE];
+            archive.write_segment(first / ARCHIVE_SEGMENT_SIZE, blocks)?;
+            for block in blocks {
+                state.apply_block(block);
+            }
+        }
+        archive.save_state(&PrunedState {
+            height: target as u32,
+            tip_hash: self.chain[target - 1].hash,
+            state: state.clone(),
+        })?;
+
+        let pruned = target - self.pruned_height.max(1);
+        for block in &mut self.chain[self.pruned_height.max(1)..target] {
+            block.transactions = Vec::new();
+            block.utxo_transactions = Vec::new();
+        }
+        self.pruned_height = target;
+        self.pruned_state = state;
+        self.reindex();
+        Ok(pruned)
+    }
+
+    /// Блоки высот `range` целиком: обрезанные читаются из хранилища
+    /// блоков, если оно подключено, иначе из архива.
+    pub fn full_blocks(&self, range: Range<usize>) -> io::Result<Vec<Block>> {
+        let end = range.end.min(self.chain.len());
+        let start = range.start.min(end);
+        let pruned = start.max(1)..end.min(self.pruned_height);
+        if pruned.is_empty() {
+            return Ok(self.chain[start..end].to_vec());
+        }
+
+        let archived = match (&self.store, &self.archive) {
+            (Some(store), _) => pruned
+                .clone()
+                .map(|height| {
+                    store.get_block_by_height(height)?.ok_or_else(|| {
+                        invalid_data(format!("block {} is missing from the block store", height))
+                    })
+                })
+                .collect::<io::Result<Vec<_>>>()?,
+            (None, Some(archive)) => archive.read_blocks(pruned.clone())?,
+            (None, None) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "no block archive configured",
+                ))
+            }
+        };
+        for (block, stub) in archived.iter().zip(&self.chain[pruned.clone()]) {
+            if block.hash != stub.hash {
+                return Err(invalid_data(format!(
+                    "pruned block {} does not match the chain",
+                    block.header.index
+                )));
+            }
+        }
+
+        let mut blocks = self.chain[start..pruned.start].to_vec();
+        blocks.extend(archived);
+        blocks.extend_from_slice(&self.chain[pruned.end..end]);
+        Ok(blocks)
+    }
+
+    /// Блок высоты `height` целиком, см. `full_blocks`.
+    pub fn full_block(&self, height: usize) -> io::Result<Option<Block>> {
+        Ok(self.full_blocks(height..height + 1)?.pop())
+    }
+
+    /// То же, что `restore_from_disk`, для обрезанной цепи: состояние
+    /// до границы обрезки берётся из архива. Блок `height - 1` в файле
+    /// должен иметь хеш `tip_hash`, а заголовки до него — верные хеши и
+    /// связность; более новые блоки проверяются полностью.
+    pub fn restore_pruned<P: AsRef<Path>>(
+        mut self,
+        path: P,
+        archive: BlockArchive,
+    ) -> io::Result<Blockchain> {
+        let pruned = archive.load_state()?;
+        let mut chain = read_chain(path)?;
+        let height = pruned.height as usize;
+        match height.checked_sub(1).and_then(|tip| chain.get(tip)) {
+            Some(block) if block.hash == pruned.tip_hash => {}
+            _ => {
+                return Err(invalid_data(format!(
+                    "pruned state at height {} does not match the stored chain",
+                    height
+                )))
+            }
+        }
+
+        for (prev, block) in chain[..height].iter().zip(&chain[1..height]) {
+            if block.header.prev_hash != prev.hash || block.hash != block.header.compute_hash() {
+                return Err(invalid_data(format!(
+                    "block {} has a bad hash",
+                    block.header.index
+                )));
+            }
+        }
+        for block in &mut chain[1..height] {
+            block.transactions = Vec::new();
+            block.utxo_transactions = Vec::new();
+        }
+
+        self.chain = chain;
+        self.pruned_height = height;
+        self.pruned_state = pruned.state;
+        self.archive = Some(Arc::new(archive));
+        self.verify()
+            .map_err(|e| invalid_data(format!("stored chain failed validation: {}", e)))?;
+        self.reindex();
+        self.bits = self.next_bits(&self.chain);
+
+        Ok(self)
+    }
+}
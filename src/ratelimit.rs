@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Instant;
+
+// ========== RATE LIMITING ==============
+// Сообщения пиров проходят через token bucket на каждый IP: корзина
+// вмещает `burst` сообщений и пополняется со скоростью `rate` в секунду.
+// Все соединения с одного адреса делят одну корзину. Сообщение сверх
+// лимита отбрасывается без ответа, а пир получает штраф (см. `banlist`),
+// так что затяжной флуд заканчивается баном.
+
+/// Сколько сообщений в секунду пир может слать непрерывно.
+pub const DEFAULT_MESSAGE_RATE: f64 = 50.0;
+
+/// Сколько сообщений пир может прислать разом после паузы.
+pub const DEFAULT_MESSAGE_BURST: f64 = 200.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    /// Пополнение корзины, сообщений в секунду.
+    pub rate: f64,
+    /// Ёмкость корзины.
+    pub burst: f64,
+    buckets: HashMap<IpAddr, Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64, burst: f64) -> Self {
+        RateLimiter {
+            rate,
+            burst,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Списывает одно сообщение с корзины `ip`. `false` — корзина пуста
+    /// и сообщение нужно отбросить.
+    pub fn allow(&mut self, ip: IpAddr, now: Instant) -> bool {
+        let burst = self.burst;
+        let bucket = self.buckets.entry(ip).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Забывает корзины, успевшие наполниться, — их владельцы ничем не
+    /// отличаются от новых пиров.
+    pub fn prune(&mut self, now: Instant) {
+        let (rate, burst) = (self.rate, self.burst);
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens + elapsed * rate < burst
+        });
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_MESSAGE_RATE, DEFAULT_MESSAGE_BURST)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::network::{send_request, Node};
+use crate::protocol::Message;
+use crate::types::Hash256;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+// ========== PERIODIC RESYNC ==============
+// Узел сверяет цепь с пиром при подключении и догоняет его, когда
+// приходит блок-сирота. После разрыва сети половины, добывшие каждая
+// свои блоки, об этом не узнают, пока кто-нибудь не найдёт следующий
+// блок. Поэтому раз в `resync_interval` секунд узел спрашивает у каждого
+// пира его вершину (GET_TIP) и, если цепь пира тяжелее, догоняет её так
+// же, как при подключении (`catch_up`: заголовки, тела, при
+// необходимости реорганизация). Так разрыв зарастает сам, как только
+// связь вернулась.
+
+/// Как часто узел сверяет вершину с пирами, в секундах.
+pub const DEFAULT_RESYNC_INTERVAL: u64 = 30;
+
+/// Вершина цепи пира из ответа TIP.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeerTip {
+    pub best_height: usize,
+    pub hash: Hash256,
+    /// Суммарная работа цепи пира.
+    pub work: u128,
+}
+
+impl Node {
+    /// Запрашивает у пира вершину его цепи (сообщение TIP).
+    pub fn request_tip(&self, peer_addr: &str) -> Option<PeerTip> {
+        match send_request(peer_addr, &Message::GetTip, &self.transport)? {
+            Message::Tip {
+                best_height,
+                hash,
+                work,
+            } => Some(PeerTip {
+                best_height,
+                hash,
+                work,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Один проход сверки: догоняет каждого пира, чья цепь тяжелее
+    /// нашей. Пока идёт загрузка, ничего не делает. Возвращает, на
+    /// сколько выросла цепь.
+    pub fn resync(&self) -> usize {
+        if self.is_syncing() {
+            return 0;
+        }
+        let mut added = 0;
+        for peer_addr in self.peer_addrs() {
+            let Some(tip) = self.request_tip(&peer_addr) else {
+                debug!(peer = %peer_addr, "peer did not report its tip");
+                continue;
+            };
+            let (our_hash, our_work) = {
+                let bc = self.blockchain.blocking_read();
+                (bc.latest_block().hash, bc.total_work())
+            };
+            // При равной работе остаёмся на своей ветке, как и при reorg
+            if tip.hash == our_hash || tip.work <= our_work {
+                continue;
+            }
+
+            info!(
+                peer = %peer_addr,
+                height = tip.best_height,
+                "peer chain is heavier, resyncing"
+            );
+            match self.catch_up(&peer_addr) {
+                Ok(count) => added += count,
+                Err(e) => warn!(peer = %peer_addr, error = %e, "resync failed"),
+            }
+        }
+        added
+    }
+
+    /// Запускает фоновую сверку с пирами раз в `interval` до `shutdown`.
+    pub fn start_resync(&self, interval: Duration) -> JoinHandle<()> {
+        self.every("resync", interval, |node| {
+            let added = node.resync();
+            if added > 0 {
+                info!(added, "caught up with peers");
+            }
+        })
+    }
+}
@@ -0,0 +1,455 @@
+use crate::block::{Blockchain, Transaction};
+#[cfg(feature = "faucet")]
+use crate::error::FaucetError;
+use crate::events::NodeEvent;
+use crate::genesis::ChainMode;
+use crate::metrics;
+use crate::network::{runtime, Node, ACCEPT_POLL_INTERVAL};
+use crate::types::{Address, Hash256};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io;
+use std::time::Duration;
+use tiny_http::{Header, Response, Server};
+use tokio::sync::broadcast::error::TryRecvError;
+use tracing::{info, warn};
+
+// ========== JSON RPC ==============
+// HTTP API для управления узлом из curl, обозревателей и других языков.
+//
+//   GET  /info                  — сводка об узле
+//   GET  /peers                 — список пиров
+//   GET  /bans                  — забаненные адреса пиров
+//   GET  /balance/{address}     — баланс и следующий nonce адреса
+//   GET  /blocks?from={высота}&limit={n} — до `limit` блоков подряд
+//                                 начиная с `from`; `next` — высота
+//                                 следующей страницы или null
+//   GET  /blocks/{index}        — блок по высоте
+//   GET  /blocks/hash/{hash}    — блок по хэшу
+//   GET  /transactions/{txid}   — подтверждённая транзакция и её блок
+//   GET  /transactions/{txid}/status — pending, confirmed (с числом
+//                                 подтверждений) или unknown
+//   GET  /history/{address}     — история транзакций адреса
+//   GET  /address/{address}/txs?page={n}&limit={n} — та же история
+//                                 постранично, страницы с нуля
+//   GET  /mempool               — ожидающие транзакции
+//   GET  /events?wait={секунды} — события узла после запроса (long polling)
+//   POST /transactions          — отправить подписанную транзакцию
+//   POST /generate              — в regtest сразу добыть блоки, тело
+//                                 `{"blocks": n, "address": ...}`
+//   POST /faucet                — получить монеты из крана, тело
+//                                 `{"address": ...}` (фича `faucet`)
+//   GET  /metrics               — метрики узла для Prometheus (text/plain)
+
+pub const DEFAULT_RPC_PORT: u16 = 8545;
+
+/// Сколько `GET /events` ждёт событий, если `wait` не задан.
+const DEFAULT_EVENTS_WAIT: u64 = 30;
+/// Предел для `wait`, чтобы запрос не занимал поток бесконечно.
+const MAX_EVENTS_WAIT: u64 = 60;
+/// Размер страницы `/blocks` и `/address/{address}/txs` без `limit`.
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+/// Предел `limit`, чтобы один запрос не сериализовал всю цепь.
+pub const MAX_PAGE_SIZE: usize = 100;
+/// Сколько блоков можно добыть одним `POST /generate`.
+pub const MAX_GENERATE_BLOCKS: usize = 1_000;
+
+/// Ответ API: HTTP-статус и JSON-тело.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl RpcResponse {
+    fn ok<T: Serialize>(body: T) -> Self {
+        RpcResponse {
+            status: 200,
+            body: serde_json::to_value(body).unwrap_or(Value::Null),
+        }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Self {
+        RpcResponse {
+            status,
+            body: json!({ "error": message.to_string() }),
+        }
+    }
+
+    fn not_found() -> Self {
+        RpcResponse::error(404, "not found")
+    }
+}
+
+/// Запускает HTTP-сервер на 127.0.0.1:`port` и обслуживает запросы
+/// до `Node::shutdown`. Каждый запрос обрабатывается в своём потоке.
+pub fn start_rpc_server(node: &Node, port: u16) -> io::Result<()> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| io::Error::new(io::ErrorKind::AddrInUse, e.to_string()))?;
+    info!(node = %node.id, port, "RPC listening");
+
+    let _worker = node.worker();
+
+    while !node.is_shutting_down() {
+        let mut request = match server.recv_timeout(ACCEPT_POLL_INTERVAL)? {
+            Some(request) => request,
+            None => continue,
+        };
+        let node = node.clone_node();
+        let worker = node.worker();
+        std::thread::spawn(move || {
+            let _worker = worker;
+            if request.method().as_str() == "GET" && request.url() == "/metrics" {
+                let content_type = Header::from_bytes("Content-Type", metrics::CONTENT_TYPE)
+                    .expect("static header is valid");
+                let reply = Response::from_string(metrics::render(&node)).with_header(content_type);
+                if let Err(e) = request.respond(reply) {
+                    warn!(error = %e, "RPC write failed");
+                }
+                return;
+            }
+
+            let mut body = String::new();
+            let response = match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => handle_request(&node, request.method().as_str(), request.url(), &body),
+                Err(e) => RpcResponse::error(400, e),
+            };
+
+            let content_type = Header::from_bytes("Content-Type", "application/json")
+                .expect("static header is valid");
+            let reply = Response::from_string(response.body.to_string())
+                .with_status_code(response.status)
+                .with_header(content_type);
+            if let Err(e) = request.respond(reply) {
+                warn!(error = %e, "RPC write failed");
+            }
+        });
+    }
+    info!(node = %node.id, port, "RPC stopped");
+    Ok(())
+}
+
+/// Маршрутизирует один запрос. Отделено от HTTP, чтобы API можно было
+/// вызывать и проверять напрямую.
+pub fn handle_request(node: &Node, method: &str, url: &str, body: &str) -> RpcResponse {
+    let path = url.split('?').next().unwrap_or("");
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["info"]) => node_info(node),
+        ("GET", ["peers"]) => RpcResponse::ok(node.peers.lock().unwrap().clone()),
+        ("GET", ["bans"]) => RpcResponse::ok(node.banned_peers()),
+        ("GET", ["balance", address]) => balance(node, address),
+        ("GET", ["blocks"]) => blocks_page(node, url),
+        ("GET", ["blocks", "hash", hash]) => block_by_hash(node, hash),
+        ("GET", ["blocks", index]) => block_by_index(node, index),
+        ("GET", ["transactions", txid]) => transaction(node, txid),
+        ("GET", ["transactions", txid, "status"]) => transaction_status(node, txid),
+        ("GET", ["history", address]) => history(node, address),
+        ("GET", ["address", address, "txs"]) => address_transactions(node, address, url),
+        ("GET", ["mempool"]) => {
+            RpcResponse::ok(&node.blockchain.blocking_read().mempool.transactions)
+        }
+        ("GET", ["events"]) => events(node, url),
+        ("POST", ["transactions"]) => submit_transaction(node, body),
+        ("POST", ["generate"]) => generate(node, body),
+        #[cfg(feature = "faucet")]
+        ("POST", ["faucet"]) => faucet(node, body),
+        _ => RpcResponse::not_found(),
+    }
+}
+
+fn node_info(node: &Node) -> RpcResponse {
+    let bc = node.blockchain.blocking_read();
+    let tip = bc.latest_block().hash;
+    RpcResponse::ok(json!({
+        "id": node.id,
+        "chain_id": bc.chain_id(),
+        "blocks": bc.chain.len(),
+        "pruned_height": bc.pruned_height(),
+        "tip": tip,
+        "difficulty": bc.difficulty(),
+        "bits": bc.bits,
+        "hash_algorithm": bc.chain[0].header.hash_algorithm,
+        "mempool": bc.mempool.size(),
+        "peers": node.peers.lock().unwrap().len(),
+        "mining": node.is_mining(),
+    }))
+}
+
+fn balance(node: &Node, address: &str) -> RpcResponse {
+    let address = Address::from(address);
+    if let Err(e) = address.validate() {
+        return RpcResponse::error(400, e);
+    }
+
+    let bc = node.blockchain.blocking_read();
+    RpcResponse::ok(json!({
+        "address": address,
+        "balance": bc.get_balance(&address),
+        "next_nonce": bc.next_nonce(&address),
+    }))
+}
+
+fn transaction(node: &Node, txid: &str) -> RpcResponse {
+    let txid: Hash256 = match txid.parse() {
+        Ok(txid) => txid,
+        Err(e) => return RpcResponse::error(400, e),
+    };
+
+    let bc = node.blockchain.blocking_read();
+    match bc.get_transaction(&txid) {
+        Some((tx, height)) => RpcResponse::ok(json!({ "transaction": tx, "height": height })),
+        None => RpcResponse::not_found(),
+    }
+}
+
+fn transaction_status(node: &Node, txid: &str) -> RpcResponse {
+    let txid: Hash256 = match txid.parse() {
+        Ok(txid) => txid,
+        Err(e) => return RpcResponse::error(400, e),
+    };
+
+    let status = node.blockchain.blocking_read().transaction_status(&txid);
+    RpcResponse::ok(status)
+}
+
+fn history(node: &Node, address: &str) -> RpcResponse {
+    let address = Address::from(address);
+    if let Err(e) = address.validate() {
+        return RpcResponse::error(400, e);
+    }
+
+    let bc = node.blockchain.blocking_read();
+    let entries: Vec<Value> = bc
+        .get_transactions_for_address(&address)
+        .into_iter()
+        .map(|(tx, height)| json!({ "txid": tx.id(), "transaction": tx, "height": height }))
+        .collect();
+    RpcResponse::ok(entries)
+}
+
+fn address_transactions(node: &Node, address: &str, url: &str) -> RpcResponse {
+    let address = Address::from(address);
+    if let Err(e) = address.validate() {
+        return RpcResponse::error(400, e);
+    }
+    let (page, limit) = match (number_param(url, "page", 0), page_limit(url)) {
+        (Ok(page), Ok(limit)) => (page, limit),
+        (Err(response), _) | (_, Err(response)) => return response,
+    };
+
+    let bc = node.blockchain.blocking_read();
+    let entries: Vec<Value> = bc
+        .address_transactions_page(&address, page.saturating_mul(limit), limit)
+        .into_iter()
+        .map(|(tx, height)| json!({ "txid": tx.id(), "transaction": tx, "height": height }))
+        .collect();
+    RpcResponse::ok(json!({
+        "address": address,
+        "page": page,
+        "limit": limit,
+        "total": bc.address_transaction_count(&address),
+        "transactions": entries,
+    }))
+}
+
+fn blocks_page(node: &Node, url: &str) -> RpcResponse {
+    let (from, limit) = match (number_param(url, "from", 0), page_limit(url)) {
+        (Ok(from), Ok(limit)) => (from, limit),
+        (Err(response), _) | (_, Err(response)) => return response,
+    };
+
+    let bc = node.blockchain.blocking_read();
+    let height = bc.chain.len();
+    let end = from.saturating_add(limit).min(height);
+    match bc.full_blocks(from..end) {
+        Ok(blocks) => RpcResponse::ok(json!({
+            "blocks": blocks,
+            "total": height,
+            "next": (end < height).then_some(end),
+        })),
+        Err(e) => RpcResponse::error(500, e),
+    }
+}
+
+fn block_by_index(node: &Node, index: &str) -> RpcResponse {
+    let index: u32 = match index.parse() {
+        Ok(index) => index,
+        Err(e) => return RpcResponse::error(400, e),
+    };
+
+    full_block(&node.blockchain.blocking_read(), index as usize)
+}
+
+fn block_by_hash(node: &Node, hash: &str) -> RpcResponse {
+    let hash: Hash256 = match hash.parse() {
+        Ok(hash) => hash,
+        Err(e) => return RpcResponse::error(400, e),
+    };
+
+    let bc = node.blockchain.blocking_read();
+    match bc.block_height(&hash) {
+        Some(height) => full_block(&bc, height),
+        None => RpcResponse::not_found(),
+    }
+}
+
+/// Блок с транзакциями, даже если он уже обрезан и лежит в архиве или
+/// хранилище.
+fn full_block(bc: &Blockchain, height: usize) -> RpcResponse {
+    match bc.full_block(height) {
+        Ok(Some(block)) => RpcResponse::ok(block),
+        Ok(None) => RpcResponse::not_found(),
+        Err(e) => RpcResponse::error(500, e),
+    }
+}
+
+/// Ждёт до `wait` секунд первого события после запроса и возвращает
+/// его вместе со всеми, что успели прийти следом; по таймауту — `[]`.
+fn events(node: &Node, url: &str) -> RpcResponse {
+    let wait = match query_param(url, "wait").map(str::parse::<u64>) {
+        None => DEFAULT_EVENTS_WAIT,
+        Some(Ok(wait)) => wait.min(MAX_EVENTS_WAIT),
+        Some(Err(e)) => return RpcResponse::error(400, e),
+    };
+
+    let mut receiver = node.subscribe();
+    let mut events: Vec<NodeEvent> = Vec::new();
+    let first = runtime().block_on(async {
+        tokio::select! {
+            _ = node.shutdown.cancelled() => None,
+            _ = tokio::time::sleep(Duration::from_secs(wait)) => None,
+            // Отставание здесь невозможно: приёмник только что создан
+            event = receiver.recv() => event.ok(),
+        }
+    });
+    events.extend(first);
+    if !events.is_empty() {
+        loop {
+            match receiver.try_recv() {
+                Ok(event) => events.push(event),
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+    }
+    RpcResponse::ok(events)
+}
+
+/// Числовой параметр `name`; `default`, если его нет.
+fn number_param(url: &str, name: &str, default: usize) -> Result<usize, RpcResponse> {
+    match query_param(url, name).map(str::parse::<usize>) {
+        None => Ok(default),
+        Some(Ok(value)) => Ok(value),
+        Some(Err(e)) => Err(RpcResponse::error(400, format!("{}: {}", name, e))),
+    }
+}
+
+/// Размер страницы из `limit`: от 1 до `MAX_PAGE_SIZE`.
+fn page_limit(url: &str) -> Result<usize, RpcResponse> {
+    number_param(url, "limit", DEFAULT_PAGE_SIZE).map(|limit| limit.clamp(1, MAX_PAGE_SIZE))
+}
+
+/// Значение параметра `name` из строки запроса URL.
+fn query_param<'a>(url: &'a str, name: &str) -> Option<&'a str> {
+    let (_, query) = url.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|pair| match pair.split_once('=') {
+            Some((key, value)) if key == name => Some(value),
+            _ => None,
+        })
+}
+
+fn submit_transaction(node: &Node, body: &str) -> RpcResponse {
+    if node.is_syncing() {
+        return RpcResponse::error(503, "node is syncing");
+    }
+
+    let tx: Transaction = match serde_json::from_str(body) {
+        Ok(tx) => tx,
+        Err(e) => return RpcResponse::error(400, e),
+    };
+    let txid = tx.id();
+
+    let result = node.blockchain.blocking_write().add_transaction(tx.clone());
+    match result {
+        Ok(()) => {
+            node.broadcast_transaction(&tx);
+            RpcResponse::ok(json!({ "txid": txid }))
+        }
+        Err(e) => RpcResponse::error(422, e),
+    }
+}
+
+/// Тело `POST /generate`.
+#[derive(Deserialize)]
+struct GenerateRequest {
+    blocks: usize,
+    address: Address,
+}
+
+fn generate(node: &Node, body: &str) -> RpcResponse {
+    if node.blockchain.blocking_read().mode() != ChainMode::Regtest {
+        return RpcResponse::error(403, "generate is only available in regtest");
+    }
+    let request: GenerateRequest = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return RpcResponse::error(400, e),
+    };
+    if let Err(e) = request.address.validate() {
+        return RpcResponse::error(400, e);
+    }
+    if request.blocks > MAX_GENERATE_BLOCKS {
+        return RpcResponse::error(
+            400,
+            format!("at most {} blocks per request", MAX_GENERATE_BLOCKS),
+        );
+    }
+
+    match node.generate(request.blocks, &request.address) {
+        Ok(blocks) => {
+            let hashes: Vec<Hash256> = blocks.iter().map(|block| block.hash).collect();
+            RpcResponse::ok(json!({ "blocks": hashes }))
+        }
+        Err(e) => RpcResponse::error(422, e),
+    }
+}
+
+/// Тело `POST /faucet`.
+#[cfg(feature = "faucet")]
+#[derive(Deserialize)]
+struct FaucetRequest {
+    address: Address,
+}
+
+#[cfg(feature = "faucet")]
+fn faucet(node: &Node, body: &str) -> RpcResponse {
+    if node.is_syncing() {
+        return RpcResponse::error(503, "node is syncing");
+    }
+    let request: FaucetRequest = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return RpcResponse::error(400, e),
+    };
+
+    let result = {
+        let mut faucet = node.faucet.lock().unwrap();
+        let Some(faucet) = faucet.as_mut() else {
+            return RpcResponse::error(404, "faucet is disabled");
+        };
+        faucet.drip(&mut node.blockchain.blocking_write(), &request.address)
+    };
+    match result {
+        Ok(tx) => {
+            node.broadcast_transaction(&tx);
+            RpcResponse::ok(json!({ "txid": tx.id(), "amount": tx.amount }))
+        }
+        Err(e @ FaucetError::InvalidAddress(_)) => RpcResponse::error(400, e),
+        Err(e @ (FaucetError::TooSoon { .. } | FaucetError::LimitReached { .. })) => {
+            RpcResponse::error(429, e)
+        }
+        Err(e) => RpcResponse::error(422, e),
+    }
+}
@@ -0,0 +1,392 @@
+use crate::error::ScriptError;
+use crate::types::{serde_via_string, Address};
+use ripemd::Ripemd160;
+use secp256k1::{PublicKey, Secp256k1};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+// ========== SCRIPT ==============
+// Маленький стековый язык в духе Bitcoin Script: средства можно
+// запереть программой вместо ключа. В цепи это адрес скрипта
+// (`Script::address`): перевод с него несёт сам скрипт
+// (`Transaction::lock_script`) и отпирающие данные (`script_sig`), а
+// `Transaction::validate` их исполняет. Выход UTXO запирается так же
+// (`TxOutput::script`, `TxInput::script_sig`). Отпирающий скрипт —
+// только данные, без операций. Сначала исполняется он, затем запирающий
+// скрипт на том же стеке; трата разрешена, если всё прошло без ошибок и
+// на вершине стека истина (непустое значение с ненулевым байтом).
+//
+//   P2PKH:    <sig> <pubkey> | OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG
+//   M-из-N:   <sig1> <sig2>  | 2 <key1> <key2> <key3> 3 OP_CHECKMULTISIG
+//   timelock: <sig> <pubkey> | <height> OP_CHECKLOCKTIMEVERIFY OP_DROP <P2PKH>
+//
+// Текстовая запись (она же в JSON) — имена опкодов и данные в hex через
+// пробел; пустые данные — `OP_0`. Числа — данные little-endian, не
+// длиннее 8 байт. Каждая операция тратит газ, так что даже длинный
+// скрипт исполняется за ограниченное время.
+
+/// Предел газа на исполнение пары скриптов.
+pub const MAX_SCRIPT_GAS: u64 = 2_000;
+/// Предел числа операций в одном скрипте.
+pub const MAX_SCRIPT_OPS: usize = 201;
+/// Предел глубины стека.
+pub const MAX_STACK_DEPTH: usize = 100;
+/// Предел размера одного элемента данных.
+pub const MAX_PUSH_SIZE: usize = 520;
+/// Газ за операцию; проверка подписи стоит дороже.
+const OP_GAS: u64 = 1;
+const SIG_GAS: u64 = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// Кладёт данные на стек.
+    Push(Vec<u8>),
+    Dup,
+    Drop,
+    Swap,
+    Equal,
+    EqualVerify,
+    Verify,
+    /// Немедленная неудача: выход с таким скриптом не потратить.
+    Return,
+    Hash160,
+    Sha256,
+    CheckSig,
+    CheckSigVerify,
+    CheckMultiSig,
+    /// Не даёт потратить выход в блоке ниже высоты на вершине стека.
+    CheckLockTimeVerify,
+}
+
+const NAMES: [(&str, Op); 13] = [
+    ("OP_DUP", Op::Dup),
+    ("OP_DROP", Op::Drop),
+    ("OP_SWAP", Op::Swap),
+    ("OP_EQUAL", Op::Equal),
+    ("OP_EQUALVERIFY", Op::EqualVerify),
+    ("OP_VERIFY", Op::Verify),
+    ("OP_RETURN", Op::Return),
+    ("OP_HASH160", Op::Hash160),
+    ("OP_SHA256", Op::Sha256),
+    ("OP_CHECKSIG", Op::CheckSig),
+    ("OP_CHECKSIGVERIFY", Op::CheckSigVerify),
+    ("OP_CHECKMULTISIG", Op::CheckMultiSig),
+    ("OP_CHECKLOCKTIMEVERIFY", Op::CheckLockTimeVerify),
+];
+
+impl Op {
+    /// Число как данные: little-endian без старших нулей.
+    pub fn num(value: u64) -> Op {
+        let bytes = value.to_le_bytes();
+        let len = 8 - value.leading_zeros() as usize / 8;
+        Op::Push(bytes[..len].to_vec())
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Push(data) if data.is_empty() => f.write_str("OP_0"),
+            Op::Push(data) => f.write_str(&hex::encode(data)),
+            op => {
+                let (name, _) = NAMES
+                    .iter()
+                    .find(|(_, named)| named == op)
+                    .expect("every opcode has a name");
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = ScriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "OP_0" {
+            return Ok(Op::Push(Vec::new()));
+        }
+        if let Some((_, op)) = NAMES.iter().find(|(name, _)| *name == s) {
+            return Ok(op.clone());
+        }
+        hex::decode(s)
+            .map(Op::Push)
+            .map_err(|_| ScriptError::UnknownOpcode(s.to_string()))
+    }
+}
+
+/// Программа — последовательность операций.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Script(pub Vec<Op>);
+
+impl Script {
+    /// Выход для владельца обычного адреса `owner`.
+    pub fn p2pkh(owner: &Address) -> Result<Script, ScriptError> {
+        let hash = owner.pubkey_hash().ok_or(ScriptError::NotAKeyAddress)?;
+        Ok(Script(vec![
+            Op::Dup,
+            Op::Hash160,
+            Op::Push(hash.to_vec()),
+            Op::EqualVerify,
+            Op::CheckSig,
+        ]))
+    }
+
+    /// Выход, который тратят любые `threshold` подписей из `public_keys`
+    /// (hex). Подписи в отпирающем скрипте идут в порядке ключей.
+    pub fn multisig(threshold: usize, public_keys: &[String]) -> Result<Script, ScriptError> {
+        let mut ops = vec![Op::num(threshold as u64)];
+        for key in public_keys {
+            ops.push(Op::Push(
+                hex::decode(key).map_err(|_| ScriptError::InvalidPublicKey)?,
+            ));
+        }
+        ops.push(Op::num(public_keys.len() as u64));
+        ops.push(Op::CheckMultiSig);
+        Ok(Script(ops))
+    }
+
+    /// `inner`, который нельзя исполнить в блоке ниже `height`.
+    pub fn timelock(height: u32, inner: Script) -> Script {
+        let mut ops = vec![Op::num(height as u64), Op::CheckLockTimeVerify, Op::Drop];
+        ops.extend(inner.0);
+        Script(ops)
+    }
+
+    /// Отпирающий скрипт из подписей и данных — только `Push`.
+    pub fn unlock(items: impl IntoIterator<Item = Vec<u8>>) -> Script {
+        Script(items.into_iter().map(Op::Push).collect())
+    }
+
+    pub fn is_push_only(&self) -> bool {
+        self.0.iter().all(|op| matches!(op, Op::Push(_)))
+    }
+
+    /// Адрес-хеш этого скрипта: на него удобно принимать платежи, а
+    /// сам скрипт раскрыть в выходе.
+    pub fn address(&self) -> Address {
+        Address::from_script(self.to_string().as_bytes())
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ops: Vec<String> = self.0.iter().map(Op::to_string).collect();
+        f.write_str(&ops.join(" "))
+    }
+}
+
+impl FromStr for Script {
+    type Err = ScriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Script)
+    }
+}
+
+serde_via_string!(Script);
+
+/// Что скрипт знает о тратящей транзакции.
+#[derive(Debug, Clone, Copy)]
+pub struct ScriptContext<'a> {
+    /// Подписываемое содержимое транзакции.
+    pub payload: &'a str,
+    /// Высота, не ниже которой блок с тратой: для UTXO — высота блока,
+    /// для перевода со счёта — его `lock_time` (см. `Transaction::is_final`).
+    pub height: u32,
+}
+
+/// Исполняет отпирающий скрипт `unlock`, затем запирающий `lock`.
+/// Возвращает потраченный газ.
+pub fn verify(unlock: &Script, lock: &Script, context: ScriptContext) -> Result<u64, ScriptError> {
+    if !unlock.is_push_only() {
+        return Err(ScriptError::NonPushUnlock);
+    }
+    let mut machine = Machine {
+        stack: Vec::new(),
+        gas: 0,
+        context,
+    };
+    machine.run(unlock)?;
+    machine.run(lock)?;
+    match machine.stack.last() {
+        Some(top) if truthy(top) => Ok(machine.gas),
+        _ => Err(ScriptError::FalseResult),
+    }
+}
+
+struct Machine<'a> {
+    stack: Vec<Vec<u8>>,
+    gas: u64,
+    context: ScriptContext<'a>,
+}
+
+impl Machine<'_> {
+    fn run(&mut self, script: &Script) -> Result<(), ScriptError> {
+        if script.0.len() > MAX_SCRIPT_OPS {
+            return Err(ScriptError::ScriptTooLong);
+        }
+        for op in &script.0 {
+            self.charge(OP_GAS)?;
+            self.step(op)?;
+            if self.stack.len() > MAX_STACK_DEPTH {
+                return Err(ScriptError::StackOverflow);
+            }
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, op: &Op) -> Result<(), ScriptError> {
+        match op {
+            Op::Push(data) => {
+                if data.len() > MAX_PUSH_SIZE {
+                    return Err(ScriptError::PushTooLarge);
+                }
+                self.stack.push(data.clone());
+            }
+            Op::Dup => {
+                let top = self.peek()?.clone();
+                self.stack.push(top);
+            }
+            Op::Drop => {
+                self.pop()?;
+            }
+            Op::Swap => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                self.stack.push(a);
+                self.stack.push(b);
+            }
+            Op::Equal | Op::EqualVerify => {
+                let equal = self.pop()? == self.pop()?;
+                self.push_bool(equal);
+                if *op == Op::EqualVerify {
+                    self.verify_top()?;
+                }
+            }
+            Op::Verify => self.verify_top()?,
+            Op::Return => return Err(ScriptError::OpReturn),
+            Op::Hash160 => {
+                let data = self.pop()?;
+                self.stack
+                    .push(Ripemd160::digest(Sha256::digest(&data)).to_vec());
+            }
+            Op::Sha256 => {
+                let data = self.pop()?;
+                self.stack.push(Sha256::digest(&data).to_vec());
+            }
+            Op::CheckSig | Op::CheckSigVerify => {
+                self.charge(SIG_GAS)?;
+                let key = self.pop()?;
+                let signature = self.pop()?;
+                let valid = check_signature(&key, &signature, self.context.payload);
+                self.push_bool(valid);
+                if *op == Op::CheckSigVerify {
+                    self.verify_top()?;
+                }
+            }
+            Op::CheckMultiSig => self.check_multisig()?,
+            Op::CheckLockTimeVerify => {
+                let lock = number(self.peek()?)?;
+                let height = u64::from(self.context.height);
+                if height < lock {
+                    return Err(ScriptError::LockTimeNotReached { lock, height });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `<sig…> m <key…> n OP_CHECKMULTISIG`: подписи сверяются с ключами
+    /// по порядку, каждый ключ используется не больше одного раза.
+    fn check_multisig(&mut self) -> Result<(), ScriptError> {
+        let n = self.pop_count()?;
+        let keys: Vec<Vec<u8>> = (0..n).map(|_| self.pop()).collect::<Result<_, _>>()?;
+        let m = self.pop_count()?;
+        if m > n {
+            return Err(ScriptError::InvalidMultisigCount);
+        }
+        let signatures: Vec<Vec<u8>> = (0..m).map(|_| self.pop()).collect::<Result<_, _>>()?;
+        self.charge(SIG_GAS * n as u64)?;
+
+        // Со стека всё снято в обратном порядке
+        let mut keys = keys.iter().rev();
+        let valid = signatures
+            .iter()
+            .rev()
+            .all(|signature| keys.any(|key| check_signature(key, signature, self.context.payload)));
+        self.push_bool(valid);
+        Ok(())
+    }
+
+    fn charge(&mut self, gas: u64) -> Result<(), ScriptError> {
+        self.gas += gas;
+        if self.gas > MAX_SCRIPT_GAS {
+            return Err(ScriptError::OutOfGas);
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Vec<u8>, ScriptError> {
+        self.stack.pop().ok_or(ScriptError::StackUnderflow)
+    }
+
+    fn peek(&self) -> Result<&Vec<u8>, ScriptError> {
+        self.stack.last().ok_or(ScriptError::StackUnderflow)
+    }
+
+    fn pop_count(&mut self) -> Result<usize, ScriptError> {
+        let count = number(&self.pop()?)?;
+        usize::try_from(count)
+            .ok()
+            .filter(|&count| count <= MAX_STACK_DEPTH)
+            .ok_or(ScriptError::InvalidMultisigCount)
+    }
+
+    fn push_bool(&mut self, value: bool) {
+        self.stack.push(if value { vec![1] } else { Vec::new() });
+    }
+
+    fn verify_top(&mut self) -> Result<(), ScriptError> {
+        if truthy(&self.pop()?) {
+            Ok(())
+        } else {
+            Err(ScriptError::VerifyFailed)
+        }
+    }
+}
+
+fn truthy(value: &[u8]) -> bool {
+    value.iter().any(|&byte| byte != 0)
+}
+
+fn number(bytes: &[u8]) -> Result<u64, ScriptError> {
+    if bytes.len() > 8 {
+        return Err(ScriptError::InvalidNumber);
+    }
+    let mut le = [0u8; 8];
+    le[..bytes.len()].copy_from_slice(bytes);
+    Ok(u64::from_le_bytes(le))
+}
+
+/// Подпись compact (64 байта) ключа `key` под SHA-256 от `payload`.
+fn check_signature(key: &[u8], signature: &[u8], payload: &str) -> bool {
+    let Ok(key) = PublicKey::from_slice(key) else {
+        return false;
+    };
+    let Ok(signature) = secp256k1::ecdsa::Signature::from_compact(signature) else {
+        return false;
+    };
+    let digest = Sha256::digest(payload.as_bytes());
+    let Ok(message) = secp256k1::Message::from_digest_slice(&digest) else {
+        return false;
+    };
+    Secp256k1::verification_only()
+        .verify_ecdsa(&message, &signature, &key)
+        .is_ok()
+}
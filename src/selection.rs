@@ -0,0 +1,195 @@
+use crate::block::Transaction;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// ========== TX SELECTION ==============
+// Как майнер наполняет блок из mempool. Стратегия получает готовые к
+// включению транзакции и возвращает индексы выбранных в порядке
+// включения; пределы блока по числу и байтам ей передаются, а
+// `MemPool::take_for_block_with` перепроверяет их, так что ошибка
+// стратегии не даст блок сверх лимитов. Порядок nonce внутри блока
+// не важен — важно лишь, чтобы nonce не повторялся.
+
+pub trait TxSelection: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Индексы транзакций из `candidates` для блока, не больше
+    /// `max_count` штук и `max_bytes` байт в сумме.
+    fn select(&self, candidates: &[Transaction], max_count: usize, max_bytes: usize) -> Vec<usize>;
+}
+
+/// Сначала большая комиссия, при равной — более ранняя транзакция.
+/// Стратегия по умолчанию.
+pub struct FeePriority;
+
+impl TxSelection for FeePriority {
+    fn name(&self) -> &'static str {
+        "fee-priority"
+    }
+
+    fn select(&self, candidates: &[Transaction], max_count: usize, max_bytes: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..candidates.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&candidates[a], &candidates[b]);
+            b.fee
+                .cmp(&a.fee)
+                .then_with(|| a.timestamp.cmp(&b.timestamp))
+        });
+        fill(candidates, order, max_count, max_bytes)
+    }
+}
+
+/// Транзакции в порядке поступления, без оглядки на комиссию.
+pub struct OldestFirst;
+
+impl TxSelection for OldestFirst {
+    fn name(&self) -> &'static str {
+        "oldest-first"
+    }
+
+    fn select(&self, candidates: &[Transaction], max_count: usize, max_bytes: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..candidates.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&candidates[a], &candidates[b]);
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then_with(|| b.fee.cmp(&a.fee))
+        });
+        fill(candidates, order, max_count, max_bytes)
+    }
+}
+
+/// По кругу: в каждом раунде по одной транзакции от каждого отправителя
+/// (у отправителя — по возрастанию nonce), так что один адрес с сотней
+/// транзакций не вытеснит остальных. Внутри раунда отправители идут по
+/// убыванию комиссии.
+pub struct SenderFairness;
+
+impl TxSelection for SenderFairness {
+    fn name(&self) -> &'static str {
+        "sender-fairness"
+    }
+
+    fn select(&self, candidates: &[Transaction], max_count: usize, max_bytes: usize) -> Vec<usize> {
+        let mut queues: HashMap<_, Vec<usize>> = HashMap::new();
+        for (i, tx) in candidates.iter().enumerate() {
+            queues.entry(&tx.from).or_default().push(i);
+        }
+        let mut queues: Vec<Vec<usize>> = queues.into_values().collect();
+        for queue in &mut queues {
+            // С конца удобнее снимать: последним кладём меньший nonce
+            queue.sort_by(|&a, &b| candidates[b].nonce.cmp(&candidates[a].nonce));
+        }
+
+        let mut order = Vec::with_capacity(candidates.len());
+        while !queues.is_empty() {
+            let mut round: Vec<usize> = queues.iter_mut().filter_map(Vec::pop).collect();
+            round.sort_by(|&a, &b| {
+                let (a, b) = (&candidates[a], &candidates[b]);
+                b.fee
+                    .cmp(&a.fee)
+                    .then_with(|| a.timestamp.cmp(&b.timestamp))
+            });
+            order.extend(round);
+            queues.retain(|queue| !queue.is_empty());
+        }
+        fill(candidates, order, max_count, max_bytes)
+    }
+}
+
+/// Жадный рюкзак: по убыванию комиссии за байт, так что блок
+/// ограниченного размера приносит больше комиссий, чем при выборе
+/// по абсолютной комиссии.
+pub struct GreedyKnapsack;
+
+impl TxSelection for GreedyKnapsack {
+    fn name(&self) -> &'static str {
+        "knapsack"
+    }
+
+    fn select(&self, candidates: &[Transaction], max_count: usize, max_bytes: usize) -> Vec<usize> {
+        let rate: Vec<f64> = candidates
+            .iter()
+            .map(|tx| tx.fee.units() as f64 / tx.serialized_size().max(1) as f64)
+            .collect();
+        let mut order: Vec<usize> = (0..candidates.len()).collect();
+        order.sort_by(|&a, &b| {
+            rate[b]
+                .total_cmp(&rate[a])
+                .then_with(|| candidates[a].timestamp.cmp(&candidates[b].timestamp))
+        });
+        fill(candidates, order, max_count, max_bytes)
+    }
+}
+
+/// Берёт транзакции в порядке `order`, пропуская не влезающие в
+/// остаток места: за крупной может следовать мелкая, которая влезет.
+fn fill(
+    candidates: &[Transaction],
+    order: Vec<usize>,
+    max_count: usize,
+    max_bytes: usize,
+) -> Vec<usize> {
+    let mut taken = Vec::new();
+    let mut bytes = 0;
+    for i in order {
+        if taken.len() == max_count {
+            break;
+        }
+        let size = candidates[i].serialized_size();
+        if bytes + size <= max_bytes {
+            bytes += size;
+            taken.push(i);
+        }
+    }
+    taken
+}
+
+/// Имя стратегии в конфигурации узла и флаге `--tx-selection`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectionStrategy {
+    #[default]
+    FeePriority,
+    OldestFirst,
+    SenderFairness,
+    Knapsack,
+}
+
+impl SelectionStrategy {
+    pub const ALL: [SelectionStrategy; 4] = [
+        SelectionStrategy::FeePriority,
+        SelectionStrategy::OldestFirst,
+        SelectionStrategy::SenderFairness,
+        SelectionStrategy::Knapsack,
+    ];
+
+    pub fn selection(self) -> Arc<dyn TxSelection> {
+        match self {
+            SelectionStrategy::FeePriority => Arc::new(FeePriority),
+            SelectionStrategy::OldestFirst => Arc::new(OldestFirst),
+            SelectionStrategy::SenderFairness => Arc::new(SenderFairness),
+            SelectionStrategy::Knapsack => Arc::new(GreedyKnapsack),
+        }
+    }
+}
+
+impl fmt::Display for SelectionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.selection().name())
+    }
+}
+
+impl FromStr for SelectionStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SelectionStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.to_string() == s)
+            .ok_or_else(|| format!("unknown transaction selection strategy: {}", s))
+    }
+}
@@ -0,0 +1,247 @@
+use crate::amount::Amount;
+use crate::block::{Block, Wallet};
+use crate::error::ShellError;
+use crate::events::NodeEvent;
+use crate::network::{runtime, Node};
+use crate::types::Address;
+use crate::watch::{Direction, WatchWallet};
+use std::str::FromStr;
+use std::thread::{self, JoinHandle};
+use tokio::sync::broadcast::error::RecvError;
+
+// ========== SHELL ==============
+// `blockchain shell` запускает узел вместе с кошельком из keystore и
+// принимает команды со стандартного ввода — для тех, кто не хочет
+// собирать запросы к RPC:
+//
+//   balance [адрес]                 — баланс, по умолчанию своего адреса
+//   send <адрес> <сумма> [комиссия] — перевод со своего адреса
+//   peers                           — подключённые пиры
+//   mine [n]                        — добыть n блоков с наградой себе
+//   info                            — сводка об узле и цепи
+//   mempool                         — ожидающие транзакции
+//   help, quit
+//
+// Пока оболочка открыта, узел работает как обычно, а о каждом блоке
+// с транзакциями своего адреса сообщает `Shell::watch_blocks`. Разбор и
+// выполнение команд не трогают терминал, так что их можно проверять
+// без него.
+
+/// Комиссия `send`, если она не указана.
+pub const DEFAULT_SHELL_FEE: Amount = Amount::from_units(10_000_000);
+
+/// Подсказка по командам для `help`.
+pub const HELP: &str = "\
+Commands:
+  balance [address]               balance of your or another address
+  send <address> <amount> [fee]   transfer from your address
+  peers                           connected peers
+  mine [n]                        mine n blocks with the reward to you
+  info                            node and chain summary
+  mempool                         pending transactions
+  help                            this list
+  quit                            save the chain and exit";
+
+const SEND_USAGE: &str = "send <address> <amount> [fee]";
+const MINE_USAGE: &str = "mine [n]";
+
+/// Команда оболочки.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShellCommand {
+    Balance(Option<Address>),
+    Send {
+        to: Address,
+        amount: Amount,
+        fee: Amount,
+    },
+    Peers,
+    Mine(usize),
+    Info,
+    Mempool,
+    Help,
+    Quit,
+}
+
+fn parse_address(address: &str) -> Result<Address, ShellError> {
+    let address = Address::from(address);
+    address.validate().map_err(ShellError::InvalidAddress)?;
+    Ok(address)
+}
+
+impl FromStr for ShellCommand {
+    type Err = ShellError;
+
+    fn from_str(line: &str) -> Result<Self, ShellError> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["balance"] => Ok(ShellCommand::Balance(None)),
+            ["balance", address] => Ok(ShellCommand::Balance(Some(parse_address(address)?))),
+            ["send", to, amount, rest @ ..] if rest.len() <= 1 => {
+                let amount = amount.parse().map_err(|_| ShellError::Usage(SEND_USAGE))?;
+                let fee = match rest.first() {
+                    Some(fee) => fee.parse().map_err(|_| ShellError::Usage(SEND_USAGE))?,
+                    None => DEFAULT_SHELL_FEE,
+                };
+                Ok(ShellCommand::Send {
+                    to: parse_address(to)?,
+                    amount,
+                    fee,
+                })
+            }
+            ["send", ..] => Err(ShellError::Usage(SEND_USAGE)),
+            ["peers"] => Ok(ShellCommand::Peers),
+            ["mine"] => Ok(ShellCommand::Mine(1)),
+            ["mine", count] => match count.parse() {
+                Ok(count) if count > 0 => Ok(ShellCommand::Mine(count)),
+                _ => Err(ShellError::Usage(MINE_USAGE)),
+            },
+            ["info"] | ["chain"] => Ok(ShellCommand::Info),
+            ["mempool"] => Ok(ShellCommand::Mempool),
+            ["help"] | ["?"] => Ok(ShellCommand::Help),
+            ["quit"] | ["exit"] => Ok(ShellCommand::Quit),
+            _ => Err(ShellError::UnknownCommand(line.trim().to_string())),
+        }
+    }
+}
+
+/// Узел и кошелёк, от имени которого оболочка переводит и майнит.
+pub struct Shell {
+    node: Node,
+    wallet: Wallet,
+}
+
+impl Shell {
+    pub fn new(node: Node, wallet: Wallet) -> Shell {
+        Shell { node, wallet }
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.get_address()
+    }
+
+    /// Выполняет команду и возвращает текст ответа. `Quit` ничего не
+    /// делает: выходить и сохранять цепь — забота вызывающего.
+    pub fn execute(&self, command: &ShellCommand) -> Result<String, ShellError> {
+        match command {
+            ShellCommand::Balance(address) => {
+                let address = address.clone().unwrap_or_else(|| self.address());
+                let bc = self.node.blockchain.blocking_read();
+                Ok(format!("{} -> {}", address, bc.get_balance(&address)))
+            }
+            ShellCommand::Send { to, amount, fee } => {
+                let tx = {
+                    let mut bc = self.node.blockchain.blocking_write();
+                    let nonce = bc.next_nonce(&self.address());
+                    let tx = self.wallet.create_transaction_for_chain(
+                        bc.chain_id(),
+                        to,
+                        *amount,
+                        *fee,
+                        nonce,
+                    );
+                    bc.add_transaction(tx.clone())?;
+                    tx
+                };
+                self.node.broadcast_transaction(&tx);
+                Ok(format!("✅ Transaction {} added to mempool", tx.id()))
+            }
+            ShellCommand::Peers => {
+                let peers = self.node.peers.lock().unwrap();
+                if peers.is_empty() {
+                    return Ok("No peers".to_string());
+                }
+                let lines: Vec<String> = peers
+                    .iter()
+                    .map(|peer| {
+                        format!(
+                            "{}  {}  height {}",
+                            peer.addr, peer.node_id, peer.best_height
+                        )
+                    })
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            ShellCommand::Mine(count) => {
+                let blocks = self.node.generate(*count, &self.address())?;
+                let tip = blocks.last().expect("at least one block is mined");
+                Ok(format!(
+                    "⛏️  Mined {} block(s), tip #{} {}",
+                    blocks.len(),
+                    tip.header.index,
+                    tip.hash
+                ))
+            }
+            ShellCommand::Info => {
+                let status = self.node.blockchain.blocking_read().chain_stats();
+                Ok(format!("{}\nStatus: {}", self.node.get_node_info(), status))
+            }
+            ShellCommand::Mempool => {
+                let bc = self.node.blockchain.blocking_read();
+                if bc.mempool.transactions.is_empty() {
+                    return Ok("Mempool is empty".to_string());
+                }
+                let lines: Vec<String> = bc
+                    .mempool
+                    .transactions
+                    .iter()
+                    .map(|tx| tx.to_string())
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            ShellCommand::Help => Ok(HELP.to_string()),
+            ShellCommand::Quit => Ok(String::new()),
+        }
+    }
+
+    /// Сообщения о транзакциях своего адреса в блоке `block`.
+    pub fn notices(&self, block: &Block) -> Vec<String> {
+        block_notices(&self.wallet.watch_only(), block)
+    }
+
+    /// Передаёт `notify` сообщения о каждом новом блоке со своими
+    /// транзакциями, пока узел не остановлен.
+    pub fn watch_blocks(&self, notify: impl Fn(String) + Send + 'static) -> JoinHandle<()> {
+        let node = self.node.clone_node();
+        let watch = self.wallet.watch_only();
+        let mut events = node.subscribe();
+        thread::spawn(move || {
+            let _worker = node.worker();
+            loop {
+                let event = runtime().block_on(async {
+                    tokio::select! {
+                        _ = node.shutdown.cancelled() => None,
+                        event = events.recv() => Some(event),
+                    }
+                });
+                let (index, hash) = match event {
+                    Some(Ok(NodeEvent::BlockAdded { index, hash, .. })) => (index, hash),
+                    Some(Ok(_)) | Some(Err(RecvError::Lagged(_))) => continue,
+                    Some(Err(RecvError::Closed)) | None => return,
+                };
+                let block = node.blockchain.blocking_read().full_block(index as usize);
+                if let Ok(Some(block)) = block {
+                    if block.hash == hash {
+                        block_notices(&watch, &block).into_iter().for_each(&notify);
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn block_notices(watch: &WatchWallet, block: &Block) -> Vec<String> {
+    watch
+        .block_transactions(block)
+        .into_iter()
+        .map(|entry| {
+            let tx = entry.tx;
+            let what = match entry.direction {
+                Direction::Incoming if tx.is_coinbase() => format!("mining reward {}", tx.amount),
+                Direction::Incoming => format!("received {} from {}", tx.amount, tx.from),
+                Direction::Outgoing => format!("sent {} to {} (fee {})", tx.amount, tx.to, tx.fee),
+                Direction::ToSelf => format!("moved {} to yourself (fee {})", tx.amount, tx.fee),
+            };
+            format!("🔔 Block #{}: {}", entry.height, what)
+        })
+        .collect()
+}
@@ -0,0 +1,49 @@
+use crate::block::Wallet;
+use crate::types::{Address, Hash256, Signature};
+use secp256k1::{Secp256k1, SecretKey};
+
+// ========== SIGNER ==============
+// Всё, что умеет подписывать хеш сообщения secp256k1-ключом: кошелёк в
+// памяти, HSM, удалённый сервис подписи или подделка в тестах. Переводы
+// (`Transaction::sign_with`) и блоки (`Block::sign`, `SignedBlocks`,
+// `ProofOfStake`) подписываются через этот трейт, так что другой
+// подписант подключается без изменений в вызывающем коде.
+//
+// Подписывается SHA-256 от payload — тот же хеш, что проверяет
+// `verify_ecdsa`.
+
+pub trait Signer: Send + Sync {
+    /// Сжатый открытый ключ в hex, как в `Wallet::public_key`.
+    fn public_key(&self) -> String;
+
+    /// Подпись хеша сообщения в компактной форме.
+    fn sign(&self, message_hash: &Hash256) -> Signature;
+
+    fn address(&self) -> Address {
+        Address::from_public_key(&hex::decode(self.public_key()).expect("Invalid public key"))
+    }
+
+    /// Подпись SHA-256 от `payload`.
+    fn sign_payload(&self, payload: &str) -> Signature {
+        self.sign(&Hash256::digest(payload.as_bytes()))
+    }
+}
+
+impl Signer for Wallet {
+    fn public_key(&self) -> String {
+        self.public_key.clone()
+    }
+
+    fn sign(&self, message_hash: &Hash256) -> Signature {
+        let secp = Secp256k1::signing_only();
+        let secret_key =
+            SecretKey::from_slice(&hex::decode(&self.private_key).expect("Invalid key"))
+                .expect("Invalid secret key");
+        let message = secp256k1::Message::from_digest(*message_hash.as_bytes());
+        Signature(secp.sign_ecdsa(&message, &secret_key).serialize_compact())
+    }
+
+    fn address(&self) -> Address {
+        self.get_address()
+    }
+}
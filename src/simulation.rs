@@ -0,0 +1,255 @@
+use crate::banlist::peer_ip;
+use crate::block::Blockchain;
+use crate::genesis::GenesisConfig;
+use crate::network::{handle_message, Node};
+use crate::protocol::Message;
+use crate::transport::Transport;
+use crate::types::Hash256;
+use futures_util::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ========== SIMULATION ==============
+// Несколько узлов в одном процессе без TCP: сообщения передаются
+// напрямую обработчику узла-получателя через `SimulatedNetwork`, так что
+// тесты консенсуса и синхронизации не зависят от свободных портов.
+// Задержку можно задать для всей сети или отдельной пары узлов, а сеть —
+// разбить на группы, которые не слышат друг друга (`partition`), и
+// затем соединить снова (`heal`).
+//
+// Узел симуляции — обычный `Node` с транспортом `Transport::Simulated`:
+// его API (подключение, синхронизация, майнинг, рассылка) работает как
+// по TCP. Адреса узлов — `10.0.0.{i + 1}:3000`, у каждого свой IP для
+// бан-листа. Ограничение частоты сообщений в симуляции не действует.
+
+/// Сколько `wait_until` ждёт между проверками условия.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Сеть в памяти: кто по какому адресу и что мешает доставке.
+#[derive(Default)]
+pub struct SimulatedNetwork {
+    nodes: Mutex<HashMap<String, Node>>,
+    latency: Mutex<Duration>,
+    /// Задержка отдельных направлений (от, к) вместо общей.
+    link_latency: Mutex<HashMap<(String, String), Duration>>,
+    /// Группа каждого адреса при разрыве; узлы разных групп не слышат
+    /// друг друга. Пусто — сеть цела.
+    groups: Mutex<HashMap<String, usize>>,
+}
+
+impl SimulatedNetwork {
+    fn latency(&self, from: &str, to: &str) -> Duration {
+        let key = (from.to_string(), to.to_string());
+        match self.link_latency.lock().unwrap().get(&key) {
+            Some(&latency) => latency,
+            None => *self.latency.lock().unwrap(),
+        }
+    }
+
+    fn reachable(&self, from: &str, to: &str) -> bool {
+        let groups = self.groups.lock().unwrap();
+        groups.get(from) == groups.get(to)
+    }
+
+    /// Узел по адресу `to`, если сообщение от `from` может до него дойти.
+    fn route(&self, from: &str, to: &str) -> Option<Node> {
+        if !self.reachable(from, to) {
+            return None;
+        }
+        let node = self.nodes.lock().unwrap().get(to)?.clone_node();
+        let ip = peer_ip(from)?;
+        (!node.is_shutting_down() && !node.is_banned(ip)).then_some(node)
+    }
+}
+
+/// Транспорт узла симуляции: его адрес и сеть, через которую он говорит.
+/// Сеть держится по слабой ссылке, чтобы она не жила вечно из-за узлов,
+/// которые сама же хранит.
+#[derive(Clone)]
+pub struct SimulatedLink {
+    network: Weak<SimulatedNetwork>,
+    addr: String,
+}
+
+impl SimulatedLink {
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Доставляет сообщение узлу `to` с задержкой в одну сторону и
+    /// возвращает его ответ с задержкой в обратную. `None`, если узла
+    /// нет, он по другую сторону разрыва или забанил отправителя.
+    ///
+    /// Обработчик сообщения сам шлёт запросы через этот транспорт, так
+    /// что future в коробке: иначе компилятор не выведет `Send` для цикла
+    /// запрос → обработчик → запрос.
+    pub(crate) fn request<'a>(
+        &'a self,
+        to: &'a str,
+        message: Message,
+    ) -> BoxFuture<'a, Option<Message>> {
+        Box::pin(async move {
+            let network = self.network.upgrade()?;
+            tokio::time::sleep(network.latency(&self.addr, to)).await;
+            let node = network.route(&self.addr, to)?;
+            let from = peer_ip(&self.addr)?;
+            let reply = handle_message(message, from, &node).await?;
+            tokio::time::sleep(network.latency(to, &self.addr)).await;
+            network.reachable(to, &self.addr).then_some(reply)
+        })
+    }
+
+    /// Отправляет сообщение без ожидания доставки, как запись в сокет.
+    pub(crate) fn send(&self, to: String, message: Message) {
+        let link = self.clone();
+        tokio::spawn(async move {
+            link.request(&to, message).await;
+        });
+    }
+}
+
+/// `count` узлов в общей сети в памяти.
+pub struct Simulation {
+    network: Arc<SimulatedNetwork>,
+    nodes: Vec<Node>,
+}
+
+impl Simulation {
+    /// Узлы сети по умолчанию; друг о друге они ещё не знают.
+    pub fn new(count: usize) -> Self {
+        Self::with_genesis(count, &GenesisConfig::default())
+    }
+
+    /// Узлы сети из `genesis`, например `GenesisConfig::regtest`.
+    pub fn with_genesis(count: usize, genesis: &GenesisConfig) -> Self {
+        let network = Arc::new(SimulatedNetwork::default());
+        let nodes = (0..count)
+            .map(|i| {
+                let addr = Self::addr_of(i);
+                let mut node = Node::new(format!("sim{}", i));
+                *node.blockchain.blocking_write() = Blockchain::new(genesis);
+                *node.listen_addr.lock().unwrap() = Some(addr.clone());
+                node.transport = Transport::Simulated(SimulatedLink {
+                    network: Arc::downgrade(&network),
+                    addr: addr.clone(),
+                });
+                network
+                    .nodes
+                    .lock()
+                    .unwrap()
+                    .insert(addr, node.clone_node());
+                node
+            })
+            .collect();
+        Simulation { network, nodes }
+    }
+
+    fn addr_of(index: usize) -> String {
+        assert!(index < 254, "simulation supports up to 254 nodes");
+        format!("10.0.0.{}:3000", index + 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, index: usize) -> &Node {
+        &self.nodes[index]
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Адрес узла `index` в симулированной сети.
+    pub fn addr(&self, index: usize) -> String {
+        Self::addr_of(index)
+    }
+
+    /// Узел `from` подключается к `to`, см. `Node::connect_to_peer`.
+    pub fn connect(&self, from: usize, to: usize) -> bool {
+        self.nodes[from].connect_to_peer(&self.addr(to))
+    }
+
+    /// Подключает каждый узел к каждому.
+    pub fn connect_all(&self) {
+        for from in 0..self.len() {
+            for to in from + 1..self.len() {
+                self.connect(from, to);
+            }
+        }
+    }
+
+    /// Задержка доставки в одну сторону для всех пар узлов без своей.
+    pub fn set_latency(&self, latency: Duration) {
+        *self.network.latency.lock().unwrap() = latency;
+    }
+
+    /// Задержка между узлами `a` и `b` в обе стороны.
+    pub fn set_link_latency(&self, a: usize, b: usize, latency: Duration) {
+        let mut links = self.network.link_latency.lock().unwrap();
+        links.insert((self.addr(a), self.addr(b)), latency);
+        links.insert((self.addr(b), self.addr(a)), latency);
+    }
+
+    /// Разбивает сеть на группы узлов, которые не слышат друг друга.
+    /// Узлы, не попавшие ни в одну группу, образуют ещё одну.
+    pub fn partition(&self, groups: &[&[usize]]) {
+        let mut map = HashMap::new();
+        for (group, members) in groups.iter().enumerate() {
+            for &index in *members {
+                map.insert(self.addr(index), group);
+            }
+        }
+        for index in 0..self.len() {
+            map.entry(self.addr(index)).or_insert(groups.len());
+        }
+        *self.network.groups.lock().unwrap() = map;
+    }
+
+    /// Восстанавливает связь между всеми узлами.
+    pub fn heal(&self) {
+        self.network.groups.lock().unwrap().clear();
+    }
+
+    /// Хеши вершин цепей узлов по порядку.
+    pub fn tips(&self) -> Vec<Hash256> {
+        self.nodes
+            .iter()
+            .map(|node| node.blockchain.blocking_read().latest_block().hash)
+            .collect()
+    }
+
+    /// У всех узлов одна и та же вершина.
+    pub fn converged(&self) -> bool {
+        let tips = self.tips();
+        tips.windows(2).all(|pair| pair[0] == pair[1])
+    }
+
+    /// Ждёт, пока `condition` не выполнится, но не дольше `timeout`.
+    pub fn wait_until(&self, timeout: Duration, condition: impl Fn(&Simulation) -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if condition(self) {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Останавливает все узлы.
+    pub fn shutdown(&self) {
+        for node in &self.nodes {
+            let _ = node.shutdown();
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::amount::Amount;
+use crate::block::Blockchain;
+use crate::storage::{invalid_data, read_chain};
+use crate::types::{Address, Hash256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// ========== SNAPSHOTS ==============
+// Снимок состояния: балансы всех адресов на некоторой высоте и хеш блока
+// этой высоты. При запуске блоки до снимка проверяются только по хешам,
+// а полностью проверяются и проигрываются лишь более новые.
+
+/// Через сколько блоков узел по умолчанию сохраняет снимок.
+pub const DEFAULT_SNAPSHOT_INTERVAL: u32 = 100;
+/// Имя файла снимка внутри `data_dir`.
+pub const SNAPSHOT_FILE_NAME: &str = "state_snapshot.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    /// Индекс последнего блока, учтённого в снимке.
+    pub height: u32,
+    pub tip_hash: Hash256,
+    pub balances: BTreeMap<Address, Amount>,
+}
+
+impl StateSnapshot {
+    /// Записывает снимок через временный файл, чтобы оборванная запись
+    /// не испортила предыдущий.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        drop(writer);
+        fs::rename(tmp, path)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<StateSnapshot> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| invalid_data(e.to_string()))
+    }
+}
+
+impl Blockchain {
+    /// Снимок состояния на вершине цепи.
+    pub fn snapshot(&self) -> StateSnapshot {
+        let tip = self.latest_block();
+        StateSnapshot {
+            height: tip.header.index,
+            tip_hash: tip.hash,
+            balances: self.current_balances().into_iter().collect(),
+        }
+    }
+
+    /// То же, что `restore_from_disk`, но состояние до высоты снимка
+    /// берётся из `snapshot`. Блок этой высоты в файле должен иметь хеш
+    /// `tip_hash`, а блоки до него — верные хеши и связность; более новые
+    /// проверяются полностью и проводятся по балансам из снимка. Выходов
+    /// UTXO снимок не несёт: их множество проигрывается по всем блокам.
+    pub fn restore_from_snapshot<P: AsRef<Path>>(
+        mut self,
+        path: P,
+        snapshot: &StateSnapshot,
+    ) -> io::Result<Blockchain> {
+        let chain = read_chain(path)?;
+        let height = snapshot.height as usize;
+        match chain.get(height) {
+            Some(block) if block.hash == snapshot.tip_hash => {}
+            Some(_) => {
+                return Err(invalid_data(format!(
+                    "snapshot does not match block {}",
+                    height
+                )))
+            }
+            None => {
+                return Err(invalid_data(format!(
+                    "snapshot height {} is beyond the stored chain",
+                    height
+                )))
+            }
+        }
+
+        for (prev, block) in chain[..=height].iter().zip(&chain[1..=height]) {
+            if block.header.prev_hash != prev.hash || !block.has_valid_hash() {
+                return Err(invalid_data(format!(
+                    "block {} has a bad hash",
+                    block.header.index
+                )));
+            }
+        }
+        self.validate_chain_from(&chain, height + 1)
+            .map_err(|e| invalid_data(format!("stored chain failed validation: {}", e)))?;
+
+        self.chain = chain;
+        self.reindex_blocks();
+        self.balances = snapshot.balances.clone().into_iter().collect();
+        self.balances_height = height + 1;
+        for newer in height + 1..self.chain.len() {
+            self.apply_balances(newer);
+        }
+        self.rebuild_utxos();
+        self.bits = self.next_bits(&self.chain);
+
+        Ok(self)
+    }
+}
@@ -0,0 +1,123 @@
+use crate::block::{Block, Blockchain, MemPool, Transaction};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use tracing::debug;
+
+// ========== STORAGE ==============
+// Цепь хранится как append-only файл: по одному блоку в JSON на строку.
+// Mempool сохраняется так же, по транзакции на строку, но переписывается
+// целиком: при перезапуске узел не теряет ожидающие транзакции.
+
+pub(crate) fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Читает блоки из файла цепи, не проверяя их.
+pub(crate) fn read_chain<P: AsRef<Path>>(path: P) -> io::Result<Vec<Block>> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut chain = Vec::new();
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let block = Block::from_json(&line)
+            .map_err(|e| invalid_data(format!("line {}: {}", line_no + 1, e)))?;
+        chain.push(block);
+    }
+
+    if chain.is_empty() {
+        return Err(invalid_data("chain file is empty".to_string()));
+    }
+    Ok(chain)
+}
+
+impl Blockchain {
+    /// Полностью перезаписывает файл текущей цепью.
+    pub fn save_to_disk<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for block in &self.chain {
+            writeln!(writer, "{}", block.to_json())?;
+        }
+        writer.flush()
+    }
+
+    /// Дописывает один блок в конец файла, не переписывая остальные.
+    pub fn append_block_to_disk<P: AsRef<Path>>(block: &Block, path: P) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", block.to_json())
+    }
+
+    /// Загружает цепь из файла и проверяет её целиком перед тем,
+    /// как вернуть. Цель PoW восстанавливается по последним блокам.
+    pub fn load_from_disk<P: AsRef<Path>>(path: P) -> io::Result<Blockchain> {
+        Blockchain::default().restore_from_disk(path)
+    }
+
+    /// То же, что `load_from_disk`, но блоки из файла заменяют цепь
+    /// `self`, а её параметры (сложность, mempool, консенсус) остаются
+    /// и используются при проверке.
+    pub fn restore_from_disk<P: AsRef<Path>>(mut self, path: P) -> io::Result<Blockchain> {
+        self.chain = read_chain(path)?;
+        self.reindex();
+        self.validate_chain(&self.chain)
+            .map_err(|e| invalid_data(format!("stored chain failed validation: {}", e)))?;
+        self.bits = self.next_bits(&self.chain);
+
+        Ok(self)
+    }
+}
+
+impl MemPool {
+    /// Сохраняет ожидающие транзакции. Запись идёт через временный файл,
+    /// чтобы оборванная запись не испортила предыдущую.
+    pub fn save_to_disk<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        for tx in &self.transactions {
+            serde_json::to_writer(&mut writer, tx)?;
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(tmp, path)
+    }
+
+    /// Читает транзакции, сохранённые `save_to_disk`, не проверяя их.
+    pub fn load_from_disk<P: AsRef<Path>>(path: P) -> io::Result<Vec<Transaction>> {
+        let reader = BufReader::new(File::open(path)?);
+
+        let mut transactions = Vec::new();
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let tx = serde_json::from_str(&line)
+                .map_err(|e| invalid_data(format!("line {}: {}", line_no + 1, e)))?;
+            transactions.push(tx);
+        }
+        Ok(transactions)
+    }
+}
+
+impl Blockchain {
+    /// Возвращает в mempool транзакции, сохранённые до перезапуска. Все
+    /// они заново проходят `add_transaction`, так что подтверждённые,
+    /// пока узел был выключен, и ставшие неоплатными отсеиваются, а
+    /// истёкшие удаляются. Возвращает, сколько транзакций принято.
+    pub fn restore_mempool(&mut self, transactions: Vec<Transaction>) -> usize {
+        for tx in transactions {
+            let txid = tx.id();
+            if let Err(e) = self.add_transaction(tx) {
+                debug!(%txid, error = %e, "dropping stored transaction");
+            }
+        }
+        let now = self.now();
+        self.mempool.purge_expired(now);
+        self.mempool.size()
+    }
+}
@@ -0,0 +1,198 @@
+use crate::amount::Amount;
+use crate::block::{Block, Blockchain};
+use crate::error::SupplyError;
+use crate::utxo::{BlockUndo, UtxoSet};
+
+// ========== SUPPLY ==============
+// Выпуск монет. Новые монеты появляются только в coinbase: genesis-блок
+// зачисляет начальные распределения, а каждый следующий блок — награду
+// по расписанию (`current_reward`) плюс комиссии своих транзакций.
+// Счётная и UTXO-coinbase делят эту сумму (см. `utxo`), а монеты на
+// непотраченных выходах входят в выпуск наравне с балансами.
+// Перевод списывает с отправителя `amount + fee` и зачисляет получателю
+// `amount`, так что сумма зачислений блока не больше суммы списаний и
+// награды, пока coinbase не превышает награду и комиссии, — это и
+// проверяет консенсус.
+//
+// С `max_supply` в genesis награда урезается так, чтобы начальные
+// распределения и награды по расписанию вместе не превысили предел;
+// когда он исчерпан, майнеры получают одни комиссии. Урезанная награда
+// зависит только от высоты, поэтому майнер и проверяющие считают её
+// одинаково. `audit_supply` перепроверяет всё это по всей цепи, включая
+// обрезанные блоки и блоки, принятые из снимка без проверки.
+//
+// Суммы целые (`Amount`), поэтому выпуск сходится с балансами точно:
+// сумма балансов всех адресов равна выпущенному до единицы.
+
+/// Итог `Blockchain::audit_supply`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupplyAudit {
+    /// Высота вершины, до которой проверена цепь.
+    pub height: u32,
+    /// Выпущено монет, считая начальные распределения.
+    pub issued: Amount,
+    /// Сколько всего уплачено комиссий.
+    pub fees: Amount,
+}
+
+impl Blockchain {
+    /// Награда по расписанию без предела выпуска: `mining_reward`,
+    /// уменьшенная вдвое за каждые полные `halving_interval` блоков.
+    /// После 64 уполовиниваний награды нет.
+    fn scheduled_reward(&self, height: u64) -> Amount {
+        let halvings = height
+            .checked_div(self.halving_interval as u64)
+            .unwrap_or(0);
+        if halvings >= 64 {
+            return Amount::ZERO;
+        }
+        Amount::from_units(self.mining_reward.units() >> halvings)
+    }
+
+    /// Сколько выпускают по расписанию блоки `1..height`. Блоки одной
+    /// эпохи получают одинаковую награду, так что сумма считается по
+    /// эпохам, а не по блокам. Насыщается на `Amount::MAX`.
+    fn scheduled_issuance(&self, height: u32) -> Amount {
+        let interval = self.halving_interval as u64;
+        let end = height as u64;
+        let mut issued = Amount::ZERO;
+        let mut start = 1;
+        while start < end {
+            let era_end = match interval {
+                0 => end,
+                interval => ((start / interval + 1) * interval).min(end),
+            };
+            let era = self
+                .scheduled_reward(start)
+                .checked_mul(era_end - start)
+                .unwrap_or(Amount::MAX);
+            issued = issued.saturating_add(era);
+            start = era_end;
+        }
+        issued
+    }
+
+    /// Награда за блок на высоте `height` без комиссий: награда по
+    /// расписанию, урезанная так, чтобы выпуск не превысил `max_supply`.
+    pub fn current_reward(&self, height: u32) -> Amount {
+        let reward = self.scheduled_reward(height as u64);
+        match self.max_supply {
+            Some(cap) => {
+                let left = cap
+                    .saturating_sub(self.genesis_supply())
+                    .saturating_sub(self.scheduled_issuance(height));
+                reward.min(left)
+            }
+            None => reward,
+        }
+    }
+
+    /// Начальные распределения, зачисленные genesis-блоком.
+    pub fn genesis_supply(&self) -> Amount {
+        coinbase_total(&self.chain[0])
+    }
+
+    /// Сколько монет выпущено к вершине: начальные распределения и
+    /// награды майнеров — на балансах и на непотраченных выходах.
+    /// Комиссии только переходят от отправителей к майнерам и выпуск
+    /// не меняют.
+    pub fn total_supply(&self) -> Amount {
+        self.current_balances()
+            .values()
+            .fold(self.utxos().total(), |total, &balance| {
+                total.saturating_add(balance)
+            })
+    }
+
+    /// Перепроверяет выпуск по всей цепи: ни один блок не платит в
+    /// coinbase больше награды и комиссий, выпуск ни на одной высоте не
+    /// превышает `max_supply`, а балансы в сумме равны выпуску.
+    /// Обрезанные блоки читаются из архива.
+    pub fn audit_supply(&self) -> Result<SupplyAudit, SupplyError> {
+        let blocks = self.full_blocks(0..self.chain.len())?;
+        let mut issued = Amount::ZERO;
+        let mut fees = Amount::ZERO;
+        let mut utxos = UtxoSet::new();
+
+        for (height, block) in blocks.iter().enumerate() {
+            let undo = utxos.connect_block(&block.utxo_transactions);
+            let height = height as u32;
+            if height == 0 {
+                issued = coinbase_total(block);
+            } else {
+                // Блоки ниже снимка не проверялись, так что суммы насыщаются
+                let paid = block
+                    .transactions
+                    .iter()
+                    .filter(|tx| !tx.is_coinbase())
+                    .fold(utxo_fees(block, &undo), |paid, tx| {
+                        paid.saturating_add(tx.fee)
+                    });
+                let minted = coinbase_total(block);
+                let allowed = self.current_reward(height).saturating_add(paid);
+                if minted > allowed {
+                    return Err(SupplyError::Inflation {
+                        height,
+                        minted,
+                        allowed,
+                    });
+                }
+                // Комиссии, не забранные майнером, сгорают
+                issued = issued.saturating_add(minted).saturating_sub(paid);
+                fees = fees.saturating_add(paid);
+            }
+            if let Some(cap) = self.max_supply.filter(|&cap| issued > cap) {
+                return Err(SupplyError::CapExceeded {
+                    height,
+                    issued,
+                    cap,
+                });
+            }
+        }
+
+        let balances = self.total_supply();
+        if balances != issued {
+            return Err(SupplyError::Unbalanced { issued, balances });
+        }
+
+        Ok(SupplyAudit {
+            height: self.latest_block().header.index,
+            issued,
+            fees,
+        })
+    }
+}
+
+/// Сколько зачисляют coinbase-транзакции блока, счётные и UTXO.
+fn coinbase_total(block: &Block) -> Amount {
+    let utxo = block
+        .utxo_transactions
+        .iter()
+        .filter(|tx| tx.is_coinbase())
+        .flat_map(|tx| &tx.outputs)
+        .fold(Amount::ZERO, |total, output| {
+            total.saturating_add(output.amount)
+        });
+    block
+        .transactions
+        .iter()
+        .filter(|tx| tx.is_coinbase())
+        .fold(utxo, |total, tx| total.saturating_add(tx.amount))
+}
+
+/// Комиссии UTXO-транзакций блока: потраченные им выходы (`undo`)
+/// за вычетом созданных не-coinbase транзакциями.
+fn utxo_fees(block: &Block, undo: &BlockUndo) -> Amount {
+    let inputs = undo.spent.iter().fold(Amount::ZERO, |total, (_, output)| {
+        total.saturating_add(output.amount)
+    });
+    let outputs = block
+        .utxo_transactions
+        .iter()
+        .filter(|tx| !tx.is_coinbase())
+        .flat_map(|tx| &tx.outputs)
+        .fold(Amount::ZERO, |total, output| {
+            total.saturating_add(output.amount)
+        });
+    inputs.saturating_sub(outputs)
+}
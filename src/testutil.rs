@@ -0,0 +1,129 @@
+use crate::amount::Amount;
+use crate::block::{Blockchain, Transaction, Wallet, DEFAULT_TARGET_BLOCK_TIME};
+use crate::clock::MockClock;
+use crate::genesis::GenesisConfig;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::Arc;
+
+// ========== TEST UTILITIES ==============
+// Детерминированные заготовки для тестов и бенчмарков. Ключи берутся из
+// генератора с фиксированным сидом, время — из `MockClock`, так что
+// одинаковая последовательность вызовов даёт одинаковые блоки вплоть
+// до хешей и nonce:
+//
+//   let builder = ChainBuilder::new();
+//   let (alice, bob) = (builder.wallet(1), builder.wallet(2));
+//   let bc = builder.mine(&alice).transfer(&alice, &bob, coins(5.0), coins(0.1)).mine(&alice).build();
+
+/// Сумма из числа монет для краткости тестов: `coins(0.1)`. Паникует
+/// на отрицательных и нечисловых значениях, см. `Amount::from_f64`.
+pub fn coins(coins: f64) -> Amount {
+    Amount::from_f64(coins).expect("invalid amount")
+}
+
+/// Генератор с фиксированным сидом.
+pub fn test_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// Кошелёк, ключ которого задаётся сидом: один сид — один адрес.
+pub fn test_wallet(seed: u64) -> Wallet {
+    Wallet::from_rng(&mut test_rng(seed))
+}
+
+/// Строит цепь на `MockClock`: часы стоят на времени genesis и перед
+/// каждым блоком уходят вперёд на `block_interval` секунд. Методы
+/// паникуют, если блок или транзакция отвергнуты, — это ошибка теста.
+pub struct ChainBuilder {
+    chain: Blockchain,
+    clock: MockClock,
+    block_interval: u64,
+}
+
+impl ChainBuilder {
+    /// Цепь сети по умолчанию.
+    pub fn new() -> Self {
+        Self::with_genesis(&GenesisConfig::default())
+    }
+
+    pub fn with_genesis(genesis: &GenesisConfig) -> Self {
+        let clock = MockClock::new(genesis.timestamp);
+        let mut chain = Blockchain::new(genesis);
+        chain.set_clock(Arc::new(clock.clone()));
+        ChainBuilder {
+            chain,
+            clock,
+            block_interval: DEFAULT_TARGET_BLOCK_TIME,
+        }
+    }
+
+    /// Сколько секунд проходит между блоками (по умолчанию — целевое
+    /// время блока, так что сложность не меняется).
+    pub fn block_interval(mut self, secs: u64) -> Self {
+        self.block_interval = secs;
+        self
+    }
+
+    pub fn clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    /// Кошелёк из сида `seed`, который помечает транзакции часами цепи.
+    pub fn wallet(&self, seed: u64) -> Wallet {
+        test_wallet(seed).with_clock(Arc::new(self.clock.clone()))
+    }
+
+    /// Добывает блок из mempool с наградой на `miner`.
+    pub fn mine(mut self, miner: &Wallet) -> Self {
+        self.clock.advance(self.block_interval);
+        self.chain
+            .mine_block(&miner.get_address())
+            .expect("test block must be accepted");
+        self
+    }
+
+    /// Добывает `count` блоков подряд.
+    pub fn mine_blocks(mut self, count: usize, miner: &Wallet) -> Self {
+        for _ in 0..count {
+            self = self.mine(miner);
+        }
+        self
+    }
+
+    /// Отправляет в mempool перевод со следующим nonce отправителя.
+    pub fn transfer(self, from: &Wallet, to: &Wallet, amount: Amount, fee: Amount) -> Self {
+        let nonce = self.chain.next_nonce(&from.get_address());
+        let tx = from.create_transaction_for_chain(
+            self.chain.chain_id(),
+            &to.get_address(),
+            amount,
+            fee,
+            nonce,
+        );
+        self.submit(tx)
+    }
+
+    /// Отправляет в mempool готовую транзакцию.
+    pub fn submit(mut self, tx: Transaction) -> Self {
+        self.chain
+            .add_transaction(tx)
+            .expect("test transaction must be accepted");
+        self
+    }
+
+    /// Цепь в текущем состоянии, без завершения построения.
+    pub fn chain(&self) -> &Blockchain {
+        &self.chain
+    }
+
+    pub fn build(self) -> Blockchain {
+        self.chain
+    }
+}
+
+impl Default for ChainBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,210 @@
+use crate::block::{Block, Transaction};
+use crate::error::{TokenError, TxValidationError};
+use crate::types::{Address, Hash256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+
+// ========== TOKENS ==============
+// Токены поверх обычных транзакций, без виртуальной машины: транзакция
+// с полем `token` создаёт токен, выпускает его или переводит. Комиссия
+// и nonce — как у обычного перевода, а `amount` в монетах цепи может
+// быть нулевым.
+//
+//   CREATE_TOKEN — новый токен с тикером `symbol`; id токена — txid этой
+//                  транзакции, эмитент — `from`, начальный выпуск
+//                  `supply` зачисляется на `to`.
+//   MINT         — эмитент выпускает ещё `amount` на `to`.
+//   TRANSFER     — `from` переводит `amount` токена на `to`.
+//
+// Балансы токенов — состояние цепи (`TokenLedger`): операции блока
+// проверяются при его подключении, и блок с невыполнимой операцией
+// отвергается целиком. Суммы — целые единицы токена.
+
+/// Тикер: от 1 до стольких заглавных латинских букв и цифр.
+pub const MAX_TOKEN_SYMBOL_LEN: usize = 10;
+
+/// Операция с токеном, см. `Transaction::token`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenOp {
+    CreateToken { symbol: String, supply: u64 },
+    Mint { token: Hash256, amount: u64 },
+    Transfer { token: Hash256, amount: u64 },
+}
+
+// В JSON операция помечена полем `op`, но bincode (блоки в сообщениях
+// пиров и архивах) внутренних тегов не понимает, и там вариант
+// кодируется номером.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TokenOp", tag = "op", rename_all = "SCREAMING_SNAKE_CASE")]
+enum TaggedTokenOp {
+    CreateToken { symbol: String, supply: u64 },
+    Mint { token: Hash256, amount: u64 },
+    Transfer { token: Hash256, amount: u64 },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TokenOp")]
+enum IndexedTokenOp {
+    CreateToken { symbol: String, supply: u64 },
+    Mint { token: Hash256, amount: u64 },
+    Transfer { token: Hash256, amount: u64 },
+}
+
+impl Serialize for TokenOp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            TaggedTokenOp::serialize(self, serializer)
+        } else {
+            IndexedTokenOp::serialize(self, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TokenOp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            TaggedTokenOp::deserialize(deserializer)
+        } else {
+            IndexedTokenOp::deserialize(deserializer)
+        }
+    }
+}
+
+impl TokenOp {
+    /// Проверки, не зависящие от состояния цепи.
+    pub fn validate(&self) -> Result<(), TxValidationError> {
+        match self {
+            TokenOp::CreateToken { symbol, .. } => {
+                let well_formed = !symbol.is_empty()
+                    && symbol.len() <= MAX_TOKEN_SYMBOL_LEN
+                    && symbol
+                        .chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+                if !well_formed {
+                    return Err(TxValidationError::InvalidTokenSymbol(symbol.clone()));
+                }
+            }
+            TokenOp::Mint { amount, .. } | TokenOp::Transfer { amount, .. } => {
+                if *amount == 0 {
+                    return Err(TxValidationError::NonPositiveAmount);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Каноническая запись для подписи транзакции.
+impl fmt::Display for TokenOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenOp::CreateToken { symbol, supply } => {
+                write!(f, "CREATE_TOKEN:{}:{}", symbol, supply)
+            }
+            TokenOp::Mint { token, amount } => write!(f, "MINT:{}:{}", token, amount),
+            TokenOp::Transfer { token, amount } => write!(f, "TRANSFER:{}:{}", token, amount),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenInfo {
+    pub id: Hash256,
+    pub symbol: String,
+    pub issuer: Address,
+    /// Всего выпущено.
+    pub supply: u64,
+}
+
+/// Токены и их балансы после некоторого префикса цепи.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenLedger {
+    tokens: HashMap<Hash256, TokenInfo>,
+    balances: HashMap<(Hash256, Address), u64>,
+}
+
+impl TokenLedger {
+    /// Состояние после всех блоков `blocks`; они должны быть проверены,
+    /// невыполнимые операции пропускаются.
+    pub fn replay(blocks: &[Block]) -> Self {
+        let mut ledger = TokenLedger::default();
+        for tx in blocks.iter().flat_map(|block| &block.transactions) {
+            let _ = ledger.apply(tx);
+        }
+        ledger
+    }
+
+    pub fn token(&self, id: &Hash256) -> Option<&TokenInfo> {
+        self.tokens.get(id)
+    }
+
+    pub fn balance(&self, address: &Address, token: &Hash256) -> u64 {
+        self.balances
+            .get(&(*token, address.clone()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Выполняет операцию транзакции; транзакции без `token` не меняют
+    /// ничего. При ошибке состояние остаётся прежним.
+    pub fn apply(&mut self, tx: &Transaction) -> Result<(), TokenError> {
+        let Some(op) = &tx.token else {
+            return Ok(());
+        };
+        match op {
+            TokenOp::CreateToken { symbol, supply } => {
+                let id = tx.id();
+                self.tokens.insert(
+                    id,
+                    TokenInfo {
+                        id,
+                        symbol: symbol.clone(),
+                        issuer: tx.from.clone(),
+                        supply: *supply,
+                    },
+                );
+                self.credit(id, &tx.to, *supply);
+            }
+            TokenOp::Mint { token, amount } => {
+                let info = self
+                    .tokens
+                    .get_mut(token)
+                    .ok_or(TokenError::UnknownToken(*token))?;
+                if info.issuer != tx.from {
+                    return Err(TokenError::NotIssuer {
+                        token: *token,
+                        address: tx.from.clone(),
+                    });
+                }
+                info.supply = info
+                    .supply
+                    .checked_add(*amount)
+                    .ok_or(TokenError::SupplyOverflow(*token))?;
+                self.credit(*token, &tx.to, *amount);
+            }
+            TokenOp::Transfer { token, amount } => {
+                if !self.tokens.contains_key(token) {
+                    return Err(TokenError::UnknownToken(*token));
+                }
+                let available = self.balance(&tx.from, token);
+                if available < *amount {
+                    return Err(TokenError::InsufficientTokens {
+                        token: *token,
+                        available,
+                        required: *amount,
+                    });
+                }
+                self.balances
+                    .insert((*token, tx.from.clone()), available - amount);
+                self.credit(*token, &tx.to, *amount);
+            }
+        }
+        Ok(())
+    }
+
+    fn credit(&mut self, token: Hash256, to: &Address, amount: u64) {
+        // Сумма балансов не превышает выпуск, а он проверен на переполнение
+        *self.balances.entry((token, to.clone())).or_insert(0) += amount;
+    }
+}
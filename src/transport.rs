@@ -0,0 +1,254 @@
+use crate::block::Wallet;
+use crate::simulation::SimulatedLink;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use secp256k1::ecdh::SharedSecret;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+// ========== ENCRYPTED TRANSPORT ==============
+// Необязательное шифрование соединений между узлами. Рукопожатие
+// повторяет схему Noise XX на secp256k1: каждая сторона присылает
+// эфемерный ключ, затем — свой статический ключ узла (ключ кошелька
+// `Transport::Encrypted`), уже зашифрованный. Три общих секрета
+// (эфемерный×эфемерный, эфемерный×статический, статический×эфемерный)
+// дают ключи AES-256-GCM для каждого направления, так что пассивный
+// наблюдатель не видит ни сообщений, ни ключей узлов, а подменить
+// кадр, не зная ключа, нельзя.
+//
+//   инициатор → e_i                       (33 байта)
+//   ответчик  → e_r, AEAD(k1, s_r)        (33 + 49 байт)
+//   инициатор → AEAD(k2, s_i)             (49 байт)
+//
+// После рукопожатия кадры прежние (длина + тело), но тело — шифротекст
+// тела открытого кадра из `protocol::encode_message` (версия,
+// контрольная сумма и bincode); nonce — счётчик кадров своего
+// направления. Обе стороны должны выбрать один транспорт: открытый пир
+// не поймёт шифрованного и соединение оборвётся на рукопожатии.
+
+/// Метка протокола, подмешиваемая в хеш рукопожатия.
+const PROTOCOL_LABEL: &[u8] = b"mini_blockchain/xx-secp256k1-aesgcm-sha256/1";
+
+const KEY_LEN: usize = 33;
+/// Статический ключ с тегом AES-GCM.
+const SEALED_KEY_LEN: usize = KEY_LEN + 16;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Как узел разговаривает с пирами.
+#[derive(Clone, Default)]
+pub enum Transport {
+    /// Открытый TCP.
+    #[default]
+    Plain,
+    /// Шифрованный канал; кошелёк задаёт статический ключ узла.
+    Encrypted(Arc<Wallet>),
+    /// Сеть в памяти вместо TCP, см. `simulation`.
+    Simulated(SimulatedLink),
+}
+
+impl fmt::Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::Plain => write!(f, "Plain"),
+            // Закрытый ключ в логи не попадает
+            Transport::Encrypted(wallet) => write!(f, "Encrypted({})", wallet.public_key),
+            Transport::Simulated(link) => write!(f, "Simulated({})", link.addr()),
+        }
+    }
+}
+
+impl Transport {
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, Transport::Encrypted(_))
+    }
+}
+
+/// Установленный шифрованный канал: ключ и счётчик каждого направления
+/// и статический ключ пира.
+pub struct SecureChannel {
+    send: Aes256Gcm,
+    recv: Aes256Gcm,
+    send_nonce: u64,
+    recv_nonce: u64,
+    remote_key: PublicKey,
+}
+
+impl fmt::Debug for SecureChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecureChannel")
+            .field("remote_key", &self.remote_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecureChannel {
+    /// Статический ключ узла на другом конце.
+    pub fn remote_key(&self) -> &PublicKey {
+        &self.remote_key
+    }
+
+    pub fn seal(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = counter_nonce(&mut self.send_nonce);
+        self.send
+            .encrypt(&nonce, plaintext)
+            .expect("AES-GCM encryption does not fail for in-memory buffers")
+    }
+
+    /// Расшифровывает очередной кадр; подменённый, переставленный или
+    /// повторённый кадр даёт `InvalidData`.
+    pub fn open(&mut self, ciphertext: &[u8]) -> io::Result<Vec<u8>> {
+        let nonce = counter_nonce(&mut self.recv_nonce);
+        self.recv
+            .decrypt(&nonce, ciphertext)
+            .map_err(|_| invalid_data("frame failed authentication"))
+    }
+}
+
+/// Рукопожатие на стороне, открывшей соединение.
+pub async fn initiate(stream: &mut TcpStream, wallet: &Wallet) -> io::Result<SecureChannel> {
+    let secp = Secp256k1::new();
+    let local = static_key(wallet)?;
+    let (ephemeral, e_i) = secp.generate_keypair(&mut OsRng);
+    stream.write_all(&e_i.serialize()).await?;
+
+    let mut reply = [0u8; KEY_LEN + SEALED_KEY_LEN];
+    stream.read_exact(&mut reply).await?;
+    let e_r = parse_key(&reply[..KEY_LEN])?;
+
+    let mut transcript = Transcript::new(&e_i, &e_r);
+    let ee = SharedSecret::new(&e_r, &ephemeral);
+    let s_r = parse_key(&open_once(
+        &transcript.key(&[&ee], b"k1"),
+        &reply[KEY_LEN..],
+    )?)?;
+    transcript.mix(&s_r);
+
+    let es = SharedSecret::new(&s_r, &ephemeral);
+    let s_i = PublicKey::from_secret_key(&secp, &local);
+    let sealed = seal_once(&transcript.key(&[&ee, &es], b"k2"), &s_i.serialize());
+    stream.write_all(&sealed).await?;
+    transcript.mix(&s_i);
+
+    let se = SharedSecret::new(&e_r, &local);
+    let secrets = [&ee, &es, &se];
+    Ok(SecureChannel {
+        send: transcript.cipher(&secrets, b"initiator"),
+        recv: transcript.cipher(&secrets, b"responder"),
+        send_nonce: 0,
+        recv_nonce: 0,
+        remote_key: s_r,
+    })
+}
+
+/// Рукопожатие на стороне, принявшей соединение.
+pub async fn respond(stream: &mut TcpStream, wallet: &Wallet) -> io::Result<SecureChannel> {
+    let secp = Secp256k1::new();
+    let local = static_key(wallet)?;
+    let mut first = [0u8; KEY_LEN];
+    stream.read_exact(&mut first).await?;
+    let e_i = parse_key(&first)?;
+
+    let (ephemeral, e_r) = secp.generate_keypair(&mut OsRng);
+    let mut transcript = Transcript::new(&e_i, &e_r);
+    let ee = SharedSecret::new(&e_i, &ephemeral);
+    let s_r = PublicKey::from_secret_key(&secp, &local);
+    let mut reply = e_r.serialize().to_vec();
+    reply.extend(seal_once(&transcript.key(&[&ee], b"k1"), &s_r.serialize()));
+    stream.write_all(&reply).await?;
+    transcript.mix(&s_r);
+
+    let mut sealed = [0u8; SEALED_KEY_LEN];
+    stream.read_exact(&mut sealed).await?;
+    let es = SharedSecret::new(&e_i, &local);
+    let s_i = parse_key(&open_once(&transcript.key(&[&ee, &es], b"k2"), &sealed)?)?;
+    transcript.mix(&s_i);
+
+    let se = SharedSecret::new(&s_i, &ephemeral);
+    let secrets = [&ee, &es, &se];
+    Ok(SecureChannel {
+        send: transcript.cipher(&secrets, b"responder"),
+        recv: transcript.cipher(&secrets, b"initiator"),
+        send_nonce: 0,
+        recv_nonce: 0,
+        remote_key: s_i,
+    })
+}
+
+/// Хеш всех открытых ключей рукопожатия: ключи выводятся из него, так
+/// что подмена любого из них ломает канал.
+struct Transcript(Sha256);
+
+impl Transcript {
+    fn new(e_i: &PublicKey, e_r: &PublicKey) -> Self {
+        let mut hash = Sha256::new();
+        hash.update(PROTOCOL_LABEL);
+        hash.update(e_i.serialize());
+        hash.update(e_r.serialize());
+        Transcript(hash)
+    }
+
+    fn mix(&mut self, key: &PublicKey) {
+        self.0.update(key.serialize());
+    }
+
+    /// HMAC-SHA256(секреты, хеш рукопожатия ‖ метка).
+    fn key(&self, secrets: &[&SharedSecret], label: &[u8]) -> [u8; 32] {
+        let ikm: Vec<u8> = secrets
+            .iter()
+            .flat_map(|secret| secret.secret_bytes())
+            .collect();
+        let mut mac =
+            <HmacSha256 as Mac>::new_from_slice(&ikm).expect("HMAC accepts any key length");
+        mac.update(&self.0.clone().finalize());
+        mac.update(label);
+        mac.finalize().into_bytes().into()
+    }
+
+    fn cipher(&self, secrets: &[&SharedSecret], label: &[u8]) -> Aes256Gcm {
+        Aes256Gcm::new(&self.key(secrets, label).into())
+    }
+}
+
+/// Ключи рукопожатия используются по одному разу, поэтому nonce нулевой.
+fn seal_once(key: &[u8; 32], plaintext: &[u8]) -> Vec<u8> {
+    Aes256Gcm::new(&(*key).into())
+        .encrypt(&Nonce::default(), plaintext)
+        .expect("AES-GCM encryption does not fail for in-memory buffers")
+}
+
+fn open_once(key: &[u8; 32], ciphertext: &[u8]) -> io::Result<Vec<u8>> {
+    Aes256Gcm::new(&(*key).into())
+        .decrypt(&Nonce::default(), ciphertext)
+        .map_err(|_| invalid_data("handshake failed authentication"))
+}
+
+/// 96-битный nonce из счётчика кадров; счётчик сдвигается.
+fn counter_nonce(counter: &mut u64) -> Nonce<aes_gcm::aead::consts::U12> {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    *counter += 1;
+    Nonce::from(nonce)
+}
+
+fn static_key(wallet: &Wallet) -> io::Result<SecretKey> {
+    hex::decode(&wallet.private_key)
+        .ok()
+        .and_then(|bytes| SecretKey::from_slice(&bytes).ok())
+        .ok_or_else(|| invalid_data("node key is not a valid secp256k1 key"))
+}
+
+fn parse_key(bytes: &[u8]) -> io::Result<PublicKey> {
+    PublicKey::from_slice(bytes).map_err(|_| invalid_data("peer sent an invalid public key"))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
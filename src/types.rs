@@ -0,0 +1,439 @@
+use ripemd::Ripemd160;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+// ========== PARSE ERROR ==============
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidHex,
+    InvalidLength { expected: usize, actual: usize },
+    Empty,
+    InvalidBase58,
+    BadChecksum,
+    UnknownVersion(u8),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidHex => write!(f, "invalid hex string"),
+            ParseError::InvalidLength { expected, actual } => {
+                write!(f, "expected {} bytes, got {}", expected, actual)
+            }
+            ParseError::Empty => write!(f, "empty value"),
+            ParseError::InvalidBase58 => write!(f, "invalid base58 string"),
+            ParseError::BadChecksum => write!(f, "address checksum mismatch"),
+            ParseError::UnknownVersion(v) => write!(f, "unknown address version {:#04x}", v),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn decode_fixed<const N: usize>(s: &str) -> Result<[u8; N], ParseError> {
+    let bytes = hex::decode(s).map_err(|_| ParseError::InvalidHex)?;
+    let actual = bytes.len();
+    bytes.try_into().map_err(|_| ParseError::InvalidLength {
+        expected: N,
+        actual,
+    })
+}
+
+/// Реализует Serialize/Deserialize через строковое представление
+/// (Display/FromStr), чтобы в JSON типы выглядели как обычные hex-строки.
+macro_rules! serde_via_string {
+    ($ty:ty) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+pub(crate) use serde_via_string;
+
+// ========== HASH256 ==============
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Hash256(pub [u8; 32]);
+
+impl Hash256 {
+    pub const ZERO: Hash256 = Hash256([0u8; 32]);
+
+    pub fn digest(data: &[u8]) -> Hash256 {
+        Hash256(Sha256::digest(data).into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Количество ведущих нулевых hex-символов.
+    pub fn leading_zero_nibbles(&self) -> u32 {
+        let mut count = 0;
+        for byte in self.0 {
+            if byte == 0 {
+                count += 2;
+            } else {
+                if byte < 0x10 {
+                    count += 1;
+                }
+                break;
+            }
+        }
+        count
+    }
+}
+
+impl fmt::Display for Hash256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for Hash256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hash256({})", self)
+    }
+}
+
+impl FromStr for Hash256 {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        decode_fixed(s).map(Hash256)
+    }
+}
+
+serde_via_string!(Hash256);
+
+// ========== TARGET ==============
+/// 256-битная цель PoW (big-endian): хеш блока, как число, не должен
+/// превышать цель. В блоке хранится в компактной форме nBits:
+/// старший байт — длина числа в байтах, младшие три — мантисса.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Target(pub [u8; 32]);
+
+impl Target {
+    /// Самая лёгкая допустимая цель; сложность 1.0 по определению.
+    pub const MAX: Target = Target([
+        0x00, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0,
+    ]);
+
+    /// Разворачивает nBits. `None`, если число не помещается в 256 бит.
+    /// Знаковый бит мантиссы (0x00800000) игнорируется.
+    pub fn from_compact(bits: u32) -> Option<Target> {
+        let size = (bits >> 24) as usize;
+        let mantissa = bits & 0x007f_ffff;
+        let mantissa_bytes = [
+            (mantissa >> 16) as u8,
+            (mantissa >> 8) as u8,
+            mantissa as u8,
+        ];
+
+        let mut bytes = [0u8; 32];
+        for (i, &byte) in mantissa_bytes.iter().enumerate() {
+            // Позиция байта, считая от младшего
+            let Some(position) = size.checked_sub(i + 1) else {
+                continue;
+            };
+            if position >= 32 {
+                if byte != 0 {
+                    return None;
+                }
+                continue;
+            }
+            bytes[31 - position] = byte;
+        }
+        Some(Target(bytes))
+    }
+
+    pub fn to_compact(&self) -> u32 {
+        let Some(first) = self.0.iter().position(|&byte| byte != 0) else {
+            return 0;
+        };
+
+        let mut size = (32 - first) as u32;
+        let byte_at = |i: usize| self.0.get(i).copied().unwrap_or(0) as u32;
+        let mut mantissa = (byte_at(first) << 16) | (byte_at(first + 1) << 8) | byte_at(first + 2);
+        // Старший бит мантиссы занят под знак — сдвигаем на байт
+        if mantissa & 0x0080_0000 != 0 {
+            mantissa >>= 8;
+            size += 1;
+        }
+        (size << 24) | mantissa
+    }
+
+    pub fn is_met_by(&self, hash: &Hash256) -> bool {
+        hash.0 <= self.0
+    }
+
+    /// target * numerator / denominator с насыщением на `Target::MAX`.
+    pub fn scale(&self, numerator: u64, denominator: u64) -> Target {
+        assert!(denominator > 0, "denominator must be positive");
+
+        let mut product = [0u8; 40];
+        let mut carry: u128 = 0;
+        for i in (0..40).rev() {
+            let byte = if i >= 8 { self.0[i - 8] as u128 } else { 0 };
+            let value = byte * numerator as u128 + carry;
+            product[i] = value as u8;
+            carry = value >> 8;
+        }
+
+        let mut quotient = [0u8; 40];
+        let mut remainder: u128 = 0;
+        for i in 0..40 {
+            remainder = (remainder << 8) | product[i] as u128;
+            quotient[i] = (remainder / denominator as u128) as u8;
+            remainder %= denominator as u128;
+        }
+
+        if quotient[..8].iter().any(|&byte| byte != 0) {
+            return Target::MAX;
+        }
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&quotient[8..]);
+        Target(bytes).min(Target::MAX)
+    }
+
+    /// Сложность относительно `Target::MAX` (как в Bitcoin).
+    pub fn difficulty(&self) -> f64 {
+        to_f64(&Target::MAX.0) / to_f64(&self.0).max(1.0)
+    }
+
+    /// Ожидаемое число хешей до попадания в цель, `2^256 / (target + 1)`,
+    /// как chainwork в Bitcoin. Целое, так что суммы по цепи точны и
+    /// одинаковы на всех узлах. Для целей меньше 2^128 насыщается на
+    /// `u128::MAX`.
+    pub fn work(&self) -> u128 {
+        let high = u128::from_be_bytes(self.0[..16].try_into().unwrap());
+        let low = u128::from_be_bytes(self.0[16..].try_into().unwrap());
+        if high == 0 {
+            return u128::MAX;
+        }
+        let (divisor_low, carry) = low.overflowing_add(1);
+        let Some(divisor_high) = high.checked_add(carry as u128) else {
+            // target + 1 = 2^256
+            return 1;
+        };
+
+        // Деление 2^256 столбиком по битам; частное меньше 2^128, так как
+        // делитель не меньше 2^128
+        let (mut rem_high, mut rem_low) = (0u128, 1u128);
+        let mut quotient = 0u128;
+        for _ in 0..256 {
+            let overflow = rem_high >> 127 == 1;
+            rem_high = (rem_high << 1) | (rem_low >> 127);
+            rem_low <<= 1;
+            quotient <<= 1;
+            if overflow || (rem_high, rem_low) >= (divisor_high, divisor_low) {
+                let (next_low, borrow) = rem_low.overflowing_sub(divisor_low);
+                rem_high = rem_high
+                    .wrapping_sub(divisor_high)
+                    .wrapping_sub(borrow as u128);
+                rem_low = next_low;
+                quotient |= 1;
+            }
+        }
+        quotient
+    }
+}
+
+fn to_f64(bytes: &[u8; 32]) -> f64 {
+    bytes
+        .iter()
+        .fold(0.0, |acc, &byte| acc * 256.0 + byte as f64)
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Target({:#010x})", self.to_compact())
+    }
+}
+
+// ========== SIGNATURE ==============
+/// Компактная ECDSA-подпись (r || s).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature(pub [u8; 64]);
+
+impl Signature {
+    pub const EMPTY: Signature = Signature([0u8; 64]);
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::EMPTY
+    }
+}
+
+impl Default for Signature {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Signature({})", self)
+    }
+}
+
+impl FromStr for Signature {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        decode_fixed(s).map(Signature)
+    }
+}
+
+serde_via_string!(Signature);
+
+// ========== ADDRESS ==============
+/// Байт версии, с которого начинается payload адреса.
+pub const ADDRESS_VERSION: u8 = 0x00;
+/// Версия адресов-хешей скрипта: M-из-N (см. `multisig`) и программ
+/// (см. `script`); они начинаются с `3`.
+pub const SCRIPT_ADDRESS_VERSION: u8 = 0x05;
+
+/// Адрес в формате Base58Check: version || RIPEMD-160(SHA-256(pubkey)) || checksum,
+/// где checksum — первые 4 байта двойного SHA-256 от version || hash.
+/// У адресов M-из-N вместо ключа хешируется политика подписи.
+/// Служебные адреса (`COINBASE`, `GENESIS`) хранятся как есть и не проходят `validate`.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address(String);
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(Sha256::digest(payload));
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+impl Address {
+    pub fn from_public_key(public_key: &[u8]) -> Address {
+        Address::encode(ADDRESS_VERSION, public_key)
+    }
+
+    /// Адрес скрипта — политики M-из-N или программы — по его
+    /// канонической записи.
+    pub fn from_script(script: &[u8]) -> Address {
+        Address::encode(SCRIPT_ADDRESS_VERSION, script)
+    }
+
+    fn encode(version: u8, data: &[u8]) -> Address {
+        let hash = Ripemd160::digest(Sha256::digest(data));
+
+        let mut payload = Vec::with_capacity(25);
+        payload.push(version);
+        payload.extend_from_slice(&hash);
+        let check = checksum(&payload);
+        payload.extend_from_slice(&check);
+
+        Address(bs58::encode(payload).into_string())
+    }
+
+    /// Проверяет кодировку, версию и контрольную сумму адреса.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        let bytes = bs58::decode(&self.0)
+            .into_vec()
+            .map_err(|_| ParseError::InvalidBase58)?;
+
+        if bytes.len() != 25 {
+            return Err(ParseError::InvalidLength {
+                expected: 25,
+                actual: bytes.len(),
+            });
+        }
+
+        if bytes[0] != ADDRESS_VERSION && bytes[0] != SCRIPT_ADDRESS_VERSION {
+            return Err(ParseError::UnknownVersion(bytes[0]));
+        }
+
+        let (payload, check) = bytes.split_at(21);
+        if checksum(payload) != check {
+            return Err(ParseError::BadChecksum);
+        }
+
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Адрес-хеш скрипта: тратить с него можно только по скрипту.
+    pub fn is_script(&self) -> bool {
+        self.payload()
+            .is_some_and(|(version, _)| version == SCRIPT_ADDRESS_VERSION)
+    }
+
+    /// RIPEMD-160(SHA-256(pubkey)) обычного адреса — то, с чем сверяет
+    /// ключ скрипт P2PKH.
+    pub fn pubkey_hash(&self) -> Option<[u8; 20]> {
+        match self.payload() {
+            Some((ADDRESS_VERSION, hash)) => Some(hash),
+            _ => None,
+        }
+    }
+
+    fn payload(&self) -> Option<(u8, [u8; 20])> {
+        let bytes = bs58::decode(&self.0).into_vec().ok()?;
+        if bytes.len() != 25 {
+            return None;
+        }
+        Some((bytes[0], bytes[1..21].try_into().ok()?))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Address({})", self.0)
+    }
+}
+
+impl FromStr for Address {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        Ok(Address(s.to_string()))
+    }
+}
+
+impl From<&str> for Address {
+    fn from(s: &str) -> Self {
+        Address(s.to_string())
+    }
+}
+
+serde_via_string!(Address);
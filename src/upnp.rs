@@ -0,0 +1,76 @@
+use crate::error::UpnpError;
+use crate::network::Node;
+use igd_next::{search_gateway, PortMappingProtocol, SearchOptions};
+use std::net::{SocketAddr, UdpSocket};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{info, warn};
+
+// ========== UPNP ==============
+// Узел за домашним роутером (NAT) входящих соединений не получает: пиры
+// знают только его адрес в локальной сети. С `upnp = true` узел при
+// запуске находит роутер по SSDP и просит пробросить на себя TCP-порт
+// `listen_port` — снаружи тот же номер. Внешний адрес роутера вместе с
+// портом становится `external_addr`: узел сообщает его в рукопожатии и
+// в ответах GET_PEERS, и пиры передают дальше уже его.
+//
+// Проброс выдаётся на `UPNP_LEASE` и продлевается вдвое чаще, так что
+// после остановки узла он сам истекает на роутере.
+
+/// На сколько роутер пробрасывает порт.
+pub const UPNP_LEASE: Duration = Duration::from_secs(60 * 60);
+
+/// Как долго ждать ответа роутера на поиск.
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+impl Node {
+    /// Пробрасывает `port` на роутере и запоминает внешний адрес узла.
+    pub fn map_port(&self, port: u16) -> Result<String, UpnpError> {
+        let gateway = search_gateway(SearchOptions {
+            timeout: Some(SEARCH_TIMEOUT),
+            ..SearchOptions::default()
+        })
+        .map_err(|e| UpnpError::NoGateway(e.to_string()))?;
+
+        // Свой адрес в локальной сети — тот, с которого уходят пакеты
+        // к роутеру
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(UpnpError::LocalAddress)?;
+        socket
+            .connect(gateway.addr)
+            .map_err(UpnpError::LocalAddress)?;
+        let local = SocketAddr::new(
+            socket.local_addr().map_err(UpnpError::LocalAddress)?.ip(),
+            port,
+        );
+
+        gateway
+            .add_port(
+                PortMappingProtocol::TCP,
+                port,
+                local,
+                UPNP_LEASE.as_secs() as u32,
+                &format!("blockchain node {}", self.id),
+            )
+            .map_err(|e| UpnpError::Mapping(e.to_string()))?;
+        let external_ip = gateway
+            .get_external_ip()
+            .map_err(|e| UpnpError::ExternalAddress(e.to_string()))?;
+
+        let external = SocketAddr::new(external_ip, port).to_string();
+        *self.external_addr.lock().unwrap() = Some(external.clone());
+        info!(node = %self.id, %local, external = %external, "port mapped");
+        Ok(external)
+    }
+
+    /// Пробрасывает `port` сейчас и продлевает проброс до `shutdown`.
+    pub fn start_port_mapping(&self, port: u16) -> JoinHandle<()> {
+        if let Err(e) = self.map_port(port) {
+            warn!(node = %self.id, error = %e, "UPnP port mapping failed");
+        }
+        self.every("upnp", UPNP_LEASE / 2, move |node| {
+            if let Err(e) = node.map_port(port) {
+                warn!(node = %node.id, error = %e, "UPnP lease renewal failed");
+            }
+        })
+    }
+}
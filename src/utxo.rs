@@ -0,0 +1,475 @@
+use crate::amount::Amount;
+use crate::block::{verify_ecdsa, Wallet};
+use crate::error::UtxoError;
+use crate::script::{self, Script, ScriptContext};
+use crate::types::{Address, Hash256, Signature};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// ========== UTXO ==============
+// Модель «непотраченных выходов» рядом со счётной: вместо балансов
+// счетов транзакция тратит выходы предыдущих транзакций и создаёт новые.
+// Блок несёт UTXO-транзакции отдельно от счётных (`Block::utxo_transactions`),
+// merkle-корень покрывает и те и другие. Цепь ведёт `UtxoSet` вместе с
+// балансами: подключает к нему каждый принятый блок, а при reorg
+// откатывает отброшенные блоки по их `BlockUndo`.
+//
+// Обе модели делят одну награду: UTXO-coinbase может забрать то, что
+// оставила счётная coinbase от `current_reward` и комиссий счётных
+// транзакций, плюс комиссии UTXO-транзакций (см. `validation`).
+
+/// Ссылка на выход: txid и номер выхода в нём.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OutPoint {
+    pub txid: Hash256,
+    pub vout: u32,
+}
+
+impl fmt::Display for OutPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.vout)
+    }
+}
+
+/// Выход, заблокированный на адрес: потратить его может только
+/// владелец соответствующего ключа. Если задан `script`, выход заперт
+/// программой (см. `script`), а адрес служит лишь для поиска и балансов.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TxOutput {
+    pub amount: Amount,
+    pub address: Address,
+    #[serde(default)]
+    pub script: Option<Script>,
+}
+
+/// Вход: тратимый выход и подпись его владельца. Для выхода со скриптом
+/// вместо подписи и ключа проверяется `script_sig`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TxInput {
+    pub outpoint: OutPoint,
+    pub signature: Signature,
+    pub public_key: String,
+    #[serde(default)]
+    pub script_sig: Script,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UtxoTransaction {
+    pub inputs: Vec<TxInput>,
+    pub outputs: Vec<TxOutput>,
+    /// Для coinbase — высота блока, чтобы txid наград не совпадали.
+    pub lock_height: u32,
+}
+
+impl UtxoTransaction {
+    /// Награда майнеру: транзакция без входов.
+    pub fn coinbase(to: Address, amount: Amount, height: u32) -> UtxoTransaction {
+        UtxoTransaction {
+            inputs: Vec::new(),
+            outputs: vec![TxOutput {
+                amount,
+                address: to,
+                script: None,
+            }],
+            lock_height: height,
+        }
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Все тратимые выходы и все новые выходы без подписей. Каждый вход
+    /// подписывает этот payload целиком, так что подменить выходы нельзя.
+    pub fn signing_payload(&self) -> String {
+        let inputs: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|input| format!("{}/{}", input.outpoint, input.public_key))
+            .collect();
+        let outputs: Vec<String> = self
+            .outputs
+            .iter()
+            .map(|output| match &output.script {
+                Some(script) => format!("{}/{}/{}", output.address, output.amount, script),
+                None => format!("{}/{}", output.address, output.amount),
+            })
+            .collect();
+        format!(
+            "{}|{}|{}",
+            inputs.join(","),
+            outputs.join(","),
+            self.lock_height
+        )
+    }
+
+    pub fn id(&self) -> Hash256 {
+        let signatures: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|input| {
+                if input.script_sig.0.is_empty() {
+                    input.signature.to_string()
+                } else {
+                    format!("{}/{}", input.signature, input.script_sig)
+                }
+            })
+            .collect();
+        let input = format!("{}|{}", self.signing_payload(), signatures.join(","));
+        Hash256::digest(input.as_bytes())
+    }
+
+    /// Сумма выходов; `None`, если она не помещается в `Amount`.
+    pub fn output_total(&self) -> Option<Amount> {
+        Amount::checked_sum(self.outputs.iter().map(|output| output.amount))
+    }
+}
+
+// ========== UTXO SET ==============
+/// Выходы, потраченные блоком: чтобы откатить его, их нужно вернуть.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BlockUndo {
+    pub spent: Vec<(OutPoint, TxOutput)>,
+}
+
+/// Множество непотраченных выходов; обновляется блок за блоком.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UtxoSet {
+    utxos: HashMap<OutPoint, TxOutput>,
+    /// Сколько блоков применено — высота следующего блока, в котором
+    /// скрипты выходов проверяют timelock.
+    #[serde(default)]
+    height: u32,
+}
+
+impl UtxoSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, outpoint: &OutPoint) -> Option<&TxOutput> {
+        self.utxos.get(outpoint)
+    }
+
+    pub fn len(&self) -> usize {
+        self.utxos.len()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.utxos.is_empty()
+    }
+
+    /// Сумма всех непотраченных выходов; насыщается на `Amount::MAX`.
+    pub fn total(&self) -> Amount {
+        self.utxos.values().fold(Amount::ZERO, |total, output| {
+            total.saturating_add(output.amount)
+        })
+    }
+
+    pub fn balance(&self, address: &Address) -> Amount {
+        self.utxos
+            .values()
+            .filter(|output| &output.address == address)
+            .fold(Amount::ZERO, |total, output| {
+                total.saturating_add(output.amount)
+            })
+    }
+
+    /// Проверяет обычную (не coinbase) транзакцию: входы существуют и не
+    /// повторяются, подписи принадлежат владельцам (или скрипты выходов
+    /// принимают `script_sig` в блоке на высоте `height`), сумма входов
+    /// покрывает выходы, а обе суммы помещаются в `Amount`. Возвращает
+    /// комиссию — разницу между ними.
+    pub fn validate_transaction(&self, tx: &UtxoTransaction) -> Result<Amount, UtxoError> {
+        self.check_spend(tx, |outpoint| self.utxos.get(outpoint))
+    }
+
+    /// `validate_transaction`, где тратимые выходы ищет `lookup`.
+    fn check_spend<'a>(
+        &self,
+        tx: &UtxoTransaction,
+        lookup: impl Fn(&OutPoint) -> Option<&'a TxOutput>,
+    ) -> Result<Amount, UtxoError> {
+        if tx.is_coinbase() {
+            return Err(UtxoError::UnexpectedCoinbase);
+        }
+        if tx.outputs.is_empty() {
+            return Err(UtxoError::NoOutputs);
+        }
+        if tx.outputs.iter().any(|output| output.amount.is_zero()) {
+            return Err(UtxoError::NonPositiveOutput);
+        }
+
+        let payload = tx.signing_payload();
+        let mut seen = HashSet::new();
+        let mut input_total = Amount::ZERO;
+        for (index, input) in tx.inputs.iter().enumerate() {
+            if !seen.insert(input.outpoint) {
+                return Err(UtxoError::DoubleSpend(input.outpoint));
+            }
+            let spent = lookup(&input.outpoint).ok_or(UtxoError::MissingInput(input.outpoint))?;
+            match &spent.script {
+                Some(lock) => {
+                    let context = ScriptContext {
+                        payload: &payload,
+                        height: self.height,
+                    };
+                    script::verify(&input.script_sig, lock, context)
+                        .map_err(|reason| UtxoError::ScriptFailed { index, reason })?;
+                }
+                None => verify_ecdsa(
+                    &input.public_key,
+                    &spent.address,
+                    &payload,
+                    &input.signature,
+                )
+                .map_err(|reason| UtxoError::InvalidInput { index, reason })?,
+            }
+            input_total = input_total
+                .checked_add(spent.amount)
+                .ok_or(UtxoError::AmountOverflow)?;
+        }
+
+        let output_total = tx.output_total().ok_or(UtxoError::AmountOverflow)?;
+        input_total
+            .checked_sub(output_total)
+            .ok_or(UtxoError::InsufficientInputs {
+                inputs: input_total,
+                outputs: output_total,
+            })
+    }
+
+    /// Проверяет транзакции следующего блока, не меняя множество: первая
+    /// может быть coinbase с высотой блока, остальные проверяются как в
+    /// `validate_transaction` с учётом выходов, созданных и потраченных
+    /// выше в том же блоке. Coinbase не платит больше `subsidy` и комиссий
+    /// остальных транзакций. Возвращает сумму комиссий.
+    pub fn validate_block(
+        &self,
+        transactions: &[UtxoTransaction],
+        subsidy: Amount,
+    ) -> Result<Amount, UtxoError> {
+        let mut view = BlockView {
+            base: self,
+            created: HashMap::new(),
+            spent: HashSet::new(),
+        };
+        let mut fees = Amount::ZERO;
+        for (position, tx) in transactions.iter().enumerate() {
+            if tx.is_coinbase() {
+                if position != 0 {
+                    return Err(UtxoError::UnexpectedCoinbase);
+                }
+                // Иначе coinbase двух блоков могли бы совпасть по txid
+                if tx.lock_height != self.height {
+                    return Err(UtxoError::CoinbaseHeight {
+                        expected: self.height,
+                        actual: tx.lock_height,
+                    });
+                }
+            } else {
+                let fee = self.check_spend(tx, |outpoint| view.get(outpoint))?;
+                fees = fees.checked_add(fee).ok_or(UtxoError::AmountOverflow)?;
+            }
+            view.apply(tx);
+        }
+
+        if let Some(coinbase) = transactions.first().filter(|tx| tx.is_coinbase()) {
+            let allowed = subsidy.checked_add(fees).ok_or(UtxoError::AmountOverflow)?;
+            let actual = coinbase.output_total().ok_or(UtxoError::AmountOverflow)?;
+            if actual > allowed {
+                return Err(UtxoError::ExcessiveCoinbase { allowed, actual });
+            }
+        }
+        Ok(fees)
+    }
+
+    /// Проверяет блок через `validate_block` и подключает его. При ошибке
+    /// множество не меняется.
+    pub fn apply_block(
+        &mut self,
+        transactions: &[UtxoTransaction],
+        subsidy: Amount,
+    ) -> Result<BlockUndo, UtxoError> {
+        self.validate_block(transactions, subsidy)?;
+        Ok(self.connect_block(transactions))
+    }
+
+    /// Подключает уже проверенный блок без проверок. Возвращает данные
+    /// для его отката при реорганизации (см. `undo_block`).
+    pub fn connect_block(&mut self, transactions: &[UtxoTransaction]) -> BlockUndo {
+        let mut undo = BlockUndo::default();
+        for tx in transactions {
+            self.apply_unchecked(tx, &mut undo);
+        }
+        self.height += 1;
+        undo
+    }
+
+    /// Откатывает последний подключённый блок: возвращает потраченные им
+    /// выходы и удаляет созданные. Блоки откатываются в обратном порядке,
+    /// каждый — со своими `undo` из `connect_block`.
+    pub fn undo_block(&mut self, transactions: &[UtxoTransaction], undo: BlockUndo) {
+        // Выход, созданный и потраченный в том же блоке, вернётся и тут же
+        // будет удалён вместе с остальными созданными
+        self.utxos.extend(undo.spent);
+        for tx in transactions {
+            let txid = tx.id();
+            for vout in 0..tx.outputs.len() {
+                self.utxos.remove(&OutPoint {
+                    txid,
+                    vout: vout as u32,
+                });
+            }
+        }
+        self.height = self.height.saturating_sub(1);
+    }
+
+    fn apply_unchecked(&mut self, tx: &UtxoTransaction, undo: &mut BlockUndo) {
+        for input in &tx.inputs {
+            if let Some(output) = self.utxos.remove(&input.outpoint) {
+                undo.spent.push((input.outpoint, output));
+            }
+        }
+        let txid = tx.id();
+        for (vout, output) in tx.outputs.iter().enumerate() {
+            let outpoint = OutPoint {
+                txid,
+                vout: vout as u32,
+            };
+            self.utxos.insert(outpoint, output.clone());
+        }
+    }
+
+    /// Подбирает выходы адреса на сумму не меньше `amount`, начиная с
+    /// крупных. Возвращает набранную сумму и выбранные выходы.
+    pub fn find_spendable_outputs(
+        &self,
+        address: &Address,
+        amount: Amount,
+    ) -> Option<(Amount, Vec<OutPoint>)> {
+        let mut owned: Vec<(&OutPoint, &TxOutput)> = self
+            .utxos
+            .iter()
+            .filter(|(_, output)| &output.address == address)
+            .collect();
+        owned.sort_by_key(|(_, output)| Reverse(output.amount));
+
+        let mut total = Amount::ZERO;
+        let mut selected = Vec::new();
+        for (outpoint, output) in owned {
+            if total >= amount {
+                break;
+            }
+            total = total.saturating_add(output.amount);
+            selected.push(*outpoint);
+        }
+
+        (total >= amount).then_some((total, selected))
+    }
+}
+
+/// Множество глазами транзакции блока: выходы, созданные выше в том же
+/// блоке, уже есть, а потраченные выше — уже нет.
+struct BlockView<'a> {
+    base: &'a UtxoSet,
+    created: HashMap<OutPoint, &'a TxOutput>,
+    spent: HashSet<OutPoint>,
+}
+
+impl<'a> BlockView<'a> {
+    fn get(&self, outpoint: &OutPoint) -> Option<&'a TxOutput> {
+        if self.spent.contains(outpoint) {
+            return None;
+        }
+        self.created
+            .get(outpoint)
+            .copied()
+            .or_else(|| self.base.utxos.get(outpoint))
+    }
+
+    fn apply(&mut self, tx: &'a UtxoTransaction) {
+        self.spent
+            .extend(tx.inputs.iter().map(|input| input.outpoint));
+        let txid = tx.id();
+        for (vout, output) in tx.outputs.iter().enumerate() {
+            let outpoint = OutPoint {
+                txid,
+                vout: vout as u32,
+            };
+            self.created.insert(outpoint, output);
+        }
+    }
+}
+
+impl Wallet {
+    /// Собирает и подписывает UTXO-перевод: тратит выходы кошелька,
+    /// сдачу возвращает себе, разница входов и выходов — комиссия.
+    pub fn create_utxo_transaction(
+        &self,
+        utxos: &UtxoSet,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+    ) -> Result<UtxoTransaction, UtxoError> {
+        let own = self.get_address();
+        let required = amount.checked_add(fee).ok_or(UtxoError::AmountOverflow)?;
+        let (total, outpoints) =
+            utxos
+                .find_spendable_outputs(&own, required)
+                .ok_or(UtxoError::InsufficientInputs {
+                    inputs: utxos.balance(&own),
+                    outputs: required,
+                })?;
+
+        let mut outputs = vec![TxOutput {
+            amount,
+            address: to.clone(),
+            script: None,
+        }];
+        let change = total - required;
+        if !change.is_zero() {
+            outputs.push(TxOutput {
+                amount: change,
+                address: own,
+                script: None,
+            });
+        }
+
+        let mut tx = UtxoTransaction {
+            inputs: outpoints
+                .into_iter()
+                .map(|outpoint| TxInput {
+                    outpoint,
+                    signature: Signature::EMPTY,
+                    public_key: self.public_key.clone(),
+                    script_sig: Script::default(),
+                })
+                .collect(),
+            outputs,
+            lock_height: 0,
+        };
+
+        let signature = self.sign_transaction(&tx.signing_payload());
+        for input in &mut tx.inputs {
+            input.signature = signature;
+            // Выход кошелька со скриптом — P2PKH, возможно с timelock
+            if utxos
+                .get(&input.outpoint)
+                .is_some_and(|spent| spent.script.is_some())
+            {
+                input.script_sig = Script::unlock([
+                    signature.0.to_vec(),
+                    hex::decode(&self.public_key).expect("wallet public key is hex"),
+                ]);
+            }
+        }
+        Ok(tx)
+    }
+}
@@ -0,0 +1,360 @@
+use crate::amount::Amount;
+use crate::block::{apply_transfers, Block, Blockchain, MAX_FUTURE_BLOCK_TIME};
+use crate::consensus::Consensus;
+use crate::error::{BlockchainError, ConsensusError, TxValidationError, ValidationError};
+use crate::genesis::ChainMode;
+use crate::tokens::TokenLedger;
+use crate::types::Address;
+use crate::utxo::UtxoSet;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+// ========== VALIDATION ==============
+// Полная проверка блока. По одному родителю можно проверить только
+// связность и сам блок (`check_successor`); медиана времени, ожидаемая
+// после пересчёта сложность, доказательство консенсуса, nonce и балансы
+// отправителей зависят от цепи под блоком. Всё это собрано в
+// `ValidationContext`: предки, ожидаемая цель, часы, сеть, правило
+// консенсуса и состояние счетов и выходов UTXO на вершине (`StateView`). В сети
+// regtest время блоков не проверяется, см. `ChainMode`.
+//
+// Проверки идут от дешёвых к дорогим; первая неудача возвращается как
+// `ValidationError`.
+
+/// Состояние счетов на вершине, поверх которой проверяется блок.
+pub trait StateView {
+    fn balance(&self, address: &Address) -> Amount;
+
+    fn nonce_used(&self, address: &Address, nonce: u64) -> bool;
+
+    /// Токены и их балансы на вершине.
+    fn tokens(&self) -> TokenLedger;
+
+    /// Непотраченные выходы на вершине. Цепь, которая ведёт их сама,
+    /// отдаёт ссылку, а не копию.
+    fn utxos(&self) -> Cow<'_, UtxoSet>;
+}
+
+impl StateView for Blockchain {
+    fn balance(&self, address: &Address) -> Amount {
+        self.get_balance(address)
+    }
+
+    fn nonce_used(&self, address: &Address, nonce: u64) -> bool {
+        Blockchain::nonce_used(self, address, nonce)
+    }
+
+    fn tokens(&self) -> TokenLedger {
+        self.current_tokens()
+    }
+
+    fn utxos(&self) -> Cow<'_, UtxoSet> {
+        Blockchain::utxos(self)
+    }
+}
+
+/// Состояние, собранное проигрыванием блоков, — для цепи, которая ещё
+/// не принята (например, цепи пира при reorg), и для части цепи,
+/// срезанной при обрезке (см. `pruning`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayedState {
+    balances: HashMap<Address, Amount>,
+    nonces: HashMap<Address, BTreeSet<u64>>,
+    tokens: TokenLedger,
+    #[serde(default)]
+    utxos: UtxoSet,
+}
+
+impl ReplayedState {
+    /// Состояние после проверенных блоков `blocks`.
+    pub fn replay(blocks: &[Block]) -> Self {
+        let mut state = ReplayedState::default();
+        for block in blocks {
+            state.apply_block(block);
+        }
+        state
+    }
+
+    /// Проводит проверенный блок: переводы, nonce, операции с токенами
+    /// и UTXO-транзакции.
+    pub fn apply_block(&mut self, block: &Block) {
+        apply_transfers(&mut self.balances, block);
+        for tx in &block.transactions {
+            if !tx.is_coinbase() {
+                self.nonces
+                    .entry(tx.from.clone())
+                    .or_default()
+                    .insert(tx.nonce);
+            }
+            let _ = self.tokens.apply(tx);
+        }
+        self.utxos.connect_block(&block.utxo_transactions);
+    }
+
+    pub fn balances(&self) -> &HashMap<Address, Amount> {
+        &self.balances
+    }
+
+    /// Наибольший использованный nonce адреса.
+    pub fn last_nonce(&self, address: &Address) -> Option<u64> {
+        self.nonces.get(address)?.last().copied()
+    }
+}
+
+impl StateView for ReplayedState {
+    fn balance(&self, address: &Address) -> Amount {
+        self.balances.get(address).copied().unwrap_or_default()
+    }
+
+    fn nonce_used(&self, address: &Address, nonce: u64) -> bool {
+        self.nonces
+            .get(address)
+            .is_some_and(|nonces| nonces.contains(&nonce))
+    }
+
+    fn tokens(&self) -> TokenLedger {
+        self.tokens.clone()
+    }
+
+    fn utxos(&self) -> Cow<'_, UtxoSet> {
+        Cow::Borrowed(&self.utxos)
+    }
+}
+
+/// Всё, что нужно знать о цепи, чтобы проверить блок поверх неё.
+pub struct ValidationContext<'a> {
+    /// Блоки от genesis до родителя проверяемого блока.
+    pub ancestors: &'a [Block],
+    /// Цель (nBits), с которой должен быть добыт блок.
+    pub expected_bits: u32,
+    /// Часы узла для проверки времени блока.
+    pub now: u64,
+    pub chain_id: &'a str,
+    /// В regtest время блока не проверяется.
+    pub mode: ChainMode,
+    /// Наибольшая награда за блок сверх комиссий его транзакций; её
+    /// делят счётная и UTXO-coinbase.
+    pub subsidy: Amount,
+    pub consensus: &'a dyn Consensus,
+    pub state: &'a dyn StateView,
+}
+
+/// Проверяет `block` как следующий блок цепи, описанной `ctx`.
+pub fn validate_block(block: &Block, ctx: &ValidationContext) -> Result<(), ValidationError> {
+    let prev = ctx.ancestors.last().ok_or(ValidationError::MissingParent)?;
+    check_successor(block, prev)?;
+    if ctx.mode != ChainMode::Regtest {
+        check_timestamp(block, ctx.ancestors, ctx.now)?;
+    }
+    ctx.consensus
+        .validate_block(block, ctx.ancestors)
+        .map_err(|e| match e {
+            ConsensusError::InsufficientWork => ValidationError::BadPoW,
+            e => ValidationError::Consensus(e),
+        })?;
+
+    // В regtest цель задаёт узел (`Blockchain::set_target`), а не расписание
+    if ctx.mode != ChainMode::Regtest && block.header.bits != ctx.expected_bits {
+        return Err(ValidationError::UnexpectedDifficulty {
+            expected: ctx.expected_bits,
+            actual: block.header.bits,
+        });
+    }
+
+    if let Some(tx) = block
+        .transactions
+        .iter()
+        .find(|tx| tx.chain_id != ctx.chain_id)
+    {
+        return Err(ValidationError::WrongChain {
+            txid: tx.id(),
+            expected: ctx.chain_id.to_string(),
+            actual: tx.chain_id.clone(),
+        });
+    }
+
+    let unclaimed = check_coinbase(block, ctx.subsidy)?;
+    check_state(block, ctx.state)?;
+    ctx.state
+        .utxos()
+        .validate_block(&block.utxo_transactions, unclaimed)
+        .map_err(ValidationError::Utxo)?;
+    Ok(())
+}
+
+/// Всё, что видно по одному родителю `prev`: связность, лимиты,
+/// транзакции, merkle-корень, хеш заголовка и подпись производителя,
+/// если она есть. Доказательство права на блок (работа или подпись)
+/// проверяет `Consensus::validate_block`.
+pub fn check_successor(block: &Block, prev: &Block) -> Result<(), ValidationError> {
+    let expected = prev.header.index + 1;
+    if block.header.index != expected {
+        return Err(ValidationError::BadIndex {
+            expected,
+            actual: block.header.index,
+        });
+    }
+    if !block.is_within_limits() {
+        return Err(ValidationError::OverLimits);
+    }
+    if block.header.prev_hash != prev.hash {
+        return Err(ValidationError::BadPrevHash);
+    }
+    if block.header.hash_algorithm != prev.header.hash_algorithm {
+        return Err(ValidationError::HashAlgorithmMismatch);
+    }
+
+    for (i, tx) in block.transactions.iter().enumerate() {
+        // Coinbase допускается только первой транзакцией блока
+        if tx.is_coinbase() {
+            if i != 0 {
+                return Err(ValidationError::MisplacedCoinbase);
+            }
+            continue;
+        }
+        tx.validate().map_err(|reason| match reason {
+            TxValidationError::MissingSignature
+            | TxValidationError::InvalidSignature
+            | TxValidationError::PublicKeyMismatch
+            | TxValidationError::NotEnoughSignatures { .. } => {
+                ValidationError::InvalidSignature { txid: tx.id() }
+            }
+            reason => ValidationError::InvalidTransaction {
+                txid: tx.id(),
+                reason,
+            },
+        })?;
+        if !tx.is_final(block.header.index as u64, block.header.timestamp) {
+            return Err(ValidationError::NonFinalTransaction { txid: tx.id() });
+        }
+    }
+
+    if block.header.merkle_root != block.merkle_root() {
+        return Err(ValidationError::BadMerkleRoot);
+    }
+    if block.hash != block.header.compute_hash() {
+        return Err(ValidationError::BadHash);
+    }
+
+    // Подпись необязательна, но если она есть, то должна быть верной
+    if !block.producer_key.is_empty() {
+        block
+            .verify_producer()
+            .map_err(ValidationError::BadProducerSignature)?;
+    }
+    Ok(())
+}
+
+/// Время блока больше медианы последних блоков `ancestors` и опережает
+/// `now` не больше чем на `MAX_FUTURE_BLOCK_TIME`.
+pub fn check_timestamp(
+    block: &Block,
+    ancestors: &[Block],
+    now: u64,
+) -> Result<(), ValidationError> {
+    let timestamp = block.header.timestamp;
+    let median = Block::median_time_past(ancestors);
+    if timestamp <= median {
+        return Err(ValidationError::TimestampTooOld { timestamp, median });
+    }
+    let max = now.saturating_add(MAX_FUTURE_BLOCK_TIME);
+    if timestamp > max {
+        return Err(ValidationError::FutureTimestamp { timestamp, max });
+    }
+    Ok(())
+}
+
+/// Coinbase блока не платит больше `subsidy` и комиссий остальных
+/// транзакций: остальные переводы монет не создают (см. `supply`), так
+/// что сумма зачислений блока не больше суммы списаний и награды.
+/// Суммы, не помещающиеся в `Amount`, отвергают блок. Возвращает
+/// незабранный остаток — его может забрать UTXO-coinbase.
+fn check_coinbase(block: &Block, subsidy: Amount) -> Result<Amount, ValidationError> {
+    let coinbase = block.transactions.first().filter(|tx| tx.is_coinbase());
+    let fees = block.transactions[usize::from(coinbase.is_some())..]
+        .iter()
+        .map(|tx| tx.fee);
+    let allowed = Amount::checked_sum(fees)
+        .and_then(|fees| fees.checked_add(subsidy))
+        .ok_or(ValidationError::AmountOverflow)?;
+    let Some(coinbase) = coinbase else {
+        return Ok(allowed);
+    };
+    allowed
+        .checked_sub(coinbase.amount)
+        .ok_or(ValidationError::ExcessiveCoinbase {
+            allowed,
+            actual: coinbase.amount,
+        })
+}
+
+/// Переводы блока по порядку применимы к `state`: nonce не повторяются,
+/// отправителям хватает средств (с учётом поступлений выше в том же
+/// блоке), операции с токенами выполнимы.
+fn check_state(block: &Block, state: &dyn StateView) -> Result<(), ValidationError> {
+    let mut nonces = HashSet::new();
+    // Баланс отправителей и получателей после уже пройденных переводов
+    let mut balances: HashMap<&Address, Amount> = HashMap::new();
+    let mut tokens = state.tokens();
+
+    for tx in &block.transactions {
+        if !tx.is_coinbase() {
+            if !nonces.insert((&tx.from, tx.nonce)) || state.nonce_used(&tx.from, tx.nonce) {
+                return Err(ValidationError::NonceReused {
+                    address: tx.from.clone(),
+                    nonce: tx.nonce,
+                });
+            }
+
+            let available = balances
+                .entry(&tx.from)
+                .or_insert_with(|| state.balance(&tx.from));
+            let required = tx
+                .amount
+                .checked_add(tx.fee)
+                .ok_or(ValidationError::AmountOverflow)?;
+            *available =
+                available
+                    .checked_sub(required)
+                    .ok_or(ValidationError::InsufficientFunds {
+                        address: tx.from.clone(),
+                        available: *available,
+                        required,
+                    })?;
+        }
+        let received = balances
+            .entry(&tx.to)
+            .or_insert_with(|| state.balance(&tx.to));
+        *received = received
+            .checked_add(tx.amount)
+            .ok_or(ValidationError::AmountOverflow)?;
+
+        tokens.apply(tx).map_err(ValidationError::Token)?;
+    }
+    Ok(())
+}
+
+/// Ошибка `Blockchain::add_block` для блока `index`, отвергнутого по
+/// причине `reason`. Причины, у которых есть своя ошибка цепи, сохраняют
+/// её, остальные становятся `InvalidBlock`.
+pub(crate) fn rejection(index: u32, reason: ValidationError) -> BlockchainError {
+    match reason {
+        ValidationError::TimestampTooOld { timestamp, .. }
+        | ValidationError::FutureTimestamp { timestamp, .. } => {
+            BlockchainError::InvalidTimestamp { index, timestamp }
+        }
+        ValidationError::Consensus(e) => BlockchainError::Consensus(e),
+        ValidationError::UnexpectedDifficulty { expected, actual } => {
+            BlockchainError::UnexpectedDifficulty { expected, actual }
+        }
+        ValidationError::WrongChain {
+            expected, actual, ..
+        } => BlockchainError::WrongChain { expected, actual },
+        ValidationError::NonceReused { address, nonce } => {
+            BlockchainError::NonceReused { address, nonce }
+        }
+        ValidationError::Token(e) => BlockchainError::Token(e),
+        reason => BlockchainError::InvalidBlock { index, reason },
+    }
+}
@@ -0,0 +1,201 @@
+use crate::amount::Amount;
+use crate::block::{Block, Blockchain, Transaction, Wallet};
+use crate::clock::{system_clock, Clock};
+use crate::error::WalletError;
+use crate::types::Address;
+use secp256k1::PublicKey;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+// ========== WATCH-ONLY WALLET ==============
+// Кошелёк без закрытого ключа: знает только адрес (и, если известен,
+// публичный ключ). Баланс и история берутся из индексов цепи, а
+// переводы собираются неподписанными — подписывает их `Wallet` на
+// другой машине, см. `Wallet::sign_unsigned`.
+
+#[derive(Clone)]
+pub struct WatchWallet {
+    address: Address,
+    public_key: Option<String>,
+    clock: Arc<dyn Clock>,
+}
+
+/// Направление транзакции относительно наблюдаемого адреса.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+    /// Перевод самому себе: теряется только комиссия.
+    ToSelf,
+}
+
+/// Подтверждённая транзакция адреса с высотой её блока; транзакции
+/// обрезанных блоков читаются из хранилища и потому принадлежат записи.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchedTransaction<'a> {
+    pub tx: Cow<'a, Transaction>,
+    pub height: u32,
+    pub direction: Direction,
+}
+
+impl WatchedTransaction<'_> {
+    /// На сколько транзакция изменила баланс адреса, в единицах
+    /// `Amount` со знаком: списание отрицательно.
+    pub fn net_amount(&self) -> i128 {
+        let (amount, fee) = (self.tx.amount.units() as i128, self.tx.fee.units() as i128);
+        match self.direction {
+            Direction::Incoming => amount,
+            Direction::Outgoing => -(amount + fee),
+            Direction::ToSelf => -fee,
+        }
+    }
+}
+
+impl WatchWallet {
+    /// Наблюдение за адресом публичного ключа `public_key` (hex, как в
+    /// `Wallet::public_key`); собранные переводы уже несут этот ключ.
+    pub fn from_public_key(public_key: &str) -> Result<WatchWallet, WalletError> {
+        let bytes = hex::decode(public_key).map_err(|_| WalletError::InvalidPublicKey)?;
+        PublicKey::from_slice(&bytes).map_err(|_| WalletError::InvalidPublicKey)?;
+        Ok(WatchWallet {
+            address: Address::from_public_key(&bytes),
+            public_key: Some(public_key.to_string()),
+            clock: system_clock(),
+        })
+    }
+
+    /// Наблюдение за одним адресом; публичный ключ в переводы добавит
+    /// подписывающий кошелёк.
+    pub fn from_address(address: Address) -> Result<WatchWallet, WalletError> {
+        address.validate().map_err(WalletError::InvalidAddress)?;
+        Ok(WatchWallet {
+            address,
+            public_key: None,
+            clock: system_clock(),
+        })
+    }
+
+    /// Помечать переводы временем часов `clock`, а не системных.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> WatchWallet {
+        self.clock = clock;
+        self
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn public_key(&self) -> Option<&str> {
+        self.public_key.as_deref()
+    }
+
+    /// Подтверждённый баланс.
+    pub fn balance(&self, chain: &Blockchain) -> Amount {
+        chain.get_balance(&self.address)
+    }
+
+    /// Баланс за вычетом переводов, ждущих в mempool.
+    pub fn available_balance(&self, chain: &Blockchain) -> Amount {
+        self.balance(chain)
+            .saturating_sub(chain.mempool.pending_spend(&self.address))
+    }
+
+    /// Подтверждённая история адреса в порядке цепи.
+    pub fn history<'a>(&self, chain: &'a Blockchain) -> Vec<WatchedTransaction<'a>> {
+        chain
+            .get_transactions_for_address(&self.address)
+            .into_iter()
+            .map(|(tx, height)| self.watched(tx, height))
+            .collect()
+    }
+
+    /// Транзакции адреса в блоке `block`, например только что добытом.
+    pub fn block_transactions<'a>(&self, block: &'a Block) -> Vec<WatchedTransaction<'a>> {
+        block
+            .transactions
+            .iter()
+            .filter(|tx| tx.from == self.address || tx.to == self.address)
+            .map(|tx| self.watched(Cow::Borrowed(tx), block.header.index))
+            .collect()
+    }
+
+    fn watched<'a>(&self, tx: Cow<'a, Transaction>, height: u32) -> WatchedTransaction<'a> {
+        let direction = match (tx.from == self.address, tx.to == self.address) {
+            (true, true) => Direction::ToSelf,
+            (true, false) => Direction::Outgoing,
+            _ => Direction::Incoming,
+        };
+        WatchedTransaction {
+            tx,
+            height,
+            direction,
+        }
+    }
+
+    pub fn incoming<'a>(&self, chain: &'a Blockchain) -> Vec<WatchedTransaction<'a>> {
+        self.history_in(chain, Direction::Incoming)
+    }
+
+    pub fn outgoing<'a>(&self, chain: &'a Blockchain) -> Vec<WatchedTransaction<'a>> {
+        self.history_in(chain, Direction::Outgoing)
+    }
+
+    fn history_in<'a>(
+        &self,
+        chain: &'a Blockchain,
+        direction: Direction,
+    ) -> Vec<WatchedTransaction<'a>> {
+        self.history(chain)
+            .into_iter()
+            .filter(|entry| entry.direction == direction)
+            .collect()
+    }
+
+    /// Неподписанный перевод для сети `chain_id`; `nonce` — очередной
+    /// номер адреса, см. `Blockchain::next_nonce`.
+    pub fn unsigned_transaction(
+        &self,
+        chain_id: &str,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        Transaction {
+            chain_id: chain_id.to_string(),
+            timestamp: self.clock.now(),
+            ..Transaction::new(
+                self.address.clone(),
+                to.clone(),
+                amount,
+                fee,
+                nonce,
+                self.public_key.clone().unwrap_or_default(),
+            )
+        }
+    }
+
+    /// Неподписанный перевод по состоянию `chain`: её сеть и следующий
+    /// свободный nonce адреса.
+    pub fn transaction_for(
+        &self,
+        chain: &Blockchain,
+        to: &Address,
+        amount: Amount,
+        fee: Amount,
+    ) -> Transaction {
+        let nonce = chain.next_nonce(&self.address);
+        self.unsigned_transaction(chain.chain_id(), to, amount, fee, nonce)
+    }
+}
+
+impl Wallet {
+    /// Наблюдающая копия кошелька — без закрытого ключа.
+    pub fn watch_only(&self) -> WatchWallet {
+        WatchWallet {
+            address: self.get_address(),
+            public_key: Some(self.public_key.clone()),
+            clock: Arc::clone(&self.clock),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::network::{runtime, Node};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use std::io;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tracing::{debug, info, info_span, warn, Instrument};
+
+// ========== WEBSOCKET EVENTS ==============
+// Поток событий узла для дашбордов и браузерных кошельков: вместо
+// опроса RPC клиент подключается к ws://127.0.0.1:{port}/ и получает
+// каждое событие (см. `events`) текстовым кадром с JSON:
+//
+//   {"type":"BLOCK_ADDED","index":5,"hash":"…","timestamp":…,"transactions":2}
+//   {"type":"TRANSACTION_ACCEPTED","txid":"…","from":"…","to":"…","amount":1.5,"fee":0.1}
+//   {"type":"CHAIN_REORGED","fork_height":3,"disconnected":["…"],"connected":2}
+//   {"type":"PEER_CONNECTED","addr":"127.0.0.1:3001","node_id":"bob"}
+//   {"type":"PEER_DISCONNECTED","addr":"127.0.0.1:3001"}
+//
+// Писать клиенту ничего не нужно. Если он не успевает читать, пропущенные
+// события заменяет кадр {"type":"LAGGED","missed":N}.
+
+/// Сколько ждать HTTP-рукопожатия WebSocket.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Запускает WebSocket-сервер на 127.0.0.1:`port` и рассылает события
+/// подключённым клиентам до `Node::shutdown`.
+pub fn start_ws_server(node: &Node, port: u16) -> io::Result<()> {
+    runtime().block_on(serve(node, port))
+}
+
+async fn serve(node: &Node, port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    info!(node = %node.id, port, "WebSocket listening");
+    let _worker = node.worker();
+
+    loop {
+        let accepted = tokio::select! {
+            _ = node.shutdown.cancelled() => break,
+            accepted = listener.accept() => accepted,
+        };
+        match accepted {
+            Ok((stream, client)) => {
+                let span = info_span!("ws", node = %node.id, %client);
+                tokio::spawn(stream_events(stream, node.clone_node()).instrument(span));
+            }
+            Err(e) => warn!(error = %e, "WebSocket accept failed"),
+        }
+    }
+    info!(node = %node.id, port, "WebSocket stopped");
+    Ok(())
+}
+
+/// Пересылает события одному клиенту, пока тот не отключится.
+async fn stream_events(stream: TcpStream, node: Node) {
+    let _worker = node.worker();
+    // Подписка до рукопожатия, чтобы не потерять события, пришедшие за это время
+    let mut events = node.subscribe();
+    let mut ws = match timeout(HANDSHAKE_TIMEOUT, tokio_tungstenite::accept_async(stream)).await {
+        Ok(Ok(ws)) => ws,
+        Ok(Err(e)) => {
+            debug!(error = %e, "WebSocket handshake failed");
+            return;
+        }
+        Err(_) => {
+            debug!("WebSocket handshake timed out");
+            return;
+        }
+    };
+    debug!("subscriber connected");
+
+    loop {
+        tokio::select! {
+            _ = node.shutdown.cancelled() => {
+                let _ = ws.close(None).await;
+                return;
+            }
+            event = events.recv() => {
+                let text = match event {
+                    Ok(event) => serde_json::to_string(&event).expect("events serialize to JSON"),
+                    Err(RecvError::Lagged(missed)) => {
+                        json!({ "type": "LAGGED", "missed": missed }).to_string()
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                if ws.send(WsMessage::Text(text)).await.is_err() {
+                    debug!("subscriber gone");
+                    return;
+                }
+            }
+            incoming = ws.next() => match incoming {
+                // На ping tungstenite отвечает сам, прочие кадры не нужны
+                Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => {
+                    debug!("subscriber disconnected");
+                    return;
+                }
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
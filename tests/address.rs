@@ -0,0 +1,40 @@
+use blockchain_p2p::types::ParseError;
+use blockchain_p2p::{Address, Wallet};
+
+#[test]
+fn wallet_address_is_valid_base58check() {
+    let wallet = Wallet::new();
+    let address = wallet.get_address();
+
+    assert!(address.as_str().starts_with('1'));
+    assert_eq!(address.validate(), Ok(()));
+}
+
+#[test]
+fn typo_in_address_is_caught() {
+    let address = Wallet::new().get_address().to_string();
+    let last = address.chars().last().unwrap();
+    let replacement = if last == '2' { '3' } else { '2' };
+    let typo: Address = format!("{}{}", &address[..address.len() - 1], replacement)
+        .parse()
+        .unwrap();
+
+    assert_eq!(typo.validate(), Err(ParseError::BadChecksum));
+}
+
+#[test]
+fn non_base58_address_is_rejected() {
+    let address = Address::from("0OIl-not-base58");
+    assert_eq!(address.validate(), Err(ParseError::InvalidBase58));
+}
+
+#[test]
+fn wallet_imports_secret_key_hex() {
+    let wallet = Wallet::new();
+    let imported = Wallet::from_secret_key_hex(&wallet.private_key).unwrap();
+    assert_eq!(imported.public_key, wallet.public_key);
+    assert_eq!(imported.get_address(), wallet.get_address());
+
+    assert!(Wallet::from_secret_key_hex("zz").is_err());
+    assert!(Wallet::from_secret_key_hex(&"00".repeat(32)).is_err());
+}
@@ -0,0 +1,128 @@
+use blockchain_p2p::amount::COIN;
+use blockchain_p2p::{Amount, AmountError};
+
+#[test]
+fn amounts_parse_and_display_as_decimal_coins() {
+    let cases = [
+        ("50", 50 * COIN),
+        ("12.5", 1_250_000_000),
+        ("0.001", 100_000),
+        (".5", 50_000_000),
+        ("0.00000001", 1),
+    ];
+    for (text, units) in cases {
+        let amount: Amount = text.parse().unwrap();
+        assert_eq!(amount, Amount::from_units(units), "{}", text);
+    }
+
+    assert_eq!(Amount::from_coins(50).to_string(), "50");
+    assert_eq!(Amount::from_units(1_250_000_000).to_string(), "12.5");
+    assert_eq!(Amount::from_units(100_000).to_string(), "0.001");
+    assert_eq!(Amount::ZERO.to_string(), "0");
+    // Запись та же, что у f64, так что payload и txid старых транзакций не меняются
+    assert_eq!(
+        Amount::from_f64(0.1).unwrap().to_string(),
+        0.1f64.to_string()
+    );
+    assert_eq!(
+        Amount::from_f64(50.4).unwrap().to_string(),
+        50.4f64.to_string()
+    );
+}
+
+#[test]
+fn malformed_amounts_are_rejected() {
+    for text in ["", ".", "-1", "1e3", "1.2.3", " 1", "abc"] {
+        assert_eq!(
+            text.parse::<Amount>(),
+            Err(AmountError::Invalid(text.to_string())),
+            "{:?}",
+            text
+        );
+    }
+    assert_eq!(
+        "0.000000001".parse::<Amount>(),
+        Err(AmountError::TooPrecise("0.000000001".to_string()))
+    );
+    assert_eq!(
+        "184467440738".parse::<Amount>(),
+        Err(AmountError::Overflow("184467440738".to_string()))
+    );
+
+    assert_eq!(Amount::from_f64(-1.0), None);
+    assert_eq!(Amount::from_f64(f64::NAN), None);
+    assert_eq!(Amount::from_f64(f64::INFINITY), None);
+}
+
+#[test]
+fn arithmetic_is_exact_and_checked() {
+    // В f64 0.1 + 0.2 != 0.3
+    let sum = "0.1".parse::<Amount>().unwrap() + "0.2".parse().unwrap();
+    assert_eq!(sum, "0.3".parse().unwrap());
+
+    let ten: Amount = [Amount::from_f64(0.1).unwrap(); 100].iter().sum();
+    assert_eq!(ten, Amount::from_coins(10));
+
+    assert_eq!(Amount::MAX.checked_add(Amount::from_units(1)), None);
+    assert_eq!(Amount::ZERO.checked_sub(Amount::from_units(1)), None);
+    assert_eq!(Amount::MAX.checked_mul(2), None);
+    assert_eq!(
+        Amount::checked_sum([Amount::MAX, Amount::from_units(1)]),
+        None
+    );
+    assert_eq!(
+        Amount::ZERO.saturating_sub(Amount::from_coins(1)),
+        Amount::ZERO
+    );
+    assert_eq!(
+        Amount::MAX.saturating_add(Amount::from_coins(1)),
+        Amount::MAX
+    );
+}
+
+#[test]
+#[should_panic(expected = "amount overflow")]
+fn operators_panic_on_overflow() {
+    let _ = Amount::MAX + Amount::from_units(1);
+}
+
+#[test]
+fn amounts_serialize_as_decimal_strings_in_json_and_units_in_bincode() {
+    let amount = Amount::from_units(1_250_000_000);
+    assert_eq!(serde_json::to_string(&amount).unwrap(), "\"12.5\"");
+    assert_eq!(serde_json::from_str::<Amount>("12.5").unwrap(), amount);
+    assert_eq!(serde_json::from_str::<Amount>("\"12.5\"").unwrap(), amount);
+    assert_eq!(
+        serde_json::from_str::<Amount>("50").unwrap(),
+        Amount::from_coins(50)
+    );
+    assert!(serde_json::from_str::<Amount>("-1").is_err());
+
+    let bytes = bincode::serialize(&amount).unwrap();
+    assert_eq!(bytes, 1_250_000_000u64.to_le_bytes());
+    assert_eq!(bincode::deserialize::<Amount>(&bytes).unwrap(), amount);
+
+    #[derive(serde::Deserialize)]
+    struct Config {
+        reward: Amount,
+    }
+    let config: Config = toml::from_str("reward = 12.5").unwrap();
+    assert_eq!(config.reward, amount);
+}
+
+#[test]
+fn json_round_trip_is_exact_beyond_f64_precision() {
+    for amount in [
+        Amount::MAX,
+        Amount::from_units((1 << 53) + 1),
+        Amount::from_units(12_345_678_901_234_567),
+    ] {
+        let json = serde_json::to_string(&amount).unwrap();
+        assert_eq!(json, format!("\"{}\"", amount));
+        assert_eq!(serde_json::from_str::<Amount>(&json).unwrap(), amount);
+    }
+    assert_eq!(
+        serde_json::to_string(&Amount::MAX).unwrap(),
+        "\"184467440737.09551615\""
+    );
+}
@@ -0,0 +1,131 @@
+use blockchain_p2p::backup::DUMP_VERSION;
+use blockchain_p2p::testutil::{coins, ChainBuilder};
+use blockchain_p2p::{BlockArchive, Blockchain};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}_{}.zst", name, std::process::id()))
+}
+
+fn chain_with_transfers() -> Blockchain {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(8.0), coins(0.2))
+        .mine_blocks(3, &bob)
+        .build()
+}
+
+#[test]
+fn archive_round_trip_restores_the_chain() {
+    let path = temp_path("backup_round_trip");
+    let bc = chain_with_transfers();
+    bc.export_archive(&path).unwrap();
+
+    let imported = Blockchain::import_archive(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(imported.chain, bc.chain);
+    assert_eq!(imported.snapshot(), bc.snapshot());
+    assert_eq!(imported.bits, bc.bits);
+}
+
+#[test]
+fn damaged_archive_is_rejected() {
+    let path = temp_path("backup_damaged");
+    let bc = chain_with_transfers();
+    bc.export_archive(&path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+
+    let mut damaged = bytes.clone();
+    let middle = damaged.len() / 2;
+    damaged[middle] ^= 0xff;
+    fs::write(&path, &damaged).unwrap();
+    assert!(Blockchain::import_archive(&path).is_err());
+
+    fs::write(&path, &bytes[..bytes.len() - 8]).unwrap();
+    assert!(Blockchain::import_archive(&path).is_err());
+
+    fs::write(&path, b"MBCX\x01\x00\x00\x00").unwrap();
+    let error = Blockchain::import_archive(&path).err().unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(error.to_string(), "not a chain archive");
+}
+
+#[test]
+fn pruned_chain_is_exported_in_full() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let full = builder.mine_blocks(120, &alice).build();
+    let dir = std::env::temp_dir().join(format!("backup_pruned_{}", std::process::id()));
+    let mut pruned = full.clone();
+    pruned.set_archive(BlockArchive::open(&dir).unwrap());
+    pruned.prune(10).unwrap();
+
+    let path = dir.join("chain.zst");
+    pruned.export_archive(&path).unwrap();
+    let imported = Blockchain::import_archive(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(imported.chain, full.chain);
+}
+
+#[test]
+fn json_dump_round_trips_blocks_state_and_mempool() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let clock = builder.clock().clone();
+    let bc = builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(8.0), coins(0.2))
+        .mine_blocks(2, &bob)
+        .transfer(&bob, &alice, coins(1.0), coins(0.1))
+        .build();
+    assert_eq!(bc.mempool.size(), 1);
+
+    let mut dump = Vec::new();
+    bc.export_json(&mut dump).unwrap();
+    let json: Value = serde_json::from_slice(&dump).unwrap();
+    assert_eq!(json["version"], DUMP_VERSION);
+    assert_eq!(json["blocks"].as_array().unwrap().len(), bc.chain.len());
+    assert_eq!(
+        json["state"]["balances"][alice.get_address().as_str()],
+        bc.get_balance(&alice.get_address()).to_string()
+    );
+
+    // Транзакции mempool проверяются по времени цепи, в которую импортируют
+    let mut target = Blockchain::default();
+    target.set_clock(Arc::new(clock));
+    let imported = target.restore_from_json(dump.as_slice()).unwrap();
+    assert_eq!(imported.chain, bc.chain);
+    assert_eq!(imported.snapshot(), bc.snapshot());
+    assert_eq!(imported.mempool.transactions, bc.mempool.transactions);
+}
+
+#[test]
+fn edited_json_dump_is_rejected() {
+    let bc = chain_with_transfers();
+    let mut dump = Vec::new();
+    bc.export_json(&mut dump).unwrap();
+    let json: Value = serde_json::from_slice(&dump).unwrap();
+    let import = |json: &Value| Blockchain::import_json(json.to_string().as_bytes());
+    assert_eq!(import(&json).unwrap().chain, bc.chain);
+
+    let mut balance = json.clone();
+    let alice = bc.chain[1].transactions[0].to.as_str().to_string();
+    balance["state"]["balances"][alice.as_str()] = Value::from(1_000.0);
+    let error = import(&balance).err().unwrap();
+    assert_eq!(error.to_string(), "dumped state does not match the blocks");
+
+    let mut amount = json.clone();
+    amount["blocks"][2]["transactions"][1]["amount"] = Value::from(9.0);
+    assert!(import(&amount).is_err());
+
+    let mut version = json;
+    version["version"] = Value::from(DUMP_VERSION + 1);
+    assert!(import(&version).is_err());
+}
@@ -0,0 +1,60 @@
+use blockchain_p2p::banlist::{peer_ip, BAN_THRESHOLD};
+use blockchain_p2p::{BanList, BannedPeer, Misbehavior};
+use std::net::{IpAddr, Ipv4Addr};
+
+const MALLORY: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 66));
+
+#[test]
+fn penalties_accumulate_until_ban() {
+    let mut bans = BanList::new(60);
+    let per_message = Misbehavior::MalformedMessage.penalty();
+    let strikes = BAN_THRESHOLD / per_message;
+
+    for _ in 1..strikes {
+        assert!(!bans.penalize(MALLORY, Misbehavior::MalformedMessage, 1_000));
+    }
+    assert_eq!(bans.score(MALLORY), (strikes - 1) * per_message);
+    assert!(!bans.is_banned(MALLORY, 1_000));
+
+    assert!(bans.penalize(MALLORY, Misbehavior::MalformedMessage, 1_000));
+    assert!(bans.is_banned(MALLORY, 1_000));
+    assert_eq!(
+        bans.banned(1_000),
+        vec![BannedPeer {
+            ip: MALLORY,
+            banned_until: 1_060
+        }]
+    );
+}
+
+#[test]
+fn invalid_block_bans_at_once() {
+    let mut bans = BanList::default();
+    assert!(bans.penalize(MALLORY, Misbehavior::InvalidBlock, 0));
+    // Повторные нарушения во время бана его не продлевают
+    assert!(!bans.penalize(MALLORY, Misbehavior::InvalidBlock, 10));
+    assert_eq!(bans.banned(10)[0].banned_until, bans.ban_duration);
+}
+
+#[test]
+fn ban_expires_with_a_clean_score() {
+    let mut bans = BanList::new(60);
+    bans.penalize(MALLORY, Misbehavior::InvalidTransaction, 0);
+    bans.ban(MALLORY, 0);
+    assert_eq!(bans.score(MALLORY), 0);
+
+    assert!(bans.is_banned(MALLORY, 59));
+    assert!(!bans.is_banned(MALLORY, 60));
+    assert!(bans.banned(60).is_empty());
+
+    bans.ban(MALLORY, 100);
+    assert!(bans.unban(MALLORY));
+    assert!(!bans.is_banned(MALLORY, 100));
+}
+
+#[test]
+fn peer_ip_ignores_port() {
+    assert_eq!(peer_ip("10.0.0.66:3000"), Some(MALLORY));
+    assert_eq!(peer_ip("[::1]:3000"), Some("::1".parse().unwrap()));
+    assert_eq!(peer_ip("localhost:3000"), None);
+}
@@ -0,0 +1,155 @@
+use blockchain_p2p::testutil::{coins, test_wallet, ChainBuilder};
+use blockchain_p2p::{
+    Block, BlockStore, Blockchain, BlockchainError, ChainError, Hash256, MemoryStore,
+};
+use std::borrow::Cow;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn accepted_blocks_are_written_to_the_store() {
+    let miner = test_wallet(1);
+    let mut bc = ChainBuilder::new().mine_blocks(2, &miner).build();
+    let store = Arc::new(MemoryStore::new());
+    bc.set_block_store(store.clone()).unwrap();
+    bc.mine_block(&miner.get_address()).unwrap();
+
+    assert_eq!(store.tip().unwrap(), Some((3, bc.latest_block().hash)));
+    assert_eq!(
+        store.get_block_by_height(1).unwrap().as_ref(),
+        Some(&bc.chain[1])
+    );
+    let stored: Vec<_> = store.iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(stored, bc.chain);
+
+    let restored = Blockchain::default().restore_from_store(store).unwrap();
+    assert_eq!(restored.chain, bc.chain);
+    assert_eq!(restored.snapshot(), bc.snapshot());
+}
+
+#[test]
+fn reorg_replaces_blocks_above_the_fork() {
+    let mut ours = ChainBuilder::new().mine_blocks(2, &test_wallet(1)).build();
+    let theirs = ChainBuilder::new().mine_blocks(3, &test_wallet(2)).build();
+    let store = Arc::new(MemoryStore::new());
+    ours.set_block_store(store.clone()).unwrap();
+    let replaced = ours.chain[1].hash;
+
+    ours.replace_chain(theirs.chain.clone()).unwrap();
+    assert_eq!(store.tip().unwrap(), Some((3, theirs.latest_block().hash)));
+    assert_eq!(store.get_block_by_hash(&replaced).unwrap(), None);
+    let stored: Vec<_> = store.iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(stored, theirs.chain);
+}
+
+#[test]
+fn store_refuses_gaps() {
+    let bc = ChainBuilder::new().mine_blocks(2, &test_wallet(1)).build();
+    let store = MemoryStore::new();
+    store.put_block(&bc.chain[0]).unwrap();
+    assert!(store.put_block(&bc.chain[2]).is_err());
+    assert_eq!(store.tip().unwrap(), Some((0, bc.chain[0].hash)));
+}
+
+/// Хранилище, которое по флагу отказывается записывать блоки.
+#[derive(Default)]
+struct FlakyStore {
+    inner: MemoryStore,
+    broken: AtomicBool,
+}
+
+impl BlockStore for FlakyStore {
+    fn put_block(&self, block: &Block) -> io::Result<()> {
+        if self.broken.load(Ordering::Relaxed) {
+            return Err(io::Error::other("disk full"));
+        }
+        self.inner.put_block(block)
+    }
+
+    fn get_block_by_height(&self, height: usize) -> io::Result<Option<Block>> {
+        self.inner.get_block_by_height(height)
+    }
+
+    fn get_block_by_hash(&self, hash: &Hash256) -> io::Result<Option<Block>> {
+        self.inner.get_block_by_hash(hash)
+    }
+
+    fn tip(&self) -> io::Result<Option<(usize, Hash256)>> {
+        self.inner.tip()
+    }
+
+    fn truncate(&self, height: usize) -> io::Result<()> {
+        self.inner.truncate(height)
+    }
+}
+
+#[test]
+fn failed_writes_are_returned_and_leave_the_chain_unchanged() {
+    let miner = test_wallet(1);
+    let mut ours = ChainBuilder::new().mine_blocks(2, &miner).build();
+    let theirs = ChainBuilder::new().mine_blocks(3, &test_wallet(2)).build();
+    let mut ahead = ours.clone();
+    let next = ahead.mine_block(&miner.get_address()).unwrap();
+    let store = Arc::new(FlakyStore::default());
+    ours.set_block_store(store.clone()).unwrap();
+    let before = ours.chain.clone();
+
+    store.broken.store(true, Ordering::Relaxed);
+    assert!(matches!(
+        ours.add_block(next),
+        Err(BlockchainError::Storage(_))
+    ));
+    assert!(matches!(
+        ours.replace_chain(theirs.chain.clone()),
+        Err(ChainError::Storage(_))
+    ));
+    assert_eq!(ours.chain, before);
+
+    // Следующая удачная запись дописывает всё, что хранилище пропустило
+    store.broken.store(false, Ordering::Relaxed);
+    ours.mine_block(&miner.get_address()).unwrap();
+    let stored: Vec<_> = store.iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(stored, ours.chain);
+}
+
+#[test]
+fn cold_blocks_are_served_from_the_store() {
+    let (miner, receiver) = (test_wallet(1), test_wallet(2));
+    let mut ours = ChainBuilder::new()
+        .mine(&miner)
+        .transfer(&miner, &receiver, coins(1.0), coins(0.1))
+        .mine_blocks(4, &miner)
+        .build();
+    let full = ours.chain.clone();
+    let tx = full[2].transactions.last().unwrap().clone();
+    let mut fork = ours.clone();
+
+    ours.set_block_store(Arc::new(MemoryStore::new())).unwrap();
+    ours.set_hot_blocks(2);
+    assert_eq!(ours.pruned_height(), 4);
+    assert!(ours.chain[2].transactions.is_empty());
+    assert_eq!(ours.get_block_by_index(2).as_deref(), Some(&full[2]));
+    assert_eq!(
+        ours.get_block_by_hash(&full[1].hash).as_deref(),
+        Some(&full[1])
+    );
+    let blocks: Vec<Block> = ours.iter().map(Cow::into_owned).collect();
+    assert_eq!(blocks, full);
+    assert_eq!(ours.get_transaction(&tx.id()), Some((Cow::Owned(tx), 2)));
+    assert_eq!(ours.get_balance(&receiver.get_address()), coins(1.0));
+
+    // Ветка, отходящая выше окна, принимается как обычно
+    ours.mine_block(&miner.get_address()).unwrap();
+    let other = test_wallet(3);
+    fork.mine_block(&other.get_address()).unwrap();
+    fork.mine_block(&other.get_address()).unwrap();
+    ours.replace_chain(fork.chain.clone()).unwrap();
+    assert_eq!(ours.latest_block(), fork.latest_block());
+    assert_eq!(ours.pruned_height(), fork.chain.len() - 2);
+    assert_eq!(
+        ours.get_balance(&other.get_address()),
+        fork.get_balance(&other.get_address())
+    );
+    assert_eq!(ours.utxos().total(), fork.utxos().total());
+}
@@ -0,0 +1,50 @@
+use blockchain_p2p::bootstrap::resolve_dns_seed;
+use blockchain_p2p::config::DEFAULT_LISTEN_PORT;
+use blockchain_p2p::simulation::Simulation;
+
+#[test]
+fn dns_seeds_resolve_with_the_default_port() {
+    assert_eq!(
+        resolve_dns_seed("10.0.0.1"),
+        vec![format!("10.0.0.1:{}", DEFAULT_LISTEN_PORT)]
+    );
+    assert_eq!(resolve_dns_seed("10.0.0.1:4000"), vec!["10.0.0.1:4000"]);
+    assert_eq!(
+        resolve_dns_seed("::1"),
+        vec![format!("[::1]:{}", DEFAULT_LISTEN_PORT)]
+    );
+    assert!(resolve_dns_seed("localhost:4000").contains(&"127.0.0.1:4000".to_string()));
+    assert!(resolve_dns_seed("no such host.invalid").is_empty());
+}
+
+#[test]
+fn empty_node_joins_through_bootstrap_addresses() {
+    let sim = Simulation::new(3);
+    sim.connect(1, 2);
+
+    // DNS-сид указывает на узел 1, а тот сообщает об узле 2
+    let peers = sim.node(0).bootstrap(&["10.0.0.2".to_string()], &[]);
+    assert_eq!(peers, 2);
+    assert!(sim
+        .node(0)
+        .connections
+        .lock()
+        .unwrap()
+        .is_known(&sim.addr(2)));
+    sim.shutdown();
+}
+
+#[test]
+fn bootstrap_is_skipped_when_peers_are_known() {
+    let sim = Simulation::new(3);
+    sim.connect(0, 1);
+
+    assert_eq!(sim.node(0).bootstrap(&[], &[sim.addr(2)]), 1);
+    assert_eq!(sim.node(0).peer_addrs(), vec![sim.addr(1)]);
+    assert_eq!(sim.node(0).bootstrap(&[], &[]), 1);
+
+    let lonely = Simulation::new(1);
+    assert_eq!(lonely.node(0).bootstrap(&[], &[]), 0);
+    lonely.shutdown();
+    sim.shutdown();
+}
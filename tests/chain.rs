@@ -0,0 +1,418 @@
+mod common;
+
+use blockchain_p2p::block::{MAX_FUTURE_BLOCK_TIME, MEDIAN_TIME_SPAN};
+use blockchain_p2p::genesis::Allocation;
+use blockchain_p2p::testutil::{coins, ChainBuilder};
+use blockchain_p2p::{
+    Amount, Block, Blockchain, BlockchainError, ChainError, GenesisConfig, Hash256, HashAlgorithm,
+    TxStatus, ValidationError, Wallet,
+};
+use common::{mined_chain, signed_transaction};
+use std::borrow::Cow;
+use std::sync::atomic::AtomicBool;
+
+fn extend(bc: &mut Blockchain, blocks: usize) {
+    let miner = Wallet::new();
+    for i in 0..blocks {
+        // Первый блок лишь начисляет майнеру награду
+        if i > 0 {
+            let receiver = Wallet::new();
+            bc.add_transaction(signed_transaction(
+                &miner,
+                &receiver,
+                coins(1.0),
+                coins(0.0),
+            ))
+            .unwrap();
+        }
+        bc.mine_block(&miner.get_address()).unwrap();
+    }
+}
+
+#[test]
+fn longer_valid_chain_replaces_current() {
+    let mut ours = Blockchain::default();
+    let mut theirs = ours.clone();
+    extend(&mut ours, 1);
+    extend(&mut theirs, 3);
+
+    let reorg = ours.replace_chain(theirs.chain.clone()).unwrap();
+    assert_eq!(reorg.fork_height, 0);
+    assert_eq!(reorg.disconnected.len(), 1);
+    assert_eq!(reorg.connected, 3);
+    assert_eq!(ours.chain, theirs.chain);
+}
+
+#[test]
+fn shorter_chain_is_rejected() {
+    let mut ours = Blockchain::default();
+    let mut theirs = ours.clone();
+    extend(&mut ours, 2);
+    extend(&mut theirs, 1);
+
+    let expected = ChainError::NotHeavier {
+        current: ours.total_work(),
+        candidate: theirs.total_work(),
+    };
+    assert_eq!(ours.replace_chain(theirs.chain), Err(expected));
+}
+
+/// Дописывает к `bc` `blocks` блоков с промежутком `spacing` секунд.
+fn extend_spaced(bc: &mut Blockchain, blocks: usize, spacing: u64) {
+    for _ in 0..blocks {
+        let timestamp = bc.latest_block().header.timestamp + spacing;
+        let block = block_at(bc, timestamp);
+        bc.add_block(block).unwrap();
+    }
+}
+
+#[test]
+fn shorter_chain_with_more_work_wins() {
+    let mut ours = Blockchain::default();
+    ours.adjustment_interval = 2;
+    let mut theirs = ours.clone();
+    // Медленные блоки не дают цели стать легче максимальной, а быстрые
+    // поднимают сложность следующих
+    extend_spaced(&mut ours, 4, 40);
+    extend_spaced(&mut theirs, 3, 1);
+    assert!(theirs.total_work() > ours.total_work());
+
+    let reorg = ours.replace_chain(theirs.chain.clone()).unwrap();
+    assert_eq!(reorg.fork_height, 0);
+    assert_eq!(ours.chain, theirs.chain);
+    assert_eq!(ours.total_work(), theirs.total_work());
+}
+
+#[test]
+fn cumulative_work_follows_the_chain() {
+    let mut bc = Blockchain::default();
+    extend(&mut bc, 3);
+
+    let per_block: Vec<u128> = bc.chain.iter().map(|b| b.header.work()).collect();
+    assert_eq!(bc.work_at(0), per_block[0]);
+    assert_eq!(bc.total_work(), per_block.iter().sum::<u128>());
+
+    // Цепь, загруженная без индексов, считает работу по блокам
+    let loaded: Blockchain = serde_json::from_str(&serde_json::to_string(&bc).unwrap()).unwrap();
+    assert_eq!(loaded.total_work(), bc.total_work());
+}
+
+#[test]
+fn foreign_genesis_is_rejected() {
+    let mut ours = Blockchain::default();
+    let mut theirs = Blockchain::default();
+    theirs.chain[0].header.nonce += 1;
+    extend(&mut theirs, 2);
+
+    assert_eq!(
+        ours.replace_chain(theirs.chain),
+        Err(ChainError::GenesisMismatch)
+    );
+}
+
+#[test]
+fn tampered_chain_is_rejected() {
+    let mut ours = Blockchain::default();
+    let mut theirs = ours.clone();
+    extend(&mut theirs, 3);
+    theirs.chain[2].transactions[1].amount = coins(500.0);
+    let txid = theirs.chain[2].transactions[1].id();
+
+    assert_eq!(
+        ours.replace_chain(theirs.chain),
+        Err(ChainError::InvalidBlock {
+            index: 2,
+            reason: ValidationError::InvalidSignature { txid },
+        })
+    );
+}
+
+#[test]
+fn reorg_returns_abandoned_transactions_to_mempool() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut ours = Blockchain::default();
+    common::fund(&mut ours, &sender);
+    let mut theirs = ours.clone();
+
+    let tx = signed_transaction(&sender, &receiver, coins(5.0), coins(0.1));
+    ours.add_transaction(tx.clone()).unwrap();
+    ours.mine_block(&sender.get_address()).unwrap();
+    assert_eq!(ours.get_balance(&receiver.get_address()), coins(5.0));
+    extend(&mut theirs, 2);
+
+    let reorg = ours.replace_chain(theirs.chain.clone()).unwrap();
+    assert_eq!(reorg.fork_height, 1);
+    assert_eq!(reorg.disconnected.len(), 1);
+    assert_eq!(reorg.connected, 2);
+    assert_eq!(reorg.reinjected, vec![tx.id()]);
+
+    // Перевод снова ждёт подтверждения, баланс откатился вместе с цепью
+    assert!(ours.mempool.contains(&tx.id()));
+    assert!(!ours.contains_transaction(&tx.id()));
+    assert_eq!(ours.get_balance(&receiver.get_address()), coins(0.0));
+}
+
+#[test]
+fn reorg_drops_transactions_confirmed_or_conflicting_in_new_chain() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut ours = Blockchain::default();
+    common::fund(&mut ours, &sender);
+    let mut theirs = ours.clone();
+
+    // Тот же перевод подтверждён в обеих ветках
+    let shared = signed_transaction(&sender, &receiver, coins(1.0), coins(0.0));
+    ours.add_transaction(shared.clone()).unwrap();
+    ours.mine_block(&sender.get_address()).unwrap();
+    theirs.add_transaction(shared.clone()).unwrap();
+    theirs.mine_block(&receiver.get_address()).unwrap();
+
+    // Ожидающий перевод и подтверждённый у пира занимают один nonce
+    let nonce = ours.next_nonce(&sender.get_address());
+    let pending = sender.create_transaction(&receiver.get_address(), coins(2.0), coins(0.0), nonce);
+    let conflicting =
+        sender.create_transaction(&Wallet::new().get_address(), coins(3.0), coins(0.0), nonce);
+    ours.add_transaction(pending).unwrap();
+    theirs.add_transaction(conflicting).unwrap();
+    theirs.mine_block(&receiver.get_address()).unwrap();
+
+    let reorg = ours.replace_chain(theirs.chain.clone()).unwrap();
+    assert_eq!(reorg.disconnected.len(), 1);
+    assert!(reorg.reinjected.is_empty());
+    assert_eq!(ours.mempool.size(), 0);
+    assert_eq!(
+        ours.get_balance(&receiver.get_address()),
+        coins(1.0) + coins(50.0) + coins(50.0)
+    );
+}
+
+#[test]
+fn nonces_follow_the_chain_through_reorg() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut ours = Blockchain::default();
+    common::fund(&mut ours, &sender);
+    let mut theirs = ours.clone();
+
+    let tx = sender.create_transaction(&receiver.get_address(), coins(1.0), coins(0.0), 7);
+    ours.add_transaction(tx).unwrap();
+    ours.mine_block(&sender.get_address()).unwrap();
+    assert!(ours.nonce_used(&sender.get_address(), 7));
+    assert_eq!(ours.next_nonce(&sender.get_address()), 8);
+
+    // Без индексов (после загрузки) ответы те же
+    let loaded: Blockchain = serde_json::from_str(&serde_json::to_string(&ours).unwrap()).unwrap();
+    assert!(loaded.nonce_used(&sender.get_address(), 7));
+    assert_eq!(loaded.next_nonce(&sender.get_address()), 8);
+
+    // Перевод ушёл из цепи обратно в mempool: nonce свободен, но занят ожидающим
+    extend(&mut theirs, 2);
+    ours.replace_chain(theirs.chain).unwrap();
+    assert!(!ours.nonce_used(&sender.get_address(), 7));
+    assert_eq!(ours.next_nonce(&sender.get_address()), 8);
+}
+
+#[test]
+fn cancelled_mining_returns_none() {
+    let bc = Blockchain::default();
+    let tip = &bc.chain[0];
+    let cancel = AtomicBool::new(true);
+
+    // При такой сложности без отмены перебор шёл бы очень долго
+    let block = Block::mine(
+        tip.header.hash_algorithm,
+        1,
+        Vec::new(),
+        tip.hash,
+        64,
+        &cancel,
+    );
+    assert!(block.is_none());
+}
+
+#[test]
+fn blocks_can_be_looked_up_by_index_and_hash() {
+    let bc = mined_chain();
+    let tip = bc.latest_block().clone();
+
+    assert_eq!(
+        bc.get_block_by_index(tip.header.index).as_deref(),
+        Some(&tip)
+    );
+    assert_eq!(bc.get_block_by_hash(&tip.hash).as_deref(), Some(&tip));
+    assert_eq!(bc.get_block_by_index(99), None);
+    assert_eq!(bc.get_block_by_hash(&Hash256::ZERO), None);
+
+    let heights: Vec<u32> = bc.iter().map(|block| block.header.index).collect();
+    let reversed: Vec<u32> = bc.iter_rev().map(|block| block.header.index).collect();
+    assert_eq!(heights, vec![0, 1, 2]);
+    assert_eq!(reversed, vec![2, 1, 0]);
+
+    // Индекс не сериализуется, но поиск после декодирования работает
+    let decoded: Blockchain = serde_json::from_str(&serde_json::to_string(&bc).unwrap()).unwrap();
+    assert_eq!(decoded.get_block_by_hash(&tip.hash).as_deref(), Some(&tip));
+}
+
+#[test]
+fn transaction_status_tracks_confirmations() {
+    let mut bc = Blockchain::default();
+    let sender = Wallet::new();
+    common::fund(&mut bc, &sender);
+    let tx = signed_transaction(&sender, &Wallet::new(), coins(5.0), coins(0.1));
+    assert_eq!(bc.transaction_status(&tx.id()), TxStatus::Unknown);
+
+    bc.add_transaction(tx.clone()).unwrap();
+    assert_eq!(bc.transaction_status(&tx.id()), TxStatus::Pending);
+
+    let block = bc.mine_block(&sender.get_address()).unwrap();
+    bc.mine_block(&sender.get_address()).unwrap();
+    assert_eq!(
+        bc.transaction_status(&tx.id()),
+        TxStatus::Confirmed {
+            block: block.hash,
+            height: 2,
+            confirmations: 2,
+        }
+    );
+}
+
+#[test]
+fn transactions_are_indexed_by_id_and_address() {
+    let mut bc = Blockchain::default();
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    common::fund(&mut bc, &sender);
+    let tx = signed_transaction(&sender, &receiver, coins(5.0), coins(0.1));
+    bc.add_transaction(tx.clone()).unwrap();
+    bc.mine_block(&sender.get_address()).unwrap();
+
+    assert_eq!(bc.get_transaction(&tx.id()), Some((Cow::Borrowed(&tx), 2)));
+    assert!(bc.contains_transaction(&tx.id()));
+    assert_eq!(bc.get_transaction(&Hash256::ZERO), None);
+
+    // Отправитель: награда за блок 1, перевод и награда за блок 2
+    let history = bc.get_transactions_for_address(&sender.get_address());
+    let heights: Vec<u32> = history.iter().map(|(_, height)| *height).collect();
+    assert_eq!(heights, vec![1, 2, 2]);
+    assert_eq!(
+        bc.get_transactions_for_address(&receiver.get_address()),
+        vec![(Cow::Borrowed(&tx), 2)]
+    );
+}
+
+/// Блок из одной coinbase поверх вершины `bc` с заданным временем.
+fn block_at(bc: &Blockchain, timestamp: u64) -> Block {
+    let tip = bc.latest_block();
+    let coinbase = blockchain_p2p::Transaction::coinbase(
+        Wallet::new().get_address(),
+        bc.mining_reward,
+        bc.chain.len() as u32,
+    );
+    Block::mine_at(
+        HashAlgorithm::default(),
+        tip.header.index + 1,
+        timestamp,
+        vec![coinbase],
+        tip.hash,
+        bc.bits,
+        &AtomicBool::new(false),
+    )
+    .unwrap()
+}
+
+#[test]
+fn block_time_must_exceed_median_time_past() {
+    let mut bc = Blockchain::default();
+    let start = bc.chain[0].header.timestamp;
+    for i in 1..=MEDIAN_TIME_SPAN as u64 {
+        bc.add_block(block_at(&bc, start + i * 100)).unwrap();
+    }
+
+    // Медиана последних 11 блоков — время шестого из них
+    let median = Block::median_time_past(&bc.chain);
+    assert_eq!(median, start + 600);
+
+    let stale = block_at(&bc, median);
+    assert_eq!(
+        bc.add_block(stale),
+        Err(BlockchainError::InvalidTimestamp {
+            index: 12,
+            timestamp: median,
+        })
+    );
+    // Раньше вершины, но позже медианы — допустимо
+    bc.add_block(block_at(&bc, median + 1)).unwrap();
+    assert!(bc.is_chain_valid());
+}
+
+#[test]
+fn block_from_the_far_future_is_rejected() {
+    let mut bc = Blockchain::default();
+    let now = Block::next_timestamp(&bc.chain);
+
+    let future = block_at(&bc, now + MAX_FUTURE_BLOCK_TIME + 60);
+    assert!(!future.is_valid(&bc.chain));
+    assert!(matches!(
+        bc.add_block(future),
+        Err(BlockchainError::InvalidTimestamp { index: 1, .. })
+    ));
+
+    let soon = block_at(&bc, now + 60);
+    assert!(soon.is_valid(&bc.chain));
+    bc.add_block(soon).unwrap();
+}
+
+#[test]
+fn blocks_mined_within_one_second_stay_valid() {
+    let mut bc = Blockchain::default();
+    let miner = Wallet::new().get_address();
+    for _ in 0..2 * MEDIAN_TIME_SPAN {
+        bc.mine_block(&miner).unwrap();
+    }
+    assert!(bc.is_chain_valid());
+    assert!(bc
+        .chain
+        .windows(2)
+        .all(|pair| pair[1].header.timestamp >= pair[0].header.timestamp));
+}
+
+#[test]
+fn reward_halves_on_schedule_and_supply_counts_issuance() {
+    let genesis = GenesisConfig {
+        block_reward: coins(40.0),
+        halving_interval: 3,
+        allocations: vec![Allocation {
+            address: Wallet::new().get_address(),
+            amount: coins(100.0),
+        }],
+        ..GenesisConfig::default()
+    };
+    let builder = ChainBuilder::with_genesis(&genesis);
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let bc = builder
+        .mine_blocks(4, &alice)
+        .transfer(&alice, &bob, coins(5.0), coins(0.5))
+        .mine_blocks(3, &alice)
+        .build();
+
+    let rewards: Vec<Amount> = (1..=7).map(|height| bc.current_reward(height)).collect();
+    let expected = [40.0, 40.0, 20.0, 20.0, 20.0, 10.0, 10.0].map(coins);
+    assert_eq!(rewards, expected);
+    // Майнер получил награды и комиссию
+    assert_eq!(
+        bc.get_balance(&alice.get_address()),
+        coins(160.0) - coins(5.0)
+    );
+    // Комиссия перешла к майнеру и выпуск не изменила
+    assert_eq!(bc.total_supply(), coins(100.0) + coins(160.0));
+    assert!(bc.is_chain_valid());
+
+    assert_eq!(bc.current_reward(3 * 64), coins(0.0));
+    let flat = Blockchain::new(&GenesisConfig {
+        halving_interval: 0,
+        ..GenesisConfig::default()
+    });
+    assert_eq!(flat.current_reward(1_000_000), flat.mining_reward);
+}
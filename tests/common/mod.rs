@@ -0,0 +1,86 @@
+#![allow(dead_code)]
+
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{
+    Amount, Block, Blockchain, Consensus, ConsensusError, MiningOptions, MiningStats, Transaction,
+    Wallet,
+};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+static NEXT_NONCE: AtomicU64 = AtomicU64::new(0);
+
+/// Подписанный перевод с уникальным nonce, чтобы одинаковые
+/// переводы в пределах одной секунды не совпадали по txid.
+pub fn signed_transaction(from: &Wallet, to: &Wallet, amount: Amount, fee: Amount) -> Transaction {
+    let nonce = NEXT_NONCE.fetch_add(1, Ordering::Relaxed);
+    from.create_transaction(&to.get_address(), amount, fee, nonce)
+}
+
+/// Добывает блок из одной coinbase, зачисляя награду на `wallet`.
+pub fn fund(bc: &mut Blockchain, wallet: &Wallet) {
+    bc.mine_block(&wallet.get_address()).unwrap();
+}
+
+/// Цепь из genesis, блока с наградой отправителю и блока с его транзакцией.
+pub fn mined_chain() -> Blockchain {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+    bc.add_transaction(signed_transaction(
+        &sender,
+        &receiver,
+        coins(3.0),
+        coins(0.1),
+    ))
+    .unwrap();
+    bc.mine_block(&sender.get_address()).unwrap();
+    bc
+}
+
+/// Консенсус без доказательства работы: блок собирается с nonce 0 и
+/// принимается без проверки цели. Связность, merkle-корень и хеш
+/// заголовка по-прежнему проверяет `Block::is_valid_successor`, так что
+/// цепи строятся мгновенно, а все прочие правила остаются в силе.
+pub struct NoWork;
+
+impl Consensus for NoWork {
+    fn name(&self) -> &'static str {
+        "no-work"
+    }
+
+    fn validate_block(&self, _block: &Block, _chain: &[Block]) -> Result<(), ConsensusError> {
+        Ok(())
+    }
+
+    fn produce_block(
+        &self,
+        chain: &[Block],
+        transactions: Vec<Transaction>,
+        bits: u32,
+        _timestamp: u64,
+        _options: &MiningOptions,
+        _cancel: &AtomicBool,
+    ) -> Option<(Block, MiningStats)> {
+        let tip = chain.last()?;
+        let mut block = Block::template(
+            tip.header.hash_algorithm,
+            tip.header.index + 1,
+            tip.header.timestamp + 10,
+            transactions,
+            tip.hash,
+            bits,
+        );
+        block.hash = block.header.compute_hash();
+        Some((block, MiningStats::default()))
+    }
+}
+
+/// Пустая цепь сети по умолчанию, блоки которой добываются без работы.
+pub fn workless_chain() -> Blockchain {
+    let mut bc = Blockchain::default();
+    bc.set_consensus(Arc::new(NoWork));
+    bc
+}
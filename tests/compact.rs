@@ -0,0 +1,88 @@
+use blockchain_p2p::compact::CompactBlock;
+use blockchain_p2p::simulation::Simulation;
+use blockchain_p2p::testutil::{coins, ChainBuilder};
+use blockchain_p2p::{GenesisConfig, Transaction, Wallet};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn compact_block_is_rebuilt_from_the_mempool() {
+    let builder = ChainBuilder::new();
+    let [alice, bob] = [1, 2].map(|seed| builder.wallet(seed));
+    let mut builder = builder.mine_blocks(2, &alice);
+    for _ in 0..20 {
+        builder = builder.transfer(&alice, &bob, coins(1.0), coins(0.1));
+    }
+    let mut bc = builder.build();
+    let pool: Vec<Transaction> = bc.mempool.transactions.clone();
+    let block = bc.mine_block(&alice.get_address()).unwrap();
+    assert_eq!(block.transactions.len(), 21);
+
+    let compact = CompactBlock::from_block(&block);
+    let full_size = serde_json::to_vec(&block).unwrap().len();
+    let compact_size = serde_json::to_vec(&compact).unwrap().len();
+    assert!(
+        compact_size * 4 < full_size,
+        "{compact_size} vs {full_size}"
+    );
+
+    assert_eq!(compact.fill(&pool).into_block(), Some(block.clone()));
+
+    // Две транзакции не дошли: их позиции дозапрашиваются
+    let mut partial = compact.fill(&pool[2..]);
+    assert_eq!(partial.missing(), vec![1, 2]);
+    assert!(partial.clone().into_block().is_none());
+    assert!(!partial.fill_missing(vec![pool[1].clone(), pool[0].clone()]));
+    assert!(partial.fill_missing(vec![pool[0].clone(), pool[1].clone()]));
+    assert_eq!(partial.into_block(), Some(block));
+}
+
+#[test]
+fn peers_reconstruct_blocks_and_fetch_missing_transactions() {
+    let sim = Simulation::with_genesis(2, &GenesisConfig::regtest());
+    sim.connect(0, 1);
+    let sender = Wallet::new();
+    sim.node(0).generate(1, &sender.get_address()).unwrap();
+    assert!(sim.wait_until(TIMEOUT, |sim| sim.converged()));
+
+    // Первая транзакция есть у обоих узлов, вторая — только у майнера
+    let [shared, private] = [0, 1].map(|nonce| {
+        let bc = sim.node(0).blockchain.blocking_read();
+        sender.create_transaction_for_chain(
+            bc.chain_id(),
+            &Wallet::new().get_address(),
+            coins(1.0),
+            coins(0.1),
+            nonce,
+        )
+    });
+    for node in sim.nodes() {
+        node.blockchain
+            .blocking_write()
+            .add_transaction(shared.clone())
+            .unwrap();
+    }
+    sim.node(0)
+        .blockchain
+        .blocking_write()
+        .add_transaction(private.clone())
+        .unwrap();
+
+    sim.node(0).generate(1, &sender.get_address()).unwrap();
+    assert!(sim.wait_until(TIMEOUT, |sim| sim.converged()));
+    let tip = sim
+        .node(1)
+        .blockchain
+        .blocking_read()
+        .latest_block()
+        .clone();
+    assert_eq!(tip.transactions.len(), 3);
+
+    let received = sim.node(1).metrics.messages_received();
+    assert_eq!(received.get("compact_block"), Some(&2));
+    assert_eq!(received.get("new_block"), None);
+    let served = sim.node(0).metrics.messages_received();
+    assert_eq!(served.get("get_block_txn"), Some(&1));
+    sim.shutdown();
+}
@@ -0,0 +1,259 @@
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Config, ConfigError, Node, SelectionStrategy, Wallet};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("{}_{}", name, std::process::id()))
+}
+
+fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    move |key| vars.get(key).cloned()
+}
+
+#[test]
+fn toml_values_are_overridden_by_environment() {
+    let path = temp_path("config_env.toml");
+    fs::write(
+        &path,
+        "node_id = \"alice\"\n\
+         listen_port = 3100\n\
+         seeds = [\"127.0.0.1:3001\"]\n\
+         mempool_size = 10\n",
+    )
+    .unwrap();
+
+    let mut config = Config::load(&path).unwrap();
+    assert_eq!(config.node_id, "alice");
+    assert_eq!(config.listen_port, 3100);
+    assert_eq!(config.rpc_port, Config::default().rpc_port);
+
+    config
+        .apply_overrides(lookup(&[
+            ("BLOCKCHAIN_LISTEN_PORT", "3200"),
+            ("BLOCKCHAIN_LISTEN_ADDRS", "0.0.0.0, ::"),
+            ("BLOCKCHAIN_SEEDS", "127.0.0.1:3002, 127.0.0.1:3003"),
+            ("BLOCKCHAIN_LOG_LEVEL", "debug"),
+            ("BLOCKCHAIN_TX_SELECTION", "sender-fairness"),
+        ]))
+        .unwrap();
+    assert_eq!(config.listen_port, 3200);
+    assert_eq!(
+        config.listen_sockets(),
+        vec![
+            "0.0.0.0:3200".parse::<SocketAddr>().unwrap(),
+            "[::]:3200".parse().unwrap()
+        ]
+    );
+    assert_eq!(config.seeds, vec!["127.0.0.1:3002", "127.0.0.1:3003"]);
+    assert_eq!(config.mempool_size, 10);
+    assert_eq!(config.log_level, "debug");
+    assert_eq!(config.tx_selection, SelectionStrategy::SenderFairness);
+    assert!(config.validate().is_ok());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn invalid_values_are_reported_with_their_key() {
+    let mut config = Config::default();
+    let err = config
+        .apply_overrides(lookup(&[("BLOCKCHAIN_LISTEN_PORT", "port")]))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ConfigError::InvalidValue { ref key, .. } if key == "BLOCKCHAIN_LISTEN_PORT"
+    ));
+
+    config.log_level = "loud".to_string();
+    assert!(matches!(
+        config.validate(),
+        Err(ConfigError::InvalidValue { ref key, .. }) if key == "log_level"
+    ));
+
+    let upnp = Config {
+        upnp: true,
+        ..Config::default()
+    };
+    assert_eq!(upnp.validate().is_ok(), cfg!(feature = "upnp"));
+
+    let path = temp_path("config_unknown.toml");
+    fs::write(&path, "listen_prot = 3000\n").unwrap();
+    assert!(matches!(
+        Config::load(&path),
+        Err(ConfigError::Malformed(_))
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn node_from_config_creates_data_dir_and_reloads_chain() {
+    let data_dir = temp_path("config_data");
+    let _ = fs::remove_dir_all(&data_dir);
+    let config = Config {
+        node_id: "configured".to_string(),
+        data_dir: data_dir.clone(),
+        max_peers: 3,
+        target_block_time: 30,
+        mempool_size: 7,
+        ..Config::default()
+    };
+
+    let node = Node::from_config(&config).unwrap();
+    assert!(data_dir.is_dir());
+    assert_eq!(node.id, "configured");
+    assert_eq!(node.max_peers, 3);
+    {
+        let mut bc = node.blockchain.blocking_write();
+        assert_eq!(bc.target_block_time, 30);
+        assert_eq!(bc.mempool.max_size, 7);
+        bc.mine_block(&Wallet::new().get_address()).unwrap();
+        bc.save_to_disk(config.chain_file()).unwrap();
+    }
+
+    let reloaded = Node::from_config(&config).unwrap();
+    let bc = reloaded.blockchain.blocking_read();
+    assert_eq!(bc.chain.len(), 2);
+    assert_eq!(bc.target_block_time, 30);
+    assert!(bc.is_chain_valid());
+
+    fs::remove_dir_all(&data_dir).unwrap();
+}
+
+#[test]
+fn shutdown_saves_snapshot_used_on_restart() {
+    let data_dir = temp_path("config_snapshot");
+    let _ = fs::remove_dir_all(&data_dir);
+    let config = Config {
+        data_dir: data_dir.clone(),
+        ..Config::default()
+    };
+    let miner = Wallet::new().get_address();
+
+    let node = Node::from_config(&config).unwrap();
+    node.blockchain.blocking_write().mine_block(&miner).unwrap();
+    node.shutdown().unwrap();
+    assert!(config.snapshot_file().is_file());
+
+    let reloaded = Node::from_config(&config).unwrap();
+    let bc = reloaded.blockchain.blocking_read();
+    assert_eq!(bc.chain.len(), 2);
+    assert_eq!(bc.get_balance(&miner), coins(50.0));
+
+    fs::remove_dir_all(&data_dir).unwrap();
+}
+
+#[test]
+fn pending_transactions_survive_restart() {
+    let data_dir = temp_path("config_mempool");
+    let _ = fs::remove_dir_all(&data_dir);
+    let config = Config {
+        data_dir: data_dir.clone(),
+        ..Config::default()
+    };
+    let sender = Wallet::new();
+    let receiver = Wallet::new().get_address();
+
+    let node = Node::from_config(&config).unwrap();
+    let (confirmed, pending) = {
+        let mut bc = node.blockchain.blocking_write();
+        bc.mine_block(&sender.get_address()).unwrap();
+        let confirmed = sender.create_transaction(&receiver, coins(5.0), coins(0.5), 0);
+        let pending = sender.create_transaction(&receiver, coins(3.0), coins(0.1), 1);
+        bc.add_transaction(confirmed.clone()).unwrap();
+        bc.add_transaction(pending.clone()).unwrap();
+        (confirmed, pending)
+    };
+    node.shutdown().unwrap();
+    assert!(config.mempool_file().is_file());
+
+    // Пока узел выключен, первая транзакция подтверждается в блоке
+    {
+        let mut bc = Node::from_config(&config)
+            .unwrap()
+            .blockchain
+            .blocking_read()
+            .clone();
+        bc.mempool.clear();
+        bc.add_transaction(confirmed.clone()).unwrap();
+        bc.mine_block(&sender.get_address()).unwrap();
+        bc.save_to_disk(config.chain_file()).unwrap();
+    }
+
+    let reloaded = Node::from_config(&config).unwrap();
+    let bc = reloaded.blockchain.blocking_read();
+    assert_eq!(bc.chain.len(), 3);
+    assert_eq!(bc.mempool.size(), 1);
+    assert!(bc.mempool.contains(&pending.id()));
+    assert!(bc.contains_transaction(&confirmed.id()));
+
+    fs::remove_dir_all(&data_dir).unwrap();
+}
+
+#[test]
+fn chain_file_from_another_network_is_rejected() {
+    let data_dir = temp_path("config_foreign");
+    let _ = fs::remove_dir_all(&data_dir);
+    let config = Config {
+        data_dir: data_dir.clone(),
+        ..Config::default()
+    };
+    let node = Node::from_config(&config).unwrap();
+    node.blockchain
+        .blocking_read()
+        .save_to_disk(config.chain_file())
+        .unwrap();
+
+    let genesis = data_dir.join("genesis.toml");
+    fs::write(&genesis, "chain_id = \"other\"\n").unwrap();
+    let other = Config {
+        genesis: Some(genesis),
+        ..config
+    };
+    assert!(matches!(
+        Node::from_config(&other),
+        Err(ConfigError::ForeignChain { .. })
+    ));
+
+    fs::remove_dir_all(&data_dir).unwrap();
+}
+
+#[test]
+fn encrypted_transport_keeps_node_key_between_restarts() {
+    let data_dir = temp_path("config_node_key");
+    let _ = fs::remove_dir_all(&data_dir);
+    let mut config = Config {
+        data_dir: data_dir.clone(),
+        ..Config::default()
+    };
+    config
+        .apply_overrides(lookup(&[("BLOCKCHAIN_ENCRYPTED_TRANSPORT", "true")]))
+        .unwrap();
+
+    let first = Node::from_config(&config).unwrap();
+    assert!(first.transport.is_encrypted());
+    let saved = fs::read_to_string(config.node_key_file()).unwrap();
+
+    let second = Node::from_config(&config).unwrap();
+    assert_eq!(
+        format!("{:?}", second.transport),
+        format!("{:?}", first.transport)
+    );
+    assert_eq!(fs::read_to_string(config.node_key_file()).unwrap(), saved);
+    // В отладочном выводе виден только открытый ключ
+    assert!(!format!("{:?}", first.transport).contains(saved.trim()));
+
+    fs::write(config.node_key_file(), "not a key").unwrap();
+    assert!(matches!(
+        Node::from_config(&config),
+        Err(ConfigError::InvalidValue { .. })
+    ));
+    fs::remove_dir_all(&data_dir).unwrap();
+}
@@ -0,0 +1,74 @@
+use blockchain_p2p::connections::ConnectionManager;
+use blockchain_p2p::health::MAX_MISSED_PINGS;
+use blockchain_p2p::simulation::Simulation;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn backoff_doubles_up_to_the_limit() {
+    let mut manager = ConnectionManager::default();
+    manager.max_backoff = Duration::from_secs(60);
+    let backoffs: Vec<u64> = (0..9)
+        .map(|failures| manager.backoff(failures).as_secs())
+        .collect();
+    assert_eq!(backoffs, [0, 1, 2, 4, 8, 16, 32, 60, 60]);
+    assert_eq!(manager.backoff(u32::MAX), Duration::from_secs(60));
+}
+
+#[test]
+fn dropped_peers_are_redialed_after_backoff() {
+    let sim = Simulation::new(3);
+    sim.node(0).connections.lock().unwrap().base_backoff = Duration::from_millis(100);
+    sim.connect(0, 1);
+    sim.connect(0, 2);
+
+    // Пропавший пир выпадает из таблицы после пропущенных PING
+    sim.partition(&[&[0, 1], &[2]]);
+    for _ in 0..MAX_MISSED_PINGS {
+        sim.node(0).ping_peers();
+    }
+    assert_eq!(sim.node(0).peer_addrs(), vec![sim.addr(1)]);
+    assert_eq!(sim.node(0).maintain_connections(), 0);
+    assert_eq!(
+        sim.node(0)
+            .connections
+            .lock()
+            .unwrap()
+            .failures(&sim.addr(2)),
+        1
+    );
+
+    // До конца задержки адрес не набирается даже при восстановленной связи
+    sim.heal();
+    assert_eq!(sim.node(0).maintain_connections(), 0);
+    thread::sleep(Duration::from_millis(150));
+    assert_eq!(sim.node(0).maintain_connections(), 1);
+    assert_eq!(sim.node(0).peer_addrs().len(), 2);
+    assert_eq!(
+        sim.node(0)
+            .connections
+            .lock()
+            .unwrap()
+            .failures(&sim.addr(2)),
+        0
+    );
+    sim.shutdown();
+}
+
+#[test]
+fn faster_peers_are_preferred() {
+    let sim = Simulation::new(3);
+    sim.connect(0, 1);
+    sim.connect(0, 2);
+    sim.set_link_latency(0, 1, Duration::from_millis(50));
+    sim.node(0).ping_peers();
+    // Менеджер запоминает время ответа подключённых пиров
+    sim.node(0).maintain_connections();
+
+    sim.node(0).peers.lock().unwrap().clear();
+    sim.node(0).connections.lock().unwrap().target_outbound = 1;
+    assert_eq!(sim.node(0).maintain_connections(), 1);
+    assert_eq!(sim.node(0).peer_addrs(), vec![sim.addr(2)]);
+    assert_eq!(sim.node(0).maintain_connections(), 0);
+    sim.shutdown();
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use blockchain_p2p::consensus::{expected_validator, select_validator};
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{
+    Blockchain, BlockchainError, Consensus, ConsensusError, GenesisConfig, GenesisError, Hash256,
+    ProofOfStake, ProofOfWork, Signature, Wallet,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// PoS-цепь, где `validator` получил награду за первый блок и застейкал
+/// 20 монет во втором.
+fn staked_chain(validator: &Wallet) -> Blockchain {
+    let mut bc = Blockchain::default();
+    bc.set_consensus(Arc::new(ProofOfStake::with_validator(validator.clone())));
+    bc.mine_block(&validator.get_address()).unwrap();
+    bc.add_transaction(validator.create_stake_transaction(coins(20.0), coins(0.0), 0))
+        .unwrap();
+    bc.mine_block(&validator.get_address()).unwrap();
+    bc
+}
+
+#[test]
+fn proof_of_work_rejects_block_without_work() {
+    let mut bc = common::mined_chain();
+    assert_eq!(bc.consensus().name(), "proof-of-work");
+
+    let mut block = bc.latest_block().clone();
+    block.header.bits = 0x0300_0001;
+    assert_eq!(
+        ProofOfWork.validate_block(&block, &bc.chain[..block.header.index as usize]),
+        Err(ConsensusError::InsufficientWork)
+    );
+
+    bc.chain.pop();
+    assert!(bc.add_block(block).is_err());
+}
+
+#[test]
+fn staked_validator_signs_blocks_instead_of_mining() {
+    let alice = Wallet::new();
+    let bc = staked_chain(&alice);
+
+    assert_eq!(expected_validator(&bc.chain), Some(alice.get_address()));
+    let tip = bc.latest_block();
+    assert_eq!(tip.header.nonce, 0);
+    assert_eq!(tip.producer_key, alice.public_key);
+    assert!(bc.is_chain_valid());
+    assert_eq!(
+        bc.get_balance(&alice.get_address()),
+        coins(100.0) - coins(20.0)
+    );
+}
+
+#[test]
+fn stake_is_signed_for_the_given_chain() {
+    let validator = Wallet::new();
+    let mut bc = Blockchain::new(&GenesisConfig::regtest());
+    bc.set_consensus(Arc::new(ProofOfStake::with_validator(validator.clone())));
+    bc.mine_block(&validator.get_address()).unwrap();
+
+    let stake =
+        validator.create_stake_transaction_for_chain(bc.chain_id(), coins(20.0), coins(0.0), 0);
+    assert_eq!(stake.chain_id, bc.chain_id());
+    bc.add_transaction(stake).unwrap();
+    bc.mine_block(&validator.get_address()).unwrap();
+
+    assert_eq!(expected_validator(&bc.chain), Some(validator.get_address()));
+    assert!(bc.is_chain_valid());
+}
+
+#[test]
+fn only_selected_validator_may_produce() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let bc = staked_chain(&alice);
+
+    let mut as_bob = bc.clone();
+    as_bob.set_consensus(Arc::new(ProofOfStake::with_validator(bob.clone())));
+    assert_eq!(
+        as_bob.mine_block(&bob.get_address()),
+        Err(BlockchainError::Consensus(ConsensusError::NotSelected))
+    );
+
+    // Блок, переподписанный не тем валидатором, цепь Алисы не примет
+    let mut tampered = bc.clone().mine_block(&alice.get_address()).unwrap();
+    tampered.producer_signature = bob.sign_transaction(&tampered.hash.to_string());
+    tampered.producer_key = bob.public_key.clone();
+    assert_eq!(
+        bc.clone().add_block(tampered),
+        Err(BlockchainError::Consensus(
+            ConsensusError::UnexpectedProducer {
+                expected: alice.get_address(),
+                actual: bob.get_address(),
+            }
+        ))
+    );
+}
+
+#[test]
+fn validator_selection_is_weighted_by_stake() {
+    let small = Wallet::new().get_address();
+    let large = Wallet::new().get_address();
+    let stakes = BTreeMap::from([(small.clone(), coins(1.0)), (large.clone(), coins(3.0))]);
+
+    let rounds = 1000;
+    let large_wins = (0..rounds)
+        .map(|i: u32| Hash256::digest(&i.to_le_bytes()))
+        .filter(|seed| select_validator(&stakes, seed) == Some(large.clone()))
+        .count();
+
+    assert!(
+        (650..=850).contains(&large_wins),
+        "large won {}",
+        large_wins
+    );
+    assert_eq!(select_validator(&BTreeMap::new(), &Hash256::ZERO), None);
+}
+
+#[test]
+fn proof_of_work_blocks_may_carry_a_producer_signature() {
+    let producer = Wallet::new();
+    let mut bc = Blockchain::default();
+    bc.sign_blocks_with(producer.clone());
+    let block = bc.mine_block(&producer.get_address()).unwrap();
+
+    assert_eq!(block.producer_key, producer.public_key);
+    assert_eq!(block.verify_producer(), Ok(producer.get_address()));
+    assert!(block.is_valid(&bc.chain[..1]));
+    assert!(bc.is_chain_valid());
+
+    // Чужая подпись делает блок недействительным, отсутствие — нет
+    let mut forged = block.clone();
+    forged.producer_signature = Wallet::new().sign_transaction(&block.hash.to_string());
+    assert!(!forged.is_valid(&bc.chain[..1]));
+    let mut unsigned = block;
+    unsigned.producer_key.clear();
+    unsigned.producer_signature = Signature::EMPTY;
+    assert!(unsigned.is_valid(&bc.chain[..1]));
+}
+
+#[test]
+fn permissioned_network_accepts_only_listed_producers() {
+    let producer = Wallet::new();
+    let outsider = Wallet::new();
+    let genesis = GenesisConfig {
+        chain_id: "permissioned".to_string(),
+        producers: vec![producer.public_key.clone()],
+        ..GenesisConfig::default()
+    };
+
+    let mut bc = Blockchain::new(&genesis);
+    assert_eq!(
+        bc.mine_block(&producer.get_address()),
+        Err(BlockchainError::Consensus(
+            ConsensusError::MissingProducerSignature
+        ))
+    );
+
+    bc.sign_blocks_with(outsider.clone());
+    assert_eq!(
+        bc.mine_block(&outsider.get_address()),
+        Err(BlockchainError::Consensus(
+            ConsensusError::UnauthorizedProducer(outsider.public_key.clone())
+        ))
+    );
+
+    let mut bc = Blockchain::new(&genesis);
+    bc.sign_blocks_with(producer.clone());
+    bc.mine_block(&producer.get_address()).unwrap();
+    assert_eq!(bc.chain.len(), 2);
+    assert!(bc.is_chain_valid());
+
+    let mut follower = Blockchain::new(&genesis);
+    assert!(follower.replace_chain(bc.chain.clone()).is_ok());
+}
+
+#[test]
+fn genesis_rejects_malformed_producer_keys() {
+    let genesis = GenesisConfig {
+        producers: vec!["not-a-key".to_string()],
+        ..GenesisConfig::default()
+    };
+    assert!(matches!(
+        genesis.validate(),
+        Err(GenesisError::InvalidProducer(key)) if key == "not-a-key"
+    ));
+}
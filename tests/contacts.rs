@@ -0,0 +1,79 @@
+use blockchain_p2p::testutil::{coins, test_wallet};
+use blockchain_p2p::{Address, Contacts, ContactsError};
+use std::env;
+use std::fs;
+
+#[test]
+fn contacts_round_trip_through_file() {
+    let path = env::temp_dir().join(format!("contacts_{}.json", std::process::id()));
+    let bob = test_wallet(2).get_address();
+    let carol = test_wallet(3).get_address();
+
+    let mut contacts = Contacts::new();
+    contacts.add("carol", carol.clone()).unwrap();
+    contacts.add("bob", bob.clone()).unwrap();
+    contacts.save(&path).unwrap();
+
+    let loaded = Contacts::load(&path).unwrap();
+    assert_eq!(loaded, contacts);
+    let names: Vec<_> = loaded.iter().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["bob", "carol"]);
+    assert_eq!(loaded.name_of(&carol), Some("carol"));
+
+    // Испорченный вручную адрес не загрузится
+    fs::write(&path, r#"{"bob": "1BoBtypo"}"#).unwrap();
+    assert!(matches!(
+        Contacts::load(&path),
+        Err(ContactsError::InvalidAddress { .. })
+    ));
+    fs::remove_file(&path).unwrap();
+    assert!(Contacts::load_or_default(&path).unwrap().is_empty());
+}
+
+#[test]
+fn invalid_and_duplicate_contacts_are_rejected() {
+    let bob = test_wallet(2).get_address();
+    let mut contacts = Contacts::new();
+    contacts.add("bob", bob.clone()).unwrap();
+
+    assert!(matches!(
+        contacts.add("bob", test_wallet(3).get_address()),
+        Err(ContactsError::Exists(_))
+    ));
+    assert!(matches!(
+        contacts.add(" bob", bob.clone()),
+        Err(ContactsError::InvalidName(_))
+    ));
+    // Имя-адрес сделало бы `--to` неоднозначным
+    assert!(matches!(
+        contacts.add(bob.as_str(), bob.clone()),
+        Err(ContactsError::InvalidName(_))
+    ));
+    assert!(matches!(
+        contacts.add("eve", Address::from("not-an-address")),
+        Err(ContactsError::InvalidAddress { .. })
+    ));
+
+    assert_eq!(contacts.remove("bob"), Some(bob));
+    assert!(contacts.is_empty());
+}
+
+#[test]
+fn wallet_sends_to_contact_by_name() {
+    let alice = test_wallet(1);
+    let bob = test_wallet(2).get_address();
+    let mut contacts = Contacts::new();
+    contacts.add("bob", bob.clone()).unwrap();
+
+    let tx = alice
+        .send_to_contact(&contacts, "bob", coins(5.0), coins(0.1), 0)
+        .unwrap();
+    assert_eq!(tx.to, bob);
+    assert_eq!(tx.amount, coins(5.0));
+    assert!(tx.verify_signature().is_ok());
+
+    assert!(matches!(
+        alice.send_to_contact(&contacts, "mallory", coins(5.0), coins(0.1), 0),
+        Err(ContactsError::Unknown(_))
+    ));
+}
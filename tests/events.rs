@@ -0,0 +1,100 @@
+mod common;
+
+use blockchain_p2p::network::PeerInfo;
+use blockchain_p2p::protocol::PROTOCOL_VERSION;
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{EventReceiver, Misbehavior, Node, NodeEvent, Wallet};
+use common::{fund, signed_transaction};
+use std::net::IpAddr;
+use tokio::sync::broadcast::error::TryRecvError;
+
+fn drain(events: &mut EventReceiver) -> Vec<NodeEvent> {
+    let mut received = Vec::new();
+    loop {
+        match events.try_recv() {
+            Ok(event) => received.push(event),
+            Err(TryRecvError::Empty) => return received,
+            Err(e) => panic!("unexpected {:?}", e),
+        }
+    }
+}
+
+#[test]
+fn mined_blocks_and_transactions_reach_subscribers() {
+    let node = Node::new("Node".to_string());
+    let sender = Wallet::new();
+    fund(&mut node.blockchain.blocking_write(), &sender);
+    let mut events = node.subscribe();
+    let mut late = node.subscribe();
+
+    let tx = signed_transaction(&sender, &Wallet::new(), coins(4.0), coins(0.1));
+    node.blockchain
+        .blocking_write()
+        .add_transaction(tx.clone())
+        .unwrap();
+    node.broadcast_transaction(&tx);
+    let handle = node.start_mining(sender.get_address());
+    let mined = loop {
+        match events.blocking_recv().unwrap() {
+            NodeEvent::BlockAdded {
+                index,
+                transactions,
+                ..
+            } => break (index, transactions),
+            NodeEvent::TransactionAccepted { txid, amount, .. } => {
+                assert_eq!(txid, tx.id());
+                assert_eq!(amount, coins(4.0));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    };
+    node.stop_mining();
+    handle.join().unwrap();
+
+    // Награда майнеру и перевод
+    assert_eq!(mined, (2, 2));
+    // Каждый подписчик получает свою копию
+    assert!(matches!(
+        late.try_recv(),
+        Ok(NodeEvent::TransactionAccepted { .. })
+    ));
+}
+
+#[test]
+fn peer_events_follow_the_peer_table() {
+    let node = Node::new("Node".to_string());
+    let mut events = node.subscribe();
+
+    assert!(node.add_peer(PeerInfo {
+        addr: "127.0.0.1:38199".parse().unwrap(),
+        node_id: "Carol".to_string(),
+        version: PROTOCOL_VERSION,
+        best_height: 1,
+    }));
+    let ip: IpAddr = "127.0.0.1".parse().unwrap();
+    node.misbehaving(ip, Misbehavior::InvalidBlock);
+
+    assert_eq!(
+        drain(&mut events),
+        vec![
+            NodeEvent::PeerConnected {
+                addr: "127.0.0.1:38199".parse().unwrap(),
+                node_id: "Carol".to_string(),
+            },
+            NodeEvent::PeerDisconnected {
+                addr: "127.0.0.1:38199".parse().unwrap(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn events_serialize_with_a_type_tag() {
+    let event = NodeEvent::PeerDisconnected {
+        addr: "127.0.0.1:3001".parse().unwrap(),
+    };
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        serde_json::json!({ "type": "PEER_DISCONNECTED", "addr": "127.0.0.1:3001" })
+    );
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use blockchain_p2p::explorer::{render, start_explorer};
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Node, Wallet};
+use common::{fund, signed_transaction};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn pages_link_blocks_transactions_and_addresses() {
+    let node = Node::new("Explorer".to_string());
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let tx = signed_transaction(&sender, &receiver, coins(5.0), coins(0.1));
+    let block = {
+        let mut bc = node.blockchain.blocking_write();
+        fund(&mut bc, &sender);
+        bc.add_transaction(tx.clone()).unwrap();
+        bc.mine_block(&sender.get_address()).unwrap()
+    };
+
+    let overview = render(&node, "/");
+    assert_eq!(overview.status, 200);
+    assert!(overview.html.contains(&format!("/block/{}", block.hash)));
+
+    let by_index = render(&node, &format!("/block/{}", block.header.index));
+    assert_eq!(by_index, render(&node, &format!("/block/{}", block.hash)));
+    assert!(by_index.html.contains(&format!("/tx/{}", tx.id())));
+
+    let tx_page = render(&node, &format!("/tx/{}", tx.id()));
+    assert!(tx_page
+        .html
+        .contains(&format!("/address/{}", receiver.get_address())));
+
+    let address = render(&node, &format!("/address/{}", receiver.get_address()));
+    assert_eq!(address.status, 200);
+    assert!(address.html.contains("<dd>5</dd>"));
+
+    assert_eq!(render(&node, "/block/99").status, 404);
+    assert_eq!(render(&node, "/tx/xyz").status, 400);
+    assert_eq!(render(&node, "/address/<script>").status, 400);
+}
+
+#[test]
+fn explorer_serves_html_over_http() {
+    let node = Node::new("Explorer".to_string());
+    let server = node.clone_node();
+    thread::spawn(move || start_explorer(&server, 38115));
+    thread::sleep(Duration::from_millis(300));
+
+    let mut stream = TcpStream::connect("127.0.0.1:38115").unwrap();
+    stream
+        .write_all(b"GET /block/0 HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).unwrap();
+
+    assert!(reply.starts_with("HTTP/1.1 200"));
+    assert!(reply.contains("text/html"));
+    assert!(reply.contains("<h1>Block #0</h1>"));
+}
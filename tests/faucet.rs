@@ -0,0 +1,83 @@
+mod common;
+
+use blockchain_p2p::faucet::Faucet;
+use blockchain_p2p::rpc::handle_request;
+use blockchain_p2p::testutil::{coins, ChainBuilder};
+use blockchain_p2p::{BlockchainError, FaucetError, Node, Wallet};
+use common::fund;
+use serde_json::json;
+
+#[test]
+fn drips_are_limited_per_address_and_in_total() {
+    let builder = ChainBuilder::new();
+    let source = builder.wallet(1);
+    let clock = builder.clock().clone();
+    let mut bc = builder.mine_blocks(2, &source).build();
+    let [alice, bob, carol] = [2, 3, 4].map(|seed| ChainBuilder::new().wallet(seed).get_address());
+
+    let mut faucet = Faucet::new(source.clone());
+    faucet.amount = coins(5.0);
+    faucet.cooldown = 100;
+    faucet.max_drips = 2;
+    faucet.window = 50;
+
+    let first = faucet.drip(&mut bc, &alice).unwrap();
+    assert_eq!((first.amount, first.nonce), (coins(5.0), 0));
+    assert_eq!(
+        faucet.drip(&mut bc, &alice).unwrap_err(),
+        FaucetError::TooSoon { retry_after: 100 }
+    );
+    assert_eq!(faucet.drip(&mut bc, &bob).unwrap().nonce, 1);
+    assert_eq!(
+        faucet.drip(&mut bc, &carol).unwrap_err(),
+        FaucetError::LimitReached { retry_after: 50 }
+    );
+    assert_eq!(bc.mempool.transactions.len(), 2);
+
+    clock.advance(50);
+    faucet.drip(&mut bc, &carol).unwrap();
+    assert!(matches!(
+        faucet.drip(&mut bc, &alice),
+        Err(FaucetError::TooSoon { retry_after: 50 })
+    ));
+    clock.advance(50);
+    faucet.drip(&mut bc, &alice).unwrap();
+}
+
+#[test]
+fn empty_faucet_does_not_count_against_the_limits() {
+    let builder = ChainBuilder::new();
+    let mut bc = builder.build();
+    let mut faucet = Faucet::new(ChainBuilder::new().wallet(1));
+    let to = ChainBuilder::new().wallet(2).get_address();
+
+    assert!(matches!(
+        faucet.drip(&mut bc, &to),
+        Err(FaucetError::Rejected(
+            BlockchainError::InsufficientFunds { .. }
+        ))
+    ));
+    bc.mine_block(&faucet.address()).unwrap();
+    faucet.drip(&mut bc, &to).unwrap();
+}
+
+#[test]
+fn faucet_endpoint_sends_coins_and_reports_limits() {
+    let node = Node::new("Faucet".to_string());
+    let body = json!({ "address": Wallet::new().get_address() }).to_string();
+    assert_eq!(handle_request(&node, "POST", "/faucet", &body).status, 404);
+
+    let source = Wallet::new();
+    fund(&mut node.blockchain.blocking_write(), &source);
+    node.enable_faucet(Faucet::new(source));
+
+    let response = handle_request(&node, "POST", "/faucet", &body);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body["amount"], "10");
+    let mempool = handle_request(&node, "GET", "/mempool", "");
+    assert_eq!(mempool.body.as_array().unwrap().len(), 1);
+
+    assert_eq!(handle_request(&node, "POST", "/faucet", &body).status, 429);
+    let bad = json!({ "address": "bad!" }).to_string();
+    assert_eq!(handle_request(&node, "POST", "/faucet", &bad).status, 400);
+}
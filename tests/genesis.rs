@@ -0,0 +1,82 @@
+use blockchain_p2p::genesis::Allocation;
+use blockchain_p2p::testutil::{coins, test_wallet, ChainBuilder};
+use blockchain_p2p::{Blockchain, GenesisConfig, GenesisError, Wallet};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_path(name: &str, extension: &str) -> PathBuf {
+    env::temp_dir().join(format!("{}_{}.{}", name, std::process::id(), extension))
+}
+
+#[test]
+fn independent_chains_share_default_genesis() {
+    let a = Blockchain::default();
+    let b = Blockchain::default();
+
+    assert_eq!(a.chain[0], b.chain[0]);
+    assert_eq!(a.chain_id(), "mini-blockchain");
+}
+
+#[test]
+fn toml_config_sets_chain_id_and_premine() {
+    let path = temp_path("genesis_premine", "toml");
+    let holder = Wallet::new().get_address();
+    fs::write(
+        &path,
+        format!(
+            "chain_id = \"testnet\"\n\
+             timestamp = 1700000000\n\
+             bits = 0x2000ffff\n\n\
+             [[allocations]]\n\
+             address = \"{}\"\n\
+             amount = 1000.0\n",
+            holder
+        ),
+    )
+    .unwrap();
+
+    let config = GenesisConfig::load(&path).unwrap();
+    let bc = Blockchain::new(&config);
+    assert_eq!(bc.chain_id(), "testnet");
+    assert_eq!(bc.chain[0].header.timestamp, 1_700_000_000);
+    assert_eq!(bc.get_balance(&holder), coins(1000.0));
+    assert_eq!(Blockchain::new(&config).chain[0], bc.chain[0]);
+    assert_ne!(bc.chain[0].hash, Blockchain::default().chain[0].hash);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn json_config_with_invalid_bits_is_rejected() {
+    let path = temp_path("genesis_bad_bits", "json");
+    fs::write(&path, r#"{ "chain_id": "broken", "bits": 0 }"#).unwrap();
+
+    assert!(matches!(
+        GenesisConfig::load(&path),
+        Err(GenesisError::InvalidBits(0))
+    ));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn premined_funds_are_spendable_in_the_first_block() {
+    let builder = ChainBuilder::with_genesis(&GenesisConfig {
+        allocations: vec![Allocation {
+            address: test_wallet(1).get_address(),
+            amount: coins(500.0),
+        }],
+        ..GenesisConfig::default()
+    });
+    let faucet = builder.wallet(1);
+    let user = builder.wallet(2);
+    let bc = builder
+        .transfer(&faucet, &user, coins(25.0), coins(0.1))
+        .mine(&faucet)
+        .build();
+
+    assert_eq!(bc.chain.len(), 2);
+    assert_eq!(bc.get_balance(&user.get_address()), coins(25.0));
+    assert!(bc.is_chain_valid());
+}
@@ -0,0 +1,134 @@
+use blockchain_p2p::fixtures::{fixture_wallet, BlockFixture};
+use blockchain_p2p::genesis::DEFAULT_CHAIN_ID;
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Block, HashAlgorithm, LockTime, TokenOp};
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// Хеши и сериализация известных блоков сверяются с tests/golden/*.json.
+// Расхождение значит, что изменился `compute_hash` или формат блока и
+// узлы новой версии не примут старую цепь. Если это сделано намеренно,
+// файлы переписываются: `UPDATE_GOLDEN=1 cargo test --test golden`.
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.json", name))
+}
+
+fn assert_golden(name: &str, block: &Block) {
+    assert!(
+        block.has_valid_hash(),
+        "{}: hash does not match header",
+        name
+    );
+    let actual = json!({
+        "hash": block.hash,
+        "txids": block.transactions.iter().map(|tx| tx.id()).collect::<Vec<_>>(),
+        "bincode": hex::encode(bincode::serialize(block).unwrap()),
+        "block": block,
+    });
+
+    let path = golden_path(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+        return;
+    }
+    let expected: Value = serde_json::from_str(
+        &fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{}: {} (run with UPDATE_GOLDEN=1)", path.display(), e)),
+    )
+    .unwrap();
+    assert_eq!(
+        actual, expected,
+        "{}: block encoding changed, this breaks consensus",
+        name
+    );
+
+    // Сохранённые байты и JSON читаются обратно в тот же блок
+    let bytes = hex::decode(expected["bincode"].as_str().unwrap()).unwrap();
+    assert_eq!(&bincode::deserialize::<Block>(&bytes).unwrap(), block);
+    assert_eq!(
+        &serde_json::from_value::<Block>(expected["block"].clone()).unwrap(),
+        block
+    );
+}
+
+#[test]
+fn genesis_block_is_unchanged() {
+    assert_golden("genesis", &Block::genesis());
+}
+
+#[test]
+fn empty_block_hashes_are_unchanged_for_every_algorithm() {
+    for algorithm in HashAlgorithm::ALL {
+        let block = BlockFixture::new(1).algorithm(algorithm).nonce(7).build();
+        let name = format!("empty_{:?}", algorithm).to_lowercase();
+        assert_golden(&name, &block);
+    }
+}
+
+#[test]
+fn transfer_block_is_unchanged() {
+    let miner = fixture_wallet(1);
+    let alice = fixture_wallet(2);
+    let bob = fixture_wallet(3);
+    let genesis = Block::genesis();
+    let block = BlockFixture::after(&genesis)
+        .coinbase(&miner.get_address(), coins(50.1))
+        .transaction(alice.create_transaction(&bob.get_address(), coins(5.0), coins(0.1), 0))
+        .nonce(42)
+        .build();
+
+    assert_eq!(block.header.prev_hash, genesis.hash);
+    assert_golden("transfer_block", &block);
+    // Фикстура воспроизводится байт в байт
+    let again = BlockFixture::after(&genesis)
+        .coinbase(&miner.get_address(), coins(50.1))
+        .transaction(alice.create_transaction(&bob.get_address(), coins(5.0), coins(0.1), 0))
+        .nonce(42)
+        .build();
+    assert_eq!(again, block);
+}
+
+#[test]
+fn block_with_tokens_data_and_lock_time_is_unchanged() {
+    let miner = fixture_wallet(1);
+    let alice = fixture_wallet(2);
+    let bob = fixture_wallet(3);
+    let create = alice.create_token_transaction(
+        &alice.get_address(),
+        TokenOp::CreateToken {
+            symbol: "GLD".to_string(),
+            supply: 1_000,
+        },
+        coins(0.1),
+        0,
+    );
+    let locked = alice.create_locked_transaction(
+        &bob.get_address(),
+        coins(1.5),
+        coins(0.1),
+        1,
+        LockTime::Height(5),
+    );
+    let data = alice.create_transaction_with_data(
+        DEFAULT_CHAIN_ID,
+        &bob.get_address(),
+        coins(0.5),
+        coins(0.2),
+        2,
+        b"invoice 42".to_vec(),
+    );
+    let block = BlockFixture::new(3)
+        .coinbase(&miner.get_address(), coins(50.4))
+        .transaction(create)
+        .transaction(locked)
+        .transaction(data)
+        .nonce(1_000)
+        .build();
+    assert_golden("feature_block", &block);
+}
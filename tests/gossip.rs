@@ -0,0 +1,97 @@
+mod common;
+
+use blockchain_p2p::gossip::SeenCache;
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Hash256, Node, Wallet};
+use common::{fund, signed_transaction};
+use std::thread;
+use std::time::Duration;
+
+fn spawn_server(node: &Node, port: u16) {
+    let server = node.clone_node();
+    thread::spawn(move || server.start_server(port));
+    thread::sleep(Duration::from_millis(300));
+}
+
+#[test]
+fn seen_cache_evicts_least_recently_used() {
+    let mut cache = SeenCache::new(2);
+    let [a, b, c] = [1u8, 2, 3].map(|i| Hash256::digest(&[i]));
+
+    assert!(cache.insert(a));
+    assert!(cache.insert(b));
+    assert!(!cache.insert(a));
+
+    // `b` давно не встречался и вытесняется первым
+    assert!(cache.insert(c));
+    assert_eq!(cache.len(), 2);
+    assert!(cache.contains(&a));
+    assert!(!cache.contains(&b));
+}
+
+#[test]
+fn transaction_reaches_peers_of_peers() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+
+    // Цепочка Alice — Bob — Carol: Alice и Carol друг о друге не знают
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+    let carol = Node::new("Carol".to_string());
+    fund(&mut alice.blockchain.blocking_write(), &sender);
+    for node in [&bob, &carol] {
+        *node.blockchain.blocking_write() = alice.blockchain.blocking_read().clone();
+    }
+    spawn_server(&alice, 38112);
+    spawn_server(&bob, 38113);
+    spawn_server(&carol, 38114);
+    assert!(alice.connect_to_peer("127.0.0.1:38113"));
+    assert!(carol.connect_to_peer("127.0.0.1:38113"));
+
+    let tx = signed_transaction(&sender, &receiver, coins(2.0), coins(0.1));
+    alice
+        .blockchain
+        .blocking_write()
+        .add_transaction(tx.clone())
+        .unwrap();
+    alice.broadcast_transaction(&tx);
+    thread::sleep(Duration::from_millis(500));
+
+    for node in [&alice, &bob, &carol] {
+        let bc = node.blockchain.blocking_read();
+        assert_eq!(bc.mempool.size(), 1, "mempool of {}", node.id);
+        assert!(node.seen_txs.lock().unwrap().contains(&tx.id()));
+    }
+}
+
+#[test]
+fn replacement_transaction_is_gossiped() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+    fund(&mut alice.blockchain.blocking_write(), &sender);
+    *bob.blockchain.blocking_write() = alice.blockchain.blocking_read().clone();
+    spawn_server(&alice, 38135);
+    spawn_server(&bob, 38136);
+    assert!(alice.connect_to_peer("127.0.0.1:38136"));
+
+    let stuck = sender.create_transaction(&receiver.get_address(), coins(2.0), coins(0.1), 0);
+    let bumped = sender.create_transaction(&receiver.get_address(), coins(2.0), coins(0.5), 0);
+    for tx in [&stuck, &bumped] {
+        alice
+            .blockchain
+            .blocking_write()
+            .add_transaction(tx.clone())
+            .unwrap();
+        alice.broadcast_transaction(tx);
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    for node in [&alice, &bob] {
+        let bc = node.blockchain.blocking_read();
+        assert_eq!(bc.mempool.size(), 1, "mempool of {}", node.id);
+        assert!(bc.mempool.contains(&bumped.id()));
+    }
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use blockchain_p2p::grpc::proto::node_client::NodeClient;
+use blockchain_p2p::grpc::proto::{self, get_block_request};
+use blockchain_p2p::grpc::start_grpc_server;
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Amount, Hash256, LockTime, Node, ProtoError, TokenOp, Transaction, Wallet};
+use common::{fund, signed_transaction};
+use std::thread;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::time::timeout;
+use tonic::Code;
+
+fn block_request(block: get_block_request::Block) -> proto::GetBlockRequest {
+    proto::GetBlockRequest { block: Some(block) }
+}
+
+#[test]
+fn balance_blocks_and_transactions_are_served() {
+    let node = Node::new("Grpc".to_string());
+    let sender = Wallet::new();
+    fund(&mut node.blockchain.blocking_write(), &sender);
+    let server = node.clone_node();
+    thread::spawn(move || start_grpc_server(&server, 38138));
+    thread::sleep(Duration::from_millis(300));
+
+    let runtime = Runtime::new().unwrap();
+    runtime.block_on(async {
+        let mut client = NodeClient::connect("http://127.0.0.1:38138").await.unwrap();
+
+        let balance = client
+            .get_balance(proto::GetBalanceRequest {
+                address: sender.get_address().to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(balance.balance, Amount::from_coins(50).units());
+        assert_eq!(balance.next_nonce, 0);
+        let bad = proto::GetBalanceRequest {
+            address: "bad!".to_string(),
+        };
+        assert_eq!(
+            client.get_balance(bad).await.unwrap_err().code(),
+            Code::InvalidArgument
+        );
+
+        let block = node.blockchain.read().await.chain[1].clone();
+        let by_height = client
+            .get_block(block_request(get_block_request::Block::Height(1)))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(by_height, proto::Block::from(&block));
+        let by_hash = client
+            .get_block(block_request(get_block_request::Block::Hash(
+                block.hash.as_bytes().to_vec(),
+            )))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(by_hash, by_height);
+        let missing = client
+            .get_block(block_request(get_block_request::Block::Height(9)))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+
+        let tx = signed_transaction(&sender, &Wallet::new(), coins(3.0), coins(0.1));
+        let reply = client
+            .submit_transaction(proto::Transaction::from(&tx))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(reply.txid, tx.id().as_bytes().to_vec());
+        assert_eq!(node.blockchain.read().await.mempool.size(), 1);
+        let duplicate = client
+            .submit_transaction(proto::Transaction::from(&tx))
+            .await
+            .unwrap_err();
+        assert_eq!(duplicate.code(), Code::FailedPrecondition);
+    });
+    node.shutdown().unwrap();
+}
+
+#[test]
+fn subscriber_receives_new_blocks() {
+    let node = Node::new("Grpc".to_string());
+    let server = node.clone_node();
+    thread::spawn(move || start_grpc_server(&server, 38139));
+    thread::sleep(Duration::from_millis(300));
+
+    let runtime = Runtime::new().unwrap();
+    let mut blocks = runtime.block_on(async {
+        let mut client = NodeClient::connect("http://127.0.0.1:38139").await.unwrap();
+        client
+            .subscribe_blocks(proto::SubscribeBlocksRequest {})
+            .await
+            .unwrap()
+            .into_inner()
+    });
+
+    let mined = node.generate(2, &Wallet::new().get_address()).unwrap();
+    for block in &mined {
+        let received = runtime
+            .block_on(async { timeout(Duration::from_secs(5), blocks.message()).await })
+            .expect("block expected")
+            .unwrap()
+            .unwrap();
+        assert_eq!(received, proto::Block::from(block));
+    }
+    node.shutdown().unwrap();
+}
+
+#[test]
+fn transactions_survive_the_proto_round_trip() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let mut tx = alice.create_token_transaction(
+        &bob.get_address(),
+        TokenOp::Mint {
+            token: Hash256::digest(b"token"),
+            amount: 5,
+        },
+        coins(0.1),
+        1,
+    );
+    tx.lock_time = Some(LockTime::Height(10));
+    tx.data = b"invoice 42".to_vec();
+
+    for tx in [tx, signed_transaction(&alice, &bob, coins(2.5), coins(0.1))] {
+        let message = proto::Transaction::from(&tx);
+        assert_eq!(Transaction::try_from(message).unwrap(), tx);
+    }
+
+    let mut truncated =
+        proto::Transaction::from(&signed_transaction(&alice, &bob, coins(1.0), coins(0.1)));
+    truncated.signature.truncate(10);
+    assert_eq!(
+        Transaction::try_from(truncated).unwrap_err(),
+        ProtoError::WrongLength {
+            field: "signature",
+            expected: 64,
+            actual: 10,
+        }
+    );
+}
@@ -0,0 +1,59 @@
+use blockchain_p2p::{Block, Blockchain, GenesisConfig, HashAlgorithm, Wallet};
+use sha2::{Digest, Sha256};
+
+fn chain_with(algorithm: HashAlgorithm) -> Blockchain {
+    let config = GenesisConfig {
+        hash_algorithm: algorithm,
+        ..GenesisConfig::default()
+    };
+    let mut bc = Blockchain::new(&config);
+    bc.mine_block(&Wallet::new().get_address()).unwrap();
+    bc
+}
+
+#[test]
+fn algorithms_produce_distinct_digests() {
+    let data = b"mini blockchain";
+    let digests: Vec<_> = HashAlgorithm::ALL.iter().map(|a| a.hash(data)).collect();
+
+    assert_eq!(digests[0].0, <[u8; 32]>::from(Sha256::digest(data)));
+    assert_eq!(
+        digests[1].0,
+        <[u8; 32]>::from(Sha256::digest(Sha256::digest(data)))
+    );
+    assert_ne!(digests[0], digests[2]);
+    assert_ne!(digests[1], digests[2]);
+}
+
+#[test]
+fn every_algorithm_mines_a_valid_chain() {
+    let genesis_hashes: Vec<_> = HashAlgorithm::ALL
+        .iter()
+        .map(|&algorithm| {
+            let bc = chain_with(algorithm);
+            assert!(bc.is_chain_valid(), "{} chain is invalid", algorithm);
+            assert_eq!(bc.latest_block().header.hash_algorithm, algorithm);
+            bc.chain[0].hash
+        })
+        .collect();
+
+    assert_ne!(genesis_hashes[0], genesis_hashes[2]);
+}
+
+#[test]
+fn block_with_another_algorithm_is_rejected() {
+    let mut bc = chain_with(HashAlgorithm::Blake3);
+    let tip = bc.latest_block().clone();
+
+    let foreign = Block::new(tip.header.index + 1, Vec::new(), tip.hash, bc.bits);
+    assert_eq!(foreign.header.hash_algorithm, HashAlgorithm::Sha256);
+    assert!(!foreign.is_valid_successor(&tip));
+    assert!(bc.add_block(foreign).is_err());
+}
+
+#[test]
+fn genesis_config_names_the_algorithm() {
+    let config: GenesisConfig = toml::from_str("hash_algorithm = \"double-sha256\"").unwrap();
+    assert_eq!(config.hash_algorithm, HashAlgorithm::DoubleSha256);
+    assert_eq!(config.hash_algorithm.to_string(), "double-sha256");
+}
@@ -0,0 +1,64 @@
+use blockchain_p2p::hd::HARDENED;
+use blockchain_p2p::{mnemonic, Wallet, WalletError};
+
+// Тестовый вектор 1 из BIP32
+const SEED: &str = "000102030405060708090a0b0c0d0e0f";
+
+#[test]
+fn master_and_child_match_bip32_vector() {
+    let master = Wallet::from_seed(&hex::decode(SEED).unwrap()).unwrap();
+    assert_eq!(
+        master.private_key,
+        "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+    );
+
+    let child = master.derive_child(HARDENED).unwrap();
+    assert_eq!(
+        child.private_key,
+        "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"
+    );
+
+    let grandchild = child.derive_child(1).unwrap();
+    assert_eq!(
+        grandchild.private_key,
+        "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+    );
+    assert_eq!(
+        master.derive_path("m/0'/1").unwrap().private_key,
+        grandchild.private_key
+    );
+}
+
+#[test]
+fn mnemonic_wallet_is_deterministic() {
+    let phrase = mnemonic::generate(12).unwrap();
+    assert_eq!(phrase.split_whitespace().count(), 12);
+    mnemonic::validate(&phrase).unwrap();
+
+    let first = Wallet::from_mnemonic(&phrase).unwrap();
+    let second = Wallet::from_mnemonic(&phrase).unwrap();
+    let a = first.derive_path("m/44'/0'/0'/0/0").unwrap();
+    let b = second.derive_path("m/44'/0'/0'/0/0").unwrap();
+    let c = first.derive_path("m/44'/0'/0'/0/1").unwrap();
+
+    assert_eq!(a.get_address(), b.get_address());
+    assert_ne!(a.get_address(), c.get_address());
+}
+
+#[test]
+fn invalid_inputs_are_rejected() {
+    assert!(matches!(
+        mnemonic::validate("abandon abandon abandon"),
+        Err(WalletError::InvalidMnemonic(_))
+    ));
+    assert_eq!(
+        Wallet::new().derive_child(0).err(),
+        Some(WalletError::NotHierarchical)
+    );
+
+    let master = Wallet::from_seed(&[7u8; 32]).unwrap();
+    assert!(matches!(
+        master.derive_path("44/0"),
+        Err(WalletError::InvalidPath(_))
+    ));
+}
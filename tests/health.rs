@@ -0,0 +1,74 @@
+use blockchain_p2p::health::MAX_MISSED_PINGS;
+use blockchain_p2p::metrics;
+use blockchain_p2p::simulation::Simulation;
+use blockchain_p2p::NodeEvent;
+use std::time::Duration;
+
+#[test]
+fn pings_measure_round_trip_time() {
+    let sim = Simulation::new(3);
+    sim.connect_all();
+    sim.set_link_latency(0, 1, Duration::from_millis(50));
+    assert!(sim
+        .node(0)
+        .peer_stats()
+        .iter()
+        .all(|peer| peer.rtt_ms.is_none()));
+
+    assert_eq!(sim.node(0).ping_peers(), 0);
+    let stats = sim.node(0).peer_stats();
+    assert_eq!(stats.len(), 2);
+    assert!(stats
+        .iter()
+        .all(|peer| peer.last_seen.is_some() && peer.missed_pings == 0));
+    let slow = stats.iter().find(|peer| peer.addr == sim.addr(1)).unwrap();
+    let fast = stats.iter().find(|peer| peer.addr == sim.addr(2)).unwrap();
+    assert!(slow.rtt_ms.unwrap() >= 100.0);
+    assert!(fast.rtt_ms.unwrap() < slow.rtt_ms.unwrap());
+
+    let text = metrics::render(sim.node(0));
+    assert!(text.contains(&format!(
+        "blockchain_peer_rtt_seconds{{peer=\"{}\"}} ",
+        sim.addr(1)
+    )));
+    assert!(text.contains(&format!(
+        "blockchain_peer_missed_pings{{peer=\"{}\"}} 0",
+        sim.addr(2)
+    )));
+    sim.shutdown();
+}
+
+#[test]
+fn silent_peers_are_dropped_after_missed_pings() {
+    let sim = Simulation::new(3);
+    sim.connect_all();
+    let mut events = sim.node(0).subscribe();
+
+    let missed = |sim: &Simulation| {
+        let stats = sim.node(0).peer_stats();
+        let silent = stats.iter().find(|peer| peer.addr == sim.addr(2));
+        silent.map(|peer| peer.missed_pings)
+    };
+
+    // Ответ сбрасывает счётчик пропусков
+    sim.partition(&[&[0, 1], &[2]]);
+    sim.node(0).ping_peers();
+    assert_eq!(missed(&sim), Some(1));
+    sim.heal();
+    sim.node(0).ping_peers();
+    assert_eq!(missed(&sim), Some(0));
+
+    sim.partition(&[&[0, 1], &[2]]);
+    for count in 1..MAX_MISSED_PINGS {
+        assert_eq!(sim.node(0).ping_peers(), 0);
+        assert_eq!(missed(&sim), Some(count));
+    }
+    assert_eq!(sim.node(0).ping_peers(), 1);
+    assert_eq!(missed(&sim), None);
+    assert_eq!(sim.node(0).peer_addrs(), vec![sim.addr(1)]);
+    assert_eq!(
+        events.try_recv().unwrap(),
+        NodeEvent::PeerDisconnected { addr: sim.addr(2) }
+    );
+    sim.shutdown();
+}
@@ -0,0 +1,55 @@
+use blockchain_p2p::simulation::Simulation;
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{GenesisConfig, Wallet};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn transactions_are_announced_and_fetched_once() {
+    let sim = Simulation::with_genesis(3, &GenesisConfig::regtest());
+    sim.connect_all();
+    let sender = Wallet::new();
+    sim.node(0).generate(1, &sender.get_address()).unwrap();
+    assert!(sim.wait_until(TIMEOUT, |sim| sim.converged()));
+
+    let tx = {
+        let bc = sim.node(0).blockchain.blocking_read();
+        sender.create_transaction_for_chain(
+            bc.chain_id(),
+            &Wallet::new().get_address(),
+            coins(5.0),
+            coins(0.1),
+            0,
+        )
+    };
+    // У третьего узла транзакция уже есть: запрашивать её ему незачем
+    for index in [0, 2] {
+        sim.node(index)
+            .blockchain
+            .blocking_write()
+            .add_transaction(tx.clone())
+            .unwrap();
+    }
+    sim.node(0).broadcast_transaction(&tx);
+    assert!(sim.wait_until(TIMEOUT, |sim| {
+        sim.node(1)
+            .blockchain
+            .blocking_read()
+            .mempool
+            .contains(&tx.id())
+    }));
+    // Второй узел объявляет принятую транзакцию третьему
+    assert!(sim.wait_until(TIMEOUT, |sim| {
+        sim.node(2).metrics.messages_received().get("inv") == Some(&2)
+    }));
+
+    let received = |index: usize, kind: &str| {
+        let messages = sim.node(index).metrics.messages_received();
+        messages.get(kind).copied().unwrap_or(0)
+    };
+    assert_eq!(received(0, "get_data"), 1);
+    assert_eq!(received(1, "get_data"), 0);
+    assert_eq!(received(1, "new_tx") + received(2, "new_tx"), 0);
+    sim.shutdown();
+}
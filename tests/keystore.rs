@@ -0,0 +1,51 @@
+use blockchain_p2p::{KeystoreError, Wallet};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("{}_{}.json", name, std::process::id()))
+}
+
+#[test]
+fn keystore_round_trip() {
+    let path = temp_path("keystore_round_trip");
+    let wallet = Wallet::new();
+
+    wallet.save_encrypted(&path, "correct horse").unwrap();
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains(&wallet.private_key));
+
+    let loaded = Wallet::load_encrypted(&path, "correct horse").unwrap();
+    assert_eq!(loaded.private_key, wallet.private_key);
+    assert_eq!(loaded.get_address(), wallet.get_address());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn keystore_rejects_wrong_passphrase() {
+    let path = temp_path("keystore_wrong_passphrase");
+    Wallet::new().save_encrypted(&path, "secret").unwrap();
+
+    let result = Wallet::load_encrypted(&path, "not secret");
+    assert!(matches!(result, Err(KeystoreError::WrongPassphrase)));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn keystore_keeps_hd_chain_code() {
+    let path = temp_path("keystore_hd_chain_code");
+    let master = Wallet::from_seed(&[1u8; 32]).unwrap();
+
+    master.save_encrypted(&path, "pw").unwrap();
+    let loaded = Wallet::load_encrypted(&path, "pw").unwrap();
+    assert_eq!(loaded.chain_code, master.chain_code);
+    assert_eq!(
+        loaded.derive_child(0).unwrap().private_key,
+        master.derive_child(0).unwrap().private_key
+    );
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{
+    BlockHeader, Blockchain, GenesisConfig, LightClient, Node, SyncError, Wallet,
+};
+use common::{mined_chain, signed_transaction};
+use std::thread;
+use std::time::Duration;
+
+fn headers_of(bc: &Blockchain) -> Vec<BlockHeader> {
+    bc.chain[1..].iter().map(|block| block.header).collect()
+}
+
+#[test]
+fn light_client_accepts_a_valid_header_chain() {
+    let bc = mined_chain();
+    let mut client = LightClient::for_network(&GenesisConfig::default());
+    assert_eq!(client.tip_hash(), bc.chain[0].hash);
+
+    assert_eq!(client.add_headers(&headers_of(&bc)), Ok(2));
+    assert_eq!(client.height(), 3);
+    assert_eq!(client.tip(), &bc.latest_block().header);
+    assert_eq!(client.tip_hash(), bc.latest_block().hash);
+    assert_eq!(client.confirmations(1), 2);
+}
+
+#[test]
+fn forged_or_unlinked_headers_are_rejected() {
+    let bc = mined_chain();
+    let mut client = LightClient::for_network(&GenesisConfig::default());
+
+    // Заголовок заявляет цель, до которой его хеш не дотягивает
+    let mut forged = bc.chain[1].header;
+    forged.bits = 0x1d00_ffff;
+    assert_eq!(
+        client.add_headers(&[forged]),
+        Err(SyncError::InvalidHeader { index: 1 })
+    );
+
+    // Пропуск заголовка рвёт связность
+    assert_eq!(
+        client.add_headers(&[bc.chain[2].header]),
+        Err(SyncError::InvalidHeader { index: 2 })
+    );
+    assert_eq!(client.height(), 1);
+}
+
+#[test]
+fn inclusion_is_checked_against_stored_headers() {
+    let bc = mined_chain();
+    let mut client = LightClient::for_network(&GenesisConfig::default());
+    client.add_headers(&headers_of(&bc)).unwrap();
+
+    let tx = &bc.chain[2].transactions[1];
+    let (index, proof) = bc.transaction_proof(&tx.id()).unwrap();
+    assert_eq!(index, 2);
+    assert!(client.verify_inclusion(&tx.id(), index, &proof));
+
+    let coinbase = bc.chain[2].transactions[0].id();
+    assert!(!client.verify_inclusion(&coinbase, index, &proof));
+    assert!(!client.verify_inclusion(&tx.id(), 1, &proof));
+}
+
+#[test]
+fn light_client_syncs_headers_and_verifies_payment_over_the_network() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let node = Node::new("Full".to_string());
+    let tx = {
+        let mut bc = node.blockchain.blocking_write();
+        common::fund(&mut bc, &sender);
+        let tx = signed_transaction(&sender, &receiver, coins(4.0), coins(0.1));
+        bc.add_transaction(tx.clone()).unwrap();
+        bc.mine_block(&sender.get_address()).unwrap();
+        bc.mine_block(&sender.get_address()).unwrap();
+        tx
+    };
+    let server = node.clone_node();
+    thread::spawn(move || server.start_server(38120));
+    thread::sleep(Duration::from_millis(300));
+
+    let peer = "127.0.0.1:38120";
+    let mut client = LightClient::for_network(&GenesisConfig::default());
+    assert_eq!(client.sync(peer), Ok(3));
+    assert_eq!(client.sync(peer), Ok(0));
+
+    assert_eq!(client.verify_transaction(peer, &tx.id()), Ok(2));
+    assert_eq!(client.confirmations(2), 2);
+
+    let unknown = signed_transaction(&sender, &receiver, coins(1.0), coins(0.0)).id();
+    assert_eq!(
+        client.verify_transaction(peer, &unknown),
+        Err(SyncError::TransactionNotFound(unknown))
+    );
+}
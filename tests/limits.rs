@@ -0,0 +1,85 @@
+mod common;
+
+use blockchain_p2p::block::{MAX_BLOCK_SIZE, MAX_BLOCK_TRANSACTIONS};
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{
+    Block, Blockchain, BlockchainError, HashAlgorithm, Transaction, ValidationError, Wallet,
+};
+use common::{fund, signed_transaction};
+use std::sync::atomic::AtomicBool;
+
+fn transfers(count: usize) -> Vec<Transaction> {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    (0..count)
+        .map(|_| signed_transaction(&sender, &receiver, coins(0.01), coins(0.0)))
+        .collect()
+}
+
+fn block_on(prev: &Block, transactions: Vec<Transaction>) -> Block {
+    Block::mine_at(
+        HashAlgorithm::default(),
+        prev.header.index + 1,
+        prev.header.timestamp + 10,
+        transactions,
+        prev.hash,
+        prev.header.bits,
+        &AtomicBool::new(false),
+    )
+    .unwrap()
+}
+
+#[test]
+fn block_with_too_many_transactions_is_rejected() {
+    let mut bc = Blockchain::default();
+    let genesis = bc.chain[0].clone();
+    let mut transactions = transfers(MAX_BLOCK_TRANSACTIONS + 1);
+
+    let oversized = block_on(&genesis, transactions.clone());
+    assert!(!oversized.is_within_limits());
+    assert!(!oversized.is_valid(std::slice::from_ref(&genesis)));
+    assert_eq!(
+        bc.add_block(oversized),
+        Err(BlockchainError::InvalidBlock {
+            index: 1,
+            reason: ValidationError::OverLimits,
+        })
+    );
+
+    transactions.pop();
+    let full = block_on(&genesis, transactions);
+    assert!(full.is_within_limits());
+    assert!(full.is_valid(std::slice::from_ref(&genesis)));
+}
+
+#[test]
+fn block_over_the_size_limit_is_rejected() {
+    let genesis = Blockchain::default().chain[0].clone();
+    let mut tx = transfers(1).remove(0);
+    tx.chain_id = "x".repeat(MAX_BLOCK_SIZE);
+
+    let block = block_on(&genesis, vec![tx]);
+    assert!(block.serialized_size() > MAX_BLOCK_SIZE);
+    assert!(!block.is_within_limits());
+    assert!(!block.is_valid(std::slice::from_ref(&genesis)));
+}
+
+#[test]
+fn mined_blocks_leave_excess_transactions_in_mempool() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    // Напрямую в пул: полная проверка тысячи транзакций здесь не нужна
+    let extra = 5;
+    for _ in 0..MAX_BLOCK_TRANSACTIONS + extra {
+        let tx = signed_transaction(&sender, &receiver, coins(0.01), coins(0.001));
+        bc.mempool.transactions.push(tx);
+    }
+
+    let block = bc.mine_block(&sender.get_address()).unwrap();
+    assert_eq!(block.transactions.len(), MAX_BLOCK_TRANSACTIONS);
+    assert!(block.serialized_size() <= MAX_BLOCK_SIZE);
+    assert_eq!(bc.mempool.size(), extra + 1);
+}
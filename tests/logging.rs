@@ -0,0 +1,55 @@
+use blockchain_p2p::{Blockchain, Wallet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Событие в виде «имя спана: поле=значение …».
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+struct Fields(String);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push_str(&format!(" {}={:?}", field.name(), value));
+    }
+}
+
+impl<S> Layer<S> for Recorder
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let spans: Vec<_> = ctx
+            .event_scope(event)
+            .map(|scope| scope.from_root().map(|span| span.name()).collect())
+            .unwrap_or_default();
+        let mut fields = Fields(format!("{}:", spans.join("/")));
+        event.record(&mut fields);
+        self.0.lock().unwrap().push(fields.0);
+    }
+}
+
+#[test]
+fn mining_emits_structured_events() {
+    let recorder = Recorder::default();
+    let subscriber = tracing_subscriber::registry().with(recorder.clone());
+
+    let mut bc = Blockchain::default();
+    let block = tracing::subscriber::with_default(subscriber, || {
+        bc.mine_block(&Wallet::new().get_address()).unwrap()
+    });
+
+    let events = recorder.0.lock().unwrap();
+    let mined = events
+        .iter()
+        .find(|event| event.contains("message=mined block"))
+        .expect("no mined block event");
+    assert!(mined.starts_with("mining:"));
+    assert!(mined.contains(" index=1"));
+    assert!(mined.contains(&format!(" nonce={}", block.header.nonce)));
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Blockchain, BlockchainError, Wallet};
+use common::{fund, signed_transaction};
+
+#[test]
+fn duplicate_transaction_is_rejected() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    let tx = signed_transaction(&sender, &receiver, coins(5.0), coins(0.1));
+    bc.add_transaction(tx.clone()).unwrap();
+    assert_eq!(
+        bc.add_transaction(tx.clone()),
+        Err(BlockchainError::DuplicateTransaction(tx.id()))
+    );
+
+    // Уже подтверждённую транзакцию тоже нельзя отправить повторно
+    fund(&mut bc, &sender);
+    assert_eq!(
+        bc.add_transaction(tx.clone()),
+        Err(BlockchainError::DuplicateTransaction(tx.id()))
+    );
+}
+
+#[test]
+fn pending_spends_count_against_balance() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    bc.add_transaction(signed_transaction(
+        &sender,
+        &receiver,
+        coins(30.0),
+        coins(1.0),
+    ))
+    .unwrap();
+    assert_eq!(
+        bc.add_transaction(signed_transaction(
+            &sender,
+            &receiver,
+            coins(20.0),
+            coins(1.0)
+        )),
+        Err(BlockchainError::InsufficientFunds {
+            available: coins(19.0),
+            required: coins(21.0)
+        })
+    );
+    bc.add_transaction(signed_transaction(
+        &sender,
+        &receiver,
+        coins(18.0),
+        coins(1.0),
+    ))
+    .unwrap();
+    assert_eq!(bc.mempool.pending_spend(&sender.get_address()), coins(50.0));
+}
+
+#[test]
+fn unfunded_sender_is_rejected() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+
+    let result = bc.add_transaction(signed_transaction(
+        &sender,
+        &receiver,
+        coins(1.0),
+        coins(0.0),
+    ));
+    assert!(matches!(
+        result,
+        Err(BlockchainError::InsufficientFunds { .. })
+    ));
+}
+
+#[test]
+fn full_mempool_evicts_cheapest_transaction() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+    bc.mempool.max_size = 2;
+
+    let cheap = signed_transaction(&sender, &receiver, coins(1.0), coins(0.1));
+    let medium = signed_transaction(&sender, &receiver, coins(1.0), coins(0.5));
+    bc.add_transaction(cheap.clone()).unwrap();
+    bc.add_transaction(medium.clone()).unwrap();
+
+    assert_eq!(
+        bc.add_transaction(signed_transaction(
+            &sender,
+            &receiver,
+            coins(1.0),
+            coins(0.1)
+        )),
+        Err(BlockchainError::MempoolFull {
+            min_fee: coins(0.1)
+        })
+    );
+    bc.add_transaction(signed_transaction(
+        &sender,
+        &receiver,
+        coins(1.0),
+        coins(1.0),
+    ))
+    .unwrap();
+    assert_eq!(bc.mempool.size(), 2);
+    assert!(!bc.mempool.contains(&cheap.id()));
+    assert!(bc.mempool.contains(&medium.id()));
+}
+
+#[test]
+fn expired_transactions_are_purged() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+    bc.mempool.ttl = 60;
+
+    let tx = signed_transaction(&sender, &receiver, coins(1.0), coins(0.1));
+    bc.add_transaction(tx.clone()).unwrap();
+
+    assert!(bc.mempool.purge_expired(tx.timestamp + 60).is_empty());
+    assert_eq!(bc.mempool.purge_expired(tx.timestamp + 61), vec![tx]);
+    assert_eq!(bc.mempool.size(), 0);
+}
+
+#[test]
+fn higher_fee_replaces_pending_transaction_with_same_nonce() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    let stuck = sender.create_transaction(&receiver.get_address(), coins(40.0), coins(0.1), 0);
+    bc.add_transaction(stuck.clone()).unwrap();
+
+    // Замена может потратить средства старой версии
+    let bumped = sender.create_transaction(&receiver.get_address(), coins(45.0), coins(1.0), 0);
+    bc.add_transaction(bumped.clone()).unwrap();
+    assert_eq!(bc.mempool.size(), 1);
+    assert!(!bc.mempool.contains(&stuck.id()));
+    assert!(bc.mempool.contains(&bumped.id()));
+    assert_eq!(bc.mempool.pending_spend(&sender.get_address()), coins(46.0));
+
+    bc.mine_block(&sender.get_address()).unwrap();
+    assert_eq!(bc.get_balance(&receiver.get_address()), coins(45.0));
+}
+
+#[test]
+fn replacement_must_pay_a_higher_fee() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    let pending = sender.create_transaction(&receiver.get_address(), coins(5.0), coins(0.5), 0);
+    bc.add_transaction(pending.clone()).unwrap();
+
+    let same_fee = sender.create_transaction(&receiver.get_address(), coins(6.0), coins(0.5), 0);
+    assert_eq!(
+        bc.add_transaction(same_fee),
+        Err(BlockchainError::ReplacementUnderpriced {
+            replaced: pending.id(),
+            min_fee: coins(0.5)
+        })
+    );
+
+    // Неоплатная замена не вытесняет исходную транзакцию
+    let unfunded = sender.create_transaction(&receiver.get_address(), coins(60.0), coins(1.0), 0);
+    assert!(matches!(
+        bc.add_transaction(unfunded),
+        Err(BlockchainError::InsufficientFunds { .. })
+    ));
+    assert!(bc.mempool.contains(&pending.id()));
+
+    // Подтверждённую транзакцию заменить уже нельзя
+    bc.mine_block(&sender.get_address()).unwrap();
+    let late = sender.create_transaction(&receiver.get_address(), coins(5.0), coins(2.0), 0);
+    assert!(matches!(
+        bc.add_transaction(late),
+        Err(BlockchainError::NonceReused { nonce: 0, .. })
+    ));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use blockchain_p2p::metrics;
+use blockchain_p2p::protocol::Message;
+use blockchain_p2p::rpc::start_rpc_server;
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Node, Wallet};
+use common::{fund, signed_transaction};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn metrics_reflect_node_state() {
+    let node = Node::new("Metrics".to_string());
+    let sender = Wallet::new();
+    {
+        let mut bc = node.blockchain.blocking_write();
+        fund(&mut bc, &sender);
+        bc.add_transaction(signed_transaction(
+            &sender,
+            &Wallet::new(),
+            coins(1.0),
+            coins(0.1),
+        ))
+        .unwrap();
+    }
+    node.metrics.record_mined_block(1500.0);
+    node.metrics.record_orphaned_block();
+    let ping = Message::GetPeers {
+        from: "peer".to_string(),
+    };
+    node.metrics.record_message(&ping);
+    node.metrics.record_message(&ping);
+
+    let text = metrics::render(&node);
+    assert!(text.contains("# TYPE blockchain_chain_height gauge\nblockchain_chain_height 2\n"));
+    assert!(text.contains("blockchain_mempool_size 1\n"));
+    assert!(text.contains("blockchain_peers_connected 0\n"));
+    assert!(text.contains("blockchain_hash_rate 1500\n"));
+    assert!(text.contains("blockchain_blocks_mined_total 1\n"));
+    assert!(text.contains("blockchain_orphaned_blocks_total 1\n"));
+    assert!(text.contains("blockchain_messages_received_total{type=\"get_peers\"} 2\n"));
+}
+
+#[test]
+fn rpc_server_serves_metrics_as_text() {
+    let node = Node::new("Metrics".to_string());
+    let server = node.clone_node();
+    thread::spawn(move || start_rpc_server(&server, 38116));
+    thread::sleep(Duration::from_millis(300));
+
+    let mut stream = TcpStream::connect("127.0.0.1:38116").unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).unwrap();
+
+    assert!(reply.starts_with("HTTP/1.1 200"));
+    assert!(reply.contains("Content-Type: text/plain; version=0.0.4"));
+    assert!(reply.contains("blockchain_chain_height 1\n"));
+}
@@ -0,0 +1,108 @@
+use blockchain_p2p::fixtures::BlockFixture;
+use blockchain_p2p::mining::search_nonce;
+use blockchain_p2p::{Blockchain, Hash256, MiningOptions, Target, Wallet};
+use std::sync::atomic::AtomicBool;
+
+/// Цель, которой удовлетворяет примерно один хеш из 4096.
+fn moderate_target() -> Target {
+    Target::from_compact(0x1f0f_ffff).unwrap()
+}
+
+#[test]
+fn workers_share_the_nonce_space() {
+    let target = moderate_target();
+    let header = |nonce: u64| Hash256::digest(&nonce.to_le_bytes());
+
+    let (single, single_stats) = search_nonce(
+        header,
+        0,
+        &target,
+        &MiningOptions::default(),
+        &AtomicBool::new(false),
+    );
+    let (parallel, parallel_stats) = search_nonce(
+        header,
+        0,
+        &target,
+        &MiningOptions::with_threads(4),
+        &AtomicBool::new(false),
+    );
+
+    let (nonce, hash) = single.unwrap();
+    assert_eq!(hash, header(nonce));
+    assert!(target.is_met_by(&hash));
+    assert_eq!(single_stats.hashes, nonce + 1);
+
+    let (nonce, hash) = parallel.unwrap();
+    assert_eq!(hash, header(nonce));
+    assert!(target.is_met_by(&hash));
+    assert!(parallel_stats.hashes > 0);
+}
+
+#[test]
+fn cancelled_search_stops_every_worker() {
+    let impossible = Target::from_compact(0x0300_0001).unwrap();
+    let (found, _) = search_nonce(
+        |nonce: u64| Hash256::digest(&nonce.to_le_bytes()),
+        0,
+        &impossible,
+        &MiningOptions::with_threads(3),
+        &AtomicBool::new(true),
+    );
+    assert_eq!(found, None);
+}
+
+#[test]
+fn search_stops_at_the_end_of_the_nonce_space() {
+    let impossible = Target::from_compact(0x0300_0001).unwrap();
+
+    // Каждый nonce до `u64::MAX` проверяется ровно один раз, даже если
+    // потоков больше, чем осталось nonce
+    for (start, threads) in [(u64::MAX - 4, 1), (u64::MAX - 4, 3), (u64::MAX, 4)] {
+        let (found, stats) = search_nonce(
+            |nonce: u64| Hash256::digest(&nonce.to_le_bytes()),
+            start,
+            &impossible,
+            &MiningOptions::with_threads(threads),
+            &AtomicBool::new(false),
+        );
+        assert_eq!(found, None);
+        assert_eq!(stats.hashes, u64::MAX - start + 1);
+    }
+}
+
+#[test]
+fn exhausted_nonces_roll_the_extra_nonce() {
+    let template = BlockFixture::new(1).bits(0x1f0f_ffff).build();
+    let mut block = template.clone();
+    block.header.nonce = u64::MAX - 2;
+
+    let (solved, stats) = block.solve(&MiningOptions::with_threads(2), &AtomicBool::new(false));
+    let solved = solved.unwrap();
+
+    assert_eq!(solved.header.extra_nonce, 1);
+    assert!(solved.header.nonce < u64::MAX - 2);
+    assert!(solved.has_valid_hash());
+    assert!(Target::from_compact(solved.header.bits)
+        .unwrap()
+        .is_met_by(&solved.hash));
+    assert!(stats.hashes > 3);
+    // Остальное в заголовке не меняется
+    assert_eq!(solved.header.timestamp, template.header.timestamp);
+    assert_eq!(solved.header.merkle_root, template.header.merkle_root);
+}
+
+#[test]
+fn chain_mined_with_several_threads_is_valid() {
+    let mut bc = Blockchain::default();
+    let miner = Wallet::new().get_address();
+
+    for _ in 0..3 {
+        let (block, stats) = bc
+            .mine_block_with(&miner, &MiningOptions::with_threads(4))
+            .unwrap();
+        assert_eq!(&block, bc.latest_block());
+        assert!(stats.hashes >= 1);
+    }
+    assert!(bc.is_chain_valid());
+}
@@ -0,0 +1,103 @@
+use blockchain_p2p::error::{TxValidationError, WalletError};
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Blockchain, Cosignature, MultisigPolicy, Transaction, Wallet};
+
+fn keys(wallets: &[&Wallet]) -> Vec<String> {
+    wallets.iter().map(|w| w.public_key.clone()).collect()
+}
+
+#[test]
+fn two_of_three_spends_after_two_signatures() {
+    let (a, b, c) = (Wallet::new(), Wallet::new(), Wallet::new());
+    let policy = MultisigPolicy::new(2, keys(&[&a, &b, &c])).unwrap();
+    let address = policy.address();
+    assert!(address.as_str().starts_with('3'));
+    assert!(address.is_script());
+    assert_eq!(address.validate(), Ok(()));
+    // Порядок ключей не влияет на адрес
+    assert_eq!(
+        MultisigPolicy::new(2, keys(&[&c, &a, &b]))
+            .unwrap()
+            .address(),
+        address
+    );
+
+    let mut bc = Blockchain::default();
+    bc.mine_block(&address).unwrap();
+    let receiver = Wallet::new().get_address();
+    let mut tx = Transaction::new_multisig(&policy, &receiver, coins(20.0), coins(0.5), 0);
+    let txid = tx.id();
+    assert_eq!(tx.validate(), Err(TxValidationError::MissingSignature));
+
+    a.co_sign(&mut tx).unwrap();
+    assert_eq!(
+        tx.validate(),
+        Err(TxValidationError::NotEnoughSignatures {
+            required: 2,
+            provided: 1,
+        })
+    );
+    // Повторная подпись тем же ключом не считается дважды
+    a.co_sign(&mut tx).unwrap();
+    assert_eq!(tx.cosignatures.len(), 1);
+
+    c.co_sign(&mut tx).unwrap();
+    assert!(tx.is_valid());
+    assert_eq!(tx.id(), txid);
+
+    bc.add_transaction(tx).unwrap();
+    bc.mine_block(&Wallet::new().get_address()).unwrap();
+    assert_eq!(bc.get_balance(&receiver), coins(20.0));
+    assert_eq!(bc.get_balance(&address), coins(50.0) - coins(20.5));
+}
+
+#[test]
+fn outsiders_and_forgeries_are_rejected() {
+    let (a, b) = (Wallet::new(), Wallet::new());
+    let outsider = Wallet::new();
+    let policy = MultisigPolicy::new(1, keys(&[&a, &b])).unwrap();
+    let to = outsider.get_address();
+    let mut tx = Transaction::new_multisig(&policy, &to, coins(1.0), coins(0.0), 0);
+
+    assert_eq!(outsider.co_sign(&mut tx), Err(WalletError::NotACosigner));
+    let mut plain = outsider.create_transaction(&a.get_address(), coins(1.0), coins(0.0), 0);
+    assert_eq!(a.co_sign(&mut plain), Err(WalletError::NotACosigner));
+
+    // Подпись чужим ключом под видом ключа политики
+    tx.cosignatures.push(Cosignature {
+        public_key: a.public_key.clone(),
+        signature: outsider.sign_transaction(&tx.signing_payload()),
+    });
+    assert_eq!(tx.validate(), Err(TxValidationError::InvalidSignature));
+
+    // Политика, не совпадающая с адресом отправителя
+    let mut tx = Transaction::new_multisig(&policy, &to, coins(1.0), coins(0.0), 0);
+    tx.multisig = Some(MultisigPolicy::new(1, keys(&[&a])).unwrap());
+    a.co_sign(&mut tx).unwrap();
+    assert_eq!(tx.validate(), Err(TxValidationError::PublicKeyMismatch));
+
+    // С адреса M-из-N нельзя тратить одной обычной подписью
+    let mut single = a.create_transaction(&to, coins(1.0), coins(0.0), 0);
+    single.from = policy.address();
+    single.signature = a.sign_transaction(&single.signing_payload());
+    assert_eq!(single.validate(), Err(TxValidationError::PublicKeyMismatch));
+}
+
+#[test]
+fn invalid_policies_are_refused() {
+    let (a, b) = (Wallet::new(), Wallet::new());
+    let invalid = |threshold, keys: Vec<String>| {
+        let count = keys.len();
+        assert_eq!(
+            MultisigPolicy::new(threshold, keys),
+            Err(TxValidationError::InvalidMultisigPolicy {
+                threshold,
+                keys: count,
+            })
+        );
+    };
+    invalid(0, keys(&[&a, &b]));
+    invalid(3, keys(&[&a, &b]));
+    invalid(1, keys(&[&a, &a]));
+    invalid(1, vec!["not a key".to_string()]);
+}
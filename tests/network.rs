@@ -0,0 +1,551 @@
+mod common;
+
+use blockchain_p2p::genesis::Allocation;
+use blockchain_p2p::network::{resolve_peer_addr, PeerInfo, IBD_BATCH_SIZE};
+use blockchain_p2p::protocol::{
+    read_message, write_message, Message, MAX_MESSAGE_SIZE, PROTOCOL_VERSION,
+};
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Blockchain, GenesisConfig, Node, NodeEvent, RateLimiter, SyncError, Wallet};
+use common::{fund, signed_transaction};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+fn peer(addr: &str) -> PeerInfo {
+    PeerInfo {
+        addr: addr.parse().unwrap(),
+        node_id: "Peer".to_string(),
+        version: PROTOCOL_VERSION,
+        best_height: 1,
+    }
+}
+
+fn spawn_server(node: &Node, port: u16) {
+    let server = node.clone_node();
+    thread::spawn(move || server.start_server(port));
+    thread::sleep(Duration::from_millis(300));
+}
+
+#[test]
+fn nodes_exchange_chain_size() {
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+    spawn_server(&alice, 38101);
+
+    assert_eq!(bob.request_chain_size("127.0.0.1:38101"), Some(1));
+}
+
+#[test]
+fn connecting_node_syncs_longer_chain() {
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+
+    // Общий genesis, иначе синхронизация невозможна
+    *bob.blockchain.blocking_write() = alice.blockchain.blocking_read().clone();
+
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    {
+        let mut bc = alice.blockchain.blocking_write();
+        fund(&mut bc, &sender);
+        bc.add_transaction(signed_transaction(
+            &sender,
+            &receiver,
+            coins(5.0),
+            coins(0.1),
+        ))
+        .unwrap();
+        bc.mine_block(&sender.get_address()).unwrap();
+    }
+
+    spawn_server(&alice, 38102);
+
+    assert!(bob.connect_to_peer("127.0.0.1:38102"));
+    assert_eq!(bob.blockchain.blocking_read().chain.len(), 3);
+    assert_eq!(bob.request_chain_size("127.0.0.1:38102"), Some(3));
+}
+
+#[test]
+fn broadcast_block_and_transaction_reach_peer() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+    fund(&mut alice.blockchain.blocking_write(), &sender);
+    *bob.blockchain.blocking_write() = alice.blockchain.blocking_read().clone();
+    spawn_server(&alice, 38103);
+    bob.peers.lock().unwrap().push(peer("127.0.0.1:38103"));
+
+    let block = {
+        let mut bc = bob.blockchain.blocking_write();
+        bc.add_transaction(signed_transaction(
+            &sender,
+            &receiver,
+            coins(2.0),
+            coins(0.1),
+        ))
+        .unwrap();
+        bc.mine_block(&sender.get_address()).unwrap();
+        bc.chain.last().cloned().unwrap()
+    };
+    bob.broadcast_block(&block);
+    bob.broadcast_transaction(&signed_transaction(
+        &sender,
+        &receiver,
+        coins(1.0),
+        coins(0.1),
+    ));
+    thread::sleep(Duration::from_millis(300));
+
+    let bc = alice.blockchain.blocking_read();
+    assert_eq!(bc.chain.last(), Some(&block));
+    assert_eq!(bc.mempool.size(), 1);
+}
+
+#[test]
+fn background_miner_mines_pending_transactions() {
+    let node = Node::new("Miner".to_string());
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    fund(&mut node.blockchain.blocking_write(), &sender);
+
+    let handle = node.start_mining(sender.get_address());
+    node.blockchain
+        .blocking_write()
+        .add_transaction(signed_transaction(
+            &sender,
+            &receiver,
+            coins(4.0),
+            coins(0.1),
+        ))
+        .unwrap();
+
+    for _ in 0..50 {
+        if node.blockchain.blocking_read().chain.len() == 3 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    node.stop_mining();
+    handle.join().unwrap();
+
+    let bc = node.blockchain.blocking_read();
+    assert_eq!(bc.chain.len(), 3);
+    assert_eq!(bc.mempool.size(), 0);
+    assert!(!node.is_mining());
+}
+
+#[test]
+fn nodes_discover_each_other_through_seed() {
+    let seed = Node::new("Seed".to_string());
+    let bob = Node::new("Bob".to_string());
+    let carol = Node::new("Carol".to_string());
+    spawn_server(&seed, 38104);
+    spawn_server(&bob, 38105);
+    spawn_server(&carol, 38106);
+    let seeds = vec!["127.0.0.1:38104".to_string()];
+
+    assert_eq!(bob.discover_peers(&seeds), 1);
+    thread::sleep(Duration::from_millis(100));
+    let seed_peers = seed.peers.lock().unwrap().clone();
+    assert_eq!(seed_peers.len(), 1);
+    assert_eq!(seed_peers[0].addr.to_string(), "127.0.0.1:38105");
+    assert_eq!(seed_peers[0].node_id, "Bob");
+
+    // Carol узнаёт о Bob из списка seed-узла и подключается к обоим
+    assert_eq!(carol.discover_peers(&seeds), 2);
+    let peers = carol.peer_addrs();
+    assert!(peers.contains(&"127.0.0.1:38104".to_string()));
+    assert!(peers.contains(&"127.0.0.1:38105".to_string()));
+}
+
+#[test]
+fn peer_table_is_deduplicated_and_bounded() {
+    let mut node = Node::new("Node".to_string());
+    node.max_peers = 2;
+
+    assert!(node.add_peer(peer("127.0.0.1:1")));
+    assert!(!node.add_peer(peer("127.0.0.1:1")));
+    assert!(node.add_peer(peer("127.0.0.1:2")));
+    assert!(!node.add_peer(peer("127.0.0.1:3")));
+    assert_eq!(node.peers.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn fresh_node_downloads_blocks_in_batches() {
+    // Без перерасчёта сложности, иначе 55 блоков майнились бы слишком долго
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+    alice.blockchain.blocking_write().adjustment_interval = u32::MAX;
+    bob.blockchain.blocking_write().adjustment_interval = u32::MAX;
+
+    let miner = Wallet::new();
+    {
+        let mut bc = alice.blockchain.blocking_write();
+        for _ in 0..(IBD_BATCH_SIZE + 5) {
+            bc.mine_block(&miner.get_address()).unwrap();
+        }
+    }
+    spawn_server(&alice, 38107);
+
+    // Genesis по умолчанию у обоих узлов один и тот же
+    assert_eq!(
+        bob.sync_with_peer("127.0.0.1:38107"),
+        Ok(IBD_BATCH_SIZE + 5)
+    );
+    assert!(!bob.is_syncing());
+
+    let bob_chain = bob.blockchain.blocking_read().chain.clone();
+    assert_eq!(bob_chain, alice.blockchain.blocking_read().chain);
+    assert_eq!(
+        bob.blockchain
+            .blocking_read()
+            .get_balance(&miner.get_address()),
+        coins(50.0).checked_mul(IBD_BATCH_SIZE as u64 + 5).unwrap()
+    );
+    assert_eq!(bob.sync_with_peer("127.0.0.1:38107"), Ok(0));
+}
+
+/// Узел с `blocks` пустыми блоками; сложность не пересчитывается.
+fn node_with_blocks(id: &str, blocks: usize) -> Node {
+    let node = Node::new(id.to_string());
+    let mut bc = node.blockchain.blocking_write();
+    bc.adjustment_interval = u32::MAX;
+    let miner = Wallet::new().get_address();
+    for _ in 0..blocks {
+        bc.mine_block(&miner).unwrap();
+    }
+    drop(bc);
+    node
+}
+
+#[test]
+fn headers_first_sync_switches_to_heavier_fork() {
+    let alice = node_with_blocks("Alice", 3);
+    let bob = node_with_blocks("Bob", 1);
+    let receiver = Wallet::new();
+    {
+        // Перевод из ветки Боба, которой нет у Алисы
+        let mut bc = bob.blockchain.blocking_write();
+        let sender = Wallet::new();
+        fund(&mut bc, &sender);
+        bc.add_transaction(signed_transaction(
+            &sender,
+            &receiver,
+            coins(2.0),
+            coins(0.0),
+        ))
+        .unwrap();
+    }
+    spawn_server(&alice, 38121);
+    let mut events = bob.subscribe();
+
+    assert_eq!(bob.sync_with_peer("127.0.0.1:38121"), Ok(1));
+    let bc = bob.blockchain.blocking_read();
+    assert_eq!(bc.chain, alice.blockchain.blocking_read().chain);
+    assert_eq!(bc.mempool.size(), 0);
+
+    match events.try_recv() {
+        Ok(NodeEvent::ChainReorged {
+            fork_height,
+            disconnected,
+            connected,
+        }) => {
+            assert_eq!(fork_height, 0);
+            assert_eq!(disconnected.len(), 2);
+            assert_eq!(connected, 3);
+        }
+        other => panic!("expected a reorg event, got {:?}", other),
+    }
+    let added = std::iter::from_fn(|| events.try_recv().ok()).count();
+    assert_eq!(added, 3);
+}
+
+#[test]
+fn lighter_fork_is_not_downloaded() {
+    let alice = node_with_blocks("Alice", 2);
+    let bob = node_with_blocks("Bob", 3);
+    spawn_server(&alice, 38122);
+
+    let before = bob.blockchain.blocking_read().chain.clone();
+    assert_eq!(bob.sync_with_peer("127.0.0.1:38122"), Ok(0));
+    assert_eq!(bob.blockchain.blocking_read().chain, before);
+}
+
+#[test]
+fn bodies_from_a_peer_on_another_branch_are_refetched() {
+    let alice = node_with_blocks("Alice", IBD_BATCH_SIZE + 5);
+    let carol = node_with_blocks("Carol", IBD_BATCH_SIZE + 5);
+    let bob = node_with_blocks("Bob", 0);
+    spawn_server(&alice, 38123);
+    spawn_server(&carol, 38124);
+
+    // Вторую пачку тел Боб попросит у Кэрол, но её блоки не совпадут
+    // с заголовками Алисы и будут перезапрошены у Алисы
+    bob.add_peer(peer("127.0.0.1:38124"));
+    assert_eq!(
+        bob.sync_with_peer("127.0.0.1:38123"),
+        Ok(IBD_BATCH_SIZE + 5)
+    );
+    assert_eq!(
+        bob.blockchain.blocking_read().chain,
+        alice.blockchain.blocking_read().chain
+    );
+}
+
+/// Ждёт, пока узел закроет соединение; `true` — если закрыл.
+fn closed_by_peer(stream: &mut TcpStream) -> bool {
+    stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    matches!(stream.read(&mut [0u8; 64]), Ok(0) | Err(_))
+}
+
+#[test]
+fn peer_sending_garbage_is_banned() {
+    let alice = Node::new("Alice".to_string());
+    spawn_server(&alice, 38125);
+
+    for _ in 0..5 {
+        let mut stream = TcpStream::connect("127.0.0.1:38125").unwrap();
+        stream
+            .write_all(&[0, 0, 0, 4, b'j', b'u', b'n', b'k'])
+            .unwrap();
+        assert!(closed_by_peer(&mut stream));
+    }
+
+    let banned = alice.banned_peers();
+    assert_eq!(banned.len(), 1);
+    assert_eq!(banned[0].ip.to_string(), "127.0.0.1");
+
+    // Новые соединения с адреса закрываются сразу, а сам узел к нему не идёт
+    let mut stream = TcpStream::connect("127.0.0.1:38125").unwrap();
+    let hello = Message::Hello(alice.hello());
+    let _ = write_message(&mut stream, &hello);
+    assert!(closed_by_peer(&mut stream));
+    assert_eq!(
+        alice.handshake("127.0.0.1:38125"),
+        Err(SyncError::PeerBanned)
+    );
+}
+
+#[test]
+fn peer_relaying_an_invalid_block_is_banned() {
+    let alice = Node::new("Alice".to_string());
+    alice.add_peer(peer("127.0.0.1:38199"));
+    spawn_server(&alice, 38126);
+
+    let mut forged = {
+        let mut bc = alice.blockchain.blocking_read().clone();
+        bc.mine_block(&Wallet::new().get_address()).unwrap()
+    };
+    forged.header.nonce += 1;
+
+    let mut stream = TcpStream::connect("127.0.0.1:38126").unwrap();
+    write_message(&mut stream, &Message::NewBlock { block: forged }).unwrap();
+    assert!(closed_by_peer(&mut stream));
+
+    assert_eq!(alice.banned_peers().len(), 1);
+    // Пиры с забаненного адреса покидают таблицу
+    assert!(alice.peer_addrs().is_empty());
+    assert_eq!(alice.blockchain.blocking_read().chain.len(), 1);
+}
+
+#[test]
+fn flooding_peer_is_throttled_and_then_banned() {
+    let alice = Node::new("Alice".to_string());
+    *alice.rate_limiter.lock().unwrap() = RateLimiter::new(0.01, 3.0);
+    spawn_server(&alice, 38127);
+
+    let mut stream = TcpStream::connect("127.0.0.1:38127").unwrap();
+    let request = Message::GetChainSize {
+        from: "Mallory".to_string(),
+    };
+    for _ in 0..3 {
+        write_message(&mut stream, &request).unwrap();
+        assert_eq!(
+            read_message(&mut stream).unwrap(),
+            Message::ChainSize { size: 1 }
+        );
+    }
+
+    // Сверх лимита ответов нет, а за десяток лишних сообщений — бан
+    for _ in 0..10 {
+        let _ = write_message(&mut stream, &request);
+    }
+    assert!(closed_by_peer(&mut stream));
+    assert_eq!(alice.banned_peers().len(), 1);
+}
+
+#[test]
+fn idle_and_oversized_connections_are_dropped() {
+    let mut alice = Node::new("Alice".to_string());
+    alice.idle_timeout = Duration::from_millis(200);
+    spawn_server(&alice, 38128);
+
+    let mut idle = TcpStream::connect("127.0.0.1:38128").unwrap();
+    assert!(closed_by_peer(&mut idle));
+
+    // Заголовок кадра с длиной сверх лимита: тело даже не читается
+    let mut oversized = TcpStream::connect("127.0.0.1:38128").unwrap();
+    let len = (MAX_MESSAGE_SIZE as u32 + 1).to_be_bytes();
+    oversized.write_all(&len).unwrap();
+    assert!(closed_by_peer(&mut oversized));
+    assert!(alice.banned_peers().is_empty());
+    assert_eq!(
+        alice
+            .bans
+            .lock()
+            .unwrap()
+            .score("127.0.0.1".parse().unwrap()),
+        20
+    );
+}
+
+#[test]
+fn nodes_on_different_chains_refuse_to_peer() {
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+    *bob.blockchain.blocking_write() = Blockchain::new(&GenesisConfig {
+        chain_id: "testnet".to_string(),
+        ..GenesisConfig::default()
+    });
+    spawn_server(&alice, 38108);
+
+    assert_eq!(
+        bob.handshake("127.0.0.1:38108"),
+        Err(SyncError::ChainMismatch {
+            ours: "testnet".to_string(),
+            theirs: "mini-blockchain".to_string(),
+        })
+    );
+    assert!(!bob.connect_to_peer("127.0.0.1:38108"));
+    assert!(bob.peers.lock().unwrap().is_empty());
+}
+
+#[test]
+fn handshake_reports_peer_and_rejects_foreign_genesis() {
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+    let carol = Node::new("Carol".to_string());
+    // Та же сеть, но другой premine — другой genesis
+    *carol.blockchain.blocking_write() = Blockchain::new(&GenesisConfig {
+        allocations: vec![Allocation {
+            address: Wallet::new().get_address(),
+            amount: coins(10.0),
+        }],
+        ..GenesisConfig::default()
+    });
+    spawn_server(&alice, 38109);
+
+    let info = bob.handshake("127.0.0.1:38109").unwrap();
+    assert_eq!(info.node_id, "Alice");
+    assert_eq!(info.version, PROTOCOL_VERSION);
+    assert_eq!(info.best_height, 1);
+
+    assert_eq!(
+        carol.handshake("127.0.0.1:38109"),
+        Err(SyncError::GenesisMismatch)
+    );
+}
+
+#[test]
+fn shutdown_stops_server_and_miner_and_saves_chain() {
+    let path = std::env::temp_dir().join(format!("shutdown_{}.jsonl", std::process::id()));
+    let mut alice = Node::new("Alice".to_string());
+    alice.chain_file = Some(path.clone());
+    let sender = Wallet::new();
+    fund(&mut alice.blockchain.blocking_write(), &sender);
+
+    let server = alice.clone_node();
+    let server_thread = thread::spawn(move || server.start_server(38117));
+    thread::sleep(Duration::from_millis(300));
+    let bob = Node::new("Bob".to_string());
+    assert!(bob.connect_to_peer("127.0.0.1:38117"));
+    let miner = alice.start_mining(sender.get_address());
+
+    alice.shutdown().unwrap();
+
+    server_thread.join().unwrap().unwrap();
+    miner.join().unwrap();
+    assert!(!alice.is_mining());
+    assert_eq!(bob.request_chain_size("127.0.0.1:38117"), None);
+    let saved = Blockchain::load_from_disk(&path).unwrap();
+    assert_eq!(saved.chain, alice.blockchain.blocking_read().chain);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn peer_addresses_accept_ipv6_literals() {
+    let v6: SocketAddr = "[::1]:3001".parse().unwrap();
+    assert_eq!(resolve_peer_addr("[::1]:3001"), Some(v6));
+    assert_eq!(
+        resolve_peer_addr("::1"),
+        Some("[::1]:3000".parse().unwrap())
+    );
+    assert_eq!(
+        resolve_peer_addr("[::1]"),
+        Some("[::1]:3000".parse().unwrap())
+    );
+    assert_eq!(
+        resolve_peer_addr("10.0.0.1"),
+        Some("10.0.0.1:3000".parse().unwrap())
+    );
+    assert_eq!(
+        resolve_peer_addr("localhost:3001").map(|addr| addr.port()),
+        Some(3001)
+    );
+    assert_eq!(resolve_peer_addr("not an address"), None);
+}
+
+#[test]
+fn node_listens_on_ipv4_and_ipv6_at_once() {
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+    let carol = Node::new("Carol".to_string());
+    let server = alice.clone_node();
+    let addrs: Vec<SocketAddr> = vec![
+        "[::1]:38137".parse().unwrap(),
+        "127.0.0.1:38137".parse().unwrap(),
+    ];
+    thread::spawn(move || server.start_server_on(&addrs));
+    thread::sleep(Duration::from_millis(300));
+    // Пирам сообщается первый адрес
+    assert_eq!(alice.hello().listen_addr.as_deref(), Some("[::1]:38137"));
+
+    assert!(bob.connect_to_peer("[::1]:38137"));
+    assert!(carol.connect_to_peer("127.0.0.1:38137"));
+    assert_eq!(bob.peer_addrs(), vec!["[::1]:38137"]);
+    assert_eq!(carol.peer_addrs(), vec!["127.0.0.1:38137"]);
+    alice.shutdown().unwrap();
+}
+
+#[test]
+fn addresses_that_fail_to_bind_are_skipped() {
+    let taken = TcpListener::bind("127.0.0.1:38140").unwrap();
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+
+    // Ни один адрес не занят — ошибка, а не паника
+    let busy: SocketAddr = "127.0.0.1:38140".parse().unwrap();
+    assert!(alice.start_server_on(&[busy]).is_err());
+
+    let server = alice.clone_node();
+    let addrs = vec![busy, "127.0.0.1:38141".parse().unwrap()];
+    let serving = thread::spawn(move || server.start_server_on(&addrs));
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(
+        alice.hello().listen_addr.as_deref(),
+        Some("127.0.0.1:38141")
+    );
+    assert!(bob.connect_to_peer("127.0.0.1:38141"));
+
+    alice.shutdown().unwrap();
+    assert!(serving.join().unwrap().is_ok());
+    drop(taken);
+}
@@ -0,0 +1,121 @@
+use blockchain_p2p::testutil::{coins, test_wallet, ChainBuilder};
+use blockchain_p2p::{
+    MultisigPolicy, PartiallySignedTransaction, PsbtError, Transaction, WalletError, WatchWallet,
+};
+
+#[test]
+fn watch_only_transfer_is_signed_offline_and_accepted() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let mut bc = builder.mine(&alice).build();
+
+    // Онлайн: собрать по одному адресу и выгрузить
+    let watch = WatchWallet::from_address(alice.get_address()).unwrap();
+    let unsigned = watch.transaction_for(&bc, &bob.get_address(), coins(5.0), coins(0.1));
+    let exported = PartiallySignedTransaction::new(unsigned).to_base64();
+
+    // Офлайн: подписать и вернуть
+    let mut psbt = PartiallySignedTransaction::import(&exported).unwrap();
+    assert_eq!(psbt.signatures(), (0, 1));
+    psbt.sign(&alice).unwrap();
+    assert!(psbt.is_complete());
+    let returned = psbt.to_json();
+
+    let tx = PartiallySignedTransaction::import(&returned)
+        .unwrap()
+        .finalize()
+        .unwrap();
+    assert_eq!(tx.public_key, alice.public_key);
+    bc.add_transaction(tx).unwrap();
+}
+
+#[test]
+fn only_the_sender_can_sign() {
+    let alice = test_wallet(1);
+    let bob = test_wallet(2);
+    let unsigned = alice.watch_only().unsigned_transaction(
+        "main",
+        &bob.get_address(),
+        coins(1.0),
+        coins(0.1),
+        0,
+    );
+
+    let mut psbt = PartiallySignedTransaction::new(unsigned);
+    assert_eq!(psbt.sign(&bob), Err(WalletError::NotTheSender));
+    assert!(matches!(
+        psbt.clone().finalize(),
+        Err(PsbtError::Incomplete {
+            signatures: 0,
+            required: 1,
+            ..
+        })
+    ));
+
+    let mut tx = psbt.transaction.clone();
+    alice.sign_unsigned(&mut tx).unwrap();
+    assert!(tx.verify_signature().is_ok());
+}
+
+#[test]
+fn multisig_transfer_collects_signatures_across_machines() {
+    let (a, b, c) = (test_wallet(1), test_wallet(2), test_wallet(3));
+    let policy = MultisigPolicy::new(
+        2,
+        vec![
+            a.public_key.clone(),
+            b.public_key.clone(),
+            c.public_key.clone(),
+        ],
+    )
+    .unwrap();
+    let unsigned = Transaction::new_multisig(
+        &policy,
+        &test_wallet(4).get_address(),
+        coins(5.0),
+        coins(0.1),
+        0,
+    );
+    let txid = unsigned.id();
+
+    let mut psbt = PartiallySignedTransaction::new(unsigned);
+    psbt.sign(&a).unwrap();
+    assert_eq!(psbt.signatures(), (1, 2));
+    assert!(matches!(
+        psbt.clone().finalize(),
+        Err(PsbtError::Incomplete {
+            signatures: 1,
+            required: 2,
+            ..
+        })
+    ));
+
+    let mut psbt = PartiallySignedTransaction::import(&psbt.to_base64()).unwrap();
+    assert_eq!(psbt.sign(&test_wallet(5)), Err(WalletError::NotACosigner));
+    psbt.sign(&c).unwrap();
+    assert_eq!(psbt.signatures(), (2, 2));
+    let tx = psbt.finalize().unwrap();
+    assert_eq!(tx.id(), txid);
+}
+
+#[test]
+fn malformed_exports_are_rejected() {
+    assert!(matches!(
+        PartiallySignedTransaction::import("not base64!"),
+        Err(PsbtError::Malformed(_))
+    ));
+    assert!(matches!(
+        PartiallySignedTransaction::import("{\"version\": 1}"),
+        Err(PsbtError::Malformed(_))
+    ));
+
+    let tx =
+        test_wallet(1).create_transaction(&test_wallet(2).get_address(), coins(1.0), coins(0.1), 0);
+    let mut psbt = PartiallySignedTransaction::new(tx);
+    psbt.version = 99;
+    assert_eq!(
+        PartiallySignedTransaction::import(&psbt.to_json()),
+        Err(PsbtError::UnsupportedVersion(99))
+    );
+}
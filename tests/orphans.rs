@@ -0,0 +1,100 @@
+use blockchain_p2p::network::PeerInfo;
+use blockchain_p2p::orphans::OrphanPool;
+use blockchain_p2p::protocol::PROTOCOL_VERSION;
+use blockchain_p2p::{BlockStatus, Blockchain, Node, Wallet};
+use std::thread;
+use std::time::Duration;
+
+/// Цепь из genesis и `blocks` пустых блоков.
+fn source_chain(blocks: usize) -> Blockchain {
+    let miner = Wallet::new().get_address();
+    let mut bc = Blockchain::default();
+    for _ in 0..blocks {
+        bc.mine_block(&miner).unwrap();
+    }
+    bc
+}
+
+#[test]
+fn blocks_arriving_out_of_order_are_connected() {
+    let source = source_chain(3);
+    let mut bc = Blockchain::default();
+
+    assert_eq!(
+        bc.process_block(source.chain[3].clone()),
+        Ok(BlockStatus::Orphan {
+            missing_parent: source.chain[2].hash
+        })
+    );
+    assert_eq!(
+        bc.process_block(source.chain[2].clone()),
+        Ok(BlockStatus::Orphan {
+            missing_parent: source.chain[1].hash
+        })
+    );
+    assert_eq!(bc.orphans.len(), 2);
+    assert_eq!(bc.chain.len(), 1);
+
+    assert_eq!(
+        bc.process_block(source.chain[1].clone()),
+        Ok(BlockStatus::Connected { orphans: 2 })
+    );
+    assert_eq!(bc.chain, source.chain);
+    assert!(bc.orphans.is_empty());
+    assert!(bc.is_chain_valid());
+}
+
+#[test]
+fn orphan_pool_evicts_the_oldest_block() {
+    let source = source_chain(3);
+    let mut pool = OrphanPool::new(2);
+
+    assert!(pool.insert(source.chain[1].clone()));
+    assert!(!pool.insert(source.chain[1].clone()));
+    assert!(pool.insert(source.chain[2].clone()));
+    assert!(pool.insert(source.chain[3].clone()));
+
+    assert_eq!(pool.len(), 2);
+    assert!(!pool.contains(&source.chain[1].hash));
+    let children = pool.take_children(&source.chain[1].hash);
+    assert_eq!(children, vec![source.chain[2].clone()]);
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn orphan_from_a_peer_triggers_download_of_its_parents() {
+    let alice = Node::new("Alice".to_string());
+    let bob = Node::new("Bob".to_string());
+    for (node, port) in [(&alice, 38118), (&bob, 38119)] {
+        let server = node.clone_node();
+        thread::spawn(move || server.start_server(port));
+    }
+    thread::sleep(Duration::from_millis(300));
+
+    *alice.blockchain.blocking_write() = source_chain(3);
+    bob.peers.lock().unwrap().push(PeerInfo {
+        addr: "127.0.0.1:38118".parse().unwrap(),
+        node_id: "Alice".to_string(),
+        version: PROTOCOL_VERSION,
+        best_height: 1,
+    });
+    alice.peers.lock().unwrap().push(PeerInfo {
+        addr: "127.0.0.1:38119".parse().unwrap(),
+        node_id: "Bob".to_string(),
+        version: PROTOCOL_VERSION,
+        best_height: 1,
+    });
+
+    let tip = alice.blockchain.blocking_read().latest_block().clone();
+    alice.broadcast_block(&tip);
+
+    for _ in 0..50 {
+        if bob.blockchain.blocking_read().chain.len() == 4 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    let bc = bob.blockchain.blocking_read();
+    assert_eq!(bc.latest_block().hash, tip.hash);
+    assert!(bc.is_chain_valid());
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Amount, Blockchain, Hash256, Wallet};
+use common::workless_chain;
+use proptest::prelude::*;
+
+/// Перевод в плане блока: от кого, кому и какую долю доступного баланса.
+type Transfer = (usize, usize, f64);
+
+const WALLETS: usize = 3;
+
+/// Порча, которую проверка цепи обязана заметить. Индексы берутся по
+/// модулю длины цепи (и числа транзакций в блоке).
+#[derive(Debug, Clone)]
+enum Mutation {
+    Amount { block: usize, tx: usize },
+    Swap { first: usize, second: usize },
+    PrevHash { block: usize, hash: [u8; 32] },
+    Nonce { block: usize },
+}
+
+fn transfer() -> impl Strategy<Value = Transfer> {
+    (0..WALLETS, 0..WALLETS, 0.01f64..0.9)
+}
+
+/// План цепи: для каждого блока — индекс майнера и переводы в нём.
+fn plan() -> impl Strategy<Value = Vec<(usize, Vec<Transfer>)>> {
+    prop::collection::vec((0..WALLETS, prop::collection::vec(transfer(), 0..4)), 2..6)
+}
+
+fn mutation() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        (any::<usize>(), any::<usize>()).prop_map(|(block, tx)| Mutation::Amount { block, tx }),
+        (any::<usize>(), any::<usize>())
+            .prop_map(|(first, second)| Mutation::Swap { first, second }),
+        (any::<usize>(), any::<[u8; 32]>())
+            .prop_map(|(block, hash)| Mutation::PrevHash { block, hash }),
+        any::<usize>().prop_map(|block| Mutation::Nonce { block }),
+    ]
+}
+
+/// Строит цепь по плану через обычные `add_transaction` и `mine_block`;
+/// переводы самому себе и те, на которые не хватает средств, пропускаются.
+fn build(plan: &[(usize, Vec<Transfer>)]) -> Blockchain {
+    let wallets: Vec<Wallet> = (0..WALLETS).map(|_| Wallet::new()).collect();
+    let mut bc = workless_chain();
+
+    for (miner, transfers) in plan {
+        for &(from, to, share) in transfers {
+            if from == to {
+                continue;
+            }
+            let sender = &wallets[from];
+            let address = sender.get_address();
+            let available = bc.get_balance(&address) - bc.mempool.pending_spend(&address);
+            let amount = Amount::from_coins((available.to_f64() * share).floor() as u64);
+            if amount < Amount::from_coins(1) {
+                continue;
+            }
+            let nonce = bc.next_nonce(&address);
+            let tx =
+                sender.create_transaction(&wallets[to].get_address(), amount, coins(0.1), nonce);
+            bc.add_transaction(tx).unwrap();
+        }
+        bc.mine_block(&wallets[*miner].get_address()).unwrap();
+    }
+    bc
+}
+
+/// Применяет порчу к блокам после genesis; каждая ветка гарантированно
+/// что-то меняет.
+fn apply(bc: &mut Blockchain, mutation: &Mutation) {
+    let mined = bc.chain.len() - 1;
+    match *mutation {
+        Mutation::Amount { block, tx } => {
+            let block = &mut bc.chain[1 + block % mined];
+            let tx = tx % block.transactions.len();
+            block.transactions[tx].amount += coins(1.0);
+        }
+        Mutation::Swap { first, second } => {
+            let first = 1 + first % mined;
+            let second = 1 + (first + second % (mined - 1)) % mined;
+            bc.chain.swap(first, second);
+        }
+        Mutation::PrevHash { block, hash } => {
+            let block = &mut bc.chain[1 + block % mined];
+            let hash = Hash256(hash);
+            block.header.prev_hash = if hash == block.header.prev_hash {
+                Hash256::digest(&hash.0)
+            } else {
+                hash
+            };
+        }
+        Mutation::Nonce { block } => {
+            bc.chain[1 + block % mined].header.nonce += 1;
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn generated_chains_are_valid(plan in plan()) {
+        let bc = build(&plan);
+        prop_assert_eq!(bc.chain.len(), plan.len() + 1);
+        prop_assert!(bc.is_chain_valid());
+    }
+
+    #[test]
+    fn every_mutation_is_rejected(plan in plan(), mutation in mutation()) {
+        let mut bc = build(&plan);
+        apply(&mut bc, &mutation);
+        prop_assert!(!bc.is_chain_valid(), "{:?} was accepted", mutation);
+    }
+}
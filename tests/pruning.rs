@@ -0,0 +1,113 @@
+use blockchain_p2p::pruning::ARCHIVE_SEGMENT_SIZE;
+use blockchain_p2p::testutil::{coins, test_wallet, ChainBuilder};
+use blockchain_p2p::{BlockArchive, Blockchain, ChainError, ProofOfStake};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}_{}", name, std::process::id()))
+}
+
+/// Цепь из 230 блоков с переводами в начале и архив для неё.
+fn pruned_pair(name: &str) -> (Blockchain, Blockchain, PathBuf) {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let full = builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(12.0), coins(0.5))
+        .mine(&bob)
+        .transfer(&bob, &alice, coins(2.0), coins(0.1))
+        .mine_blocks(227, &alice)
+        .build();
+
+    let dir = temp_dir(name);
+    let mut pruned = full.clone();
+    pruned.set_archive(BlockArchive::open(&dir).unwrap());
+    assert_eq!(pruned.prune(50).unwrap(), ARCHIVE_SEGMENT_SIZE - 1);
+    (full, pruned, dir)
+}
+
+#[test]
+fn pruned_chain_keeps_state_and_serves_archived_blocks() {
+    let (full, pruned, dir) = pruned_pair("pruning_state");
+    let alice = test_wallet(1).get_address();
+    let bob = test_wallet(2).get_address();
+
+    assert_eq!(pruned.pruned_height(), ARCHIVE_SEGMENT_SIZE);
+    assert!(pruned.chain[1..100]
+        .iter()
+        .all(|b| b.transactions.is_empty()));
+    assert_eq!(pruned.chain[100], full.chain[100]);
+    assert!(pruned.is_chain_valid());
+
+    assert_eq!(pruned.get_balance(&alice), full.get_balance(&alice));
+    assert_eq!(pruned.get_balance(&bob), full.get_balance(&bob));
+    assert_eq!(pruned.next_nonce(&bob), 1);
+    assert!(pruned.nonce_used(&alice, 0));
+
+    assert_eq!(pruned.full_block(2).unwrap().as_ref(), Some(&full.chain[2]));
+    assert_eq!(pruned.full_blocks(0..full.chain.len()).unwrap(), full.chain);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn pruned_chain_is_restored_from_disk() {
+    let (full, pruned, dir) = pruned_pair("pruning_restore");
+    let chain_path = dir.join("chain.jsonl");
+    pruned.save_to_disk(&chain_path).unwrap();
+
+    let archive = BlockArchive::open(&dir).unwrap();
+    let restored = Blockchain::default()
+        .restore_pruned(&chain_path, archive)
+        .unwrap();
+    assert_eq!(restored.chain, pruned.chain);
+    assert_eq!(restored.pruned_height(), ARCHIVE_SEGMENT_SIZE);
+    assert_eq!(restored.snapshot(), full.snapshot());
+
+    // Испорченный архивный файл не выдаётся за блоки цепи
+    let segment = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().contains("blocks_"))
+        .unwrap();
+    fs::write(&segment, b"garbage").unwrap();
+    assert!(restored.full_block(5).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn reorg_below_the_pruned_height_is_refused() {
+    let (full, mut pruned, dir) = pruned_pair("pruning_reorg");
+
+    let mut fork = full.chain.clone();
+    fork[50].header.nonce += 1;
+    fork[50].hash = fork[50].header.compute_hash();
+    assert_eq!(
+        pruned.replace_chain(fork).unwrap_err(),
+        ChainError::BelowPruned {
+            fork_height: 49,
+            pruned_height: 100,
+        }
+    );
+
+    // Ветка выше границы принимается, а обрезанные блоки остаются обрезанными
+    let mut longer = full.clone();
+    longer.mine_block(&test_wallet(3).get_address()).unwrap();
+    pruned.replace_chain(longer.chain.clone()).unwrap();
+    assert_eq!(pruned.chain.len(), longer.chain.len());
+    assert!(pruned.chain[1].transactions.is_empty());
+    assert!(pruned.is_chain_valid());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn proof_of_stake_chains_are_not_pruned() {
+    let dir = temp_dir("pruning_pos");
+    let mut bc = ChainBuilder::new().build();
+    bc.set_archive(BlockArchive::open(&dir).unwrap());
+    bc.set_consensus(Arc::new(ProofOfStake::with_validator(test_wallet(1))));
+    assert!(bc.prune(0).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,47 @@
+use blockchain_p2p::RateLimiter;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+
+const PEER: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+const OTHER: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+#[test]
+fn burst_is_allowed_then_refilled_at_rate() {
+    let mut limiter = RateLimiter::new(2.0, 3.0);
+    let start = Instant::now();
+
+    for _ in 0..3 {
+        assert!(limiter.allow(PEER, start));
+    }
+    assert!(!limiter.allow(PEER, start));
+    // У другого адреса своя корзина
+    assert!(limiter.allow(OTHER, start));
+
+    // За полсекунды набегает одно сообщение
+    let later = start + Duration::from_millis(500);
+    assert!(limiter.allow(PEER, later));
+    assert!(!limiter.allow(PEER, later));
+
+    // Корзина не наполняется выше burst
+    let much_later = later + Duration::from_secs(60);
+    for _ in 0..3 {
+        assert!(limiter.allow(PEER, much_later));
+    }
+    assert!(!limiter.allow(PEER, much_later));
+}
+
+#[test]
+fn full_buckets_are_pruned() {
+    let mut limiter = RateLimiter::new(1.0, 2.0);
+    let start = Instant::now();
+    limiter.allow(PEER, start);
+    limiter.allow(PEER, start);
+    limiter.allow(PEER, start);
+
+    limiter.prune(start + Duration::from_secs(5));
+    // Забытый пир снова начинает с полной корзины
+    let now = start + Duration::from_secs(5);
+    assert!(limiter.allow(PEER, now));
+    assert!(limiter.allow(PEER, now));
+    assert!(!limiter.allow(PEER, now));
+}
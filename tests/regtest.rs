@@ -0,0 +1,107 @@
+use blockchain_p2p::genesis::REGTEST_BITS;
+use blockchain_p2p::rpc::handle_request;
+use blockchain_p2p::testutil::ChainBuilder;
+use blockchain_p2p::{
+    Blockchain, ChainError, ChainMode, Config, ConfigError, GenesisConfig, GenesisError, Node,
+    ValidationError, Wallet,
+};
+use serde_json::json;
+use std::path::PathBuf;
+
+#[test]
+fn regtest_keeps_its_target_and_ignores_block_times() {
+    for (genesis, valid) in [
+        (GenesisConfig::regtest(), true),
+        (GenesisConfig::default(), false),
+    ] {
+        let builder = ChainBuilder::with_genesis(&genesis);
+        let miner = builder.wallet(1);
+        let clock = builder.clock().clone();
+
+        // Блок из будущего, а затем часы узла отстают от него на сутки
+        clock.advance(24 * 60 * 60);
+        let mut bc = builder.mine_blocks(25, &miner).build();
+        clock.set(genesis.timestamp);
+
+        assert_eq!(bc.is_chain_valid(), valid);
+        if valid {
+            assert_eq!(bc.mode(), ChainMode::Regtest);
+            assert_eq!(bc.bits, REGTEST_BITS);
+            bc.mine_block(&miner.get_address()).unwrap();
+        } else {
+            assert!(matches!(
+                bc.verify(),
+                Err(ChainError::InvalidBlock {
+                    index: 1,
+                    reason: ValidationError::FutureTimestamp { .. },
+                })
+            ));
+        }
+    }
+}
+
+#[test]
+fn only_regtest_genesis_may_be_easier_than_max_target() {
+    assert!(GenesisConfig::regtest().validate().is_ok());
+    let easy = GenesisConfig {
+        bits: REGTEST_BITS,
+        ..GenesisConfig::default()
+    };
+    assert!(matches!(
+        easy.validate(),
+        Err(GenesisError::InvalidBits(REGTEST_BITS))
+    ));
+}
+
+#[test]
+fn generate_mines_blocks_only_in_regtest() {
+    let node = Node::new("Regtest".to_string());
+    let address = Wallet::new().get_address();
+    let body = json!({ "blocks": 5, "address": address }).to_string();
+    assert_eq!(
+        handle_request(&node, "POST", "/generate", &body).status,
+        403
+    );
+
+    *node.blockchain.blocking_write() = Blockchain::new(&GenesisConfig::regtest());
+    let response = handle_request(&node, "POST", "/generate", &body);
+    assert_eq!(response.status, 200);
+    let hashes = response.body["blocks"].as_array().unwrap();
+    assert_eq!(hashes.len(), 5);
+
+    let bc = node.blockchain.blocking_read();
+    assert_eq!(bc.chain.len(), 6);
+    assert_eq!(hashes[4], json!(bc.chain[5].hash));
+    assert_eq!(
+        bc.get_balance(&address),
+        bc.mining_reward.checked_mul(5).unwrap()
+    );
+    drop(bc);
+
+    let too_many = json!({ "blocks": 1_000_000, "address": address }).to_string();
+    assert_eq!(
+        handle_request(&node, "POST", "/generate", &too_many).status,
+        400
+    );
+}
+
+#[test]
+fn config_starts_a_regtest_network_without_genesis() {
+    let config = Config {
+        data_dir: std::env::temp_dir().join(format!("regtest_config_{}", std::process::id())),
+        regtest: true,
+        ..Config::default()
+    };
+    let node = Node::from_config(&config).unwrap();
+    assert_eq!(node.blockchain.blocking_read().chain_id(), "regtest");
+    std::fs::remove_dir_all(&config.data_dir).unwrap();
+
+    let with_genesis = Config {
+        genesis: Some(PathBuf::from("genesis.toml")),
+        ..config
+    };
+    assert!(matches!(
+        with_genesis.validate(),
+        Err(ConfigError::InvalidValue { ref key, .. }) if key == "regtest"
+    ));
+}
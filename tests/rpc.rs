@@ -0,0 +1,177 @@
+mod common;
+
+use blockchain_p2p::rpc::{handle_request, start_rpc_server};
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Hash256, Misbehavior, Node, Wallet};
+use common::{fund, signed_transaction};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn balance_and_blocks_are_served() {
+    let node = Node::new("Rpc".to_string());
+    let wallet = Wallet::new();
+    fund(&mut node.blockchain.blocking_write(), &wallet);
+
+    let url = format!("/balance/{}", wallet.get_address());
+    let response = handle_request(&node, "GET", &url, "");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body["balance"], "50");
+    assert_eq!(response.body["next_nonce"], 0);
+
+    let block = node.blockchain.blocking_read().chain[1].clone();
+    let by_index = handle_request(&node, "GET", "/blocks/1", "");
+    let by_hash = handle_request(&node, "GET", &format!("/blocks/hash/{}", block.hash), "");
+    assert_eq!(by_index.body, serde_json::to_value(&block).unwrap());
+    assert_eq!(by_hash.body, by_index.body);
+
+    assert_eq!(handle_request(&node, "GET", "/blocks/9", "").status, 404);
+    assert_eq!(
+        handle_request(&node, "GET", "/balance/bad!", "").status,
+        400
+    );
+}
+
+#[test]
+fn blocks_and_address_history_are_paged() {
+    let node = Node::new("Rpc".to_string());
+    let wallet = Wallet::new();
+    for _ in 0..5 {
+        fund(&mut node.blockchain.blocking_write(), &wallet);
+    }
+
+    let first = handle_request(&node, "GET", "/blocks?limit=4", "");
+    assert_eq!(first.status, 200);
+    assert_eq!(first.body["blocks"].as_array().unwrap().len(), 4);
+    assert_eq!(first.body["total"], 6);
+    assert_eq!(first.body["next"], 4);
+    let last = handle_request(&node, "GET", "/blocks?from=4&limit=4", "");
+    assert_eq!(last.body["blocks"][0]["header"]["index"], 4);
+    assert_eq!(last.body["blocks"].as_array().unwrap().len(), 2);
+    assert!(last.body["next"].is_null());
+    assert_eq!(
+        handle_request(&node, "GET", "/blocks?from=x", "").status,
+        400
+    );
+
+    let url = format!("/address/{}/txs?page=1&limit=2", wallet.get_address());
+    let page = handle_request(&node, "GET", &url, "");
+    assert_eq!(page.status, 200);
+    assert_eq!(page.body["total"], 5);
+    let transactions = page.body["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[0]["height"], 3);
+
+    let history = handle_request(
+        &node,
+        "GET",
+        &format!("/history/{}", wallet.get_address()),
+        "",
+    );
+    assert_eq!(transactions[0], history.body[2]);
+    assert_eq!(
+        handle_request(&node, "GET", "/address/bad!/txs", "").status,
+        400
+    );
+}
+
+#[test]
+fn submitted_transaction_reaches_mempool() {
+    let node = Node::new("Rpc".to_string());
+    let sender = Wallet::new();
+    fund(&mut node.blockchain.blocking_write(), &sender);
+
+    let tx = signed_transaction(&sender, &Wallet::new(), coins(3.0), coins(0.1));
+    let body = serde_json::to_string(&tx).unwrap();
+    let response = handle_request(&node, "POST", "/transactions", &body);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body["txid"], tx.id().to_string());
+
+    let mempool = handle_request(&node, "GET", "/mempool", "");
+    assert_eq!(mempool.body.as_array().unwrap().len(), 1);
+
+    let url = format!("/transactions/{}/status", tx.id());
+    let status = handle_request(&node, "GET", &url, "");
+    assert_eq!(status.body, serde_json::json!({ "status": "pending" }));
+    node.blockchain
+        .blocking_write()
+        .mine_block(&sender.get_address())
+        .unwrap();
+    let status = handle_request(&node, "GET", &url, "");
+    assert_eq!(status.body["status"], "confirmed");
+    assert_eq!(status.body["confirmations"], 1);
+    let unknown = format!("/transactions/{}/status", Hash256::ZERO);
+    assert_eq!(
+        handle_request(&node, "GET", &unknown, "").body["status"],
+        "unknown"
+    );
+
+    let duplicate = handle_request(&node, "POST", "/transactions", &body);
+    assert_eq!(duplicate.status, 422);
+}
+
+#[test]
+fn events_are_long_polled() {
+    let node = Node::new("Rpc".to_string());
+    assert_eq!(
+        handle_request(&node, "GET", "/events?wait=0", "").body,
+        serde_json::json!([])
+    );
+    assert_eq!(
+        handle_request(&node, "GET", "/events?wait=soon", "").status,
+        400
+    );
+
+    let sender = Wallet::new();
+    fund(&mut node.blockchain.blocking_write(), &sender);
+    let tx = signed_transaction(&sender, &Wallet::new(), coins(1.0), coins(0.1));
+    let submitter = node.clone_node();
+    let body = serde_json::to_string(&tx).unwrap();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        handle_request(&submitter, "POST", "/transactions", &body);
+    });
+
+    let response = handle_request(&node, "GET", "/events?wait=5", "");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body[0]["type"], "TRANSACTION_ACCEPTED");
+    assert_eq!(response.body[0]["txid"], tx.id().to_string());
+}
+
+#[test]
+fn banned_peers_are_listed() {
+    let node = Node::new("Rpc".to_string());
+    assert_eq!(
+        handle_request(&node, "GET", "/bans", "").body,
+        serde_json::json!([])
+    );
+
+    node.misbehaving("10.0.0.66".parse().unwrap(), Misbehavior::InvalidBlock);
+    let bans = handle_request(&node, "GET", "/bans", "");
+    assert_eq!(bans.status, 200);
+    assert_eq!(bans.body[0]["ip"], "10.0.0.66");
+    assert!(bans.body[0]["banned_until"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn rpc_server_answers_over_http() {
+    let node = Node::new("Rpc".to_string());
+    let server = node.clone_node();
+    thread::spawn(move || start_rpc_server(&server, 38111));
+    thread::sleep(Duration::from_millis(300));
+
+    let mut stream = TcpStream::connect("127.0.0.1:38111").unwrap();
+    stream
+        .write_all(b"GET /info HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).unwrap();
+
+    assert!(reply.starts_with("HTTP/1.1 200"));
+    let body = reply.split("\r\n\r\n").nth(1).unwrap();
+    let info: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(info["id"], "Rpc");
+    assert_eq!(info["blocks"], 1);
+}
@@ -0,0 +1,282 @@
+use blockchain_p2p::script::{self, Op, ScriptContext};
+use blockchain_p2p::testutil::{coins, ChainBuilder};
+use blockchain_p2p::utxo::{OutPoint, TxInput, TxOutput, UtxoSet, UtxoTransaction};
+use blockchain_p2p::{
+    Address, LockTime, Script, ScriptError, Signature, Transaction, TxValidationError, UtxoError,
+    Wallet,
+};
+
+/// Набор, где единственный выход (50 монет) заперт скриптом `lock`.
+fn locked(lock: Script, address: Address) -> (UtxoSet, OutPoint) {
+    let mut reward = UtxoTransaction::coinbase(address, coins(50.0), 0);
+    reward.outputs[0].script = Some(lock);
+    let outpoint = OutPoint {
+        txid: reward.id(),
+        vout: 0,
+    };
+    let mut utxos = UtxoSet::new();
+    utxos.apply_block(&[reward], coins(50.0)).unwrap();
+    (utxos, outpoint)
+}
+
+fn spend(outpoint: OutPoint, to: &Wallet) -> UtxoTransaction {
+    UtxoTransaction {
+        inputs: vec![TxInput {
+            outpoint,
+            signature: Signature::EMPTY,
+            public_key: String::new(),
+            script_sig: Script::default(),
+        }],
+        outputs: vec![TxOutput {
+            amount: coins(49.0),
+            address: to.get_address(),
+            script: None,
+        }],
+        lock_height: 0,
+    }
+}
+
+fn pay_to(wallet: &Wallet) -> Script {
+    Script::p2pkh(&wallet.get_address()).unwrap()
+}
+
+#[test]
+fn p2pkh_output_is_spent_by_its_owner_only() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let (utxos, _) = locked(pay_to(&alice), alice.get_address());
+
+    let tx = alice
+        .create_utxo_transaction(&utxos, &bob.get_address(), coins(20.0), coins(1.0))
+        .unwrap();
+    assert!(!tx.inputs[0].script_sig.0.is_empty());
+    assert_eq!(utxos.validate_transaction(&tx), Ok(coins(1.0)));
+
+    // Чужой ключ не совпадает с хешем в скрипте
+    let mut forged = tx.clone();
+    let signature = bob.sign_transaction(&forged.signing_payload());
+    forged.inputs[0].script_sig =
+        Script::unlock([signature.0.to_vec(), hex::decode(&bob.public_key).unwrap()]);
+    assert_eq!(
+        utxos.validate_transaction(&forged),
+        Err(UtxoError::ScriptFailed {
+            index: 0,
+            reason: ScriptError::VerifyFailed
+        })
+    );
+}
+
+#[test]
+fn script_multisig_needs_threshold_signatures_in_key_order() {
+    let wallets: Vec<Wallet> = (0..3).map(|_| Wallet::new()).collect();
+    let keys: Vec<String> = wallets.iter().map(|w| w.public_key.clone()).collect();
+    let lock = Script::multisig(2, &keys).unwrap();
+    let (utxos, outpoint) = locked(lock.clone(), lock.address());
+    assert!(lock.address().is_script());
+
+    let mut tx = spend(outpoint, &wallets[0]);
+    let payload = tx.signing_payload();
+    let sig = |i: usize| wallets[i].sign_transaction(&payload).0.to_vec();
+
+    tx.inputs[0].script_sig = Script::unlock([sig(0), sig(2)]);
+    assert_eq!(utxos.validate_transaction(&tx), Ok(coins(1.0)));
+
+    // Порядок подписей должен совпадать с порядком ключей
+    tx.inputs[0].script_sig = Script::unlock([sig(2), sig(0)]);
+    assert!(matches!(
+        utxos.validate_transaction(&tx),
+        Err(UtxoError::ScriptFailed {
+            reason: ScriptError::FalseResult,
+            ..
+        })
+    ));
+
+    tx.inputs[0].script_sig = Script::unlock([sig(1)]);
+    assert!(matches!(
+        utxos.validate_transaction(&tx),
+        Err(UtxoError::ScriptFailed { .. })
+    ));
+}
+
+#[test]
+fn timelocked_output_is_spendable_from_its_height() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let lock = Script::timelock(3, pay_to(&alice));
+    let (mut utxos, _) = locked(lock, alice.get_address());
+
+    let tx = alice
+        .create_utxo_transaction(&utxos, &bob.get_address(), coins(10.0), coins(0.0))
+        .unwrap();
+    assert_eq!(
+        utxos.validate_transaction(&tx),
+        Err(UtxoError::ScriptFailed {
+            index: 0,
+            reason: ScriptError::LockTimeNotReached { lock: 3, height: 1 }
+        })
+    );
+
+    for height in 1..3 {
+        let reward = UtxoTransaction::coinbase(bob.get_address(), coins(50.0), height);
+        utxos.apply_block(&[reward], coins(50.0)).unwrap();
+    }
+    assert_eq!(utxos.height(), 3);
+    assert_eq!(utxos.validate_transaction(&tx), Ok(coins(0.0)));
+}
+
+#[test]
+fn execution_is_bounded_and_unlock_is_data_only() {
+    let context = ScriptContext {
+        payload: "payload",
+        height: 0,
+    };
+    let always = Script(vec![Op::num(1)]);
+    assert!(script::verify(&Script::default(), &always, context).is_ok());
+
+    let burned = Script(vec![Op::Return]);
+    assert_eq!(
+        script::verify(&Script::default(), &burned, context),
+        Err(ScriptError::OpReturn)
+    );
+
+    let greedy = Script(vec![Op::CheckSig; 45]);
+    let unlock = Script::unlock(vec![vec![0]; 60]);
+    assert_eq!(
+        script::verify(&unlock, &greedy, context),
+        Err(ScriptError::OutOfGas)
+    );
+
+    let sneaky = Script(vec![Op::Dup]);
+    assert_eq!(
+        script::verify(&sneaky, &always, context),
+        Err(ScriptError::NonPushUnlock)
+    );
+}
+
+#[test]
+fn scripts_round_trip_through_text_and_json() {
+    let alice = Wallet::new();
+    let lock = Script::timelock(500, pay_to(&alice));
+    let text = lock.to_string();
+    assert!(text.starts_with("f401 OP_CHECKLOCKTIMEVERIFY OP_DROP OP_DUP OP_HASH160 "));
+    assert_eq!(text.parse::<Script>().unwrap(), lock);
+
+    let json = serde_json::to_string(&lock).unwrap();
+    assert_eq!(serde_json::from_str::<Script>(&json).unwrap(), lock);
+
+    assert_eq!(
+        "OP_NOPE".parse::<Script>(),
+        Err(ScriptError::UnknownOpcode("OP_NOPE".to_string()))
+    );
+}
+
+/// Цепь, где `alice` добыла блок и перевела 10 монет на адрес `lock`.
+fn funded_script_address(alice: &Wallet, lock: &Script) -> ChainBuilder {
+    let builder = ChainBuilder::new().mine(alice);
+    let nonce = builder.chain().next_nonce(&alice.get_address());
+    let funding = alice.create_transaction(&lock.address(), coins(10.0), coins(0.0), nonce);
+    builder.submit(funding).mine(alice)
+}
+
+/// Перевод 4 монет с адреса `lock` на `to`, отпертый данными `unlock`.
+fn spend_script(
+    lock: &Script,
+    to: &Wallet,
+    unlock: impl Fn(&Transaction) -> Script,
+) -> Transaction {
+    let mut tx = Transaction::new_script(lock, &to.get_address(), coins(4.0), coins(0.1), 0);
+    tx.script_sig = unlock(&tx);
+    tx
+}
+
+fn p2pkh_unlock(tx: &Transaction, owner: &Wallet) -> Script {
+    Script::unlock([
+        tx.script_signature(owner),
+        hex::decode(&owner.public_key).unwrap(),
+    ])
+}
+
+#[test]
+fn script_address_is_spent_on_chain_by_its_program() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let carol = Wallet::new();
+    let lock = pay_to(&bob);
+    let builder = funded_script_address(&alice, &lock);
+    assert_eq!(builder.chain().get_balance(&lock.address()), coins(10.0));
+
+    // Подпись не того ключа скрипт не принимает
+    let forged = spend_script(&lock, &carol, |tx| p2pkh_unlock(tx, &alice));
+    assert_eq!(
+        forged.validate(),
+        Err(TxValidationError::Script(ScriptError::VerifyFailed))
+    );
+    // Скрипт должен совпадать с адресом отправителя
+    let mut other_lock = spend_script(&lock, &carol, |tx| p2pkh_unlock(tx, &bob));
+    other_lock.lock_script = Some(pay_to(&carol));
+    assert_eq!(
+        other_lock.validate(),
+        Err(TxValidationError::PublicKeyMismatch)
+    );
+
+    let tx = spend_script(&lock, &carol, |tx| p2pkh_unlock(tx, &bob));
+    assert_eq!(tx.validate(), Ok(()));
+    let bc = builder.submit(tx).mine(&alice).build();
+    assert_eq!(bc.get_balance(&lock.address()), coins(5.9));
+    assert_eq!(bc.get_balance(&carol.get_address()), coins(4.0));
+    assert!(bc.is_chain_valid());
+}
+
+#[test]
+fn multisig_script_address_needs_enough_signatures() {
+    let alice = Wallet::new();
+    let keys = [Wallet::new(), Wallet::new(), Wallet::new()];
+    let public_keys: Vec<String> = keys.iter().map(|key| key.public_key.clone()).collect();
+    let lock = Script::multisig(2, &public_keys).unwrap();
+    let builder = funded_script_address(&alice, &lock);
+
+    let one = spend_script(&lock, &alice, |tx| {
+        Script::unlock([tx.script_signature(&keys[0]), tx.script_signature(&keys[0])])
+    });
+    assert_eq!(
+        one.validate(),
+        Err(TxValidationError::Script(ScriptError::FalseResult))
+    );
+
+    let two = spend_script(&lock, &alice, |tx| {
+        Script::unlock([tx.script_signature(&keys[0]), tx.script_signature(&keys[2])])
+    });
+    let bc = builder.submit(two).mine(&alice).build();
+    assert_eq!(bc.get_balance(&lock.address()), coins(5.9));
+}
+
+#[test]
+fn timelocked_script_address_is_spent_from_its_height() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let lock = Script::timelock(4, pay_to(&bob));
+    let builder = funded_script_address(&alice, &lock);
+
+    // Без `lock_time` не ниже высоты скрипта трата не проходит
+    let early = spend_script(&lock, &alice, |tx| p2pkh_unlock(tx, &bob));
+    assert_eq!(
+        early.validate(),
+        Err(TxValidationError::Script(ScriptError::LockTimeNotReached {
+            lock: 4,
+            height: 0
+        }))
+    );
+
+    let mut tx = Transaction::new_script(&lock, &alice.get_address(), coins(4.0), coins(0.1), 0);
+    tx.lock_time = Some(LockTime::Height(4));
+    tx.script_sig = p2pkh_unlock(&tx, &bob);
+    assert_eq!(tx.validate(), Ok(()));
+
+    // Перевод ждёт в mempool, пока цепь не дорастёт до высоты 4
+    let builder = builder.submit(tx).mine(&alice);
+    assert_eq!(builder.chain().get_balance(&lock.address()), coins(10.0));
+    let bc = builder.mine(&alice).build();
+    assert_eq!(bc.height(), 5);
+    assert_eq!(bc.get_balance(&lock.address()), coins(5.9));
+    assert!(bc.is_chain_valid());
+}
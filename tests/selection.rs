@@ -0,0 +1,136 @@
+use blockchain_p2p::genesis::DEFAULT_CHAIN_ID;
+use blockchain_p2p::selection::{FeePriority, GreedyKnapsack, OldestFirst, SenderFairness};
+use blockchain_p2p::testutil::{coins, test_wallet, ChainBuilder};
+use blockchain_p2p::{Amount, SelectionStrategy, Transaction, TxSelection, Wallet};
+use std::sync::Arc;
+
+fn transfer(from: &Wallet, fee: f64, nonce: u64) -> Transaction {
+    from.create_transaction(
+        &test_wallet(99).get_address(),
+        coins(1.0),
+        coins(fee),
+        nonce,
+    )
+}
+
+fn fees(candidates: &[Transaction], selected: &[usize]) -> Vec<f64> {
+    selected
+        .iter()
+        .map(|&i| candidates[i].fee.to_f64())
+        .collect()
+}
+
+#[test]
+fn fee_priority_and_oldest_first_order_candidates() {
+    let sender = test_wallet(1);
+    let mut candidates = vec![
+        transfer(&sender, 0.1, 0),
+        transfer(&sender, 0.3, 1),
+        transfer(&sender, 0.2, 2),
+    ];
+    for (i, tx) in candidates.iter_mut().enumerate() {
+        tx.timestamp = 100 + i as u64;
+    }
+
+    let by_fee = FeePriority.select(&candidates, 2, usize::MAX);
+    assert_eq!(fees(&candidates, &by_fee), vec![0.3, 0.2]);
+    let by_age = OldestFirst.select(&candidates, 2, usize::MAX);
+    assert_eq!(fees(&candidates, &by_age), vec![0.1, 0.3]);
+}
+
+#[test]
+fn sender_fairness_gives_every_sender_a_slot() {
+    let whale = test_wallet(1);
+    let small = test_wallet(2);
+    let candidates = vec![
+        transfer(&whale, 1.0, 0),
+        transfer(&whale, 0.9, 1),
+        transfer(&whale, 0.8, 2),
+        transfer(&small, 0.1, 0),
+    ];
+
+    let by_fee = FeePriority.select(&candidates, 2, usize::MAX);
+    assert_eq!(fees(&candidates, &by_fee), vec![1.0, 0.9]);
+
+    let fair = SenderFairness.select(&candidates, 2, usize::MAX);
+    assert_eq!(fees(&candidates, &fair), vec![1.0, 0.1]);
+    // Второй раунд — снова от кита, по возрастанию nonce
+    let fair = SenderFairness.select(&candidates, 3, usize::MAX);
+    assert_eq!(fees(&candidates, &fair), vec![1.0, 0.1, 0.9]);
+}
+
+#[test]
+fn knapsack_prefers_fee_per_byte_under_size_limit() {
+    let sender = test_wallet(1);
+    let small = [transfer(&sender, 0.6, 0), transfer(&sender, 0.6, 1)];
+    let data = vec![0u8; small[0].serialized_size() / 2];
+    let large = sender.create_transaction_with_data(
+        DEFAULT_CHAIN_ID,
+        &test_wallet(99).get_address(),
+        coins(1.0),
+        coins(1.0),
+        2,
+        data,
+    );
+    let max_bytes = small[0].serialized_size() + small[1].serialized_size();
+    assert!(large.serialized_size() <= max_bytes);
+    let candidates = vec![large, small[0].clone(), small[1].clone()];
+
+    // Большая комиссия занимает блок целиком, а две мелких приносят больше
+    let by_fee = FeePriority.select(&candidates, 10, max_bytes);
+    assert_eq!(fees(&candidates, &by_fee), vec![1.0]);
+    let knapsack = GreedyKnapsack.select(&candidates, 10, max_bytes);
+    assert_eq!(fees(&candidates, &knapsack), vec![0.6, 0.6]);
+}
+
+/// Майнер, который не берёт транзакций дешевле `min_fee`.
+struct MinimumFee(Amount);
+
+impl TxSelection for MinimumFee {
+    fn name(&self) -> &'static str {
+        "minimum-fee"
+    }
+
+    fn select(
+        &self,
+        candidates: &[Transaction],
+        max_count: usize,
+        _max_bytes: usize,
+    ) -> Vec<usize> {
+        (0..candidates.len())
+            .filter(|&i| candidates[i].fee >= self.0)
+            .take(max_count)
+            .collect()
+    }
+}
+
+#[test]
+fn chain_mines_with_custom_selection() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let mut bc = builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(1.0), coins(0.01))
+        .transfer(&alice, &bob, coins(1.0), coins(0.5))
+        .build();
+    bc.set_tx_selection(Arc::new(MinimumFee(coins(0.1))));
+    assert_eq!(bc.tx_selection().name(), "minimum-fee");
+
+    bc.mine_block(&bob.get_address()).unwrap();
+    let block = bc.latest_block();
+    assert_eq!(block.transactions.len(), 2);
+    assert_eq!(block.transactions[1].fee, coins(0.5));
+    assert_eq!(bc.mempool.size(), 1);
+    assert_eq!(bc.mempool.transactions[0].fee, coins(0.01));
+}
+
+#[test]
+fn strategies_parse_from_their_names() {
+    for strategy in SelectionStrategy::ALL {
+        assert_eq!(strategy.to_string().parse(), Ok(strategy));
+        assert_eq!(strategy.selection().name(), strategy.to_string());
+    }
+    assert_eq!("knapsack".parse(), Ok(SelectionStrategy::Knapsack));
+    assert!("lifo".parse::<SelectionStrategy>().is_err());
+}
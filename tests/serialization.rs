@@ -0,0 +1,72 @@
+mod common;
+
+use blockchain_p2p::protocol::{write_message, MessageCodec};
+use blockchain_p2p::{Block, Blockchain, Message};
+use bytes::BytesMut;
+use common::mined_chain;
+use tokio_util::codec::{Decoder, Encoder};
+
+#[test]
+fn block_json_round_trip() {
+    let bc = mined_chain();
+    let block = &bc.chain[1];
+
+    let decoded = Block::from_json(&block.to_json()).unwrap();
+    assert_eq!(&decoded, block);
+    assert!(decoded.is_valid(&bc.chain[..1]));
+}
+
+#[test]
+fn block_bincode_round_trip() {
+    let bc = mined_chain();
+    let block = &bc.chain[1];
+
+    let decoded = Block::from_bytes(&block.to_bytes()).unwrap();
+    assert_eq!(&decoded, block);
+}
+
+#[test]
+fn blockchain_json_round_trip() {
+    let bc = mined_chain();
+
+    let json = serde_json::to_string(&bc).unwrap();
+    let decoded: Blockchain = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(decoded.chain, bc.chain);
+    assert_eq!(decoded.bits, bc.bits);
+    assert!(decoded.is_chain_valid());
+}
+
+#[test]
+fn blockchain_bincode_round_trip() {
+    let bc = mined_chain();
+
+    let bytes = bincode::serialize(&bc).unwrap();
+    let decoded: Blockchain = bincode::deserialize(&bytes).unwrap();
+
+    assert_eq!(decoded.chain, bc.chain);
+    assert!(decoded.is_chain_valid());
+}
+
+#[test]
+fn corrupted_json_is_rejected() {
+    assert!(Block::from_json("{\"index\": 1}").is_err());
+}
+
+#[test]
+fn message_codec_matches_blocking_framing() {
+    let message = Message::NewBlock {
+        block: mined_chain().chain[1].clone(),
+    };
+
+    let mut blocking = Vec::new();
+    write_message(&mut blocking, &message).unwrap();
+    let mut framed = BytesMut::new();
+    MessageCodec::new()
+        .encode(message.clone(), &mut framed)
+        .unwrap();
+    assert_eq!(&framed[..], &blocking[..]);
+
+    let decoded = MessageCodec::new().decode(&mut framed).unwrap();
+    assert_eq!(decoded, Some(message));
+}
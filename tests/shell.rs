@@ -0,0 +1,118 @@
+use blockchain_p2p::shell::{Shell, ShellCommand, DEFAULT_SHELL_FEE};
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Node, ShellError, Wallet};
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[test]
+fn commands_are_parsed() {
+    let to = Wallet::new().get_address();
+    assert_eq!(
+        "balance".parse::<ShellCommand>().unwrap(),
+        ShellCommand::Balance(None)
+    );
+    assert_eq!(
+        format!("send {} 2.5", to).parse::<ShellCommand>().unwrap(),
+        ShellCommand::Send {
+            to: to.clone(),
+            amount: coins(2.5),
+            fee: DEFAULT_SHELL_FEE,
+        }
+    );
+    assert_eq!(
+        format!("  send {} 2.5 0.3 ", to)
+            .parse::<ShellCommand>()
+            .unwrap(),
+        ShellCommand::Send {
+            to,
+            amount: coins(2.5),
+            fee: coins(0.3),
+        }
+    );
+    assert_eq!(
+        "mine".parse::<ShellCommand>().unwrap(),
+        ShellCommand::Mine(1)
+    );
+    assert_eq!(
+        "mine 3".parse::<ShellCommand>().unwrap(),
+        ShellCommand::Mine(3)
+    );
+    assert_eq!("chain".parse::<ShellCommand>().unwrap(), ShellCommand::Info);
+    assert_eq!("exit".parse::<ShellCommand>().unwrap(), ShellCommand::Quit);
+
+    assert!(matches!(
+        "mine 0".parse::<ShellCommand>(),
+        Err(ShellError::Usage(_))
+    ));
+    assert!(matches!(
+        "send somebody".parse::<ShellCommand>(),
+        Err(ShellError::Usage(_))
+    ));
+    assert!(matches!(
+        "balance bad!".parse::<ShellCommand>(),
+        Err(ShellError::InvalidAddress(_))
+    ));
+    assert!(matches!(
+        "dance".parse::<ShellCommand>(),
+        Err(ShellError::UnknownCommand(_))
+    ));
+}
+
+#[test]
+fn shell_mines_sends_and_reports_its_transactions() {
+    let node = Node::new("Shell".to_string());
+    let shell = Shell::new(node.clone_node(), Wallet::new());
+    let recipient = Wallet::new().get_address();
+
+    shell.execute(&ShellCommand::Mine(1)).unwrap();
+    let balance = shell.execute(&ShellCommand::Balance(None)).unwrap();
+    assert_eq!(balance, format!("{} -> 50", shell.address()));
+
+    let send = ShellCommand::Send {
+        to: recipient.clone(),
+        amount: coins(5.0),
+        fee: coins(0.5),
+    };
+    let reply = shell.execute(&send).unwrap();
+    let txid = node.blockchain.blocking_read().mempool.transactions[0].id();
+    assert!(reply.contains(&txid.to_string()));
+    assert!(shell
+        .execute(&ShellCommand::Mempool)
+        .unwrap()
+        .contains(&txid.to_string()));
+
+    shell.execute(&ShellCommand::Mine(1)).unwrap();
+    let block = node.blockchain.blocking_read().latest_block().clone();
+    let notices = shell.notices(&block);
+    assert_eq!(notices.len(), 2);
+    assert!(notices
+        .iter()
+        .any(|notice| notice.contains("mining reward")));
+    assert!(notices
+        .iter()
+        .any(|notice| notice.contains(&format!("sent 5 to {}", recipient))));
+    assert_eq!(
+        shell.execute(&ShellCommand::Mempool).unwrap(),
+        "Mempool is empty"
+    );
+
+    // Без денег на счёте перевод отклоняется
+    let broke = Shell::new(node.clone_node(), Wallet::new());
+    assert!(matches!(broke.execute(&send), Err(ShellError::Rejected(_))));
+}
+
+#[test]
+fn new_blocks_with_own_transactions_are_announced() {
+    let node = Node::new("Shell".to_string());
+    let shell = Shell::new(node.clone_node(), Wallet::new());
+    let (sender, notices) = mpsc::channel();
+    shell.watch_blocks(move |notice| sender.send(notice).unwrap());
+
+    // Чужой блок молча пропускается
+    node.generate(1, &Wallet::new().get_address()).unwrap();
+    node.generate(1, &shell.address()).unwrap();
+    let notice = notices.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(notice, "🔔 Block #2: mining reward 50");
+    assert!(notices.recv_timeout(Duration::from_millis(200)).is_err());
+    node.shutdown().unwrap();
+}
@@ -0,0 +1,87 @@
+use blockchain_p2p::testutil::{coins, test_wallet, ChainBuilder};
+use blockchain_p2p::{
+    Hash256, MultisigPolicy, ProofOfStake, Signature, Signer, Transaction, Wallet,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Подписант «на другой стороне»: ключ недоступен вызывающему коду,
+/// а каждая подпись учитывается.
+struct RemoteSigner {
+    key: Wallet,
+    requests: Arc<AtomicUsize>,
+}
+
+impl RemoteSigner {
+    fn new(seed: u64) -> (Self, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let signer = RemoteSigner {
+            key: test_wallet(seed),
+            requests: Arc::clone(&requests),
+        };
+        (signer, requests)
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn public_key(&self) -> String {
+        self.key.public_key.clone()
+    }
+
+    fn sign(&self, message_hash: &Hash256) -> Signature {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        self.key.sign(message_hash)
+    }
+}
+
+#[test]
+fn wallet_signatures_match_the_signer_trait() {
+    let wallet = test_wallet(1);
+    let tx = wallet.create_transaction(&test_wallet(2).get_address(), coins(1.0), coins(0.1), 0);
+    assert_eq!(tx.signature, wallet.sign_payload(&tx.signing_payload()));
+    assert_eq!(Signer::address(&wallet), wallet.get_address());
+}
+
+#[test]
+fn transactions_are_signed_by_any_signer() {
+    let (signer, requests) = RemoteSigner::new(1);
+    let builder = ChainBuilder::new();
+    let bob = builder.wallet(2);
+    let mut bc = builder.mine(&test_wallet(1)).build();
+
+    let mut tx = Transaction::new(
+        signer.address(),
+        bob.get_address(),
+        coins(5.0),
+        coins(0.1),
+        0,
+        String::new(),
+    );
+    tx.sign_with(&signer);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    bc.add_transaction(tx).unwrap();
+
+    let (cosigner, _) = RemoteSigner::new(3);
+    let policy =
+        MultisigPolicy::new(1, vec![cosigner.public_key(), bob.public_key.clone()]).unwrap();
+    let mut spend =
+        Transaction::new_multisig(&policy, &bob.get_address(), coins(1.0), coins(0.1), 0);
+    spend.co_sign_with(&cosigner).unwrap();
+    assert!(spend.verify_signature().is_ok());
+}
+
+#[test]
+fn blocks_are_signed_by_any_signer() {
+    let (signer, requests) = RemoteSigner::new(1);
+    let address = signer.address();
+    let mut bc = ChainBuilder::new().build();
+    bc.sign_blocks_with(signer);
+
+    let block = bc.mine_block(&address).unwrap();
+    assert_eq!(block.verify_producer(), Ok(address.clone()));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    let (validator, _) = RemoteSigner::new(1);
+    let pos = ProofOfStake::with_validator(validator);
+    assert_eq!(pos.validator_address(), Some(address));
+}
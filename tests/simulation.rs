@@ -0,0 +1,132 @@
+use blockchain_p2p::simulation::Simulation;
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{GenesisConfig, Wallet};
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn height(sim: &Simulation, index: usize) -> usize {
+    sim.node(index).blockchain.blocking_read().chain.len()
+}
+
+#[test]
+fn blocks_and_transactions_reach_every_node() {
+    let sim = Simulation::with_genesis(3, &GenesisConfig::regtest());
+    sim.connect_all();
+    assert!(sim.nodes().iter().all(|node| node.peer_addrs().len() == 2));
+
+    // По блоку за раз, чтобы никто не получил сироту и не ушёл в
+    // загрузку, во время которой транзакции не принимаются
+    let sender = Wallet::new();
+    for expected in 2..=3 {
+        sim.node(0).generate(1, &sender.get_address()).unwrap();
+        assert!(sim.wait_until(TIMEOUT, |sim| sim.converged() && height(sim, 2) == expected));
+    }
+
+    let tx = {
+        let mut bc = sim.node(1).blockchain.blocking_write();
+        let tx = sender.create_transaction_for_chain(
+            bc.chain_id(),
+            &Wallet::new().get_address(),
+            coins(5.0),
+            coins(0.1),
+            0,
+        );
+        bc.add_transaction(tx.clone()).unwrap();
+        tx
+    };
+    sim.node(1).broadcast_transaction(&tx);
+    assert!(sim.wait_until(TIMEOUT, |sim| {
+        sim.node(2)
+            .blockchain
+            .blocking_read()
+            .mempool
+            .contains(&tx.id())
+    }));
+    sim.shutdown();
+}
+
+#[test]
+fn partitioned_nodes_follow_the_heavier_branch_after_healing() {
+    let sim = Simulation::with_genesis(3, &GenesisConfig::regtest());
+    sim.connect_all();
+    let miner = Wallet::new().get_address();
+
+    sim.partition(&[&[0], &[1, 2]]);
+    sim.node(0).generate(1, &miner).unwrap();
+    sim.node(1).generate(2, &miner).unwrap();
+    assert!(sim.wait_until(TIMEOUT, |sim| height(sim, 2) == 3));
+    assert_eq!(height(&sim, 0), 2);
+    assert_ne!(sim.tips()[0], sim.tips()[1]);
+
+    // Следующий блок тяжёлой ветки доходит до отрезанного узла, и тот
+    // догоняет её как недостающих родителей сироты
+    sim.heal();
+    sim.node(1).generate(1, &miner).unwrap();
+    assert!(sim.wait_until(TIMEOUT, |sim| sim.converged()));
+    assert_eq!(height(&sim, 0), 4);
+    sim.shutdown();
+}
+
+#[test]
+fn latency_delays_delivery() {
+    let sim = Simulation::with_genesis(2, &GenesisConfig::regtest());
+    sim.connect(0, 1);
+    sim.set_link_latency(0, 1, Duration::from_millis(200));
+
+    let start = Instant::now();
+    sim.node(0)
+        .generate(1, &Wallet::new().get_address())
+        .unwrap();
+    assert_eq!(height(&sim, 1), 1);
+    assert!(sim.wait_until(TIMEOUT, |sim| sim.converged()));
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    sim.shutdown();
+}
+
+#[test]
+fn periodic_resync_heals_a_partition_without_new_blocks() {
+    let sim = Simulation::with_genesis(3, &GenesisConfig::regtest());
+    sim.connect_all();
+    let miner = Wallet::new().get_address();
+
+    sim.partition(&[&[0], &[1, 2]]);
+    sim.node(0).generate(1, &miner).unwrap();
+    sim.node(1).generate(2, &miner).unwrap();
+    assert!(sim.wait_until(TIMEOUT, |sim| height(sim, 2) == 3));
+
+    // Тяжёлой половине сверка ничего не даёт, а отрезанный узел видит
+    // её вершину
+    sim.heal();
+    assert_eq!(sim.node(1).resync(), 0);
+    let tip = sim.node(0).request_tip(&sim.addr(1)).unwrap();
+    assert_eq!((tip.best_height, tip.hash), (3, sim.tips()[1]));
+
+    for node in sim.nodes() {
+        node.start_resync(Duration::from_millis(50));
+    }
+    assert!(sim.wait_until(TIMEOUT, |sim| sim.converged()));
+    assert_eq!(height(&sim, 0), 3);
+    sim.shutdown();
+}
+
+#[test]
+fn external_address_is_gossiped_instead_of_the_local_one() {
+    let sim = Simulation::new(3);
+    let external = "203.0.113.7:3000".to_string();
+    *sim.node(0).external_addr.lock().unwrap() = Some(external.clone());
+    sim.connect(0, 1);
+
+    // Узел 1 запомнил узел 0 по внешнему адресу и сообщает его другим
+    assert_eq!(sim.node(1).peer_addrs(), vec![external.clone()]);
+    let peers = sim.node(2).request_peers(&sim.addr(1)).unwrap();
+    assert!(peers.contains(&external));
+    assert!(!peers.contains(&sim.addr(0)));
+
+    // Свой внешний адрес узел в таблицу не заносит
+    let own = sim.node(0).request_peers(&sim.addr(1)).unwrap();
+    assert!(own.contains(&external));
+    sim.node(0).discover_peers(&[sim.addr(1)]);
+    assert_eq!(sim.node(0).peer_addrs(), vec![sim.addr(1)]);
+    sim.shutdown();
+}
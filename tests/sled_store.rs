@@ -0,0 +1,30 @@
+use blockchain_p2p::testutil::{test_wallet, ChainBuilder};
+use blockchain_p2p::{BlockStore, Blockchain, SledStore};
+use std::fs;
+use std::sync::Arc;
+
+#[test]
+fn sled_store_survives_reopening() {
+    let dir = std::env::temp_dir().join(format!("sled_store_{}", std::process::id()));
+    let builder = ChainBuilder::new();
+    let miner = test_wallet(1);
+    let mut bc = builder.mine_blocks(3, &miner).build();
+    bc.set_block_store(Arc::new(SledStore::open(&dir).unwrap()))
+        .unwrap();
+    bc.mine_block(&miner.get_address()).unwrap();
+    let expected = bc.chain.clone();
+    drop(bc);
+
+    let store = SledStore::open(&dir).unwrap();
+    assert_eq!(store.tip().unwrap(), Some((4, expected[4].hash)));
+    assert_eq!(
+        store.get_block_by_hash(&expected[2].hash).unwrap().as_ref(),
+        Some(&expected[2])
+    );
+    let restored = Blockchain::default()
+        .restore_from_store(Arc::new(store))
+        .unwrap();
+    assert_eq!(restored.chain, expected);
+    drop(restored);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Blockchain, Hash256, StateSnapshot, Wallet};
+use common::{mined_chain, signed_transaction};
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}_{}.json", name, std::process::id()))
+}
+
+#[test]
+fn restore_replays_blocks_after_snapshot() {
+    let chain_path = temp_path("snapshot_chain");
+    let snapshot_path = temp_path("snapshot_state");
+    let mut bc = mined_chain();
+    bc.snapshot().save(&snapshot_path).unwrap();
+
+    // Блок после снимка должен быть проведён по балансам при загрузке
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    bc.mine_block(&sender.get_address()).unwrap();
+    bc.add_transaction(signed_transaction(
+        &sender,
+        &receiver,
+        coins(7.0),
+        coins(0.5),
+    ))
+    .unwrap();
+    bc.mine_block(&sender.get_address()).unwrap();
+    bc.save_to_disk(&chain_path).unwrap();
+
+    let snapshot = StateSnapshot::load(&snapshot_path).unwrap();
+    assert_eq!(snapshot.height, 2);
+    let restored = Blockchain::default()
+        .restore_from_snapshot(&chain_path, &snapshot)
+        .unwrap();
+    let replayed = Blockchain::load_from_disk(&chain_path).unwrap();
+    fs::remove_file(&chain_path).unwrap();
+    fs::remove_file(&snapshot_path).unwrap();
+
+    assert_eq!(restored.chain, bc.chain);
+    assert_eq!(restored.bits, replayed.bits);
+    assert_eq!(restored.snapshot(), replayed.snapshot());
+    assert_eq!(restored.get_balance(&receiver.get_address()), coins(7.0));
+    assert_eq!(
+        restored.get_balance(&sender.get_address()),
+        replayed.get_balance(&sender.get_address())
+    );
+}
+
+#[test]
+fn balances_below_snapshot_come_from_snapshot() {
+    let chain_path = temp_path("snapshot_trusted_chain");
+    let bc = mined_chain();
+    bc.save_to_disk(&chain_path).unwrap();
+
+    // Блоки до снимка не проигрываются: баланс берётся из него как есть
+    let holder = Wallet::new().get_address();
+    let mut snapshot = bc.snapshot();
+    snapshot.balances.insert(holder.clone(), coins(123.0));
+    let restored = Blockchain::default()
+        .restore_from_snapshot(&chain_path, &snapshot)
+        .unwrap();
+    fs::remove_file(&chain_path).unwrap();
+
+    assert_eq!(restored.get_balance(&holder), coins(123.0));
+}
+
+#[test]
+fn snapshot_from_another_chain_is_rejected() {
+    let chain_path = temp_path("snapshot_foreign_chain");
+    let bc = mined_chain();
+    bc.save_to_disk(&chain_path).unwrap();
+
+    let mut foreign = bc.snapshot();
+    foreign.tip_hash = Hash256::ZERO;
+    let mismatched = Blockchain::default().restore_from_snapshot(&chain_path, &foreign);
+
+    let mut beyond = bc.snapshot();
+    beyond.height = 10;
+    let too_high = Blockchain::default().restore_from_snapshot(&chain_path, &beyond);
+    fs::remove_file(&chain_path).unwrap();
+
+    assert!(mismatched.is_err());
+    assert!(too_high.is_err());
+}
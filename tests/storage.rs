@@ -0,0 +1,49 @@
+mod common;
+
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::Blockchain;
+use common::mined_chain;
+use std::fs;
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{}_{}.jsonl", name, std::process::id()))
+}
+
+#[test]
+fn chain_survives_save_and_load() {
+    let path = temp_path("storage_round_trip");
+    let bc = mined_chain();
+
+    bc.save_to_disk(&path).unwrap();
+    let loaded = Blockchain::load_from_disk(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.chain, bc.chain);
+    assert_eq!(loaded.bits, bc.bits);
+}
+
+#[test]
+fn appended_blocks_are_loaded() {
+    let path = temp_path("storage_append");
+    let bc = mined_chain();
+
+    Blockchain::append_block_to_disk(&bc.chain[0], &path).unwrap();
+    Blockchain::append_block_to_disk(&bc.chain[1], &path).unwrap();
+    let loaded = Blockchain::load_from_disk(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.chain.len(), 2);
+}
+
+#[test]
+fn tampered_chain_is_rejected() {
+    let path = temp_path("storage_tampered");
+    let mut bc = mined_chain();
+    bc.chain[2].transactions[1].amount = coins(1000.0);
+
+    bc.save_to_disk(&path).unwrap();
+    let result = Blockchain::load_from_disk(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert!(result.is_err());
+}
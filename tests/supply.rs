@@ -0,0 +1,218 @@
+use blockchain_p2p::genesis::Allocation;
+use blockchain_p2p::testutil::{coins, test_wallet, ChainBuilder};
+use blockchain_p2p::{
+    Amount, Block, Blockchain, BlockchainError, GenesisConfig, GenesisError, SupplyAudit,
+    SupplyError, Transaction, TxValidationError, ValidationError, Wallet,
+};
+use std::sync::atomic::AtomicBool;
+
+/// Сеть с наградой 40, уполовиниванием каждые 3 блока, 100 монетами
+/// в genesis и пределом выпуска 210: награда урезается в блоке 4.
+fn capped_genesis() -> GenesisConfig {
+    GenesisConfig {
+        block_reward: coins(40.0),
+        halving_interval: 3,
+        max_supply: Some(coins(210.0)),
+        allocations: vec![Allocation {
+            address: Wallet::new().get_address(),
+            amount: coins(100.0),
+        }],
+        ..GenesisConfig::default()
+    }
+}
+
+/// Добывает блок с `transactions` поверх вершины `bc` без проверки.
+fn mine_on(bc: &Blockchain, transactions: Vec<Transaction>) -> Block {
+    let tip = bc.latest_block();
+    Block::mine_at(
+        tip.header.hash_algorithm,
+        tip.header.index + 1,
+        Block::next_timestamp_at(&bc.chain, bc.now()),
+        transactions,
+        tip.hash,
+        bc.bits,
+        &AtomicBool::new(false),
+    )
+    .unwrap()
+}
+
+#[test]
+fn rewards_stop_at_the_supply_cap() {
+    let builder = ChainBuilder::with_genesis(&capped_genesis());
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let bc = builder
+        .mine_blocks(5, &alice)
+        .transfer(&alice, &bob, coins(5.0), coins(0.5))
+        .mine(&alice)
+        .build();
+
+    let rewards: Vec<Amount> = (1..=6).map(|height| bc.current_reward(height)).collect();
+    let expected = [40.0, 40.0, 20.0, 10.0, 0.0, 0.0].map(coins);
+    assert_eq!(rewards, expected);
+    assert_eq!(bc.genesis_supply(), coins(100.0));
+    assert_eq!(bc.total_supply(), coins(210.0));
+    // После предела майнер получает одни комиссии
+    assert_eq!(bc.get_balance(&alice.get_address()), coins(105.0));
+    assert!(bc.is_chain_valid());
+    assert_eq!(
+        bc.audit_supply().unwrap(),
+        SupplyAudit {
+            height: 6,
+            issued: coins(210.0),
+            fees: coins(0.5),
+        }
+    );
+}
+
+#[test]
+fn coinbase_above_the_capped_reward_is_rejected() {
+    let builder = ChainBuilder::with_genesis(&capped_genesis());
+    let alice = builder.wallet(1);
+    let mut bc = builder.mine_blocks(4, &alice).build();
+    let coinbase = Transaction {
+        chain_id: bc.chain_id().to_string(),
+        ..Transaction::coinbase(alice.get_address(), coins(20.0), 5)
+    };
+    let block = mine_on(&bc, vec![coinbase]);
+
+    assert_eq!(
+        bc.add_block(block),
+        Err(BlockchainError::InvalidBlock {
+            index: 5,
+            reason: ValidationError::ExcessiveCoinbase {
+                allowed: Amount::ZERO,
+                actual: coins(20.0),
+            },
+        })
+    );
+}
+
+#[test]
+fn amounts_that_overflow_are_rejected() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let mut bc = builder.mine(&alice).build();
+    let to = Wallet::new().get_address();
+    let chain_id = bc.chain_id().to_string();
+    let transfer = |amount, fee| alice.create_transaction_for_chain(&chain_id, &to, amount, fee, 0);
+
+    // Списание `amount + fee` не помещается в u64
+    let overflowing = transfer(Amount::MAX, coins(0.1));
+    assert_eq!(
+        overflowing.validate(),
+        Err(TxValidationError::AmountOverflow)
+    );
+    assert!(matches!(
+        bc.add_transaction(overflowing),
+        Err(BlockchainError::InsufficientFunds { .. })
+    ));
+
+    // Комиссия сама по себе допустима, но вместе с наградой переполняет coinbase
+    let huge_fee = transfer(coins(1.0), Amount::MAX - coins(1.0));
+    assert_eq!(huge_fee.validate(), Ok(()));
+    let coinbase = Transaction {
+        chain_id: bc.chain_id().to_string(),
+        ..Transaction::coinbase(alice.get_address(), coins(50.0), 2)
+    };
+    let block = mine_on(&bc, vec![coinbase, huge_fee]);
+    assert_eq!(
+        bc.add_block(block),
+        Err(BlockchainError::InvalidBlock {
+            index: 2,
+            reason: ValidationError::AmountOverflow,
+        })
+    );
+}
+
+#[test]
+fn audit_finds_inflated_blocks_and_broken_caps() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let bc = builder
+        .mine_blocks(3, &alice)
+        .transfer(&alice, &bob, coins(5.0), coins(0.1))
+        .mine(&alice)
+        .build();
+    assert_eq!(bc.audit_supply().unwrap().issued, coins(200.0));
+
+    // Блок, подменённый в обход проверки, например в файле ниже снимка
+    let mut inflated = bc.clone();
+    inflated.chain[2].transactions[0].amount = coins(60.0);
+    inflated.reindex();
+    let error = inflated.audit_supply().unwrap_err();
+    assert!(matches!(
+        error,
+        SupplyError::Inflation { height: 2, minted, allowed }
+            if minted == coins(60.0) && allowed == coins(50.0)
+    ));
+    assert_eq!(
+        error.to_string(),
+        "block 2 mints 60 while at most 50 is allowed"
+    );
+
+    // Перевод больше баланса отправителя создал бы монеты из ничего
+    let mut overspent = bc.clone();
+    overspent.chain[4].transactions[1].amount = coins(500.0);
+    overspent.reindex();
+    assert!(matches!(
+        overspent.audit_supply(),
+        Err(SupplyError::Unbalanced { issued, balances })
+            if issued == coins(200.0) && balances > issued
+    ));
+
+    let genesis = GenesisConfig {
+        allocations: vec![Allocation {
+            address: alice.get_address(),
+            amount: coins(100.0),
+        }],
+        ..GenesisConfig::default()
+    };
+    let mut capped = ChainBuilder::with_genesis(&genesis).build();
+    capped.max_supply = Some(coins(50.0));
+    assert!(matches!(
+        capped.audit_supply(),
+        Err(SupplyError::CapExceeded { height: 0, issued, cap })
+            if issued == coins(100.0) && cap == coins(50.0)
+    ));
+
+    // Такой предел не пропустит и проверка genesis
+    let genesis = GenesisConfig {
+        max_supply: Some(coins(50.0)),
+        ..genesis
+    };
+    assert!(matches!(
+        genesis.validate(),
+        Err(GenesisError::Malformed(_))
+    ));
+}
+
+#[test]
+fn fees_that_would_overflow_the_coinbase_wait_in_mempool() {
+    let half = Amount::from_units(u64::MAX / 2);
+    let genesis = GenesisConfig {
+        allocations: [1, 2]
+            .map(|seed| Allocation {
+                address: test_wallet(seed).get_address(),
+                amount: half,
+            })
+            .to_vec(),
+        ..GenesisConfig::default()
+    };
+    let builder = ChainBuilder::with_genesis(&genesis);
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let miner = builder.wallet(3);
+    let fee = half - coins(1.0);
+
+    // Обе комиссии вместе с наградой не помещаются в одну coinbase
+    let mut bc = builder
+        .transfer(&alice, &miner, coins(1.0), fee)
+        .transfer(&bob, &miner, coins(1.0), fee)
+        .build();
+    let block = bc.mine_block(&miner.get_address()).unwrap();
+    assert_eq!(block.transactions.len(), 2);
+    assert_eq!(block.transactions[0].amount, coins(50.0) + fee);
+    assert_eq!(bc.mempool.size(), 1);
+}
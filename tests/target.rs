@@ -0,0 +1,101 @@
+use blockchain_p2p::block::INITIAL_BITS;
+use blockchain_p2p::{Blockchain, BlockchainError, GenesisConfig, Hash256, Target, Wallet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[test]
+fn compact_encoding_matches_bitcoin() {
+    // nBits генезис-блока Bitcoin
+    let target = Target::from_compact(0x1d00ffff).unwrap();
+    let mut expected = [0u8; 32];
+    expected[4] = 0xff;
+    expected[5] = 0xff;
+    assert_eq!(target.0, expected);
+    assert_eq!(target.to_compact(), 0x1d00ffff);
+
+    assert_eq!(Target::MAX.to_compact(), INITIAL_BITS);
+    assert_eq!(Target::from_compact(INITIAL_BITS), Some(Target::MAX));
+    assert_eq!(Target::from_compact(0x2200ffff), None);
+}
+
+#[test]
+fn hashes_are_compared_numerically() {
+    let target = Target::from_compact(0x1f00ffff).unwrap();
+    let mut below = [0u8; 32];
+    below[2] = 0xff;
+    let mut above = [0u8; 32];
+    above[1] = 0x01;
+
+    assert!(target.is_met_by(&Hash256(below)));
+    assert!(!target.is_met_by(&Hash256(above)));
+}
+
+#[test]
+fn scaling_allows_fine_grained_targets() {
+    let half = Target::MAX.scale(1, 2);
+    assert_eq!(half.to_compact(), 0x1f7fff80);
+    assert!((half.difficulty() - 2.0).abs() < 1e-3);
+    assert_eq!(Target::MAX.scale(4, 1), Target::MAX);
+
+    let mut bc = Blockchain::new(&GenesisConfig::regtest());
+    bc.set_difficulty(1.5).unwrap();
+    assert!((bc.difficulty() - 1.5).abs() < 1e-3);
+}
+
+#[test]
+fn manual_difficulty_is_regtest_only_and_survives_verification() {
+    let miner = Wallet::new();
+    let mut bc = Blockchain::default();
+    assert_eq!(bc.set_difficulty(2.0), Err(BlockchainError::RegtestOnly));
+    assert_eq!(bc.bits, INITIAL_BITS);
+
+    let mut regtest = Blockchain::new(&GenesisConfig::regtest());
+    regtest.mine_block(&miner.get_address()).unwrap();
+    regtest.set_difficulty(2.0).unwrap();
+    let bits = regtest.bits;
+    for _ in 0..2 {
+        let block = regtest.mine_block(&miner.get_address()).unwrap();
+        assert_eq!(block.header.bits, bits);
+    }
+    assert_eq!(regtest.bits, bits);
+    assert_eq!(regtest.verify(), Ok(()));
+}
+
+#[test]
+fn fast_blocks_raise_difficulty() {
+    // Genesis «сейчас», иначе окно начиналось бы с давней метки genesis
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let mut bc = Blockchain::new(&GenesisConfig {
+        timestamp: now.as_secs(),
+        ..GenesisConfig::default()
+    });
+    let miner = Wallet::new();
+    for _ in 0..bc.adjustment_interval - 1 {
+        bc.mine_block(&miner.get_address()).unwrap();
+    }
+
+    // Блоки найдены быстрее цели — время окна упирается в нижнюю
+    // границу: четверть от ожидаемых 90 секунд, округлённая вверх
+    assert!((bc.difficulty() - 90.0 / 23.0).abs() < 1e-3);
+    assert!(bc.is_chain_valid());
+}
+
+#[test]
+fn work_is_the_exact_expected_number_of_hashes() {
+    let from_parts = |high: u128, low: u128| {
+        let mut bytes = [0u8; 32];
+        bytes[..16].copy_from_slice(&high.to_be_bytes());
+        bytes[16..].copy_from_slice(&low.to_be_bytes());
+        Target(bytes)
+    };
+    // 2^256 / 2^129
+    assert_eq!(from_parts(1, u128::MAX).work(), 1 << 127);
+    // 2^256 / (2^255 + 1) и 2^256 / 2^256
+    assert_eq!(from_parts(1 << 127, 0).work(), 1);
+    assert_eq!(Target([0xff; 32]).work(), 1);
+    // Цели ниже 2^128 насыщаются
+    assert_eq!(from_parts(0, u128::MAX).work(), u128::MAX);
+
+    // Максимальная цель — 0xffff * 2^232: работа 2^24 / 0xffff с округлением вниз
+    assert_eq!(Target::MAX.work(), (1u128 << 24) / 0xffff);
+    assert_eq!(Target::MAX.scale(1, 1000).work() / Target::MAX.work(), 1000);
+}
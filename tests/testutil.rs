@@ -0,0 +1,84 @@
+use blockchain_p2p::block::{DEFAULT_MEMPOOL_TTL, MAX_FUTURE_BLOCK_TIME};
+use blockchain_p2p::genesis::DEFAULT_GENESIS_TIMESTAMP;
+use blockchain_p2p::hashing::HashAlgorithm;
+use blockchain_p2p::testutil::{coins, test_wallet, ChainBuilder};
+use blockchain_p2p::{Block, BlockchainError, MockClock};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+fn build_chain() -> Vec<Block> {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(5.0), coins(0.1))
+        .mine_blocks(2, &bob)
+        .build()
+        .chain
+}
+
+#[test]
+fn same_steps_build_identical_chains() {
+    let first = build_chain();
+    assert_eq!(first, build_chain());
+    assert_eq!(first.len(), 4);
+    assert_eq!(first[1].header.timestamp, DEFAULT_GENESIS_TIMESTAMP + 10);
+    assert_eq!(
+        first[2].transactions[1].timestamp,
+        first[1].header.timestamp
+    );
+
+    assert_eq!(test_wallet(7).get_address(), test_wallet(7).get_address());
+    assert_ne!(test_wallet(7).get_address(), test_wallet(8).get_address());
+}
+
+#[test]
+fn chain_checks_block_time_against_its_clock() {
+    let builder = ChainBuilder::new();
+    let miner = builder.wallet(1);
+    let mut bc = builder.mine(&miner).build();
+    let tip = bc.latest_block().clone();
+    let bits = bc.bits;
+
+    let block_at = |timestamp| {
+        Block::mine_at(
+            HashAlgorithm::default(),
+            tip.header.index + 1,
+            timestamp,
+            Vec::new(),
+            tip.hash,
+            bits,
+            &AtomicBool::new(false),
+        )
+        .unwrap()
+    };
+    // По системным часам блок из 2024 года давно не в будущем, а по
+    // часам цепи — ещё да
+    let ahead = bc.now() + MAX_FUTURE_BLOCK_TIME + 1;
+    assert!(matches!(
+        bc.add_block(block_at(ahead)),
+        Err(BlockchainError::InvalidTimestamp { index: 2, .. })
+    ));
+
+    let clock = MockClock::new(ahead);
+    bc.set_clock(Arc::new(clock));
+    bc.add_block(block_at(ahead)).unwrap();
+}
+
+#[test]
+fn mock_clock_expires_pending_transactions() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let builder = builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(1.0), coins(0.1));
+    let clock = builder.clock().clone();
+    let mut bc = builder.build();
+
+    clock.advance(DEFAULT_MEMPOOL_TTL);
+    assert!(bc.mempool.purge_expired(bc.now()).is_empty());
+    clock.advance(1);
+    assert_eq!(bc.mempool.purge_expired(bc.now()).len(), 1);
+}
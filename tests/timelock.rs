@@ -0,0 +1,148 @@
+mod common;
+
+use blockchain_p2p::block::DEFAULT_MEMPOOL_TTL;
+use blockchain_p2p::hashing::HashAlgorithm;
+use blockchain_p2p::testutil::{coins, ChainBuilder};
+use blockchain_p2p::{
+    Block, Blockchain, BlockchainError, GenesisConfig, LockTime, TxValidationError,
+    ValidationError, Wallet,
+};
+use common::fund;
+use std::sync::atomic::AtomicBool;
+
+#[test]
+fn height_locked_transaction_waits_in_mempool() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    let tx = sender.create_locked_transaction(
+        &receiver.get_address(),
+        coins(5.0),
+        coins(0.1),
+        0,
+        LockTime::Height(4),
+    );
+    let txid = tx.id();
+    bc.add_transaction(tx).unwrap();
+
+    for index in 2..4 {
+        let block = bc.mine_block(&sender.get_address()).unwrap();
+        assert_eq!(block.header.index, index);
+        assert_eq!(block.transactions.len(), 1);
+        assert!(bc.mempool.contains(&txid));
+    }
+
+    let block = bc.mine_block(&sender.get_address()).unwrap();
+    assert_eq!(block.transactions[1].id(), txid);
+    assert_eq!(bc.get_balance(&receiver.get_address()), coins(5.0));
+    assert!(bc.is_chain_valid());
+}
+
+#[test]
+fn block_with_premature_transaction_is_invalid() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    let tip = bc.latest_block().clone();
+    let bits = bc.bits;
+    let tx = sender.create_locked_transaction(
+        &receiver.get_address(),
+        coins(5.0),
+        coins(0.1),
+        0,
+        LockTime::Timestamp(tip.header.timestamp + 10),
+    );
+    let block_at = |timestamp: u64| {
+        Block::mine_at(
+            HashAlgorithm::default(),
+            tip.header.index + 1,
+            timestamp,
+            vec![tx.clone()],
+            tip.hash,
+            bits,
+            &AtomicBool::new(false),
+        )
+        .unwrap()
+    };
+
+    let premature = block_at(tip.header.timestamp + 9);
+    assert!(!premature.is_valid(&bc.chain));
+    assert_eq!(
+        bc.add_block(premature),
+        Err(BlockchainError::InvalidBlock {
+            index: 2,
+            reason: ValidationError::NonFinalTransaction { txid: tx.id() },
+        })
+    );
+
+    let mature = block_at(tip.header.timestamp + 10);
+    assert!(mature.is_valid(&bc.chain));
+    bc.add_block(mature).unwrap();
+}
+
+#[test]
+fn lock_time_is_signed_and_extends_mempool_ttl() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    let unlock = bc.latest_block().header.timestamp + 2 * DEFAULT_MEMPOOL_TTL;
+    let tx = sender.create_locked_transaction(
+        &receiver.get_address(),
+        coins(5.0),
+        coins(0.1),
+        0,
+        LockTime::Timestamp(unlock),
+    );
+
+    let mut tampered = tx.clone();
+    tampered.lock_time = None;
+    assert_eq!(
+        tampered.validate(),
+        Err(TxValidationError::InvalidSignature)
+    );
+
+    bc.add_transaction(tx.clone()).unwrap();
+    bc.mine_block(&sender.get_address()).unwrap();
+    assert!(bc.mempool.contains(&tx.id()));
+
+    // Срок жизни в mempool отсчитывается от момента разблокировки
+    assert!(bc
+        .mempool
+        .purge_expired(tx.timestamp + DEFAULT_MEMPOOL_TTL + 1)
+        .is_empty());
+    assert_eq!(
+        bc.mempool.purge_expired(unlock + DEFAULT_MEMPOOL_TTL + 1),
+        vec![tx]
+    );
+}
+
+#[test]
+fn locked_transactions_are_signed_for_the_given_chain() {
+    let builder = ChainBuilder::with_genesis(&GenesisConfig::regtest());
+    let sender = builder.wallet(1);
+    let receiver = builder.wallet(2);
+    let mut builder = builder.mine(&sender);
+    let chain_id = builder.chain().chain_id().to_string();
+
+    let tx = sender.create_locked_transaction_for_chain(
+        &chain_id,
+        &receiver.get_address(),
+        coins(5.0),
+        coins(0.1),
+        0,
+        LockTime::Height(2),
+    );
+    assert_eq!(tx.chain_id, chain_id);
+    assert!(format!("{:?}", tx).contains("lock_time: Some(Height(2))"));
+    builder = builder.submit(tx).mine(&sender);
+
+    let bc = builder.build();
+    assert_eq!(bc.get_balance(&receiver.get_address()), coins(5.0));
+    assert!(bc.is_chain_valid());
+}
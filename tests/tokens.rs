@@ -0,0 +1,197 @@
+mod common;
+
+use blockchain_p2p::testutil::{coins, ChainBuilder};
+use blockchain_p2p::{
+    Block, Blockchain, BlockchainError, GenesisConfig, Hash256, TokenError, TokenOp,
+    TxValidationError, Wallet,
+};
+use common::fund;
+use std::sync::atomic::AtomicBool;
+
+/// Цепь, где `issuer` создал токен GOLD с выпуском 1000 себе.
+fn chain_with_token(issuer: &Wallet) -> (Blockchain, Hash256) {
+    let mut bc = Blockchain::default();
+    fund(&mut bc, issuer);
+    let create = issuer.create_token_transaction(
+        &issuer.get_address(),
+        TokenOp::CreateToken {
+            symbol: "GOLD".to_string(),
+            supply: 1000,
+        },
+        coins(0.1),
+        0,
+    );
+    let token = create.id();
+    bc.add_transaction(create).unwrap();
+    bc.mine_block(&issuer.get_address()).unwrap();
+    (bc, token)
+}
+
+#[test]
+fn tokens_are_created_minted_and_transferred() {
+    let issuer = Wallet::new();
+    let holder = Wallet::new();
+    let (mut bc, token) = chain_with_token(&issuer);
+    assert_eq!(bc.token_balance(&issuer.get_address(), &token), 1000);
+
+    let mint = issuer.create_token_transaction(
+        &holder.get_address(),
+        TokenOp::Mint { token, amount: 500 },
+        coins(0.1),
+        1,
+    );
+    let transfer = issuer.create_token_transaction(
+        &holder.get_address(),
+        TokenOp::Transfer { token, amount: 300 },
+        coins(0.1),
+        2,
+    );
+    bc.add_transaction(mint).unwrap();
+    bc.add_transaction(transfer).unwrap();
+    bc.mine_block(&issuer.get_address()).unwrap();
+
+    assert_eq!(bc.token_balance(&issuer.get_address(), &token), 700);
+    assert_eq!(bc.token_balance(&holder.get_address(), &token), 800);
+    let info = bc.token_info(&token).unwrap();
+    assert_eq!((info.symbol.as_str(), info.supply), ("GOLD", 1500));
+    assert_eq!(info.issuer, issuer.get_address());
+
+    // Состояние токенов не сериализуется, но восстанавливается по цепи
+    let decoded: Blockchain = serde_json::from_str(&serde_json::to_string(&bc).unwrap()).unwrap();
+    assert_eq!(decoded.token_balance(&holder.get_address(), &token), 800);
+}
+
+#[test]
+fn impossible_operations_are_refused() {
+    let issuer = Wallet::new();
+    let holder = Wallet::new();
+    let (mut bc, token) = chain_with_token(&issuer);
+    fund(&mut bc, &holder);
+
+    let mint = holder.create_token_transaction(
+        &holder.get_address(),
+        TokenOp::Mint { token, amount: 5 },
+        coins(0.1),
+        0,
+    );
+    assert_eq!(
+        bc.add_transaction(mint),
+        Err(BlockchainError::Token(TokenError::NotIssuer {
+            token,
+            address: holder.get_address(),
+        }))
+    );
+
+    // Второй перевод не проходит с учётом первого, ждущего в mempool
+    let transfer = |amount, nonce| {
+        issuer.create_token_transaction(
+            &holder.get_address(),
+            TokenOp::Transfer { token, amount },
+            coins(0.1),
+            nonce,
+        )
+    };
+    bc.add_transaction(transfer(600, 1)).unwrap();
+    assert_eq!(
+        bc.add_transaction(transfer(600, 2)),
+        Err(BlockchainError::Token(TokenError::InsufficientTokens {
+            token,
+            available: 400,
+            required: 600,
+        }))
+    );
+
+    let unknown = issuer.create_token_transaction(
+        &holder.get_address(),
+        TokenOp::Transfer {
+            token: Hash256::ZERO,
+            amount: 1,
+        },
+        coins(0.1),
+        3,
+    );
+    assert_eq!(
+        bc.add_transaction(unknown),
+        Err(BlockchainError::Token(TokenError::UnknownToken(
+            Hash256::ZERO
+        )))
+    );
+
+    let bad_symbol = issuer.create_token_transaction(
+        &issuer.get_address(),
+        TokenOp::CreateToken {
+            symbol: "gold coin".to_string(),
+            supply: 1,
+        },
+        coins(0.1),
+        4,
+    );
+    assert_eq!(
+        bad_symbol.validate(),
+        Err(TxValidationError::InvalidTokenSymbol(
+            "gold coin".to_string()
+        ))
+    );
+}
+
+#[test]
+fn block_with_an_overdraft_is_rejected() {
+    let issuer = Wallet::new();
+    let (mut bc, token) = chain_with_token(&issuer);
+    let overdraft = issuer.create_token_transaction(
+        &Wallet::new().get_address(),
+        TokenOp::Transfer {
+            token,
+            amount: 1001,
+        },
+        coins(0.1),
+        1,
+    );
+
+    let tip = bc.latest_block().clone();
+    let block = Block::mine_at(
+        tip.header.hash_algorithm,
+        tip.header.index + 1,
+        tip.header.timestamp + 1,
+        vec![overdraft],
+        tip.hash,
+        bc.bits,
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    assert!(matches!(
+        bc.add_block(block.clone()),
+        Err(BlockchainError::Token(
+            TokenError::InsufficientTokens { .. }
+        ))
+    ));
+
+    let mut candidate = bc.chain.clone();
+    candidate.push(block);
+    assert!(bc.validate_chain(&candidate).is_err());
+}
+
+#[test]
+fn token_operations_are_signed_for_the_given_chain() {
+    let builder = ChainBuilder::with_genesis(&GenesisConfig::regtest());
+    let issuer = builder.wallet(1);
+    let builder = builder.mine(&issuer);
+    let chain_id = builder.chain().chain_id().to_string();
+
+    let create = issuer.create_token_transaction_for_chain(
+        &chain_id,
+        &issuer.get_address(),
+        TokenOp::CreateToken {
+            symbol: "TEST".to_string(),
+            supply: 10,
+        },
+        coins(0.1),
+        0,
+    );
+    assert_eq!(create.chain_id, chain_id);
+    let token = create.id();
+    let bc = builder.submit(create).mine(&issuer).build();
+
+    assert_eq!(bc.token_balance(&issuer.get_address(), &token), 10);
+    assert!(bc.is_chain_valid());
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use blockchain_p2p::block::{DATA_FEE_PER_BYTE, MAX_TX_DATA_SIZE};
+use blockchain_p2p::genesis::DEFAULT_CHAIN_ID;
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Blockchain, BlockchainError, Transaction, TxValidationError, Wallet};
+use common::fund;
+
+#[test]
+fn signed_transaction_verifies() {
+    let sender = Wallet::new();
+    let tx = sender.create_transaction(&Wallet::new().get_address(), coins(1.0), coins(0.1), 0);
+
+    assert_eq!(tx.validate(), Ok(()));
+}
+
+#[test]
+fn tampered_fields_break_signature() {
+    let sender = Wallet::new();
+    let mut tx = sender.create_transaction(&Wallet::new().get_address(), coins(1.0), coins(0.1), 0);
+
+    tx.amount = coins(100.0);
+    assert_eq!(tx.validate(), Err(TxValidationError::InvalidSignature));
+}
+
+#[test]
+fn txid_covers_every_field_and_appears_in_logs() {
+    let sender = Wallet::new();
+    let tx = sender.create_transaction(&Wallet::new().get_address(), coins(1.0), coins(0.1), 0);
+    let txid = tx.id().to_string();
+
+    let mut bumped = tx.clone();
+    bumped.fee = coins(0.2);
+    assert_ne!(bumped.id(), tx.id());
+    assert_eq!(tx.clone().id(), tx.id());
+
+    assert!(tx.to_string().starts_with(&txid));
+    assert!(format!("{:?}", tx).contains(&txid));
+}
+
+#[test]
+fn foreign_public_key_is_rejected() {
+    let sender = Wallet::new();
+    let mut tx = sender.create_transaction(&Wallet::new().get_address(), coins(1.0), coins(0.1), 0);
+
+    tx.public_key = Wallet::new().public_key;
+    assert_eq!(tx.validate(), Err(TxValidationError::PublicKeyMismatch));
+}
+
+#[test]
+fn reused_nonce_is_rejected() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new().get_address();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    let nonce = bc.next_nonce(&sender.get_address());
+    bc.add_transaction(sender.create_transaction(&receiver, coins(1.0), coins(0.1), nonce))
+        .unwrap();
+    fund(&mut bc, &sender);
+    assert_eq!(bc.next_nonce(&sender.get_address()), nonce + 1);
+
+    let replay = sender.create_transaction(&receiver, coins(2.0), coins(0.1), nonce);
+    assert_eq!(
+        bc.add_transaction(replay),
+        Err(BlockchainError::NonceReused {
+            address: sender.get_address(),
+            nonce
+        })
+    );
+}
+
+#[test]
+fn transaction_from_another_chain_is_rejected() {
+    let sender = Wallet::new();
+    let receiver = Wallet::new().get_address();
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+
+    let mut tx =
+        sender.create_transaction_for_chain("testnet", &receiver, coins(1.0), coins(0.1), 0);
+    assert_eq!(
+        bc.add_transaction(tx.clone()),
+        Err(BlockchainError::WrongChain {
+            expected: "mini-blockchain".to_string(),
+            actual: "testnet".to_string(),
+        })
+    );
+
+    // Подмена сети ломает подпись
+    tx.chain_id = bc.chain_id().to_string();
+    assert_eq!(tx.validate(), Err(TxValidationError::InvalidSignature));
+}
+
+#[test]
+fn data_is_signed_anchored_and_paid_for() {
+    let sender = Wallet::new();
+    let to = Wallet::new().get_address();
+    let document = b"sha256:6b86b273ff34fce19d6b804eff5a3f57".to_vec();
+    let tx = sender.create_transaction_with_data(
+        DEFAULT_CHAIN_ID,
+        &to,
+        coins(1.0),
+        coins(0.1),
+        0,
+        document,
+    );
+    assert_eq!(tx.validate(), Ok(()));
+
+    // Данные входят в подпись и в txid
+    let mut tampered = tx.clone();
+    tampered.data[0] ^= 1;
+    assert_ne!(tampered.id(), tx.id());
+    assert_eq!(
+        tampered.validate(),
+        Err(TxValidationError::InvalidSignature)
+    );
+
+    // В JSON — hex-строка, и она читается обратно
+    let json = serde_json::to_value(&tx).unwrap();
+    assert_eq!(json["data"], hex::encode(&tx.data));
+    let decoded: Transaction = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded, tx);
+
+    let mut bc = Blockchain::default();
+    fund(&mut bc, &sender);
+    bc.add_transaction(tx.clone()).unwrap();
+    bc.mine_block(&sender.get_address()).unwrap();
+    assert_eq!(bc.get_transaction(&tx.id()).unwrap().0.data, tx.data);
+}
+
+#[test]
+fn oversized_or_underpaid_data_is_rejected() {
+    let sender = Wallet::new();
+    let to = Wallet::new().get_address();
+
+    let cheap = sender.create_transaction_with_data(
+        DEFAULT_CHAIN_ID,
+        &to,
+        coins(1.0),
+        coins(0.05),
+        0,
+        vec![7; 100],
+    );
+    assert_eq!(
+        cheap.validate(),
+        Err(TxValidationError::FeeTooLow {
+            required: DATA_FEE_PER_BYTE.checked_mul(100).unwrap(),
+            provided: coins(0.05),
+        })
+    );
+
+    let huge = sender.create_transaction_with_data(
+        DEFAULT_CHAIN_ID,
+        &to,
+        coins(1.0),
+        coins(1.0),
+        0,
+        vec![0; MAX_TX_DATA_SIZE + 1],
+    );
+    assert_eq!(
+        huge.validate(),
+        Err(TxValidationError::DataTooLarge {
+            size: MAX_TX_DATA_SIZE + 1,
+            max: MAX_TX_DATA_SIZE,
+        })
+    );
+}
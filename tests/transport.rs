@@ -0,0 +1,96 @@
+use blockchain_p2p::transport::{initiate, respond};
+use blockchain_p2p::{LightClient, Node, SyncError, Transport, Wallet};
+use secp256k1::PublicKey;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+
+fn encrypted_node(id: &str) -> Node {
+    let mut node = Node::new(id.to_string());
+    node.transport = Transport::Encrypted(Arc::new(Wallet::new()));
+    node
+}
+
+fn spawn_server(node: &Node, port: u16) {
+    let server = node.clone_node();
+    thread::spawn(move || server.start_server(port));
+    thread::sleep(Duration::from_millis(300));
+}
+
+fn public_key(wallet: &Wallet) -> PublicKey {
+    PublicKey::from_slice(&hex::decode(&wallet.public_key).unwrap()).unwrap()
+}
+
+#[test]
+fn handshake_authenticates_both_sides_and_protects_frames() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+
+    let (mut to_bob, mut to_alice) = Runtime::new().unwrap().block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let responder = async {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            respond(&mut stream, &bob).await.unwrap()
+        };
+        let initiator = async {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            initiate(&mut stream, &alice).await.unwrap()
+        };
+        tokio::join!(initiator, responder)
+    });
+    assert_eq!(to_bob.remote_key(), &public_key(&bob));
+    assert_eq!(to_alice.remote_key(), &public_key(&alice));
+
+    let frame = to_bob.seal(b"{\"type\":\"GET_PEERS\"}");
+    assert!(!frame.windows(9).any(|w| w == b"GET_PEERS"));
+    assert_eq!(to_alice.open(&frame).unwrap(), b"{\"type\":\"GET_PEERS\"}");
+
+    // Повтор и подмена кадра не проходят проверку
+    assert!(to_alice.open(&frame).is_err());
+    let mut tampered = to_bob.seal(b"hello");
+    tampered[0] ^= 1;
+    assert!(to_alice.open(&tampered).is_err());
+}
+
+#[test]
+fn encrypted_nodes_sync_with_each_other() {
+    let alice = encrypted_node("Alice");
+    {
+        let mut bc = alice.blockchain.blocking_write();
+        let miner = Wallet::new().get_address();
+        bc.mine_block(&miner).unwrap();
+        bc.mine_block(&miner).unwrap();
+    }
+    spawn_server(&alice, 38130);
+
+    let bob = encrypted_node("Bob");
+    assert_eq!(bob.sync_with_peer("127.0.0.1:38130"), Ok(2));
+
+    let mut light = LightClient::new(bob.blockchain.blocking_read().chain[0].header);
+    light.transport = bob.transport.clone();
+    assert_eq!(light.sync("127.0.0.1:38130"), Ok(2));
+}
+
+#[test]
+fn plain_and_encrypted_nodes_do_not_connect() {
+    let alice = encrypted_node("Alice");
+    spawn_server(&alice, 38131);
+    let carol = Node::new("Carol".to_string());
+    spawn_server(&carol, 38132);
+
+    let bob = Node::new("Bob".to_string());
+    assert_eq!(
+        bob.handshake("127.0.0.1:38131"),
+        Err(SyncError::PeerUnreachable)
+    );
+    let dave = encrypted_node("Dave");
+    assert_eq!(
+        dave.handshake("127.0.0.1:38132"),
+        Err(SyncError::PeerUnreachable)
+    );
+    // Сорванное рукопожатие — не повод для бана
+    assert!(alice.banned_peers().is_empty());
+}
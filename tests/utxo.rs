@@ -0,0 +1,290 @@
+use blockchain_p2p::testutil::{coins, ChainBuilder};
+use blockchain_p2p::utxo::{OutPoint, UtxoSet, UtxoTransaction};
+use blockchain_p2p::{
+    Amount, Block, Blockchain, BlockchainError, HashAlgorithm, MiningOptions, TxValidationError,
+    UtxoError, ValidationError, Wallet,
+};
+use std::sync::atomic::AtomicBool;
+
+fn funded(wallet: &Wallet, amounts: &[f64]) -> UtxoSet {
+    let mut utxos = UtxoSet::new();
+    for (height, &amount) in amounts.iter().enumerate() {
+        let reward = UtxoTransaction::coinbase(wallet.get_address(), coins(amount), height as u32);
+        utxos.apply_block(&[reward], coins(amount)).unwrap();
+    }
+    utxos
+}
+
+#[test]
+fn transfer_spends_inputs_and_returns_change() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let mut utxos = funded(&alice, &[50.0, 30.0]);
+
+    let tx = alice
+        .create_utxo_transaction(&utxos, &bob.get_address(), coins(60.0), coins(1.0))
+        .unwrap();
+    assert_eq!(tx.inputs.len(), 2);
+    assert_eq!(utxos.validate_transaction(&tx), Ok(coins(1.0)));
+
+    utxos
+        .apply_block(std::slice::from_ref(&tx), Amount::ZERO)
+        .unwrap();
+    assert_eq!(utxos.balance(&bob.get_address()), coins(60.0));
+    assert_eq!(utxos.balance(&alice.get_address()), coins(19.0));
+
+    // Повторная трата тех же выходов
+    assert!(matches!(
+        utxos.apply_block(&[tx], Amount::ZERO),
+        Err(UtxoError::MissingInput(_))
+    ));
+}
+
+#[test]
+fn only_the_owner_can_unlock_outputs() {
+    let alice = Wallet::new();
+    let mallory = Wallet::new();
+    let utxos = funded(&alice, &[50.0]);
+
+    let mut tx = alice
+        .create_utxo_transaction(&utxos, &mallory.get_address(), coins(10.0), coins(0.0))
+        .unwrap();
+    tx.inputs[0].public_key = mallory.public_key.clone();
+    tx.inputs[0].signature = mallory.sign_transaction(&tx.signing_payload());
+
+    assert_eq!(
+        utxos.validate_transaction(&tx),
+        Err(UtxoError::InvalidInput {
+            index: 0,
+            reason: TxValidationError::PublicKeyMismatch
+        })
+    );
+}
+
+#[test]
+fn spendable_outputs_cover_the_amount() {
+    let alice = Wallet::new();
+    let utxos = funded(&alice, &[5.0, 20.0, 10.0]);
+
+    let (total, outpoints) = utxos
+        .find_spendable_outputs(&alice.get_address(), coins(25.0))
+        .unwrap();
+    assert_eq!(total, coins(30.0));
+    assert_eq!(outpoints.len(), 2);
+    assert!(outpoints
+        .iter()
+        .all(|op: &OutPoint| utxos.get(op).is_some()));
+
+    assert!(utxos
+        .find_spendable_outputs(&alice.get_address(), coins(100.0))
+        .is_none());
+}
+
+#[test]
+fn undo_block_restores_spent_outputs() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let mut utxos = funded(&alice, &[50.0]);
+    let before = utxos.clone();
+
+    let tx = alice
+        .create_utxo_transaction(&utxos, &bob.get_address(), coins(20.0), coins(0.0))
+        .unwrap();
+    let block = [
+        UtxoTransaction::coinbase(bob.get_address(), coins(50.0), 1),
+        tx,
+    ];
+    let undo = utxos.apply_block(&block, coins(50.0)).unwrap();
+    assert_eq!(undo.spent.len(), 1);
+    assert_eq!(utxos.balance(&bob.get_address()), coins(70.0));
+
+    utxos.undo_block(&block, undo);
+    assert_eq!(utxos.len(), before.len());
+    assert_eq!(utxos.balance(&alice.get_address()), coins(50.0));
+    assert_eq!(utxos.balance(&bob.get_address()), coins(0.0));
+}
+
+#[test]
+fn overflowing_and_empty_outputs_are_rejected() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let utxos = funded(&alice, &[50.0]);
+
+    assert_eq!(
+        alice.create_utxo_transaction(&utxos, &bob.get_address(), Amount::MAX, coins(1.0)),
+        Err(UtxoError::AmountOverflow)
+    );
+
+    let mut tx = alice
+        .create_utxo_transaction(&utxos, &bob.get_address(), coins(10.0), coins(0.0))
+        .unwrap();
+    let resign = |tx: &mut UtxoTransaction| {
+        tx.inputs[0].signature = alice.sign_transaction(&tx.signing_payload());
+    };
+
+    // Сумма выходов не помещается в u64 и не может «сравняться» со входами
+    tx.outputs[0].amount = Amount::MAX;
+    tx.outputs[1].amount = Amount::from_units(1);
+    resign(&mut tx);
+    assert_eq!(tx.output_total(), None);
+    assert_eq!(
+        utxos.validate_transaction(&tx),
+        Err(UtxoError::AmountOverflow)
+    );
+
+    tx.outputs[1].amount = Amount::ZERO;
+    resign(&mut tx);
+    assert_eq!(
+        utxos.validate_transaction(&tx),
+        Err(UtxoError::NonPositiveOutput)
+    );
+}
+
+/// Добывает поверх вершины блок без счётных транзакций с UTXO-транзакциями
+/// `utxo` и подключает его.
+fn mine_utxo_block(bc: &mut Blockchain, utxo: Vec<UtxoTransaction>) -> Result<(), BlockchainError> {
+    let block = Block::template(
+        HashAlgorithm::default(),
+        bc.height() as u32,
+        Block::next_timestamp_at(&bc.chain, bc.now()),
+        Vec::new(),
+        bc.latest_block().hash,
+        bc.bits,
+    )
+    .with_utxo_transactions(utxo)
+    .solve(&MiningOptions::default(), &AtomicBool::new(false))
+    .0
+    .unwrap();
+    bc.add_block(block)
+}
+
+#[test]
+fn outputs_created_and_spent_in_one_block_are_undone() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let mut utxos = funded(&alice, &[50.0]);
+    let before = utxos.clone();
+
+    let to_bob = alice
+        .create_utxo_transaction(&utxos, &bob.get_address(), coins(20.0), coins(1.0))
+        .unwrap();
+    let mut after_first = utxos.clone();
+    after_first
+        .apply_block(std::slice::from_ref(&to_bob), Amount::ZERO)
+        .unwrap();
+    let back = bob
+        .create_utxo_transaction(&after_first, &alice.get_address(), coins(5.0), coins(1.0))
+        .unwrap();
+
+    let block = [to_bob, back];
+    assert_eq!(utxos.validate_block(&block, Amount::ZERO), Ok(coins(2.0)));
+    let undo = utxos.apply_block(&block, Amount::ZERO).unwrap();
+    assert_eq!(utxos.balance(&bob.get_address()), coins(14.0));
+
+    utxos.undo_block(&block, undo);
+    assert_eq!(utxos.len(), before.len());
+    assert_eq!(utxos.total(), coins(50.0));
+    assert_eq!(utxos.height(), before.height());
+}
+
+#[test]
+fn coinbase_is_capped_by_subsidy_and_fees() {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let utxos = funded(&alice, &[50.0]);
+    let tx = alice
+        .create_utxo_transaction(&utxos, &bob.get_address(), coins(10.0), coins(2.0))
+        .unwrap();
+
+    let block = |reward: f64| {
+        [
+            UtxoTransaction::coinbase(bob.get_address(), coins(reward), utxos.height()),
+            tx.clone(),
+        ]
+    };
+    assert_eq!(
+        utxos.validate_block(&block(27.0), coins(25.0)),
+        Ok(coins(2.0))
+    );
+    assert_eq!(
+        utxos.validate_block(&block(27.5), coins(25.0)),
+        Err(UtxoError::ExcessiveCoinbase {
+            allowed: coins(27.0),
+            actual: coins(27.5)
+        })
+    );
+
+    // Coinbase с чужой высотой совпала бы по txid с наградой другого блока
+    let stale = UtxoTransaction::coinbase(bob.get_address(), coins(1.0), 0);
+    assert_eq!(
+        utxos.validate_block(&[stale], coins(25.0)),
+        Err(UtxoError::CoinbaseHeight {
+            expected: 1,
+            actual: 0
+        })
+    );
+}
+
+#[test]
+fn chain_connects_utxo_transactions_of_its_blocks() {
+    let builder = ChainBuilder::new();
+    let (alice, bob) = (builder.wallet(1), builder.wallet(2));
+    let mut bc = builder.build();
+
+    // Блок без счётной coinbase: вся награда достаётся UTXO-coinbase
+    let reward = UtxoTransaction::coinbase(alice.get_address(), coins(50.0), 1);
+    mine_utxo_block(&mut bc, vec![reward]).unwrap();
+    assert_eq!(bc.utxos().balance(&alice.get_address()), coins(50.0));
+
+    let tx = alice
+        .create_utxo_transaction(&bc.utxos(), &bob.get_address(), coins(20.0), coins(1.0))
+        .unwrap();
+    let greedy = UtxoTransaction::coinbase(bob.get_address(), coins(51.5), 2);
+    assert_eq!(
+        mine_utxo_block(&mut bc, vec![greedy, tx.clone()]),
+        Err(BlockchainError::InvalidBlock {
+            index: 2,
+            reason: ValidationError::Utxo(UtxoError::ExcessiveCoinbase {
+                allowed: coins(51.0),
+                actual: coins(51.5)
+            })
+        })
+    );
+
+    let fair = UtxoTransaction::coinbase(bob.get_address(), coins(51.0), 2);
+    mine_utxo_block(&mut bc, vec![fair, tx]).unwrap();
+    assert_eq!(bc.utxos().balance(&bob.get_address()), coins(71.0));
+    assert_eq!(bc.utxos().balance(&alice.get_address()), coins(29.0));
+    assert_eq!(bc.audit_supply().unwrap().issued, coins(100.0));
+    assert!(bc.is_chain_valid());
+}
+
+#[test]
+fn reorg_undoes_utxo_transactions_of_disconnected_blocks() {
+    let builder = ChainBuilder::new();
+    let (alice, bob, carol) = (builder.wallet(1), builder.wallet(2), builder.wallet(3));
+    let mut bc = builder.build();
+    let reward = UtxoTransaction::coinbase(alice.get_address(), coins(50.0), 1);
+    mine_utxo_block(&mut bc, vec![reward]).unwrap();
+
+    // Ветка, отходящая после первого блока, длиннее на блок
+    let mut fork = bc.clone();
+    fork.mine_block(&carol.get_address()).unwrap();
+    fork.mine_block(&carol.get_address()).unwrap();
+
+    let tx = alice
+        .create_utxo_transaction(&bc.utxos(), &bob.get_address(), coins(20.0), coins(0.0))
+        .unwrap();
+    mine_utxo_block(&mut bc, vec![tx]).unwrap();
+    assert_eq!(bc.utxos().balance(&bob.get_address()), coins(20.0));
+
+    bc.replace_chain(fork.chain.clone()).unwrap();
+    assert_eq!(bc.utxos().height(), 4);
+    assert_eq!(bc.utxos().balance(&alice.get_address()), coins(50.0));
+    assert_eq!(bc.utxos().balance(&bob.get_address()), Amount::ZERO);
+
+    let mut rebuilt = bc.clone();
+    rebuilt.reindex();
+    assert_eq!(rebuilt.utxos().len(), bc.utxos().len());
+    assert_eq!(rebuilt.utxos().total(), bc.utxos().total());
+}
@@ -0,0 +1,233 @@
+use blockchain_p2p::testutil::{coins, ChainBuilder};
+use blockchain_p2p::validation::{self, ReplayedState};
+use blockchain_p2p::{
+    Amount, Block, Blockchain, BlockchainError, ChainError, Transaction, ValidationContext,
+    ValidationError, Wallet,
+};
+use std::sync::atomic::AtomicBool;
+
+/// Блок из `transactions` поверх вершины `bc`, добытый с ожидаемой целью.
+fn block_on(bc: &Blockchain, transactions: Vec<Transaction>, timestamp: u64) -> Block {
+    let tip = bc.latest_block();
+    Block::mine_at(
+        tip.header.hash_algorithm,
+        tip.header.index + 1,
+        timestamp,
+        transactions,
+        tip.hash,
+        bc.bits,
+        &AtomicBool::new(false),
+    )
+    .unwrap()
+}
+
+fn transfer(
+    bc: &Blockchain,
+    from: &Wallet,
+    to: &Wallet,
+    amount: Amount,
+    nonce: u64,
+) -> Transaction {
+    from.create_transaction_for_chain(bc.chain_id(), &to.get_address(), amount, coins(0.1), nonce)
+}
+
+#[test]
+fn overspending_block_is_rejected() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let mut bc = builder.mine(&alice).build();
+    let now = Block::next_timestamp_at(&bc.chain, bc.now());
+    let balance = bc.get_balance(&alice.get_address());
+
+    let overspend = block_on(&bc, vec![transfer(&bc, &alice, &bob, balance, 0)], now);
+    let reason = ValidationError::InsufficientFunds {
+        address: alice.get_address(),
+        available: balance,
+        required: balance + coins(0.1),
+    };
+    assert_eq!(bc.validate_block(&overspend), Err(reason.clone()));
+    assert_eq!(
+        bc.add_block(overspend),
+        Err(BlockchainError::InvalidBlock { index: 2, reason })
+    );
+
+    // Полученное выше в том же блоке уже можно тратить
+    let chained = block_on(
+        &bc,
+        vec![
+            transfer(&bc, &alice, &bob, coins(10.0), 0),
+            transfer(&bc, &bob, &alice, coins(5.0), 0),
+        ],
+        now,
+    );
+    bc.add_block(chained).unwrap();
+    assert!(bc.is_chain_valid());
+}
+
+#[test]
+fn rejection_reasons_are_granular() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bc = builder.mine(&alice).build();
+    let now = Block::next_timestamp_at(&bc.chain, bc.now());
+
+    let mut detached = block_on(&bc, Vec::new(), now);
+    detached.header.prev_hash = bc.chain[0].hash;
+    assert_eq!(
+        bc.validate_block(&detached),
+        Err(ValidationError::BadPrevHash)
+    );
+
+    let future = block_on(&bc, Vec::new(), now + 3 * 60 * 60);
+    assert!(matches!(
+        bc.validate_block(&future),
+        Err(ValidationError::FutureTimestamp { .. })
+    ));
+
+    let stale = block_on(&bc, Vec::new(), bc.latest_block().header.timestamp);
+    assert!(matches!(
+        bc.validate_block(&stale),
+        Err(ValidationError::TimestampTooOld { .. })
+    ));
+
+    assert_eq!(bc.validate_block(&block_on(&bc, Vec::new(), now)), Ok(()));
+}
+
+#[test]
+fn context_validates_against_any_state_view() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let bc = builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(5.0), coins(0.1))
+        .mine(&bob)
+        .build();
+
+    let ancestors = &bc.chain[..2];
+    let context = |state| ValidationContext {
+        ancestors,
+        expected_bits: bc.next_bits(ancestors),
+        now: bc.now(),
+        chain_id: bc.chain_id(),
+        mode: bc.mode(),
+        subsidy: bc.current_reward(2),
+        consensus: bc.consensus().as_ref(),
+        state,
+    };
+
+    let funded = ReplayedState::replay(ancestors);
+    assert_eq!(
+        validation::validate_block(&bc.chain[2], &context(&funded)),
+        Ok(())
+    );
+
+    // Без блока с наградой Алисе нечем платить
+    let unfunded = ReplayedState::replay(&bc.chain[..1]);
+    assert!(matches!(
+        validation::validate_block(&bc.chain[2], &context(&unfunded)),
+        Err(ValidationError::InsufficientFunds { .. })
+    ));
+}
+
+#[test]
+fn block_validation_names_the_reason() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let bc = builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(5.0), coins(0.1))
+        .mine(&bob)
+        .build();
+    let (ancestors, block) = bc.chain.split_at(2);
+    let block = &block[0];
+    let now = bc.now();
+    assert_eq!(block.validate_at(ancestors, now), Ok(()));
+
+    let mut forged = block.clone();
+    forged.transactions[1].amount = coins(50.0);
+    let txid = forged.transactions[1].id();
+    assert_eq!(
+        forged.validate_at(ancestors, now),
+        Err(ValidationError::InvalidSignature { txid })
+    );
+
+    let mut unworked = block.clone();
+    unworked.header.bits = 0x0300_0001;
+    unworked.hash = unworked.header.compute_hash();
+    assert_eq!(
+        unworked.validate_at(ancestors, now),
+        Err(ValidationError::BadPoW)
+    );
+    assert_eq!(
+        block.validate_at(&[], now),
+        Err(ValidationError::MissingParent)
+    );
+}
+
+#[test]
+fn chain_validation_reports_the_first_invalid_block() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bc = builder.mine_blocks(4, &alice).build();
+    assert!(bc.is_chain_valid());
+
+    let mut chain = bc.chain.clone();
+    chain[2].header.prev_hash = chain[0].hash;
+    chain[2].hash = chain[2].header.compute_hash();
+    let error = bc.validate_chain(&chain).unwrap_err();
+    assert_eq!(
+        error,
+        ChainError::InvalidBlock {
+            index: 2,
+            reason: ValidationError::BadPrevHash,
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "block 2 is invalid: previous hash does not match parent"
+    );
+}
+
+#[test]
+fn coinbase_may_not_exceed_subsidy_and_fees() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let bc = builder.mine(&alice).build();
+    let now = Block::next_timestamp_at(&bc.chain, bc.now());
+    let coinbase = |amount| Transaction {
+        chain_id: bc.chain_id().to_string(),
+        ..Transaction::coinbase(bob.get_address(), amount, 2)
+    };
+    let fee = coins(0.1);
+    let allowed = bc.current_reward(2) + fee;
+
+    let honest = block_on(
+        &bc,
+        vec![
+            coinbase(allowed),
+            transfer(&bc, &alice, &bob, coins(1.0), 0),
+        ],
+        now,
+    );
+    assert_eq!(bc.validate_block(&honest), Ok(()));
+
+    let greedy = block_on(
+        &bc,
+        vec![
+            coinbase(allowed + coins(1.0)),
+            transfer(&bc, &alice, &bob, coins(1.0), 0),
+        ],
+        now,
+    );
+    assert_eq!(
+        bc.validate_block(&greedy),
+        Err(ValidationError::ExcessiveCoinbase {
+            allowed,
+            actual: allowed + coins(1.0),
+        })
+    );
+}
@@ -0,0 +1,76 @@
+use blockchain_p2p::testutil::{coins, test_wallet, ChainBuilder};
+use blockchain_p2p::watch::Direction;
+use blockchain_p2p::{Address, WalletError, WatchWallet};
+
+#[test]
+fn watch_wallet_follows_balance_and_history() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let bc = builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(5.0), coins(0.1))
+        .mine(&bob)
+        .transfer(&alice, &bob, coins(1.0), coins(0.1))
+        .build();
+
+    let watch = WatchWallet::from_address(alice.get_address()).unwrap();
+    assert_eq!(watch.balance(&bc), bc.get_balance(&alice.get_address()));
+    assert_eq!(
+        watch.available_balance(&bc),
+        watch.balance(&bc) - coins(1.1)
+    );
+
+    let history = watch.history(&bc);
+    let directions: Vec<_> = history.iter().map(|entry| entry.direction).collect();
+    assert_eq!(directions, vec![Direction::Incoming, Direction::Outgoing]);
+    assert_eq!(history[1].height, 2);
+    assert_eq!(history[1].net_amount(), -(coins(5.1).units() as i128));
+    let net: i128 = history.iter().map(|entry| entry.net_amount()).sum();
+    assert_eq!(net, watch.balance(&bc).units() as i128);
+
+    let bob_watch = bob.watch_only();
+    assert_eq!(bob_watch.incoming(&bc).len(), 2);
+    assert!(bob_watch.outgoing(&bc).is_empty());
+}
+
+#[test]
+fn watch_wallet_builds_unsigned_transactions() {
+    let builder = ChainBuilder::new();
+    let alice = builder.wallet(1);
+    let bob = builder.wallet(2);
+    let bc = builder
+        .mine(&alice)
+        .transfer(&alice, &bob, coins(5.0), coins(0.1))
+        .build();
+
+    let watch = WatchWallet::from_public_key(&alice.public_key).unwrap();
+    assert_eq!(watch.address(), &alice.get_address());
+
+    let tx = watch.transaction_for(&bc, &bob.get_address(), coins(2.0), coins(0.1));
+    assert_eq!(tx.nonce, 1);
+    assert_eq!(tx.chain_id, bc.chain_id());
+    assert_eq!(tx.public_key, alice.public_key);
+    assert!(tx.verify_signature().is_err());
+
+    // Без публичного ключа его добавит подписывающий кошелёк
+    let by_address = WatchWallet::from_address(alice.get_address()).unwrap();
+    assert!(by_address.public_key().is_none());
+    assert!(by_address
+        .transaction_for(&bc, &bob.get_address(), coins(2.0), coins(0.1))
+        .public_key
+        .is_empty());
+}
+
+#[test]
+fn invalid_keys_and_addresses_are_rejected() {
+    assert!(matches!(
+        WatchWallet::from_public_key("02abcd"),
+        Err(WalletError::InvalidPublicKey)
+    ));
+    assert!(matches!(
+        WatchWallet::from_address(Address::from("1NotAnAddress")),
+        Err(WalletError::InvalidAddress(_))
+    ));
+    assert!(WatchWallet::from_address(test_wallet(1).get_address()).is_ok());
+}
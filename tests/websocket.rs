@@ -0,0 +1,85 @@
+mod common;
+
+use blockchain_p2p::network::PeerInfo;
+use blockchain_p2p::protocol::PROTOCOL_VERSION;
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::websocket::start_ws_server;
+use blockchain_p2p::{Node, Wallet};
+use common::{fund, signed_transaction};
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::thread;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message;
+
+#[test]
+fn subscriber_receives_block_transaction_and_peer_events() {
+    let alice = Node::new("Alice".to_string());
+    let p2p = alice.clone_node();
+    thread::spawn(move || p2p.start_server(38134));
+    let ws = alice.clone_node();
+    thread::spawn(move || start_ws_server(&ws, 38133));
+    thread::sleep(Duration::from_millis(300));
+
+    let runtime = Runtime::new().unwrap();
+    let (mut client, _) = runtime
+        .block_on(tokio_tungstenite::connect_async("ws://127.0.0.1:38133/"))
+        .unwrap();
+    let mut next_event = || -> Value {
+        let frame = runtime
+            .block_on(async { timeout(Duration::from_secs(5), client.next()).await })
+            .expect("event expected")
+            .unwrap()
+            .unwrap();
+        match frame {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            other => panic!("unexpected frame {:?}", other),
+        }
+    };
+
+    // Блок и перевод приходят к Алисе от пира
+    let bob = Node::new("Bob".to_string());
+    let sender = Wallet::new();
+    let block = {
+        let mut bc = bob.blockchain.blocking_write();
+        fund(&mut bc, &sender);
+        bc.latest_block().clone()
+    };
+    bob.add_peer(PeerInfo {
+        addr: "127.0.0.1:38134".parse().unwrap(),
+        node_id: "Alice".to_string(),
+        version: PROTOCOL_VERSION,
+        best_height: 1,
+    });
+    bob.broadcast_block(&block);
+    let event = next_event();
+    assert_eq!(event["type"], "BLOCK_ADDED");
+    assert_eq!(event["index"], 1);
+    assert_eq!(event["hash"], block.hash.to_string());
+
+    let tx = signed_transaction(&sender, &Wallet::new(), coins(2.0), coins(0.1));
+    bob.broadcast_transaction(&tx);
+    let event = next_event();
+    assert_eq!(event["type"], "TRANSACTION_ACCEPTED");
+    assert_eq!(event["txid"], tx.id().to_string());
+    assert_eq!(event["amount"], "2");
+
+    alice.add_peer(PeerInfo {
+        addr: "127.0.0.1:38199".parse().unwrap(),
+        node_id: "Carol".to_string(),
+        version: PROTOCOL_VERSION,
+        best_height: 1,
+    });
+    let event = next_event();
+    assert_eq!(event["type"], "PEER_CONNECTED");
+    assert_eq!(event["node_id"], "Carol");
+
+    // Остановка узла закрывает поток событий
+    alice.shutdown().unwrap();
+    let closed = runtime
+        .block_on(async { timeout(Duration::from_secs(5), client.next()).await })
+        .unwrap();
+    assert!(matches!(closed, Some(Ok(Message::Close(_))) | None));
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use blockchain_p2p::compact::CompactBlock;
+use blockchain_p2p::merkle::ProofStep;
+use blockchain_p2p::protocol::{
+    decode_message, encode_message, read_message, write_message, Hello, WIRE_VERSION,
+};
+use blockchain_p2p::testutil::coins;
+use blockchain_p2p::{Block, Hash256, MerkleProof, Message, TokenOp, Transaction, Wallet};
+use common::{mined_chain, signed_transaction};
+use proptest::prelude::*;
+use std::io::{Cursor, ErrorKind};
+
+/// Блоки и транзакции, из которых собираются сообщения: обычные
+/// переводы, coinbase и операции с токенами.
+fn samples() -> (Vec<Block>, Vec<Transaction>) {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+    let bc = mined_chain();
+    let token = Hash256::digest(b"token");
+    let mut transactions = vec![
+        signed_transaction(&alice, &bob, coins(2.5), coins(0.1)),
+        alice.create_token_transaction(
+            &bob.get_address(),
+            TokenOp::CreateToken {
+                symbol: "GOLD".to_string(),
+                supply: 1_000,
+            },
+            coins(0.1),
+            1,
+        ),
+        alice.create_token_transaction(
+            &bob.get_address(),
+            TokenOp::Mint { token, amount: 5 },
+            coins(0.1),
+            2,
+        ),
+        alice.create_token_transaction(
+            &bob.get_address(),
+            TokenOp::Transfer { token, amount: 3 },
+            coins(0.1),
+            3,
+        ),
+    ];
+    transactions.extend(bc.chain[2].transactions.iter().cloned());
+    (bc.chain, transactions)
+}
+
+fn hash() -> impl Strategy<Value = Hash256> {
+    any::<[u8; 32]>().prop_map(Hash256)
+}
+
+fn addr() -> impl Strategy<Value = String> {
+    "[a-z0-9.]{1,20}:[0-9]{1,5}"
+}
+
+fn message() -> impl Strategy<Value = Message> {
+    let (blocks, transactions) = samples();
+    let block = prop::sample::select(blocks.clone());
+    let block_list = prop::sample::subsequence(blocks.clone(), 0..=blocks.len());
+    let tx = prop::sample::select(transactions.clone());
+    let tx_list = prop::sample::subsequence(transactions.clone(), 0..=transactions.len());
+    let header = block.clone().prop_map(|block| block.header);
+
+    prop_oneof![
+        (
+            "[A-Za-z0-9-]{0,16}",
+            any::<u32>(),
+            hash(),
+            any::<usize>(),
+            prop::option::of(addr())
+        )
+            .prop_map(
+                |(node_id, version, genesis_hash, best_height, listen_addr)| {
+                    Message::Hello(Hello {
+                        node_id,
+                        version,
+                        chain_id: "mainnet".to_string(),
+                        genesis_hash,
+                        best_height,
+                        listen_addr: listen_addr.clone(),
+                        external_addr: listen_addr,
+                    })
+                }
+            ),
+        block.clone().prop_map(|block| Message::NewBlock { block }),
+        (block.clone(), addr()).prop_map(|(block, origin)| Message::CompactBlock {
+            block: CompactBlock::from_block(&block),
+            origin,
+        }),
+        (hash(), prop::collection::vec(any::<u32>(), 0..8))
+            .prop_map(|(hash, indexes)| Message::GetBlockTxn { hash, indexes }),
+        (hash(), tx_list.clone())
+            .prop_map(|(hash, transactions)| Message::BlockTxn { hash, transactions }),
+        (tx.clone(), prop::option::of(addr())).prop_map(|(transaction, origin)| Message::NewTx {
+            transaction,
+            origin
+        }),
+        (prop::collection::vec(hash(), 0..8), addr())
+            .prop_map(|(txids, origin)| Message::Inv { txids, origin }),
+        prop::collection::vec(hash(), 0..8).prop_map(|txids| Message::GetData { txids }),
+        tx_list.prop_map(|transactions| Message::Transactions { transactions }),
+        addr().prop_map(|from| Message::GetChain { from }),
+        block_list
+            .clone()
+            .prop_map(|chain| Message::ChainResponse { chain }),
+        addr().prop_map(|from| Message::GetChainSize { from }),
+        any::<usize>().prop_map(|size| Message::ChainSize { size }),
+        any::<u64>().prop_map(|timestamp| Message::Ping { timestamp }),
+        any::<u64>().prop_map(|timestamp| Message::Pong { timestamp }),
+        Just(Message::GetTip),
+        (any::<usize>(), hash(), any::<u128>()).prop_map(|(best_height, hash, work)| {
+            Message::Tip {
+                best_height,
+                hash,
+                work,
+            }
+        }),
+        addr().prop_map(|from| Message::GetPeers { from }),
+        prop::collection::vec(addr(), 0..8).prop_map(|peers| Message::Peers { peers }),
+        (any::<u32>(), any::<u32>()).prop_map(|(start, end)| Message::GetBlocks { start, end }),
+        block_list.prop_map(|blocks| Message::Blocks { blocks }),
+        (any::<u32>(), any::<u32>()).prop_map(|(start, end)| Message::GetHeaders { start, end }),
+        prop::collection::vec(header, 0..4).prop_map(|headers| Message::Headers { headers }),
+        hash().prop_map(|txid| Message::GetMerkleProof { txid }),
+        (
+            hash(),
+            prop::option::of((
+                any::<u32>(),
+                any::<usize>(),
+                prop::collection::vec((hash(), any::<bool>()), 0..6)
+            ))
+        )
+            .prop_map(|(txid, proof)| Message::MerkleProof {
+                txid,
+                proof: proof.map(|(height, leaf_index, steps)| {
+                    let steps = steps
+                        .into_iter()
+                        .map(|(hash, is_left)| ProofStep { hash, is_left })
+                        .collect();
+                    (height, MerkleProof { leaf_index, steps })
+                }),
+            }),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn every_message_round_trips(message in message()) {
+        let decoded = decode_message(&encode_message(&message).unwrap()).unwrap();
+        prop_assert_eq!(&decoded, &message);
+
+        let mut framed = Vec::new();
+        write_message(&mut framed, &message).unwrap();
+        prop_assert_eq!(read_message(&mut Cursor::new(framed)).unwrap(), message);
+    }
+
+    #[test]
+    fn corrupted_frames_are_rejected(message in message(), flip in any::<usize>(), bit in 0u8..8) {
+        let mut body = encode_message(&message).unwrap();
+        // Любой бит после байта версии: контрольная сумма или данные
+        let index = 1 + flip % (body.len() - 1);
+        body[index] ^= 1 << bit;
+        let error = decode_message(&body).unwrap_err();
+        prop_assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn frame_starts_with_version_and_checksum() {
+    let body = encode_message(&Message::GetTip).unwrap();
+    assert_eq!(body[0], WIRE_VERSION);
+    let payload = &body[5..];
+    assert_eq!(&body[1..5], &Hash256::digest(payload).as_bytes()[..4]);
+}
+
+#[test]
+fn unknown_wire_version_is_rejected() {
+    let mut body = encode_message(&Message::Ping { timestamp: 7 }).unwrap();
+    body[0] = WIRE_VERSION + 1;
+    let error = decode_message(&body).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(error.to_string().contains("wire version"));
+
+    assert!(decode_message(&[WIRE_VERSION, 0, 0]).is_err());
+}
+
+#[test]
+fn token_operations_keep_their_json_shape() {
+    let op = TokenOp::Mint {
+        token: Hash256::digest(b"token"),
+        amount: 5,
+    };
+    let json = serde_json::to_value(&op).unwrap();
+    assert_eq!(json["op"], "MINT");
+    assert_eq!(serde_json::from_value::<TokenOp>(json).unwrap(), op);
+}